@@ -10,15 +10,21 @@ use std::{
     error::Error,
     fmt,
     fmt::Debug,
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use clap::{AppSettings, Parser};
-use error_stack::{IntoReport, Result, ResultExt};
+use clap::{error::ErrorKind, AppSettings, CommandFactory, Parser, Subcommand};
+use error_stack::{ensure, IntoReport, Report, Result, ResultExt};
+use execution::package::simulation::Seed;
 use experiment_control::environment::init_logger;
-use experiment_structure::{ExperimentType, Manifest};
-use orchestrator::{Experiment, ExperimentConfig, Server};
+use experiment_structure::{check_project, ExperimentType, Manifest};
+use orchestrator::{
+    reaper::{self, ReaperConfig},
+    test_suite::{run_test_suite, TestSuiteConfig},
+    Experiment, ExperimentConfig, Server,
+};
 
 /// Arguments passed to the CLI
 #[derive(Debug, Parser)]
@@ -26,16 +32,44 @@ use orchestrator::{Experiment, ExperimentConfig, Server};
 #[clap(global_setting(AppSettings::PropagateVersion))]
 #[clap(setting(AppSettings::UseLongFormatForHelpSubcommand))]
 pub struct Args {
-    /// Path to the project to be run.
+    /// Path to the project to be run, tested, or checked.
+    ///
+    /// Required unless running `cleanup`.
     #[clap(short, long, env = "HASH_PROJECT")]
-    project: PathBuf,
+    project: Option<PathBuf>,
 
     #[clap(flatten)]
     experiment_config: ExperimentConfig,
 
-    /// Experiment type to be run.
     #[clap(subcommand)]
-    r#type: ExperimentType,
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[clap(flatten)]
+    Run(ExperimentType),
+
+    /// Run the experiments in the project's _integration-test.json_ and compare their outputs with
+    /// the expected outputs.
+    ///
+    /// Each experiment is run with every language specific initial state of the project, e.g.
+    /// `src/init-js.json` and `src/init-py.json`. Exits with an error if any test failed.
+    Test(TestSuiteConfig),
+
+    /// Check the project for errors without running it.
+    ///
+    /// Validates the behavior keys, _globals.json_, _views/analysis.json_, _experiments.json_, and
+    /// _dependencies.json_, and compiles JavaScript and TypeScript behaviors. Every problem found is
+    /// reported with its file and, if known, its line and column. Exits with an error if any
+    /// problem was found.
+    Check,
+
+    /// Remove shared-memory segments and socket files left behind by experiments which didn't
+    /// exit cleanly.
+    ///
+    /// By default, only files which are not in use by any running process anymore are removed.
+    Cleanup(ReaperConfig),
 }
 
 #[derive(Debug)]
@@ -69,31 +103,160 @@ async fn main() -> Result<(), CliError> {
     .attach_printable("Failed to initialize the logger")
     .change_context(CliError)?;
 
+    match args.command {
+        Command::Cleanup(reaper_config) => {
+            cleanup(&reaper_config, args.experiment_config.memory_dir)
+        }
+        Command::Check => {
+            let project = project_path(args.project)?;
+            check(&project)
+        }
+        Command::Test(test_suite_config) => {
+            let project = project_path(args.project)?;
+            reaper::reap_orphans(args.experiment_config.memory_dir.clone());
+            test(&project, &test_suite_config, &args.experiment_config).await
+        }
+        Command::Run(experiment_type) => {
+            let project = project_path(args.project)?;
+            reaper::reap_orphans(args.experiment_config.memory_dir.clone());
+            run(&project, experiment_type, args.experiment_config, now).await
+        }
+    }
+}
+
+/// Returns the absolute path of the project passed with `--project`.
+///
+/// Exits with a usage error if no project was passed.
+fn project_path(project: Option<PathBuf>) -> Result<PathBuf, CliError> {
+    let project = project.unwrap_or_else(|| {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "The argument '--project <PROJECT>' is required for this subcommand",
+            )
+            .exit()
+    });
+    project
+        .canonicalize()
+        .into_report()
+        .attach_printable_lazy(|| format!("Could not canonicalize project path: {project:?}"))
+        .change_context(CliError)
+}
+
+/// Runs an experiment of the project at `project_path`.
+async fn run(
+    project_path: &Path,
+    experiment_type: ExperimentType,
+    experiment_config: ExperimentConfig,
+    now: u128,
+) -> Result<(), CliError> {
     let nng_listen_url = format!("ipc://hash-orchestrator-{now}");
 
     let (mut experiment_server, handler) = Server::create(nng_listen_url);
     tokio::spawn(async move { experiment_server.run().await });
 
-    let absolute_project_path = args
-        .project
-        .canonicalize()
-        .into_report()
-        .attach_printable_lazy(|| {
-            format!("Could not canonicalize project path: {:?}", args.project)
-        })
-        .change_context(CliError)?;
-    let manifest = Manifest::from_local(&absolute_project_path)
-        .attach_printable_lazy(|| format!("Could not read local project {absolute_project_path:?}"))
+    let manifest = Manifest::from_local(project_path)
+        .attach_printable_lazy(|| format!("Could not read local project {project_path:?}"))
         .change_context(CliError)?;
     let experiment_run = manifest
-        .read(args.r#type)
+        .read(experiment_type, experiment_config.seed.map(Seed::new))
         .attach_printable("Could not read manifest")
         .change_context(CliError)?;
 
-    let experiment = Experiment::new(args.experiment_config);
+    let experiment = Experiment::new(experiment_config);
 
     experiment
         .run(experiment_run, handler, None)
         .await
         .change_context(CliError)
 }
+
+/// Runs the tests in the _integration-test.json_ of the project at `project_path` and prints
+/// their results.
+async fn test(
+    project_path: &Path,
+    config: &TestSuiteConfig,
+    experiment_config: &ExperimentConfig,
+) -> Result<(), CliError> {
+    let report = run_test_suite(project_path, config, experiment_config)
+        .await
+        .attach_printable_lazy(|| format!("Could not run the tests of {project_path:?}"))
+        .change_context(CliError)?;
+
+    for result in &report.results {
+        let status = if result.failure.is_some() {
+            "FAILED"
+        } else {
+            "ok"
+        };
+        println!(
+            "test {} ... {status} ({:.2}s)",
+            result.name,
+            result.duration.as_secs_f64()
+        );
+    }
+    for result in &report.results {
+        if let Some(failure) = &result.failure {
+            eprintln!(
+                "\n---- {} ----\n{}\n{}",
+                result.name, failure.message, failure.details
+            );
+        }
+    }
+
+    let failures = report.failures();
+    println!(
+        "\ntest result: {}. {} passed; {failures} failed",
+        if failures == 0 { "ok" } else { "FAILED" },
+        report.results.len() - failures
+    );
+
+    if let Some(junit_path) = &config.junit {
+        fs::write(junit_path, report.to_junit())
+            .into_report()
+            .attach_printable_lazy(|| format!("Could not write JUnit report to {junit_path:?}"))
+            .change_context(CliError)?;
+    }
+
+    ensure!(
+        failures == 0,
+        Report::new(CliError).attach_printable(format!("{failures} tests failed"))
+    );
+    Ok(())
+}
+
+/// Checks the project at `project_path` for errors and prints them.
+fn check(project_path: &Path) -> Result<(), CliError> {
+    let diagnostics = check_project(project_path);
+    for diagnostic in &diagnostics {
+        eprintln!("error: {diagnostic}");
+    }
+
+    ensure!(
+        diagnostics.is_empty(),
+        Report::new(CliError).attach_printable(format!(
+            "Found {} problems in {project_path:?}",
+            diagnostics.len()
+        ))
+    );
+    println!("No problems found in {}", project_path.display());
+    Ok(())
+}
+
+/// Removes the files left behind by experiments and prints their paths.
+fn cleanup(config: &ReaperConfig, memory_dir: Option<PathBuf>) -> Result<(), CliError> {
+    let removed = reaper::reap(config, memory_dir)
+        .attach_printable("Could not clean up after previous experiments")
+        .change_context(CliError)?;
+    for path in &removed {
+        println!("{}", path.display());
+    }
+    if removed.is_empty() {
+        eprintln!("Nothing to clean up");
+    } else if config.dry_run {
+        eprintln!("Would remove {} files", removed.len());
+    } else {
+        eprintln!("Removed {} files", removed.len());
+    }
+    Ok(())
+}
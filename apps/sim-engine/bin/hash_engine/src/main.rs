@@ -11,7 +11,7 @@ use error_stack::{IntoReport, Result, ResultExt};
 use execution::runner::RunnerConfig;
 use experiment_control::{
     controller::run::{cleanup_experiment, run_experiment},
-    environment::{init_logger, Args, Environment},
+    environment::{init_logger, init_memory_backend, Args, Environment},
 };
 use experiment_structure::{ExperimentConfig, FetchDependencies};
 
@@ -34,6 +34,7 @@ pub fn experiment_config(args: &Args, env: &Environment) -> Result<ExperimentCon
         RunnerConfig {
             js_runner_initial_heap_constraint: args.js_runner_initial_heap_constraint,
             js_runner_max_heap_size: args.js_runner_max_heap_size,
+            python_runner_dir: args.python_runner_dir.clone(),
         },
     )
     .attach_printable("Could not create experiment config")
@@ -66,6 +67,10 @@ async fn main() -> Result<(), EngineError> {
         .await
         .attach_printable("Could not fetch dependencies for experiment")
         .change_context(EngineError)?;
+    let _owner_lock = init_memory_backend(&args, &env.experiment)
+        .into_report()
+        .attach_printable("Could not set up the memory backend")
+        .change_context(EngineError)?;
 
     // Generate the configuration for packages from the environment
     let config = experiment_config(&args, &env)?;
@@ -3,7 +3,7 @@ use tokio::sync::mpsc::error::SendError;
 
 use crate::{
     package::simulation::SimulationId,
-    runner::{JavaScriptError, MessageTarget, PythonError},
+    runner::{JavaScriptError, MessageTarget, PythonError, RustError, WasmError},
     task::{SharedContext, SharedState, TaskId},
     worker_pool::WorkerIndex,
 };
@@ -28,6 +28,12 @@ pub enum Error {
     #[error("Python error: {0}")]
     Python(#[from] PythonError),
 
+    #[error("Rust error: {0}")]
+    Rust(#[from] RustError),
+
+    #[error("WebAssembly error: {0}")]
+    Wasm(#[from] WasmError),
+
     #[error("Arrow Error: {0}")]
     Arrow(#[from] arrow2::error::Error),
 
@@ -128,6 +134,9 @@ pub enum Error {
 
     #[error("Missing simulation run with id {0}")]
     MissingSimulationRun(SimulationId),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(String),
 }
 
 impl Error {
@@ -25,6 +25,8 @@ mod error;
 pub mod package;
 pub mod runner;
 pub mod task;
+#[cfg(test)]
+mod test_server;
 pub mod worker;
 pub mod worker_pool;
 
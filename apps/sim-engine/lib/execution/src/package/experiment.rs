@@ -27,6 +27,7 @@ use crate::{
     package::experiment::{
         basic::{BasicExperimentConfig, SimpleExperiment, SingleRunExperiment},
         comms::{control::ExpPkgCtlSend, update::ExpPkgUpdateRecv, ExperimentPackageComms},
+        extended::{ExtendedExperimentConfig, OptimizationExperiment},
     },
     Result,
 };
@@ -37,7 +38,7 @@ pub struct ExperimentPackage {
 }
 
 impl ExperimentPackage {
-    pub async fn new(config: BasicExperimentConfig) -> Result<ExperimentPackage> {
+    pub async fn new(config: ExperimentPackageConfig) -> Result<ExperimentPackage> {
         let (ctl_send, ctl_recv) = comms::control::new_pair();
         let (step_update_sender, exp_pkg_update_recv) = comms::update::new_pair();
         let join_handle = Self::create_join_handle(config, ctl_send, exp_pkg_update_recv)?;
@@ -50,23 +51,29 @@ impl ExperimentPackage {
     }
 
     fn create_join_handle(
-        exp_package_config: BasicExperimentConfig,
+        exp_package_config: ExperimentPackageConfig,
         pkg_to_exp: ExpPkgCtlSend,
         exp_pkg_update_recv: ExpPkgUpdateRecv,
     ) -> Result<JoinHandle<Result<()>>> {
         let future = match exp_package_config {
-            BasicExperimentConfig::Simple(config) => {
+            ExperimentPackageConfig::Basic(BasicExperimentConfig::Simple(config)) => {
                 let pkg = SimpleExperiment::new(config)?;
                 tokio::spawn(
                     async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await }.in_current_span(),
                 )
             }
-            BasicExperimentConfig::SingleRun(config) => {
+            ExperimentPackageConfig::Basic(BasicExperimentConfig::SingleRun(config)) => {
                 let pkg = SingleRunExperiment::new(config)?;
                 tokio::spawn(
                     async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await }.in_current_span(),
                 )
             }
+            ExperimentPackageConfig::Extended(ExtendedExperimentConfig::Optimization(config)) => {
+                let pkg = OptimizationExperiment::new(config)?;
+                tokio::spawn(
+                    async move { pkg.run(pkg_to_exp, exp_pkg_update_recv).await }.in_current_span(),
+                )
+            }
         };
         Ok(future)
     }
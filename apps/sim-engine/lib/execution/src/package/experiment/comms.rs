@@ -2,7 +2,7 @@ pub(crate) mod control;
 pub(crate) mod update;
 
 pub use self::{control::ExpPkgCtlRecv, update::ExpPkgUpdateSend};
-use crate::package::simulation::SimulationId;
+use crate::package::simulation::{output::analysis::AnalysisOutput, SimulationId};
 
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
@@ -37,4 +37,6 @@ pub struct StepUpdate {
     pub sim_id: SimulationId,
     pub was_error: bool,
     pub stop_signal: bool,
+    /// The analysis output of the last step, sent when the simulation run has finished
+    pub analysis_output: Option<AnalysisOutput>,
 }
@@ -2,7 +2,9 @@ mod optimization;
 
 use serde::{Deserialize, Serialize};
 
-pub use self::optimization::{OptimizationExperimentConfig, OptimizationExperimentConfigPayload};
+pub use self::optimization::{
+    OptimizationExperiment, OptimizationExperimentConfig, OptimizationExperimentConfigPayload,
+};
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
 pub enum ExtendedExperimentConfig {
@@ -1,6 +1,22 @@
+mod sampler;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
 use serde::{Deserialize, Serialize};
 
-use crate::package::experiment::extended::{MetricObjective, PackageDataField};
+use self::sampler::Sampler;
+use crate::{
+    package::{
+        experiment::{
+            comms::{
+                control::ExpPkgCtlSend, update::ExpPkgUpdateRecv, ExperimentControl, StepUpdate,
+            },
+            extended::{MetricObjective, PackageDataField},
+        },
+        simulation::{output::analysis::AnalysisSingleOutput, Seed, SimulationId},
+    },
+    Error, Result,
+};
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
 pub struct OptimizationExperimentConfigPayload {
@@ -33,4 +49,484 @@ pub struct OptimizationExperimentConfig {
     pub payload: OptimizationExperimentConfigPayload,
     /// Number of simulation runs that are to be run in parallel
     pub num_parallel_runs: usize,
+    /// The seed of the experiment. If set, the proposed globals only depend on the metrics of the
+    /// finished runs, regardless of the order in which runs running in parallel finish.
+    #[serde(default)]
+    pub seed: Option<Seed>,
+}
+
+/// Experiment, which searches for the globals optimizing an analysis metric.
+///
+/// Each run reports the value of the metric at its last step. The globals of the next run are
+/// proposed based on the metrics of all finished runs until `maxRuns` runs were started.
+///
+/// If the experiment is seeded, the metrics are observed in the order the runs were started, so
+/// the proposals don't depend on the timing of runs running in parallel. A run finishing before an
+/// earlier one is buffered, and the next run is only started when the earlier one has finished.
+pub struct OptimizationExperiment {
+    experiment_name: String,
+    metric_name: String,
+    objective: MetricObjective,
+    max_runs: usize,
+    max_num_steps: usize,
+    min_num_steps: usize,
+    num_parallel_runs: usize,
+    initial_points: VecDeque<serde_json::Value>,
+    sampler: Sampler,
+    /// Whether the metrics are observed in the order the runs were started
+    in_order: bool,
+}
+
+struct RunProgress {
+    /// The index of the run in the order the runs were started
+    index: usize,
+    changed_globals: serde_json::Value,
+    n_steps: usize,
+}
+
+impl OptimizationExperiment {
+    pub fn new(config: OptimizationExperimentConfig) -> Result<OptimizationExperiment> {
+        let payload = config.payload;
+        let metric_name = payload
+            .metric_name
+            .ok_or_else(|| Error::from("Optimization experiment requires a `metricName`"))?;
+        let objective = match payload.metric_objective {
+            Some(objective @ (MetricObjective::Max | MetricObjective::Min)) => objective,
+            Some(MetricObjective::Other(objective)) => {
+                return Err(Error::from(format!(
+                    "Unknown metric objective `{objective}`, expected `max` or `min`"
+                )));
+            }
+            None => {
+                return Err(Error::from(
+                    "Optimization experiment requires a `metricObjective`",
+                ));
+            }
+        };
+        let max_runs = payload
+            .max_runs
+            .filter(|max_runs| *max_runs > 0)
+            .ok_or_else(|| Error::from("Optimization experiment requires a positive `maxRuns`"))?
+            as usize;
+        let max_num_steps = payload
+            .max_steps
+            .filter(|max_steps| *max_steps > 0)
+            .ok_or_else(|| Error::from("Optimization experiment requires a positive `maxSteps`"))?
+            as usize;
+        let min_num_steps = payload.min_steps.unwrap_or(0).max(0) as usize;
+        if min_num_steps > max_num_steps {
+            return Err(Error::from(
+                "`minSteps` of optimization experiment must not be greater than `maxSteps`",
+            ));
+        }
+
+        let initial_points = payload.initial_points.unwrap_or_default();
+        if initial_points.iter().any(|point| !point.is_object()) {
+            return Err(Error::from(
+                "Initial points of optimization experiment must be objects of globals",
+            ));
+        }
+
+        let sampler = Sampler::new(
+            &payload.fields.unwrap_or_default(),
+            objective.clone(),
+            config.seed,
+        )?;
+        let in_order = config.seed.is_some();
+
+        Ok(OptimizationExperiment {
+            experiment_name: config.experiment_name,
+            metric_name,
+            objective,
+            max_runs,
+            max_num_steps,
+            min_num_steps,
+            num_parallel_runs: config.num_parallel_runs.max(1),
+            initial_points: initial_points.into(),
+            sampler,
+            in_order,
+        })
+    }
+
+    /// Reads the metric to optimize from the analysis output of a finished run.
+    fn metric(&self, update: &StepUpdate) -> Option<f64> {
+        match update
+            .analysis_output
+            .as_ref()?
+            .inner
+            .get(&self.metric_name)
+        {
+            Some(AnalysisSingleOutput::Number(metric)) => *metric,
+            Some(_) => {
+                tracing::warn!(
+                    "Metric `{}` is not a single number and can't be optimized",
+                    self.metric_name
+                );
+                None
+            }
+            None => None,
+        }
+    }
+
+    async fn start_next_run(
+        &mut self,
+        sim_idx: usize,
+        pkg_to_exp: &mut ExpPkgCtlSend,
+        active: &mut HashMap<SimulationId, RunProgress>,
+    ) -> Result<()> {
+        let changed_globals = match self.initial_points.pop_front() {
+            Some(point) => point,
+            None => self.sampler.suggest(),
+        };
+        // We sometimes use 0 as a default/null value, therefore it's not a valid
+        // SimulationShortId
+        let sim_id = SimulationId::new(sim_idx as u32 + 1);
+        tracing::debug!("Starting optimization run {sim_id} with {changed_globals}");
+
+        active.insert(
+            sim_id,
+            RunProgress {
+                index: sim_idx,
+                changed_globals: changed_globals.clone(),
+                n_steps: 0,
+            },
+        );
+        pkg_to_exp
+            .send(ExperimentControl::StartSim {
+                span_id: tracing::Span::current().id(),
+                sim_id,
+                changed_globals,
+                max_num_steps: self.max_num_steps,
+            })
+            .await
+    }
+
+    /// Returns the metric of a finished run, or `None` if the run is not taken into account.
+    fn finished_metric(&self, progress: &RunProgress, update: &StepUpdate) -> Option<f64> {
+        if update.was_error {
+            tracing::warn!(
+                "Optimization run {} failed and is not taken into account",
+                update.sim_id
+            );
+            None
+        } else if progress.n_steps < self.min_num_steps {
+            tracing::warn!(
+                "Optimization run {} stopped after {} steps, before reaching `minSteps`, and is \
+                 not taken into account",
+                update.sim_id,
+                progress.n_steps
+            );
+            None
+        } else if let Some(metric) = self.metric(update) {
+            tracing::info!(
+                "Optimization run {} with {} finished with `{}` = {metric}",
+                update.sim_id,
+                progress.changed_globals,
+                self.metric_name
+            );
+            Some(metric)
+        } else {
+            tracing::warn!(
+                "Optimization run {} did not report a value for metric `{}`",
+                update.sim_id,
+                self.metric_name
+            );
+            None
+        }
+    }
+
+    /// Runs the experiment until `maxRuns` runs have finished and returns the globals of the run
+    /// with the best metric together with the metric.
+    async fn optimize(
+        &mut self,
+        pkg_to_exp: &mut ExpPkgCtlSend,
+        exp_pkg_update_recv: &mut ExpPkgUpdateRecv,
+    ) -> Result<Option<(serde_json::Value, f64)>> {
+        let mut active = HashMap::new();
+        let mut num_started = 0;
+        let mut best: Option<(serde_json::Value, f64)> = None;
+        // If the runs are observed in order, finished runs wait here for earlier runs to finish
+        let mut finished = BTreeMap::new();
+        let mut num_observed = 0;
+
+        tracing::trace!(
+            "Starting {} optimization runs in parallel",
+            self.num_parallel_runs
+        );
+        while num_started < self.num_parallel_runs.min(self.max_runs) {
+            self.start_next_run(num_started, pkg_to_exp, &mut active)
+                .await?;
+            num_started += 1;
+        }
+
+        loop {
+            let response = exp_pkg_update_recv.recv().await.ok_or_else(|| {
+                Error::ExperimentRecv(
+                    "Experiment main loop closed when experiment package was still running".into(),
+                )
+            })?;
+
+            if response.was_error || response.stop_signal {
+                let progress = active.remove(&response.sim_id).ok_or_else(|| {
+                    tracing::warn!("Sim run with unknown id {} stopped", &response.sim_id);
+                    Error::MissingSimulationRun(response.sim_id)
+                })?;
+                let metric = self.finished_metric(&progress, &response);
+                let observable = if self.in_order {
+                    finished.insert(progress.index, (progress, metric));
+                    let mut observable = Vec::new();
+                    while let Some(run) = finished.remove(&num_observed) {
+                        observable.push(run);
+                        num_observed += 1;
+                    }
+                    observable
+                } else {
+                    vec![(progress, metric)]
+                };
+
+                // Every observed run frees a slot for the next run
+                for (progress, metric) in observable {
+                    if let Some(metric) = metric {
+                        self.sampler.observe(&progress.changed_globals, metric);
+                        let is_better =
+                            best.as_ref()
+                                .map_or(true, |(_, best)| match self.objective {
+                                    MetricObjective::Max => metric > *best,
+                                    _ => metric < *best,
+                                });
+                        if is_better {
+                            best = Some((progress.changed_globals, metric));
+                        }
+                    }
+
+                    if num_started < self.max_runs {
+                        self.start_next_run(num_started, pkg_to_exp, &mut active)
+                            .await?;
+                        num_started += 1;
+                    }
+                }
+
+                if active.is_empty() {
+                    break;
+                }
+            } else {
+                let progress = active
+                    .get_mut(&response.sim_id)
+                    .ok_or(Error::MissingSimulationRun(response.sim_id))?;
+
+                progress.n_steps += 1;
+            }
+        }
+
+        Ok(best)
+    }
+
+    pub async fn run(
+        mut self,
+        mut pkg_to_exp: ExpPkgCtlSend,
+        mut exp_pkg_update_recv: ExpPkgUpdateRecv,
+    ) -> Result<()> {
+        match self
+            .optimize(&mut pkg_to_exp, &mut exp_pkg_update_recv)
+            .await?
+        {
+            Some((changed_globals, metric)) => tracing::info!(
+                "Optimization experiment `{}` finished, best `{}` = {metric} with {changed_globals}",
+                self.experiment_name,
+                self.metric_name
+            ),
+            None => tracing::warn!(
+                "Optimization experiment `{}` finished without any value for metric `{}`",
+                self.experiment_name,
+                self.metric_name
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::package::{
+        experiment::comms::{control, update, ExpPkgCtlRecv, ExpPkgUpdateSend},
+        simulation::output::analysis::AnalysisOutput,
+    };
+
+    fn config(
+        max_runs: i64,
+        num_parallel_runs: usize,
+        initial_points: Vec<serde_json::Value>,
+        seed: Option<Seed>,
+    ) -> OptimizationExperimentConfig {
+        OptimizationExperimentConfig {
+            experiment_name: "optimization".to_string(),
+            payload: OptimizationExperimentConfigPayload {
+                metric_name: Some("metric".to_string()),
+                metric_objective: Some(MetricObjective::Max),
+                max_runs: Some(max_runs),
+                max_steps: Some(10),
+                min_steps: Some(2),
+                fields: Some(vec![PackageDataField {
+                    name: "x".to_string(),
+                    values: None,
+                    range: Some("0-10".to_string()),
+                }]),
+                initial_points: Some(initial_points),
+            },
+            num_parallel_runs,
+            seed,
+        }
+    }
+
+    /// Stands in for the experiment controller, which runs the simulations.
+    ///
+    /// The channels are unbounded, so the updates of all runs can be sent before the experiment
+    /// is run, as long as every run is started before its updates are received.
+    struct Controller {
+        pkg_to_exp: ExpPkgCtlSend,
+        ctl_recv: ExpPkgCtlRecv,
+        update_send: ExpPkgUpdateSend,
+        exp_pkg_update_recv: ExpPkgUpdateRecv,
+    }
+
+    impl Controller {
+        fn new() -> Self {
+            let (pkg_to_exp, ctl_recv) = control::new_pair();
+            let (update_send, exp_pkg_update_recv) = update::new_pair();
+            Self {
+                pkg_to_exp,
+                ctl_recv,
+                update_send,
+                exp_pkg_update_recv,
+            }
+        }
+
+        /// Queues `num_steps` steps of the run `sim_id` and the update ending it.
+        async fn finish_run(
+            &self,
+            sim_id: u32,
+            num_steps: usize,
+            was_error: bool,
+            metric: Option<f64>,
+        ) {
+            let sim_id = SimulationId::new(sim_id);
+            for _ in 0..num_steps {
+                self.update_send
+                    .send(StepUpdate {
+                        sim_id,
+                        was_error: false,
+                        stop_signal: false,
+                        analysis_output: None,
+                    })
+                    .await
+                    .unwrap();
+            }
+            let analysis_output = metric.map(|metric| AnalysisOutput {
+                inner: [(
+                    Arc::new("metric".to_string()),
+                    AnalysisSingleOutput::some_number(metric),
+                )]
+                .into_iter()
+                .collect(),
+            });
+            self.update_send
+                .send(StepUpdate {
+                    sim_id,
+                    was_error,
+                    stop_signal: !was_error,
+                    analysis_output,
+                })
+                .await
+                .unwrap();
+        }
+
+        /// Runs `experiment` on the queued updates and returns the best run and the globals of
+        /// every started run.
+        async fn optimize(
+            mut self,
+            mut experiment: OptimizationExperiment,
+        ) -> (Option<(serde_json::Value, f64)>, Vec<serde_json::Value>) {
+            let best = experiment
+                .optimize(&mut self.pkg_to_exp, &mut self.exp_pkg_update_recv)
+                .await
+                .unwrap();
+
+            drop(self.pkg_to_exp);
+            let mut started = Vec::new();
+            while let Some(msg) = self.ctl_recv.recv().await {
+                match msg {
+                    ExperimentControl::StartSim {
+                        sim_id,
+                        changed_globals,
+                        max_num_steps,
+                        ..
+                    } => {
+                        assert_eq!(sim_id, SimulationId::new(started.len() as u32 + 1));
+                        assert_eq!(max_num_steps, 10);
+                        started.push(changed_globals);
+                    }
+                    msg => panic!("Unexpected message {msg:?}"),
+                }
+            }
+            (best, started)
+        }
+    }
+
+    #[tokio::test]
+    async fn run_skips_unfinished_runs_and_picks_the_best() {
+        let initial_points = (1..=4).map(|x| json!({ "x": x })).collect::<Vec<_>>();
+        let experiment =
+            OptimizationExperiment::new(config(4, 1, initial_points.clone(), None)).unwrap();
+
+        let controller = Controller::new();
+        controller.finish_run(1, 2, false, Some(5.0)).await;
+        // Failed runs and runs stopping before `minSteps` would be the best otherwise
+        controller.finish_run(2, 3, true, Some(100.0)).await;
+        controller.finish_run(3, 1, false, Some(100.0)).await;
+        controller.finish_run(4, 2, false, Some(7.0)).await;
+        let (best, started) = controller.optimize(experiment).await;
+
+        // No more runs than `maxRuns` are started
+        assert_eq!(started, initial_points);
+        assert_eq!(best, Some((json!({ "x": 4 }), 7.0)));
+    }
+
+    /// Runs a seeded experiment with two parallel runs, where the runs finish in the order of
+    /// `finish_order`, and returns the proposed globals.
+    async fn seeded_proposals(finish_order: &[u32]) -> Vec<serde_json::Value> {
+        let experiment = OptimizationExperiment::new(config(
+            finish_order.len() as i64,
+            2,
+            Vec::new(),
+            Some(Seed::new(3)),
+        ))
+        .unwrap();
+
+        let controller = Controller::new();
+        for &sim_id in finish_order {
+            controller
+                .finish_run(sim_id, 2, false, Some(f64::from(sim_id % 5)))
+                .await;
+        }
+        controller.optimize(experiment).await.1
+    }
+
+    #[tokio::test]
+    async fn seeded_proposals_do_not_depend_on_the_finish_order() {
+        // Enough runs to use the TPE sampler, which depends on the observed metrics
+        let in_order = (1..=16).collect::<Vec<u32>>();
+        let swapped = in_order
+            .chunks(2)
+            .flat_map(|pair| [pair[1], pair[0]])
+            .collect::<Vec<_>>();
+
+        let proposals = seeded_proposals(&in_order).await;
+        assert_eq!(proposals.len(), 16);
+        assert_eq!(proposals, seeded_proposals(&swapped).await);
+    }
 }
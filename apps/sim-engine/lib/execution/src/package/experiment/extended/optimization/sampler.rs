@@ -0,0 +1,432 @@
+//! Proposes the globals of the next run of an optimization experiment.
+//!
+//! The first runs are sampled uniformly from the search space. As soon as enough runs have
+//! finished, a Tree-structured Parzen Estimator (TPE) is used: finished runs are split into a
+//! good and a bad group by their metric, a density is estimated for the values of each parameter
+//! in both groups, and the candidate with the best ratio of good to bad density is proposed.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::{Distribution, Normal};
+
+use crate::{
+    package::{
+        experiment::extended::{MetricObjective, PackageDataField},
+        simulation::Seed,
+    },
+    Error, Result,
+};
+
+/// Number of runs sampled randomly before the TPE sampler is used.
+const NUM_STARTUP_RUNS: usize = 10;
+
+/// Fraction of finished runs considered to be good.
+const GOOD_FRACTION: f64 = 0.25;
+
+/// Number of candidates drawn from the good density for every parameter.
+const NUM_CANDIDATES: usize = 24;
+
+/// Values a parameter can take.
+#[derive(Debug, Clone, PartialEq)]
+enum Domain {
+    /// One of a list of values, e.g. `"values": [1, 2, 4]`.
+    Categorical(Vec<serde_json::Value>),
+    /// An integer in an inclusive range, e.g. `"range": "1-10"`.
+    Integer { min: i64, max: i64 },
+    /// A number in an inclusive range, e.g. `"range": "0.5-2.5"`.
+    Float { min: f64, max: f64 },
+}
+
+impl Domain {
+    fn from_range(range: &str) -> Result<Self> {
+        // The first character is skipped, so the lower bound may be negative
+        let separator = range
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| *c == '-')
+            .map(|(index, _)| index)
+            .ok_or_else(|| Error::from(format!("Range `{range}` is not of the form `min-max`")))?;
+        let (min, max) = (range[..separator].trim(), range[separator + 1..].trim());
+
+        let domain = match (min.parse::<i64>(), max.parse::<i64>()) {
+            (Ok(min), Ok(max)) => Domain::Integer { min, max },
+            _ => {
+                let parse = |bound: &str| {
+                    bound.parse::<f64>().map_err(|_| {
+                        Error::from(format!(
+                            "Range bound `{bound}` in `{range}` is not a number"
+                        ))
+                    })
+                };
+                Domain::Float {
+                    min: parse(min)?,
+                    max: parse(max)?,
+                }
+            }
+        };
+        let is_empty = match domain {
+            Domain::Integer { min, max } => min > max,
+            Domain::Float { min, max } => min > max,
+            Domain::Categorical(_) => false,
+        };
+        if is_empty {
+            return Err(Error::from(format!("Range `{range}` is empty")));
+        }
+        Ok(domain)
+    }
+
+    /// The bounds of a numeric domain as floats.
+    fn bounds(&self) -> Option<(f64, f64)> {
+        match *self {
+            Domain::Integer { min, max } => Some((min as f64, max as f64)),
+            Domain::Float { min, max } => Some((min, max)),
+            Domain::Categorical(_) => None,
+        }
+    }
+
+    fn to_value(&self, x: f64) -> serde_json::Value {
+        match self {
+            Domain::Integer { .. } => serde_json::Value::from(x.round() as i64),
+            _ => serde_json::Value::from(x),
+        }
+    }
+
+    fn sample_uniform(&self, rng: &mut impl Rng) -> serde_json::Value {
+        match self {
+            Domain::Categorical(values) => values[rng.gen_range(0..values.len())].clone(),
+            Domain::Integer { min, max } => serde_json::Value::from(rng.gen_range(*min..=*max)),
+            Domain::Float { min, max } => serde_json::Value::from(rng.gen_range(*min..=*max)),
+        }
+    }
+}
+
+/// A hyperparameter of the optimization, i.e. a global property to change.
+#[derive(Debug, Clone, PartialEq)]
+struct Parameter {
+    name: String,
+    domain: Domain,
+}
+
+impl TryFrom<&PackageDataField> for Parameter {
+    type Error = Error;
+
+    fn try_from(field: &PackageDataField) -> Result<Self> {
+        let domain = match (&field.values, &field.range) {
+            (Some(values), _) if !values.is_empty() => Domain::Categorical(values.clone()),
+            (_, Some(range)) => Domain::from_range(range)?,
+            _ => {
+                return Err(Error::from(format!(
+                    "Field `{}` must specify either `values` or a `range`",
+                    field.name
+                )));
+            }
+        };
+        Ok(Self {
+            name: field.name.clone(),
+            domain,
+        })
+    }
+}
+
+/// A finished run with the values it used for each parameter.
+struct Observation {
+    values: Vec<serde_json::Value>,
+    /// The metric of the run, negated if the metric is maximized.
+    loss: f64,
+}
+
+/// Density estimate of the values of a single parameter.
+enum Parzen {
+    /// Probability of each value of a categorical domain.
+    Categorical(Vec<f64>),
+    /// Mixture of a uniform prior over `[min, max]` and a Gaussian kernel at every observation.
+    Numeric {
+        min: f64,
+        max: f64,
+        centers: Vec<f64>,
+        bandwidth: f64,
+    },
+}
+
+impl Parzen {
+    fn new(domain: &Domain, observed: &[&serde_json::Value]) -> Self {
+        match domain {
+            Domain::Categorical(values) => {
+                // Every value is counted once as a prior, so unseen values can still be sampled
+                let mut counts = vec![1.0; values.len()];
+                for value in observed {
+                    if let Some(index) = values.iter().position(|v| v == *value) {
+                        counts[index] += 1.0;
+                    }
+                }
+                let total: f64 = counts.iter().sum();
+                Parzen::Categorical(counts.into_iter().map(|count| count / total).collect())
+            }
+            Domain::Integer { .. } | Domain::Float { .. } => {
+                let (min, max) = domain.bounds().expect("Domain is numeric");
+                let centers: Vec<f64> = observed.iter().filter_map(|v| v.as_f64()).collect();
+                // Scott's rule of thumb for a uniformly spread sample, bounded so the density
+                // doesn't degenerate for many observations or an empty range.
+                let width = (max - min).max(f64::EPSILON);
+                let bandwidth =
+                    (width * (centers.len() as f64 + 1.0).powf(-0.2)).max(width / 100.0);
+                Parzen::Numeric {
+                    min,
+                    max,
+                    centers,
+                    bandwidth,
+                }
+            }
+        }
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> f64 {
+        match self {
+            Parzen::Categorical(probabilities) => {
+                let mut threshold = rng.gen_range(0.0..1.0);
+                for (index, probability) in probabilities.iter().enumerate() {
+                    if threshold < *probability {
+                        return index as f64;
+                    }
+                    threshold -= probability;
+                }
+                (probabilities.len() - 1) as f64
+            }
+            Parzen::Numeric {
+                min,
+                max,
+                centers,
+                bandwidth,
+            } => {
+                // Component 0 is the prior
+                let component = rng.gen_range(0..=centers.len());
+                if component == 0 {
+                    return rng.gen_range(*min..=*max);
+                }
+                let kernel = Normal::new(centers[component - 1], *bandwidth)
+                    .expect("Bandwidth is positive and finite");
+                kernel.sample(rng).clamp(*min, *max)
+            }
+        }
+    }
+
+    /// Logarithm of the (unnormalized) density at `x`.
+    fn log_density(&self, x: f64) -> f64 {
+        match self {
+            Parzen::Categorical(probabilities) => probabilities[x as usize].ln(),
+            Parzen::Numeric {
+                min,
+                max,
+                centers,
+                bandwidth,
+            } => {
+                let prior = 1.0 / (max - min).max(f64::EPSILON);
+                let kernels: f64 = centers
+                    .iter()
+                    .map(|center| {
+                        let z = (x - center) / bandwidth;
+                        (-0.5 * z * z).exp() / (bandwidth * (2.0 * std::f64::consts::PI).sqrt())
+                    })
+                    .sum();
+                ((prior + kernels) / (centers.len() as f64 + 1.0)).ln()
+            }
+        }
+    }
+}
+
+/// Proposes new points in the search space based on the results of previous runs.
+pub(super) struct Sampler {
+    parameters: Vec<Parameter>,
+    objective: MetricObjective,
+    observations: Vec<Observation>,
+    rng: StdRng,
+}
+
+impl Sampler {
+    /// Creates a sampler exploring `fields`.
+    ///
+    /// A seeded sampler proposes the same globals for the same metrics observed in the same order,
+    /// otherwise the random number generator is seeded from the operating system.
+    pub(super) fn new(
+        fields: &[PackageDataField],
+        objective: MetricObjective,
+        seed: Option<Seed>,
+    ) -> Result<Self> {
+        if fields.is_empty() {
+            return Err(Error::from(
+                "Optimization experiment requires at least one field to explore",
+            ));
+        }
+        Ok(Self {
+            parameters: fields
+                .iter()
+                .map(Parameter::try_from)
+                .collect::<Result<_>>()?,
+            objective,
+            observations: Vec::new(),
+            rng: seed.map_or_else(StdRng::from_entropy, Seed::rng),
+        })
+    }
+
+    /// Returns the changed globals for the next run.
+    pub(super) fn suggest(&mut self) -> serde_json::Value {
+        let values: Vec<_> = if self.observations.len() < NUM_STARTUP_RUNS {
+            self.parameters
+                .iter()
+                .map(|parameter| parameter.domain.sample_uniform(&mut self.rng))
+                .collect()
+        } else {
+            self.suggest_tpe()
+        };
+
+        self.parameters
+            .iter()
+            .zip(values)
+            .map(|(parameter, value)| (parameter.name.clone(), value))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    fn suggest_tpe(&mut self) -> Vec<serde_json::Value> {
+        let mut sorted: Vec<_> = self.observations.iter().collect();
+        sorted.sort_by(|lhs, rhs| lhs.loss.total_cmp(&rhs.loss));
+        let num_good = ((sorted.len() as f64 * GOOD_FRACTION).ceil() as usize).max(1);
+        let (good, bad) = sorted.split_at(num_good);
+
+        let rng = &mut self.rng;
+        self.parameters
+            .iter()
+            .enumerate()
+            .map(|(index, parameter)| {
+                let observed = |observations: &[&Observation]| {
+                    observations
+                        .iter()
+                        .map(|observation| &observation.values[index])
+                        .collect::<Vec<_>>()
+                };
+                let good = Parzen::new(&parameter.domain, &observed(good));
+                let bad = Parzen::new(&parameter.domain, &observed(bad));
+
+                let best = (0..NUM_CANDIDATES)
+                    .map(|_| {
+                        let candidate = good.sample(rng);
+                        let score = good.log_density(candidate) - bad.log_density(candidate);
+                        (candidate, score)
+                    })
+                    .max_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs))
+                    .map(|(candidate, _)| candidate)
+                    .expect("At least one candidate is drawn");
+
+                match &parameter.domain {
+                    Domain::Categorical(values) => values[best as usize].clone(),
+                    domain => domain.to_value(best),
+                }
+            })
+            .collect()
+    }
+
+    /// Records the `metric` of a finished run with the `changed_globals` returned by
+    /// [`suggest()`](Self::suggest) or passed as initial point.
+    pub(super) fn observe(&mut self, changed_globals: &serde_json::Value, metric: f64) {
+        let values = self
+            .parameters
+            .iter()
+            .map(|parameter| {
+                changed_globals
+                    .get(&parameter.name)
+                    .cloned()
+                    .unwrap_or(serde_json::Value::Null)
+            })
+            .collect();
+        let loss = match self.objective {
+            MetricObjective::Max => -metric,
+            _ => metric,
+        };
+        self.observations.push(Observation { values, loss });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn field(name: &str, range: &str) -> PackageDataField {
+        PackageDataField {
+            name: name.to_string(),
+            values: None,
+            range: Some(range.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(
+            Domain::from_range("1-10").unwrap(),
+            Domain::Integer { min: 1, max: 10 }
+        );
+        assert_eq!(
+            Domain::from_range("-1.5 - 2").unwrap(),
+            Domain::Float {
+                min: -1.5,
+                max: 2.0
+            }
+        );
+        assert!(Domain::from_range("10-1").is_err());
+        assert!(Domain::from_range("10").is_err());
+        assert!(Domain::from_range("a-b").is_err());
+    }
+
+    #[test]
+    fn test_suggestions_within_domain() {
+        let fields = [
+            field("x", "0-1"),
+            field("n", "-3-3"),
+            PackageDataField {
+                name: "kind".to_string(),
+                values: Some(vec![json!("a"), json!("b")]),
+                range: None,
+            },
+        ];
+        let mut sampler = Sampler::new(&fields, MetricObjective::Min, None).unwrap();
+
+        for _ in 0..(NUM_STARTUP_RUNS * 3) {
+            let suggestion = sampler.suggest();
+            let x = suggestion["x"].as_f64().unwrap();
+            let n = suggestion["n"].as_i64().unwrap();
+            assert!((0.0..=1.0).contains(&x));
+            assert!((-3..=3).contains(&n));
+            assert!(suggestion["kind"] == json!("a") || suggestion["kind"] == json!("b"));
+            sampler.observe(&suggestion, (x - 0.3).abs());
+        }
+    }
+
+    /// Proposes `num_runs` globals, observing a metric for every proposal.
+    fn proposals(seed: Seed, num_runs: usize) -> Vec<serde_json::Value> {
+        let fields = [field("x", "0-1"), field("n", "-3-3")];
+        let mut sampler = Sampler::new(&fields, MetricObjective::Max, Some(seed)).unwrap();
+        (0..num_runs)
+            .map(|_| {
+                let suggestion = sampler.suggest();
+                let x = suggestion["x"].as_f64().unwrap();
+                let n = suggestion["n"].as_i64().unwrap();
+                sampler.observe(&suggestion, x * n as f64);
+                suggestion
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_suggestions_are_reproducible() {
+        // Covers the random startup runs and the TPE runs
+        let num_runs = NUM_STARTUP_RUNS * 3;
+        assert_eq!(
+            proposals(Seed::new(7), num_runs),
+            proposals(Seed::new(7), num_runs)
+        );
+        assert_ne!(
+            proposals(Seed::new(7), num_runs),
+            proposals(Seed::new(8), num_runs)
+        );
+    }
+}
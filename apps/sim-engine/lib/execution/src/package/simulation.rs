@@ -223,6 +223,7 @@ mod dependencies;
 mod id;
 mod name;
 mod package_type;
+mod seed;
 mod task;
 
 use stateful::{
@@ -230,7 +231,6 @@ use stateful::{
     global::Globals,
 };
 
-pub(crate) use self::name::{PackageIdGenerator, PackageMetadata};
 pub use self::{
     comms::PackageComms,
     config::{
@@ -241,8 +241,13 @@ pub use self::{
     id::SimulationId,
     name::PackageName,
     package_type::PackageType,
+    seed::Seed,
     task::PackageTask,
 };
+pub(crate) use self::{
+    name::{PackageIdGenerator, PackageMetadata},
+    seed::random_agent_id,
+};
 use crate::Result;
 
 pub trait PackageCreator: Send + Sync {
@@ -295,6 +300,14 @@ pub trait Package: Send {
     fn simulation_setup_message(&self) -> Result<serde_json::Value> {
         Ok(serde_json::Value::Null)
     }
+
+    /// Called when the globals of the simulation run were changed while it's running.
+    ///
+    /// The new globals apply from the next step onwards. Packages, which derive their
+    /// configuration from the globals, have to update it here.
+    fn update_globals(&mut self, _globals: &Globals) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Packages, which are running in parallel, may be bound by the CPU.
@@ -1,10 +1,13 @@
 use std::{collections::HashMap, sync::Arc};
 
 use serde::{Deserialize, Serialize};
-use stateful::{agent::AgentSchema, global::Globals};
+use stateful::{
+    agent::AgentSchema,
+    global::{Dataset, Globals},
+};
 
 use crate::package::simulation::{
-    init::InitialState, state::behavior_execution::Behavior, PackageName,
+    init::InitialState, state::behavior_execution::Behavior, PackageName, Seed,
 };
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -36,4 +39,8 @@ pub struct PackageCreatorConfig {
     pub agent_schema: Arc<AgentSchema>,
     pub globals: Globals,
     pub persistence: PersistenceConfig,
+    /// The seed of the simulation run, if the experiment is seeded
+    pub seed: Option<Seed>,
+    /// The datasets of the project, shared by all simulation runs of the experiment
+    pub datasets: Arc<[Dataset]>,
 }
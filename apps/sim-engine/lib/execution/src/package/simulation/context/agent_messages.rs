@@ -1,5 +1,12 @@
 //! Messages sending between agents and to the engine.
-
+//!
+//! Besides agent ids and names, messages can be sent to a [`RecipientSpec`], i.e. to all agents
+//! within a radius of the sender or to all agents subscribed to a topic through their
+//! `subscriptions` field.
+//!
+//! [`RecipientSpec`]: stateful::message::RecipientSpec
+
+mod broadcast;
 mod collected;
 mod fields;
 mod indices;
@@ -22,10 +29,14 @@ use stateful::{
 };
 use tracing::Span;
 
-use self::{collected::Messages, fields::MESSAGES_FIELD_NAME};
+use self::{
+    collected::Messages,
+    fields::{MESSAGES_FIELD_NAME, SUBSCRIPTIONS_FIELD_NAME},
+};
 use crate::{
     package::simulation::{
         context::{ContextPackage, ContextPackageCreator},
+        state::topology::TopologyConfig,
         MaybeCpuBound, Package, PackageComms, PackageCreator, PackageCreatorConfig,
         PackageInitConfig,
     },
@@ -42,13 +53,14 @@ pub struct AgentMessagesCreator;
 impl ContextPackageCreator for AgentMessagesCreator {
     fn create(
         &self,
-        _config: &PackageCreatorConfig,
+        config: &PackageCreatorConfig,
         _init_config: &PackageInitConfig,
         _comms: PackageComms,
         _state_field_spec_accessor: FieldSpecMapAccessor,
         context_field_spec_accessor: FieldSpecMapAccessor,
     ) -> Result<Box<dyn ContextPackage>> {
         Ok(Box::new(AgentMessages {
+            topology: TopologyConfig::from_globals(&config.globals)?,
             context_field_spec_accessor,
         }))
     }
@@ -67,9 +79,22 @@ impl PackageCreator for AgentMessagesCreator {
     fn worker_init_message(&self) -> Result<Value> {
         Ok(Value::Null)
     }
+
+    fn get_state_field_specs(
+        &self,
+        _config: &PackageInitConfig,
+        _globals: &Globals,
+        field_spec_creator: &RootFieldSpecCreator,
+    ) -> Result<Vec<RootFieldSpec>> {
+        Ok(vec![fields::get_subscriptions_field_spec(
+            field_spec_creator,
+        )?])
+    }
 }
 
 pub struct AgentMessages {
+    /// Used to resolve messages sent to all agents within a radius
+    topology: TopologyConfig,
     context_field_spec_accessor: FieldSpecMapAccessor,
 }
 
@@ -83,6 +108,11 @@ impl Package for AgentMessages {
     fn simulation_setup_message(&self) -> Result<Value> {
         Ok(Value::Null)
     }
+
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.topology = TopologyConfig::from_globals(globals)?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -101,7 +131,24 @@ impl ContextPackage for AgentMessages {
         let id_name_iter =
             agent::arrow::agent_id_iter(&batches)?.zip(agent::arrow::agent_name_iter(&batches)?);
 
-        let messages = Messages::gather(&snapshot.message_map, id_name_iter)?;
+        let message_map = &snapshot.message_map;
+        // Subscriptions are only read if any message was sent to a topic
+        let subscriptions: Box<dyn Iterator<Item = Option<Vec<&str>>> + '_> =
+            if message_map.has_topics() {
+                Box::new(agent::arrow::str_list_iter(
+                    &batches,
+                    SUBSCRIPTIONS_FIELD_NAME,
+                )?)
+            } else {
+                Box::new(std::iter::repeat_with(|| None))
+            };
+        let broadcasts = if message_map.broadcasts().is_empty() {
+            Vec::new()
+        } else {
+            broadcast::received_broadcasts(message_map.broadcasts(), &batches, &self.topology)?
+        };
+
+        let messages = Messages::gather(message_map, id_name_iter, subscriptions, &broadcasts)?;
         let field_key = self
             .context_field_spec_accessor
             .get_agent_scoped_field_spec(MESSAGES_FIELD_NAME)?
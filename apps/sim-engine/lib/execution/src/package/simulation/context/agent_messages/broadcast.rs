@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use stateful::{
+    agent::{self, AgentBatch},
+    field::UUID_V4_LEN,
+    message::Broadcast,
+    state::MessageReference,
+};
+
+use crate::{
+    package::simulation::{
+        context::neighbors::map::{agents_adjacency_map, gather_neighbors, NeighborRef},
+        state::topology::TopologyConfig,
+    },
+    Result,
+};
+
+/// Resolves `broadcasts` to the agents within the radius of their sender.
+///
+/// Returns the received messages of every agent in the order of `batches`. The sender doesn't
+/// receive its own broadcast. Broadcasts of agents without a position, or of agents which were
+/// removed in this step, are not received by anyone.
+pub(super) fn received_broadcasts(
+    broadcasts: &[Broadcast],
+    batches: &[&AgentBatch],
+    topology: &TopologyConfig,
+) -> Result<Vec<Vec<MessageReference>>> {
+    let agents: Vec<NeighborRef> = agent::arrow::position_iter(batches)?
+        .zip(agent::arrow::index_iter(batches))
+        .map(|agent| (agent, None))
+        .collect();
+    let senders = agent::arrow::agent_id_iter(batches)?
+        .enumerate()
+        .map(|(index, id)| (id, index))
+        .collect::<HashMap<&[u8; UUID_V4_LEN], usize>>();
+    let batch_offsets = batches
+        .iter()
+        .scan(0, |offset, batch| {
+            let batch_offset = *offset;
+            *offset += batch.num_agents();
+            Some(batch_offset)
+        })
+        .collect::<Vec<_>>();
+
+    let tree = agents_adjacency_map(&agents)?;
+    let mut received = vec![Vec::new(); agents.len()];
+    for broadcast in broadcasts {
+        let ((position, index), _) = match senders.get(broadcast.sender.as_bytes()) {
+            Some(&sender) => &agents[sender],
+            None => continue,
+        };
+        let position = match position {
+            Some(position) => position,
+            None => continue,
+        };
+        for recipient in
+            gather_neighbors(&tree, *index, position, &Some(broadcast.radius), topology)?
+        {
+            let recipient =
+                batch_offsets[recipient.group_index as usize] + recipient.agent_index as usize;
+            received[recipient].push(broadcast.message.clone());
+        }
+    }
+    Ok(received)
+}
+
+#[cfg(test)]
+mod tests {
+    use memory::shared_memory::MemoryId;
+    use serde_json::json;
+    use stateful::{
+        agent::{Agent, AgentId, AgentSchema},
+        field::{FieldSpecMap, RootFieldSpec},
+    };
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn agent_id(id: u128) -> AgentId {
+        AgentId::from_bytes(*Uuid::from_u128(id).as_bytes())
+    }
+
+    fn agent_batch(agents: serde_json::Value, schema: &AgentSchema) -> AgentBatch {
+        let agents: Vec<Agent> = serde_json::from_value(agents).unwrap();
+        AgentBatch::from_agent_states(agents.as_slice(), schema, MemoryId::new(Uuid::new_v4()))
+            .unwrap()
+    }
+
+    #[test]
+    fn receive_broadcasts_within_radius() {
+        let mut field_spec_map = FieldSpecMap::empty();
+        field_spec_map
+            .try_extend(RootFieldSpec::base_agent_fields().unwrap())
+            .unwrap();
+        let schema = AgentSchema::new(field_spec_map).unwrap();
+
+        // `a`, `b`, and `c` are placed on the x-axis, `d` has no position
+        let batches = [
+            agent_batch(
+                json!([
+                    { "agent_id": Uuid::from_u128(1).to_string(), "position": [0, 0, 0] },
+                    { "agent_id": Uuid::from_u128(2).to_string(), "position": [1, 0, 0] },
+                ]),
+                &schema,
+            ),
+            agent_batch(
+                json!([
+                    { "agent_id": Uuid::from_u128(3).to_string(), "position": [5, 0, 0] },
+                    { "agent_id": Uuid::from_u128(4).to_string() },
+                ]),
+                &schema,
+            ),
+        ];
+        let broadcast = |sender, radius, message_index| Broadcast {
+            sender: agent_id(sender),
+            radius,
+            message: MessageReference::new(0, 0, message_index),
+        };
+        let broadcasts = [
+            broadcast(1, 2.0, 0),
+            broadcast(3, 4.5, 1),
+            // Senders without a position or which were removed don't reach anyone
+            broadcast(4, 100.0, 2),
+            broadcast(5, 100.0, 3),
+        ];
+
+        let received = received_broadcasts(
+            &broadcasts,
+            &batches.iter().collect::<Vec<_>>(),
+            &TopologyConfig::default(),
+        )
+        .unwrap();
+        let received: Vec<Vec<usize>> = received
+            .iter()
+            .map(|messages| {
+                messages
+                    .iter()
+                    .map(|message| message.message_index)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(received, [vec![], vec![0, 1], vec![], vec![]]);
+    }
+}
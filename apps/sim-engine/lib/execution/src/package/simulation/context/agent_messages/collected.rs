@@ -1,4 +1,4 @@
-use stateful::{field::UUID_V4_LEN, message::MessageMap};
+use stateful::{field::UUID_V4_LEN, message::MessageMap, state::MessageReference};
 
 use crate::{package::simulation::context::agent_messages::indices::AgentMessageIndices, Result};
 
@@ -11,14 +11,23 @@ pub struct Messages {
 }
 
 impl Messages {
+    /// Collects the messages received by every agent.
+    ///
+    /// Agents receive the messages sent to their id, their name, and the topics they subscribed to.
+    /// `broadcasts` contains the received broadcasts of every agent or is empty if no broadcast was
+    /// sent.
     pub fn gather<'a>(
         message_map: &MessageMap,
         ids_and_names: impl Iterator<Item = (&'a [u8; UUID_V4_LEN], Option<&'a str>)>,
+        subscriptions: impl Iterator<Item = Option<Vec<&'a str>>>,
+        broadcasts: &[Vec<MessageReference>],
     ) -> Result<Messages> {
         let mut total_count = 0;
         //TODO[4](optimization) parallelism
         let indices = ids_and_names
-            .map(|(agent_id, agent_name)| {
+            .zip(subscriptions)
+            .enumerate()
+            .map(|(agent_index, ((agent_id, agent_name), topics))| {
                 let by_id = message_map.get_msg_refs(
                     &uuid::Uuid::from_slice(agent_id)?.hyphenated().to_string(), //TODO[6](optimization) lose the string creation
                 );
@@ -30,7 +39,13 @@ impl Messages {
                 if let Some(by_name) = by_name {
                     indices.add(by_name);
                 }
-                total_count += by_id.len() + by_name.map(|m| m.len()).unwrap_or(0);
+                for topic in topics.into_iter().flatten() {
+                    indices.add(message_map.get_topic_msg_refs(topic));
+                }
+                if let Some(by_radius) = broadcasts.get(agent_index) {
+                    indices.add(by_radius);
+                }
+                total_count += indices.num_messages();
                 Ok(indices)
             })
             .collect::<Result<_>>()?;
@@ -5,6 +5,7 @@ use stateful::field::{
 use crate::{package::simulation::context::agent_messages::MESSAGE_INDEX_COUNT, Result};
 
 pub(super) const MESSAGES_FIELD_NAME: &str = "messages";
+pub(super) const SUBSCRIPTIONS_FIELD_NAME: &str = "subscriptions";
 
 fn agent_messages() -> FieldType {
     let variant = FieldTypeVariant::VariableLengthArray(Box::new(FieldType::new(
@@ -34,3 +35,21 @@ pub(super) fn get_messages_field_spec(
         FieldScope::Agent,
     ))
 }
+
+pub(super) fn get_subscriptions_field_spec(
+    field_spec_creator: &RootFieldSpecCreator,
+) -> Result<RootFieldSpec> {
+    // Topics, which the agent receives messages for
+    let subscriptions = FieldType::new(
+        FieldTypeVariant::VariableLengthArray(Box::new(FieldType::new(
+            FieldTypeVariant::String,
+            false,
+        ))),
+        true,
+    );
+    Ok(field_spec_creator.create(
+        SUBSCRIPTIONS_FIELD_NAME.into(),
+        subscriptions,
+        FieldScope::Agent,
+    ))
+}
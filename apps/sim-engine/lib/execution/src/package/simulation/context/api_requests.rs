@@ -24,8 +24,18 @@ use stateful::{
 };
 use tracing::{Instrument, Span};
 
-pub use self::handlers::CustomApiMessageError;
-use self::response::{ApiResponseMap, ApiResponses};
+pub use self::{
+    handlers::{
+        register_message_handler, CustomApiMessageError, HttpError, HttpMessageHandler,
+        HttpMessageHandlerConfig, HttpMethod, MessageHandler, MessageHandlerConfig, Request,
+        Requests,
+    },
+    response::ApiResponseMap,
+};
+use self::{
+    handlers::{resolve_message_handlers, ResolvedMessageHandler},
+    response::ApiResponses,
+};
 use crate::{
     package::simulation::{
         context::{
@@ -50,7 +60,8 @@ impl ContextPackageCreator for ApiRequestsCreator {
         _state_field_spec_accessor: FieldSpecMapAccessor,
         context_field_spec_accessor: FieldSpecMapAccessor,
     ) -> Result<Box<dyn ContextPackage>> {
-        let custom_message_handlers = custom_message_handlers_from_globals(&config.globals)?;
+        let custom_message_handlers =
+            resolve_message_handlers(custom_message_handlers_from_globals(&config.globals)?)?;
         Ok(Box::new(ApiRequests {
             custom_message_handlers,
             context_field_spec_accessor,
@@ -72,7 +83,7 @@ impl ContextPackageCreator for ApiRequestsCreator {
 impl PackageCreator for ApiRequestsCreator {}
 
 pub struct ApiRequests {
-    custom_message_handlers: Option<Vec<String>>,
+    custom_message_handlers: Vec<ResolvedMessageHandler>,
     context_field_spec_accessor: FieldSpecMapAccessor,
 }
 
@@ -82,7 +93,13 @@ impl MaybeCpuBound for ApiRequests {
     }
 }
 
-impl Package for ApiRequests {}
+impl Package for ApiRequests {
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.custom_message_handlers =
+            resolve_message_handlers(custom_message_handlers_from_globals(globals)?)?;
+        Ok(())
+    }
+}
 
 #[async_trait]
 impl ContextPackage for ApiRequests {
@@ -96,13 +113,10 @@ impl ContextPackage for ApiRequests {
         let pkg_span = Span::current();
         let run_span = tracing::trace_span!("run"); // store an un-entered span for the async
 
-        let mut api_response_maps = if let Some(ref handlers) = self.custom_message_handlers {
-            build_api_response_maps(&snapshot, handlers)
+        let mut api_response_maps =
+            build_api_response_maps(&snapshot, &self.custom_message_handlers)
                 .instrument(run_span.clone())
-                .await
-        } else {
-            Ok(vec![])
-        }?;
+                .await?;
 
         let _entered = run_span.entered(); // The rest of this is sync so this is fine
 
@@ -173,25 +187,23 @@ impl ContextPackage for ApiRequests {
     }
 }
 
-pub fn custom_message_handlers_from_globals(globals: &Globals) -> Result<Option<Vec<String>>> {
+/// Reads the custom message handlers enabled in the `"messageHandlers"` global.
+pub fn custom_message_handlers_from_globals(
+    globals: &Globals,
+) -> Result<Vec<MessageHandlerConfig>> {
     globals
         .get_cloned("messageHandlers")
-        .map(|handlers| match handlers {
-            serde_json::Value::Array(handlers) => handlers
-                .into_iter()
-                .map(|handler| match handler {
-                    serde_json::Value::String(handler) => Ok(handler),
-                    _ => Err(Error::GlobalsParseError("messageHandlers".into())),
-                })
-                .collect::<Result<Vec<String>>>(),
-            _ => Err(Error::GlobalsParseError("messageHandlers".into())),
+        .map(|handlers| {
+            serde_json::from_value(handlers)
+                .map_err(|_| Error::GlobalsParseError("messageHandlers".into()))
         })
         .transpose()
+        .map(Option::unwrap_or_default)
 }
 
 async fn build_api_response_maps(
     snapshot: &StateSnapshot,
-    handlers: &[String],
+    handlers: &[ResolvedMessageHandler],
 ) -> Result<Vec<ApiResponseMap>> {
     let mut futs = FuturesOrdered::new();
     {
@@ -199,10 +211,10 @@ async fn build_api_response_maps(
         let reader = MessageReader::from_message_pool(message_proxies)?;
 
         handlers.iter().try_for_each::<_, Result<()>>(|handler| {
-            let messages = snapshot.message_map.get_msg_refs(handler);
+            let messages = snapshot.message_map.get_msg_refs(&handler.name);
             if !messages.is_empty() {
                 let messages = handlers::gather_requests(&reader, messages)?;
-                futs.push_back(handlers::run_custom_message_handler(handler, messages))
+                futs.push_back(handler.handler.handle(&handler.name, messages))
             }
             Ok(())
         })?;
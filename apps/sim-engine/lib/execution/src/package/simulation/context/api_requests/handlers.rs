@@ -1,20 +1,56 @@
+//! Custom message handlers, which respond to messages sent to them by agents.
+//!
+//! Handlers are enabled per project by listing them in the `"messageHandlers"` global. An entry
+//! is either the name of a registered handler, e.g. `"mapbox"`, or the configuration of an HTTP
+//! handler (see [`HttpMessageHandlerConfig`]). Additional handlers can be implemented in Rust and
+//! made available to projects with [`register_message_handler`].
+
+mod http;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use async_trait::async_trait;
+use serde::Deserialize;
 use serde_json::Value;
 use stateful::{field::UUID_V4_LEN, message::MessageReader, state::MessageReference};
 use thiserror::Error as ThisError;
 
-use crate::{
-    package::simulation::context::api_requests::{handlers, ApiResponseMap},
-    Error, Result,
-};
+pub use self::http::{HttpError, HttpMessageHandler, HttpMessageHandlerConfig, HttpMethod};
+use crate::{package::simulation::context::api_requests::ApiResponseMap, Error, Result};
 
 pub const ACTIVE_REQUESTS: usize = 10;
 
-type Request = ([u8; UUID_V4_LEN], Value);
+/// A message sent to a custom message handler as the id of the sending agent and the message
+/// data.
+pub type Request = ([u8; UUID_V4_LEN], Value);
 
+/// All messages sent to a custom message handler in one step.
 pub struct Requests {
     inner: Vec<Request>,
 }
 
+impl Requests {
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl IntoIterator for Requests {
+    type IntoIter = std::vec::IntoIter<Request>;
+    type Item = Request;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
+
 pub fn gather_requests(
     reader: &MessageReader<'_>,
     messages: &[MessageReference],
@@ -34,18 +70,99 @@ pub fn gather_requests(
     Ok(Requests { inner })
 }
 
-pub async fn run_custom_message_handler(name: &str, requests: Requests) -> Result<ApiResponseMap> {
-    match name {
-        "mapbox" => handlers::mapbox::get(requests).await,
-        _ => Err(CustomApiMessageError::InvalidCustomMessageHandler(name.to_string()).into()),
+/// Responds to the messages sent to a custom message handler.
+///
+/// The responses are delivered to the agents as messages in the next step.
+#[async_trait]
+pub trait MessageHandler: Send + Sync {
+    /// Handles all `requests` sent to `name` in the current step.
+    async fn handle(&self, name: &str, requests: Requests) -> Result<ApiResponseMap>;
+}
+
+type Registry = RwLock<HashMap<String, Arc<dyn MessageHandler>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        RwLock::new(HashMap::from([(
+            "mapbox".to_string(),
+            Arc::new(mapbox::Mapbox) as Arc<dyn MessageHandler>,
+        )]))
+    })
+}
+
+/// Makes `handler` available to projects under `name`, replacing any handler previously
+/// registered with the same name.
+///
+/// Handlers have to be registered in the engine process before the experiment is started.
+pub fn register_message_handler(name: impl Into<String>, handler: impl MessageHandler + 'static) {
+    registry()
+        .write()
+        .expect("message handler registry is poisoned")
+        .insert(name.into(), Arc::new(handler));
+}
+
+/// An entry of the `"messageHandlers"` global.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum MessageHandlerConfig {
+    /// The name of a registered handler
+    Registered(String),
+    Http(HttpMessageHandlerConfig),
+}
+
+impl MessageHandlerConfig {
+    /// The name, to which messages are sent to reach the handler.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Registered(name) => name,
+            Self::Http(config) => &config.name,
+        }
     }
 }
 
+/// A custom message handler enabled for a simulation run.
+pub struct ResolvedMessageHandler {
+    pub name: String,
+    pub handler: Arc<dyn MessageHandler>,
+}
+
+/// Looks up the registered handlers and creates the HTTP handlers of `configs`.
+pub fn resolve_message_handlers(
+    configs: Vec<MessageHandlerConfig>,
+) -> Result<Vec<ResolvedMessageHandler>> {
+    let registry = registry()
+        .read()
+        .expect("message handler registry is poisoned");
+    configs
+        .into_iter()
+        .map(|config| {
+            let handler: Arc<dyn MessageHandler> = match &config {
+                MessageHandlerConfig::Registered(name) => {
+                    registry.get(name).cloned().ok_or_else(|| {
+                        CustomApiMessageError::InvalidCustomMessageHandler(name.clone())
+                    })?
+                }
+                MessageHandlerConfig::Http(config) => {
+                    Arc::new(HttpMessageHandler::new(config.clone()).map_err(CustomError::conv)?)
+                }
+            };
+            Ok(ResolvedMessageHandler {
+                name: config.name().to_string(),
+                handler,
+            })
+        })
+        .collect()
+}
+
 #[derive(ThisError, Debug)]
 pub enum CustomApiMessageError {
     #[error("Mapbox error: {0}")]
     Mapbox(#[from] mapbox::MapboxError),
 
+    #[error("HTTP message handler error: {0}")]
+    Http(#[from] HttpError),
+
     #[error("Unknown custom message handler: {0}")]
     InvalidCustomMessageHandler(String),
 }
@@ -63,14 +180,13 @@ trait CustomError: Into<CustomApiMessageError> {
 }
 
 pub mod mapbox {
-    use std::collections::{hash_map, HashMap};
-
+    use async_trait::async_trait;
     use futures::StreamExt;
     use stateful::field::UUID_V4_LEN;
     use thiserror::Error as ThisError;
 
     use crate::package::simulation::context::api_requests::{
-        handlers::{CustomError, Request, Requests, ACTIVE_REQUESTS},
+        handlers::{CustomError, MessageHandler, Request, Requests, ACTIVE_REQUESTS},
         ApiResponseMap, Result,
     };
 
@@ -107,26 +223,53 @@ pub mod mapbox {
     }
 
     pub async fn get<'a>(requests: Requests) -> Result<ApiResponseMap> {
-        let responses = futures::stream::iter(requests.inner.into_iter().map(get_))
+        let responses = futures::stream::iter(requests.into_iter().map(get_))
             .buffer_unordered(ACTIVE_REQUESTS)
             .collect::<Vec<_>>()
             .await
             .into_iter()
             .collect::<Result<Vec<_>>>()?;
 
-        let mut map = HashMap::<[u8; UUID_V4_LEN], Vec<String>>::new();
-        responses.into_iter().for_each(|(to, content)| {
-            if let hash_map::Entry::Vacant(e) = map.entry(to) {
-                e.insert(vec![content]);
-            } else {
-                map.get_mut(&to).unwrap().push(content)
-            }
-        });
-
-        Ok(ApiResponseMap {
-            from: "mapbox",
-            r#type: "mapbox_response",
-            map,
-        })
+        Ok(ApiResponseMap::new("mapbox", "mapbox_response", responses))
+    }
+
+    /// The built-in handler for Mapbox directions requests.
+    pub struct Mapbox;
+
+    #[async_trait]
+    impl MessageHandler for Mapbox {
+        async fn handle(&self, _name: &str, requests: Requests) -> Result<ApiResponseMap> {
+            get(requests).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_handler_configs() {
+        let configs: Vec<MessageHandlerConfig> = serde_json::from_value(json!([
+            "mapbox",
+            { "name": "pricing", "url": "http://localhost/price/{product}", "method": "POST" }
+        ]))
+        .unwrap();
+        let names = configs
+            .iter()
+            .map(MessageHandlerConfig::name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["mapbox", "pricing"]);
+
+        let handlers = resolve_message_handlers(configs).unwrap();
+        assert_eq!(handlers.len(), 2);
+    }
+
+    #[test]
+    fn rejects_unknown_handlers() {
+        let configs = vec![MessageHandlerConfig::Registered("unknown".to_string())];
+        assert!(resolve_message_handlers(configs).is_err());
     }
 }
@@ -0,0 +1,365 @@
+//! Generic message handler forwarding messages to an HTTP/JSON service.
+
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use serde_json::Value;
+use stateful::field::UUID_V4_LEN;
+use surf::{
+    http::{headers::HeaderName, Method},
+    Client, Url,
+};
+use thiserror::Error as ThisError;
+
+use crate::package::simulation::context::api_requests::{
+    handlers::{CustomError, MessageHandler, Request, Requests, ACTIVE_REQUESTS},
+    ApiResponseMap, Result,
+};
+
+#[derive(ThisError, Debug)]
+pub enum HttpError {
+    #[error("Invalid configuration of handler `{name}`: {message}")]
+    Config { name: String, message: String },
+
+    #[error("Field `{field}` of the URL template is missing in message data: {data:?}")]
+    MissingField { field: String, data: Value },
+
+    #[error("Invalid URL template `{0}`, `{{` is not closed")]
+    UrlTemplate(String),
+
+    #[error("Invalid URL `{url}`: {message}")]
+    InvalidUrl { url: String, message: String },
+
+    #[error("Request to {url} failed: {message}")]
+    Request { url: Url, message: String },
+
+    #[error("Request to {url} failed with status {status}")]
+    Status { url: Url, status: u16 },
+
+    #[error("Response of {url} is not valid JSON: {message}")]
+    Response { url: Url, message: String },
+
+    #[error("`{pointer}` does not point to a value in {value:?}")]
+    Pointer { pointer: String, value: Value },
+}
+
+impl CustomError for HttpError {}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl From<HttpMethod> for Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => Method::Get,
+            HttpMethod::Post => Method::Post,
+            HttpMethod::Put => Method::Put,
+            HttpMethod::Patch => Method::Patch,
+            HttpMethod::Delete => Method::Delete,
+        }
+    }
+}
+
+/// Configuration of a message handler, which sends every message it receives to an HTTP service
+/// and delivers the JSON responses back to the sending agents.
+///
+/// ```json
+/// {
+///   "name": "pricing",
+///   "url": "http://localhost:8080/price/{product}",
+///   "method": "POST",
+///   "headers": { "Authorization": "Bearer 1234" },
+///   "request": "/order",
+///   "response": "/price",
+///   "responseType": "price"
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HttpMessageHandlerConfig {
+    /// The name, to which agents send their messages
+    pub name: String,
+    /// The URL of the service. Placeholders like `{product}` are replaced by the URL-encoded
+    /// field of the message data.
+    pub url: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// JSON pointer to the part of the message data, which is sent as request body. Defaults to
+    /// the whole message data. `GET` requests are sent without a body.
+    #[serde(default)]
+    pub request: Option<String>,
+    /// JSON pointer to the part of the response, which is delivered to the agent. Defaults to the
+    /// whole response.
+    #[serde(default)]
+    pub response: Option<String>,
+    /// The type of the messages delivering the responses, defaults to `<name>_response`.
+    #[serde(default)]
+    pub response_type: Option<String>,
+}
+
+pub struct HttpMessageHandler {
+    config: HttpMessageHandlerConfig,
+    headers: Vec<(HeaderName, String)>,
+    response_type: Arc<str>,
+    client: Client,
+}
+
+impl HttpMessageHandler {
+    pub fn new(config: HttpMessageHandlerConfig) -> Result<Self, HttpError> {
+        let config_error = |message: String| HttpError::Config {
+            name: config.name.clone(),
+            message,
+        };
+        if config.name.is_empty() {
+            return Err(config_error("the name must not be empty".to_string()));
+        }
+        for pointer in config.request.iter().chain(&config.response) {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(config_error(format!(
+                    "`{pointer}` is not a JSON pointer, it has to start with `/`"
+                )));
+            }
+        }
+        let headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                HeaderName::from_str(name)
+                    .map(|name| (name, value.clone()))
+                    .map_err(|_| config_error(format!("invalid header name `{name}`")))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let response_type = config
+            .response_type
+            .clone()
+            .unwrap_or_else(|| format!("{}_response", config.name));
+        Ok(Self {
+            headers,
+            response_type: response_type.into(),
+            client: Client::new(),
+            config,
+        })
+    }
+
+    async fn send(&self, (from, data): Request) -> Result<([u8; UUID_V4_LEN], String), HttpError> {
+        let url = expand_url(&self.config.url, &data)?;
+        let url = Url::parse(&url).map_err(|err| HttpError::InvalidUrl {
+            url,
+            message: err.to_string(),
+        })?;
+
+        let mut request = surf::Request::builder(self.config.method.into(), url.clone());
+        for (name, value) in &self.headers {
+            request = request.header(name.clone(), value.as_str());
+        }
+        if self.config.method != HttpMethod::Get {
+            let body = select(&data, self.config.request.as_deref())?;
+            request = request.body_json(body).map_err(|err| HttpError::Request {
+                url: url.clone(),
+                message: err.to_string(),
+            })?;
+        }
+
+        let mut response = self
+            .client
+            .send(request)
+            .await
+            .map_err(|err| HttpError::Request {
+                url: url.clone(),
+                message: err.to_string(),
+            })?;
+        if !response.status().is_success() {
+            return Err(HttpError::Status {
+                url,
+                status: response.status().into(),
+            });
+        }
+        let body: Value = response
+            .body_json()
+            .await
+            .map_err(|err| HttpError::Response {
+                url,
+                message: err.to_string(),
+            })?;
+
+        let data = select(&body, self.config.response.as_deref())?;
+        Ok((from, data.to_string()))
+    }
+}
+
+#[async_trait]
+impl MessageHandler for HttpMessageHandler {
+    async fn handle(&self, _name: &str, requests: Requests) -> Result<ApiResponseMap> {
+        let responses =
+            futures::stream::iter(requests.into_iter().map(|request| self.send(request)))
+                .buffer_unordered(ACTIVE_REQUESTS)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(CustomError::conv)?;
+
+        Ok(ApiResponseMap::new(
+            self.config.name.as_str(),
+            Arc::clone(&self.response_type),
+            responses,
+        ))
+    }
+}
+
+/// Replaces the `{field}` placeholders of `template` by the URL-encoded fields of `data`.
+fn expand_url(template: &str, data: &Value) -> Result<String, HttpError> {
+    let mut url = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        url.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| HttpError::UrlTemplate(template.to_string()))?
+            + start;
+        let field = &rest[start + 1..end];
+        let value = match data.get(field) {
+            Some(Value::String(value)) => value.clone(),
+            Some(value) => value.to_string(),
+            None => {
+                return Err(HttpError::MissingField {
+                    field: field.to_string(),
+                    data: data.clone(),
+                });
+            }
+        };
+        url.extend(utf8_percent_encode(&value, NON_ALPHANUMERIC));
+        rest = &rest[end + 1..];
+    }
+    url.push_str(rest);
+    Ok(url)
+}
+
+fn select<'v>(value: &'v Value, pointer: Option<&str>) -> Result<&'v Value, HttpError> {
+    match pointer {
+        Some(pointer) => value.pointer(pointer).ok_or_else(|| HttpError::Pointer {
+            pointer: pointer.to_string(),
+            value: value.clone(),
+        }),
+        None => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_server::{self, Request, Response};
+
+    /// Answers every request with its method, path, and JSON body. Requests to `/fail` are
+    /// answered with status 500.
+    fn respond(request: Request) -> Response {
+        let (status, response) = if request.path == "/fail" {
+            ("500 Internal Server Error", json!({}))
+        } else {
+            let body = serde_json::from_slice::<Value>(&request.body).unwrap_or(Value::Null);
+            (
+                "200 OK",
+                json!({ "method": request.method, "path": request.path, "body": body }),
+            )
+        };
+        Response::new(status, response.to_string()).header("Content-Type", "application/json")
+    }
+
+    async fn mock_server() -> String {
+        test_server::serve(respond).await
+    }
+
+    fn handler(config: Value) -> HttpMessageHandler {
+        HttpMessageHandler::new(serde_json::from_value(config).unwrap()).unwrap()
+    }
+
+    fn requests(data: Value) -> Requests {
+        Requests {
+            inner: vec![([1; UUID_V4_LEN], data)],
+        }
+    }
+
+    #[tokio::test]
+    async fn delivers_responses_to_senders() {
+        let server = mock_server().await;
+        let handler = handler(json!({
+            "name": "pricing",
+            "url": format!("{server}/price/{{product}}"),
+            "method": "POST",
+            "request": "/order",
+        }));
+
+        let mut responses = handler
+            .handle(
+                "pricing",
+                requests(json!({ "product": "green apple", "order": { "amount": 3 } })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(&*responses.from, "pricing");
+        assert_eq!(&*responses.r#type, "pricing_response");
+
+        let data = responses.map.remove(&[1; UUID_V4_LEN]).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(
+            serde_json::from_str::<Value>(&data[0]).unwrap(),
+            json!({ "method": "POST", "path": "/price/green%20apple", "body": { "amount": 3 } })
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_responses() {
+        let server = mock_server().await;
+        let handler = handler(json!({
+            "name": "routing",
+            "url": format!("{server}/route"),
+            "response": "/path",
+            "responseType": "route",
+        }));
+
+        let mut responses = handler
+            .handle("routing", requests(json!({})))
+            .await
+            .unwrap();
+        assert_eq!(&*responses.r#type, "route");
+        assert_eq!(
+            responses.map.remove(&[1; UUID_V4_LEN]).unwrap(),
+            ["\"/route\""]
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_on_error_status() {
+        let server = mock_server().await;
+        let handler = handler(json!({ "name": "failing", "url": format!("{server}/fail") }));
+
+        assert!(handler
+            .handle("failing", requests(json!({})))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_missing_url_fields() {
+        assert!(matches!(
+            expand_url("http://localhost/{id}", &json!({})),
+            Err(HttpError::MissingField { .. })
+        ));
+    }
+}
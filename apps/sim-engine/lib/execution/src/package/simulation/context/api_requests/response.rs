@@ -1,29 +1,47 @@
-use std::{collections::HashMap, marker::PhantomData};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 
 use stateful::field::UUID_V4_LEN;
 
 pub struct ApiResponseToAnonymous {
-    pub from: &'static str,
-    pub r#type: &'static str,
+    pub from: Arc<str>,
+    pub r#type: Arc<str>,
     pub data: String,
 }
 
 /// Struct returned by a custom message handler
 pub struct ApiResponseMap {
-    pub from: &'static str,
-    pub r#type: &'static str,
+    pub from: Arc<str>,
+    pub r#type: Arc<str>,
     pub map: HashMap<[u8; UUID_V4_LEN], Vec<String>>,
 }
 
 impl ApiResponseMap {
+    /// Groups the `responses` by the agent they are sent to. Every response is delivered as a
+    /// message of type `type` from `from`.
+    pub fn new(
+        from: impl Into<Arc<str>>,
+        r#type: impl Into<Arc<str>>,
+        responses: impl IntoIterator<Item = ([u8; UUID_V4_LEN], String)>,
+    ) -> Self {
+        let mut map = HashMap::<[u8; UUID_V4_LEN], Vec<String>>::new();
+        for (to, content) in responses {
+            map.entry(to).or_default().push(content);
+        }
+        Self {
+            from: from.into(),
+            r#type: r#type.into(),
+            map,
+        }
+    }
+
     pub fn take_for_agent(&mut self, id: &[u8; UUID_V4_LEN]) -> Vec<ApiResponseToAnonymous> {
         self.map
             .remove(id)
             .map(|v| {
                 v.into_iter()
                     .map(|data| ApiResponseToAnonymous {
-                        from: self.from,
-                        r#type: self.r#type,
+                        from: Arc::clone(&self.from),
+                        r#type: Arc::clone(&self.r#type),
                         data,
                     })
                     .collect()
@@ -32,9 +50,9 @@ impl ApiResponseMap {
     }
 }
 
-/// Static string column representation for API messages
-pub struct SizedStaticStringColumn {
-    pub data: Vec<Vec<&'static str>>,
+/// Shared string column representation for API messages
+pub struct SizedSharedStringColumn {
+    pub data: Vec<Vec<Arc<str>>>,
     /// Sum of string lengths
     pub char_count: usize,
 }
@@ -48,8 +66,8 @@ pub struct SizedStringColumn {
 
 /// Columnar native representation of external API responses
 pub struct ApiResponses<'a> {
-    pub from: SizedStaticStringColumn,
-    pub r#type: SizedStaticStringColumn,
+    pub from: SizedSharedStringColumn,
+    pub r#type: SizedSharedStringColumn,
     pub data: SizedStringColumn,
     /// Number of messages in total
     pub msg_count: usize,
@@ -60,19 +78,19 @@ impl From<Vec<Vec<ApiResponseToAnonymous>>> for ApiResponses<'_> {
     fn from(v: Vec<Vec<ApiResponseToAnonymous>>) -> Self {
         // TODO: performance: into_iter to access fields at same time and avoid clones
         ApiResponses {
-            from: SizedStaticStringColumn {
+            from: SizedSharedStringColumn {
                 data: v
                     .iter()
-                    .map(|v| v.iter().map(|v| v.from).collect())
+                    .map(|v| v.iter().map(|v| Arc::clone(&v.from)).collect())
                     .collect(),
                 char_count: v.iter().fold(0, |acc, elem| {
                     acc + elem.iter().map(|e| e.from.len()).sum::<usize>()
                 }),
             },
-            r#type: SizedStaticStringColumn {
+            r#type: SizedSharedStringColumn {
                 data: v
                     .iter()
-                    .map(|v| v.iter().map(|v| v.r#type).collect())
+                    .map(|v| v.iter().map(|v| Arc::clone(&v.r#type)).collect())
                     .collect(),
                 char_count: v.iter().fold(0, |acc, elem| {
                     acc + elem.iter().map(|e| e.r#type.len()).sum::<usize>()
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, fmt};
 
 use lazy_static::lazy_static;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use stateful::field::PackageId;
 
 use crate::{
@@ -16,7 +16,7 @@ use crate::{
 };
 
 /// All context package names are registered in this enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ContextPackageName {
     AgentMessages,
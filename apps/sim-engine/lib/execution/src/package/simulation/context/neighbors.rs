@@ -12,12 +12,15 @@ use stateful::{
     agent::AgentBatch,
     context::{ContextColumn, ContextSchema},
     field::{FieldSpecMapAccessor, RootFieldKey, RootFieldSpec, RootFieldSpecCreator},
-    global::Globals,
+    global::{Dataset, Globals},
     state::{StateReadProxy, StateSnapshot},
 };
 use tracing::Span;
 
-use self::map::{NeighborMap, NeighborRef};
+use self::{
+    map::{NeighborMap, NeighborRef},
+    network::Network,
+};
 use crate::{
     package::simulation::{
         context::{neighbors::fields::NEIGHBORS_FIELD_NAME, ContextPackage, ContextPackageCreator},
@@ -30,7 +33,8 @@ use crate::{
 
 mod adjacency;
 mod fields;
-mod map;
+pub(super) mod map;
+mod network;
 mod writer;
 
 const CPU_BOUND: bool = true;
@@ -46,11 +50,18 @@ impl ContextPackageCreator for NeighborsCreator {
         config: &PackageCreatorConfig,
         _init_config: &PackageInitConfig,
         _comms: PackageComms,
-        _state_field_spec_accessor: FieldSpecMapAccessor,
+        state_field_spec_accessor: FieldSpecMapAccessor,
         context_field_spec_accessor: FieldSpecMapAccessor,
     ) -> Result<Box<dyn ContextPackage>> {
         let neighbors = Neighbors {
             topology: Arc::new(TopologyConfig::from_globals(&config.globals)?),
+            network: Network::from_globals(
+                &config.globals,
+                &config.datasets,
+                &state_field_spec_accessor,
+            )?,
+            datasets: Arc::clone(&config.datasets),
+            state_field_spec_accessor,
             context_field_spec_accessor,
         };
         Ok(Box::new(neighbors))
@@ -81,6 +92,10 @@ impl PackageCreator for NeighborsCreator {
 
 pub struct Neighbors {
     topology: Arc<TopologyConfig>,
+    /// If set, neighbors are given by the edges of the network instead of the agents' positions
+    network: Option<Network>,
+    datasets: Arc<[Dataset]>,
+    state_field_spec_accessor: FieldSpecMapAccessor,
     context_field_spec_accessor: FieldSpecMapAccessor,
 }
 
@@ -99,7 +114,14 @@ impl MaybeCpuBound for Neighbors {
     }
 }
 
-impl Package for Neighbors {}
+impl Package for Neighbors {
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.topology = Arc::new(TopologyConfig::from_globals(globals)?);
+        self.network =
+            Network::from_globals(globals, &self.datasets, &self.state_field_spec_accessor)?;
+        Ok(())
+    }
+}
 
 #[async_trait]
 impl ContextPackage for Neighbors {
@@ -115,8 +137,10 @@ impl ContextPackage for Neighbors {
 
         let agent_pool = state_proxy.agent_pool();
         let batches = agent_pool.batches_iter().collect::<Vec<_>>();
-        let states = Self::neighbor_vec(&batches)?;
-        let map = NeighborMap::gather(states, &self.topology)?;
+        let map = match &self.network {
+            Some(network) => network.gather(&batches)?,
+            None => NeighborMap::gather(Self::neighbor_vec(&batches)?, &self.topology)?,
+        };
 
         let field_key = self
             .context_field_spec_accessor
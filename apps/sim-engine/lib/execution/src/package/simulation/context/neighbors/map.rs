@@ -6,8 +6,8 @@ use stateful::state::AgentIndex;
 
 use crate::{package::simulation::state::topology::TopologyConfig, Error, Result};
 
-pub(super) type PositionSubType = f64;
-pub(super) type Position = [PositionSubType; 3];
+pub(in crate::package::simulation::context) type PositionSubType = f64;
+pub(in crate::package::simulation::context) type Position = [PositionSubType; 3];
 
 pub type Tree<'a> = KdTree<PositionSubType, AgentIndex, Position>;
 
@@ -22,7 +22,9 @@ pub type NeighborRef = ((Option<[f64; 3]>, AgentIndex), Option<f64>);
 
 /// # Errors
 /// This function will not fail
-fn agents_adjacency_map(agents: &'_ [NeighborRef]) -> Result<Tree<'_>> {
+pub(in crate::package::simulation::context) fn agents_adjacency_map(
+    agents: &'_ [NeighborRef],
+) -> Result<Tree<'_>> {
     let mut tree = kdtree::kdtree::KdTree::new(3);
     agents.iter().try_for_each(|((pos, idx), _)| {
         pos.map_or(Ok(()), |unwrapped| {
@@ -33,7 +35,7 @@ fn agents_adjacency_map(agents: &'_ [NeighborRef]) -> Result<Tree<'_>> {
 }
 
 #[allow(clippy::module_name_repetitions)]
-fn gather_neighbors(
+pub(in crate::package::simulation::context) fn gather_neighbors(
     adjacency_map: &Tree<'_>,
     idx: AgentIndex,
     position: &Position,
@@ -0,0 +1,524 @@
+//! Neighbors given by explicit edges between agents instead of their positions.
+//!
+//! A network is configured in the globals under `"network"`. The edges are either read from an
+//! agent field containing the identifiers of the neighbors of every agent, or from an edge list
+//! stored in a dataset:
+//!
+//! ```json
+//! { "network": { "edges_field": "friends" } }
+//! { "network": { "dataset": "edges.csv", "source": "from", "target": "to", "undirected": true } }
+//! ```
+//!
+//! Agents are identified by their `agent_id`, or by their `agent_name` if `"key": "agent_name"` is
+//! set. Edges referring to agents which don't exist (anymore) are ignored.
+
+use std::collections::{HashMap, HashSet};
+
+use arrow2::{datatypes::DataType, io::csv};
+use serde::Deserialize;
+use serde_json::Value;
+use stateful::{
+    agent::{self, AgentBatch},
+    field::FieldSpecMapAccessor,
+    global::{Dataset, Globals},
+    state::AgentIndex,
+};
+use uuid::Uuid;
+
+use crate::{package::simulation::context::neighbors::map::NeighborMap, Error, Result};
+
+/// The agent field used to identify the agents referenced by an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NodeKey {
+    AgentId,
+    AgentName,
+}
+
+impl Default for NodeKey {
+    fn default() -> Self {
+        Self::AgentId
+    }
+}
+
+impl NodeKey {
+    /// Brings an identifier into the form used for the lookup of agents, i.e. agent ids are
+    /// converted into their hyphenated lowercase representation.
+    fn normalize(self, identifier: &str) -> String {
+        match self {
+            Self::AgentId => Uuid::parse_str(identifier)
+                .map(|id| id.to_string())
+                .unwrap_or_else(|_| identifier.to_string()),
+            Self::AgentName => identifier.to_string(),
+        }
+    }
+}
+
+fn default_source() -> String {
+    "source".to_string()
+}
+
+fn default_target() -> String {
+    "target".to_string()
+}
+
+/// The `"network"` object in the globals.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkConfig {
+    /// Agent field containing a list of the identifiers of the agent's neighbors
+    #[serde(default)]
+    edges_field: Option<String>,
+    /// Name of the dataset containing the edge list
+    #[serde(default)]
+    dataset: Option<String>,
+    /// Column or key of the edge list containing the source of an edge
+    #[serde(default = "default_source")]
+    source: String,
+    /// Column or key of the edge list containing the target of an edge
+    #[serde(default = "default_target")]
+    target: String,
+    /// If set, every edge makes both agents neighbors of each other
+    #[serde(default)]
+    undirected: bool,
+    #[serde(default)]
+    key: NodeKey,
+}
+
+enum Edges {
+    /// The edges are read from the agent field with the given name and type every step
+    Field { name: String, data_type: DataType },
+    /// A static edge list of normalized identifiers
+    List(Vec<(String, String)>),
+}
+
+/// A network topology, where the neighbors of an agent are the agents it shares an edge with.
+pub(super) struct Network {
+    edges: Edges,
+    undirected: bool,
+    key: NodeKey,
+}
+
+impl Network {
+    /// Creates the network from the `"network"` object in the globals, or returns `None` if the
+    /// globals don't specify a network.
+    pub(super) fn from_globals(
+        globals: &Globals,
+        datasets: &[Dataset],
+        state_field_spec_accessor: &FieldSpecMapAccessor,
+    ) -> Result<Option<Self>> {
+        let config: NetworkConfig = match globals.0.get("network") {
+            Some(network) => serde_json::from_value(network.clone())
+                .map_err(|err| Error::from(format!("Invalid network in globals: {err}")))?,
+            None => return Ok(None),
+        };
+
+        let edges = match (config.edges_field, config.dataset) {
+            (Some(name), None) => {
+                let data_type = DataType::from(
+                    state_field_spec_accessor
+                        .get_agent_scoped_field_spec(&name)?
+                        .inner
+                        .field_type
+                        .variant
+                        .clone(),
+                );
+                Edges::Field { name, data_type }
+            }
+            (None, Some(dataset_name)) => {
+                let dataset = datasets
+                    .iter()
+                    .find(|dataset| {
+                        dataset.shortname == dataset_name || dataset.filename == dataset_name
+                    })
+                    .ok_or_else(|| {
+                        Error::from(format!("Network dataset '{dataset_name}' does not exist"))
+                    })?;
+                Edges::List(edge_list(
+                    dataset,
+                    &config.source,
+                    &config.target,
+                    config.key,
+                )?)
+            }
+            _ => {
+                return Err(Error::from(
+                    "Network in globals must specify exactly one of 'edges_field' and 'dataset'",
+                ));
+            }
+        };
+
+        Ok(Some(Self {
+            edges,
+            undirected: config.undirected,
+            key: config.key,
+        }))
+    }
+
+    /// Collects the neighbors of every agent in `batches`.
+    ///
+    /// Self-loops and duplicate edges are ignored.
+    pub(super) fn gather(&self, batches: &[&AgentBatch]) -> Result<NeighborMap> {
+        let indices = agent::arrow::index_iter(batches).collect::<Vec<_>>();
+        let identifiers: Vec<Option<String>> = match self.key {
+            NodeKey::AgentId => agent::arrow::agent_id_iter(batches)?
+                .map(|id| Some(Uuid::from_bytes(*id).to_string()))
+                .collect(),
+            NodeKey::AgentName => agent::arrow::agent_name_iter(batches)?
+                .map(|name| name.map(str::to_string))
+                .collect(),
+        };
+
+        // Agent names are not unique, so an identifier may refer to several agents
+        let mut lookup = HashMap::<&str, Vec<usize>>::new();
+        for (position, identifier) in identifiers.iter().enumerate() {
+            if let Some(identifier) = identifier {
+                lookup.entry(identifier).or_default().push(position);
+            }
+        }
+        let resolve = |identifier: &str| lookup.get(identifier).map_or(&[][..], Vec::as_slice);
+
+        let mut neighbors = vec![Vec::new(); indices.len()];
+        let mut add_edge = |source: usize, target: usize| {
+            if source != target {
+                neighbors[source].push(target);
+                if self.undirected {
+                    neighbors[target].push(source);
+                }
+            }
+        };
+        match &self.edges {
+            Edges::Field { name, data_type } => {
+                for (source, value) in
+                    agent::arrow::json_value_iter_cols(batches, name, data_type)?.enumerate()
+                {
+                    for target in identifier_list(&value, name)? {
+                        for &target in resolve(&self.key.normalize(&target)) {
+                            add_edge(source, target);
+                        }
+                    }
+                }
+            }
+            Edges::List(edges) => {
+                for (source, target) in edges {
+                    for &source in resolve(source) {
+                        for &target in resolve(target) {
+                            add_edge(source, target);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut total_count = 0;
+        let data = neighbors
+            .into_iter()
+            .map(|positions| {
+                let mut seen = HashSet::with_capacity(positions.len());
+                let neighbors: Vec<AgentIndex> = positions
+                    .into_iter()
+                    .filter(|position| seen.insert(*position))
+                    .map(|position| indices[position])
+                    .collect();
+                total_count += neighbors.len();
+                neighbors
+            })
+            .collect();
+        Ok(NeighborMap { data, total_count })
+    }
+}
+
+/// Returns the identifiers stored in the edge field of an agent.
+fn identifier_list(value: &Value, field_name: &str) -> Result<Vec<String>> {
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::Array(identifiers) => identifiers
+            .iter()
+            .filter(|identifier| !identifier.is_null())
+            .map(|identifier| identifier_to_string(identifier, field_name))
+            .collect(),
+        _ => Err(Error::from(format!(
+            "Network edge field '{field_name}' must be a list of agent identifiers"
+        ))),
+    }
+}
+
+fn identifier_to_string(identifier: &Value, context: &str) -> Result<String> {
+    match identifier {
+        Value::String(identifier) => Ok(identifier.clone()),
+        Value::Number(identifier) => Ok(identifier.to_string()),
+        _ => Err(Error::from(format!(
+            "Invalid agent identifier in {context}: {identifier}"
+        ))),
+    }
+}
+
+/// Reads the edges from `dataset`.
+///
+/// The dataset is a list of edges, where every edge is either an object containing the `source`
+/// and `target` keys, or a list whose first two elements are the source and the target. In a CSV
+/// dataset, every row is an edge and the `source` and `target` columns are looked up in the header.
+/// CSV datasets without a header use the first two columns.
+fn edge_list(
+    dataset: &Dataset,
+    source: &str,
+    target: &str,
+    key: NodeKey,
+) -> Result<Vec<(String, String)>> {
+    let context = format!("network dataset '{}'", dataset.shortname);
+    let data = dataset
+        .data
+        .as_deref()
+        .ok_or_else(|| Error::from(format!("The {context} has not been loaded")))?;
+    let parse_error =
+        |err: &dyn std::fmt::Display| Error::from(format!("Could not parse {context}: {err}"));
+
+    let (rows, source_column, target_column) = if dataset.raw_csv {
+        let mut reader = csv::read::ReaderBuilder::new()
+            .has_headers(dataset.has_header)
+            .flexible(true)
+            .from_reader(data.as_bytes());
+        let (source_column, target_column) = if dataset.has_header {
+            let header = reader.headers().map_err(|err| parse_error(&err))?;
+            let column = |name: &str| {
+                header
+                    .iter()
+                    .position(|column| column == name)
+                    .ok_or_else(|| Error::from(format!("The {context} has no column '{name}'")))
+            };
+            (column(source)?, column(target)?)
+        } else {
+            (0, 1)
+        };
+        let rows = reader
+            .records()
+            .map(|row| row.map(|row| Value::from(row.iter().collect::<Vec<_>>())))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|err| parse_error(&err))?;
+        (rows, source_column, target_column)
+    } else {
+        let rows: Vec<Value> = serde_json::from_str(data).map_err(|err| parse_error(&err))?;
+        (rows, 0, 1)
+    };
+
+    rows.into_iter()
+        .map(|row| {
+            let (source, target) = match &row {
+                Value::Object(edge) => (edge.get(source), edge.get(target)),
+                Value::Array(edge) => (edge.get(source_column), edge.get(target_column)),
+                _ => (None, None),
+            };
+            match (source, target) {
+                (Some(source), Some(target)) => Ok((
+                    key.normalize(&identifier_to_string(source, &context)?),
+                    key.normalize(&identifier_to_string(target, &context)?),
+                )),
+                _ => Err(Error::from(format!("Invalid edge in {context}: {row}"))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use memory::shared_memory::MemoryId;
+    use serde_json::json;
+    use stateful::{
+        agent::{Agent, AgentSchema},
+        field::{
+            FieldScope, FieldSource, FieldSpecMap, FieldType, FieldTypeVariant, RootFieldSpec,
+            RootFieldSpecCreator,
+        },
+    };
+
+    use super::*;
+
+    /// Creates an agent batch for every list of agents in `groups`.
+    ///
+    /// Every agent has a `friends` field containing a list of agent names.
+    fn agent_batches(groups: Value) -> (Arc<FieldSpecMap>, Vec<AgentBatch>) {
+        let mut field_spec_map = FieldSpecMap::empty();
+        field_spec_map
+            .try_extend(RootFieldSpec::base_agent_fields().unwrap())
+            .unwrap();
+        field_spec_map
+            .try_extend([RootFieldSpecCreator::new(FieldSource::Engine).create(
+                "friends".to_string(),
+                FieldType::new(
+                    FieldTypeVariant::VariableLengthArray(Box::new(FieldType::new(
+                        FieldTypeVariant::String,
+                        true,
+                    ))),
+                    true,
+                ),
+                FieldScope::Agent,
+            )])
+            .unwrap();
+        let schema = AgentSchema::new(field_spec_map).unwrap();
+
+        let groups: Vec<Vec<Agent>> = serde_json::from_value(groups).unwrap();
+        let batches = groups
+            .iter()
+            .map(|agents| {
+                AgentBatch::from_agent_states(
+                    agents.as_slice(),
+                    &schema,
+                    MemoryId::new(Uuid::new_v4()),
+                )
+                .unwrap()
+            })
+            .collect();
+        (Arc::clone(&schema.field_spec_map), batches)
+    }
+
+    fn network(network: Value, datasets: &[Dataset], field_spec_map: Arc<FieldSpecMap>) -> Network {
+        Network::from_globals(
+            &Globals(json!({ "network": network })),
+            datasets,
+            &FieldSpecMapAccessor::new(FieldSource::Engine, field_spec_map),
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    fn index(group_index: u32, agent_index: u32) -> AgentIndex {
+        AgentIndex {
+            group_index,
+            agent_index,
+        }
+    }
+
+    fn dataset(data: Value) -> Dataset {
+        Dataset {
+            name: None,
+            shortname: "edges".to_string(),
+            filename: "edges".to_string(),
+            url: None,
+            raw_csv: false,
+            has_header: true,
+            data: Some(data.to_string()),
+        }
+    }
+
+    fn csv_dataset(data: &str, has_header: bool) -> Dataset {
+        Dataset {
+            name: None,
+            shortname: "edges.csv".to_string(),
+            filename: "edges.csv".to_string(),
+            url: None,
+            raw_csv: true,
+            has_header,
+            data: Some(data.to_string()),
+        }
+    }
+
+    #[test]
+    fn edge_lists_are_read_from_json_and_csv() {
+        let expected = vec![
+            ("a".to_string(), "b".to_string()),
+            ("b".to_string(), "c".to_string()),
+        ];
+
+        let objects = json!([{"from": "a", "to": "b"}, {"from": "b", "to": "c"}]);
+        assert_eq!(
+            edge_list(&dataset(objects), "from", "to", NodeKey::AgentName).unwrap(),
+            expected
+        );
+
+        let pairs = json!([["a", "b"], ["b", "c"]]);
+        assert_eq!(
+            edge_list(&dataset(pairs), "from", "to", NodeKey::AgentName).unwrap(),
+            expected
+        );
+
+        let csv = "weight,to,from\n1,b,a\n2,c,b\n";
+        assert_eq!(
+            edge_list(&csv_dataset(csv, true), "from", "to", NodeKey::AgentName).unwrap(),
+            expected
+        );
+        let csv = "a,b\nb,c\n";
+        assert_eq!(
+            edge_list(&csv_dataset(csv, false), "from", "to", NodeKey::AgentName).unwrap(),
+            expected
+        );
+        assert!(edge_list(
+            &csv_dataset("weight,to\n1,b\n", true),
+            "from",
+            "to",
+            NodeKey::AgentName
+        )
+        .is_err());
+        assert!(edge_list(&dataset(json!([["a"]])), "from", "to", NodeKey::AgentName).is_err());
+    }
+
+    #[test]
+    fn gather_from_edge_field() {
+        let (field_spec_map, batches) = agent_batches(json!([
+            [
+                // Duplicates, self-loops and unknown agents are ignored
+                { "agent_name": "a", "friends": ["b", "c", "b", "a", "unknown"] },
+                { "agent_name": "b", "friends": [] },
+            ],
+            [{ "agent_name": "c", "friends": null }],
+        ]));
+        let batches = batches.iter().collect::<Vec<_>>();
+
+        let directed = network(
+            json!({ "edges_field": "friends", "key": "agent_name" }),
+            &[],
+            Arc::clone(&field_spec_map),
+        );
+        let neighbors = directed.gather(&batches).unwrap();
+        assert_eq!(
+            neighbors.data,
+            vec![vec![index(0, 1), index(1, 0)], vec![], vec![]]
+        );
+        assert_eq!(neighbors.total_count, 2);
+
+        let undirected = network(
+            json!({ "edges_field": "friends", "key": "agent_name", "undirected": true }),
+            &[],
+            field_spec_map,
+        );
+        let neighbors = undirected.gather(&batches).unwrap();
+        assert_eq!(
+            neighbors.data,
+            vec![
+                vec![index(0, 1), index(1, 0)],
+                vec![index(0, 0)],
+                vec![index(0, 0)]
+            ]
+        );
+        assert_eq!(neighbors.total_count, 4);
+    }
+
+    #[test]
+    fn gather_from_edge_list() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let (field_spec_map, batches) = agent_batches(json!([
+            [{ "agent_id": a.to_string() }],
+            [{ "agent_id": b.to_string() }, { "agent_id": Uuid::new_v4().to_string() }],
+        ]));
+        let batches = batches.iter().collect::<Vec<_>>();
+
+        // Agent ids are normalized, so they may be written in upper case or without hyphens
+        let edges = json!([
+            [a.as_simple().to_string().to_uppercase(), b.to_string()],
+            [b.to_string(), Uuid::new_v4().to_string()],
+        ]);
+        let network = network(
+            json!({ "dataset": "edges", "undirected": true }),
+            &[dataset(edges)],
+            field_spec_map,
+        );
+        let neighbors = network.gather(&batches).unwrap();
+        assert_eq!(
+            neighbors.data,
+            vec![vec![index(1, 0)], vec![index(0, 0)], vec![]]
+        );
+        assert_eq!(neighbors.total_count, 2);
+    }
+}
@@ -8,6 +8,7 @@
 //! [`State`]: stateful::state::State
 //! [`Context`]: stateful::context::Context
 
+pub mod columnar;
 pub mod js_py;
 pub mod json;
 
@@ -18,6 +19,7 @@ mod state;
 mod task;
 
 use async_trait::async_trait;
+use memory::arrow::record_batch::RecordBatch;
 use stateful::{agent::Agent, field::FieldSpecMapAccessor};
 
 pub use self::{
@@ -35,9 +37,16 @@ use crate::{
     Result,
 };
 
+/// The agents created by an [`InitPackage`].
+pub enum InitialAgents {
+    Agents(Vec<Agent>),
+    /// Record batches of the agent schema, which are used without converting them into [`Agent`]s
+    Batches(Vec<RecordBatch>),
+}
+
 #[async_trait]
 pub trait InitPackage: Package + MaybeCpuBound {
-    async fn run(&mut self) -> Result<Vec<Agent>>;
+    async fn run(&mut self) -> Result<InitialAgents>;
 }
 
 pub trait InitPackageCreator: PackageCreator {
@@ -0,0 +1,232 @@
+//! Initial state generation from a CSV, Parquet, or Arrow IPC file.
+//!
+//! The file is read in chunks, which are converted directly into record batches of the agent
+//! schema without creating an [`Agent`](stateful::agent::Agent) for every row. Columns are matched
+//! to agent fields by name. Parquet and Arrow IPC columns keep the type stored in the file and are
+//! cast to the type of the field, CSV columns are read as the type of the field. See
+//! [`columns_to_agent_batch`] for details. Agents without an `agent_id` get an id, which is derived
+//! from the seed in seeded simulation runs.
+
+use std::{cell::Cell, fs::File, io::BufReader, path::PathBuf, sync::Arc};
+
+use arrow2::{
+    array::Array,
+    chunk::Chunk,
+    datatypes::{DataType, Field, Schema},
+    io::{csv, ipc, parquet},
+};
+use async_trait::async_trait;
+use memory::arrow::record_batch::RecordBatch;
+use stateful::{
+    agent::{arrow::columns_to_agent_batch, AgentId, AgentSchema},
+    field::{FieldSpecMapAccessor, FieldTypeVariant, RootFieldKey},
+};
+
+use crate::{
+    package::simulation::{
+        init::{InitPackage, InitPackageCreator, InitialAgents, InitialStateName},
+        MaybeCpuBound, Package, PackageComms, PackageCreator, PackageCreatorConfig,
+        PackageInitConfig, Seed,
+    },
+    Error, Result,
+};
+
+/// The number of rows read from a CSV file at once.
+const CSV_CHUNK_SIZE: usize = 1 << 16;
+
+pub struct ColumnarInit {
+    name: InitialStateName,
+    path: PathBuf,
+    agent_schema: Arc<AgentSchema>,
+    seed: Option<Seed>,
+    /// The number of agents read so far, which didn't have an `agent_id`.
+    num_new_agent_ids: Cell<usize>,
+}
+
+impl Package for ColumnarInit {}
+
+impl MaybeCpuBound for ColumnarInit {
+    fn cpu_bound(&self) -> bool {
+        true
+    }
+}
+
+impl ColumnarInit {
+    fn to_agent_batch(
+        &self,
+        schema: &Schema,
+        chunk: &Chunk<Box<dyn Array>>,
+    ) -> Result<RecordBatch> {
+        Ok(columns_to_agent_batch(
+            schema,
+            chunk,
+            &self.agent_schema,
+            || self.new_agent_id(),
+        )?)
+    }
+
+    fn new_agent_id(&self) -> AgentId {
+        let index = self.num_new_agent_ids.get();
+        self.num_new_agent_ids.set(index + 1);
+        self.seed
+            .map_or_else(AgentId::generate, |seed| seed.initial_agent_id(index))
+    }
+
+    fn read_csv(&self) -> Result<Vec<RecordBatch>> {
+        let mut reader = csv::read::ReaderBuilder::new()
+            .from_path(&self.path)
+            .map_err(|error| Error::from(error.to_string()))?;
+        // Only columns matching an agent field are read
+        let mut projection = Vec::new();
+        let fields = reader
+            .headers()
+            .map_err(|error| Error::from(error.to_string()))?
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                match self
+                    .agent_schema
+                    .arrow
+                    .fields
+                    .iter()
+                    .find(|f| f.name == name)
+                {
+                    Some(field) => {
+                        projection.push(index);
+                        Field::new(name, self.csv_data_type(field), true)
+                    }
+                    None => Field::new(name, DataType::Utf8, true),
+                }
+            })
+            .collect::<Vec<_>>();
+        let schema = Schema::from(
+            projection
+                .iter()
+                .map(|&index| fields[index].clone())
+                .collect::<Vec<_>>(),
+        );
+
+        let mut batches = Vec::new();
+        let mut rows = vec![csv::read::ByteRecord::default(); CSV_CHUNK_SIZE];
+        let mut line_number = 0;
+        loop {
+            let rows_read = csv::read::read_rows(&mut reader, 0, &mut rows)?;
+            if rows_read == 0 {
+                break;
+            }
+            let chunk = csv::read::deserialize_batch(
+                &rows[..rows_read],
+                &fields,
+                Some(&projection),
+                line_number,
+                csv::read::deserialize_column,
+            )?;
+            batches.push(self.to_agent_batch(&schema, &chunk)?);
+            line_number += rows_read;
+        }
+        Ok(batches)
+    }
+
+    /// The type a CSV column is read as, which is the type of the agent `field` if the CSV reader
+    /// supports it.
+    ///
+    /// Other columns are read as strings, which are parsed as JSON when converting the chunk.
+    fn csv_data_type(&self, field: &Field) -> DataType {
+        let is_categorical = self
+            .agent_schema
+            .field_spec_map
+            .get_field_spec(&RootFieldKey::new(field.name.clone()))
+            .map_or(false, |spec| {
+                matches!(
+                    spec.inner.field_type.variant,
+                    FieldTypeVariant::Categorical(_)
+                )
+            });
+        match field.data_type() {
+            DataType::Boolean
+            | DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+                if !is_categorical =>
+            {
+                field.data_type().clone()
+            }
+            _ => DataType::Utf8,
+        }
+    }
+
+    fn read_parquet(&self) -> Result<Vec<RecordBatch>> {
+        let reader = BufReader::new(File::open(&self.path)?);
+        let reader = parquet::read::FileReader::try_new(reader, None, None, None, None)?;
+        let schema = reader.schema().clone();
+        reader
+            .map(|chunk| self.to_agent_batch(&schema, &chunk?))
+            .collect()
+    }
+
+    fn read_arrow(&self) -> Result<Vec<RecordBatch>> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let metadata = ipc::read::read_file_metadata(&mut reader)?;
+        let schema = metadata.schema.clone();
+        ipc::read::FileReader::new(reader, metadata, None)
+            .map(|chunk| self.to_agent_batch(&schema, &chunk?))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl InitPackage for ColumnarInit {
+    async fn run(&mut self) -> Result<InitialAgents> {
+        let batches = match self.name {
+            InitialStateName::InitCsv => self.read_csv(),
+            InitialStateName::InitParquet => self.read_parquet(),
+            InitialStateName::InitArrow => self.read_arrow(),
+            _ => unreachable!("columnar init package was created for {:?}", self.name),
+        }
+        .map_err(|error| {
+            Error::from(format!(
+                "Failed to read initial state from {:?}: {error}",
+                self.path
+            ))
+        })?;
+        Ok(InitialAgents::Batches(batches))
+    }
+}
+
+pub struct ColumnarInitCreator;
+
+impl InitPackageCreator for ColumnarInitCreator {
+    fn create(
+        &self,
+        config: &PackageCreatorConfig,
+        init_config: &PackageInitConfig,
+        _comms: PackageComms,
+        _accessor: FieldSpecMapAccessor,
+    ) -> Result<Box<dyn InitPackage>> {
+        let initial_state = &init_config.initial_state;
+        if initial_state.name.is_columnar() {
+            Ok(Box::new(ColumnarInit {
+                name: initial_state.name.clone(),
+                path: PathBuf::from(&initial_state.src),
+                agent_schema: Arc::clone(&config.agent_schema),
+                seed: config.seed,
+                num_new_agent_ids: Cell::new(0),
+            }))
+        } else {
+            Err(Error::from(format!(
+                "Trying to create a columnar init package but the init file didn't end in .csv, \
+                 .parquet or .arrow but instead was: {:?}",
+                initial_state.name
+            )))
+        }
+    }
+}
+
+impl PackageCreator for ColumnarInitCreator {}
@@ -3,7 +3,8 @@ use std::{collections::HashMap, sync::OnceLock};
 use crate::{
     package::simulation::{
         init::{
-            js_py::JsPyInitCreator, json::JsonInitCreator, InitPackageCreator, InitPackageName,
+            columnar::ColumnarInitCreator, js_py::JsPyInitCreator, json::JsonInitCreator,
+            InitPackageCreator, InitPackageName,
         },
         PackageInitConfig,
     },
@@ -19,9 +20,10 @@ impl InitPackageCreators {
         static PACKAGE_CREATORS: OnceLock<InitPackageCreators> = OnceLock::new();
         PACKAGE_CREATORS.get_or_try_init(|| {
             tracing::debug!("Initializing Init Package Creators");
-            let mut creators = HashMap::<_, Box<dyn InitPackageCreator>>::with_capacity(2);
+            let mut creators = HashMap::<_, Box<dyn InitPackageCreator>>::with_capacity(3);
             creators.insert(InitPackageName::Json, Box::new(JsonInitCreator));
             creators.insert(InitPackageName::JsPy, Box::new(JsPyInitCreator));
+            creators.insert(InitPackageName::Columnar, Box::new(ColumnarInitCreator));
             Ok(Self { creators })
         })
     }
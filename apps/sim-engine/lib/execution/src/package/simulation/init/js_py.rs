@@ -4,7 +4,7 @@ mod message;
 mod task;
 
 use async_trait::async_trait;
-use stateful::{agent::Agent, field::FieldSpecMapAccessor};
+use stateful::field::FieldSpecMapAccessor;
 
 pub use self::{
     message::{FailedMessage, JsPyInitTaskMessage, StartMessage, SuccessMessage},
@@ -13,11 +13,11 @@ pub use self::{
 use crate::{
     package::simulation::{
         init::{
-            InitPackage, InitPackageCreator, InitTask, InitTaskMessage, InitialState,
-            InitialStateName,
+            InitPackage, InitPackageCreator, InitTask, InitTaskMessage, InitialAgents,
+            InitialState, InitialStateName,
         },
         MaybeCpuBound, Package, PackageComms, PackageCreator, PackageCreatorConfig,
-        PackageInitConfig, PackageTask,
+        PackageInitConfig, PackageTask, Seed,
     },
     task::{TaskMessage, TaskSharedStore},
     Error, Result,
@@ -25,6 +25,7 @@ use crate::{
 
 pub struct JsPyInit {
     initial_state: InitialState,
+    seed: Option<Seed>,
     comms: PackageComms,
 }
 
@@ -38,13 +39,15 @@ impl Package for JsPyInit {}
 
 #[async_trait]
 impl InitPackage for JsPyInit {
-    async fn run(&mut self) -> Result<Vec<Agent>> {
+    async fn run(&mut self) -> Result<InitialAgents> {
         let task = match &self.initial_state.name {
             InitialStateName::InitPy => InitTask::PyInitTask(PyInitTask {
                 initial_state_source: self.initial_state.src.clone(),
+                seed: self.seed,
             }),
             InitialStateName::InitJs => InitTask::JsInitTask(JsInitTask {
                 initial_state_source: self.initial_state.src.clone(),
+                seed: self.seed,
             }),
             name => {
                 // should be unreachable
@@ -67,7 +70,9 @@ impl InitPackage for JsPyInit {
         };
 
         match task_message {
-            JsPyInitTaskMessage::Success(SuccessMessage { agents }) => Ok(agents),
+            JsPyInitTaskMessage::Success(SuccessMessage { agents }) => {
+                Ok(InitialAgents::Agents(agents))
+            }
             _ => Err(Error::from("Init Task failed")),
         }
     }
@@ -83,7 +88,7 @@ impl PackageCreator for JsPyInitCreator {
 impl InitPackageCreator for JsPyInitCreator {
     fn create(
         &self,
-        _config: &PackageCreatorConfig,
+        config: &PackageCreatorConfig,
         init_config: &PackageInitConfig,
         comms: PackageComms,
         _accessor: FieldSpecMapAccessor,
@@ -91,6 +96,7 @@ impl InitPackageCreator for JsPyInitCreator {
         match &init_config.initial_state.name {
             InitialStateName::InitPy | InitialStateName::InitJs => Ok(Box::new(JsPyInit {
                 initial_state: init_config.initial_state.clone(),
+                seed: config.seed,
                 comms,
             })),
             name => Err(Error::from(format!(
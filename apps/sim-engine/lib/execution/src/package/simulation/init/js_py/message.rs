@@ -13,6 +13,8 @@ pub enum JsPyInitTaskMessage {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StartMessage {
     pub initial_state_source: String,
+    /// The seed of the simulation run, if it's seeded
+    pub seed: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
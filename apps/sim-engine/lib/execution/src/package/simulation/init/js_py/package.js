@@ -1,3 +1,5 @@
+import * as hash_util from "./lib/execution/src/runner/javascript/hash_util.js";
+
 const _prepare_user_trace = (error, trace) => {
   // TODO: Check that line numbers aren't off by 2
   const frames = [];
@@ -15,14 +17,28 @@ const _prepare_user_trace = (error, trace) => {
   };
 };
 
-const _load_init_fn = (console, source) => {
+/// Returns `hash_stdlib` as passed to the init function. In seeded simulation runs, the ids returned
+/// by `generateAgentID` are derived from `seed` and the number of ids generated before.
+const _stdlib = (seed) => {
+  if (seed === null || seed === undefined) {
+    return hash_stdlib;
+  }
+  let num_agent_ids = 0;
+  return Object.freeze({
+    ...hash_stdlib,
+    generateAgentID: () =>
+      hash_util.uuid_from_key(seed + ":init:agent_id:" + num_agent_ids++),
+  });
+};
+
+const _load_init_fn = (console, source, stdlib) => {
   try {
     const fn = new Function(
       "hash_stdlib",
       "hstd",
       "console",
       `${source}\nreturn init`,
-    )(hash_stdlib, hash_stdlib, console);
+    )(stdlib, stdlib, console);
 
     if (typeof fn !== "function") {
       throw new Error(`must be a function not '${typeof fn}'`);
@@ -83,7 +99,11 @@ export const run_task = (
     },
   );
 
-  let init_fn = _load_init_fn(console, source);
+  let init_fn = _load_init_fn(
+    console,
+    source,
+    _stdlib(task_message.Start.seed),
+  );
 
   let agents;
   try {
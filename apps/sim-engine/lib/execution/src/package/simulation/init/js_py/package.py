@@ -10,7 +10,7 @@ class UserCodeError(Exception):
 
 def _load_initializer(code):
     try:
-        init_globals = dict()
+        init_globals = {"hash_stdlib": hash_stdlib, "hstd": hash_stdlib}
         bytecode = compile(code, "init.py", "exec")
         exec(bytecode, init_globals)
         init_fn = init_globals.get("init")
@@ -32,6 +32,9 @@ def run_task(_experiment, _sim, task_message, _group_state, context):
         raise Exception("Unknown message type received, expected a Start")
 
     state_src = task_message["Start"]["initial_state_source"]
+    # In seeded simulation runs, agent ids generated by `init` are derived from the seed
+    seed = task_message["Start"]["seed"]
+    hash_stdlib.seed_agent_ids(None if seed is None else f"{seed}:init:agent_id")
     init_fn = _load_initializer(code=state_src)
 
     try:
@@ -1,7 +1,10 @@
 use crate::{
-    package::simulation::init::{
-        js_py::{JsPyInitTaskMessage, StartMessage},
-        InitTaskMessage,
+    package::simulation::{
+        init::{
+            js_py::{JsPyInitTaskMessage, StartMessage},
+            InitTaskMessage,
+        },
+        Seed,
     },
     runner::MessageTarget,
     task::{TargetedTaskMessage, Task, TaskMessage},
@@ -12,6 +15,7 @@ use crate::{
 #[derive(Clone, Debug)]
 pub struct JsInitTask {
     pub initial_state_source: String,
+    pub seed: Option<Seed>,
 }
 
 impl Task for JsInitTask {
@@ -24,6 +28,7 @@ impl WorkerHandler for JsInitTask {
     fn start_message(&self) -> Result<TargetedTaskMessage> {
         let jspy_init_task_msg = JsPyInitTaskMessage::Start(StartMessage {
             initial_state_source: self.initial_state_source.clone(),
+            seed: self.seed.map(|seed| seed.to_string()),
         });
         let init_task_msg = InitTaskMessage::JsPyInitTaskMessage(jspy_init_task_msg);
         Ok(TargetedTaskMessage {
@@ -36,6 +41,7 @@ impl WorkerHandler for JsInitTask {
 #[derive(Clone, Debug)]
 pub struct PyInitTask {
     pub initial_state_source: String,
+    pub seed: Option<Seed>,
 }
 
 impl Task for PyInitTask {
@@ -48,6 +54,7 @@ impl WorkerHandler for PyInitTask {
     fn start_message(&self) -> Result<TargetedTaskMessage> {
         let start_msg = StartMessage {
             initial_state_source: self.initial_state_source.clone(),
+            seed: self.seed.map(|seed| seed.to_string()),
         };
         let jspy_task_msg = JsPyInitTaskMessage::Start(start_msg);
         let init_task_msg = InitTaskMessage::JsPyInitTaskMessage(jspy_task_msg);
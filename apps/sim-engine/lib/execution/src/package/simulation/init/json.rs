@@ -5,7 +5,7 @@ use stateful::{agent::Agent, field::FieldSpecMapAccessor};
 
 use crate::{
     package::simulation::{
-        init::{InitPackage, InitPackageCreator, InitialStateName},
+        init::{InitPackage, InitPackageCreator, InitialAgents, InitialStateName},
         MaybeCpuBound, Package, PackageComms, PackageCreator, PackageCreatorConfig,
         PackageInitConfig,
     },
@@ -26,13 +26,15 @@ impl MaybeCpuBound for JsonInit {
 
 #[async_trait]
 impl InitPackage for JsonInit {
-    async fn run(&mut self) -> Result<Vec<Agent>> {
+    async fn run(&mut self) -> Result<InitialAgents> {
         // TODO: Map Error when we design package errors
-        serde_json::from_str(&self.initial_state_src).map_err(|e| {
-            Error::from(format!(
-                "Failed to parse agent state JSON to Vec<Agent>: {e:?}"
-            ))
-        })
+        serde_json::from_str::<Vec<Agent>>(&self.initial_state_src)
+            .map(InitialAgents::Agents)
+            .map_err(|e| {
+                Error::from(format!(
+                    "Failed to parse agent state JSON to Vec<Agent>: {e:?}"
+                ))
+            })
     }
 }
 
@@ -1,23 +1,24 @@
 use std::{collections::HashMap, fmt};
 
 use lazy_static::lazy_static;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use stateful::field::PackageId;
 
 use crate::{
     package::simulation::{
-        init::{js_py::JsPyInitCreator, json::JsonInitCreator},
+        init::{columnar::ColumnarInitCreator, js_py::JsPyInitCreator, json::JsonInitCreator},
         Dependencies, PackageCreator, PackageIdGenerator, PackageMetadata, PackageType,
     },
     Error, Result,
 };
 
 /// All init package names are registered in this enum
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum InitPackageName {
     Json,
     JsPy,
+    Columnar,
 }
 
 impl InitPackageName {
@@ -46,7 +47,7 @@ impl fmt::Display for InitPackageName {
 
 lazy_static! {
     static ref METADATA: HashMap<InitPackageName, PackageMetadata> = {
-        use InitPackageName::{JsPy, Json};
+        use InitPackageName::{Columnar, JsPy, Json};
         let mut id_creator = PackageIdGenerator::new(PackageType::Init);
         let mut m = HashMap::new();
         m.insert(
@@ -63,6 +64,13 @@ lazy_static! {
                 dependencies: JsPyInitCreator::dependencies(),
             },
         );
+        m.insert(
+            Columnar,
+            PackageMetadata {
+                id: id_creator.next(),
+                dependencies: ColumnarInitCreator::dependencies(),
+            },
+        );
         m
     };
 }
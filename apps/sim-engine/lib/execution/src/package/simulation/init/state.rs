@@ -6,10 +6,23 @@ pub enum InitialStateName {
     InitJson,
     InitPy,
     InitJs,
+    InitCsv,
+    InitParquet,
+    InitArrow,
+}
+
+impl InitialStateName {
+    /// Returns if the initial state is read from a columnar file.
+    ///
+    /// Columnar files can get large, so they are only read when the simulation is initialized.
+    pub fn is_columnar(&self) -> bool {
+        matches!(self, Self::InitCsv | Self::InitParquet | Self::InitArrow)
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct InitialState {
     pub name: InitialStateName,
+    /// The contents of the initial state file, or its path for columnar initial states.
     pub src: String,
 }
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use stateful::field::PackageId;
 
 use crate::{
@@ -11,7 +11,10 @@ use crate::{
     Result,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The name of any package, (de)serialized as the name of the package without its type, e.g.
+/// `"neighbors"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(untagged)]
 pub enum PackageName {
     Context(ContextPackageName),
     Init(InitPackageName),
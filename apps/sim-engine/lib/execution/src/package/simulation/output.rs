@@ -50,7 +50,16 @@ pub enum Output {
 
 #[async_trait]
 pub trait OutputPackage: Package + MaybeCpuBound {
-    async fn run(&mut self, state: Arc<State>, context: Arc<Context>) -> Result<Output>;
+    /// Computes the output of `step` from the `state` and `context` of that step.
+    ///
+    /// Step 0 is the initial state. The initial state of a resumed simulation run is the state at
+    /// the step of its checkpoint.
+    async fn run(
+        &mut self,
+        state: Arc<State>,
+        context: Arc<Context>,
+        step: usize,
+    ) -> Result<Output>;
 
     fn span(&self) -> Span;
 }
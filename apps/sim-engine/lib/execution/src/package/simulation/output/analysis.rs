@@ -6,8 +6,10 @@ mod macros;
 mod analyzer;
 mod buffer;
 mod config;
+mod context_iter;
 mod index_iter;
 mod output;
+mod statistics;
 mod validation;
 mod value_iter;
 
@@ -19,11 +21,13 @@ use stateful::{
 };
 use tracing::Span;
 
-use self::analyzer::Analyzer;
+pub(crate) use self::analyzer::ULPS;
+use self::analyzer::{Agents, AnalysisSourceRepr, Analyzer};
 pub use self::{
+    analyzer::ComparisonRepr,
     buffer::AnalysisBuffer,
     config::AnalysisOutputConfig,
-    output::{AnalysisFinalOutput, AnalysisOutput, AnalysisSingleOutput},
+    output::{AnalysisFinalOutput, AnalysisOutput, AnalysisSingleOutput, Histogram},
 };
 use crate::{
     package::simulation::{
@@ -48,12 +52,29 @@ impl Package for Analysis {}
 
 #[async_trait]
 impl OutputPackage for Analysis {
-    async fn run(&mut self, state: Arc<State>, _context: Arc<Context>) -> Result<Output> {
+    async fn run(
+        &mut self,
+        state: Arc<State>,
+        context: Arc<Context>,
+        _step: usize,
+    ) -> Result<Output> {
         // TODO: use filtering to avoid exposing hidden values to users
         let agent_proxies = state.agent_pool().read_proxies()?;
+        let sent_message_proxies = state.message_pool().read_proxies()?;
+        let received_message_proxies = context.message_pool().read_proxies()?;
         // TODO: propagate Deref trait bound through run
         let dynamic_pool = agent_proxies.batches_iter().collect::<Vec<_>>();
-        self.analyzer.run(&dynamic_pool, state.num_agents())?;
+        let sent_messages = sent_message_proxies.batches_iter().collect::<Vec<_>>();
+        let received_messages = received_message_proxies.batches_iter().collect::<Vec<_>>();
+        self.analyzer.run(
+            Agents {
+                batches: &dynamic_pool,
+                sent_messages: &sent_messages,
+                received_messages: &received_messages,
+                context: context.global_batch(),
+            },
+            state.num_agents(),
+        )?;
 
         Ok(Output::AnalysisOutput(
             self.analyzer.get_latest_output_set(),
@@ -80,6 +101,19 @@ pub(self) fn get_analysis_source(sim_packages: &[SimPackageArgs]) -> Result<Stri
     Err(Error::from("Did not find analysis source"))
 }
 
+/// Validates the analysis definition (_analysis.json_) without creating an analyzer.
+///
+/// The fields referenced by the operations are not validated, as they depend on the packages of
+/// the experiment.
+///
+/// # Errors
+///
+/// - if `analysis_source` is not valid JSON
+/// - if an output has no operations or its operations can't be chained
+pub fn validate_analysis_source(analysis_source: &str) -> Result<()> {
+    AnalysisSourceRepr::try_from(analysis_source)?.validate_def()
+}
+
 pub struct AnalysisCreator;
 
 impl OutputPackageCreator for AnalysisCreator {
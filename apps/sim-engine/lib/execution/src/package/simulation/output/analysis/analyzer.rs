@@ -4,19 +4,30 @@ use json_comments::StripComments;
 use serde::{Deserialize, Serialize};
 use stateful::{
     agent::{AgentBatch, AgentSchema},
+    context::ContextBatch,
     field::FieldSpecMapAccessor,
+    message::MessageBatch,
 };
 
 use crate::{
     package::simulation::output::analysis::{
-        index_iter, AnalysisFinalOutput, AnalysisOutput, AnalysisSingleOutput,
+        context_iter, index_iter, AnalysisFinalOutput, AnalysisOutput, AnalysisSingleOutput,
     },
     Error, Result,
 };
 
 pub(crate) const ULPS: i64 = 2;
 
-type Agents<'a> = &'a [&'a AgentBatch];
+/// The agents of a step together with their context.
+#[derive(Clone, Copy)]
+pub struct Agents<'a> {
+    pub batches: &'a [&'a AgentBatch],
+    /// The messages sent by the agents in the current step, one batch per agent batch
+    pub sent_messages: &'a [&'a MessageBatch],
+    /// The messages sent in the previous step, which are referenced by the context
+    pub received_messages: &'a [&'a MessageBatch],
+    pub context: &'a ContextBatch,
+}
 pub(crate) type IndexIterator<'a> = Box<dyn Iterator<Item = usize> + Send + Sync + 'a>;
 pub(crate) type OutputRunner<'agents> =
     Box<dyn FnOnce(IndexIterator<'agents>) -> Result<AnalysisSingleOutput> + Send + Sync + 'agents>;
@@ -64,11 +75,11 @@ impl Analyzer {
         })
     }
 
-    pub fn run(&mut self, dynamic_pool: &[&AgentBatch], num_agents: usize) -> Result<()> {
+    pub fn run(&mut self, agents: Agents<'_>, num_agents: usize) -> Result<()> {
         self.outputs
             .iter_mut()
             .try_for_each(|(output_name, creator, outputs)| {
-                let output = creator.run(dynamic_pool, num_agents).map_err(|e| {
+                let output = creator.run(agents, num_agents).map_err(|e| {
                     Error::from(format!(
                         "Error in the analysis output \"{}\": {:?}",
                         output_name, e
@@ -138,8 +149,8 @@ impl OutputCreator {
         Ok(OutputCreator { creator })
     }
 
-    fn run(&self, dynamic_pool: &[&AgentBatch], num_agents: usize) -> Result<AnalysisSingleOutput> {
-        ((self.creator)(dynamic_pool)?)(Box::new(0..num_agents))
+    fn run(&self, agents: Agents<'_>, num_agents: usize) -> Result<AnalysisSingleOutput> {
+        ((self.creator)(agents)?)(Box::new(0..num_agents))
     }
 
     pub(super) fn index_creator(
@@ -169,6 +180,17 @@ impl OutputCreator {
             AnalysisOperationRepr::Get { field: _ } => {
                 index_iter::index_iterator_mapper_creator(operations, accessor)
             }
+            AnalysisOperationRepr::NeighborCount
+            | AnalysisOperationRepr::SentMessageCount { .. }
+            | AnalysisOperationRepr::ReceivedMessageCount { .. } => {
+                index_iter::index_iterator_context_mapper_creator(operations)
+            }
+            AnalysisOperationRepr::Isolated => {
+                context_iter::index_iterator_isolated_creator(operations, accessor)
+            }
+            AnalysisOperationRepr::MessageTypeCounts { received } => Ok(
+                context_iter::index_iterator_message_types_creator(*received),
+            ),
             AnalysisOperationRepr::Count => Ok(Box::new(move |_| {
                 Ok(Box::new(
                     move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -176,10 +198,26 @@ impl OutputCreator {
                     },
                 ))
             })),
+            AnalysisOperationRepr::GroupBy { field } => {
+                index_iter::index_iterator_group_by_creator(
+                    operations,
+                    accessor,
+                    field
+                        .as_str()
+                        .ok_or_else(|| Error::from("A 'group_by' must access a field by string"))?
+                        .to_string(),
+                )
+            }
             AnalysisOperationRepr::Sum
             | AnalysisOperationRepr::Min
             | AnalysisOperationRepr::Max
-            | AnalysisOperationRepr::Mean => Err(Error::from(
+            | AnalysisOperationRepr::Mean
+            | AnalysisOperationRepr::StdDev
+            | AnalysisOperationRepr::Variance
+            | AnalysisOperationRepr::Median
+            | AnalysisOperationRepr::Percentile { .. }
+            | AnalysisOperationRepr::Histogram { .. }
+            | AnalysisOperationRepr::CountDistinct => Err(Error::from(
                 "Aggregators of numbers may not be called directly",
             )),
         }
@@ -208,11 +246,57 @@ pub enum AnalysisOperationRepr {
     Get {
         field: serde_json::Value, // May be a string or an index (usize)
     },
+    /// Splits the agents by the value of `field` and applies the following operations to every
+    /// group separately
+    GroupBy {
+        field: serde_json::Value,
+    },
+    /// Maps every agent to its number of neighbors
+    NeighborCount,
+    /// Keeps only agents without neighbors
+    Isolated,
+    /// Maps every agent to the number of messages it sent in the current step
+    SentMessageCount {
+        /// Only count messages of this type
+        #[serde(default, rename = "type")]
+        message_type: Option<String>,
+    },
+    /// Maps every agent to the number of messages it received in the current step
+    ReceivedMessageCount {
+        /// Only count messages of this type
+        #[serde(default, rename = "type")]
+        message_type: Option<String>,
+    },
+    /// Counts the messages sent (or received) by the agents for every message type
+    MessageTypeCounts {
+        #[serde(default)]
+        received: bool,
+    },
     Count,
     Sum,
     Min,
     Max,
     Mean,
+    /// Population standard deviation
+    StdDev,
+    /// Population variance
+    Variance,
+    Median,
+    /// The `p`-th percentile (`0 <= p <= 100`), linearly interpolated between the closest ranks
+    Percentile {
+        p: f64,
+    },
+    /// Counts the values in `bins` equally sized bins between `min` and `max`, which default to
+    /// the smallest and largest value
+    Histogram {
+        bins: usize,
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// Counts the distinct non-null values
+    CountDistinct,
 }
 
 impl AnalysisOperationRepr {
@@ -224,13 +308,52 @@ impl AnalysisOperationRepr {
         matches!(self, Self::Get { .. })
     }
 
+    /// Returns `true` if the operation maps agents to a value of their context.
+    pub fn is_context_map(&self) -> bool {
+        matches!(
+            self,
+            Self::NeighborCount | Self::SentMessageCount { .. } | Self::ReceivedMessageCount { .. }
+        )
+    }
+
+    /// Returns `true` if the operation filters agents by their context.
+    pub fn is_context_filter(&self) -> bool {
+        matches!(self, Self::Isolated)
+    }
+
+    /// Returns `true` if the operation requires agents as its input, so it can't follow a 'get'.
+    pub fn requires_agents(&self) -> bool {
+        self.is_group_by()
+            || self.is_context_map()
+            || self.is_context_filter()
+            || matches!(self, Self::MessageTypeCounts { .. })
+    }
+
+    /// Returns `true` if the operation may follow an operation, which selects agents.
+    pub fn is_agent_operation(&self) -> bool {
+        self.is_filter() || self.is_map() || self.is_count() || self.requires_agents()
+    }
+
+    pub fn is_group_by(&self) -> bool {
+        matches!(self, Self::GroupBy { .. })
+    }
+
     pub fn is_count(&self) -> bool {
         matches!(self, Self::Count)
     }
 
     pub fn is_num_aggregator(&self) -> bool {
         match self {
-            Self::Sum | Self::Min | Self::Max | Self::Mean => true,
+            Self::Sum
+            | Self::Min
+            | Self::Max
+            | Self::Mean
+            | Self::StdDev
+            | Self::Variance
+            | Self::Median
+            | Self::Percentile { .. }
+            | Self::Histogram { .. }
+            | Self::CountDistinct => true,
             _ => self.is_count(),
         }
     }
@@ -0,0 +1,382 @@
+//! Operations on the context of agents, i.e. their neighbors and the messages they sent and
+//! received.
+
+use std::collections::BTreeMap;
+
+use arrow2::array::{FixedSizeListArray, ListArray, UInt32Array};
+use stateful::{context::ContextBatch, field::FieldSpecMapAccessor, message::MessageLoader};
+
+use crate::{
+    package::simulation::output::analysis::{
+        analyzer::{
+            Agents, AnalysisOperationRepr, IndexIterator, OutputCreator, OutputRunnerCreator,
+            ValueIterator, ValueIteratorCreator,
+        },
+        AnalysisSingleOutput,
+    },
+    Error, Result,
+};
+
+const NEIGHBORS_FIELD_NAME: &str = "neighbors";
+const MESSAGES_FIELD_NAME: &str = "messages";
+
+/// Reads a context column containing a list of indices for every agent, e.g. the locations of the
+/// neighbors or the references to the received messages.
+fn context_index_lists<'a>(
+    context: &'a ContextBatch,
+    field_name: &str,
+) -> Result<Vec<Vec<&'a [u32]>>> {
+    let record_batch = context.record_batch();
+    let column_index = record_batch
+        .schema()
+        .fields
+        .iter()
+        .position(|field| field.name == field_name)
+        .ok_or_else(|| {
+            Error::from(format!(
+                "The context does not contain '{field_name}', is the corresponding context \
+                 package enabled?"
+            ))
+        })?;
+
+    let lists = record_batch
+        .column(column_index)
+        .as_any()
+        .downcast_ref::<ListArray<i32>>()
+        .ok_or_else(|| Error::from(format!("Context column '{field_name}' must be a list")))?;
+    let entries = lists
+        .values()
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .ok_or_else(|| {
+            Error::from(format!(
+                "Context column '{field_name}' must contain fixed-size lists"
+            ))
+        })?;
+    let indices = entries
+        .values()
+        .as_any()
+        .downcast_ref::<UInt32Array>()
+        .ok_or_else(|| {
+            Error::from(format!(
+                "Context column '{field_name}' must contain unsigned integers"
+            ))
+        })?
+        .values()
+        .as_slice();
+
+    let size = entries.size();
+    Ok(lists
+        .offsets()
+        .windows(2)
+        .map(|window| {
+            (window[0] as usize..window[1] as usize)
+                .map(|entry| &indices[entry * size..(entry + 1) * size])
+                .collect()
+        })
+        .collect())
+}
+
+/// Returns the number of neighbors of every agent.
+fn neighbor_counts(agents: Agents<'_>) -> Result<Vec<usize>> {
+    Ok(context_index_lists(agents.context, NEIGHBORS_FIELD_NAME)?
+        .iter()
+        .map(Vec::len)
+        .collect())
+}
+
+/// Returns the types of the messages every agent sent in the current step, or, if `received` is
+/// set, the types of the messages every agent received in the current step.
+fn message_types<'a>(agents: Agents<'a>, received: bool) -> Result<Vec<Vec<&'a str>>> {
+    if received {
+        let loaders = agents
+            .received_messages
+            .iter()
+            .map(|batch| Ok(MessageLoader::from_batch(batch)?))
+            .collect::<Result<Vec<_>>>()?;
+        context_index_lists(agents.context, MESSAGES_FIELD_NAME)?
+            .into_iter()
+            .map(|references| {
+                references
+                    .into_iter()
+                    .map(|reference| {
+                        let loader = loaders.get(reference[0] as usize).ok_or_else(|| {
+                            Error::from("Received message references an invalid batch")
+                        })?;
+                        Ok(loader.get_type(reference[1] as usize, reference[2] as usize))
+                    })
+                    .collect()
+            })
+            .collect()
+    } else {
+        let mut types = Vec::new();
+        for batch in agents.sent_messages {
+            let loader = MessageLoader::from_batch(batch)?;
+            types.extend((0..loader.num_agents()).map(|agent_index| {
+                (0..loader.num_messages(agent_index))
+                    .map(|message_index| loader.get_type(agent_index, message_index))
+                    .collect::<Vec<_>>()
+            }));
+        }
+        Ok(types)
+    }
+}
+
+/// Maps every agent to the number of its neighbors.
+pub(super) fn neighbor_count_getter() -> ValueIteratorCreator {
+    Box::new(|agents: Agents<'_>| {
+        let counts = neighbor_counts(agents)?;
+        Ok(Box::new(counts.into_iter().map(serde_json::Value::from)) as ValueIterator<'_>)
+    })
+}
+
+/// Maps every agent to the number of messages it sent or received in the current step, optionally
+/// only counting messages of `message_type`.
+pub(super) fn message_count_getter(
+    received: bool,
+    message_type: Option<String>,
+) -> ValueIteratorCreator {
+    Box::new(move |agents: Agents<'_>| {
+        let message_type = message_type.clone();
+        let counts = message_types(agents, received)?
+            .into_iter()
+            .map(move |types| match &message_type {
+                Some(message_type) => types
+                    .into_iter()
+                    .filter(|t| *t == message_type.as_str())
+                    .count(),
+                None => types.len(),
+            });
+        Ok(Box::new(counts.map(serde_json::Value::from)) as ValueIterator<'_>)
+    })
+}
+
+/// Keeps only agents without neighbors.
+pub(super) fn index_iterator_isolated_creator(
+    operations: &[AnalysisOperationRepr],
+    accessor: &FieldSpecMapAccessor,
+) -> Result<OutputRunnerCreator> {
+    let following = OutputCreator::index_creator(&operations[1..], accessor)?;
+    Ok(Box::new(move |agents: Agents<'_>| {
+        let counts = neighbor_counts(agents)?;
+        let next = following(agents)?;
+        Ok(Box::new(
+            move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
+                let isolated: IndexIterator<'_> =
+                    Box::new(iterator.filter(move |index| counts.get(*index) == Some(&0)));
+                next(isolated)
+            },
+        ))
+    }))
+}
+
+/// Counts the messages sent or received by the agents for every message type.
+pub(super) fn index_iterator_message_types_creator(received: bool) -> OutputRunnerCreator {
+    Box::new(move |agents: Agents<'_>| {
+        let types = message_types(agents, received)?;
+        Ok(Box::new(
+            move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
+                let mut counts = BTreeMap::<_, usize>::new();
+                for index in iterator {
+                    for message_type in types.get(index).into_iter().flatten() {
+                        *counts.entry(message_type.to_string()).or_default() += 1;
+                    }
+                }
+                Ok(AnalysisSingleOutput::Grouped(
+                    counts
+                        .into_iter()
+                        .map(|(message_type, count)| {
+                            (
+                                message_type,
+                                AnalysisSingleOutput::some_number(count as f64),
+                            )
+                        })
+                        .collect(),
+                ))
+            },
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow2::{
+        array::Array,
+        chunk::Chunk,
+        datatypes::{DataType, Field, Schema},
+    };
+    use memory::shared_memory::MemoryId;
+    use serde_json::json;
+    use stateful::{
+        agent::Agent,
+        context::{Context, ContextSchema},
+        field::{FieldSource, FieldSpecMap},
+        message::{MessageBatch, MessageSchema},
+    };
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::package::simulation::output::analysis::analyzer::OutputCreator;
+
+    /// Creates a context column containing a list of indices of length `size` for every agent.
+    fn index_lists(lists: &[&[&[u32]]], size: usize) -> Box<dyn Array> {
+        let entry_type =
+            DataType::FixedSizeList(Box::new(Field::new("item", DataType::UInt32, false)), size);
+        let indices = lists.iter().flat_map(|list| list.concat()).collect();
+        let entries = FixedSizeListArray::new(
+            entry_type.clone(),
+            Box::new(UInt32Array::from_vec(indices)),
+            None,
+        );
+        let offsets = std::iter::once(0)
+            .chain(lists.iter().scan(0, |offset, list| {
+                *offset += list.len() as i32;
+                Some(*offset)
+            }))
+            .collect::<Vec<_>>();
+        Box::new(ListArray::<i32>::new(
+            DataType::List(Box::new(Field::new("item", entry_type, false))),
+            offsets.into(),
+            Box::new(entries),
+            None,
+        ))
+    }
+
+    /// Three agents, where `a` and `b` are neighbors and `c` is isolated.
+    ///
+    /// `a` sends two `ping`s to `b` and `b` sends a `pong` to `a`. The same messages are received
+    /// in the step analyzed.
+    struct Fixture {
+        context: Context,
+        messages: MessageBatch,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let columns = Chunk::new(vec![
+                index_lists(&[&[&[0, 1]], &[&[0, 0]], &[]], 2),
+                index_lists(&[&[&[0, 1, 0]], &[&[0, 0, 0], &[0, 0, 1]], &[]], 3),
+            ]);
+            let schema = ContextSchema {
+                arrow: Arc::new(Schema::from(vec![
+                    Field::new(NEIGHBORS_FIELD_NAME, columns[0].data_type().clone(), false),
+                    Field::new(MESSAGES_FIELD_NAME, columns[1].data_type().clone(), false),
+                ])),
+                field_spec_map: Arc::new(FieldSpecMap::empty()),
+            };
+            let context =
+                Context::from_columns(columns, &schema, MemoryId::new(Uuid::new_v4())).unwrap();
+
+            let agents: Vec<Agent> = serde_json::from_value(json!([
+                {
+                    "agent_name": "a",
+                    "messages": [
+                        { "to": "b", "type": "ping" },
+                        { "to": "b", "type": "ping" },
+                    ],
+                },
+                { "agent_name": "b", "messages": [{ "to": "a", "type": "pong" }] },
+                { "agent_name": "c", "messages": [] },
+            ]))
+            .unwrap();
+            let messages = MessageBatch::from_agent_states(
+                agents.as_slice(),
+                &MessageSchema::new(),
+                MemoryId::new(Uuid::new_v4()),
+            )
+            .unwrap();
+
+            Self { context, messages }
+        }
+
+        fn run(&self, operations: serde_json::Value) -> AnalysisSingleOutput {
+            let operations: Vec<AnalysisOperationRepr> =
+                serde_json::from_value(operations).unwrap();
+            let accessor =
+                FieldSpecMapAccessor::new(FieldSource::Engine, Arc::new(FieldSpecMap::empty()));
+            let creator = OutputCreator::index_creator(&operations, &accessor).unwrap();
+
+            let messages = [&self.messages];
+            let agents = Agents {
+                batches: &[],
+                sent_messages: &messages,
+                received_messages: &messages,
+                context: self.context.global_batch(),
+            };
+            let runner = creator(agents).unwrap();
+            runner(Box::new(0..3)).unwrap()
+        }
+    }
+
+    fn numbers(numbers: &[f64]) -> AnalysisSingleOutput {
+        AnalysisSingleOutput::Vec(Some(numbers.iter().copied().map(Some).collect()))
+    }
+
+    #[test]
+    fn neighbors() {
+        let fixture = Fixture::new();
+
+        assert_eq!(
+            fixture.run(json!([{ "op": "neighbor_count" }])),
+            numbers(&[1.0, 1.0, 0.0])
+        );
+        assert_eq!(
+            fixture.run(json!([{ "op": "isolated" }, { "op": "count" }])),
+            AnalysisSingleOutput::some_number(1.0)
+        );
+    }
+
+    #[test]
+    fn message_counts() {
+        let fixture = Fixture::new();
+
+        assert_eq!(
+            fixture.run(json!([{ "op": "sent_message_count" }])),
+            numbers(&[2.0, 1.0, 0.0])
+        );
+        assert_eq!(
+            fixture.run(json!([{ "op": "sent_message_count", "type": "pong" }])),
+            numbers(&[0.0, 1.0, 0.0])
+        );
+        assert_eq!(
+            fixture.run(json!([{ "op": "received_message_count" }])),
+            numbers(&[1.0, 2.0, 0.0])
+        );
+        assert_eq!(
+            fixture.run(json!([
+                { "op": "received_message_count", "type": "ping" },
+                { "op": "sum" },
+            ])),
+            AnalysisSingleOutput::some_number(2.0)
+        );
+    }
+
+    #[test]
+    fn message_type_counts() {
+        let fixture = Fixture::new();
+        let expected = AnalysisSingleOutput::Grouped(
+            [
+                ("ping".to_string(), AnalysisSingleOutput::some_number(2.0)),
+                ("pong".to_string(), AnalysisSingleOutput::some_number(1.0)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        assert_eq!(
+            fixture.run(json!([{ "op": "message_type_counts" }])),
+            expected
+        );
+        assert_eq!(
+            fixture.run(json!([{ "op": "message_type_counts", "received": true }])),
+            expected
+        );
+        // Only `c` is isolated and it neither sent nor received messages
+        assert_eq!(
+            fixture.run(json!([{ "op": "isolated" }, { "op": "message_type_counts" }])),
+            AnalysisSingleOutput::Grouped(Default::default())
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::BTreeMap, sync::Arc};
 
 use arrow2::datatypes::DataType;
 use float_cmp::approx_eq;
@@ -10,9 +10,11 @@ use stateful::{
 use crate::{
     package::simulation::output::analysis::{
         analyzer::{
-            AnalysisOperationRepr, ComparisonRepr, IndexIterator, OutputCreator, OutputRunner,
-            OutputRunnerCreator, ValueIterator, ValueIteratorCreator, ULPS,
+            Agents, AnalysisOperationRepr, ComparisonRepr, IndexIterator, OutputCreator,
+            OutputRunner, OutputRunnerCreator, ValueIterator, ValueIteratorCreator, ULPS,
         },
+        context_iter,
+        statistics::{self, StatisticalAggregator},
         value_iter::{value_iterator_filter, value_iterator_mapper},
         AnalysisSingleOutput,
     },
@@ -594,10 +596,25 @@ fn f64_iter_aggregate(
                 ))
             )
         }
-        _ => Err(Error::from(
-            "The last operation must be an aggregator: either 'count', 'sum', 'min', 'max' or \
-             'mean'",
-        )),
+        AnalysisOperationRepr::CountDistinct => {
+            apply_aggregator_f64!(
+                first_field,
+                iterator,
+                Ok(AnalysisSingleOutput::some_number(
+                    statistics::count_distinct_numbers(iterator) as f64
+                ))
+            )
+        }
+        operation => match StatisticalAggregator::from_operation(operation) {
+            Some(statistical) => {
+                apply_aggregator_f64!(first_field, iterator, Ok(statistical.aggregate(iterator)))
+            }
+            None => Err(Error::from(
+                "The last operation must be an aggregator: either 'count', 'count_distinct', \
+                 'sum', 'min', 'max', 'mean', 'std_dev', 'variance', 'median', 'percentile' or \
+                 'histogram'",
+            )),
+        },
     }?;
     Ok(result)
 }
@@ -685,22 +702,95 @@ pub(super) fn index_iterator_filter_creator(
     }
 }
 
+pub(super) fn index_iterator_group_by_creator(
+    operations: &[AnalysisOperationRepr],
+    accessor: &FieldSpecMapAccessor,
+    field: String,
+) -> Result<OutputRunnerCreator> {
+    let field_type = &accessor
+        .get_agent_scoped_field_spec(&field)?
+        .inner
+        .field_type;
+
+    let key_getter = match &field_type.variant {
+        FieldTypeVariant::AnyType => {
+            let a: ValueIteratorCreator = Box::new(move |agents: Agents<'_>| {
+                let iterator = agent::arrow::json_serialized_value_iter(agents.batches, &field)?;
+                Ok(Box::new(iterator) as ValueIterator<'_>)
+            });
+            a
+        }
+        _ => default_first_getter(accessor, &field)?,
+    };
+    // The following operations are run once per group, so the creator is shared between the
+    // runners of every step
+    let following = Arc::new(OutputCreator::index_creator(&operations[1..], accessor)?);
+
+    Ok(Box::new(move |agents: Agents<'_>| {
+        let key_iter = key_getter(agents)?;
+        let following = Arc::clone(&following);
+        let runner: OutputRunner<'_> = Box::new(
+            move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
+                let mut key_iter = key_iter;
+                let mut current_index = 0;
+
+                let mut groups = BTreeMap::<_, Vec<usize>>::new();
+                for index in iterator {
+                    for _ in current_index..index {
+                        // Skip some values
+                        key_iter.next();
+                    }
+                    current_index = index + 1;
+                    let key = match key_iter.next().unwrap_or(serde_json::Value::Null) {
+                        serde_json::Value::String(string) => string,
+                        value => value.to_string(),
+                    };
+                    groups.entry(key).or_default().push(index);
+                }
+
+                let outputs = groups
+                    .into_iter()
+                    .map(|(key, indices)| {
+                        let runner = following(agents)?;
+                        Ok((key, runner(Box::new(indices.into_iter()))?))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(AnalysisSingleOutput::Grouped(outputs))
+            },
+        );
+        Ok(runner)
+    }))
+}
+
 fn default_first_getter(
     accessor: &FieldSpecMapAccessor,
     first_field: &str,
 ) -> Result<ValueIteratorCreator> {
-    let data_type = DataType::from(
-        accessor
-            .get_agent_scoped_field_spec(first_field)?
-            .inner
-            .field_type
-            .variant
-            .clone(),
-    );
+    let variant = accessor
+        .get_agent_scoped_field_spec(first_field)?
+        .inner
+        .field_type
+        .variant
+        .clone();
 
     let first_field = first_field.to_string();
-    let a: ValueIteratorCreator = Box::new(move |agents: &_| {
-        let iterator = agent::arrow::json_value_iter_cols(agents, &first_field, &data_type)?;
+    if let FieldTypeVariant::Categorical(categories) = variant {
+        // Categorical columns only store the index of the category
+        let a: ValueIteratorCreator = Box::new(move |agents: Agents<'_>| {
+            let iterator = agent::arrow::json_categorical_value_iter_cols(
+                agents.batches,
+                &first_field,
+                &categories,
+            )?;
+            Ok(iterator as ValueIterator<'_>)
+        });
+        return Ok(a);
+    }
+
+    let data_type = DataType::from(variant);
+    let a: ValueIteratorCreator = Box::new(move |agents: Agents<'_>| {
+        let iterator =
+            agent::arrow::json_value_iter_cols(agents.batches, &first_field, &data_type)?;
         Ok(iterator as ValueIterator<'_>)
     });
     Ok(a)
@@ -746,8 +836,9 @@ pub(super) fn index_iterator_mapper_creator(
             }
         }
         FieldTypeVariant::AnyType => {
-            let a: ValueIteratorCreator = Box::new(move |agents| {
-                let iterator = agent::arrow::json_serialized_value_iter(agents, &first_field)?;
+            let a: ValueIteratorCreator = Box::new(move |agents: Agents<'_>| {
+                let iterator =
+                    agent::arrow::json_serialized_value_iter(agents.batches, &first_field)?;
                 Ok(Box::new(iterator) as ValueIterator<'_>)
             });
             a
@@ -755,6 +846,37 @@ pub(super) fn index_iterator_mapper_creator(
         _ => default_first_getter(accessor, &first_field)?,
     };
 
+    value_iterator_runner_creator(operations, first_mapper)
+}
+
+/// Maps every agent to a value derived from its context, e.g. its number of neighbors, which is
+/// processed by the following operations like the value of a 'get' operation.
+pub(super) fn index_iterator_context_mapper_creator(
+    operations: &[AnalysisOperationRepr],
+) -> Result<OutputRunnerCreator> {
+    let first_mapper = match &operations[0] {
+        AnalysisOperationRepr::NeighborCount => context_iter::neighbor_count_getter(),
+        AnalysisOperationRepr::SentMessageCount { message_type } => {
+            context_iter::message_count_getter(false, message_type.clone())
+        }
+        AnalysisOperationRepr::ReceivedMessageCount { message_type } => {
+            context_iter::message_count_getter(true, message_type.clone())
+        }
+        _ => {
+            return Err(Error::from(
+                "Expected an operation on the context of agents",
+            ))
+        }
+    };
+
+    value_iterator_runner_creator(operations, first_mapper)
+}
+
+/// Applies the operations following the first getter to the values returned by `first_mapper`.
+fn value_iterator_runner_creator(
+    operations: &[AnalysisOperationRepr],
+    first_mapper: ValueIteratorCreator,
+) -> Result<OutputRunnerCreator> {
     let is_aggregated = operations.last().unwrap().is_num_aggregator();
     // Combine all subsequent getters and filters
     let combined_mapper = if operations.len() == 2 {
@@ -775,7 +897,7 @@ pub(super) fn index_iterator_mapper_creator(
                     value,
                 } => {
                     let mapper = value_iterator_filter(field.clone(), comparison, value)?;
-                    let builder: ValueIteratorCreator = Box::new(move |agents| {
+                    let builder: ValueIteratorCreator = Box::new(move |agents: Agents<'_>| {
                         let iterator = result(agents)?;
                         mapper(iterator)
                     });
@@ -783,7 +905,7 @@ pub(super) fn index_iterator_mapper_creator(
                 }
                 AnalysisOperationRepr::Get { field } => {
                     let mapper = value_iterator_mapper(field.clone())?;
-                    let builder: ValueIteratorCreator = Box::new(move |agents| {
+                    let builder: ValueIteratorCreator = Box::new(move |agents: Agents<'_>| {
                         let iterator = result(agents)?;
                         mapper(iterator)
                     });
@@ -881,10 +1003,26 @@ pub(super) fn index_iterator_mapper_creator(
                     ))
                 )
             }
-            _ => Err(Error::from("Expected an aggregator as the last operation")),
+            AnalysisOperationRepr::CountDistinct => {
+                apply_aggregator!(
+                    combined_mapper,
+                    iterator,
+                    Ok(AnalysisSingleOutput::some_number(
+                        statistics::count_distinct_values(iterator) as f64
+                    ))
+                )
+            }
+            operation => match StatisticalAggregator::from_operation(operation) {
+                Some(statistical) => apply_aggregator!(
+                    combined_mapper,
+                    iterator,
+                    Ok(statistical.aggregate(iterator.map(|a| a.as_f64())))
+                ),
+                None => Err(Error::from("Expected an aggregator as the last operation")),
+            },
         }?
     } else {
-        let runner: OutputRunnerCreator = Box::new(move |agents: &_| {
+        let runner: OutputRunnerCreator = Box::new(move |agents: Agents<'_>| {
             let value_runner: ValueIterator<'_> = combined_mapper(agents)?;
             Ok(Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -946,3 +1084,200 @@ pub(super) fn index_iterator_mapper_creator(
 
     Ok(runner)
 }
+
+#[cfg(test)]
+mod tests {
+    use arrow2::{chunk::Chunk, datatypes::Schema};
+    use memory::shared_memory::MemoryId;
+    use serde_json::json;
+    use stateful::{
+        agent::{Agent, AgentBatch, AgentSchema},
+        context::{Context, ContextSchema},
+        field::{
+            FieldScope, FieldSource, FieldSpecMap, FieldType, RootFieldSpec, RootFieldSpecCreator,
+        },
+    };
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Four agents with a string, a number, and a boolean field to group by.
+    struct Fixture {
+        accessor: FieldSpecMapAccessor,
+        agents: AgentBatch,
+        context: Context,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let creator = RootFieldSpecCreator::new(FieldSource::Engine);
+            let mut field_spec_map = FieldSpecMap::empty();
+            field_spec_map
+                .try_extend(RootFieldSpec::base_agent_fields().unwrap())
+                .unwrap();
+            field_spec_map
+                .try_extend(
+                    [
+                        ("team", FieldTypeVariant::String),
+                        ("size", FieldTypeVariant::Number),
+                        ("active", FieldTypeVariant::Boolean),
+                    ]
+                    .map(|(name, variant)| {
+                        creator.create(
+                            name.to_string(),
+                            FieldType::new(variant, false),
+                            FieldScope::Agent,
+                        )
+                    }),
+                )
+                .unwrap();
+            let schema = AgentSchema::new(field_spec_map).unwrap();
+
+            let agents: Vec<Agent> = serde_json::from_value(json!([
+                { "agent_name": "a", "team": "red", "size": 1.5, "active": true },
+                { "agent_name": "b", "team": "red", "size": 2.5, "active": false },
+                { "agent_name": "c", "team": "blue", "size": 2.5, "active": true },
+                { "agent_name": "d", "team": "blue", "size": 4.5, "active": true },
+            ]))
+            .unwrap();
+            let agents = AgentBatch::from_agent_states(
+                agents.as_slice(),
+                &schema,
+                MemoryId::new(Uuid::new_v4()),
+            )
+            .unwrap();
+
+            // Grouping doesn't access the context
+            let context_schema = ContextSchema {
+                arrow: Arc::new(Schema::from(vec![])),
+                field_spec_map: Arc::new(FieldSpecMap::empty()),
+            };
+            let context = Context::from_columns(
+                Chunk::new(vec![]),
+                &context_schema,
+                MemoryId::new(Uuid::new_v4()),
+            )
+            .unwrap();
+
+            Self {
+                accessor: FieldSpecMapAccessor::new(
+                    FieldSource::Engine,
+                    Arc::clone(&schema.field_spec_map),
+                ),
+                agents,
+                context,
+            }
+        }
+
+        fn run(&self, operations: serde_json::Value) -> AnalysisSingleOutput {
+            let operations: Vec<AnalysisOperationRepr> =
+                serde_json::from_value(operations).unwrap();
+            let creator = OutputCreator::index_creator(&operations, &self.accessor).unwrap();
+
+            let batches = [&self.agents];
+            let agents = Agents {
+                batches: &batches,
+                sent_messages: &[],
+                received_messages: &[],
+                context: self.context.global_batch(),
+            };
+            let runner = creator(agents).unwrap();
+            runner(Box::new(0..4)).unwrap()
+        }
+    }
+
+    fn grouped<const N: usize>(groups: [(&str, AnalysisSingleOutput); N]) -> AnalysisSingleOutput {
+        AnalysisSingleOutput::Grouped(
+            groups
+                .into_iter()
+                .map(|(key, output)| (key.to_string(), output))
+                .collect(),
+        )
+    }
+
+    fn number(number: f64) -> AnalysisSingleOutput {
+        AnalysisSingleOutput::some_number(number)
+    }
+
+    #[test]
+    fn group_by_string() {
+        let fixture = Fixture::new();
+
+        assert_eq!(
+            fixture.run(json!([{ "op": "group_by", "field": "team" }, { "op": "count" }])),
+            grouped([("blue", number(2.0)), ("red", number(2.0))])
+        );
+        // The following operations are applied to every group
+        assert_eq!(
+            fixture.run(json!([
+                { "op": "group_by", "field": "team" },
+                { "op": "get", "field": "size" },
+                { "op": "sum" },
+            ])),
+            grouped([("blue", number(7.0)), ("red", number(4.0))])
+        );
+    }
+
+    #[test]
+    fn group_by_number_and_boolean() {
+        let fixture = Fixture::new();
+
+        assert_eq!(
+            fixture.run(json!([{ "op": "group_by", "field": "size" }, { "op": "count" }])),
+            grouped([
+                ("1.5", number(1.0)),
+                ("2.5", number(2.0)),
+                ("4.5", number(1.0)),
+            ])
+        );
+        assert_eq!(
+            fixture.run(json!([{ "op": "group_by", "field": "active" }, { "op": "count" }])),
+            grouped([("false", number(1.0)), ("true", number(3.0))])
+        );
+    }
+
+    #[test]
+    fn group_by_after_filter() {
+        let fixture = Fixture::new();
+
+        // Only the agents kept by the filter are grouped, so `a` is not counted
+        assert_eq!(
+            fixture.run(json!([
+                { "op": "filter", "field": "size", "comparison": "gt", "value": 2 },
+                { "op": "group_by", "field": "team" },
+                { "op": "count" },
+            ])),
+            grouped([("blue", number(2.0)), ("red", number(1.0))])
+        );
+        // Groups without any agent left are omitted
+        assert_eq!(
+            fixture.run(json!([
+                { "op": "filter", "field": "team", "comparison": "eq", "value": "blue" },
+                { "op": "group_by", "field": "team" },
+                { "op": "count" },
+            ])),
+            grouped([("blue", number(2.0))])
+        );
+    }
+
+    #[test]
+    fn nested_group_by() {
+        let fixture = Fixture::new();
+
+        assert_eq!(
+            fixture.run(json!([
+                { "op": "group_by", "field": "team" },
+                { "op": "group_by", "field": "active" },
+                { "op": "get", "field": "size" },
+                { "op": "max" },
+            ])),
+            grouped([
+                ("blue", grouped([("true", number(4.5))])),
+                (
+                    "red",
+                    grouped([("false", number(2.5)), ("true", number(1.5))])
+                ),
+            ])
+        );
+    }
+}
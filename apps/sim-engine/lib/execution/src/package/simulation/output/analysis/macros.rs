@@ -139,8 +139,8 @@ macro_rules! apply_index_filter_f64 {
         let following: OutputRunnerCreator =
             OutputCreator::index_creator(&$operations[1..], $accessor)?;
         let field = $field.clone();
-        Ok(Box::new(move |agents| {
-            let f64_iterator = agent::arrow::f64_iter(agents, &field)?;
+        Ok(Box::new(move |agents: Agents<'_>| {
+            let f64_iterator = agent::arrow::f64_iter(agents.batches, &field)?;
             let next = following(agents)?;
             Ok(Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -179,8 +179,8 @@ macro_rules! apply_index_filter_str {
         let following =
             OutputCreator::index_creator(&$operations[1..], $accessor)? as OutputRunnerCreator;
         let field = $field.clone();
-        Ok(Box::new(move |agents| {
-            let str_iterator = agent::arrow::str_iter(&agents, &field)?;
+        Ok(Box::new(move |agents: Agents<'_>| {
+            let str_iterator = agent::arrow::str_iter(agents.batches, &field)?;
             let next = following(agents)?;
             let $cloned = $string.clone();
             Ok(Box::new(
@@ -219,8 +219,8 @@ macro_rules! apply_index_filter_serialized_json_str {
         let following =
             OutputCreator::index_creator(&$operations[1..], $accessor)? as OutputRunnerCreator;
         let field = $field.clone();
-        Ok(Box::new(move |agents| {
-            let str_iterator = agent::arrow::str_iter(agents, &field)?;
+        Ok(Box::new(move |agents: Agents<'_>| {
+            let str_iterator = agent::arrow::str_iter(agents.batches, &field)?;
             let next = following(agents)?;
             let $cloned = $string.clone();
             Ok(Box::new(
@@ -250,8 +250,8 @@ macro_rules! apply_index_filter_serialized_json {
     ($operations:ident, $accessor:expr, $field:expr, $comparison:expr, $default:expr) => {{
         let following = OutputCreator::index_creator(&$operations[1..], $accessor)?;
         let field = $field.clone();
-        Ok(Box::new(move |agents| {
-            let str_iterator = agent::arrow::str_iter(agents, &field)?;
+        Ok(Box::new(move |agents: Agents<'_>| {
+            let str_iterator = agent::arrow::str_iter(agents.batches, &field)?;
             let next = following(agents)?;
             Ok(Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -281,8 +281,8 @@ macro_rules! apply_index_filter_null {
         let following =
             OutputCreator::index_creator(&$operations[1..], $accessor)? as OutputRunnerCreator;
         let field = $field.clone();
-        Ok(Box::new(move |agents| {
-            let exists_iter = agent::arrow::exists_iter(agents, &field)?;
+        Ok(Box::new(move |agents: Agents<'_>| {
+            let exists_iter = agent::arrow::exists_iter(agents.batches, &field)?;
             let next = following(agents)?;
             Ok(Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -310,8 +310,8 @@ macro_rules! apply_index_filter_bool {
         let following =
             OutputCreator::index_creator(&$operations[1..], $accessor)? as OutputRunnerCreator;
         let field = $field.clone();
-        Ok(Box::new(move |agents| {
-            let bool_iterator = agent::arrow::bool_iter(agents, &field)?;
+        Ok(Box::new(move |agents: Agents<'_>| {
+            let bool_iterator = agent::arrow::bool_iter(agents.batches, &field)?;
             let next = following(agents)?;
             Ok(Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -339,7 +339,7 @@ macro_rules! apply_index_filter_bool {
 
 macro_rules! apply_aggregator {
     ($getter:ident, $iter:ident, $aggr:expr) => {{
-        let runner: OutputRunnerCreator = Box::new(move |agents: &_| {
+        let runner: OutputRunnerCreator = Box::new(move |agents: Agents<'_>| {
             let value_runner: ValueIterator<'_> = $getter(agents)?;
             let runner: OutputRunner<'_> = Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
@@ -364,8 +364,8 @@ macro_rules! apply_aggregator {
 
 macro_rules! apply_aggregator_f64 {
     ($field_name:ident, $iter:ident, $aggr:expr) => {{
-        let runner: OutputRunnerCreator = Box::new(move |agents: &_| {
-            let f64_iter = agent::arrow::f64_iter(agents, &$field_name)?;
+        let runner: OutputRunnerCreator = Box::new(move |agents: Agents<'_>| {
+            let f64_iter = agent::arrow::f64_iter(agents.batches, &$field_name)?;
             let runner: OutputRunner<'_> = Box::new(
                 move |iterator: Box<dyn Iterator<Item = usize> + Send + Sync>| {
                     let mut iter = f64_iter;
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -8,15 +11,26 @@ pub struct AnalysisFinalOutput {
 }
 
 // Output for a single step
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AnalysisOutput {
     pub inner: HashMap<Arc<String>, AnalysisSingleOutput>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum AnalysisSingleOutput {
     Number(Option<f64>),
     Vec(Option<Vec<Option<f64>>>),
+    Histogram(Option<Histogram>),
+    /// The outputs of the operations following a `group_by`, keyed by the value of the grouped
+    /// field
+    Grouped(BTreeMap<String, AnalysisSingleOutput>),
+}
+
+/// The counts of values in equally sized bins, bin `i` spans from `edges[i]` to `edges[i + 1]`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<u64>,
 }
 
 impl AnalysisSingleOutput {
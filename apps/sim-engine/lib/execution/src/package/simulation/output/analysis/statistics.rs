@@ -0,0 +1,195 @@
+//! Aggregators which need more than a single running value, e.g. to sort the values or to keep
+//! track of the values already seen.
+
+use std::collections::HashSet;
+
+use crate::package::simulation::output::analysis::{
+    analyzer::AnalysisOperationRepr, AnalysisSingleOutput, Histogram,
+};
+
+/// The statistical aggregators of [`AnalysisOperationRepr`].
+///
+/// In contrast to [`AnalysisOperationRepr`] this is `Copy`, so it can be moved into the runners
+/// created for every step.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum StatisticalAggregator {
+    StdDev,
+    Variance,
+    Percentile(f64),
+    Histogram {
+        bins: usize,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+}
+
+impl StatisticalAggregator {
+    pub(super) fn from_operation(operation: &AnalysisOperationRepr) -> Option<Self> {
+        match operation {
+            AnalysisOperationRepr::StdDev => Some(Self::StdDev),
+            AnalysisOperationRepr::Variance => Some(Self::Variance),
+            AnalysisOperationRepr::Median => Some(Self::Percentile(50.0)),
+            AnalysisOperationRepr::Percentile { p } => Some(Self::Percentile(*p)),
+            AnalysisOperationRepr::Histogram { bins, min, max } => Some(Self::Histogram {
+                bins: *bins,
+                min: *min,
+                max: *max,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Aggregates the numbers yielded by `iterator`, ignoring nulls, NaNs, Infs and -Infs.
+    pub(super) fn aggregate(
+        self,
+        iterator: impl Iterator<Item = Option<f64>>,
+    ) -> AnalysisSingleOutput {
+        let numbers = iterator.flatten().filter(|number| number.is_finite());
+        match self {
+            Self::StdDev => AnalysisSingleOutput::Number(variance(numbers).map(f64::sqrt)),
+            Self::Variance => AnalysisSingleOutput::Number(variance(numbers)),
+            Self::Percentile(p) => AnalysisSingleOutput::Number(percentile(numbers.collect(), p)),
+            Self::Histogram { bins, min, max } => {
+                AnalysisSingleOutput::Histogram(histogram(numbers.collect(), bins, min, max))
+            }
+        }
+    }
+}
+
+/// Population variance using Welford's algorithm, `None` if there are no numbers.
+fn variance(numbers: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut num_elements = 0;
+    let mut mean = 0.0;
+    let mut squared_distances = 0.0;
+    for number in numbers {
+        num_elements += 1;
+        let delta = number - mean;
+        mean += delta / num_elements as f64;
+        squared_distances += delta * (number - mean);
+    }
+
+    if num_elements != 0 {
+        Some(squared_distances / num_elements as f64)
+    } else {
+        None
+    }
+}
+
+/// The `p`-th percentile (`0 <= p <= 100`), linearly interpolated between the closest ranks.
+fn percentile(mut numbers: Vec<f64>, p: f64) -> Option<f64> {
+    if numbers.is_empty() {
+        return None;
+    }
+    // All numbers are finite, so they are totally ordered
+    numbers.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (numbers.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(numbers[lower] + (numbers[upper] - numbers[lower]) * (rank - lower as f64))
+}
+
+/// Counts the numbers in `bins` equally sized bins between `min` and `max`.
+///
+/// If `min` or `max` is not specified, the smallest or largest number is used. Numbers outside of
+/// the range are ignored, the last bin includes `max`.
+fn histogram(
+    numbers: Vec<f64>,
+    bins: usize,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Option<Histogram> {
+    let min = min.or_else(|| numbers.iter().copied().reduce(f64::min))?;
+    let max = max.or_else(|| numbers.iter().copied().reduce(f64::max))?;
+    let bins = bins.max(1);
+
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for number in numbers {
+        if number < min || number > max {
+            continue;
+        }
+        let bin = if width > 0.0 {
+            (((number - min) / width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[bin] += 1;
+    }
+
+    Some(Histogram {
+        edges: (0..=bins).map(|i| min + width * i as f64).collect(),
+        counts,
+    })
+}
+
+/// Counts the distinct non-null numbers, where `0.0` and `-0.0` are considered equal.
+pub(super) fn count_distinct_numbers(iterator: impl Iterator<Item = Option<f64>>) -> usize {
+    iterator
+        .flatten()
+        .filter(|number| !number.is_nan())
+        .map(|number| {
+            if number == 0.0 {
+                0_u64
+            } else {
+                number.to_bits()
+            }
+        })
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Counts the distinct non-null values.
+pub(super) fn count_distinct_values(iterator: impl Iterator<Item = serde_json::Value>) -> usize {
+    iterator
+        .filter(|value| !value.is_null())
+        .map(|value| value.to_string())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_ignore_nulls_and_non_finite_numbers() {
+        let numbers = || {
+            [
+                Some(4.0),
+                None,
+                Some(1.0),
+                Some(f64::NAN),
+                Some(3.0),
+                Some(2.0),
+            ]
+            .into_iter()
+        };
+
+        assert_eq!(
+            StatisticalAggregator::Variance.aggregate(numbers()),
+            AnalysisSingleOutput::some_number(1.25)
+        );
+        assert_eq!(
+            StatisticalAggregator::Percentile(50.0).aggregate(numbers()),
+            AnalysisSingleOutput::some_number(2.5)
+        );
+        assert_eq!(
+            StatisticalAggregator::Histogram {
+                bins: 3,
+                min: None,
+                max: None
+            }
+            .aggregate(numbers()),
+            AnalysisSingleOutput::Histogram(Some(Histogram {
+                edges: vec![1.0, 2.0, 3.0, 4.0],
+                counts: vec![1, 1, 2],
+            }))
+        );
+        assert_eq!(
+            StatisticalAggregator::StdDev.aggregate(std::iter::once(None)),
+            AnalysisSingleOutput::null_number()
+        );
+        assert_eq!(count_distinct_numbers(numbers()), 4);
+    }
+}
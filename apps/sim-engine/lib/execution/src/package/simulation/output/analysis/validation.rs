@@ -22,15 +22,37 @@ impl AnalysisSourceRepr {
                 }
 
                 let mut prev_operation = &operations[0];
+                let mut is_mapped = prev_operation.is_map() || prev_operation.is_context_map();
                 for operation in operations.iter().skip(1) {
                     if let Some(err) =
                         operation.is_not_valid_subsequent_operation(prev_operation)?
                     {
                         error.add(err);
                     }
+                    if operation.requires_agents() && is_mapped {
+                        error.add(format!(
+                            "A '{}' operation must not follow a 'get' operation, as it operates \
+                             on agents",
+                            serde_json::to_string(operation)?
+                        ));
+                    }
+                    is_mapped |= operation.is_map() || operation.is_context_map();
                     prev_operation = operation;
                 }
 
+                if prev_operation.is_group_by() || prev_operation.is_context_filter() {
+                    error.add(format!(
+                        "A '{}' operation must not be the last operation",
+                        serde_json::to_string(prev_operation)?
+                    ));
+                }
+
+                for operation in operations {
+                    if let Some(why) = operation.has_invalid_arguments() {
+                        error.add(why);
+                    }
+                }
+
                 Ok((name.clone(), error.finish()))
             })
             .collect::<Result<_>>()?;
@@ -59,8 +81,12 @@ impl AnalysisSourceRepr {
 impl AnalysisOperationRepr {
     pub fn is_not_valid_first_operation(&self) -> Result<Option<String>> {
         let mut error = ErrorBuilder::new();
-        if !(self.is_filter() || self.is_map() || self.is_count()) {
-            error.add("The first operation must either be 'filter', 'sum' or 'count'".into());
+        if !self.is_agent_operation() {
+            error.add(
+                "The first operation must operate on agents, e.g. 'filter', 'get', 'group_by', \
+                 'count' or 'neighbor_count'"
+                    .into(),
+            );
         }
 
         if let AnalysisOperationRepr::Filter {
@@ -105,15 +131,55 @@ impl AnalysisOperationRepr {
                     );
                 }
 
-                if !(self.is_filter() || self.is_map() || self.is_count()) {
+                if !self.is_agent_operation() {
+                    error.add(
+                        "A 'filter' operation must be followed by an operation on agents, e.g. \
+                         'filter', 'get', 'group_by' or 'count'"
+                            .into(),
+                    );
+                }
+                error.finish()
+            }
+            AnalysisOperationRepr::GroupBy { field } => {
+                let mut error = ErrorBuilder::new();
+                if !field.is_string() {
+                    error.add(
+                        "A 'group_by' operation must access a field of an agent by string".into(),
+                    );
+                }
+
+                if !self.is_agent_operation() {
                     error.add(
-                        "A 'filter' operation must be followed either by 'filter', 'get' or \
-                         'count' operations"
+                        "A 'group_by' operation must be followed by an operation on agents, e.g. \
+                         'filter', 'get', 'group_by' or 'count'"
                             .into(),
                     );
                 }
                 error.finish()
             }
+            AnalysisOperationRepr::Isolated => {
+                if self.is_agent_operation() {
+                    None
+                } else {
+                    Some(
+                        "An 'isolated' operation must be followed by an operation on agents, e.g. \
+                         'filter', 'get', 'group_by' or 'count'"
+                            .into(),
+                    )
+                }
+            }
+            AnalysisOperationRepr::NeighborCount
+            | AnalysisOperationRepr::SentMessageCount { .. }
+            | AnalysisOperationRepr::ReceivedMessageCount { .. } => {
+                if self.is_num_aggregator() {
+                    None
+                } else {
+                    Some(format!(
+                        "A '{}' operation may only be followed by an aggregator",
+                        serde_json::to_string(preceding)?
+                    ))
+                }
+            }
             AnalysisOperationRepr::Get { field } => {
                 let mut error = None;
                 if !(field.is_string() || field.is_u64()) {
@@ -133,6 +199,26 @@ impl AnalysisOperationRepr {
 
         Ok(result)
     }
+
+    pub fn has_invalid_arguments(&self) -> Option<String> {
+        match self {
+            AnalysisOperationRepr::Percentile { p } if !(0.0..=100.0).contains(p) => Some(format!(
+                "A 'percentile' operation requires 'p' to be between 0 and 100, got {p}"
+            )),
+            AnalysisOperationRepr::Histogram { bins: 0, .. } => {
+                Some("A 'histogram' operation requires at least one bin".into())
+            }
+            AnalysisOperationRepr::Histogram {
+                min: Some(min),
+                max: Some(max),
+                ..
+            } if min >= max => Some(format!(
+                "A 'histogram' operation requires 'min' to be less than 'max', got {min} and \
+                 {max}"
+            )),
+            _ => None,
+        }
+    }
 }
 
 struct ErrorBuilder {
@@ -163,3 +249,114 @@ impl ErrorBuilder {
         Some(finished)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Validates an analysis with the single output `operations` and returns the errors, if any.
+    fn validate(operations: serde_json::Value) -> Option<String> {
+        let source: AnalysisSourceRepr =
+            serde_json::from_value(json!({ "outputs": { "output": operations } })).unwrap();
+        source.validate_def().err().map(|err| err.to_string())
+    }
+
+    fn assert_invalid(operations: serde_json::Value, expected: &str) {
+        let error = validate(operations).expect("Analysis was expected to be invalid");
+        assert!(error.contains(expected), "{error}");
+    }
+
+    #[test]
+    fn valid_group_by() {
+        assert_eq!(
+            validate(json!([
+                { "op": "filter", "field": "size", "comparison": "gt", "value": 1 },
+                { "op": "group_by", "field": "team" },
+                { "op": "group_by", "field": "active" },
+                { "op": "get", "field": "size" },
+                { "op": "percentile", "p": 50 },
+            ])),
+            None
+        );
+        assert_eq!(
+            validate(json!([
+                { "op": "group_by", "field": "team" },
+                { "op": "get", "field": "size" },
+                { "op": "histogram", "bins": 4, "min": 0, "max": 8 },
+            ])),
+            None
+        );
+    }
+
+    #[test]
+    fn group_by_field_must_be_a_string() {
+        assert_invalid(
+            json!([{ "op": "group_by", "field": 0 }, { "op": "count" }]),
+            "A 'group_by' operation must access a field of an agent by string",
+        );
+    }
+
+    #[test]
+    fn group_by_must_be_followed_by_an_operation_on_agents() {
+        assert_invalid(
+            json!([{ "op": "group_by", "field": "team" }, { "op": "sum" }]),
+            "A 'group_by' operation must be followed by an operation on agents",
+        );
+    }
+
+    #[test]
+    fn group_by_must_not_follow_get() {
+        assert_invalid(
+            json!([
+                { "op": "get", "field": "size" },
+                { "op": "group_by", "field": "team" },
+                { "op": "count" },
+            ]),
+            "operation must not follow a 'get' operation, as it operates on agents",
+        );
+    }
+
+    #[test]
+    fn group_by_must_not_be_last() {
+        assert_invalid(
+            json!([
+                { "op": "filter", "field": "active", "comparison": "eq", "value": true },
+                { "op": "group_by", "field": "team" },
+            ]),
+            "operation must not be the last operation",
+        );
+    }
+
+    #[test]
+    fn percentile_must_be_between_0_and_100() {
+        assert_invalid(
+            json!([{ "op": "get", "field": "size" }, { "op": "percentile", "p": 101 }]),
+            "A 'percentile' operation requires 'p' to be between 0 and 100, got 101",
+        );
+        assert_invalid(
+            json!([{ "op": "get", "field": "size" }, { "op": "percentile", "p": -1 }]),
+            "got -1",
+        );
+    }
+
+    #[test]
+    fn histogram_requires_bins() {
+        assert_invalid(
+            json!([{ "op": "get", "field": "size" }, { "op": "histogram", "bins": 0 }]),
+            "A 'histogram' operation requires at least one bin",
+        );
+    }
+
+    #[test]
+    fn histogram_requires_min_less_than_max() {
+        assert_invalid(
+            json!([
+                { "op": "get", "field": "size" },
+                { "op": "histogram", "bins": 2, "min": 2, "max": 1 },
+            ]),
+            "A 'histogram' operation requires 'min' to be less than 'max', got 2 and 1",
+        );
+    }
+}
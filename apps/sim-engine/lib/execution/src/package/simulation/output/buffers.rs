@@ -2,7 +2,7 @@ use crate::{
     package::{
         experiment::ExperimentId,
         simulation::{
-            output::{analysis::AnalysisBuffer, OutputPartBuffer},
+            output::{analysis::AnalysisBuffer, persistence::GlobalsUpdate, OutputPartBuffer},
             OutputPackagesSimConfig, SimulationId,
         },
     },
@@ -12,6 +12,7 @@ use crate::{
 pub struct OutputBuffers {
     pub json_state: OutputPartBuffer,
     pub analysis: AnalysisBuffer,
+    pub globals_updates: Vec<GlobalsUpdate>,
 }
 
 impl OutputBuffers {
@@ -24,6 +25,7 @@ impl OutputBuffers {
             // TODO: This should be dynamically created by the output packages
             json_state: OutputPartBuffer::new("json_state", exp_id, sim_id)?,
             analysis: AnalysisBuffer::new(output_packages_sim_config)?,
+            globals_updates: Vec::new(),
         })
     }
 }
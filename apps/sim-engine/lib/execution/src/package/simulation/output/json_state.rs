@@ -1,21 +1,32 @@
 //! Raw state data output.
+//!
+//! The state of every step is persisted either as JSON or in a columnar format, see
+//! [`JsonStateFormat`]. Which steps, agents, and fields are persisted is configured by
+//! [`JsonStateOutputConfig`].
 
 mod config;
+mod filter;
+mod format;
 mod output;
 
 use std::sync::Arc;
 
+use arrow2::{chunk::Chunk, compute::filter::filter_chunk};
 use async_trait::async_trait;
+use memory::arrow::record_batch::RecordBatch;
 use stateful::{
-    agent::{AgentSchema, IntoAgents},
-    context::Context,
-    field::{FieldScope, FieldSpecMapAccessor},
-    global::Globals,
+    agent::AgentSchema, context::Context, field::FieldSpecMapAccessor, global::Globals,
     state::State,
 };
 use tracing::Span;
 
-pub use self::{config::JsonStateOutputConfig, output::JsonStateOutput};
+use self::filter::AgentSelection;
+pub use self::{
+    config::JsonStateOutputConfig,
+    filter::AgentFilter,
+    format::{JsonStateFormat, StepIndexEntry},
+    output::JsonStateOutput,
+};
 use crate::{
     package::simulation::{
         output::{Output, OutputPackage, OutputPackageCreator, OutputPackageName},
@@ -27,7 +38,9 @@ use crate::{
 
 pub struct JsonState {
     agent_schema: Arc<AgentSchema>,
-    output_config: JsonStateOutputConfig,
+    output_config: Arc<JsonStateOutputConfig>,
+    /// `None` if all agents are persisted
+    agent_selection: Option<AgentSelection>,
 }
 
 impl MaybeCpuBound for JsonState {
@@ -40,41 +53,46 @@ impl Package for JsonState {}
 
 #[async_trait]
 impl OutputPackage for JsonState {
-    async fn run(&mut self, state: Arc<State>, _context: Arc<Context>) -> Result<Output> {
+    async fn run(
+        &mut self,
+        state: Arc<State>,
+        _context: Arc<Context>,
+        step: usize,
+    ) -> Result<Output> {
+        let mut output = JsonStateOutput::new(
+            Arc::clone(&self.agent_schema),
+            step,
+            Arc::clone(&self.output_config),
+        );
+        if !output.is_persisted() {
+            return Ok(Output::JsonStateOutput(output));
+        }
+
         let state = state.read()?;
-        let agent_states: stateful::Result<Vec<_>> = state
+        // The conversion to agents is deferred to the output persistence, which may write the
+        // record batches directly
+        for (agent_batch, message_batch) in state
             .agent_pool()
             .batches_iter()
             .zip(state.message_pool().batches_iter())
-            .map(|(agent_batch, message_batch)| {
-                (
-                    agent_batch.batch.record_batch()?,
-                    message_batch.batch.record_batch()?,
-                )
-                    .to_agent_states(Some(&self.agent_schema))
-            })
-            .collect();
-
-        let agent_states: Vec<_> = agent_states?
-            .into_iter()
-            .flatten()
-            .map(|mut agent| {
-                agent.custom.retain(|key, _| {
-                    if key.starts_with(FieldScope::Hidden.prefix()) {
-                        self.output_config.retain_hidden
-                    } else if key.starts_with(FieldScope::Private.prefix()) {
-                        self.output_config.retain_private
-                    } else {
-                        true
-                    }
-                });
-                agent
-            })
-            .collect();
-
-        Ok(Output::JsonStateOutput(JsonStateOutput {
-            inner: agent_states,
-        }))
+        {
+            let agent_record_batch = agent_batch.batch.record_batch()?;
+            let message_record_batch = message_batch.batch.record_batch()?;
+            let mut agent_columns = Chunk::new(agent_record_batch.columns().to_vec());
+            let mut message_columns = Chunk::new(message_record_batch.columns().to_vec());
+            // Messages are stored in a row per agent, so both batches are filtered the same way
+            if let Some(selection) = &self.agent_selection {
+                let mask = selection.mask(agent_batch)?;
+                agent_columns = filter_chunk(&agent_columns, &mask)?;
+                message_columns = filter_chunk(&message_columns, &mask)?;
+            }
+            output.batches.push((
+                RecordBatch::new(agent_record_batch.schema(), agent_columns),
+                RecordBatch::new(message_record_batch.schema(), message_columns),
+            ));
+        }
+
+        Ok(Output::JsonStateOutput(output))
     }
 
     fn span(&self) -> Span {
@@ -90,7 +108,7 @@ impl OutputPackageCreator for JsonStateCreator {
         config: &PackageCreatorConfig,
         _init_config: &PackageInitConfig,
         _comms: PackageComms,
-        _accessor: FieldSpecMapAccessor,
+        accessor: FieldSpecMapAccessor,
     ) -> Result<Box<dyn OutputPackage>> {
         let value = config
             .persistence
@@ -99,9 +117,19 @@ impl OutputPackageCreator for JsonStateCreator {
             .get(&PackageName::Output(OutputPackageName::JsonState))
             .ok_or_else(|| Error::from("Missing JSON state config"))?;
         let output_config: JsonStateOutputConfig = serde_json::from_value(value.clone())?;
+        let agent_selection = if output_config.filters_agents() {
+            Some(AgentSelection::new(
+                output_config.agent_names.clone(),
+                &output_config.agent_filters,
+                &accessor,
+            )?)
+        } else {
+            None
+        };
         Ok(Box::new(JsonState {
             agent_schema: Arc::clone(&config.agent_schema),
-            output_config,
+            output_config: Arc::new(output_config),
+            agent_selection,
         }))
     }
 
@@ -116,3 +144,138 @@ impl OutputPackageCreator for JsonStateCreator {
 }
 
 impl PackageCreator for JsonStateCreator {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use arrow2::{
+        array::UInt32Array,
+        datatypes::{DataType, Field, Schema},
+    };
+    use memory::shared_memory::MemoryId;
+    use serde_json::json;
+    use stateful::{
+        agent::Agent,
+        context::ContextSchema,
+        field::{
+            FieldScope, FieldSource, FieldSpecMap, FieldType, FieldTypeVariant, RootFieldSpec,
+            RootFieldSpecCreator,
+        },
+        message::MessageSchema,
+        state::StateCreateParameters,
+    };
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Agents `a` to `d` with an `age` of 1 to 4, spread over two groups.
+    fn state(agent_schema: &Arc<AgentSchema>) -> State {
+        let agents: Vec<Agent> = serde_json::from_value(json!([
+            { "agent_name": "a", "age": 1 },
+            { "agent_name": "b", "age": 2 },
+            { "agent_name": "c", "age": 3 },
+            { "agent_name": "d", "age": 4 },
+        ]))
+        .unwrap();
+        State::from_agent_states(
+            &agents,
+            StateCreateParameters {
+                target_min_groups: 2,
+                target_group_size: 1..4,
+                memory_base_id: Uuid::new_v4(),
+                agent_schema: Arc::clone(agent_schema),
+                message_schema: Arc::new(MessageSchema::new()),
+            },
+        )
+        .unwrap()
+    }
+
+    /// The output packages don't depend on a specific context, so a single column is enough.
+    fn context(num_agents: usize) -> Context {
+        let schema = ContextSchema {
+            arrow: Arc::new(Schema::from(vec![Field::new(
+                "unused",
+                DataType::UInt32,
+                false,
+            )])),
+            field_spec_map: Arc::new(FieldSpecMap::empty()),
+        };
+        Context::from_columns(
+            Chunk::new(vec![UInt32Array::from_vec(vec![0; num_agents]).boxed()]),
+            &schema,
+            MemoryId::new(Uuid::new_v4()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn persists_configured_steps_agents_and_fields() {
+        let mut field_spec_map = FieldSpecMap::empty();
+        field_spec_map
+            .try_extend(RootFieldSpec::base_agent_fields().unwrap())
+            .unwrap();
+        field_spec_map
+            .try_extend([RootFieldSpecCreator::new(FieldSource::Engine).create(
+                "age".to_string(),
+                FieldType::new(FieldTypeVariant::Number, true),
+                FieldScope::Agent,
+            )])
+            .unwrap();
+        let agent_schema = Arc::new(AgentSchema::new(field_spec_map).unwrap());
+        let accessor = FieldSpecMapAccessor::new(
+            FieldSource::Engine,
+            Arc::clone(&agent_schema.field_spec_map),
+        );
+
+        let output_config: JsonStateOutputConfig = serde_json::from_value(json!({
+            "stride": 2,
+            "fields": ["agent_name"],
+            "agent_filters": [{ "field": "age", "comparison": "gt", "value": 2 }],
+        }))
+        .unwrap();
+        let mut package = JsonState {
+            agent_schema: Arc::clone(&agent_schema),
+            agent_selection: Some(
+                AgentSelection::new(
+                    output_config.agent_names.clone(),
+                    &output_config.agent_filters,
+                    &accessor,
+                )
+                .unwrap(),
+            ),
+            output_config: Arc::new(output_config),
+        };
+
+        let state = Arc::new(state(&agent_schema));
+        let context = Arc::new(context(4));
+        // A resumed simulation run starts with the step of its checkpoint
+        for step in [3, 4, 5, 6] {
+            let output = match package
+                .run(Arc::clone(&state), Arc::clone(&context), step)
+                .await
+                .unwrap()
+            {
+                Output::JsonStateOutput(output) => output,
+                Output::AnalysisOutput(_) => unreachable!(),
+            };
+            assert_eq!(output.step(), step);
+            assert_eq!(output.is_persisted(), step % 2 == 0, "step {step}");
+            if !output.is_persisted() {
+                assert_eq!(output.num_agents(), 0);
+                continue;
+            }
+
+            let agents = output.to_json().unwrap();
+            let names: Vec<_> = agents.iter().map(|agent| &agent["agent_name"]).collect();
+            assert_eq!(names, [&json!("c"), &json!("d")]);
+            for agent in &agents {
+                let fields: HashSet<_> = agent.as_object().unwrap().keys().cloned().collect();
+                assert_eq!(
+                    fields,
+                    HashSet::from(["agent_id".to_string(), "agent_name".to_string()])
+                );
+            }
+        }
+    }
+}
@@ -1,16 +1,159 @@
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{package::simulation::PackageInitConfig, Result};
+use crate::{
+    package::simulation::{
+        output::json_state::filter::AgentFilter, PackageInitConfig, SimPackageArgs,
+    },
+    Error, Result,
+};
+
+fn default_stride() -> usize {
+    1
+}
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// Configures which steps, agents, and fields are persisted by the JSON state package.
+///
+/// The configuration is read from the `"json_state"` member of the output configuration of the
+/// project (_views/output.json_). Without a configuration, every field of every agent is persisted
+/// in every step, except hidden and private fields.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct JsonStateOutputConfig {
+    /// Only every `stride`-th step is persisted, starting with the first one
+    #[serde(default = "default_stride")]
+    pub stride: usize,
+    /// If set, only these fields are persisted. `agent_id` is always persisted.
+    #[serde(default)]
+    pub fields: Option<HashSet<String>>,
+    /// Fields, which are not persisted
+    #[serde(default)]
+    pub exclude_fields: HashSet<String>,
+    #[serde(default)]
     pub retain_hidden: bool,
+    #[serde(default)]
     pub retain_private: bool,
+    /// If set, only agents with one of these names are persisted
+    #[serde(default)]
+    pub agent_names: Option<HashSet<String>>,
+    /// Only agents matching all of these filters are persisted
+    #[serde(default)]
+    pub agent_filters: Vec<AgentFilter>,
+}
+
+impl Default for JsonStateOutputConfig {
+    fn default() -> Self {
+        Self {
+            stride: default_stride(),
+            fields: None,
+            exclude_fields: HashSet::new(),
+            retain_hidden: false,
+            retain_private: false,
+            agent_names: None,
+            agent_filters: Vec::new(),
+        }
+    }
 }
 
 impl JsonStateOutputConfig {
-    pub fn new(_config: &PackageInitConfig) -> Result<JsonStateOutputConfig> {
-        // TODO: make this configurable
-        Ok(JsonStateOutputConfig::default())
+    pub fn new(config: &PackageInitConfig) -> Result<JsonStateOutputConfig> {
+        let output_config = match get_output_source(&config.packages)? {
+            Some(source) => serde_json::from_str::<serde_json::Value>(&source)
+                .map_err(|err| Error::from(format!("Could not parse output config: {err}")))?,
+            None => return Ok(JsonStateOutputConfig::default()),
+        };
+
+        let config: JsonStateOutputConfig = match output_config.get("json_state") {
+            Some(json_state) => serde_json::from_value(json_state.clone())
+                .map_err(|err| Error::from(format!("Invalid JSON state output config: {err}")))?,
+            None => JsonStateOutputConfig::default(),
+        };
+        if config.stride == 0 {
+            return Err(Error::from(
+                "The stride of the JSON state output must not be 0",
+            ));
+        }
+        config
+            .agent_filters
+            .iter()
+            .try_for_each(AgentFilter::validate)?;
+        Ok(config)
+    }
+
+    /// Returns `true` if the state of the `step`-th step is persisted.
+    pub fn is_persisted_step(&self, step: usize) -> bool {
+        step % self.stride == 0
+    }
+
+    /// Returns `true` if agents are filtered by their name or by a predicate.
+    pub fn filters_agents(&self) -> bool {
+        self.agent_names.is_some() || !self.agent_filters.is_empty()
+    }
+}
+
+/// Returns the content of the output configuration of the project, if it has one.
+fn get_output_source(sim_packages: &[SimPackageArgs]) -> Result<Option<String>> {
+    for args in sim_packages {
+        if args.name.as_str() == "output" {
+            return match &args.data {
+                serde_json::Value::String(src) if src.trim().is_empty() => Ok(None),
+                serde_json::Value::String(src) => Ok(Some(src.clone())),
+                serde_json::Value::Null => Ok(None),
+                _ => Err(Error::from("Output config source must be a string")),
+            };
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::package::simulation::init::{InitialState, InitialStateName};
+
+    fn init_config(output: serde_json::Value) -> PackageInitConfig {
+        PackageInitConfig {
+            packages: vec![SimPackageArgs {
+                name: "output".to_string(),
+                data: serde_json::Value::String(output.to_string()),
+            }],
+            initial_state: InitialState {
+                name: InitialStateName::InitJson,
+                src: String::new(),
+            },
+            behaviors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reads_json_state_config() {
+        let config = JsonStateOutputConfig::new(&init_config(json!({
+            "json_state": {
+                "stride": 10,
+                "fields": ["position"],
+                "agent_filters": [{ "op": "filter", "field": "age", "comparison": "gt", "value": 3 }]
+            }
+        })))
+        .unwrap();
+        assert!(config.is_persisted_step(20));
+        assert!(!config.is_persisted_step(25));
+        assert!(config.filters_agents());
+
+        assert_eq!(
+            JsonStateOutputConfig::new(&init_config(json!({})))
+                .unwrap()
+                .stride,
+            1
+        );
+        assert!(
+            JsonStateOutputConfig::new(&init_config(json!({"json_state": {"stride": 0}}))).is_err()
+        );
+        assert!(
+            JsonStateOutputConfig::new(&init_config(json!({"json_state": {"strides": 2}})))
+                .is_err()
+        );
     }
 }
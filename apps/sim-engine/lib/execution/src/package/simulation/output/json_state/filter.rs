@@ -0,0 +1,154 @@
+//! Selection of the agents persisted by the JSON state package.
+
+use std::{cmp::Ordering, collections::HashSet};
+
+use arrow2::{array::BooleanArray, datatypes::DataType};
+use float_cmp::approx_eq;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stateful::{
+    agent::{self, AgentBatch},
+    field::FieldSpecMapAccessor,
+};
+
+use crate::{
+    package::simulation::output::analysis::{ComparisonRepr, ULPS},
+    Error, Result,
+};
+
+/// A predicate on an agent field, using the syntax of the `filter` operation of the analysis.
+///
+/// Like in the analysis, `null` values only pass `neq` comparisons against non-null values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentFilter {
+    pub field: String,
+    pub comparison: ComparisonRepr,
+    pub value: Value,
+}
+
+impl AgentFilter {
+    pub(super) fn validate(&self) -> Result<()> {
+        match &self.value {
+            Value::Null | Value::Bool(_)
+                if !matches!(self.comparison, ComparisonRepr::Eq | ComparisonRepr::Neq) =>
+            {
+                Err(Error::from(format!(
+                    "Agent filter on '{}' compares to '{}', so only the 'eq' and 'neq' \
+                     comparisons can be applied",
+                    self.field, self.value
+                )))
+            }
+            Value::Array(_) | Value::Object(_) => Err(Error::from(format!(
+                "Agent filter on '{}' must compare to a number, boolean, string or null",
+                self.field
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        let ordering = match (value, &self.value) {
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            (Value::Number(lhs), Value::Number(rhs)) => {
+                let (lhs, rhs) = (lhs.as_f64().unwrap(), rhs.as_f64().unwrap());
+                if approx_eq!(f64, lhs, rhs, ulps = ULPS) {
+                    Some(Ordering::Equal)
+                } else {
+                    lhs.partial_cmp(&rhs)
+                }
+            }
+            (Value::String(lhs), Value::String(rhs)) => Some(lhs.cmp(rhs)),
+            (Value::Bool(lhs), Value::Bool(rhs)) => Some(lhs.cmp(rhs)),
+            _ => None,
+        };
+        match ordering {
+            Some(ordering) => match self.comparison {
+                ComparisonRepr::Eq => ordering == Ordering::Equal,
+                ComparisonRepr::Neq => ordering != Ordering::Equal,
+                ComparisonRepr::Lt => ordering == Ordering::Less,
+                ComparisonRepr::Lte => ordering != Ordering::Greater,
+                ComparisonRepr::Gt => ordering == Ordering::Greater,
+                ComparisonRepr::Gte => ordering != Ordering::Less,
+            },
+            // Values of different types are never equal
+            None => matches!(self.comparison, ComparisonRepr::Neq),
+        }
+    }
+}
+
+/// The agent filters of the configuration with the resolved types of the filtered fields.
+pub(super) struct AgentSelection {
+    names: Option<HashSet<String>>,
+    filters: Vec<(AgentFilter, DataType)>,
+}
+
+impl AgentSelection {
+    pub(super) fn new(
+        names: Option<HashSet<String>>,
+        filters: &[AgentFilter],
+        accessor: &FieldSpecMapAccessor,
+    ) -> Result<Self> {
+        let filters = filters
+            .iter()
+            .map(|filter| {
+                let data_type = DataType::from(
+                    accessor
+                        .get_agent_scoped_field_spec(&filter.field)?
+                        .inner
+                        .field_type
+                        .variant
+                        .clone(),
+                );
+                Ok((filter.clone(), data_type))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { names, filters })
+    }
+
+    /// Returns which agents of `batch` are selected.
+    pub(super) fn mask(&self, batch: &AgentBatch) -> Result<BooleanArray> {
+        let batches = [batch];
+        let mut mask = vec![true; batch.num_agents()];
+        if let Some(names) = &self.names {
+            for (selected, name) in mask
+                .iter_mut()
+                .zip(agent::arrow::agent_name_iter(&batches)?)
+            {
+                *selected &= name.map_or(false, |name| names.contains(name));
+            }
+        }
+        for (filter, data_type) in &self.filters {
+            let values = agent::arrow::json_value_iter_cols(&batches, &filter.field, data_type)?;
+            for (selected, value) in mask.iter_mut().zip(values) {
+                *selected &= filter.matches(&value);
+            }
+        }
+        Ok(BooleanArray::from_slice(mask))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn filter(comparison: ComparisonRepr, value: Value) -> AgentFilter {
+        AgentFilter {
+            field: "field".to_string(),
+            comparison,
+            value,
+        }
+    }
+
+    #[test]
+    fn filters_compare_like_analysis_filters() {
+        assert!(filter(ComparisonRepr::Gt, json!(3)).matches(&json!(3.5)));
+        assert!(filter(ComparisonRepr::Lte, json!(0.3)).matches(&json!(0.1 + 0.2)));
+        assert!(filter(ComparisonRepr::Eq, json!("a")).matches(&json!("a")));
+        assert!(!filter(ComparisonRepr::Eq, json!(true)).matches(&Value::Null));
+        assert!(filter(ComparisonRepr::Neq, json!(true)).matches(&Value::Null));
+        assert!(filter(ComparisonRepr::Eq, Value::Null).matches(&Value::Null));
+        assert!(filter(ComparisonRepr::Lt, json!(true)).validate().is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use std::{fmt, io::Write, str::FromStr};
+
+use arrow2::{
+    array::Array,
+    chunk::Chunk,
+    datatypes::Schema,
+    io::{ipc, parquet},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// The format the agent state of every step is persisted in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JsonStateFormat {
+    /// A single `json_state.json` containing a list of agents for every step.
+    #[default]
+    Json,
+    /// One Arrow IPC file per step, containing the agent batches of that step.
+    ArrowIpc,
+    /// One Parquet file per step, containing a row group for every agent batch of that step.
+    Parquet,
+}
+
+impl JsonStateFormat {
+    /// The extension of the files written for every step, or `None` for [`Json`](Self::Json).
+    pub fn step_file_extension(self) -> Option<&'static str> {
+        match self {
+            Self::Json => None,
+            Self::ArrowIpc => Some("arrow"),
+            Self::Parquet => Some("parquet"),
+        }
+    }
+
+    /// Writes the `chunks` of a single step in a columnar format.
+    ///
+    /// Returns an error for [`Json`](Self::Json), which isn't a columnar format.
+    pub fn write_step<W: Write>(
+        self,
+        writer: W,
+        schema: &Schema,
+        chunks: Vec<Chunk<Box<dyn Array>>>,
+    ) -> Result<()> {
+        match self {
+            Self::Json => Err(Error::from("JSON state can't be written per step")),
+            Self::ArrowIpc => {
+                let mut writer = ipc::write::FileWriter::try_new(
+                    writer,
+                    schema,
+                    None,
+                    ipc::write::WriteOptions { compression: None },
+                )?;
+                for chunk in &chunks {
+                    writer.write(chunk, None)?;
+                }
+                writer.finish()?;
+                Ok(())
+            }
+            Self::Parquet => {
+                let options = parquet::write::WriteOptions {
+                    write_statistics: true,
+                    compression: parquet::write::CompressionOptions::Snappy,
+                    version: parquet::write::Version::V2,
+                };
+                let encodings = schema
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        parquet::write::transverse(&field.data_type, |_| {
+                            parquet::write::Encoding::Plain
+                        })
+                    })
+                    .collect();
+                let row_groups = parquet::write::RowGroupIterator::try_new(
+                    chunks.into_iter().map(Ok),
+                    schema,
+                    options,
+                    encodings,
+                )?;
+
+                let mut writer =
+                    parquet::write::FileWriter::try_new(writer, schema.clone(), options)?;
+                for group in row_groups {
+                    writer.write(group?)?;
+                }
+                writer.end(None)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for JsonStateFormat {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => fmt.write_str("json"),
+            Self::ArrowIpc => fmt.write_str("arrow-ipc"),
+            Self::Parquet => fmt.write_str("parquet"),
+        }
+    }
+}
+
+impl FromStr for JsonStateFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "json" => Ok(Self::Json),
+            "arrow-ipc" | "arrow" | "ipc" => Ok(Self::ArrowIpc),
+            "parquet" => Ok(Self::Parquet),
+            _ => Err(format!(
+                "Unknown JSON state format `{format}`, expected one of `json`, `arrow-ipc` or \
+                 `parquet`"
+            )),
+        }
+    }
+}
+
+/// Entry of the step index, which is written next to the per-step files of columnar formats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepIndexEntry {
+    pub step: usize,
+    /// The file name relative to the directory of the index
+    pub file: String,
+    pub num_agents: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow2::{
+        array::{BooleanArray, Float64Array, Utf8Array},
+        datatypes::{DataType, Field, Metadata},
+    };
+
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from(vec![
+            Field::new("agent_name", DataType::Utf8, true),
+            Field::new("age", DataType::Float64, true),
+            Field::new("alive", DataType::Boolean, false),
+        ])
+        .with_metadata(Metadata::from([("key".to_string(), "value".to_string())]))
+    }
+
+    /// Two agent batches, one row group or record batch each.
+    fn chunks() -> Vec<Chunk<Box<dyn Array>>> {
+        vec![
+            Chunk::new(vec![
+                Utf8Array::<i32>::from([Some("a"), None]).boxed(),
+                Float64Array::from([Some(1.5), Some(2.0)]).boxed(),
+                BooleanArray::from_slice([true, false]).boxed(),
+            ]),
+            Chunk::new(vec![
+                Utf8Array::<i32>::from([Some("c")]).boxed(),
+                Float64Array::from([None]).boxed(),
+                BooleanArray::from_slice([true]).boxed(),
+            ]),
+        ]
+    }
+
+    fn write(format: JsonStateFormat) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        format.write_step(&mut buffer, &schema(), chunks()).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn arrow_ipc_round_trip() {
+        let mut reader = Cursor::new(write(JsonStateFormat::ArrowIpc));
+        let metadata = ipc::read::read_file_metadata(&mut reader).unwrap();
+        assert_eq!(metadata.schema, schema());
+
+        let read = ipc::read::FileReader::new(reader, metadata, None)
+            .collect::<arrow2::error::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, chunks());
+    }
+
+    #[test]
+    fn parquet_round_trip() {
+        let reader = parquet::read::FileReader::try_new(
+            Cursor::new(write(JsonStateFormat::Parquet)),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(reader.schema(), &schema());
+
+        let read = reader.collect::<arrow2::error::Result<Vec<_>>>().unwrap();
+        assert_eq!(read, chunks());
+    }
+
+    #[test]
+    fn json_is_not_columnar() {
+        assert!(JsonStateFormat::Json
+            .write_step(Vec::new(), &schema(), chunks())
+            .is_err());
+    }
+}
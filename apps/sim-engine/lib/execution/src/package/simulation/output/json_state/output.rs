@@ -1,6 +1,152 @@
-use stateful::agent::Agent;
+use std::sync::Arc;
 
+use arrow2::{
+    array::Array,
+    chunk::Chunk,
+    datatypes::{Field, Schema},
+};
+use memory::arrow::record_batch::RecordBatch;
+use stateful::{
+    agent::{Agent, AgentSchema, AgentStateField, IntoAgents},
+    field::FieldScope,
+};
+
+use crate::{package::simulation::output::json_state::JsonStateOutputConfig, Result};
+
+/// The agent state of a single step.
+///
+/// The state is kept as Arrow record batches, so it can either be converted to a list of
+/// [`Agent`]s or be written in a columnar format without a conversion in between. The batches only
+/// contain the agents selected by the [`JsonStateOutputConfig`].
 #[derive(Debug)]
 pub struct JsonStateOutput {
-    pub inner: Vec<Agent>,
+    pub(super) agent_schema: Arc<AgentSchema>,
+    /// The step this state belongs to
+    pub(super) step: usize,
+    /// The agent batches and their corresponding message batches
+    pub(super) batches: Vec<(RecordBatch, RecordBatch)>,
+    pub(super) config: Arc<JsonStateOutputConfig>,
+}
+
+impl JsonStateOutput {
+    pub(super) fn new(
+        agent_schema: Arc<AgentSchema>,
+        step: usize,
+        config: Arc<JsonStateOutputConfig>,
+    ) -> Self {
+        Self {
+            agent_schema,
+            step,
+            batches: Vec::new(),
+            config,
+        }
+    }
+
+    fn is_retained(&self, key: &str) -> bool {
+        if key == AgentStateField::AgentId.name() {
+            return true;
+        }
+        if self.config.exclude_fields.contains(key)
+            || matches!(&self.config.fields, Some(fields) if !fields.contains(key))
+        {
+            false
+        } else if key.starts_with(FieldScope::Hidden.prefix()) {
+            self.config.retain_hidden
+        } else if key.starts_with(FieldScope::Private.prefix()) {
+            self.config.retain_private
+        } else {
+            true
+        }
+    }
+
+    /// The step this state belongs to.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Returns `false` if the step is skipped because of the configured stride.
+    pub fn is_persisted(&self) -> bool {
+        self.config.is_persisted_step(self.step)
+    }
+
+    /// Converts the state into a list of [`Agent`]s including their outbound messages.
+    ///
+    /// Only custom fields are removed, use [`to_json`](Self::to_json) to also remove built-in
+    /// fields, which are not retained.
+    pub fn to_agents(&self) -> Result<Vec<Agent>> {
+        let agent_states: stateful::Result<Vec<_>> = self
+            .batches
+            .iter()
+            .map(|(agent_batch, message_batch)| {
+                (agent_batch, message_batch).to_agent_states(Some(&self.agent_schema))
+            })
+            .collect();
+
+        Ok(agent_states?
+            .into_iter()
+            .flatten()
+            .map(|mut agent| {
+                agent.custom.retain(|key, _| self.is_retained(key));
+                agent
+            })
+            .collect())
+    }
+
+    /// Converts the state into a list of JSON objects, one for every agent, containing the retained
+    /// fields including their outbound messages.
+    pub fn to_json(&self) -> Result<Vec<serde_json::Value>> {
+        self.to_agents()?
+            .into_iter()
+            .map(|agent| {
+                let mut agent = serde_json::to_value(agent)?;
+                if let serde_json::Value::Object(fields) = &mut agent {
+                    fields.retain(|key, _| self.is_retained(key));
+                }
+                Ok(agent)
+            })
+            .collect()
+    }
+
+    /// Returns the schema of the columns returned by [`to_chunks`](Self::to_chunks).
+    ///
+    /// This is the schema of the agent batches without the columns, which are not retained.
+    pub fn schema(&self) -> Schema {
+        let fields: Vec<Field> = self
+            .agent_schema
+            .arrow
+            .fields
+            .iter()
+            .filter(|field| self.is_retained(&field.name))
+            .cloned()
+            .collect();
+        Schema::from(fields).with_metadata(self.agent_schema.arrow.metadata.clone())
+    }
+
+    /// Returns the columns of every agent batch, which are retained.
+    ///
+    /// Outbound messages are not included.
+    pub fn to_chunks(&self) -> Vec<Chunk<Box<dyn Array>>> {
+        self.batches
+            .iter()
+            .map(|(agent_batch, _)| {
+                let columns = agent_batch
+                    .schema()
+                    .fields
+                    .iter()
+                    .zip(agent_batch.columns())
+                    .filter(|(field, _)| self.is_retained(&field.name))
+                    .map(|(_, column)| column.clone())
+                    .collect();
+                Chunk::new(columns)
+            })
+            .collect()
+    }
+
+    /// Returns the number of agents in this step.
+    pub fn num_agents(&self) -> usize {
+        self.batches
+            .iter()
+            .map(|(agent_batch, _)| agent_batch.num_rows())
+            .sum()
+    }
 }
@@ -18,7 +18,8 @@ const CHAR_OPEN_RIGHT_SQUARE_BRACKET: u8 = 0x5D; // ]
 
 /// ### Buffer for list of outputs
 ///
-/// Persists in parts onto disk with an in-memory cache layer
+/// Persists in parts onto disk with an in-memory cache layer. Alternatively, the parts can be taken
+/// out of the buffer directly, e.g. to upload them, in which case nothing is written to disk.
 pub struct OutputPartBuffer {
     output_type: &'static str,
     current: Vec<u8>,
@@ -37,8 +38,6 @@ impl OutputPartBuffer {
             .join(experiment_id.to_string())
             .join(simulation_run_id.to_string());
 
-        std::fs::create_dir_all(&base_path)?;
-
         // Twice the size so we rarely exceed it
         let mut current = Vec::with_capacity(IN_MEMORY_SIZE * 2);
         current.push(CHAR_OPEN_LEFT_SQUARE_BRACKET); // New step array
@@ -59,6 +58,7 @@ impl OutputPartBuffer {
 
     pub fn persist_current_on_disk(&mut self) -> Result<()> {
         tracing::trace!("Persisting current output to disk");
+        std::fs::create_dir_all(&self.base_path)?;
         let mut next_i = self.parts.len();
 
         let current = std::mem::replace(
@@ -111,6 +111,36 @@ impl OutputPartBuffer {
         self.persist_current_on_disk()?;
         Ok(&self.parts)
     }
+
+    /// Takes all parts of exactly [`MAX_BYTE_SIZE`] out of the in-memory buffer, keeping the
+    /// remainder for later steps.
+    pub fn take_complete_parts(&mut self) -> Vec<Vec<u8>> {
+        let part_count = self.current.len() / MAX_BYTE_SIZE;
+        if part_count == 0 {
+            return Vec::new();
+        }
+
+        let mut remainder = Vec::with_capacity(IN_MEMORY_SIZE * 2);
+        remainder.extend_from_slice(&self.current[part_count * MAX_BYTE_SIZE..]);
+        let current = std::mem::replace(&mut self.current, remainder);
+
+        current
+            .chunks_exact(MAX_BYTE_SIZE)
+            .map(<[u8]>::to_vec)
+            .collect()
+    }
+
+    /// Closes the list of outputs and takes all remaining parts out of the in-memory buffer.
+    ///
+    /// Only the last part may be smaller than [`MAX_BYTE_SIZE`].
+    pub fn finalize_in_memory(&mut self) -> Vec<Vec<u8>> {
+        self.current.push(CHAR_OPEN_RIGHT_SQUARE_BRACKET);
+        let mut parts = self.take_complete_parts();
+        if !self.current.is_empty() {
+            parts.push(std::mem::take(&mut self.current));
+        }
+        parts
+    }
 }
 
 impl Drop for OutputPartBuffer {
@@ -119,6 +149,9 @@ impl Drop for OutputPartBuffer {
             std::fs::remove_file(part)
                 .unwrap_or_else(|error| tracing::error!("Failed to remove part {part:?}: {error}"))
         }
+        if !self.base_path.exists() {
+            return;
+        }
         std::fs::remove_dir_all(&self.base_path).unwrap_or_else(|error| {
             tracing::error!(
                 "Failed to temporary part directory {:?}: {error}",
@@ -127,3 +160,79 @@ impl Drop for OutputPartBuffer {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer() -> OutputPartBuffer {
+        OutputPartBuffer::new("test", &ExperimentId::generate(), SimulationId::new(1)).unwrap()
+    }
+
+    #[test]
+    fn take_exactly_one_part() {
+        let mut buffer = buffer();
+        buffer.current = vec![b'a'; MAX_BYTE_SIZE];
+
+        let parts = buffer.take_complete_parts();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].len(), MAX_BYTE_SIZE);
+        assert!(buffer.current.is_empty());
+
+        // Nothing is left to take except of the closing bracket
+        assert!(buffer.take_complete_parts().is_empty());
+        assert_eq!(buffer.finalize_in_memory(), vec![b"]".to_vec()]);
+    }
+
+    #[test]
+    fn take_one_part_and_keep_remainder() {
+        let mut buffer = buffer();
+        buffer.current = vec![b'a'; MAX_BYTE_SIZE + 1];
+
+        let parts = buffer.take_complete_parts();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].len(), MAX_BYTE_SIZE);
+        assert_eq!(buffer.current, b"a");
+
+        assert_eq!(buffer.finalize_in_memory(), vec![b"a]".to_vec()]);
+    }
+
+    #[test]
+    fn finalize_exactly_one_part() {
+        let mut buffer = buffer();
+        buffer.current = vec![b'a'; MAX_BYTE_SIZE - 1];
+        assert!(buffer.take_complete_parts().is_empty());
+
+        // The closing bracket completes the part, so no empty part is appended
+        let parts = buffer.finalize_in_memory();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].len(), MAX_BYTE_SIZE);
+        assert_eq!(parts[0].last(), Some(&CHAR_OPEN_RIGHT_SQUARE_BRACKET));
+    }
+
+    #[test]
+    fn finalize_empty_run() {
+        let mut buffer = buffer();
+        assert!(buffer.take_complete_parts().is_empty());
+        assert_eq!(buffer.finalize_in_memory(), vec![b"[]".to_vec()]);
+    }
+
+    #[test]
+    fn parts_concatenate_to_steps() {
+        let mut buffer = buffer();
+        let step = "a".repeat(MAX_BYTE_SIZE / 3);
+        let mut parts = Vec::new();
+        for _ in 0..7 {
+            buffer.append_step(&step).unwrap();
+            parts.extend(buffer.take_complete_parts());
+        }
+        parts.extend(buffer.finalize_in_memory());
+
+        assert_eq!(parts.len(), 3);
+        assert!(parts[..parts.len() - 1]
+            .iter()
+            .all(|part| part.len() == MAX_BYTE_SIZE));
+        let steps: Vec<String> = serde_json::from_slice(&parts.concat()).unwrap();
+        assert_eq!(steps, vec![step; 7]);
+    }
+}
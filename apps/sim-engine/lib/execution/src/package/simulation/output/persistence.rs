@@ -1,10 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use stateful::global::Globals;
 
 use crate::package::simulation::SimulationId;
 
 pub mod local;
 pub mod none;
+pub mod s3;
 
 use crate::{
     package::simulation::{output::Output, PersistenceConfig},
@@ -20,10 +21,23 @@ pub trait OutputPersistenceCreator: Send + Sync + 'static {
     ) -> Result<Self::SimulationOutputPersistence>;
 }
 
+/// A change of the globals while the simulation run was executing.
+///
+/// The globals persisted with the output are the globals the simulation run was started with, so
+/// together with the updates in the order they were applied, the run can be reproduced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalsUpdate {
+    /// The first step, which was calculated with the updated globals
+    pub step: usize,
+    /// The JSON merge-patch, which was applied to the globals
+    pub patch: serde_json::Value,
+}
+
 #[async_trait::async_trait]
 pub trait SimulationOutputPersistence: Send + Sync + 'static {
     type OutputPersistenceResult: OutputPersistenceResult;
     async fn add_step_output(&mut self, output: Vec<Output>) -> Result<()>;
+    async fn add_globals_update(&mut self, update: GlobalsUpdate) -> Result<()>;
     async fn finalize(self, globals: &Globals) -> Result<Self::OutputPersistenceResult>;
 }
 
@@ -11,8 +11,10 @@ use crate::{
         experiment::{ExperimentId, ExperimentName},
         simulation::{
             output::{
+                json_state::{JsonStateFormat, JsonStateOutput, StepIndexEntry},
                 persistence::{
-                    OutputPersistenceCreator, OutputPersistenceResult, SimulationOutputPersistence,
+                    GlobalsUpdate, OutputPersistenceCreator, OutputPersistenceResult,
+                    SimulationOutputPersistence,
                 },
                 Output, OutputBuffers,
             },
@@ -40,6 +42,52 @@ pub struct LocalSimulationOutputPersistence {
     pub sim_id: SimulationId,
     pub buffers: OutputBuffers,
     pub config: LocalPersistenceConfig,
+    /// The number of steps of JSON state, which were persisted so far
+    pub num_json_state_steps: usize,
+    /// Index of the per-step files, if the JSON state is persisted in a columnar format
+    pub step_index: Vec<StepIndexEntry>,
+}
+
+impl LocalSimulationOutputPersistence {
+    fn output_path(&self) -> PathBuf {
+        self.config
+            .output_folder
+            .join(&self.project_name)
+            .join(self.experiment_name.as_str())
+            .join(self.experiment_id.to_string())
+            .join(self.sim_id.to_string())
+    }
+
+    /// Writes the state of a single step into its own file in the `json_state` directory.
+    ///
+    /// The file is named after the step of the simulation run, so the steps skipped by the stride
+    /// and the steps before the checkpoint of a resumed simulation run are left out of the
+    /// numbering.
+    fn write_json_state_step(&mut self, output: JsonStateOutput) -> Result<()> {
+        let extension = self
+            .config
+            .json_state_format
+            .step_file_extension()
+            .expect("JSON state is persisted in a columnar format");
+        let step = output.step();
+        let directory = self.output_path().join("json_state");
+        std::fs::create_dir_all(&directory)?;
+
+        let file_name = format!("step-{step:06}.{extension}");
+        let file = std::fs::File::create(directory.join(&file_name))?;
+        self.config.json_state_format.write_step(
+            BufWriter::new(file),
+            &output.schema(),
+            output.to_chunks(),
+        )?;
+
+        self.step_index.push(StepIndexEntry {
+            step,
+            file: file_name,
+            num_agents: output.num_agents(),
+        });
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -52,8 +100,14 @@ impl SimulationOutputPersistence for LocalSimulationOutputPersistence {
                 Output::AnalysisOutput(output) => {
                     self.buffers.analysis.add(output)?;
                 }
+                Output::JsonStateOutput(output) if !output.is_persisted() => {}
                 Output::JsonStateOutput(output) => {
-                    self.buffers.json_state.append_step(output.inner)?;
+                    if self.config.json_state_format == JsonStateFormat::Json {
+                        self.buffers.json_state.append_step(output.to_json()?)?;
+                    } else {
+                        self.write_json_state_step(output)?;
+                    }
+                    self.num_json_state_steps += 1;
                 }
             }
             Ok(()) as Result<()>
@@ -61,36 +115,43 @@ impl SimulationOutputPersistence for LocalSimulationOutputPersistence {
         Ok(())
     }
 
+    async fn add_globals_update(&mut self, update: GlobalsUpdate) -> Result<()> {
+        self.buffers.globals_updates.push(update);
+        Ok(())
+    }
+
     async fn finalize(mut self, globals: &Globals) -> Result<Self::OutputPersistenceResult> {
         tracing::trace!("Finalizing output");
-        // JSON state
-        let parts = self.buffers.json_state.finalize()?;
-        let path = self
-            .config
-            .output_folder
-            .join(&self.project_name)
-            .join(self.experiment_name.as_str())
-            .join(self.experiment_id.to_string())
-            .join(self.sim_id.to_string());
-
+        let path = self.output_path();
         tracing::info!("Making new output directory: {:?}", path);
         std::fs::create_dir_all(&path)?;
 
-        let json_state_path = path.join("json_state.json");
-        std::fs::File::create(&json_state_path)?;
-
-        let file_out = std::fs::OpenOptions::new()
-            .append(true)
-            .open(json_state_path)?;
-
-        let mut buf_writer = BufWriter::new(file_out);
-
-        parts.iter().try_for_each(|v| -> Result<()> {
-            let file_in = std::fs::File::open(v)?;
-            let mut buf_reader = BufReader::new(file_in);
-            std::io::copy(&mut buf_reader, &mut buf_writer)?;
-            Ok(())
-        })?;
+        // JSON state
+        if self.config.json_state_format == JsonStateFormat::Json {
+            let parts = self.buffers.json_state.finalize()?;
+            let json_state_path = path.join("json_state.json");
+            std::fs::File::create(&json_state_path)?;
+
+            let file_out = std::fs::OpenOptions::new()
+                .append(true)
+                .open(json_state_path)?;
+
+            let mut buf_writer = BufWriter::new(file_out);
+
+            parts.iter().try_for_each(|v| -> Result<()> {
+                let file_in = std::fs::File::open(v)?;
+                let mut buf_reader = BufReader::new(file_in);
+                std::io::copy(&mut buf_reader, &mut buf_writer)?;
+                Ok(())
+            })?;
+        } else {
+            let json_state_path = path.join("json_state");
+            std::fs::create_dir_all(&json_state_path)?;
+            std::fs::write(
+                json_state_path.join("index.json"),
+                serde_json::to_string(&self.step_index)?,
+            )?;
+        }
 
         // Analysis
         let analysis_path = path.join("analysis_outputs.json");
@@ -104,6 +165,12 @@ impl SimulationOutputPersistence for LocalSimulationOutputPersistence {
         let globals_path = path.join("globals.json");
         std::fs::File::create(&globals_path)?;
         std::fs::write(&globals_path, serde_json::to_string(globals)?)?;
+        if !self.buffers.globals_updates.is_empty() {
+            std::fs::write(
+                path.join("globals_updates.json"),
+                serde_json::to_string(&self.buffers.globals_updates)?,
+            )?;
+        }
 
         Ok(LocalPersistenceResult {
             persistence_path: path.canonicalize()?.to_string_lossy().to_string(),
@@ -114,6 +181,9 @@ impl SimulationOutputPersistence for LocalSimulationOutputPersistence {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalPersistenceConfig {
     pub output_folder: PathBuf,
+    /// The format the JSON state is written in
+    #[serde(default)]
+    pub json_state_format: JsonStateFormat,
 }
 
 pub struct LocalOutputPersistence {
@@ -143,6 +213,8 @@ impl OutputPersistenceCreator for LocalOutputPersistence {
             sim_id,
             buffers,
             config: self.config.clone(),
+            num_json_state_steps: 0,
+            step_index: Vec::new(),
         })
     }
 }
@@ -2,7 +2,7 @@ use stateful::global::Globals;
 
 use crate::package::simulation::{
     output::{
-        persistence::{OutputPersistenceCreator, SimulationOutputPersistence},
+        persistence::{GlobalsUpdate, OutputPersistenceCreator, SimulationOutputPersistence},
         Output,
     },
     PersistenceConfig, Result, SimulationId,
@@ -39,6 +39,10 @@ impl SimulationOutputPersistence for NoSimulationOutputPersistence {
         Ok(())
     }
 
+    async fn add_globals_update(&mut self, _update: GlobalsUpdate) -> Result<()> {
+        Ok(())
+    }
+
     async fn finalize(self, _globals: &Globals) -> Result<Self::OutputPersistenceResult> {
         Ok(())
     }
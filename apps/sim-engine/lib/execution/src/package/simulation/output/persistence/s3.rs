@@ -0,0 +1,528 @@
+//! Output persistence to an S3-compatible object store.
+//!
+//! The JSON state is streamed as a multipart upload while the simulation run is executing, so
+//! only a small amount of the output is kept in memory and nothing is written to disk. Any
+//! S3-compatible store can be used by setting an `endpoint`, e.g. a local MinIO instance.
+
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::{
+    model::{CompletedMultipartUpload, CompletedPart},
+    types::ByteStream,
+    Client, Region,
+};
+use serde::{Deserialize, Serialize};
+use stateful::global::Globals;
+
+use crate::{
+    package::{
+        experiment::{ExperimentId, ExperimentName},
+        simulation::{
+            output::{
+                persistence::{
+                    GlobalsUpdate, OutputPersistenceCreator, OutputPersistenceResult,
+                    SimulationOutputPersistence,
+                },
+                Output, OutputBuffers,
+            },
+            PersistenceConfig, SimulationId,
+        },
+    },
+    Error, Result,
+};
+
+#[derive(Serialize)]
+pub struct S3PersistenceResult {
+    pub persistence_path: String,
+}
+
+impl OutputPersistenceResult for S3PersistenceResult {
+    fn into_value(self) -> Result<(&'static str, serde_json::Value)> {
+        Ok(("s3", serde_json::Value::String(self.persistence_path)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3PersistenceConfig {
+    /// The bucket to upload the output to
+    pub bucket: String,
+    /// Prefix of the keys of every uploaded object
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// The region of the bucket. If not set, the region is read from the environment
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Custom endpoint of an S3-compatible store, e.g. `http://localhost:9000` for MinIO
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Use path-style addressing (`<endpoint>/<bucket>/<key>`), which is required by MinIO
+    #[serde(default)]
+    pub force_path_style: bool,
+}
+
+/// A multipart upload, which was started on the first uploaded part.
+struct MultipartUpload {
+    upload_id: String,
+    parts: Vec<CompletedPart>,
+}
+
+pub struct S3SimulationOutputPersistence {
+    client: Client,
+    bucket: String,
+    key_prefix: String,
+    buffers: OutputBuffers,
+    json_state_upload: Option<MultipartUpload>,
+}
+
+impl S3SimulationOutputPersistence {
+    fn json_state_key(&self) -> String {
+        format!("{}/json_state.json", self.key_prefix)
+    }
+
+    async fn upload_json_state_parts(&mut self, parts: Vec<Vec<u8>>) -> Result<()> {
+        for part in parts {
+            if let Err(err) = self.upload_json_state_part(part).await {
+                self.abort_json_state_upload().await;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    async fn upload_json_state_part(&mut self, part: Vec<u8>) -> Result<()> {
+        let key = self.json_state_key();
+        if self.json_state_upload.is_none() {
+            tracing::debug!("Starting multipart upload of {key}");
+            let output = self
+                .client
+                .create_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&key)
+                .content_type("application/json")
+                .send()
+                .await
+                .map_err(|err| Error::ObjectStore(err.to_string()))?;
+            let upload_id = output
+                .upload_id()
+                .ok_or_else(|| Error::ObjectStore(format!("Missing upload id for {key}")))?;
+            self.json_state_upload = Some(MultipartUpload {
+                upload_id: upload_id.to_string(),
+                parts: Vec::new(),
+            });
+        }
+        let upload = self
+            .json_state_upload
+            .as_mut()
+            .expect("Multipart upload was started above");
+
+        // Part numbers start at 1
+        let part_number = upload.parts.len() as i32 + 1;
+        tracing::trace!("Uploading part {part_number} of {key}");
+        let output = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(&key)
+            .upload_id(&upload.upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(part))
+            .send()
+            .await
+            .map_err(|err| Error::ObjectStore(err.to_string()))?;
+        upload.parts.push(
+            CompletedPart::builder()
+                .set_e_tag(output.e_tag().map(ToString::to_string))
+                .part_number(part_number)
+                .build(),
+        );
+        Ok(())
+    }
+
+    async fn complete_json_state_upload(&mut self) -> Result<()> {
+        let key = self.json_state_key();
+        let upload = self
+            .json_state_upload
+            .take()
+            .ok_or_else(|| Error::ObjectStore(format!("No parts were uploaded for {key}")))?;
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&key)
+            .upload_id(&upload.upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(upload.parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|err| Error::ObjectStore(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Aborts the multipart upload so the store doesn't keep the already uploaded parts around.
+    async fn abort_json_state_upload(&mut self) {
+        let key = self.json_state_key();
+        if let Some(upload) = self.json_state_upload.take() {
+            if let Err(err) = self
+                .client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&key)
+                .upload_id(&upload.upload_id)
+                .send()
+                .await
+            {
+                tracing::error!("Failed to abort multipart upload of {key}: {err}");
+            }
+        }
+    }
+
+    async fn put_json(&self, name: &str, body: Vec<u8>) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(format!("{}/{name}", self.key_prefix))
+            .content_type("application/json")
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|err| Error::ObjectStore(err.to_string()))?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SimulationOutputPersistence for S3SimulationOutputPersistence {
+    type OutputPersistenceResult = S3PersistenceResult;
+
+    async fn add_step_output(&mut self, output: Vec<Output>) -> Result<()> {
+        output.into_iter().try_for_each(|output| {
+            match output {
+                Output::AnalysisOutput(output) => {
+                    self.buffers.analysis.add(output)?;
+                }
+                Output::JsonStateOutput(output) if !output.is_persisted() => {}
+                Output::JsonStateOutput(output) => {
+                    self.buffers.json_state.append_step(output.to_json()?)?;
+                }
+            }
+            Ok(()) as Result<()>
+        })?;
+
+        let parts = self.buffers.json_state.take_complete_parts();
+        self.upload_json_state_parts(parts).await
+    }
+
+    async fn add_globals_update(&mut self, update: GlobalsUpdate) -> Result<()> {
+        self.buffers.globals_updates.push(update);
+        Ok(())
+    }
+
+    async fn finalize(mut self, globals: &Globals) -> Result<Self::OutputPersistenceResult> {
+        tracing::trace!("Finalizing output");
+        // JSON state
+        let parts = self.buffers.json_state.finalize_in_memory();
+        self.upload_json_state_parts(parts).await?;
+        if let Err(err) = self.complete_json_state_upload().await {
+            self.abort_json_state_upload().await;
+            return Err(err);
+        }
+
+        // Analysis
+        self.put_json(
+            "analysis_outputs.json",
+            serde_json::to_vec(&self.buffers.analysis)?,
+        )
+        .await?;
+
+        // Globals
+        self.put_json("globals.json", serde_json::to_vec(globals)?)
+            .await?;
+        if !self.buffers.globals_updates.is_empty() {
+            self.put_json(
+                "globals_updates.json",
+                serde_json::to_vec(&self.buffers.globals_updates)?,
+            )
+            .await?;
+        }
+
+        Ok(S3PersistenceResult {
+            persistence_path: format!("s3://{}/{}", self.bucket, self.key_prefix),
+        })
+    }
+}
+
+pub struct S3OutputPersistence {
+    pub project_name: String,
+    pub experiment_name: ExperimentName,
+    pub experiment_id: ExperimentId,
+    pub config: S3PersistenceConfig,
+    client: Client,
+}
+
+impl S3OutputPersistence {
+    /// Creates the client for the configured store.
+    ///
+    /// Credentials are read from the environment, e.g. `AWS_ACCESS_KEY_ID` and
+    /// `AWS_SECRET_ACCESS_KEY`, or from the AWS profile.
+    pub async fn new(
+        project_name: String,
+        experiment_name: ExperimentName,
+        experiment_id: ExperimentId,
+        config: S3PersistenceConfig,
+    ) -> S3OutputPersistence {
+        let region = RegionProviderChain::first_try(config.region.clone().map(Region::new))
+            .or_default_provider();
+        let shared_config = aws_config::from_env().region(region).load().await;
+
+        let mut client_config = aws_sdk_s3::config::Builder::from(&shared_config)
+            .force_path_style(config.force_path_style);
+        if let Some(endpoint) = &config.endpoint {
+            client_config = client_config.endpoint_url(endpoint);
+        }
+
+        S3OutputPersistence {
+            project_name,
+            experiment_name,
+            experiment_id,
+            config,
+            client: Client::from_conf(client_config.build()),
+        }
+    }
+}
+
+impl OutputPersistenceCreator for S3OutputPersistence {
+    type SimulationOutputPersistence = S3SimulationOutputPersistence;
+
+    fn new_simulation(
+        &self,
+        sim_id: SimulationId,
+        persistence_config: &PersistenceConfig,
+    ) -> Result<Self::SimulationOutputPersistence> {
+        let buffers = OutputBuffers::new(
+            &self.experiment_id,
+            sim_id,
+            &persistence_config.output_config,
+        )?;
+        let key_prefix = [
+            self.config
+                .prefix
+                .as_deref()
+                .unwrap_or_default()
+                .trim_matches('/'),
+            &self.project_name,
+            self.experiment_name.as_str(),
+            &self.experiment_id.to_string(),
+            &sim_id.to_string(),
+        ]
+        .into_iter()
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+
+        Ok(S3SimulationOutputPersistence {
+            client: self.client.clone(),
+            bucket: self.config.bucket.clone(),
+            key_prefix,
+            buffers,
+            json_state_upload: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::{Arc, Mutex},
+    };
+
+    use aws_sdk_s3::{Config, Credentials};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        package::simulation::{output::OutputPackageName, OutputPackagesSimConfig, PackageName},
+        test_server::{self, Request, Response},
+    };
+
+    /// Objects stored by the [`S3StandIn`].
+    #[derive(Default)]
+    struct Store {
+        objects: HashMap<String, Vec<u8>>,
+        /// Uploaded parts by key and part number
+        parts: HashMap<String, BTreeMap<u32, Vec<u8>>>,
+        aborted: Vec<String>,
+    }
+
+    /// A local stand-in for S3, which supports path-style `PutObject` and multipart uploads.
+    #[derive(Clone, Default)]
+    struct S3StandIn {
+        store: Arc<Mutex<Store>>,
+    }
+
+    impl S3StandIn {
+        async fn serve(self) -> String {
+            test_server::serve(move |request| self.respond(request)).await
+        }
+
+        fn respond(&self, request: Request) -> Response {
+            let Request {
+                method,
+                path: key,
+                query,
+                body,
+            } = request;
+            let mut store = self.store.lock().unwrap();
+            let (status, response) = match (
+                method.as_str(),
+                query.get("partNumber"),
+                query.contains_key("uploadId"),
+            ) {
+                ("POST", ..) if query.contains_key("uploads") => (
+                    "200 OK",
+                    format!(
+                        "<InitiateMultipartUploadResult><Key>{key}</Key><UploadId>upload</\
+                         UploadId></InitiateMultipartUploadResult>"
+                    ),
+                ),
+                ("PUT", Some(part_number), true) => {
+                    store
+                        .parts
+                        .entry(key)
+                        .or_default()
+                        .insert(part_number.parse().unwrap(), body);
+                    ("200 OK", String::new())
+                }
+                ("POST", None, true) => {
+                    let parts = store.parts.remove(&key).unwrap_or_default();
+                    let object = parts.into_values().collect::<Vec<_>>().concat();
+                    store.objects.insert(key.clone(), object);
+                    (
+                        "200 OK",
+                        format!(
+                            "<CompleteMultipartUploadResult><Key>{key}</Key></\
+                             CompleteMultipartUploadResult>"
+                        ),
+                    )
+                }
+                ("DELETE", None, true) => {
+                    store.parts.remove(&key);
+                    store.aborted.push(key);
+                    ("204 No Content", String::new())
+                }
+                ("PUT", None, false) => {
+                    store.objects.insert(key, body);
+                    ("200 OK", String::new())
+                }
+                _ => ("400 Bad Request", String::new()),
+            };
+            Response::new(status, response).header("ETag", "\"etag\"")
+        }
+
+        fn object(&self, key: &str) -> Option<serde_json::Value> {
+            let store = self.store.lock().unwrap();
+            store
+                .objects
+                .get(key)
+                .map(|object| serde_json::from_slice(object).unwrap())
+        }
+    }
+
+    fn creator(endpoint: String, experiment_id: ExperimentId) -> S3OutputPersistence {
+        let config = S3PersistenceConfig {
+            bucket: "bucket".to_string(),
+            prefix: Some("/outputs/".to_string()),
+            region: Some("us-east-1".to_string()),
+            endpoint: Some(endpoint.clone()),
+            force_path_style: true,
+        };
+        let client_config = Config::builder()
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("test", "test", None, None, "test"))
+            .endpoint_url(endpoint)
+            .force_path_style(true)
+            .build();
+        S3OutputPersistence {
+            project_name: "project".to_string(),
+            experiment_name: ExperimentName::from("experiment".to_string()),
+            experiment_id,
+            config,
+            client: Client::from_conf(client_config),
+        }
+    }
+
+    fn persistence_config() -> PersistenceConfig {
+        PersistenceConfig {
+            output_config: OutputPackagesSimConfig {
+                map: HashMap::from([(
+                    PackageName::Output(OutputPackageName::Analysis),
+                    json!({ "outputs": {}, "manifest": "" }),
+                )]),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn uploads_json_state_in_parts() {
+        let store = S3StandIn::default();
+        let endpoint = store.clone().serve().await;
+        let experiment_id = ExperimentId::generate();
+        let creator = creator(endpoint, experiment_id);
+        let mut persistence = creator
+            .new_simulation(SimulationId::new(1), &persistence_config())
+            .unwrap();
+
+        // Every step has a third of the size of a part, so the JSON state is uploaded in 3 parts
+        let step = json!({ "agents": "a".repeat(5242880 / 3) });
+        for _ in 0..7 {
+            persistence.buffers.json_state.append_step(&step).unwrap();
+            let parts = persistence.buffers.json_state.take_complete_parts();
+            persistence.upload_json_state_parts(parts).await.unwrap();
+        }
+        assert!(persistence.json_state_upload.is_some());
+
+        let result = persistence
+            .finalize(&Globals(json!({ "population": 7 })))
+            .await
+            .unwrap();
+
+        let prefix = format!("outputs/project/experiment/{experiment_id}/1");
+        assert_eq!(result.persistence_path, format!("s3://bucket/{prefix}"));
+        assert_eq!(
+            store.object(&format!("/bucket/{prefix}/json_state.json")),
+            Some(json!(vec![step; 7]))
+        );
+        assert_eq!(
+            store.object(&format!("/bucket/{prefix}/globals.json")),
+            Some(json!({ "population": 7 }))
+        );
+        assert!(store
+            .object(&format!("/bucket/{prefix}/analysis_outputs.json"))
+            .is_some());
+        assert!(store
+            .object(&format!("/bucket/{prefix}/globals_updates.json"))
+            .is_none());
+        assert!(store.store.lock().unwrap().aborted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn uploads_empty_run() {
+        let store = S3StandIn::default();
+        let endpoint = store.clone().serve().await;
+        let experiment_id = ExperimentId::generate();
+        let creator = creator(endpoint, experiment_id);
+        let persistence = creator
+            .new_simulation(SimulationId::new(2), &persistence_config())
+            .unwrap();
+
+        persistence.finalize(&Globals(json!({}))).await.unwrap();
+
+        assert_eq!(
+            store.object(&format!(
+                "/bucket/outputs/project/experiment/{experiment_id}/2/json_state.json"
+            )),
+            Some(json!([]))
+        );
+    }
+}
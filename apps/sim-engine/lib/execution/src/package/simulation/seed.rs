@@ -0,0 +1,165 @@
+//! Seeds of the random number generators used in a simulation run.
+//!
+//! A seeded experiment derives all of its random number generators from a single [`Seed`]:
+//!
+//! - the experiment plan is sampled with the experiment seed,
+//! - every simulation run derives its seed from the experiment seed and its [`SimulationId`],
+//! - every behavior executed on an agent derives its stream from the simulation seed, the current
+//!   step, the [`AgentId`], and the index of the behavior in the behavior chain of the agent,
+//! - agents created without an `agent_id` get an id derived from the simulation seed, the step
+//!   they were created at, the agent which created them, and a counter.
+//!
+//! As the streams are bound to agents rather than to the workers executing them, the results of a
+//! seeded run don't depend on how the agents are distributed over the workers.
+
+use std::fmt;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use stateful::agent::AgentId;
+
+use crate::package::simulation::SimulationId;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Seed {
+    seed: u64,
+}
+
+impl Seed {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.seed
+    }
+
+    /// Derives the seed of an independent stream identified by `stream`.
+    #[must_use]
+    pub fn derive(self, stream: u64) -> Self {
+        Self::new(mix(self.seed.wrapping_add(mix(stream))))
+    }
+
+    /// Derives the seed of the simulation run with the given `id`.
+    #[must_use]
+    pub fn for_simulation(self, id: SimulationId) -> Self {
+        self.derive(u64::from(id.as_u32()))
+    }
+
+    /// Derives the seed of the agent with `agent_id` at `step`.
+    #[must_use]
+    pub fn for_agent(self, step: usize, agent_id: &AgentId) -> Self {
+        let id = u128::from_le_bytes(*agent_id.as_bytes());
+        self.derive(step as u64)
+            .derive((id >> 64) as u64)
+            .derive(id as u64)
+    }
+
+    /// Derives the id of the `index`-th agent without an `agent_id` in the initial state.
+    #[must_use]
+    pub fn initial_agent_id(self, index: usize) -> AgentId {
+        random_agent_id(
+            &mut self
+                .derive(INITIAL_AGENT_ID_STREAM)
+                .derive(index as u64)
+                .rng(),
+        )
+    }
+
+    /// Derives the id of the `index`-th agent without an `agent_id`, which was created by `creator`
+    /// at `step`.
+    ///
+    /// Agents, which are not created by another agent, have no `creator`.
+    #[must_use]
+    pub fn created_agent_id(self, step: usize, creator: Option<&AgentId>, index: usize) -> AgentId {
+        let mut seed = self.derive(CREATED_AGENT_ID_STREAM).derive(step as u64);
+        if let Some(creator) = creator {
+            let id = u128::from_le_bytes(*creator.as_bytes());
+            seed = seed.derive((id >> 64) as u64).derive(id as u64);
+        }
+        random_agent_id(&mut seed.derive(index as u64).rng())
+    }
+
+    /// Creates a random number generator, which is seeded with this seed.
+    pub fn rng(self) -> StdRng {
+        StdRng::seed_from_u64(self.seed)
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.seed, fmt)
+    }
+}
+
+/// Streams of the agent ids, which are separate from the streams of the simulation runs.
+const INITIAL_AGENT_ID_STREAM: u64 = u64::MAX;
+const CREATED_AGENT_ID_STREAM: u64 = u64::MAX - 1;
+
+/// Generates the id of a new agent from `rng`, so seeded simulation runs create the same agents.
+pub(crate) fn random_agent_id<R: Rng + ?Sized>(rng: &mut R) -> AgentId {
+    AgentId::from_bytes(
+        uuid::Builder::from_random_bytes(rng.gen())
+            .into_uuid()
+            .into_bytes(),
+    )
+}
+
+/// The finalizer of SplitMix64, which maps similar inputs, e.g. consecutive ids, to unrelated
+/// outputs.
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn derived_streams_are_reproducible() {
+        let seed = Seed::new(42);
+        let agent_id = AgentId::generate();
+
+        let sample = |seed: Seed| seed.rng().gen::<u64>();
+        assert_eq!(
+            sample(seed.for_agent(3, &agent_id)),
+            sample(seed.for_agent(3, &agent_id))
+        );
+        assert_ne!(
+            sample(seed.for_agent(3, &agent_id)),
+            sample(seed.for_agent(4, &agent_id))
+        );
+        assert_ne!(
+            seed.for_simulation(SimulationId::new(1)),
+            seed.for_simulation(SimulationId::new(2))
+        );
+    }
+
+    #[test]
+    fn derived_agent_ids_are_reproducible() {
+        let seed = Seed::new(42);
+        let creator = AgentId::generate();
+
+        assert_eq!(seed.initial_agent_id(0), seed.initial_agent_id(0));
+        assert_ne!(seed.initial_agent_id(0), seed.initial_agent_id(1));
+        assert_eq!(
+            seed.created_agent_id(3, Some(&creator), 0),
+            seed.created_agent_id(3, Some(&creator), 0)
+        );
+        assert_ne!(
+            seed.created_agent_id(3, Some(&creator), 0),
+            seed.created_agent_id(3, Some(&creator), 1)
+        );
+        assert_ne!(
+            seed.created_agent_id(3, Some(&creator), 0),
+            seed.created_agent_id(4, Some(&creator), 0)
+        );
+        assert_ne!(seed.created_agent_id(0, None, 0), seed.initial_agent_id(0));
+    }
+}
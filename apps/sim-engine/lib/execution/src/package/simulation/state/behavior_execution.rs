@@ -7,6 +7,8 @@ mod fields;
 mod message;
 mod reset_index_col;
 mod task;
+mod typescript;
+mod wasm;
 
 use std::sync::Arc;
 
@@ -23,20 +25,26 @@ use stateful::{
 use tracing::Span;
 
 pub use self::{
-    behavior::{Behavior, BehaviorKeyJsonError, BehaviorMap},
+    behavior::{Behavior, BehaviorKeyJsonError, BehaviorKeys, BehaviorMap},
     message::ExecuteBehaviorsTaskMessage,
     task::ExecuteBehaviorsTask,
+    typescript::{original_position, transpile_typescript, SourceSegments, TranspiledTypeScript},
+    wasm::encode_wasm,
 };
 use self::{
     config::{exp_init_message, BehaviorIds},
-    fields::{BEHAVIOR_IDS_FIELD_NAME, BEHAVIOR_INDEX_FIELD_NAME},
     reset_index_col::reset_index_col,
 };
+pub(crate) use self::{
+    config::{BehaviorDescription, BehaviorId, SimSetupMessage},
+    fields::{BEHAVIORS_FIELD_NAME, BEHAVIOR_IDS_FIELD_NAME, BEHAVIOR_INDEX_FIELD_NAME},
+    wasm::decode_wasm,
+};
 use crate::{
     package::simulation::{
         state::{StatePackage, StatePackageCreator, StatePackageName, StateTask},
         Package, PackageComms, PackageCreator, PackageCreatorConfig, PackageInitConfig,
-        PackageName, PackageTask,
+        PackageName, PackageTask, Seed,
     },
     runner::Language,
     task::{ActiveTask, TaskSharedStoreBuilder},
@@ -131,6 +139,7 @@ impl StatePackageCreator for BehaviorExecutionCreator {
             behavior_ids_col_index,
             behavior_ids_col_data_types,
             behavior_index_col_index,
+            seed: config.seed,
             comms,
         }))
     }
@@ -151,10 +160,18 @@ pub struct BehaviorExecution {
     behavior_ids_col_index: usize,
     behavior_ids_col_data_types: [arrow2::datatypes::DataType; 3],
     behavior_index_col_index: usize,
+    seed: Option<Seed>,
     comms: PackageComms,
 }
 
-impl Package for BehaviorExecution {}
+impl Package for BehaviorExecution {
+    fn simulation_setup_message(&self) -> Result<serde_json::Value> {
+        let msg = SimSetupMessage {
+            seed: self.seed.map(|seed| seed.to_string()),
+        };
+        Ok(serde_json::to_value(msg)?)
+    }
+}
 
 impl BehaviorExecution {
     /// Iterates over all "behaviors" fields of agents and writes them into their "behaviors" field.
@@ -7,7 +7,7 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-pub use self::{error::BehaviorKeyJsonError, field::BehaviorMap};
+pub use self::{error::BehaviorKeyJsonError, field::BehaviorMap, keys::BehaviorKeys};
 use crate::{runner::Language, Result};
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -23,6 +23,9 @@ pub struct Behavior {
     pub shortnames: Vec<String>,
     /// Source code for the behaviors
     pub behavior_src: Option<String>,
+    /// Source map from `behavior_src` to the original source, if the behavior was transpiled
+    #[serde(default)]
+    pub source_map: Option<String>,
     /// Behavior key definition for this behavior
     pub behavior_keys_src: Option<String>,
 }
@@ -47,6 +50,14 @@ impl fmt::Debug for Behavior {
                     &"None"
                 },
             )
+            .field(
+                "source_map",
+                if self.source_map.is_some() {
+                    &"Some(...)"
+                } else {
+                    &"None"
+                },
+            )
             .field(
                 "behavior_keys_src",
                 if self.behavior_keys_src.is_some() {
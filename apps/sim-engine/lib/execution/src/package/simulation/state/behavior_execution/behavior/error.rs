@@ -47,6 +47,16 @@ pub enum BehaviorKeyJsonError {
          \"fixed_size_list\"-type sub-types"
     )]
     InvalidKeyLengthType(String),
+    #[error(
+        "Expected key with name {0} to have a \"categories\" sub-field containing a non-empty \
+         array of unique strings in one of its \"categorical\"-type sub-types"
+    )]
+    InvalidKeyCategoriesType(String),
+    #[error(
+        "Key with name {0} is nested inside a \"struct\", \"list\" or \"fixed_size_list\", but \
+         \"categorical\" keys can only be used at the top level"
+    )]
+    NestedCategoricalKey(String),
     #[error("Invalid built-in key name {0}")]
     InvalidBuiltInKeyName(String),
     #[error("Dynamic access flag must be boolean if present")]
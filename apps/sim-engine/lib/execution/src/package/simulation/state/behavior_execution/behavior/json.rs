@@ -6,6 +6,9 @@ pub(super) enum BaseKeyType {
     String,
     Boolean,
     Number,
+    Integer,
+    Timestamp,
+    Categorical,
     Struct,
     List,
     FixedSizeList,
@@ -20,6 +23,9 @@ impl TryFrom<&str> for BaseKeyType {
             "string" => Ok(BaseKeyType::String),
             "boolean" => Ok(BaseKeyType::Boolean),
             "number" => Ok(BaseKeyType::Number),
+            "integer" => Ok(BaseKeyType::Integer),
+            "timestamp" => Ok(BaseKeyType::Timestamp),
+            "categorical" => Ok(BaseKeyType::Categorical),
             "struct" => Ok(BaseKeyType::Struct),
             "list" => Ok(BaseKeyType::List),
             "fixed_size_list" => Ok(BaseKeyType::FixedSizeList),
@@ -61,7 +67,29 @@ pub(super) fn field_type_from_json(name: &str, source: &serde_json::Value) -> Re
                 BaseKeyType::String => FieldTypeVariant::String,
                 BaseKeyType::Boolean => FieldTypeVariant::Boolean,
                 BaseKeyType::Number => FieldTypeVariant::Number,
+                BaseKeyType::Integer => FieldTypeVariant::Integer,
+                BaseKeyType::Timestamp => FieldTypeVariant::Timestamp,
                 BaseKeyType::Any => FieldTypeVariant::AnyType,
+                BaseKeyType::Categorical => {
+                    let categories = match map.get("categories") {
+                        Some(serde_json::Value::Array(values)) => values
+                            .iter()
+                            .map(|value| value.as_str().map(str::to_string))
+                            .collect::<Option<Vec<_>>>(),
+                        _ => None,
+                    }
+                    .filter(|categories| {
+                        !categories.is_empty()
+                            && categories
+                                .iter()
+                                .enumerate()
+                                .all(|(i, category)| !categories[..i].contains(category))
+                    })
+                    .ok_or_else(|| {
+                        BehaviorKeyJsonError::InvalidKeyCategoriesType(name.to_string())
+                    })?;
+                    FieldTypeVariant::Categorical(categories)
+                }
                 BaseKeyType::Struct => {
                     let mut children = vec![];
                     match map.get("fields").ok_or_else(|| {
@@ -69,7 +97,9 @@ pub(super) fn field_type_from_json(name: &str, source: &serde_json::Value) -> Re
                     })? {
                         serde_json::Value::Object(map) => {
                             for (k, v) in map {
-                                children.push(field_spec_from_json(k.as_ref(), v)?);
+                                let child = field_spec_from_json(k.as_ref(), v)?;
+                                ensure_not_categorical(k, &child.field_type)?;
+                                children.push(child);
                             }
                             Ok(())
                         }
@@ -85,6 +115,7 @@ pub(super) fn field_type_from_json(name: &str, source: &serde_json::Value) -> Re
                         BehaviorKeyJsonError::InvalidKeyChildType(name.to_string())
                     })?;
                     let child_key_type = field_type_from_json(name, child_source)?;
+                    ensure_not_categorical(name, &child_key_type)?;
                     FieldTypeVariant::VariableLengthArray(Box::new(child_key_type))
                 }
                 BaseKeyType::FixedSizeList => {
@@ -92,6 +123,7 @@ pub(super) fn field_type_from_json(name: &str, source: &serde_json::Value) -> Re
                         BehaviorKeyJsonError::InvalidKeyChildType(name.to_string())
                     })?;
                     let child_key_type = field_type_from_json(name, child_source)?;
+                    ensure_not_categorical(name, &child_key_type)?;
                     let len = match map.get("length").ok_or_else(|| {
                         BehaviorKeyJsonError::InvalidKeyLengthType(name.to_string())
                     })? {
@@ -117,3 +149,13 @@ pub(super) fn field_type_from_json(name: &str, source: &serde_json::Value) -> Re
         _ => Err(BehaviorKeyJsonError::ExpectedKeyObject(name.to_string()).into()),
     }
 }
+
+/// The categories of a categorical field are stored in the schema metadata, which is only
+/// available for top-level fields.
+fn ensure_not_categorical(name: &str, field_type: &FieldType) -> Result<()> {
+    if matches!(field_type.variant, FieldTypeVariant::Categorical(_)) {
+        Err(BehaviorKeyJsonError::NestedCategoricalKey(name.to_string()).into())
+    } else {
+        Ok(())
+    }
+}
@@ -3,7 +3,11 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    package::simulation::state::behavior_execution::BehaviorMap, runner::Language, Error, Result,
+    package::simulation::state::behavior_execution::{
+        typescript::decode_source_map, BehaviorMap, SourceSegments,
+    },
+    runner::Language,
+    Error, Result,
 };
 
 #[derive(Serialize, Deserialize)]
@@ -12,6 +16,8 @@ pub struct BehaviorDescription {
     pub name: String,
     pub short_names: Vec<String>,
     pub source: String,
+    /// Decoded source map from `source` to the original source, if the behavior was transpiled
+    pub source_map: Option<SourceSegments>,
     pub required_field_keys: Vec<String>,
     pub language: Language,
     pub dyn_access: bool,
@@ -21,6 +27,10 @@ pub struct BehaviorDescription {
 pub struct BehaviorId(u16, u16);
 
 impl BehaviorId {
+    pub(crate) fn new(lang_index: u16, lang_behavior_index: u16) -> Self {
+        Self(lang_index, lang_behavior_index)
+    }
+
     pub fn lang_index(&self) -> u16 {
         self.0
     }
@@ -47,7 +57,7 @@ impl BehaviorIds {
             let lang_index = Language::from_file_name(&shared.name)
                 .map_err(|_| Error::from(format!("Invalid behavior name: \"{}\"", &shared.name)))?
                 .as_index();
-            let behavior_id = BehaviorId(lang_index as u16, lang_counts[lang_index]);
+            let behavior_id = BehaviorId::new(lang_index as u16, lang_counts[lang_index]);
             lang_counts[lang_index] += 1;
 
             index_to_name.insert(behavior_id, shared.name.clone());
@@ -98,6 +108,15 @@ impl BehaviorName {
     }
 }
 
+/// The message sent to the workers when a simulation run is started.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimSetupMessage {
+    /// The seed of the simulation run, if it's seeded.
+    ///
+    /// The seed is sent as string as JavaScript numbers can't represent every `u64`.
+    pub seed: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendableBehaviorKeys {
     field_names: Vec<String>,
@@ -124,10 +143,14 @@ pub fn exp_init_message(
                 .name_to_index
                 .get(shared.name.as_bytes())
                 .ok_or_else(|| Error::from("Couldn't get index from behavior name"))?;
-            let source = shared
-                .behavior_src
-                .clone()
-                .ok_or_else(|| Error::from("SharedBehavior didn't have an attached source"))?;
+            let source = match (&shared.behavior_src, language) {
+                (Some(source), _) => source.clone(),
+                // Built-in Rust behaviors are compiled into the engine and don't need a source.
+                (None, Language::Rust) => String::new(),
+                (None, _) => {
+                    return Err(Error::from("SharedBehavior didn't have an attached source"));
+                }
+            };
             let required_field_keys = keys
                 .inner
                 .iter()
@@ -144,6 +167,11 @@ pub fn exp_init_message(
                 name: shared.name.to_string(),
                 short_names: shared.shortnames.clone(),
                 source,
+                source_map: shared
+                    .source_map
+                    .as_deref()
+                    .map(decode_source_map)
+                    .transpose()?,
                 required_field_keys,
                 language,
                 dyn_access: keys.dyn_access,
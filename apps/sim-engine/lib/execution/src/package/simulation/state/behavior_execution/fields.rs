@@ -11,9 +11,9 @@ use crate::{
     Result,
 };
 
-pub(super) const BEHAVIORS_FIELD_NAME: &str = "behaviors";
-pub(super) const BEHAVIOR_INDEX_FIELD_NAME: &str = "behavior_index";
-pub(super) const BEHAVIOR_IDS_FIELD_NAME: &str = "behavior_ids";
+pub(crate) const BEHAVIORS_FIELD_NAME: &str = "behaviors";
+pub(crate) const BEHAVIOR_INDEX_FIELD_NAME: &str = "behavior_index";
+pub(crate) const BEHAVIOR_IDS_FIELD_NAME: &str = "behavior_ids";
 
 fn get_behaviors_field_spec(field_spec_creator: &RootFieldSpecCreator) -> Result<RootFieldSpec> {
     let field_type = FieldType::new(
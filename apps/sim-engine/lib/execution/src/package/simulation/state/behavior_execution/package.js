@@ -1,3 +1,5 @@
+import * as hash_util from "./lib/execution/src/runner/javascript/hash_util.js";
+
 // TODO: Propagate field specs to runners and use in state and context objects
 const BEHAVIOR_INDEX_FIELD_KEY = "_PRIVATE_7_behavior_index";
 const BEHAVIOR_IDS_FIELD_KEY = "_PRIVATE_7_behavior_ids";
@@ -11,7 +13,29 @@ const BEHAVIOR_IDS_FIELD_KEY = "_PRIVATE_7_behavior_ids";
 // middle of running the behavior execution package, but `__behaviors`
 // contains behavior ids and shouldn't be modified.
 
-const prepare_user_trace = (error, trace) => {
+/// Maps the 1-based `line` and `column` of the transpiled code of a behavior to its source with
+/// the segments of its `source_map`, which is decoded by the engine. Every line of the transpiled
+/// code has a list of `[column, source line, source column]` segments (all 0-based), sorted by
+/// column. Positions before the first segment of a line are mapped to the first segment,
+/// positions in lines without segments are not mapped.
+const original_position = (source_map, line, column) => {
+  const segments = source_map[line - 1];
+  if (!segments || segments.length === 0) {
+    return { line: line, col: column };
+  }
+  let segment = segments[0];
+  for (var i = 1; i < segments.length && segments[i][0] <= column - 1; ++i) {
+    segment = segments[i];
+  }
+  return { line: segment[1] + 1, col: segment[2] + 1 };
+};
+
+/// Frames of behavior code have no file name, as behaviors are loaded with the `Function`
+/// constructor, so they are reported with the name of the behavior instead. Positions in
+/// transpiled behaviors, i.e. TypeScript behaviors, are mapped back to their source with the
+/// `source_map` of the behavior, so the line and column always refer to the file of the
+/// behavior.
+const prepare_user_trace = (error, trace, behavior) => {
   let behavior_index = -1;
   for (var i = trace.length - 1; i >= 0; --i) {
     if (trace[i].isEval() && trace[i].getFunctionName() === "behavior") {
@@ -32,10 +56,22 @@ const prepare_user_trace = (error, trace) => {
   const frames = [];
   for (var i = 0; i < trace.length; ++i) {
     var t = trace[i];
+    let position = { line: t.getLineNumber(), col: t.getColumnNumber() };
+    if (t.isEval()) {
+      // The `Function` constructor adds two lines in front of the behavior code.
+      position.line -= 2;
+      if (behavior.source_map) {
+        position = original_position(
+          behavior.source_map,
+          position.line,
+          position.col,
+        );
+      }
+    }
     frames[i] = {
-      file: t.getFileName(),
-      // Behavior line numbers are off by 2 somehow.
-      line: t.getLineNumber() - (i === behavior_index ? 2 : 0),
+      file: t.isEval() ? behavior.name : t.getFileName(),
+      line: position.line,
+      col: position.col,
       fn: t.getFunctionName(),
     };
   }
@@ -46,8 +82,36 @@ const prepare_user_trace = (error, trace) => {
   };
 };
 
-/// `behavior_descs` should be a list of objects with fields `id`, `name`, `source`, `columns`,
-/// `language` and `dyn_access`.
+/// Formats an error thrown by `behavior` like a stack trace.
+const format_user_error = (error, behavior) => {
+  Error.prepareStackTrace = (error, trace) =>
+    prepare_user_trace(error, trace, behavior);
+  const trace = error instanceof Error ? error.stack : undefined;
+  if (!trace || typeof trace === "string") {
+    // The stack was already formatted or the behavior threw something other than an `Error`.
+    return trace || behavior.name + ": " + String(error);
+  }
+
+  const lines = [trace.msg];
+  for (var i = 0; i < trace.frames.length; ++i) {
+    const frame = trace.frames[i];
+    lines.push(
+      "    at " +
+        (frame.fn || "<anonymous>") +
+        " (" +
+        frame.file +
+        ":" +
+        frame.line +
+        ":" +
+        frame.col +
+        ")",
+    );
+  }
+  return lines.join("\n");
+};
+
+/// `behavior_descs` should be a list of objects with fields `id`, `name`, `source`, `source_map`,
+/// `columns`, `language` and `dyn_access`.
 const load_behaviors = (experiment, behavior_descs) => {
   experiment.logged = "";
   const console = new Proxy(
@@ -81,6 +145,7 @@ const load_behaviors = (experiment, behavior_descs) => {
     }
 
     const code = desc.source;
+    const source_map = desc.source_map || null;
     let fn;
     try {
       fn = new Function(
@@ -88,10 +153,14 @@ const load_behaviors = (experiment, behavior_descs) => {
         "hstd",
         "console",
         `${code}\nreturn behavior`,
-      )(hash_stdlib, hash_stdlib, console);
+      )(stdlib, stdlib, console);
     } catch (e) {
       // Catch behavior code syntax errors and rethrow.
-      Error.prepareStackTrace = prepare_user_trace;
+      Error.prepareStackTrace = (error, trace) =>
+        prepare_user_trace(error, trace, {
+          name: desc.name,
+          source_map: source_map,
+        });
       const trace = e.stack;
       trace.msg =
         "Couldn't load behavior (NAME " +
@@ -113,6 +182,7 @@ const load_behaviors = (experiment, behavior_descs) => {
     behaviors[desc.id] = {
       fn: fn,
       name: desc.name,
+      source_map: source_map,
       required_col_names: desc.columns,
       dyn_access: desc.dyn_access,
       language: desc.language,
@@ -143,6 +213,45 @@ export const start_experiment = (
   load_behaviors(experiment, init_message);
 };
 
+/// `init_message.seed` is the seed of the simulation run as string or `null` if it isn't seeded.
+export const start_sim = (experiment, sim, init_message, init_context) => {
+  sim.seed = init_message.seed;
+  if (sim.seed !== null) {
+    hash_stdlib.setSeed(sim.seed);
+  }
+};
+
+/// In seeded simulation runs, the ids returned by `generateAgentID` are derived from this key and
+/// the number of ids the behavior generated before. `null` if the simulation run isn't seeded.
+let agent_id_key = null;
+let num_agent_ids = 0;
+
+const generate_agent_id = () =>
+  agent_id_key === null
+    ? hash_stdlib.generateAgentID()
+    : hash_util.uuid_from_key(agent_id_key + ":" + num_agent_ids++);
+
+/// `hash_stdlib` as passed to behaviors
+const stdlib = Object.freeze({
+  ...hash_stdlib,
+  generateAgentID: generate_agent_id,
+});
+
+/// Seeds `hash_stdlib.random` and `generateAgentID` for the behavior at `i_behavior` of the
+/// agent's behavior chain, so every behavior of a seeded simulation run gets its own reproducible
+/// stream.
+const seed_behavior = (sim, step, agent_state, i_behavior) => {
+  if (sim.seed === null) {
+    agent_id_key = null;
+    return;
+  }
+  const key =
+    sim.seed + ":" + step + ":" + agent_state.agent_id + ":" + i_behavior;
+  hash_stdlib.setSeed(key);
+  agent_id_key = key + ":agent_id";
+  num_agent_ids = 0;
+};
+
 // Fill an array with a default value until its length is 3
 const fill3 = (arr, val) => {
   while (arr.length < 3) {
@@ -191,6 +300,7 @@ export const run_task = (
   let agent_state = null;
   let agent_ctx = null;
 
+  const step = group_context.step();
   const n_agents_in_group = group_state.n_agents();
   for (var i_agent = 0; i_agent < n_agents_in_group; ++i_agent) {
     // TODO: Reuse `agent_state` objects. When using the old `agent_state`, the indices for behaviors are borked
@@ -220,13 +330,14 @@ export const run_task = (
       }
 
       agent_state.set_dynamic_access(behavior.dyn_access);
+      seed_behavior(sim, step, agent_state, i_behavior);
       try {
         behavior.fn(agent_state, agent_ctx);
         postprocess(agent_state);
       } catch (e) {
-        Error.prepareStackTrace = prepare_user_trace;
-        const trace = e.stack;
-        throw Error(JSON.stringify(trace));
+        return {
+          user_errors: [format_user_error(e, behavior)],
+        };
       }
 
       // Increment the behavior index to point to the next one to be executed
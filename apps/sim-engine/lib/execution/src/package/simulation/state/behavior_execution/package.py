@@ -33,7 +33,7 @@ def _load_behaviors(behavior_descs):
 
         try:
             # behavior_globals should contain a callable `behavior` if the user's code is correct
-            behavior_globals = {}
+            behavior_globals = {"hash_stdlib": hash_stdlib, "hstd": hash_stdlib}
             bytecode = compile(desc['source'], desc['name'], "exec")
             exec(bytecode, behavior_globals)
             behavior_fn = behavior_globals.get("behavior")
@@ -76,7 +76,12 @@ def start_experiment(experiment, init_message, _experiment_context):
     }
 
 
+# `init_message['seed']` is the seed of the simulation run as string or `None` if it isn't seeded.
 def start_sim(experiment, sim, init_message, init_context):
+    sim['seed'] = init_message['seed']
+    if sim['seed'] is not None:
+        hash_stdlib.set_seed(sim['seed'])
+
     loaders = {
         BEHAVIOR_INDEX_FIELD_KEY: hash_util.load_full
     }
@@ -85,6 +90,19 @@ def start_sim(experiment, sim, init_message, init_context):
     }
 
 
+# Seeds `hash_stdlib.random` and `hash_stdlib.generate_agent_id` for the behavior at `i_behavior`
+# of the agent's behavior chain, so every behavior of a seeded simulation run gets its own
+# reproducible stream.
+def _seed_behavior(sim, step, agent_state, i_behavior):
+    if sim['seed'] is None:
+        hash_stdlib.seed_agent_ids(None)
+        return
+
+    key = f"{sim['seed']}:{step}:{agent_state.agent_id}:{i_behavior}"
+    hash_stdlib.set_seed(key)
+    hash_stdlib.seed_agent_ids(f"{key}:agent_id")
+
+
 def _format_behavior_error(behavior_name, exc_info):
     n_pkg_fns = 2
     return f"Behavior `{behavior_name}` error: {traceback.format_exception(*exc_info)[n_pkg_fns:]}"
@@ -136,10 +154,11 @@ def _postprocess(agent_state):
 # starting after the last behavior already executed (during this step / more generally
 # behavior execution package call) and stopping when all behaviors are executed or
 # the next behavior is in a different language (i.e. not Python).
-def run_task(experiment, _sim, _task_message, group_state, group_context):
+def run_task(experiment, sim, _task_message, group_state, group_context):
     next_lang = None
     agent_state = None
     agent_context = None
+    step = group_context.step()
 
     for i_agent in range(group_state.n_agents()):
         # TODO: Reuse `agent_state` and `agent_context` objects.
@@ -167,6 +186,7 @@ def run_task(experiment, _sim, _task_message, group_state, group_context):
                 break
 
             agent_state.set_dynamic_access(behavior['dyn_access'])
+            _seed_behavior(sim, step, agent_state, i_behavior)
             try:
                 behavior['fn'](agent_state, agent_context)
                 _postprocess(agent_state)  # Errors from post-processing are considered user errors.
@@ -0,0 +1,272 @@
+//! Transpilation of TypeScript behaviors to JavaScript.
+//!
+//! The TypeScript syntax is stripped with [`swc_ecma_transforms_typescript`], which also compiles
+//! constructs with runtime semantics like `enum`s, `namespace`s, and parameter properties. The
+//! JavaScript is generated from the syntax tree together with a source map, which is decoded into
+//! [`SourceSegments`] for the JavaScript runner, so it reports errors at their line and column in
+//! the TypeScript source.
+
+use swc_common::{
+    comments::SingleThreadedComments, sync::Lrc, FileName, Globals, Mark, SourceMap, GLOBALS,
+};
+use swc_ecma_ast::EsVersion;
+use swc_ecma_codegen::{text_writer::JsWriter, Config, Emitter};
+use swc_ecma_parser::{parse_file_as_module, Syntax, TsConfig};
+use swc_ecma_transforms_base::{fixer::fixer, resolver};
+use swc_ecma_transforms_typescript::strip;
+use swc_ecma_visit::FoldWith;
+
+use crate::{Error, Result};
+
+/// A TypeScript behavior transpiled to JavaScript.
+#[derive(Debug, Clone)]
+pub struct TranspiledTypeScript {
+    /// The JavaScript source of the behavior.
+    pub source: String,
+    /// The source map from [`source`](Self::source) to the TypeScript source in the JSON format.
+    pub source_map: String,
+}
+
+/// Transpiles the TypeScript `source` of the behavior `name` to JavaScript.
+///
+/// # Errors
+///
+/// - if `source` can't be parsed, the error contains the line and column of the syntax error
+/// - if the JavaScript or its source map can't be written
+pub fn transpile_typescript(name: &str, source: &str) -> Result<TranspiledTypeScript> {
+    let source_map: Lrc<SourceMap> = Lrc::default();
+    let file = source_map.new_source_file(FileName::Custom(name.to_string()), source.to_string());
+    let comments = SingleThreadedComments::default();
+
+    // Recoverable errors are rejected as well, V8 wouldn't compile the code either
+    let mut errors = Vec::new();
+    let module = parse_file_as_module(
+        &file,
+        Syntax::Typescript(TsConfig::default()),
+        EsVersion::latest(),
+        Some(&comments),
+        &mut errors,
+    );
+    let module = match (module, errors.into_iter().next()) {
+        (Ok(module), None) => module,
+        (Err(error), _) | (Ok(_), Some(error)) => {
+            let location = source_map.lookup_char_pos(error.span().lo);
+            return Err(Error::from(format!(
+                "{} at line {}, column {}",
+                error.kind().msg(),
+                location.line,
+                location.col_display + 1
+            )));
+        }
+    };
+
+    let module = GLOBALS.set(&Globals::new(), || {
+        let unresolved_mark = Mark::new();
+        let top_level_mark = Mark::new();
+        module
+            .fold_with(&mut resolver(unresolved_mark, top_level_mark, true))
+            .fold_with(&mut strip(top_level_mark))
+            .fold_with(&mut fixer(Some(&comments)))
+    });
+
+    let mut javascript = Vec::new();
+    let mut mappings = Vec::new();
+    Emitter {
+        cfg: Config::default(),
+        cm: Lrc::clone(&source_map),
+        comments: Some(&comments),
+        wr: JsWriter::new(
+            Lrc::clone(&source_map),
+            "\n",
+            &mut javascript,
+            Some(&mut mappings),
+        ),
+    }
+    .emit_module(&module)
+    .map_err(|err| Error::from(format!("Could not write JavaScript: {err}")))?;
+
+    let mut transpiled_source_map = Vec::new();
+    source_map
+        .build_source_map(&mut mappings)
+        .to_writer(&mut transpiled_source_map)
+        .map_err(|err| Error::from(format!("Could not write source map: {err}")))?;
+
+    Ok(TranspiledTypeScript {
+        source: String::from_utf8(javascript)
+            .map_err(|err| Error::from(format!("Transpiled JavaScript is not UTF-8: {err}")))?,
+        source_map: String::from_utf8(transpiled_source_map)
+            .map_err(|err| Error::from(format!("Source map is not UTF-8: {err}")))?,
+    })
+}
+
+/// Returns the 1-based line and column in the TypeScript source of the 1-based `line` and
+/// `column` in the transpiled JavaScript.
+///
+/// `source_map` is the [`source_map`](TranspiledTypeScript::source_map) returned by
+/// [`transpile_typescript`]. Returns `None` if it can't be parsed or if the position isn't mapped.
+pub fn original_position(source_map: &str, line: usize, column: usize) -> Option<(usize, usize)> {
+    let source_map = sourcemap::SourceMap::from_slice(source_map.as_bytes()).ok()?;
+    let token = source_map.lookup_token(
+        u32::try_from(line.checked_sub(1)?).ok()?,
+        u32::try_from(column.checked_sub(1)?).ok()?,
+    )?;
+    Some((
+        token.get_src_line() as usize + 1,
+        token.get_src_col() as usize + 1,
+    ))
+}
+
+/// The decoded mappings of a source map.
+///
+/// Contains the segments of every line of the transpiled JavaScript, each segment is
+/// `[column, source line, source column]` (all 0-based) and the segments of a line are sorted by
+/// column.
+pub type SourceSegments = Vec<Vec<[u32; 3]>>;
+
+/// Decodes the [`source_map`](TranspiledTypeScript::source_map) returned by
+/// [`transpile_typescript`] into the segments of every line of the transpiled JavaScript.
+///
+/// # Errors
+///
+/// - if `source_map` can't be parsed
+pub fn decode_source_map(source_map: &str) -> Result<SourceSegments> {
+    let source_map = sourcemap::SourceMap::from_slice(source_map.as_bytes())
+        .map_err(|err| Error::from(format!("Could not parse source map: {err}")))?;
+    let mut lines = SourceSegments::new();
+    for token in source_map.tokens() {
+        // Segments without a source position can't be mapped
+        if token.get_source().is_none() {
+            continue;
+        }
+        let line = token.get_dst_line() as usize;
+        if lines.len() <= line {
+            lines.resize_with(line + 1, Vec::new);
+        }
+        lines[line].push([
+            token.get_dst_col(),
+            token.get_src_line(),
+            token.get_src_col(),
+        ]);
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Removes all whitespace, so the code generated by swc can be compared regardless of its
+    /// formatting.
+    fn without_whitespace(source: &str) -> String {
+        source.split_whitespace().collect()
+    }
+
+    fn assert_transpiles(typescript: &str, javascript: &str) {
+        let transpiled = transpile_typescript("behavior.ts", typescript).unwrap();
+        assert_eq!(
+            without_whitespace(&transpiled.source),
+            without_whitespace(javascript),
+            "{}",
+            transpiled.source
+        );
+    }
+
+    #[test]
+    fn strips_annotations() {
+        assert_transpiles(
+            "const behavior = (state: AgentState, context?: Context): void => {
+              let x: number[] = state.get(\"x\") as number[];
+              state.y = x.length > 0 ? x[0]! : 1;
+            };",
+            "const behavior = (state, context) => {
+              let x = state.get(\"x\");
+              state.y = x.length > 0 ? x[0] : 1;
+            };",
+        );
+    }
+
+    #[test]
+    fn strips_declarations() {
+        assert_transpiles(
+            "interface Props extends Base<number> {
+              a: number;
+            }
+            type Id<T> = string | T;
+            declare const hstd: any;
+            const f = <T,>(x: T) => identity<T>(x);",
+            "const f = (x) => identity(x);",
+        );
+    }
+
+    #[test]
+    fn compiles_runtime_constructs() {
+        let transpiled =
+            transpile_typescript("behavior.ts", "enum Color { Red }\nconst red = Color.Red;")
+                .unwrap();
+        let javascript = without_whitespace(&transpiled.source);
+        assert!(!javascript.contains("enum"), "{}", transpiled.source);
+        assert!(
+            javascript.contains("Color[Color[\"Red\"]=0]=\"Red\""),
+            "{}",
+            transpiled.source
+        );
+    }
+
+    #[test]
+    fn maps_positions_to_the_typescript_source() {
+        let typescript = "interface State {
+  x: number;
+}
+
+const behavior = (state: State): void => {
+  state.x = (state.x as number) + missing;
+};
+";
+        let transpiled = transpile_typescript("behavior.ts", typescript).unwrap();
+        let (line, javascript_line) = transpiled
+            .source
+            .lines()
+            .enumerate()
+            .find(|(_, line)| line.contains("missing"))
+            .unwrap();
+        let column = javascript_line.find("missing").unwrap();
+
+        assert_eq!(
+            original_position(&transpiled.source_map, line + 1, column + 1),
+            Some((6, 35))
+        );
+    }
+
+    #[test]
+    fn decodes_segments_of_every_line() {
+        let typescript = "const a: number = 1;\n\nconst b: string = \"b\";\n";
+        let transpiled = transpile_typescript("behavior.ts", typescript).unwrap();
+        let segments = decode_source_map(&transpiled.source_map).unwrap();
+
+        let (line, javascript_line) = transpiled
+            .source
+            .lines()
+            .enumerate()
+            .find(|(_, line)| line.contains("const b"))
+            .unwrap();
+        let column = javascript_line.find("const b").unwrap() as u32;
+        assert!(
+            segments[line].contains(&[column, 2, 0]),
+            "{segments:?}\n{}",
+            transpiled.source
+        );
+        for line in &segments {
+            assert!(line.windows(2).all(|pair| pair[0][0] <= pair[1][0]));
+        }
+
+        assert!(decode_source_map("not a source map").is_err());
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let error = transpile_typescript("behavior.ts", "const a = 1;\nconst b: number = ;")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("at line 2"), "{error}");
+    }
+}
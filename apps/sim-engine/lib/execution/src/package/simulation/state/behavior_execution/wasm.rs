@@ -0,0 +1,30 @@
+//! Encoding of WebAssembly behaviors.
+//!
+//! Behavior sources are passed to the language runners as strings, so binary `.wasm` modules are
+//! base64-encoded when they are read. Modules in the WebAssembly text format (`.wat`) are passed
+//! on unchanged.
+
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// Encodes the binary WebAssembly `module` as behavior source.
+pub fn encode_wasm(module: &[u8]) -> String {
+    base64::encode(module)
+}
+
+/// Returns the module of the WebAssembly behavior `file_name` from its `source`, either in the
+/// binary or in the text format.
+pub(crate) fn decode_wasm(file_name: &str, source: &str) -> Result<Vec<u8>> {
+    match Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some("wat") => Ok(source.as_bytes().to_vec()),
+        _ => base64::decode(source).map_err(|err| {
+            Error::from(format!(
+                "Could not decode WebAssembly behavior `{file_name}`: {err}"
+            ))
+        }),
+    }
+}
@@ -82,7 +82,12 @@ impl Topology {
     }
 }
 
-impl Package for Topology {}
+impl Package for Topology {
+    fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.config = Arc::new(TopologyConfig::from_globals(globals)?);
+        Ok(())
+    }
+}
 
 #[async_trait]
 impl StatePackage for Topology {
@@ -1,8 +1,9 @@
 //! Language runner implementations to run [`package`]s.
 //!
-//! Currently, three [`Language`] runners are available: JavaScript, Python, and Rust. The latter
-//! is WIP and currently disabled. To drive the language runners, the [`comms`] module provides
-//! messages to be sent to the runners or received from the runners.
+//! Currently, four [`Language`] runners are available: JavaScript, Python, Rust, and WebAssembly.
+//! The Rust runner only runs the built-in `@hash` behaviors, which are compiled into the engine,
+//! and the WebAssembly runner only runs behaviors. To drive the language runners, the [`comms`]
+//! module provides messages to be sent to the runners or received from the runners.
 //!
 //! [`package`]: crate::package
 
@@ -11,17 +12,24 @@ pub mod comms;
 mod javascript;
 mod python;
 mod rust;
+mod wasm;
 
 mod config;
 mod error;
+mod in_process;
 mod language;
 mod target;
 
 pub use self::{
-    config::RunnerConfig, error::RunnerError, language::Language, target::MessageTarget,
+    config::RunnerConfig,
+    error::RunnerError,
+    javascript::{compile_javascript_behavior, JavaScriptSyntaxError},
+    language::Language,
+    target::MessageTarget,
 };
 pub(crate) use self::{
     javascript::{JavaScriptError, JavaScriptRunner},
     python::{PythonError, PythonRunner},
-    rust::RustRunner,
+    rust::{get_built_in_behavior, RustError, RustRunner},
+    wasm::{WasmError, WasmRunner},
 };
@@ -1,5 +1,7 @@
 //! TODO: DOC
-use std::fmt;
+use std::{fmt, sync::Arc};
+
+use stateful::global::Globals;
 
 use crate::{
     package::simulation::SimulationId,
@@ -19,6 +21,8 @@ pub enum InboundToRunnerMsgPayload {
     TerminateSimulationRun,
     TerminateRunner,
     NewSimulationRun(NewSimulationRun),
+    /// Replaces the globals of a running simulation run.
+    UpdateGlobals(Arc<Globals>),
 }
 
 impl From<SyncPayload> for InboundToRunnerMsgPayload {
@@ -43,6 +47,7 @@ impl InboundToRunnerMsgPayload {
             Self::TerminateSimulationRun => "TerminateSimulationRun",
             Self::TerminateRunner => "TerminateRunner",
             Self::NewSimulationRun(_) => "NewSimulationRun",
+            Self::UpdateGlobals(_) => "UpdateGlobals",
         }
     }
 }
@@ -1,5 +1,11 @@
+use std::path::PathBuf;
+
 #[derive(Debug, Default, Clone)]
 pub struct RunnerConfig {
     pub js_runner_initial_heap_constraint: Option<usize>,
     pub js_runner_max_heap_size: Option<usize>,
+    /// Directory containing the Python runner, i.e. _run.sh_ and _main.py_.
+    ///
+    /// Defaults to the location of the Python runner in the repository the engine was built from.
+    pub python_runner_dir: Option<PathBuf>,
 }
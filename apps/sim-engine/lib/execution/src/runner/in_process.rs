@@ -0,0 +1,63 @@
+//! Shared implementation of the runners, which execute behaviors inside of the engine process.
+//!
+//! The Rust and the WebAssembly runner both operate directly on the shared Arrow batches without
+//! going through an interpreter. They only differ in how a behavior is invoked on an agent, which
+//! is defined by their [`BehaviorRuntime`]. Handling the messages from the worker, keeping the
+//! state of the simulation runs, and running the behavior chains of the agents is implemented
+//! here once for both of them.
+
+mod behavior_execution;
+mod columns;
+mod error;
+mod run;
+mod state;
+
+pub(in crate::runner) use self::{
+    behavior_execution::AgentBehaviors,
+    columns::{AgentColumns, AgentGroup, Outbox},
+    error::{InProcessError, InProcessResult},
+    run::run_experiment,
+    state::{SimContext, SimState},
+};
+use crate::{
+    package::simulation::state::behavior_execution::BehaviorDescription,
+    runner::{Language, MessageTarget},
+};
+
+/// The language specific part of an in-process runner, which invokes behaviors on agents.
+pub(in crate::runner) trait BehaviorRuntime: Sized {
+    /// The language of the behaviors invoked by the runtime.
+    const LANGUAGE: Language;
+
+    /// The name of the runner used in messages, e.g. `"Rust"`.
+    const NAME: &'static str;
+
+    /// Whether behaviors have access to the messages received by their agent.
+    ///
+    /// The messages of the state snapshot are only loaded if this is set.
+    const RECEIVES_MESSAGES: bool;
+
+    /// A behavior ready to be invoked, e.g. a compiled module.
+    type Behavior;
+
+    /// State of the runtime while running a task on a group of agents, which is shared by all
+    /// agents of the group.
+    type Group;
+
+    fn new() -> InProcessResult<Self>;
+
+    /// Prepares the behavior described by `description`, so it only has to be invoked when running
+    /// a task.
+    fn load(&self, description: &BehaviorDescription) -> InProcessResult<Self::Behavior>;
+
+    fn start_group(&self, sim: &SimState) -> InProcessResult<Self::Group>;
+
+    /// Invokes `behaviors` on their agent in `agents`, see [`AgentBehaviors::run`].
+    fn run_agent(
+        &self,
+        group: &mut Self::Group,
+        sim: &SimState,
+        agents: &mut AgentGroup,
+        behaviors: AgentBehaviors<'_, Self::Behavior>,
+    ) -> InProcessResult<Option<MessageTarget>>;
+}
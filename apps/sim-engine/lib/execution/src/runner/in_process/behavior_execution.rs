@@ -0,0 +1,338 @@
+use std::{collections::HashMap, fmt};
+
+use arrow2::array::{Array, FixedSizeListArray, Float64Array, ListArray, UInt16Array};
+use memory::arrow::{record_batch::RecordBatch, ColumnChange};
+use stateful::{
+    agent::AgentId,
+    field::{FieldScope, FieldSource, PackageId, RootFieldKey},
+    state::StateWriteProxy,
+};
+
+use crate::{
+    package::simulation::{
+        state::behavior_execution::{
+            BehaviorDescription, BehaviorId, SimSetupMessage, BEHAVIOR_IDS_FIELD_NAME,
+            BEHAVIOR_INDEX_FIELD_NAME,
+        },
+        Seed,
+    },
+    runner::{
+        comms::{PackageMsgs, UserError},
+        in_process::{
+            columns::{AgentColumns, AgentGroup, Outbox},
+            error::{InProcessError, InProcessResult},
+            state::SimState,
+            BehaviorRuntime,
+        },
+        Language, MessageTarget,
+    },
+    task::TaskSharedStore,
+    worker::PackageInitMsgForWorker,
+};
+
+struct Behavior<B> {
+    name: String,
+    language: Language,
+    /// The loaded behavior, `None` if it's written in another language.
+    loaded: Option<B>,
+}
+
+/// The behaviors of an agent, which are still to be executed in the current step.
+pub(in crate::runner) struct AgentBehaviors<'t, B> {
+    /// The index of the agent in its group.
+    pub agent_index: usize,
+    /// The position of the agent in the context.
+    pub context_index: usize,
+    /// The seed of the agent in the current step, if the simulation run is seeded.
+    pub seed: Option<Seed>,
+    agent_id: AgentId,
+    behaviors: &'t HashMap<BehaviorId, Behavior<B>>,
+    behavior_ids: &'t [BehaviorId],
+    /// The index of the next behavior to be executed.
+    behavior_index: &'t mut f64,
+}
+
+impl<B> AgentBehaviors<'_, B> {
+    /// Calls `invoke` for every behavior starting at the behavior index of the agent and returns
+    /// the next target if a behavior of another language was encountered.
+    ///
+    /// `invoke` gets the index of the behavior in the behavior chain of the agent, its id, and the
+    /// loaded behavior. The behavior index is incremented after every executed behavior.
+    pub fn run<E: fmt::Display>(
+        self,
+        mut invoke: impl FnMut(usize, BehaviorId, &B) -> Result<(), E>,
+    ) -> InProcessResult<Option<MessageTarget>> {
+        for (index, behavior_id) in self
+            .behavior_ids
+            .iter()
+            .enumerate()
+            .skip(*self.behavior_index as usize)
+        {
+            let behavior = self
+                .behaviors
+                .get(behavior_id)
+                .ok_or(InProcessError::InvalidBehavior(*behavior_id))?;
+            let loaded = match &behavior.loaded {
+                Some(loaded) => loaded,
+                None => return Ok(Some(MessageTarget::from(behavior.language))),
+            };
+
+            invoke(index, *behavior_id, loaded).map_err(|err| {
+                InProcessError::User(vec![UserError(format!(
+                    "Behavior `{}` failed on agent {}: {err}",
+                    behavior.name, self.agent_id
+                ))])
+            })?;
+
+            // Increment the behavior index to point to the next one to be executed
+            *self.behavior_index = (index + 1) as f64;
+        }
+        Ok(None)
+    }
+}
+
+/// The in-process implementation of the behavior execution package.
+pub(in crate::runner) struct BehaviorPackage<R: BehaviorRuntime> {
+    id: PackageId,
+    runtime: R,
+    behaviors: HashMap<BehaviorId, Behavior<R::Behavior>>,
+    behavior_index_key: String,
+    behavior_ids_key: String,
+}
+
+impl<R: BehaviorRuntime> BehaviorPackage<R> {
+    pub fn id(&self) -> PackageId {
+        self.id
+    }
+
+    /// Loads the behaviors of the runtime's language, so they only have to be invoked when
+    /// running a task.
+    pub fn start_experiment(init: &PackageInitMsgForWorker) -> InProcessResult<Self> {
+        let descriptions: Vec<BehaviorDescription> = serde_json::from_value(init.payload.clone())?;
+
+        let private_key = |name| {
+            RootFieldKey::new_private_or_hidden_scoped(
+                name,
+                FieldSource::Package(init.id),
+                FieldScope::Private,
+            )
+            .map(|key| key.value().to_string())
+        };
+        let behavior_index_key = private_key(BEHAVIOR_INDEX_FIELD_NAME)?;
+        let behavior_ids_key = private_key(BEHAVIOR_IDS_FIELD_NAME)?;
+
+        let runtime = R::new()?;
+        let mut behaviors = HashMap::new();
+        for description in descriptions {
+            let loaded = if description.language == R::LANGUAGE {
+                Some(runtime.load(&description)?)
+            } else {
+                None
+            };
+
+            let behavior = Behavior {
+                name: description.name,
+                language: description.language,
+                loaded,
+            };
+            if behaviors.insert(description.id, behavior).is_some() {
+                return Err(InProcessError::from(format!(
+                    "Duplicate behavior id {:?}",
+                    description.id
+                )));
+            }
+        }
+        Ok(Self {
+            id: init.id,
+            runtime,
+            behaviors,
+            behavior_index_key,
+            behavior_ids_key,
+        })
+    }
+
+    /// Returns the seed of a new simulation run from the setup message of the package.
+    pub fn start_sim(&self, packages: &PackageMsgs) -> InProcessResult<Option<Seed>> {
+        let init = match packages.0.get(&self.id) {
+            Some(init) => init,
+            None => return Ok(None),
+        };
+        let msg: SimSetupMessage = serde_json::from_value(init.payload.clone())?;
+        msg.seed
+            .map(|seed| {
+                seed.parse()
+                    .map(Seed::new)
+                    .map_err(|_| InProcessError::from(format!("Invalid seed: {seed}")))
+            })
+            .transpose()
+    }
+
+    /// Runs the behaviors of the runtime's language on every agent in the writable groups of
+    /// `shared_store`.
+    ///
+    /// Behaviors are executed starting at the behavior index of each agent until the first
+    /// behavior in another language is reached. Returns the language runner to continue with or
+    /// [`MessageTarget::Main`] if all behaviors were executed.
+    pub fn run_task(
+        &self,
+        sim: &SimState,
+        shared_store: &mut TaskSharedStore,
+    ) -> InProcessResult<MessageTarget> {
+        let (proxy, group_indices) = shared_store
+            .get_write_proxies()
+            .map_err(|_| InProcessError::from("cannot obtain the state as writable"))?;
+        proxy
+            .maybe_reload()
+            .map_err(|_| InProcessError::from("could not reload batches (this is a bug)"))?;
+
+        let mut next_target = MessageTarget::Main;
+        for (proxy_index, group_index) in group_indices.into_iter().enumerate() {
+            let (agent_changes, message_change) = {
+                let agent_batch = proxy.agent_pool().batch(proxy_index).ok_or_else(|| {
+                    InProcessError::from(format!("Missing agent batch {group_index}"))
+                })?;
+                let message_batch = proxy.message_pool().batch(proxy_index).ok_or_else(|| {
+                    InProcessError::from(format!("Missing message batch {group_index}"))
+                })?;
+                let record_batch = agent_batch.batch.record_batch()?;
+
+                let behavior_ids = self.behavior_ids(record_batch)?;
+                let (behavior_index_column, mut behavior_indices) =
+                    self.behavior_indices(record_batch)?;
+                let mut agents = AgentGroup {
+                    columns: AgentColumns::from_record_batch(record_batch, &sim.agent_schema)?,
+                    outbox: Outbox::default(),
+                };
+
+                let mut group = self.runtime.start_group(sim)?;
+                let group_start_index = sim.group_start_index(group_index)?;
+                for (agent_index, (behavior_ids, behavior_index)) in behavior_ids
+                    .iter()
+                    .zip(behavior_indices.iter_mut())
+                    .enumerate()
+                {
+                    let agent_id = agents.columns.agent_id(agent_index);
+                    let behaviors = AgentBehaviors {
+                        agent_index,
+                        context_index: group_start_index + agent_index,
+                        seed: sim.agent_seed(&agent_id),
+                        agent_id,
+                        behaviors: &self.behaviors,
+                        behavior_ids,
+                        behavior_index,
+                    };
+                    if let Some(target) =
+                        self.runtime
+                            .run_agent(&mut group, sim, &mut agents, behaviors)?
+                    {
+                        next_target = target;
+                    }
+                }
+
+                let mut agent_changes = agents.columns.changes(record_batch, &sim.agent_schema)?;
+                agent_changes.push(ColumnChange {
+                    data: Float64Array::from_vec(behavior_indices).boxed(),
+                    index: behavior_index_column,
+                });
+                (agent_changes, agents.outbox.change(message_batch)?)
+            };
+
+            Self::flush(proxy, proxy_index, agent_changes, message_change)?;
+        }
+
+        Ok(next_target)
+    }
+
+    /// Reads the behavior chain of every agent in `record_batch`.
+    fn behavior_ids(&self, record_batch: &RecordBatch) -> InProcessResult<Vec<Vec<BehaviorId>>> {
+        let (_, column) = column_by_name(record_batch, &self.behavior_ids_key)?;
+        let lists = column
+            .as_any()
+            .downcast_ref::<ListArray<i32>>()
+            .ok_or_else(|| InProcessError::from("Behavior ids are expected to be a list"))?;
+        let ids = lists
+            .values()
+            .as_any()
+            .downcast_ref::<FixedSizeListArray>()
+            .ok_or_else(|| {
+                InProcessError::from("Behavior ids are expected to be fixed-size lists")
+            })?;
+        let indices = ids
+            .values()
+            .as_any()
+            .downcast_ref::<UInt16Array>()
+            .ok_or_else(|| {
+                InProcessError::from("Behavior ids are expected to be unsigned integers")
+            })?
+            .values();
+
+        Ok(lists
+            .offsets()
+            .windows(2)
+            .map(|window| {
+                (window[0] as usize..window[1] as usize)
+                    .map(|id| {
+                        let id = id * ids.size();
+                        BehaviorId::new(indices[id], indices[id + 1])
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Reads the behavior index of every agent in `record_batch` and returns it together with the
+    /// index of its column.
+    fn behavior_indices(&self, record_batch: &RecordBatch) -> InProcessResult<(usize, Vec<f64>)> {
+        let (column_index, column) = column_by_name(record_batch, &self.behavior_index_key)?;
+        let indices = column
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| InProcessError::from("Behavior index is expected to be a number"))?;
+        Ok((column_index, indices.values().to_vec()))
+    }
+
+    /// Writes the changes made by behaviors back into the batches at `proxy_index`.
+    fn flush(
+        proxy: &mut StateWriteProxy,
+        proxy_index: usize,
+        agent_changes: Vec<ColumnChange>,
+        message_change: Option<ColumnChange>,
+    ) -> InProcessResult<()> {
+        let agent_batch = proxy
+            .agent_pool_mut()
+            .batch_mut(proxy_index)
+            .ok_or_else(|| InProcessError::from(format!("Missing agent batch {proxy_index}")))?;
+        for change in agent_changes {
+            agent_batch.batch.queue_change(change)?;
+        }
+        agent_batch.batch.flush_changes()?;
+
+        if let Some(change) = message_change {
+            let message_batch =
+                proxy
+                    .message_pool_mut()
+                    .batch_mut(proxy_index)
+                    .ok_or_else(|| {
+                        InProcessError::from(format!("Missing message batch {proxy_index}"))
+                    })?;
+            message_batch.batch.queue_change(change)?;
+            message_batch.batch.flush_changes()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the index and the array of the column `name` in `record_batch`.
+fn column_by_name<'r>(
+    record_batch: &'r RecordBatch,
+    name: &str,
+) -> InProcessResult<(usize, &'r dyn Array)> {
+    record_batch
+        .schema()
+        .fields
+        .iter()
+        .position(|field| field.name == name)
+        .map(|index| (index, record_batch.column(index).as_ref()))
+        .ok_or_else(|| InProcessError::from(format!("Missing column `{name}`")))
+}
@@ -0,0 +1,603 @@
+//! Typed access to the agent and message batches of a task.
+//!
+//! The fields agents have access to are decoded into [`Column`]s of native values once per batch,
+//! so behaviors don't go through JSON when reading or writing a number or a position. Only the
+//! columns modified by behaviors are encoded again and written back as [`ColumnChange`]s.
+
+use std::collections::{HashMap, HashSet};
+
+use arrow2::{
+    array::{
+        Array, BooleanArray, FixedSizeBinaryArray, FixedSizeListArray, Float64Array, Utf8Array,
+    },
+    bitmap::Bitmap,
+    datatypes::{DataType, Field},
+};
+use memory::arrow::{
+    categorical_to_json_vals, col_to_json_vals, json_utf8_json_vals, json_vals_to_any_type_col,
+    json_vals_to_categorical_col, json_vals_to_col, record_batch::RecordBatch, ColumnChange,
+};
+use serde_json::{Map, Value};
+use stateful::{
+    agent::{AgentId, AgentSchema, AgentStateField},
+    field::{FieldScope, FieldTypeVariant},
+    message::{Message, MessageBatch},
+    Vec3,
+};
+
+use crate::runner::in_process::error::{InProcessError, InProcessResult};
+
+/// The values of a field of every agent in a batch.
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Number(Vec<Option<f64>>),
+    Boolean(Vec<Option<bool>>),
+    String(Vec<Option<String>>),
+    /// Fixed-size lists of three numbers like `position` or `velocity`.
+    Vec3(Vec<Option<Vec3>>),
+    /// Fields of any other type, e.g. lists, structs, or fields of any type.
+    Json(Vec<Value>),
+}
+
+impl Column {
+    fn decode(
+        array: &dyn Array,
+        field: &Field,
+        variant: &FieldTypeVariant,
+    ) -> InProcessResult<Self> {
+        let column = match (variant, field.data_type()) {
+            (FieldTypeVariant::AnyType, _) => Self::Json(json_utf8_json_vals(array)?),
+            (FieldTypeVariant::Categorical(categories), _) => {
+                Self::Json(categorical_to_json_vals(array, categories)?)
+            }
+            (_, DataType::Float64) => Self::Number(
+                downcast::<Float64Array>(array, field)?
+                    .iter()
+                    .map(|value| value.copied())
+                    .collect(),
+            ),
+            (_, DataType::Boolean) => {
+                Self::Boolean(downcast::<BooleanArray>(array, field)?.iter().collect())
+            }
+            (_, DataType::Utf8) => Self::String(
+                downcast::<Utf8Array<i32>>(array, field)?
+                    .iter()
+                    .map(|value| value.map(str::to_string))
+                    .collect(),
+            ),
+            (_, DataType::FixedSizeList(inner_field, 3))
+                if inner_field.data_type() == &DataType::Float64 =>
+            {
+                let lists = downcast::<FixedSizeListArray>(array, field)?;
+                let coordinates = downcast::<Float64Array>(lists.values().as_ref(), field)?;
+                Self::Vec3(
+                    (0..lists.len())
+                        .map(|index| {
+                            lists.is_valid(index).then(|| {
+                                Vec3(
+                                    coordinates.value(index * 3),
+                                    coordinates.value(index * 3 + 1),
+                                    coordinates.value(index * 3 + 2),
+                                )
+                            })
+                        })
+                        .collect(),
+                )
+            }
+            (_, data_type) => {
+                let mut values = col_to_json_vals(array, data_type)?;
+                for (index, value) in values.iter_mut().enumerate() {
+                    if !array.is_valid(index) {
+                        *value = Value::Null;
+                    }
+                }
+                Self::Json(values)
+            }
+        };
+        Ok(column)
+    }
+
+    fn encode(&self, field: &Field, variant: &FieldTypeVariant) -> InProcessResult<Box<dyn Array>> {
+        let array = match self {
+            Self::Number(values) => Float64Array::from_iter(values.iter().copied()).boxed(),
+            Self::Boolean(values) => BooleanArray::from_iter(values.iter().copied()).boxed(),
+            Self::String(values) => {
+                Utf8Array::<i32>::from_iter(values.iter().map(Option::as_deref)).boxed()
+            }
+            Self::Vec3(values) => {
+                let coordinates = values
+                    .iter()
+                    .flat_map(|value| <[f64; 3]>::from(value.unwrap_or_default()))
+                    .collect();
+                FixedSizeListArray::new(
+                    field.data_type().clone(),
+                    Float64Array::from_vec(coordinates).boxed(),
+                    Some(values.iter().map(Option::is_some).collect::<Bitmap>()),
+                )
+                .boxed()
+            }
+            Self::Json(values) => match variant {
+                FieldTypeVariant::AnyType => {
+                    json_vals_to_any_type_col(values.clone(), field.data_type())?
+                }
+                FieldTypeVariant::Categorical(categories) => {
+                    json_vals_to_categorical_col(values.clone(), categories, field.is_nullable)?
+                }
+                _ => json_vals_to_col(values.clone(), field, field.is_nullable)?,
+            },
+        };
+        Ok(array)
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Number(_) => "a number",
+            Self::Boolean(_) => "a boolean",
+            Self::String(_) => "a string",
+            Self::Vec3(_) => "a list of three numbers",
+            Self::Json(_) => "a JSON value",
+        }
+    }
+
+    fn number(&self, index: usize) -> Option<f64> {
+        match self {
+            Self::Number(values) => values[index],
+            Self::Json(values) => values[index].as_f64(),
+            _ => None,
+        }
+    }
+
+    fn boolean(&self, index: usize) -> Option<bool> {
+        match self {
+            Self::Boolean(values) => values[index],
+            Self::Json(values) => values[index].as_bool(),
+            _ => None,
+        }
+    }
+
+    fn string(&self, index: usize) -> Option<&str> {
+        match self {
+            Self::String(values) => values[index].as_deref(),
+            Self::Json(values) => values[index].as_str(),
+            _ => None,
+        }
+    }
+
+    fn vec3(&self, index: usize) -> Option<Vec3> {
+        match self {
+            Self::Vec3(values) => values[index],
+            Self::Json(values) => Vec3::try_from(values[index].clone()).ok(),
+            _ => None,
+        }
+    }
+
+    fn json(&self, index: usize) -> Value {
+        match self {
+            Self::Number(values) => values[index].map_or(Value::Null, Value::from),
+            Self::Boolean(values) => values[index].map_or(Value::Null, Value::Bool),
+            Self::String(values) => values[index].clone().map_or(Value::Null, Value::String),
+            Self::Vec3(values) => values[index].map_or(Value::Null, |value| {
+                Value::from(<[f64; 3]>::from(value).to_vec())
+            }),
+            Self::Json(values) => values[index].clone(),
+        }
+    }
+
+    /// Sets the value at `index`, `value` is returned if it doesn't have the type of the column.
+    fn set_json(&mut self, index: usize, value: Value) -> Result<(), Value> {
+        match self {
+            Self::Number(values) => {
+                values[index] = match value {
+                    Value::Null => None,
+                    value => Some(value.as_f64().ok_or(value)?),
+                }
+            }
+            Self::Boolean(values) => {
+                values[index] = match value {
+                    Value::Null => None,
+                    value => Some(value.as_bool().ok_or(value)?),
+                }
+            }
+            Self::String(values) => {
+                values[index] = match value {
+                    Value::Null => None,
+                    Value::String(value) => Some(value),
+                    value => return Err(value),
+                }
+            }
+            Self::Vec3(values) => {
+                values[index] = match value {
+                    Value::Null => None,
+                    value => Some(Vec3::try_from(value.clone()).map_err(|_| value)?),
+                }
+            }
+            Self::Json(values) => values[index] = value,
+        }
+        Ok(())
+    }
+}
+
+fn downcast<'a, T: 'static>(array: &'a dyn Array, field: &Field) -> InProcessResult<&'a T> {
+    array.as_any().downcast_ref().ok_or_else(|| {
+        InProcessError::from(format!(
+            "Column `{}` doesn't match its data type {:?}",
+            field.name,
+            field.data_type()
+        ))
+    })
+}
+
+/// Returns the type of the field `name` if it's accessible by agents.
+fn agent_field_variant<'s>(
+    agent_schema: &'s AgentSchema,
+    name: &str,
+) -> Option<&'s FieldTypeVariant> {
+    agent_schema
+        .field_spec_map
+        .iter()
+        .find(|(key, field_spec)| field_spec.scope == FieldScope::Agent && key.value() == name)
+        .map(|(_, field_spec)| &field_spec.inner.field_type.variant)
+}
+
+/// The fields of every agent in an agent batch, which are accessible by agents.
+///
+/// Private and hidden fields of packages are not loaded. The agent ids are loaded, but can't be
+/// modified.
+#[derive(Default)]
+pub(in crate::runner) struct AgentColumns {
+    agent_ids: Vec<AgentId>,
+    columns: HashMap<String, Column>,
+    /// Names of the columns, which were modified since they were loaded.
+    modified: HashSet<String>,
+}
+
+impl AgentColumns {
+    pub fn from_record_batch(
+        record_batch: &RecordBatch,
+        agent_schema: &AgentSchema,
+    ) -> InProcessResult<Self> {
+        let mut agent_ids = Vec::new();
+        let mut columns = HashMap::new();
+        let schema = record_batch.schema();
+        for (field, array) in schema.fields.iter().zip(record_batch.columns()) {
+            if field.name == AgentStateField::AgentId.name() {
+                let ids = downcast::<FixedSizeBinaryArray>(array.as_ref(), field)?;
+                agent_ids = (0..ids.len())
+                    .map(|index| AgentId::from_slice(ids.value(index)))
+                    .collect::<stateful::Result<_>>()?;
+            } else if let Some(variant) = agent_field_variant(agent_schema, &field.name) {
+                let column = Column::decode(array.as_ref(), field, variant)?;
+                columns.insert(field.name.clone(), column);
+            }
+        }
+
+        Ok(Self {
+            agent_ids,
+            columns,
+            modified: HashSet::new(),
+        })
+    }
+
+    pub fn num_agents(&self) -> usize {
+        self.agent_ids.len()
+    }
+
+    pub fn agent_id(&self, index: usize) -> AgentId {
+        self.agent_ids[index]
+    }
+
+    pub fn number(&self, key: &str, index: usize) -> Option<f64> {
+        self.columns.get(key)?.number(index)
+    }
+
+    pub fn boolean(&self, key: &str, index: usize) -> Option<bool> {
+        self.columns.get(key)?.boolean(index)
+    }
+
+    pub fn string(&self, key: &str, index: usize) -> Option<&str> {
+        self.columns.get(key)?.string(index)
+    }
+
+    pub fn vec3(&self, key: &str, index: usize) -> Option<Vec3> {
+        self.columns.get(key)?.vec3(index)
+    }
+
+    /// Returns the field `key` of the agent at `index` as JSON, `null` if there is no such field.
+    pub fn json(&self, key: &str, index: usize) -> Value {
+        if key == AgentStateField::AgentId.name() {
+            return Value::String(self.agent_ids[index].to_string());
+        }
+        self.columns
+            .get(key)
+            .map_or(Value::Null, |column| column.json(index))
+    }
+
+    /// Returns all fields except for `null` fields of the agent at `index` as JSON object.
+    pub fn fields_json(&self, index: usize) -> Map<String, Value> {
+        self.columns
+            .iter()
+            .map(|(key, column)| (key.clone(), column.json(index)))
+            .filter(|(_, value)| !value.is_null())
+            .collect()
+    }
+
+    fn column_mut(&mut self, key: &str) -> InProcessResult<&mut Column> {
+        let column = self.columns.get_mut(key).ok_or_else(|| {
+            InProcessError::from(format!(
+                "`{key}` can't be set, it's not a field of the agents"
+            ))
+        })?;
+        if !self.modified.contains(key) {
+            self.modified.insert(key.to_string());
+        }
+        Ok(column)
+    }
+
+    pub fn set_number(&mut self, key: &str, index: usize, value: f64) -> InProcessResult<()> {
+        match self.column_mut(key)? {
+            Column::Number(values) => {
+                values[index] = Some(value);
+                Ok(())
+            }
+            _ => self.set_json(key, index, Value::from(value)),
+        }
+    }
+
+    pub fn set_boolean(&mut self, key: &str, index: usize, value: bool) -> InProcessResult<()> {
+        match self.column_mut(key)? {
+            Column::Boolean(values) => {
+                values[index] = Some(value);
+                Ok(())
+            }
+            _ => self.set_json(key, index, Value::Bool(value)),
+        }
+    }
+
+    pub fn set_vec3(&mut self, key: &str, index: usize, value: Vec3) -> InProcessResult<()> {
+        match self.column_mut(key)? {
+            Column::Vec3(values) => {
+                values[index] = Some(value);
+                Ok(())
+            }
+            _ => self.set_json(key, index, Value::from(<[f64; 3]>::from(value).to_vec())),
+        }
+    }
+
+    /// Sets the field `key` of the agent at `index`.
+    ///
+    /// # Errors
+    ///
+    /// - if there is no field `key`
+    /// - if `value` can't be converted to the type of the field
+    pub fn set_json(&mut self, key: &str, index: usize, value: Value) -> InProcessResult<()> {
+        let column = self.column_mut(key)?;
+        column.set_json(index, value).map_err(|value| {
+            InProcessError::from(format!(
+                "`{key}` can't be set to {value}, it has to be {}",
+                column.type_name()
+            ))
+        })
+    }
+
+    /// Encodes the modified columns as changes to `record_batch`, which the columns were loaded
+    /// from.
+    pub fn changes(
+        &self,
+        record_batch: &RecordBatch,
+        agent_schema: &AgentSchema,
+    ) -> InProcessResult<Vec<ColumnChange>> {
+        let schema = record_batch.schema();
+        schema
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| self.modified.contains(&field.name))
+            .map(|(index, field)| {
+                let variant = agent_field_variant(agent_schema, &field.name).ok_or_else(|| {
+                    InProcessError::from(format!("`{}` is not a field of the agents", field.name))
+                })?;
+                let data = self.columns[&field.name].encode(field, variant)?;
+                Ok(ColumnChange { data, index })
+            })
+            .collect()
+    }
+}
+
+/// The messages sent by the agents of a message batch while running a task.
+///
+/// The messages already sent in the current step, e.g. by behaviors in other languages, are only
+/// loaded when writing back the messages, and only if an agent sent a message.
+#[derive(Default)]
+pub(in crate::runner) struct Outbox {
+    /// The sent messages, indexed by the index of the sending agent.
+    sent: Vec<Vec<Message>>,
+}
+
+impl Outbox {
+    /// Adds `message` to the outbound messages of the agent at `index`.
+    pub fn push(&mut self, index: usize, message: Message) {
+        if self.sent.len() <= index {
+            self.sent.resize_with(index + 1, Vec::new);
+        }
+        self.sent[index].push(message);
+    }
+
+    /// Returns the change to `message_batch` adding the sent messages, or `None` if no message
+    /// was sent.
+    pub fn change(&self, message_batch: &MessageBatch) -> InProcessResult<Option<ColumnChange>> {
+        if self.sent.iter().all(Vec::is_empty) {
+            return Ok(None);
+        }
+
+        let mut messages = message_batch.messages()?;
+        for (index, sent) in self.sent.iter().enumerate() {
+            messages
+                .get_mut(index)
+                .ok_or_else(|| InProcessError::from(format!("Missing messages of agent {index}")))?
+                .extend(sent.iter().cloned());
+        }
+        Ok(Some(message_batch.messages_change(&messages)?))
+    }
+}
+
+/// The agents of a group while running a task on them.
+#[derive(Default)]
+pub(in crate::runner) struct AgentGroup {
+    pub columns: AgentColumns,
+    pub outbox: Outbox,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use stateful::{
+        agent::{arrow::IntoRecordBatch, Agent},
+        field::{
+            FieldScope, FieldSource, FieldSpecMap, FieldType, FieldTypeVariant, RootFieldSpec,
+            RootFieldSpecCreator,
+        },
+    };
+
+    use super::*;
+
+    /// Returns a schema with a few custom fields and the key of a hidden field.
+    fn agent_schema() -> (AgentSchema, String) {
+        let field_spec_creator = RootFieldSpecCreator::new(FieldSource::Engine);
+        let field = |name: &str, variant, scope| {
+            field_spec_creator.create(name.to_string(), FieldType::new(variant, true), scope)
+        };
+        let hidden = field("secret", FieldTypeVariant::Number, FieldScope::Hidden);
+        let hidden_key = hidden.create_key().unwrap().value().to_string();
+
+        let mut field_spec_map = FieldSpecMap::empty();
+        field_spec_map
+            .try_extend(RootFieldSpec::base_agent_fields().unwrap())
+            .unwrap();
+        field_spec_map
+            .try_extend([
+                field("age", FieldTypeVariant::Number, FieldScope::Agent),
+                field("alive", FieldTypeVariant::Boolean, FieldScope::Agent),
+                field(
+                    "scores",
+                    FieldTypeVariant::VariableLengthArray(Box::new(FieldType::new(
+                        FieldTypeVariant::Number,
+                        false,
+                    ))),
+                    FieldScope::Agent,
+                ),
+                hidden,
+            ])
+            .unwrap();
+        (AgentSchema::new(field_spec_map).unwrap(), hidden_key)
+    }
+
+    fn record_batch(agents: Value, schema: &AgentSchema) -> RecordBatch {
+        let agents: Vec<Agent> = serde_json::from_value(agents).unwrap();
+        agents.as_slice().to_agent_batch(schema).unwrap()
+    }
+
+    fn column_index(record_batch: &RecordBatch, name: &str) -> usize {
+        record_batch
+            .schema()
+            .fields
+            .iter()
+            .position(|field| field.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_fields_accessible_by_agents() {
+        let (schema, hidden_key) = agent_schema();
+        let mut agent = json!({
+            "agent_name": "a",
+            "age": 3,
+            "alive": true,
+            "position": [1, 2],
+            "scores": [1, 2],
+        });
+        agent[hidden_key.as_str()] = json!(7);
+        let record_batch = record_batch(json!([agent, {}]), &schema);
+
+        let columns = AgentColumns::from_record_batch(&record_batch, &schema).unwrap();
+        assert_eq!(columns.num_agents(), 2);
+        assert_eq!(columns.string("agent_name", 0), Some("a"));
+        assert_eq!(
+            columns.json("agent_id", 0),
+            Value::String(columns.agent_id(0).to_string())
+        );
+        assert_eq!(columns.number("age", 0), Some(3.0));
+        assert_eq!(columns.number("age", 1), None);
+        assert_eq!(columns.boolean("alive", 0), Some(true));
+        assert_eq!(columns.vec3("position", 0), Some(Vec3(1.0, 2.0, 0.0)));
+        assert_eq!(columns.json("scores", 0), json!([1.0, 2.0]));
+        assert_eq!(columns.json("scores", 1), Value::Null);
+        // Fields of the engine and of packages are not loaded
+        assert_eq!(columns.json(&hidden_key, 0), Value::Null);
+        assert!(!columns.fields_json(0).contains_key(&hidden_key));
+    }
+
+    #[test]
+    fn writes_back_only_modified_columns() {
+        let (schema, _) = agent_schema();
+        let record_batch = record_batch(
+            json!([
+                { "age": 3, "position": [1, 2] },
+                { "age": 5 },
+                { "position": [0, 0] },
+            ]),
+            &schema,
+        );
+        let mut columns = AgentColumns::from_record_batch(&record_batch, &schema).unwrap();
+        assert!(columns.changes(&record_batch, &schema).unwrap().is_empty());
+
+        columns.set_number("age", 1, 6.0).unwrap();
+        columns
+            .set_vec3("position", 2, Vec3(3.0, 4.0, 5.0))
+            .unwrap();
+        columns.set_json("scores", 0, json!([4])).unwrap();
+
+        let mut changes = columns.changes(&record_batch, &schema).unwrap();
+        changes.sort_by_key(|change| change.index);
+        let indices: Vec<_> = changes.iter().map(|change| change.index).collect();
+        let mut expected_indices = [
+            column_index(&record_batch, "age"),
+            column_index(&record_batch, "position"),
+            column_index(&record_batch, "scores"),
+        ];
+        expected_indices.sort_unstable();
+        assert_eq!(indices, expected_indices);
+
+        // Decoding the changes again must return the modified values and keep all others
+        let written = |name: &str| {
+            let index = column_index(&record_batch, name);
+            let change = changes.iter().find(|change| change.index == index).unwrap();
+            let arrow_schema = record_batch.schema();
+            let field = &arrow_schema.fields[index];
+            let variant = agent_field_variant(&schema, name).unwrap();
+            Column::decode(change.data.as_ref(), field, variant).unwrap()
+        };
+        assert_eq!(
+            written("age"),
+            Column::Number(vec![Some(3.0), Some(6.0), None])
+        );
+        let positions = written("position");
+        assert_eq!(positions.vec3(0), Some(Vec3(1.0, 2.0, 0.0)));
+        assert_eq!(positions.vec3(2), Some(Vec3(3.0, 4.0, 5.0)));
+        let scores = written("scores");
+        assert_eq!(scores.json(0), json!([4.0]));
+        assert_eq!(scores.json(1), Value::Null);
+    }
+
+    #[test]
+    fn rejects_writes_to_invalid_fields() {
+        let (schema, hidden_key) = agent_schema();
+        let record_batch = record_batch(json!([{ "age": 3 }]), &schema);
+        let mut columns = AgentColumns::from_record_batch(&record_batch, &schema).unwrap();
+
+        assert!(columns.set_json("age", 0, json!("old")).is_err());
+        assert!(columns.set_boolean("age", 0, true).is_err());
+        assert!(columns.set_number("unknown", 0, 1.0).is_err());
+        assert!(columns.set_number(&hidden_key, 0, 1.0).is_err());
+        assert_eq!(columns.number("age", 0), Some(3.0));
+    }
+}
@@ -0,0 +1,63 @@
+use thiserror::Error as ThisError;
+use tokio::sync::mpsc::error::SendError;
+
+use crate::{
+    package::simulation::{state::behavior_execution::BehaviorId, SimulationId},
+    runner::comms::{OutboundFromRunnerMsg, UserError},
+};
+
+pub type InProcessResult<T, E = InProcessError> = std::result::Result<T, E>;
+
+#[derive(ThisError, Debug)]
+pub enum InProcessError {
+    #[error("{0}")]
+    Unique(String),
+
+    #[error("Memory error: {0}")]
+    Memory(#[from] memory::Error),
+
+    #[error("Stateful error: {0}")]
+    Stateful(#[from] stateful::Error),
+
+    #[error("Arrow: {0}")]
+    Arrow(#[from] arrow2::error::Error),
+
+    #[error("serde: {0:?}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Missing simulation run with id {0}")]
+    MissingSimulationRun(SimulationId),
+
+    #[error("Couldn't terminate missing simulation run with id {0}")]
+    TerminateMissingSimulationRun(SimulationId),
+
+    #[error("Duplicate simulation run id: {0}")]
+    DuplicateSimulationRun(SimulationId),
+
+    #[error("Message type '{0}' must have a simulation run id")]
+    SimulationIdRequired(&'static str),
+
+    #[error("Unknown behavior id {0:?}")]
+    InvalidBehavior(BehaviorId),
+
+    #[error("User errors: {0:?}")]
+    User(Vec<UserError>),
+
+    #[error("Couldn't send outbound message from runner: {0}")]
+    OutboundSend(#[from] SendError<OutboundFromRunnerMsg>),
+
+    #[error("Couldn't receive inbound message from worker")]
+    InboundReceive,
+}
+
+impl From<&str> for InProcessError {
+    fn from(s: &str) -> Self {
+        Self::Unique(s.to_string())
+    }
+}
+
+impl From<String> for InProcessError {
+    fn from(s: String) -> Self {
+        Self::Unique(s)
+    }
+}
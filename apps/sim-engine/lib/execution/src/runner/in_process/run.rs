@@ -0,0 +1,214 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tracing::Span;
+
+use crate::{
+    package::simulation::{state::StatePackageName, PackageName, SimulationId},
+    runner::{
+        comms::{
+            ExperimentInitRunnerMsg, InboundToRunnerMsgPayload, NewSimulationRun,
+            OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerTaskMessage,
+            TargetedRunnerTaskMsg,
+        },
+        in_process::{
+            behavior_execution::BehaviorPackage,
+            error::{InProcessError, InProcessResult},
+            state::SimState,
+            BehaviorRuntime,
+        },
+    },
+};
+
+/// Holds the state of an in-process runner for one experiment run.
+struct ExperimentRunner<R: BehaviorRuntime> {
+    behavior_execution: Option<BehaviorPackage<R>>,
+    sims_state: HashMap<SimulationId, SimState>,
+}
+
+impl<R: BehaviorRuntime> ExperimentRunner<R> {
+    fn new(init_msg: &ExperimentInitRunnerMsg) -> InProcessResult<Self> {
+        let behavior_execution = init_msg
+            .package_config
+            .0
+            .values()
+            .find(|init| {
+                matches!(
+                    init.name,
+                    PackageName::State(StatePackageName::BehaviorExecution)
+                )
+            })
+            .map(BehaviorPackage::start_experiment)
+            .transpose()?;
+
+        Ok(Self {
+            behavior_execution,
+            sims_state: HashMap::new(),
+        })
+    }
+
+    fn sim_state(&mut self, sim_id: SimulationId) -> InProcessResult<&mut SimState> {
+        self.sims_state
+            .get_mut(&sim_id)
+            .ok_or(InProcessError::MissingSimulationRun(sim_id))
+    }
+
+    fn start_sim(&mut self, run: NewSimulationRun) -> InProcessResult<()> {
+        let seed = match &self.behavior_execution {
+            Some(package) => package.start_sim(&run.packages)?,
+            None => None,
+        };
+        let state = SimState::new(
+            Arc::clone(&run.datastore.agent_batch_schema),
+            Arc::clone(&run.globals),
+            seed,
+            R::RECEIVES_MESSAGES,
+        );
+        self.sims_state
+            .try_insert(run.short_id, state)
+            .map_err(|_| InProcessError::DuplicateSimulationRun(run.short_id))?;
+        Ok(())
+    }
+
+    fn handle_task_msg(
+        &mut self,
+        sim_id: SimulationId,
+        mut msg: RunnerTaskMessage,
+        outbound_sender: &UnboundedSender<OutboundFromRunnerMsg>,
+    ) -> InProcessResult<()> {
+        let package = self
+            .behavior_execution
+            .as_ref()
+            .filter(|package| package.id() == msg.package_id)
+            .ok_or_else(|| {
+                InProcessError::from(format!(
+                    "Package with id {} doesn't have a {} implementation",
+                    msg.package_id,
+                    R::NAME
+                ))
+            })?;
+        let sim = self
+            .sims_state
+            .get(&sim_id)
+            .ok_or(InProcessError::MissingSimulationRun(sim_id))?;
+
+        let payload = match package.run_task(sim, &mut msg.shared_store) {
+            // The behavior execution task message doesn't carry any data, so it's passed on as is.
+            Ok(target) => {
+                OutboundFromRunnerMsgPayload::TaskMsg(TargetedRunnerTaskMsg { target, msg })
+            }
+            // User errors are not fatal to the runner
+            Err(InProcessError::User(errors)) => OutboundFromRunnerMsgPayload::UserErrors(errors),
+            // All other types of errors are fatal.
+            Err(err) => return Err(err),
+        };
+        outbound_sender.send(OutboundFromRunnerMsg {
+            span: Span::current(),
+            source: R::LANGUAGE,
+            sim_id,
+            payload,
+        })?;
+
+        Ok(())
+    }
+
+    fn handle_msg(
+        &mut self,
+        sim_id: Option<SimulationId>,
+        msg: InboundToRunnerMsgPayload,
+        outbound_sender: &UnboundedSender<OutboundFromRunnerMsg>,
+    ) -> InProcessResult<bool> {
+        match msg {
+            InboundToRunnerMsgPayload::TerminateRunner => {
+                tracing::debug!("Stopping execution on {} runner", R::NAME);
+                return Ok(false); // Don't continue running.
+            }
+            InboundToRunnerMsgPayload::NewSimulationRun(new_run) => {
+                self.start_sim(new_run)?;
+            }
+            InboundToRunnerMsgPayload::TerminateSimulationRun => {
+                let sim_id = sim_id.ok_or(InProcessError::SimulationIdRequired("terminate sim"))?;
+                self.sims_state
+                    .remove(&sim_id)
+                    .ok_or(InProcessError::TerminateMissingSimulationRun(sim_id))?;
+            }
+            InboundToRunnerMsgPayload::UpdateGlobals(globals) => {
+                let sim_id =
+                    sim_id.ok_or(InProcessError::SimulationIdRequired("update globals"))?;
+                self.sim_state(sim_id)?.update_globals(globals);
+            }
+            InboundToRunnerMsgPayload::StateSync(state_msg) => {
+                // The state is read directly from the task's shared store when running a task, so
+                // there is nothing to load here.
+                state_msg.completion_sender.send(Ok(())).map_err(|err| {
+                    InProcessError::from(format!(
+                        "Couldn't send state sync completion to worker: {err:?}"
+                    ))
+                })?;
+            }
+            InboundToRunnerMsgPayload::StateInterimSync(_) => {
+                // Same as for `StateSync`, batches are reloaded when running a task.
+            }
+            InboundToRunnerMsgPayload::StateSnapshotSync(state_msg) => {
+                let sim_id = sim_id.ok_or(InProcessError::SimulationIdRequired("snapshot sync"))?;
+                self.sim_state(sim_id)?
+                    .state_snapshot_sync(state_msg.state_proxy)?;
+            }
+            InboundToRunnerMsgPayload::ContextBatchSync(ctx_batch) => {
+                let sim_id =
+                    sim_id.ok_or(InProcessError::SimulationIdRequired("context batch sync"))?;
+                self.sim_state(sim_id)?.ctx_batch_sync(ctx_batch)?;
+            }
+            InboundToRunnerMsgPayload::TaskMsg(msg) => {
+                let sim_id = sim_id.ok_or(InProcessError::SimulationIdRequired("run task"))?;
+                self.handle_task_msg(sim_id, msg, outbound_sender)?;
+            }
+            InboundToRunnerMsgPayload::CancelTask(task_id) => {
+                // Tasks are run to completion as soon as they are received, so when a cancel
+                // message arrives, the result of the task was already sent to the worker and
+                // there is nothing left to cancel.
+                tracing::trace!("Ignoring cancellation of finished task {task_id}");
+            }
+        }
+
+        Ok(true) // Continue running.
+    }
+}
+
+/// Runs the experiment with the behaviors of the runtime `R` until the runner is terminated.
+pub(in crate::runner) fn run_experiment<R: BehaviorRuntime>(
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    mut inbound_receiver: UnboundedReceiver<(
+        Span,
+        Option<SimulationId>,
+        InboundToRunnerMsgPayload,
+    )>,
+    outbound_sender: UnboundedSender<OutboundFromRunnerMsg>,
+) -> InProcessResult<()> {
+    let mut runner = ExperimentRunner::<R>::new(&init_msg)?;
+
+    loop {
+        match inbound_receiver.blocking_recv() {
+            Some((span, sim_id, msg)) => {
+                let _span = span.entered();
+                let msg_str = msg.as_str();
+                tracing::debug!("{} runner got sim `{sim_id:?}` inbound {msg_str}", R::NAME);
+                let keep_running = runner.handle_msg(sim_id, msg, &outbound_sender)?;
+                tracing::debug!(
+                    "{} runner handled sim `{sim_id:?}` inbound {msg_str}",
+                    R::NAME
+                );
+                if !keep_running {
+                    tracing::debug!("{} runner has finished execution, stopping", R::NAME);
+                    break;
+                }
+            }
+            None => {
+                tracing::error!("Inbound sender to {} exited", R::NAME);
+                return Err(InProcessError::InboundReceive);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use arrow2::array::{FixedSizeListArray, ListArray, UInt32Array};
+use stateful::{
+    agent::{AgentId, AgentSchema},
+    context::ContextBatch,
+    global::Globals,
+    message::Message,
+    state::StateReadProxy,
+};
+
+use crate::{
+    package::simulation::Seed,
+    runner::in_process::{
+        columns::AgentColumns,
+        error::{InProcessError, InProcessResult},
+    },
+    worker::ContextBatchSync,
+};
+
+const NEIGHBORS_FIELD_NAME: &str = "neighbors";
+const MESSAGES_FIELD_NAME: &str = "messages";
+
+/// Location of an agent in the state snapshot, as `(group index, index in group)`.
+type AgentLocation = [usize; 2];
+
+/// Location of a message in the state snapshot, as `(group index, index in group, index in the
+/// messages of the agent)`.
+type MessageLocation = [usize; 3];
+
+/// The state of the previous step.
+#[derive(Default)]
+struct Snapshot {
+    /// The agents grouped by batch.
+    agents: Vec<AgentColumns>,
+    /// The messages sent by the agents, grouped like `agents`.
+    messages: Vec<Vec<Vec<Message>>>,
+}
+
+/// The data of a simulation run, which behaviors have access to while running a task.
+///
+/// Every part is reference counted, so the context can be moved into a runtime, e.g. into the
+/// store of the WebAssembly instances, without copying the state snapshot.
+#[derive(Clone)]
+pub(in crate::runner) struct SimContext {
+    /// The globals of the simulation run.
+    pub globals: Arc<Globals>,
+    /// The current step of the simulation run.
+    pub step: usize,
+    /// Neighbor locations of every agent, indexed by the position of the agent in the context.
+    neighbors: Arc<Vec<Vec<AgentLocation>>>,
+    /// Locations of the received messages of every agent, indexed by the position of the agent in
+    /// the context.
+    messages: Arc<Vec<Vec<MessageLocation>>>,
+    snapshot: Arc<Snapshot>,
+}
+
+impl SimContext {
+    fn snapshot_agent(
+        &self,
+        [group_index, agent_index]: AgentLocation,
+    ) -> InProcessResult<(&AgentColumns, usize)> {
+        self.snapshot
+            .agents
+            .get(group_index)
+            .filter(|columns| agent_index < columns.num_agents())
+            .map(|columns| (columns, agent_index))
+            .ok_or_else(|| {
+                InProcessError::from(format!(
+                    "Agent ({group_index}, {agent_index}) is not part of the state snapshot"
+                ))
+            })
+    }
+
+    /// Returns the neighbors of the agent at `index` in the context as the columns of their batch
+    /// in the state snapshot and their index in that batch.
+    pub fn neighbors(&self, index: usize) -> InProcessResult<Vec<(&AgentColumns, usize)>> {
+        // The context might not contain neighbors, e.g. if the neighbors package is disabled.
+        let locations = self.neighbors.get(index).map(Vec::as_slice).unwrap_or(&[]);
+        locations
+            .iter()
+            .map(|&location| self.snapshot_agent(location))
+            .collect()
+    }
+
+    /// Returns the messages received by the agent at `index` in the context together with the id
+    /// of their sender.
+    ///
+    /// Messages are only available if the runner was created with received messages enabled, see
+    /// [`SimState::new`].
+    pub fn messages(&self, index: usize) -> InProcessResult<Vec<(AgentId, &Message)>> {
+        let locations = self.messages.get(index).map(Vec::as_slice).unwrap_or(&[]);
+        locations
+            .iter()
+            .map(|&[group_index, agent_index, message_index]| {
+                let (columns, _) = self.snapshot_agent([group_index, agent_index])?;
+                let message = self
+                    .snapshot
+                    .messages
+                    .get(group_index)
+                    .and_then(|group| group.get(agent_index))
+                    .and_then(|messages| messages.get(message_index))
+                    .ok_or_else(|| {
+                        InProcessError::from(format!(
+                            "Message {message_index} of agent ({group_index}, {agent_index}) is \
+                             not part of the state snapshot"
+                        ))
+                    })?;
+                Ok((columns.agent_id(agent_index), message))
+            })
+            .collect()
+    }
+}
+
+/// Simulation run data an in-process runner needs to execute behaviors.
+pub(in crate::runner) struct SimState {
+    pub agent_schema: Arc<AgentSchema>,
+    /// The seed of the simulation run, if it's seeded.
+    seed: Option<Seed>,
+    /// Whether the messages received by agents are loaded from the state snapshot.
+    receives_messages: bool,
+    group_start_indices: Arc<Vec<usize>>,
+    context: SimContext,
+}
+
+impl SimState {
+    pub fn new(
+        agent_schema: Arc<AgentSchema>,
+        globals: Arc<Globals>,
+        seed: Option<Seed>,
+        receives_messages: bool,
+    ) -> Self {
+        Self {
+            agent_schema,
+            seed,
+            receives_messages,
+            group_start_indices: Arc::new(Vec::new()),
+            context: SimContext {
+                globals,
+                step: 0,
+                neighbors: Arc::new(Vec::new()),
+                messages: Arc::new(Vec::new()),
+                snapshot: Arc::new(Snapshot::default()),
+            },
+        }
+    }
+
+    pub fn context(&self) -> &SimContext {
+        &self.context
+    }
+
+    pub fn ctx_batch_sync(&mut self, ctx_batch: ContextBatchSync) -> InProcessResult<()> {
+        self.context.neighbors = Arc::new(context_locations(
+            &ctx_batch.context_batch,
+            NEIGHBORS_FIELD_NAME,
+        )?);
+        if self.receives_messages {
+            self.context.messages = Arc::new(context_locations(
+                &ctx_batch.context_batch,
+                MESSAGES_FIELD_NAME,
+            )?);
+        }
+        self.context.step = ctx_batch.current_step;
+        self.group_start_indices = ctx_batch.state_group_start_indices;
+        Ok(())
+    }
+
+    /// Replaces the globals, which are used by behaviors from the next task onwards.
+    pub fn update_globals(&mut self, globals: Arc<Globals>) {
+        self.context.globals = globals;
+    }
+
+    /// Loads the agents and, if received messages are enabled, their sent messages of the
+    /// snapshot, so neighbors and received messages can be looked up while running behaviors.
+    ///
+    /// The proxy is dropped afterwards, so the snapshot batches are not locked while the runner is
+    /// idle.
+    pub fn state_snapshot_sync(&mut self, state_proxy: StateReadProxy) -> InProcessResult<()> {
+        let agents: Vec<AgentColumns> = state_proxy
+            .agent_pool()
+            .batches_iter()
+            .map(|agent_batch| {
+                AgentColumns::from_record_batch(
+                    agent_batch.batch.record_batch()?,
+                    &self.agent_schema,
+                )
+            })
+            .collect::<InProcessResult<_>>()?;
+        let messages: Vec<Vec<Vec<Message>>> = if self.receives_messages {
+            state_proxy
+                .message_pool()
+                .batches_iter()
+                .map(|message_batch| message_batch.messages())
+                .collect::<stateful::Result<_>>()?
+        } else {
+            Vec::new()
+        };
+        self.context.snapshot = Arc::new(Snapshot { agents, messages });
+        Ok(())
+    }
+
+    /// Returns the index of the first agent of the group at `group_index` in the context.
+    pub fn group_start_index(&self, group_index: usize) -> InProcessResult<usize> {
+        self.group_start_indices
+            .get(group_index)
+            .copied()
+            .ok_or_else(|| {
+                InProcessError::from(format!("Missing start index of group {group_index}"))
+            })
+    }
+
+    /// Returns the seed of the agent with `agent_id` in the current step, if the simulation run is
+    /// seeded.
+    pub fn agent_seed(&self, agent_id: &AgentId) -> Option<Seed> {
+        self.seed
+            .map(|seed| seed.for_agent(self.context.step, agent_id))
+    }
+}
+
+/// Reads the locations stored for every agent in the column `field_name` of the context batch.
+///
+/// The column is a list of fixed-size lists of indices, e.g. `(group index, index in group)` for
+/// neighbors. The column is missing if the corresponding context package is disabled.
+fn context_locations<const N: usize>(
+    context_batch: &ContextBatch,
+    field_name: &str,
+) -> InProcessResult<Vec<Vec<[usize; N]>>> {
+    let record_batch = context_batch.record_batch();
+    let column_index = match record_batch
+        .schema()
+        .fields
+        .iter()
+        .position(|field| field.name == field_name)
+    {
+        Some(index) => index,
+        None => return Ok(Vec::new()),
+    };
+
+    let column = record_batch
+        .column(column_index)
+        .as_any()
+        .downcast_ref::<ListArray<i32>>()
+        .ok_or_else(|| {
+            InProcessError::from(format!("Context column `{field_name}` must be a list"))
+        })?;
+    let locations = column
+        .values()
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .ok_or_else(|| {
+            InProcessError::from(format!(
+                "Locations in context column `{field_name}` must be fixed-size lists"
+            ))
+        })?;
+    let indices = locations
+        .values()
+        .as_any()
+        .downcast_ref::<UInt32Array>()
+        .ok_or_else(|| {
+            InProcessError::from(format!(
+                "Indices in context column `{field_name}` must be unsigned integers"
+            ))
+        })?
+        .values();
+
+    if locations.size() != N {
+        return Err(InProcessError::from(format!(
+            "Locations in context column `{field_name}` must have {N} indices, not {}",
+            locations.size()
+        )));
+    }
+    Ok(column
+        .offsets()
+        .windows(2)
+        .map(|window| {
+            (window[0] as usize..window[1] as usize)
+                .map(|location| {
+                    std::array::from_fn(|offset| indices[location * N + offset] as usize)
+                })
+                .collect()
+        })
+        .collect())
+}
@@ -12,6 +12,7 @@
 // - Modules always evaluate to a `promise` which resolves to `undefined` without the "--harmony_top_level_await" flag, https://github.com/denoland/deno/issues/3696#issuecomment-578488613.
 //   To access values inside a module use v8::Module::get_module_namespace.
 
+mod compile;
 mod conversion;
 mod data_ffi;
 pub(crate) mod error;
@@ -33,6 +34,7 @@ use arrow2::{
 };
 use memory::shared_memory::arrow_continuation;
 
+pub use self::compile::{compile_javascript_behavior, JavaScriptSyntaxError};
 pub(crate) use self::{
     error::{JavaScriptError, JavaScriptResult},
     runner::JavaScriptRunner,
@@ -120,7 +122,11 @@ impl<'s> JsPackage<'s> {
     }
 }
 
+/// Reads the source code at `path`, preferring the sources embedded into the binary.
 fn read_file(path: &str) -> JavaScriptResult<String> {
+    if let Some(source) = modules::embedded_source(path) {
+        return Ok(source.to_string());
+    }
     fs::read_to_string(path).map_err(|err| JavaScriptError::IO(path.into(), err))
 }
 
@@ -45,6 +45,12 @@ const parse_any_type_fields = (metadata) => {
   return any_type_fields;
 };
 
+/// Returns a map from the names of categorical fields to their categories.
+const parse_categorical_fields = (metadata) => {
+  const categorical_fields = metadata.get("categorical_fields");
+  return new Map(Object.entries(JSON.parse(categorical_fields || "{}")));
+};
+
 const load_vectors = (record_batch_bytes, schema) => {
   const reader = new arrow.MessageReader(record_batch_bytes);
   const msg = reader.readMessage();
@@ -59,6 +65,7 @@ const load_vectors = (record_batch_bytes, schema) => {
   );
   const vector_list = loader.visitMany(schema.fields);
   const any_type_fields = parse_any_type_fields(schema.metadata);
+  const categorical_fields = parse_categorical_fields(schema.metadata);
   // Unnecessary:
   // const record_batch = new arrow.RecordBatch(schema, header.length, vector_list);
 
@@ -68,12 +75,13 @@ const load_vectors = (record_batch_bytes, schema) => {
     const vector = new arrow.makeVector(vector_list[i]);
     const field = schema.fields[i];
     vector.type.is_any = any_type_fields.has(field.name);
+    vector.type.categories = categorical_fields.get(field.name);
     vectors[field.name] = vector;
   }
   return vectors;
 };
 
-const load_markers = (shared_bytes) => {
+export const load_markers = (shared_bytes) => {
   // `shared_bytes` should be ArrayBuffer.
   const dataview = new DataView(shared_bytes);
   const m = {
@@ -105,7 +113,7 @@ const load_markers = (shared_bytes) => {
   return m;
 };
 
-const load_marked_vectors = (shared_bytes, schema) => {
+export const load_marked_vectors = (shared_bytes, schema) => {
   const markers = load_markers(shared_bytes); // Record batch bytes are subset of all shared.
   const data_end = markers.data_offset + markers.data_size;
   const record_batch_offset = markers.meta_offset;
@@ -192,6 +200,35 @@ Batch.prototype.load_missing_cols = function (schema, loaders) {
   }
 };
 
+const is_int64 = (type) => arrow.DataType.isInt(type) && type.bitWidth === 64;
+
+/// Whether `type` contains 64-bit integers, which JS Arrow only accepts as `BigInt`s.
+const contains_int64 = (type) =>
+  is_int64(type) ||
+  (type.children || []).some((child) => contains_int64(child.type));
+
+/// Converts the numbers in `value` (which has the Arrow type `type`) to `BigInt`s where `type`
+/// requires 64-bit integers.
+const numbers_to_bigints = (value, type) => {
+  if (value === null || value === undefined || !contains_int64(type)) {
+    return value;
+  }
+  if (is_int64(type)) {
+    // Throws a `RangeError` if `value` isn't an integer.
+    return typeof value === "bigint" ? value : BigInt(value);
+  }
+  if (arrow.DataType.isStruct(type)) {
+    const converted = { ...value };
+    for (const child of type.children) {
+      converted[child.name] = numbers_to_bigints(value[child.name], child.type);
+    }
+    return converted;
+  }
+  // List or fixed-size list
+  const child_type = type.children[0].type;
+  return Array.from(value, (elem) => numbers_to_bigints(elem, child_type));
+};
+
 const array_data_from_col = (col, field_type) => {
   // TODO: Would `new arrow.Builder` work?
   // TODO: Faster way to convert JS array to vector than using `Builder`?
@@ -258,10 +295,23 @@ Batch.prototype.flush_changes = function (schema, skip) {
     // might be missing from `cols`. (But columns that
     // are in `cols` should always be in schema too.)
 
-    if (this.vectors[field.name].type.is_any) {
+    const type = this.vectors[field.name].type;
+    if (type.is_any) {
       for (var i_agent = 0; i_agent < col.length; ++i_agent) {
         col[i_agent] = JSON.stringify(col[i_agent]);
       }
+    } else if (type.categories) {
+      for (var i_agent = 0; i_agent < col.length; ++i_agent) {
+        col[i_agent] = hash_util.category_to_index(
+          col[i_agent],
+          type.categories,
+          field.name,
+        );
+      }
+    } else if (contains_int64(field.type)) {
+      for (var i_agent = 0; i_agent < col.length; ++i_agent) {
+        col[i_agent] = numbers_to_bigints(col[i_agent], field.type);
+      }
     }
     const array_data = array_data_from_col(col, field.type);
     const data = ffi_data_from_array_data(array_data);
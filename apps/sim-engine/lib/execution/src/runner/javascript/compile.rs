@@ -0,0 +1,104 @@
+use std::sync::Once;
+
+use thiserror::Error as ThisError;
+
+use crate::runner::javascript::utils::new_js_string;
+
+/// A behavior is loaded as the body of a function by the JavaScript runner, see `load_behaviors`
+/// in the _package.js_ of the behavior execution package. The wrapper adds one line in front of
+/// the behavior code.
+const BEHAVIOR_PREFIX: &str = "(function (hash_stdlib, hstd, console) {\n";
+const BEHAVIOR_SUFFIX: &str = "\nreturn behavior;\n})";
+
+/// A JavaScript behavior which could not be compiled.
+#[derive(ThisError, Debug, Clone, PartialEq, Eq)]
+#[error("{message}")]
+pub struct JavaScriptSyntaxError {
+    pub message: String,
+    /// 1-based line in the source of the behavior
+    pub line: Option<usize>,
+    /// 1-based column in the source of the behavior
+    pub column: Option<usize>,
+}
+
+/// Initializes V8 for the current process.
+///
+/// V8 may only be initialized once, subsequent calls do nothing.
+pub(in crate::runner::javascript) fn initialize_v8() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let platform = v8::new_default_platform(0, false).make_shared();
+        v8::V8::initialize_platform(platform);
+        v8::V8::initialize();
+    });
+}
+
+/// Compiles the JavaScript `source` of a behavior in V8 without running it.
+///
+/// The source is compiled in the same way as the JavaScript runner loads it, so any syntax error
+/// reported here would make the experiment fail. Errors which only occur when running the
+/// behavior, e.g. a missing `behavior` function, are not detected.
+///
+/// # Errors
+///
+/// - if `source` is not valid JavaScript
+pub fn compile_javascript_behavior(source: &str) -> Result<(), JavaScriptSyntaxError> {
+    initialize_v8();
+
+    let mut isolate = v8::Isolate::new(v8::Isolate::create_params());
+    let mut handle_scope = v8::HandleScope::new(&mut isolate);
+    let context = v8::Context::new(&mut handle_scope);
+    let mut context_scope = v8::ContextScope::new(&mut handle_scope, context);
+    let mut try_catch_scope = v8::TryCatch::new(&mut context_scope);
+
+    let code = new_js_string(
+        &mut try_catch_scope,
+        format!("{BEHAVIOR_PREFIX}{source}{BEHAVIOR_SUFFIX}"),
+    );
+    if v8::Script::compile(&mut try_catch_scope, code, None).is_some() {
+        return Ok(());
+    }
+
+    let message = match try_catch_scope.message() {
+        Some(message) => message,
+        None => {
+            return Err(JavaScriptSyntaxError {
+                message: String::from("Could not compile behavior"),
+                line: None,
+                column: None,
+            });
+        }
+    };
+    let text = message
+        .get(&mut try_catch_scope)
+        .to_rust_string_lossy(&mut try_catch_scope);
+    let line = message
+        .get_line_number(&mut try_catch_scope)
+        .and_then(|line| line.checked_sub(BEHAVIOR_PREFIX.lines().count()))
+        .filter(|&line| line > 0);
+    // The column is 0-based and not shifted, as the prefix ends with a line break
+    let column = line.map(|_| message.get_start_column() + 1);
+    Err(JavaScriptSyntaxError {
+        message: text,
+        line,
+        column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_behavior() {
+        compile_javascript_behavior("function behavior(state, context) {\n  state.age += 1;\n}")
+            .unwrap();
+
+        let error =
+            compile_javascript_behavior("function behavior(state, context) {\n  state.age += ;\n}")
+                .unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(16));
+        assert!(error.message.contains("SyntaxError"), "{}", error.message);
+    }
+}
@@ -1,3 +1,5 @@
+import { load_table } from "./lib/execution/src/runner/javascript/dataset.js";
+
 /// `x` must not contain reference cycles.
 const deepfreeze = (x) => {
   for (var k in x) {
@@ -45,6 +47,10 @@ const gen_agent_ctx = (ctx_schema, getters) => {
     return this.__experiment_ctx.data();
   };
 
+  AgentContext.prototype.dataTable = function (name) {
+    return this.__experiment_ctx.dataTable(name);
+  };
+
   AgentContext.prototype.globals = function () {
     return this.__globals;
   };
@@ -86,6 +92,10 @@ const gen_group_ctx = (AgentContext) => {
     return this.__experiment_ctx.data();
   };
 
+  GroupContext.prototype.dataTable = function (name) {
+    return this.__experiment_ctx.dataTable(name);
+  };
+
   GroupContext.prototype.globals = function () {
     return this.__globals;
   };
@@ -136,10 +146,19 @@ export const gen_sim_ctx = (ctx_schema, getters) => {
     return this.__experiment_ctx.data();
   };
 
+  SimContext.prototype.dataTable = function (name) {
+    return this.__experiment_ctx.dataTable(name);
+  };
+
   SimContext.prototype.globals = function () {
     return this.__globals;
   };
 
+  /// Invalidates existing `GroupContext` and `AgentContext` objects.
+  SimContext.prototype.set_globals = function (globals) {
+    this.__globals = deepfreeze(globals);
+  };
+
   SimContext.prototype.step = function () {
     return this.__current_step;
   };
@@ -195,14 +214,59 @@ export const gen_sim_ctx = (ctx_schema, getters) => {
   return Object.seal(SimContext);
 };
 
-export const ExperimentContext = function (datasets) {
-  this.__datasets = deepfreeze(datasets);
+/// Tabular datasets are read from shared memory, other datasets are passed as JSON strings. In
+/// `datasets`, every dataset is the array or object it would have been parsed into, which is
+/// created when the dataset is accessed for the first time. `tables` contains the typed tables of
+/// the tabular datasets.
+const load_datasets = (raw_datasets) => {
+  const datasets = {};
+  const tables = {};
+  for (const name in raw_datasets) {
+    const raw = raw_datasets[name];
+    let load;
+    if (typeof raw === "string") {
+      load = () => JSON.parse(raw);
+    } else {
+      const table = (tables[name] = load_table(raw));
+      load = () => table.to_json();
+    }
+
+    let parsed;
+    Object.defineProperty(datasets, name, {
+      enumerable: true,
+      get: () => {
+        if (parsed === undefined) {
+          parsed = deepfreeze(load());
+        }
+        return parsed;
+      },
+    });
+  }
+  return { datasets: Object.freeze(datasets), tables: Object.freeze(tables) };
+};
+
+export const ExperimentContext = function (raw_datasets) {
+  const { datasets, tables } = load_datasets(raw_datasets);
+  this.__datasets = datasets;
+  this.__tables = tables;
 };
 
 ExperimentContext.prototype.data = function () {
   return this.__datasets;
 };
 
+/// Returns the typed table of the tabular dataset `name`.
+ExperimentContext.prototype.dataTable = function (name) {
+  const table = this.__tables[name];
+  if (!table) {
+    throw new RangeError(
+      `${JSON.stringify(name)} is not a tabular dataset, ` +
+        `expected one of ${JSON.stringify(Object.keys(this.__tables))}`,
+    );
+  }
+  return table;
+};
+
 export const SimInitContext = function (experiment_ctx, globals, agent_schema) {
   this.__experiment_ctx = experiment_ctx;
   this.__globals = deepfreeze(globals);
@@ -213,6 +277,10 @@ SimInitContext.prototype.data = function () {
   return this.__experiment_ctx.data();
 };
 
+SimInitContext.prototype.dataTable = function (name) {
+  return this.__experiment_ctx.dataTable(name);
+};
+
 SimInitContext.prototype.globals = function () {
   return this.__globals;
 };
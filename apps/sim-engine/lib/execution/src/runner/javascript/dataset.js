@@ -0,0 +1,96 @@
+// noinspection BadExpressionStatementJS
+import { arrow } from "./lib/execution/src/runner/javascript/apache-arrow-bundle.js";
+import {
+  load_markers,
+  load_marked_vectors,
+} from "./lib/execution/src/runner/javascript/batch.js";
+import * as hash_util from "./lib/execution/src/runner/javascript/hash_util.js";
+
+const value_at = (vector, index) => {
+  const value = vector.get(index);
+  // Integer columns only contain values which fit into a number.
+  return typeof value === "bigint" ? Number(value) : value;
+};
+
+/// A tabular dataset, which is read directly from shared memory.
+///
+/// `shared_bytes` should be the ArrayBuffer of the dataset's batch.
+const Table = function (shared_bytes) {
+  const markers = load_markers(shared_bytes);
+  const schema = new arrow.MessageReader(
+    new Uint8Array(shared_bytes, markers.schema_offset, markers.schema_size),
+  ).readSchema();
+  const vectors = load_marked_vectors(shared_bytes, schema);
+
+  this.column_names = Object.freeze(schema.fields.map((field) => field.name));
+  this.__vectors = this.column_names.map((name) => vectors[name]);
+  this.__columns = []; // Column index --> loaded column.
+  this.__is_csv = schema.metadata.get("dataset_format") === "csv";
+  const header = schema.metadata.get("csv_header");
+  this.__header = header ? Object.freeze(JSON.parse(header)) : null;
+
+  this.num_rows = this.__vectors[0].length;
+};
+
+/// Returns the column `name_or_index` as array of numbers, booleans, strings or `null`s.
+/// The header of a CSV dataset is not part of the column.
+Table.prototype.column = function (name_or_index) {
+  const index =
+    typeof name_or_index === "number"
+      ? name_or_index
+      : this.column_names.indexOf(name_or_index);
+  const vector = this.__vectors[index];
+  if (!vector) {
+    throw new RangeError(
+      `Dataset has no column ${JSON.stringify(name_or_index)}, ` +
+        `expected one of ${JSON.stringify(this.column_names)}`,
+    );
+  }
+
+  let column = this.__columns[index];
+  if (!column) {
+    column = this.__columns[index] = Object.freeze(
+      hash_util.load_shallow(vector),
+    );
+  }
+  return column;
+};
+
+/// Returns the row at `index` as object from column names to values, or `undefined` if there is
+/// no such row. The header of a CSV dataset is not counted as row.
+Table.prototype.row = function (index) {
+  if (!(index >= 0 && index < this.num_rows)) return undefined;
+
+  const row = {};
+  for (var i = 0; i < this.column_names.length; ++i) {
+    row[this.column_names[i]] = value_at(this.__vectors[i], index);
+  }
+  return Object.freeze(row);
+};
+
+/// Returns the array the dataset would have been parsed into, i.e. an array of rows of strings for
+/// CSV datasets (including the header, if any) and an array of objects for JSON datasets.
+Table.prototype.to_json = function () {
+  const rows = [];
+  if (!this.__is_csv) {
+    for (var i = 0; i < this.num_rows; ++i) {
+      rows.push({ ...this.row(i) });
+    }
+    return rows;
+  }
+
+  if (this.__header) rows.push([...this.__header]);
+  for (var i = 0; i < this.num_rows; ++i) {
+    const cells = [];
+    for (var j = 0; j < this.__vectors.length; ++j) {
+      const value = value_at(this.__vectors[j], i);
+      cells[j] = value === null ? "" : String(value);
+    }
+    rows.push(cells);
+  }
+  return rows;
+};
+Table.prototype.toJSON = Table.prototype.to_json;
+
+/// `dataset` should have `id` (string) and `mem` (ArrayBuffer) fields.
+export const load_table = (dataset) => Object.freeze(new Table(dataset.mem));
@@ -7,6 +7,7 @@ use crate::runner::JavaScriptError;
 pub(in crate::runner::javascript) struct Embedded<'s> {
     pub(in crate::runner::javascript) start_experiment: Function<'s>,
     pub(in crate::runner::javascript) start_sim: Function<'s>,
+    pub(in crate::runner::javascript) update_globals: Function<'s>,
     pub(in crate::runner::javascript) run_task: Function<'s>,
     pub(in crate::runner::javascript) ctx_batch_sync: Function<'s>,
     pub(in crate::runner::javascript) state_sync: Function<'s>,
@@ -43,14 +44,16 @@ impl<'s> Embedded<'s> {
         let [
             start_experiment,
             start_sim,
+            update_globals,
             run_task,
             ctx_batch_sync,
             state_sync,
             state_interim_sync,
             state_snapshot_sync,
-        ]: [Function<'_>; 7] = [
+        ]: [Function<'_>; 8] = [
             "start_experiment",
             "start_sim",
+            "update_globals",
             "run_task",
             "ctx_batch_sync",
             "state_sync",
@@ -79,6 +82,7 @@ impl<'s> Embedded<'s> {
         Ok(Embedded {
             start_experiment,
             start_sim,
+            update_globals,
             run_task,
             ctx_batch_sync,
             state_sync,
@@ -87,3 +91,53 @@ impl<'s> Embedded<'s> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, env, fs, process::Command, rc::Rc};
+
+    use super::*;
+    use crate::runner::javascript::{compile::initialize_v8, modules::ModuleMap};
+
+    /// Set for the child process started by [`import_outside_of_source_tree`].
+    const CHILD_ENV: &str = "HASH_TEST_JS_RUNNER_CHILD";
+
+    fn import_runner() {
+        initialize_v8();
+
+        let mut isolate = v8::Isolate::new(v8::Isolate::create_params());
+        let mut handle_scope = v8::HandleScope::new(&mut isolate);
+        let context = v8::Context::new(&mut handle_scope);
+        let mut context_scope = v8::ContextScope::new(&mut handle_scope, context);
+        context_scope.set_slot(Rc::new(RefCell::new(ModuleMap::new())));
+
+        if let Err(err) = Embedded::import_common_js_files(&mut context_scope) {
+            panic!("Could not import the JavaScript runner: {err}");
+        }
+    }
+
+    /// The working directory is shared by all tests, so the runner is imported by running this
+    /// test again in a child process started in a directory outside of the source tree.
+    #[test]
+    fn import_outside_of_source_tree() {
+        if env::var_os(CHILD_ENV).is_some() {
+            import_runner();
+            return;
+        }
+
+        let working_dir = env::temp_dir().join(format!("hash-js-runner-{}", std::process::id()));
+        fs::create_dir_all(&working_dir).unwrap();
+        let status = Command::new(env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "runner::javascript::embedded::tests::import_outside_of_source_tree",
+                "--nocapture",
+            ])
+            .env(CHILD_ENV, "1")
+            .current_dir(&working_dir)
+            .status();
+        fs::remove_dir_all(&working_dir).unwrap();
+
+        assert!(status.unwrap().success());
+    }
+}
@@ -62,7 +62,7 @@ pub enum JavaScriptError {
     #[error("Error in embedded JavaScript: {0}")]
     Embedded(String),
 
-    #[error("Task target must be Python, JavaScript, Rust, Dynamic or Main, not {0}")]
+    #[error("Task target must be Python, JavaScript, Rust, Wasm, Dynamic or Main, not {0}")]
     UnknownTarget(String),
 
     #[error("Couldn't send inbound message to runner: {0}")]
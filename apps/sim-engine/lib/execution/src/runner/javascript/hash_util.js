@@ -51,14 +51,44 @@ export const json_deepcopy = (x, _depth) => {
   return copy;
 };
 
+const MIN_SAFE_BIGINT = BigInt(Number.MIN_SAFE_INTEGER);
+const MAX_SAFE_BIGINT = BigInt(Number.MAX_SAFE_INTEGER);
+
+// JS Arrow returns 64-bit integers (i.e. `integer` fields) as `BigInt`s, which can't be mixed with
+// numbers in arithmetic, so they are loaded as numbers if a number can represent them exactly.
+// Integers beyond `Number.MAX_SAFE_INTEGER` are kept as `BigInt`s instead of silently losing
+// precision. Both are accepted when writing the field.
+const from_arrow_value = (value) =>
+  typeof value === "bigint" &&
+  value >= MIN_SAFE_BIGINT &&
+  value <= MAX_SAFE_BIGINT
+    ? Number(value)
+    : value;
+
+/// Returns the index of `category` in `categories`, which is how categorical fields are stored.
+/// Throws an `Error` if `category` isn't one of the `categories`.
+export const category_to_index = (category, categories, field_name) => {
+  if (category === null || category === undefined) return null;
+
+  const index = categories.indexOf(category);
+  if (index === -1) {
+    throw new Error(
+      `${JSON.stringify(category)} is not a category of the field ${field_name}, ` +
+        `expected one of ${JSON.stringify(categories)}`,
+    );
+  }
+  return index;
+};
+
 // NB: If input is an `any`-type column, will return an array of strings (containing JSON).
+//     If input is a categorical column, will return an array of category indices.
 // TODO: Change arguments after upgrading Arrow (is_nullable and is_any will probably become
 //       unnecessary as we want to store them in column metadata).
 export const load_shallow = (vector, is_nullable, is_any) => {
   // `vector.toArray` returns array-like (in some cases? TODO), not actual array.
   const shallow = [];
   for (var i = 0; i < vector.length; ++i) {
-    shallow[i] = vector.get(i);
+    shallow[i] = from_arrow_value(vector.get(i));
   }
   return shallow;
 };
@@ -86,7 +116,7 @@ const _vector_to_array = (vector) => {
   // TODO: This function is called often enough that it
   //       might be worth benchmarking and micro-optimizing.
   if (!vector || typeof vector.toArray === "undefined") {
-    return vector; // `vector` isn't actually a vector.
+    return from_arrow_value(vector); // `vector` isn't actually a vector.
   }
   const shallow = load_shallow(vector);
 
@@ -135,6 +165,16 @@ export const load_full = (vector, is_nullable, is_any) => {
     }
     return array;
   }
+  if (vector.type.categories) {
+    // Can only have top-level categorical fields.
+    const categories = vector.type.categories;
+    const array = [];
+    for (var j = 0; j < vector.length; ++j) {
+      const index = vector.get(j);
+      array[j] = index === null ? null : categories[index];
+    }
+    return array;
+  }
   return _vector_to_array(vector);
 };
 
@@ -151,9 +191,13 @@ export const load_elem = (elem, type) => {
     //       Otherwise would need `return elem ? JSON.parse(elem) : null`;
     return JSON.parse(elem);
   }
+  if (type.categories) {
+    // Can only have top-level categorical fields.
+    return elem === null ? null : type.categories[elem];
+  }
 
   const children = type.children;
-  if (!children) return elem;
+  if (!children) return from_arrow_value(elem);
   if (_is_primitive_or_list(children)) return _vector_to_array(elem);
   return _struct_vec_to_obj(elem, children);
 };
@@ -222,3 +266,39 @@ export const uuid_to_str = (bytes) => {
   ).toLowerCase();
   return uuid;
 };
+
+/// Returns a version 4 UUID, which is derived from the string `key`, so the same key always
+/// results in the same UUID. Used for the agent ids of seeded simulation runs.
+export const uuid_from_key = (key) => {
+  // cyrb128, a fast, non-cryptographic 128-bit string hash
+  let h1 = 1779033703;
+  let h2 = 3144134277;
+  let h3 = 1013904242;
+  let h4 = 2773480762;
+  for (let i = 0; i < key.length; ++i) {
+    const k = key.charCodeAt(i);
+    h1 = h2 ^ Math.imul(h1 ^ k, 597399067);
+    h2 = h3 ^ Math.imul(h2 ^ k, 2869860233);
+    h3 = h4 ^ Math.imul(h3 ^ k, 951274213);
+    h4 = h1 ^ Math.imul(h4 ^ k, 2716044179);
+  }
+  h1 = Math.imul(h3 ^ (h1 >>> 18), 597399067);
+  h2 = Math.imul(h4 ^ (h2 >>> 22), 2869860233);
+  h3 = Math.imul(h1 ^ (h3 >>> 17), 951274213);
+  h4 = Math.imul(h2 ^ (h4 >>> 19), 2716044179);
+  h1 ^= h2 ^ h3 ^ h4;
+  h2 ^= h1;
+  h3 ^= h1;
+  h4 ^= h1;
+
+  const bytes = new Uint8Array(16);
+  const view = new DataView(bytes.buffer);
+  view.setUint32(0, h1 >>> 0);
+  view.setUint32(4, h2 >>> 0);
+  view.setUint32(8, h3 >>> 0);
+  view.setUint32(12, h4 >>> 0);
+  bytes[6] = (bytes[6] & 0x0f) | 0x40; // Version 4
+  bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+  return uuid_to_str(bytes);
+};
+
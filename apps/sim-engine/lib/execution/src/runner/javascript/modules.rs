@@ -7,6 +7,69 @@ use super::{
 };
 use crate::runner::JavaScriptError;
 
+/// JavaScript sources compiled into the binary, keyed by the specifier they are imported with.
+///
+/// The runner and the packages import each other with paths relative to the engine's root
+/// directory in the repository. Serving these paths from memory allows the engine to run
+/// independently of the current working directory.
+const EMBEDDED_SOURCES: &[(&str, &str)] = &[
+    (
+        "./lib/execution/src/runner/javascript/apache-arrow-bundle.js",
+        include_str!("apache-arrow-bundle.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/batch.js",
+        include_str!("batch.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/context.js",
+        include_str!("context.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/dataset.js",
+        include_str!("dataset.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/hash_stdlib.js",
+        include_str!("hash_stdlib.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/hash_util.js",
+        include_str!("hash_util.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/runner.js",
+        include_str!("runner.js"),
+    ),
+    (
+        "./lib/execution/src/runner/javascript/state.js",
+        include_str!("state.js"),
+    ),
+    (
+        "./lib/execution/src/package/simulation/context/agent_messages/package.js",
+        include_str!("../../package/simulation/context/agent_messages/package.js"),
+    ),
+    (
+        "./lib/execution/src/package/simulation/context/neighbors/package.js",
+        include_str!("../../package/simulation/context/neighbors/package.js"),
+    ),
+    (
+        "./lib/execution/src/package/simulation/init/js_py/package.js",
+        include_str!("../../package/simulation/init/js_py/package.js"),
+    ),
+    (
+        "./lib/execution/src/package/simulation/state/behavior_execution/package.js",
+        include_str!("../../package/simulation/state/behavior_execution/package.js"),
+    ),
+];
+
+/// Returns the source code embedded for `path`, if any.
+pub(in crate::runner::javascript) fn embedded_source(path: &str) -> Option<&'static str> {
+    EMBEDDED_SOURCES
+        .iter()
+        .find_map(|(specifier, source)| (*specifier == path).then_some(*source))
+}
+
 /// Caches modules to avoid evaluating them twice which is against the [JavaScript
 /// Specifications].
 ///
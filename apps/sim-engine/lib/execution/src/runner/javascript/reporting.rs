@@ -16,12 +16,15 @@ pub(in crate::runner::javascript) fn array_to_user_errors<'s>(
             .expect("UserJavaScriptErrors array conversion failed");
         let errors = (0..array.length())
             .map(|i| {
-                let element = array.get_index(scope, i).ok_or_else(|| {
-                    JavaScriptError::V8(format!(
-                        "Could not get error at index {i} in the UserJavaScriptErrors array"
-                    ))
-                });
-                element.map(|err| UserError(format!("{err:?}")))
+                let element = array
+                    .get_index(scope, i)
+                    .and_then(|err| err.to_string(scope))
+                    .ok_or_else(|| {
+                        JavaScriptError::V8(format!(
+                            "Could not get error at index {i} in the UserJavaScriptErrors array"
+                        ))
+                    });
+                element.map(|err| UserError(err.to_rust_string_lossy(scope)))
             })
             .collect();
 
@@ -8,8 +8,8 @@ use crate::{
     runner::{
         comms::{ExperimentInitRunnerMsg, InboundToRunnerMsgPayload, OutboundFromRunnerMsg},
         javascript::{
-            modules::ModuleMap, near_heap_limit_callback, thread_local_runner::ThreadLocalRunner,
-            MB,
+            compile::initialize_v8, modules::ModuleMap, near_heap_limit_callback,
+            thread_local_runner::ThreadLocalRunner, MB,
         },
         JavaScriptError,
     },
@@ -32,9 +32,7 @@ pub(in crate::runner::javascript) fn run_experiment(
 
     tokio::pin! {
         let impl_future = async {
-            let platform = v8::new_default_platform(0, false).make_shared();
-            v8::V8::initialize_platform(platform);
-            v8::V8::initialize();
+            initialize_v8();
 
             // 0 makes V8 use its default value
             let js_runner_initial_heap_constraint = init_msg.runner_config.js_runner_initial_heap_constraint.unwrap_or(0);
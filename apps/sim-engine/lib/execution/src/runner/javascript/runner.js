@@ -18,8 +18,6 @@ const make_hash_set = (fields) => {
 
 export function start_experiment(datasets, pkg_init_msgs, pkg_fns) {
   this.batches = new Batches();
-  for (var dataset_name in datasets)
-    datasets[dataset_name] = JSON.parse(datasets[dataset_name]);
   this.experiment_ctx = new ExperimentContext(datasets);
   this.sims = {};
 
@@ -170,6 +168,12 @@ export function start_sim(
   sim.GroupState = gen_group_state(sim.schema.agent, sim.state_getters);
 }
 
+/// Replaces the globals of a running simulation run. The new globals are visible to behaviors from the
+/// next task onwards.
+export function update_globals(sim_id, globals) {
+  this.sims[sim_id].ctx.set_globals(JSON.parse(globals));
+}
+
 export function run_task(sim_id, i_group, pkg_id, task_message) {
   const pkg = this.pkgs[pkg_id];
   const pkg_run_task = pkg.run_task;
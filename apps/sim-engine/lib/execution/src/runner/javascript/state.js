@@ -134,16 +134,18 @@ const gen_agent_state = (agent_schema, getters) => {
   /// Similarly to `get` and `set`, if the user mutates the arguments
   /// of `addMessage` later, it won't affect the agent's state.
 
-  /// `to` must be either a string or an object. If it's a string,
-  /// it must be a single agent id or name. If it's an object, it must
-  /// be an array of agent ids and/or names. `to` is automatically
+  /// `to` must be either a recipient or an array of recipients. A
+  /// recipient is either an agent id or name, or a recipient object
+  /// like `{ within: 5 }`, `{ topic: "news" }`, or `{ to: "a", delay: 3 }`.
+  /// Recipient objects are stored as JSON strings. `to` is automatically
   /// converted to an array if it's not one already.
 
   /// `data` is an optional argument. `data` must be JSON-serializable.
   AgentState.prototype.addMessage = function (to, msg_type, data) {
+    const recipient = (r) => (typeof r === "string" ? r : JSON.stringify(r));
     // Keeps native messages native and JSON messages as JSON.
     let new_message = {
-      to: typeof to === "string" ? [to] : to.slice(),
+      to: Array.isArray(to) ? to.map(recipient) : [recipient(to)],
       type: msg_type, // `msg_type` is a string, so don't need to deepcopy it.
       data: hash_util.json_deepcopy(data),
     };
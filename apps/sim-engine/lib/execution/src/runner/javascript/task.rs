@@ -16,6 +16,7 @@ pub(in crate::runner::javascript) fn get_next_task<'s>(
                 "JavaScript" => MessageTarget::JavaScript,
                 "Python" => MessageTarget::Python,
                 "Rust" => MessageTarget::Rust,
+                "Wasm" => MessageTarget::Wasm,
                 "Dynamic" => MessageTarget::Dynamic,
                 "Main" => MessageTarget::Main,
                 _ => return Err(JavaScriptError::UnknownTarget(target)),
@@ -9,7 +9,7 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::Span;
 
 use super::{
-    conversion::{bytes_to_js, sim_id_to_js},
+    conversion::{batch_to_js, bytes_to_js, sim_id_to_js},
     embedded::Embedded,
     error::{JavaScriptError, JavaScriptResult},
     schema_to_stream_bytes,
@@ -60,15 +60,21 @@ impl<'s> ThreadLocalRunner<'s> {
         for (dataset_name, dataset) in shared_ctx.datasets.iter() {
             let js_name = new_js_string(scope, &dataset_name);
 
-            let json = dataset.data();
-            // TODO: Use `from_utf8_unchecked` instead here?
-            //       (Since datasets' json can be quite large.)
-            let json = std::str::from_utf8(json)
-                .map_err(|_| JavaScriptError::Unique("Dataset not utf8".into()))?;
-            let json = new_js_string(scope, json);
+            // Tabular datasets are read from shared memory by JS, other datasets are passed as
+            // JSON string and parsed on first access.
+            let js_dataset = if dataset.is_tabular() {
+                batch_to_js(scope, dataset.segment())?
+            } else {
+                let json = dataset.data();
+                // TODO: Use `from_utf8_unchecked` instead here?
+                //       (Since datasets' json can be quite large.)
+                let json = std::str::from_utf8(json)
+                    .map_err(|_| JavaScriptError::Unique("Dataset not utf8".into()))?;
+                new_js_string(scope, json).into()
+            };
 
             js_datasets
-                .set(scope, js_name.into(), json.into())
+                .set(scope, js_name.into(), js_dataset)
                 .ok_or_else(|| {
                     JavaScriptError::V8("Could not set property on Object".to_string())
                 })?;
@@ -215,6 +221,33 @@ impl<'s> ThreadLocalRunner<'s> {
         Ok(())
     }
 
+    /// Replaces the globals of the simulation run in the JavaScript runtime.
+    fn update_globals(
+        &mut self,
+        scope: &mut v8::HandleScope<'s>,
+        sim_id: SimulationId,
+        globals: &Globals,
+    ) -> JavaScriptResult<()> {
+        if !self.sims_state.contains_key(&sim_id) {
+            return Err(JavaScriptError::MissingSimulationRun(sim_id));
+        }
+
+        let globals = serde_json::to_string(globals).unwrap();
+        let globals = new_js_string(scope, &globals);
+        let js_sim_id = sim_id_to_js(scope, sim_id);
+        call_js_function(
+            scope,
+            self.embedded.update_globals,
+            self.this,
+            &[js_sim_id, globals.into()],
+        )
+        .map_err(|err| {
+            JavaScriptError::V8(format!("Could not run update_globals Function: {err}"))
+        })?;
+
+        Ok(())
+    }
+
     // TODO: DOC
     fn handle_task_msg(
         &mut self,
@@ -420,6 +453,11 @@ impl<'s> ThreadLocalRunner<'s> {
                     .remove(&sim_id)
                     .ok_or(JavaScriptError::TerminateMissingSimulationRun(sim_id))?;
             }
+            InboundToRunnerMsgPayload::UpdateGlobals(globals) => {
+                let sim_id =
+                    sim_id.ok_or(JavaScriptError::SimulationIdRequired("update globals"))?;
+                self.update_globals(scope, sim_id, &globals)?;
+            }
             InboundToRunnerMsgPayload::StateSync(state_msg) => {
                 let sim_id = sim_id.ok_or(JavaScriptError::SimulationIdRequired("state sync"))?;
                 self.state_sync(scope, sim_id, state_msg)?;
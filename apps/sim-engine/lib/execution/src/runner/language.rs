@@ -10,6 +10,7 @@ pub enum Language {
     JavaScript = 0,
     Python = 1,
     Rust = 2,
+    Wasm = 3,
 }
 
 impl fmt::Display for Language {
@@ -19,9 +20,13 @@ impl fmt::Display for Language {
 }
 
 impl Language {
-    pub const NUM: usize = 3;
-    pub const ORDERED: [Language; Self::NUM] =
-        [Language::JavaScript, Language::Python, Language::Rust];
+    pub const NUM: usize = 4;
+    pub const ORDERED: [Language; Self::NUM] = [
+        Language::JavaScript,
+        Language::Python,
+        Language::Rust,
+        Language::Wasm,
+    ];
 
     pub fn as_index(self) -> usize {
         self as usize
@@ -42,8 +47,11 @@ impl Language {
 
         match file_path.extension().and_then(std::ffi::OsStr::to_str) {
             Some("py") => Ok(Language::Python),
-            Some("js") => Ok(Language::JavaScript),
+            // TypeScript behaviors are transpiled to JavaScript when the manifest is read
+            Some("js" | "ts") => Ok(Language::JavaScript),
             Some("rs") => Ok(Language::Rust),
+            // Binary modules and their text format are both compiled by the WebAssembly runner
+            Some("wasm" | "wat") => Ok(Language::Wasm),
             _ => Err(Error::ParseBehavior(file_name.to_string())),
         }
     }
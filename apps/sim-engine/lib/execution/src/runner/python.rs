@@ -6,6 +6,7 @@ mod sender;
 use std::{
     collections::{hash_map::Entry, HashMap},
     future::Future,
+    path::{Path, PathBuf},
     pin::Pin,
     result::Result as StdResult,
     sync::Arc,
@@ -33,6 +34,27 @@ use crate::{
     Error, Result,
 };
 
+/// Location of the Python runner in the repository if no directory is configured.
+///
+/// The path is absolute, so the engine can be started from any working directory as long as the
+/// repository it was built from is still present.
+const PYTHON_RUNNER_DIR_DEFAULT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/runner/python");
+
+/// Environment variable read by _run.sh_ to locate the directory containing `libmemory`.
+const MEMORY_LIBRARY_DIR_ENV: &str = "HASH_MEMORY_LIBRARY_DIR";
+
+/// Returns the directory `libmemory` is installed to alongside the engine binary, if any.
+///
+/// The library is built into the same directory as the binaries and is expected to be shipped
+/// next to them.
+fn memory_library_dir() -> Option<PathBuf> {
+    let install_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    ["libmemory.so", "libmemory.dylib"]
+        .iter()
+        .any(|library| install_dir.join(library).exists())
+        .then_some(install_dir)
+}
+
 pub struct PythonRunner {
     // Args to RunnerImpl::new
     init_msg: Arc<ExperimentInitRunnerMsg>,
@@ -172,10 +194,21 @@ async fn _run(
     let mut nng_receiver = NngReceiver::new(init_msg.experiment_id, init_msg.worker_index)?;
 
     // Spawn Python process.
+    let runner_dir = init_msg
+        .runner_config
+        .python_runner_dir
+        .as_deref()
+        .unwrap_or_else(|| Path::new(PYTHON_RUNNER_DIR_DEFAULT));
     let mut cmd = Command::new("sh");
-    cmd.arg("./lib/execution/src/runner/python/run.sh")
+    cmd.arg(runner_dir.join("run.sh"))
         .arg(&init_msg.experiment_id.to_string())
         .arg(&init_msg.worker_index.to_string());
+    // An explicitly configured library directory takes precedence over the install directory.
+    if std::env::var_os(MEMORY_LIBRARY_DIR_ENV).is_none() {
+        if let Some(memory_library_dir) = memory_library_dir() {
+            cmd.env(MEMORY_LIBRARY_DIR_ENV, memory_library_dir);
+        }
+    }
     let _process = cmd.spawn().map_err(PythonError::Spawn)?;
     tracing::debug!("Started Python process {}", init_msg.worker_index);
 
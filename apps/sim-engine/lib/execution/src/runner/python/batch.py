@@ -79,6 +79,28 @@ def parse_any_type_fields(metadata):
     return any_type_fields
 
 
+# Returns a dictionary from the names of categorical fields to their categories.
+def parse_categorical_fields(metadata):
+    if metadata is None:
+        return {}
+
+    categorical_fields = metadata.get("categorical_fields")
+    return json.loads(categorical_fields) if categorical_fields else {}
+
+
+def category_to_index(category, categories, field_name):
+    if category is None:
+        return None
+
+    try:
+        return categories.index(category)
+    except ValueError:
+        raise ValueError(
+            f"{category!r} is not a category of the field {field_name}, expected one of "
+            f"{categories!r}"
+        )
+
+
 def load_record_batch(mem, schema=None):
     (
         schema_offset,
@@ -105,10 +127,66 @@ def load_record_batch(mem, schema=None):
     return record_batch, any_type_fields
 
 
-# Returns dataset name, dataset contents and whether JSON could be loaded.
+def _cell_to_str(value):
+    if value is None:
+        return ""
+    if isinstance(value, bool):
+        return "true" if value else "false"
+    if isinstance(value, float) and value.is_integer():
+        return str(int(value))
+    return str(value)
+
+
+class Table:
+    """A tabular dataset, which is read directly from shared memory.
+
+    `column` and `row` return typed values and don't include the header of a CSV dataset.
+    `to_list` returns the list the dataset would have been parsed into.
+    """
+
+    def __init__(self, record_batch):
+        self.__record_batch = record_batch
+        metadata = record_batch.schema.metadata or {}
+        self.__is_csv = metadata.get(b"dataset_format") == b"csv"
+        header = metadata.get(b"csv_header")
+        self.__header = json.loads(header) if header is not None else None
+
+        self.column_names = record_batch.schema.names
+        self.num_rows = record_batch.num_rows
+
+    def to_list(self):
+        """Returns rows of strings for CSV datasets (including the header, if any) and dicts for
+        JSON datasets."""
+        columns = [column.to_pylist() for column in self.__record_batch.columns]
+        if not self.__is_csv:
+            return [dict(zip(self.column_names, values)) for values in zip(*columns)]
+
+        rows = [] if self.__header is None else [list(self.__header)]
+        rows.extend([_cell_to_str(cell) for cell in cells] for cells in zip(*columns))
+        return rows
+
+    def column(self, key):
+        """Returns the column with the name or index `key` as `pyarrow.Array`."""
+        if not isinstance(key, int):
+            if key not in self.column_names:
+                raise KeyError(
+                    f"Dataset has no column {key!r}, expected one of {self.column_names!r}"
+                )
+            key = self.column_names.index(key)
+        return self.__record_batch.column(key)
+
+    def row(self, index):
+        """Returns the row at `index` as dict from column names to values."""
+        return {
+            name: column[index].as_py()
+            for name, column in zip(self.column_names, self.__record_batch.columns)
+        }
+
+
+# Returns dataset name, dataset contents and whether the dataset could be loaded as table or JSON.
 def load_dataset(batch_id):
     mem = shared_buf_from_c_memory(load_shared_mem(batch_id))
-    (_, _, header_offset, header_size, _, _, data_offset, data_size) = load_markers(mem)
+    (_, schema_size, header_offset, header_size, _, _, data_offset, data_size) = load_markers(mem)
 
     # The header has the shortname of the dataset
     n_metaversion_bytes = 8  # Memory u32 + batch u32 version
@@ -117,6 +195,11 @@ def load_dataset(batch_id):
     name_buf = mem[name_offset:header_end]
     dataset_name = str(name_buf.to_pybytes().decode("utf-8"))
 
+    # Tabular datasets are stored as record batch
+    if schema_size > 0:
+        record_batch, _ = load_record_batch(mem)
+        return dataset_name, Table(record_batch), True
+
     # This data buffer has the dataset as a JSON string
     data_buf = mem[data_offset: data_offset + data_size]
     dataset_utf8 = data_buf.to_pybytes().decode("utf8")
@@ -140,6 +223,7 @@ class Batch:
         # TODO: Remove `any_type_fields` after upgrading Arrow and putting metadata in individual
         #       columns.
         self.any_type_fields = None
+        self.categorical_fields = None
         # Syncing erases columns that have become invalid.
         self.cols = {}
 
@@ -192,6 +276,9 @@ class Batch:
             self.record_batch, self.any_type_fields = load_record_batch(
                 self.mem, schema
             )
+            self.categorical_fields = parse_categorical_fields(
+                self.record_batch.schema.metadata
+            )
             self.cols = {}  # Avoid using obsolete column data.
             self.static_meta = static_meta_from_schema(self.record_batch.schema)
             self.dynamic_meta = dynamic_meta_from_c_memory(self.c_memory)
@@ -212,7 +299,9 @@ class Batch:
             # only agent-scoped fields are fully loaded by default
             col = vector
         else:
-            col = hash_util.load_full(vector, field.nullable, is_any)
+            col = hash_util.load_full(
+                vector, field.nullable, is_any, self.categorical_fields.get(name)
+            )
 
         self.cols[name] = col
         return col
@@ -227,6 +316,7 @@ class Batch:
 
     def flush_changes(self, schema, skip):
         any_type_fields = parse_any_type_fields(schema.metadata)
+        categorical_fields = parse_categorical_fields(schema.metadata)
 
         # Dynamically accessed columns (if any) were added to `cols` by `state`.
         changes = []
@@ -245,6 +335,12 @@ class Batch:
                 # Convert `any`-type array of JSON values to array of JSON strings
                 # for Arrow serialization as a string column.
                 py_col = [json.dumps(elem) for elem in col]
+            elif field.name in categorical_fields:
+                # Categorical fields are stored as the index of the category.
+                categories = categorical_fields[field.name]
+                py_col = [
+                    category_to_index(elem, categories, field.name) for elem in col
+                ]
             elif isinstance(col[0], pa.Scalar):
                 # Shallow-loaded column; can be modified in place
                 continue
@@ -15,6 +15,9 @@ class AgentContext:
     def data(self):
         return self.__sim_ctx.data()
 
+    def data_table(self, name):
+        return self.__sim_ctx.data_table(name)
+
     def step(self):
         return self.__sim_ctx.step()
 
@@ -54,6 +57,9 @@ class GroupContext:
     def data(self):
         return self.__sim_ctx.data()
 
+    def data_table(self, name):
+        return self.__sim_ctx.data_table(name)
+
     def step(self):
         return self.__sim_ctx.step()
 
@@ -86,6 +92,10 @@ class SimContext:
     def set_step(self, cur_step):
         self.__step = cur_step
 
+    # Globals can be replaced between steps while the simulation run is executing.
+    def set_globals(self, sim_globals):
+        self.__globals = sim_globals
+
     # TODO: step getter method
 
     def get_group(self, i_group):
@@ -105,6 +115,9 @@ class SimContext:
     def data(self):
         return self.__experiment_ctx.data()
 
+    def data_table(self, name):
+        return self.__experiment_ctx.data_table(name)
+
     def step(self):
         return self.__step
 
@@ -121,6 +134,9 @@ class SimInitContext:
     def data(self):
         return self.__experiment_ctx.data()
 
+    def data_table(self, name):
+        return self.__experiment_ctx.data_table(name)
+
 
 class ExperimentContext:
     def __init__(self, datasets):
@@ -8,4 +8,5 @@ class Target(object):
     Rust = 2
     Main = 3
     Dynamic = 4
+    Wasm = 5
 
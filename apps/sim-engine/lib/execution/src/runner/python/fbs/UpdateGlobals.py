@@ -0,0 +1,45 @@
+# automatically generated by the FlatBuffers compiler, do not modify
+
+# namespace: 
+
+import flatbuffers
+from flatbuffers.compat import import_numpy
+np = import_numpy()
+
+class UpdateGlobals(object):
+    __slots__ = ['_tab']
+
+    @classmethod
+    def GetRootAs(cls, buf, offset=0):
+        n = flatbuffers.encode.Get(flatbuffers.packer.uoffset, buf, offset)
+        x = UpdateGlobals()
+        x.Init(buf, n + offset)
+        return x
+
+    @classmethod
+    def GetRootAsUpdateGlobals(cls, buf, offset=0):
+        """This method is deprecated. Please switch to GetRootAs."""
+        return cls.GetRootAs(buf, offset)
+    # UpdateGlobals
+    def Init(self, buf, pos):
+        self._tab = flatbuffers.table.Table(buf, pos)
+
+    # UpdateGlobals
+    def Globals(self):
+        o = flatbuffers.number_types.UOffsetTFlags.py_type(self._tab.Offset(4))
+        if o != 0:
+            return self._tab.String(o + self._tab.Pos)
+        return None
+
+def Start(builder): builder.StartObject(1)
+def UpdateGlobalsStart(builder):
+    """This method is deprecated. Please switch to Start."""
+    return Start(builder)
+def AddGlobals(builder, globals): builder.PrependUOffsetTRelativeSlot(0, flatbuffers.number_types.UOffsetTFlags.py_type(globals), 0)
+def UpdateGlobalsAddGlobals(builder, globals):
+    """This method is deprecated. Please switch to AddGlobals."""
+    return AddGlobals(builder, globals)
+def End(builder): return builder.EndObject()
+def UpdateGlobalsEnd(builder):
+    """This method is deprecated. Please switch to End."""
+    return End(builder)
\ No newline at end of file
@@ -0,0 +1,56 @@
+"""
+Random number generation for behaviors, mirroring `setSeed`, `random` and `generateAgentID` of the
+JavaScript `hash_stdlib`.
+
+Behaviors can access this module as `hash_stdlib` or `hstd`. In seeded simulation runs the
+behavior execution package seeds it before every behavior, so behaviors using `hash_stdlib.random`,
+`hash_stdlib.generate_agent_id` or the `random` module of the standard library are reproducible.
+"""
+import hashlib as _hashlib
+import random as _random
+import uuid as _uuid
+
+_rng = _random.Random()
+
+# The ids returned by `generate_agent_id` are derived from this key and the number of ids generated
+# before, if it's not `None`
+_agent_id_key = None
+_num_agent_ids = 0
+
+
+def set_seed(seed):
+    """
+    Seeds `random()` and the global generator of the `random` module with the string `seed`.
+    """
+    _rng.seed(str(seed))
+    _random.seed(str(seed))
+
+
+def random():
+    """
+    Returns a random float in `[0, 1)`.
+    """
+    return _rng.random()
+
+
+def seed_agent_ids(key):
+    """
+    Derives the ids returned by `generate_agent_id` from the string `key`. If `key` is `None`,
+    random ids are returned.
+    """
+    global _agent_id_key, _num_agent_ids  # pylint: disable=global-statement
+    _agent_id_key = key
+    _num_agent_ids = 0
+
+
+def generate_agent_id():
+    """
+    Returns a new agent id as string.
+    """
+    global _num_agent_ids  # pylint: disable=global-statement
+    if _agent_id_key is None:
+        return str(_uuid.uuid4())
+
+    digest = _hashlib.sha256(f"{_agent_id_key}:{_num_agent_ids}".encode("utf-8")).digest()
+    _num_agent_ids += 1
+    return str(_uuid.UUID(bytes=digest[:16], version=4))
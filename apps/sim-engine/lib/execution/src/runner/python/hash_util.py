@@ -18,12 +18,18 @@ def _writable_in_place(typ):
 
 
 def load_full(
-        vector, is_nullable, is_any
+        vector, is_nullable, is_any, categories=None
 ):  # TODO: Change arguments after upgrading pyarrow from 0.17.
     if is_any:
         # `any` type fields are expensive
         return [loads(any_obj.as_buffer().to_pybytes()) for any_obj in vector]
 
+    if categories is not None:
+        # Categorical fields store the index of the category
+        return [
+            None if index is None else categories[index] for index in vector.to_pylist()
+        ]
+
     if is_nullable or not _writable_in_place(vector.type):
         # NOTE: Even if some nullable field were writable in place,
         #       changing it could change the null count, so its
@@ -15,6 +15,7 @@ from fbs.StateSnapshotSync import StateSnapshotSync
 from fbs.ContextBatchSync import ContextBatchSync
 from fbs.StateInterimSync import StateInterimSync
 from fbs.NewSimulationRun import NewSimulationRun
+from fbs.UpdateGlobals import UpdateGlobals
 from fbs.Target import Target
 
 # Outbound
@@ -36,7 +37,7 @@ from fbs import RunnerOutboundMsg
 from fbs import SyncCompletion
 from fbs.RunnerOutboundMsgPayload import RunnerOutboundMsgPayload
 
-from batch import load_dataset
+from batch import load_dataset, Table
 
 
 # TODO(permanently?): Keep in sync with fbs.PackageType
@@ -72,16 +73,34 @@ class PyInit:
 class PySharedContext:
     def __init__(self, shared_context_fbs):
         self.__datasets = {}
+        self.__tables = {}
         for i_dataset in range(shared_context_fbs.DatasetsLength()):
             name, data, _did_parse = load_dataset(
                 shared_context_fbs.Datasets(i_dataset).BatchId()
             )
             # TODO: Use `did_parse` to show warnings to user?
+            if isinstance(data, Table):
+                self.__tables[name] = data
+                data = None
             self.__datasets[name] = data
+        self.__tables_loaded = not self.__tables
 
     def data(self):
+        # Tables are only converted into lists if `data` is used
+        if not self.__tables_loaded:
+            for name, table in self.__tables.items():
+                self.__datasets[name] = table.to_list()
+            self.__tables_loaded = True
         return self.__datasets
 
+    def data_table(self, name):
+        """Returns the typed table of the tabular dataset `name`."""
+        if name not in self.__tables:
+            raise KeyError(
+                f"{name!r} is not a tabular dataset, expected one of {list(self.__tables)!r}"
+            )
+        return self.__tables[name]
+
 
 class PyPackage:
     def __init__(self, package_fbs):
@@ -155,6 +174,12 @@ class PySimRun:
         # TODO: DatastoreInit datasets per sim run?
 
 
+class PyUpdateGlobals:
+    def __init__(self, sim_id, update_globals_fbs):
+        self.sim_id = sim_id
+        self.globals = json.loads(update_globals_fbs.Globals().decode("utf-8"))
+
+
 class PySchema:
     def __init__(self, schema_fbs):
         self.agent = pa.ipc.read_schema(
@@ -262,6 +287,11 @@ class Messenger:
             msg.Init(payload.Bytes, payload.Pos)
             return PySimRun(msg), msg_type
 
+        if msg_type == RunnerInboundMsgPayload.UpdateGlobals:
+            msg = UpdateGlobals()
+            msg.Init(payload.Bytes, payload.Pos)
+            return PyUpdateGlobals(sim_sid, msg), msg_type
+
         raise RuntimeError(f"Unknown message type {msg_type} from sim {sim_sid}")
 
     def send_task_continuation(
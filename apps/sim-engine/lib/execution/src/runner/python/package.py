@@ -3,12 +3,15 @@ from pathlib import Path
 import sys
 import traceback
 
+import hash_stdlib
 import hash_util
 
 
 def get_pkg_path(pkg_name, pkg_type):
-    # The engine should be started from the engine's root directory in the repo.
-    return Path(f"./lib/execution/src/package/simulation/{pkg_type}/{pkg_name}/package.py")
+    # Packages are located relative to the runner, so the engine doesn't have to be started from
+    # the engine's root directory in the repo.
+    runner_dir = Path(__file__).resolve().parent
+    return runner_dir / f"../../package/simulation/{pkg_type}/{pkg_name}/package.py"
 
 
 def load_fns(pkg_name, pkg_type):
@@ -22,7 +25,7 @@ def load_fns(pkg_name, pkg_type):
         return [None, None, None]
 
     # Run code.
-    pkg_globals = {"hash_util": hash_util}
+    pkg_globals = {"hash_util": hash_util, "hash_stdlib": hash_stdlib}
     try:
         bytecode = compile(code, pkg_name, "exec")
         # pylint: disable=exec-used
@@ -7,15 +7,21 @@ VENV_ACTIVATE_PATH="$SCRIPT_DIR/runner_venv/bin/activate"
 
 . $VENV_ACTIVATE_PATH
 
-# Find the latest "hash_engine_lib" compiled shared library
-LATEST_LIBRARY=$(find "$SCRIPT_DIR/../../../../../target" -name "libmemory.so" -print0 | xargs -r -0 ls -1 -t | head -1)
-LATEST_LIBRARY_DIR=$(dirname "$LATEST_LIBRARY")
+# Use the directory of `libmemory` passed by the engine or set by the user. Otherwise, find the
+# latest compiled shared library in the target directory of the repository.
+if [ -n "${HASH_MEMORY_LIBRARY_DIR-}" ]
+then
+  LATEST_LIBRARY_DIR="$HASH_MEMORY_LIBRARY_DIR"
+else
+  LATEST_LIBRARY=$(find "$SCRIPT_DIR/../../../../../target" -name "libmemory.so" -print0 | xargs -r -0 ls -1 -t | head -1)
+  LATEST_LIBRARY_DIR=$(dirname "$LATEST_LIBRARY")
+fi
 
-if [ -z "${SCRIPT_DIR-}" ]
+if [ -z "${LD_LIBRARY_PATH-}" ]
 then
   export LD_LIBRARY_PATH="$LATEST_LIBRARY_DIR"
 else
-  export LD_LIBRARY_PATH="LD_LIBRARY_PATH:$LATEST_LIBRARY_DIR"
+  export LD_LIBRARY_PATH="$LD_LIBRARY_PATH:$LATEST_LIBRARY_DIR"
 fi
 
 python3 -u "$SCRIPT_DIR/main.py" "$1" "$2" "$SCRIPT_DIR"
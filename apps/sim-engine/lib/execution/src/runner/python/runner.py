@@ -434,6 +434,10 @@ class Runner:
                     logging.debug("Terminating simulation run")
                     del self.sims[msg.sim_id]
 
+                elif msg_type == RunnerInboundMsgPayload.UpdateGlobals:
+                    logging.debug("Updating globals")
+                    self.sims[msg.sim_id].set_globals(msg.globals)
+
                 elif msg_type == RunnerInboundMsgPayload.ContextBatchSync:
                     logging.debug("Handling context batch sync")
                     self.ctx_batch_sync(msg.sim_id, msg.batch, msg.cur_step)
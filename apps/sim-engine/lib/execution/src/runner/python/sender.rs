@@ -273,6 +273,21 @@ fn inbound_to_nng(
                 flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsgPayload::NewSimulationRun,
             )
         }
+        InboundToRunnerMsgPayload::UpdateGlobals(globals) => {
+            let globals =
+                serde_json::to_string(&globals.0).expect("Can serialize serde_json::Value");
+            let globals = fbb.create_string(&globals);
+            let msg = flatbuffers_gen::runner_inbound_msg_generated::UpdateGlobals::create(
+                fbb,
+                &flatbuffers_gen::runner_inbound_msg_generated::UpdateGlobalsArgs {
+                    globals: Some(globals),
+                },
+            );
+            (
+                msg.as_union_value(),
+                flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsgPayload::UpdateGlobals,
+            )
+        }
     };
 
     let msg = flatbuffers_gen::runner_inbound_msg_generated::RunnerInboundMsg::create(
@@ -29,11 +29,14 @@ def find_directory(patterns, path):
 def last_modified(path):
     return os.path.getmtime(path)
 
-library_dirs = sorted(
-    find_directory(["libmemory.so", "libmemory.dylib"], f"{script_path}/../../../../../"),
-    key=last_modified,
-    reverse=True,
-)
+if "HASH_MEMORY_LIBRARY_DIR" in os.environ:
+    library_dirs = [os.environ["HASH_MEMORY_LIBRARY_DIR"]]
+else:
+    library_dirs = sorted(
+        find_directory(["libmemory.so", "libmemory.dylib"], f"{script_path}/../../../../../"),
+        key=last_modified,
+        reverse=True,
+    )
 
 # Convert path to Python module prefix.
 if script_path == ".":
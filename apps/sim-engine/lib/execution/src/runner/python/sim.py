@@ -18,6 +18,10 @@ class Sim:
         self.context = SimContext(self.context_getters, experiment_ctx, sim_globals)
         self.state = SimState(self.state_getters)
 
+    def set_globals(self, sim_globals):
+        self.globals = sim_globals
+        self.context.set_globals(sim_globals)
+
     # `pkg` should have properties `name`, `loaders`, `getters` and `owns_field`.
     def maybe_add_custom_fns(self, to_add, custom_property, pkg):
         to_add = to_add.get(custom_property)
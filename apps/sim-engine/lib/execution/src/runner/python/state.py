@@ -91,16 +91,23 @@ class AgentState:
     # Similarly to `get` and `set`, if the user mutates the arguments
     # of `addMessage` later, it won't affect the agent's state.
 
-    # `to` must be either a string or a list. If it's a string,
-    # it must be a single agent id or name. If it's a list, it must
-    # be a list of agent ids and/or names. `to` is automatically
+    # `to` must be either a recipient or a list of recipients. A
+    # recipient is either an agent id or name, or a recipient dict
+    # like `{"within": 5}`, `{"topic": "news"}`, or `{"to": "a", "delay": 3}`.
+    # Recipient dicts are stored as JSON strings. `to` is automatically
     # converted to a list if it's not one already.
 
     # `data` is an optional argument. `data` must be JSON-serializable.
     def add_message(self, to, msg_type, data=None):
         idx = self.__dict__["__idx_in_group"]
+
+        def recipient(r):
+            return r if isinstance(r, str) else json.dumps(r)
+
         new_message = {
-            "to": [to] if isinstance(to, str) else to,
+            "to": [recipient(r) for r in to]
+            if isinstance(to, list)
+            else [recipient(to)],
             "type": msg_type,
             "data": deepcopy(data)
             if self.__dict__["__msgs_native"][idx]
@@ -0,0 +1,176 @@
+//! The agents built-in behaviors operate on, backed by the [`AgentColumns`] of their batch.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use stateful::{
+    agent::{AgentId, AgentStateField, StrVec},
+    message::Message,
+    Vec3,
+};
+
+use crate::runner::{
+    in_process::{AgentColumns, Outbox},
+    rust::error::{RustError, RustResult},
+};
+
+/// Read access to the fields of an agent.
+///
+/// The getters return `None` if the agent doesn't have the field, if it's `null`, or if it has
+/// another type.
+pub(in crate::runner::rust) trait AgentFields {
+    /// The columns of the batch the agent is part of.
+    fn columns(&self) -> &AgentColumns;
+
+    /// The index of the agent in its batch.
+    fn index(&self) -> usize;
+
+    fn agent_id(&self) -> AgentId {
+        self.columns().agent_id(self.index())
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        self.columns().number(key, self.index())
+    }
+
+    fn boolean(&self, key: &str) -> Option<bool> {
+        self.columns().boolean(key, self.index())
+    }
+
+    fn string(&self, key: &str) -> Option<&str> {
+        self.columns().string(key, self.index())
+    }
+
+    fn vec3(&self, key: &str) -> Option<Vec3> {
+        self.columns().vec3(key, self.index())
+    }
+
+    /// Returns the field `key` as JSON, `null` if the agent doesn't have the field.
+    fn json(&self, key: &str) -> Value {
+        self.columns().json(key, self.index())
+    }
+
+    /// Deserializes the field `key`, e.g. a list or an object.
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_value(self.json(key)).ok()
+    }
+
+    /// Returns the position of the agent.
+    ///
+    /// # Errors
+    ///
+    /// - if the agent doesn't have a position
+    fn position(&self) -> RustResult<Vec3> {
+        self.vec3(AgentStateField::Position.name()).ok_or_else(|| {
+            RustError::from(format!(
+                "Agent {} does not have a position",
+                self.agent_id()
+            ))
+        })
+    }
+
+    fn direction(&self) -> Option<Vec3> {
+        self.vec3(AgentStateField::Direction.name())
+    }
+
+    fn velocity(&self) -> Option<Vec3> {
+        self.vec3(AgentStateField::Velocity.name())
+    }
+}
+
+/// The agent a behavior is executed on.
+pub(in crate::runner::rust) struct AgentState<'s> {
+    columns: &'s mut AgentColumns,
+    outbox: &'s mut Outbox,
+    index: usize,
+}
+
+impl AgentFields for AgentState<'_> {
+    fn columns(&self) -> &AgentColumns {
+        self.columns
+    }
+
+    fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'s> AgentState<'s> {
+    pub fn new(columns: &'s mut AgentColumns, outbox: &'s mut Outbox, index: usize) -> Self {
+        Self {
+            columns,
+            outbox,
+            index,
+        }
+    }
+
+    pub fn set_number(&mut self, key: &str, value: f64) -> RustResult<()> {
+        Ok(self.columns.set_number(key, self.index, value)?)
+    }
+
+    pub fn set_boolean(&mut self, key: &str, value: bool) -> RustResult<()> {
+        Ok(self.columns.set_boolean(key, self.index, value)?)
+    }
+
+    pub fn set_vec3(&mut self, key: &str, value: Vec3) -> RustResult<()> {
+        Ok(self.columns.set_vec3(key, self.index, value)?)
+    }
+
+    /// Sets the field `key` to the serialized `value`, e.g. a list or an object.
+    pub fn set<T: Serialize>(&mut self, key: &str, value: T) -> RustResult<()> {
+        Ok(self
+            .columns
+            .set_json(key, self.index, serde_json::to_value(value)?)?)
+    }
+
+    pub fn set_position(&mut self, position: Vec3) -> RustResult<()> {
+        self.set_vec3(AgentStateField::Position.name(), position)
+    }
+
+    pub fn set_direction(&mut self, direction: Vec3) -> RustResult<()> {
+        self.set_vec3(AgentStateField::Direction.name(), direction)
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vec3) -> RustResult<()> {
+        self.set_vec3(AgentStateField::Velocity.name(), velocity)
+    }
+
+    /// Returns the fields of the agent as JSON object, leaving out `null` fields.
+    pub fn fields_json(&self) -> Map<String, Value> {
+        self.columns.fields_json(self.index)
+    }
+
+    /// Sends a message of type `kind` to `to`, see [`Message::from_sender`].
+    pub fn add_message<T: StrVec>(
+        &mut self,
+        to: &T,
+        kind: &str,
+        data: Option<Value>,
+    ) -> RustResult<()> {
+        let message = Message::from_sender(self.agent_id(), to.to_vec(), kind, data)?;
+        self.outbox.push(self.index, message);
+        Ok(())
+    }
+}
+
+/// A neighbor of the agent a behavior is executed on, as it was in the previous step.
+#[derive(Clone, Copy)]
+pub(in crate::runner::rust) struct Neighbor<'c> {
+    columns: &'c AgentColumns,
+    index: usize,
+}
+
+impl<'c> Neighbor<'c> {
+    pub fn new(columns: &'c AgentColumns, index: usize) -> Self {
+        Self { columns, index }
+    }
+}
+
+impl AgentFields for Neighbor<'_> {
+    fn columns(&self) -> &AgentColumns {
+        self.columns
+    }
+
+    fn index(&self) -> usize {
+        self.index
+    }
+}
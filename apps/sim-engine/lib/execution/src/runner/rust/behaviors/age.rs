@@ -1,25 +1,18 @@
-use super::{Context, Result, SharedBehavior, State};
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
+};
 
-pub fn behavior(state: &mut State<'_>, _context: &Context<'_>) -> Result<()> {
-    let age = state.age_mut()?;
-    age.iter_mut().for_each(|opt| {
-        if let Some(v) = opt {
-            *v += 1.0;
-        } else {
-            *opt = Some(1.0);
-        }
-    });
-    Ok(())
-}
+pub fn behavior(state: &mut AgentState<'_>, _context: &AgentContext<'_>) -> RustResult<()> {
+    let age = match state.number("age") {
+        Some(age) => age + 1.0,
+        None => 1.0,
+    };
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/age/age.rs".into(),
-        name: "@hash/age/age.rs".into(),
-        shortnames: vec!["@hash/age/age.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("age.rs.json").to_string()),
-    }
+    state.set_number("age", age)?;
+
+    Ok(())
 }
 
 // Original Source
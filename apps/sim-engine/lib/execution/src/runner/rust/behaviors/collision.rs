@@ -1,37 +1,34 @@
-use super::{Context, Result, SharedBehavior, State};
-use crate::hash_types::{Agent, Vec3};
-
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/physics/collision.rs".into(),
-        name: "@hash/physics/collision.rs".into(),
-        shortnames: vec!["@hash/physics/collision.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("collision.rs.json").to_string()),
-    }
-}
+use stateful::Vec3;
+
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
+};
 
 /// Causes the agent to collide with other agents.
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
+pub fn behavior(state: &mut AgentState<'_>, context: &AgentContext<'_>) -> RustResult<()> {
     let min_dist: f64 = 1.0;
-    let pos = state.position()?[0].ok_or("Agent must have position")?;
-    let vel = state.velocity()?[0];
-    let mass = state.mass()?[0];
+    let pos = state.position()?;
+    let vel = state.velocity().unwrap_or_default();
+    let mass = state.number("mass").ok_or("Please specify a mass")?;
 
     // TODO: access globals to determine what the % elasticity of the collision should be
     let epsilon: f64 = 1.0;
 
     let mut dv = Vec3(0.0, 0.0, 0.0);
-    let neighbors = context.neighbors(0)?;
-    let neighbors_pos = neighbors
-        .iter()
-        .filter(|&n| n.position().is_some())
-        .filter(|&n| (n.position().unwrap() - pos).magnitude() <= min_dist);
+    let neighbors_pos = context.neighbors.iter().filter_map(|neighbor| {
+        neighbor
+            .position()
+            .ok()
+            .filter(|&n_pos| (n_pos - pos).magnitude() <= min_dist)
+            .map(|n_pos| (neighbor, n_pos))
+    });
 
-    for neighbor in neighbors_pos {
-        let dir = neighbor.position().unwrap() - pos;
+    for (neighbor, n_pos) in neighbors_pos {
+        let dir = n_pos - pos;
 
-        let n_vel: Vec3 = neighbor.get_custom_or("velocity", Vec3(0.0, 0.0, 0.0))?;
+        let n_vel = neighbor.velocity().unwrap_or_default();
 
         // Check if agent is actually moving towards neighbor or vice versa
         // Dot product of velocity and direction to neighbor is positive
@@ -39,7 +36,7 @@ pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
             continue;
         }
 
-        let n_mass: f64 = neighbor.get_custom_or("mass", f64::INFINITY)?;
+        let n_mass = neighbor.number("mass").unwrap_or(f64::INFINITY);
 
         // Calculate normalized direction of reflection
         let norm = dir.norm();
@@ -52,6 +49,6 @@ pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
         dv += norm * j / mass;
     }
 
-    state.velocity_set(vec![vel - dv])?;
+    state.set_velocity(vel - dv)?;
     Ok(())
 }
@@ -1,34 +1,35 @@
-use super::{Context, Result, SharedBehavior, State};
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
+};
 
-pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
-    let alive = state.alive_mut()?;
-    alive
-        .iter_mut()
-        .enumerate()
-        .try_for_each::<_, Result<()>>(|(i, agent_alive)| {
-            let neighbors = context.neighbors(i)?;
-            let live_neighbors = neighbors.iter().filter(|neighbor| neighbor.alive()).count();
+/// Implements Conway's Game of Life rules for an agent.
+///
+/// Depends on the agent and its neighbors having position, and alive properties. This version
+/// assumes that each "Grid cell" will be an agent.
+pub fn behavior(state: &mut AgentState<'_>, context: &AgentContext<'_>) -> RustResult<()> {
+    let alive = state
+        .boolean("alive")
+        .ok_or("Expected 'alive' in agent state")?;
 
-            let is_alive = if *agent_alive {
-                !(live_neighbors < 2 || live_neighbors > 3)
-            } else {
-                live_neighbors == 3
-            };
+    let live_neighbors = context
+        .neighbors
+        .iter()
+        .filter(|neighbor| neighbor.boolean("alive").unwrap_or(false))
+        .count();
 
-            *agent_alive = is_alive;
-            Ok(())
-        })?;
-    Ok(())
-}
+    let is_alive = if alive {
+        (2..=3).contains(&live_neighbors)
+    } else {
+        live_neighbors == 3
+    };
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/conway/conway.rs".into(),
-        name: "@hash/conway/conway.rs".into(),
-        shortnames: vec!["@hash/conway/conway.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("conway.rs.json").to_string()),
+    if is_alive != alive {
+        state.set_boolean("alive", is_alive)?;
     }
+
+    Ok(())
 }
 
 // Original Source
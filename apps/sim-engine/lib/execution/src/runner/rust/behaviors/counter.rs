@@ -1,47 +1,28 @@
-use super::{Context, Result, SharedBehavior, State};
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
+};
 
-pub fn behavior(state: &mut State<'_>, _context: &Context<'_>) -> Result<()> {
-    let increment_col = state.counter_increment()?;
-    let reset_at_col = state.counter_reset_at()?;
-    let reset_to_col = state.counter_reset_to()?;
-    let counter_col = state.counter()?;
-    let mut counter_vec = Vec::with_capacity(increment_col.len());
+pub fn behavior(state: &mut AgentState<'_>, _context: &AgentContext<'_>) -> RustResult<()> {
+    let counter = state.number("counter").unwrap_or(0.0);
+    let increment = state.number("counter_increment").unwrap_or(1.0);
 
-    for i in 0..counter_col.len() {
-        let counter = counter_col[i].unwrap_or(0.0);
-        let increment = increment_col[i].unwrap_or(1.0);
-        let reset_at = &reset_at_col[i];
-        let reset_to = &reset_to_col[i];
-
-        if let Some(value) = reset_at {
-            // compare within same error
-            if (counter - *value).abs() < std::f64::EPSILON {
-                if let Some(reset_value) = reset_to {
-                    counter_vec.push(Some(*reset_value));
-                    continue;
-                }
+    if let Some(value) = state.number("counter_reset_at") {
+        // compare within same error
+        if (counter - value).abs() < f64::EPSILON {
+            if let Some(reset_value) = state.number("counter_reset_to") {
+                state.set_number("counter", reset_value)?;
+                return Ok(());
             }
         }
-
-        counter_vec.push(Some(counter + increment));
     }
 
-    debug_assert_eq!(counter_vec.len(), increment_col.len());
-    state.counter_set(counter_vec)?;
+    state.set_number("counter", counter + increment)?;
 
     Ok(())
 }
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/counter/counter.rs".into(),
-        name: "@hash/counter/counter.rs".into(),
-        shortnames: vec!["@hash/counter/counter.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("counter.rs.json").to_string()),
-    }
-}
-
 // Original Source
 /*
 use crate::prelude::{AgentState, Context, SimulationResult};
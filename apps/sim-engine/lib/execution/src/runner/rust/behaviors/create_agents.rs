@@ -1,19 +1,17 @@
-use super::{
-    super::{Agent, OutboundMessage},
-    Context, Result, SharedBehavior, State,
+use stateful::message::payload::CreateAgent;
+
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
 };
 
-pub fn behavior(state: &mut State<'_>, _context: &Context<'_>) -> Result<()> {
-    for i in 0..state.num_agents() {
-        if let Some(agents_object) = &state.agents()?[i] {
-            if let Some(agents_to_create) = agents_object.clone().as_object() {
-                for (_key, agent_array) in agents_to_create.iter() {
-                    if let Some(agents) = agent_array.as_array() {
-                        for agent in agents {
-                            let message = OutboundMessage::create_agent(Agent::from(agent.clone()));
-                            state.messages_mut()?[i].push(message);
-                        }
-                    }
+pub fn behavior(state: &mut AgentState<'_>, _context: &AgentContext<'_>) -> RustResult<()> {
+    if let Some(agents_to_create) = state.json("agents").as_object() {
+        for agent_array in agents_to_create.values() {
+            if let Some(agents) = agent_array.as_array() {
+                for agent in agents {
+                    state.add_message(&"hash", CreateAgent::KIND, Some(agent.clone()))?;
                 }
             }
         }
@@ -22,16 +20,6 @@ pub fn behavior(state: &mut State<'_>, _context: &Context<'_>) -> Result<()> {
     Ok(())
 }
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/create_agents/create_agents.rs".into(),
-        name: "@hash/create_agents/create_agents.rs".into(),
-        shortnames: vec!["@hash/create_agents/create_agents.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("create_agents.rs.json").to_string()),
-    }
-}
-
 // Original Source
 /*
 use crate::prelude::{AgentState, Context, OutboundMessage, SimulationResult};
@@ -1,34 +1,24 @@
-use super::{Context, Result, SharedBehavior, State};
-use crate::hash_types::{Agent, Vec3};
+use stateful::Vec3;
+
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    behaviors::get_state_or_property,
+    context::AgentContext,
+    error::RustResult,
+};
 
 /// Runs a semi-implicit Euler integration to calculate the change in velocity and
 /// position, based on the the current forces acting on the agent.
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let dt: f64 = state
-        .get_value("dt")
-        .map(|v| v.as_f64().unwrap())
-        .unwrap_or(0.01);
-    let mass = state.mass()?[0];
-    let force = &mut state.force_mut()?[0];
-    let v = state.velocity_mut()?;
-
-    let dv = *force * (dt / mass);
-    v[0] += dv;
+pub fn behavior(state: &mut AgentState<'_>, context: &AgentContext<'_>) -> RustResult<()> {
+    let dt: f64 = get_state_or_property(state, context.globals, "dt", 0.01);
+    let mass = state.number("mass").ok_or("Please specify a mass")?;
+    let force = state.vec3("force").unwrap_or_default();
 
-    let dp = v * dt;
-    let pos = state.position_mut()?[0].as_mut().ok_or("Agent must have a position")?;
-    *pos += dp;
+    let v = state.velocity().unwrap_or_default() + force * (dt / mass);
+    let pos = state.position()?;
+    state.set_position(pos + v * dt)?;
+    state.set_velocity(v)?;
 
-    *force = Vec3(0.0, 0.0, 0.0);
+    state.set_vec3("force", Vec3(0.0, 0.0, 0.0))?;
     Ok(())
 }
-
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/physics/forces.rs".into(),
-        name: "@hash/physics/forces.rs".into(),
-        shortnames: vec!["@hash/physics/forces.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("forces.rs.json").to_string()),
-    }
-}
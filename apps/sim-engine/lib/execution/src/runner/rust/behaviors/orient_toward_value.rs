@@ -1,102 +1,78 @@
 use std::collections::HashMap;
 
-use super::{Context, Error, Result, SharedBehavior, State};
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
+};
 
-pub fn behavior(state: &mut State, context: &Context) -> Result<()> {
-    let mut json_state = state.get_json_state()?;
-
-    for i in 0..state.num_agents() {
-        if !json_state[i]["orient_toward_value"].is_string() {
-            return Ok(());
-        }
-
-        let target = json_state[i]["orient_toward_value"]
-            .as_str()
-            .ok_or("not a string")?;
+/// # Errors
+/// This function can fail when a neighbor with the target value doesn't have a position
+pub fn behavior(state: &mut AgentState<'_>, context: &AgentContext<'_>) -> RustResult<()> {
+    let target = match state.string("orient_toward_value") {
+        Some(target) => target.to_string(),
+        None => return Ok(()),
+    };
 
-        // True -> looking for max (greater) value
-        // False -> looking for min (smaller) value
-        let uphill = match json_state[i]["orient_toward_value_uphill"].as_bool() {
-            Some(value) => value,
-            None => true,
-        };
+    // True -> looking for max (greater) value
+    // False -> looking for min (smaller) value
+    let uphill = state.boolean("orient_toward_value_uphill").unwrap_or(true);
 
-        let cumulative = match json_state[i]["orient_toward_value_cumulative"].as_bool() {
-            Some(value) => value,
-            None => false,
-        };
+    let cumulative = state
+        .boolean("orient_toward_value_cumulative")
+        .unwrap_or(false);
 
-        if let Some(target_value) = json_state[i].get_as_json(target)?.as_f64() {
-            let mut current_max = target_value;
+    if let Some(target_value) = state.number(&target) {
+        let mut current_max = target_value;
 
-            let mut neighbor_map = HashMap::new();
+        let mut neighbor_map: HashMap<[i64; 3], f64> = HashMap::new();
 
-            for neighbor in context.neighbors(i)? {
-                if let Some(neighbor_value) = neighbor.get_custom_as_json(target)?.as_f64() {
-                    let position = neighbor
-                        .position()
-                        .ok_or("Neighbors should have position")?
-                        .to_grid();
+        for neighbor in &context.neighbors {
+            if let Some(neighbor_value) = neighbor.number(&target) {
+                let position = neighbor.position()?.as_grid();
 
-                    if neighbor_map.contains_key(&position) {
-                        let old_value = *neighbor_map[&position];
+                match neighbor_map.get(&position) {
+                    Some(&old_value) => {
                         let monotone = uphill == (old_value < neighbor_value);
                         if cumulative {
                             neighbor_map.insert(position, old_value + neighbor_value);
                         } else if monotone {
                             neighbor_map.insert(position, neighbor_value);
                         }
-                    } else {
+                    }
+                    None => {
                         neighbor_map.insert(position, neighbor_value);
                     }
                 }
             }
+        }
 
-            for (position, neighbor_value) in neighbor_map {
-                let my_position = json_state[i]
-                    .get_pos()
-                    .map_err(|e| Error::from(e.to_string()))?;
-                if uphill {
-                    if neighbor_value > current_max {
-                        current_max = neighbor_value;
-
-                        let x_change = position[0] as f64 - my_position[0];
-                        let y_change = position[1] as f64 - my_position[1];
-
-                        json_state[i].direction = Some([x_change, y_change].into());
-                    }
-                } else if neighbor_value < current_max {
-                    current_max = neighbor_value;
+        let my_position = state.position()?;
+        for (position, neighbor_value) in neighbor_map {
+            let improves = if uphill {
+                neighbor_value > current_max
+            } else {
+                neighbor_value < current_max
+            };
+            if improves {
+                current_max = neighbor_value;
 
-                    let x_change = position[0] as f64 - my_position[0];
-                    let y_change = position[1] as f64 - my_position[1];
+                let x_change = position[0] as f64 - my_position.x();
+                let y_change = position[1] as f64 - my_position.y();
 
-                    json_state[i].direction = Some([x_change, y_change].into());
-                }
+                state.set_direction([x_change, y_change].into())?;
             }
+        }
 
-            // compare within same error
-            if (current_max - target_value).abs() <= std::f64::EPSILON {
-                json_state[i].direction = Some([].into());
-            }
+        // compare within same error
+        if (current_max - target_value).abs() <= f64::EPSILON {
+            state.set_direction([].into())?;
         }
     }
 
-    // Important to call this for continuity of state
-    state.set_json_state(json_state)?;
     Ok(())
 }
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/orient_toward_value/orient_toward_value.rs".into(),
-        name: "@hash/orient_toward_value/orient_toward_value.rs".into(),
-        shortnames: vec!["@hash/orient_toward_value/orient_toward_value.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("orient_toward_value.rs.json").to_string()),
-    }
-}
-
 // Original Source
 /*
 use crate::prelude::{AgentState, Context, SimulationResult};
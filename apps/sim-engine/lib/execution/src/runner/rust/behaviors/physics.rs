@@ -1,7 +1,12 @@
-use super::{error::SimulationError, Context, Result, SharedBehavior, State};
+use crate::runner::rust::{
+    agent::{AgentFields, AgentState},
+    context::AgentContext,
+    error::RustResult,
+};
 
-pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
-    let mut position = state.take_position()?;
+/// Moves an agent's position based on the applied force using Euler's method.
+pub fn behavior(state: &mut AgentState<'_>, context: &AgentContext<'_>) -> RustResult<()> {
+    let m = state.number("mass").ok_or("Please specify a mass")?;
 
     let dt = context
         .globals
@@ -10,40 +15,27 @@ pub fn behavior(state: &mut State<'_>, context: &Context<'_>) -> Result<()> {
         .as_f64()
         .ok_or("dt needs to be a number")?;
 
-    for i in 0..state.num_agents() {
-        let m = state.mass()?[i];
-        let mut v = state.velocity()?[i].clone();
-        let f = state.force()?[i].clone();
+    let mut v = state
+        .velocity()
+        .ok_or("Velocity not specified, or not a proper Vec3")?;
+    let f = state
+        .vec3("force")
+        .ok_or("Force not specified, or not a proper Vec3")?;
 
-        if let Some(p) = &mut position[i] {
-            // Newton's law, F = MA
-            // v = a t
-            // x = 1/2 a t^2
-            v += f * dt / m;
+    // Newton's law, F = MA
+    // v = a t
+    // x = 1/2 a t^2
+    v += f * dt / m;
 
-            // Move the agent as well
-            *p += v * dt;
-            state.velocity_mut()?[i] = v;
-        } else {
-            Err(SimulationError::from("Expected position to exist on agent"))?;
-        }
-    }
+    // Move the agent as well
+    let pos = state.position()?;
+    state.set_position(pos + v * dt)?;
 
-    state.set_position(position);
+    state.set_velocity(v)?;
 
     Ok(())
 }
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/physics/physics.rs".into(),
-        name: "@hash/physics/physics.rs".into(),
-        shortnames: vec!["@hash/physics/physics.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("physics.rs.json").to_string()),
-    }
-}
-
 // Original Source
 /*
 //! Moves an agent's position based on the applied force
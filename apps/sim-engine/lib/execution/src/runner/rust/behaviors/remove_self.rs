@@ -1,30 +1,13 @@
-use super::super::OutboundMessage;
-use super::{Context, Result, SharedBehavior, State};
+use stateful::message::payload::RemoveAgent;
 
-pub fn behavior(state: &mut State<'_>, _context: &Context<'_>) -> Result<()> {
-    let mut messages = state.take_messages()?;
-    for i in 0..state.num_agents() {
-        let m = &mut messages[i];
-        m.push(OutboundMessage::remove_agent(
-            state.agent_id()?[i].to_hyphenated_ref().to_string(),
-        ));
-    }
-
-    state.set_messages(messages);
+use crate::runner::rust::{agent::AgentState, context::AgentContext, error::RustResult};
 
+pub fn behavior(state: &mut AgentState<'_>, _context: &AgentContext<'_>) -> RustResult<()> {
+    // Without data, the message refers to the agent itself
+    state.add_message(&"hash", RemoveAgent::KIND, None)?;
     Ok(())
 }
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/remove_self/remove_self.rs".into(),
-        name: "@hash/remove_self/remove_self.rs".into(),
-        shortnames: vec!["@hash/remove_self/remove_self.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("remove_self.rs.json").to_string()),
-    }
-}
-
 // Original Source
 /*
 use crate::prelude::{AgentState, Context, OutboundMessage, SimulationResult};
@@ -1,51 +1,49 @@
-use super::super::OutboundMessage;
-use super::{Context, Result, SharedBehavior, State};
-
 use rand::Rng;
+use serde_json::json;
+use stateful::{agent::AgentStateField, message::payload::CreateAgent};
 
-pub fn behavior(state: &mut State<'_>, _context: &Context<'_>) -> Result<()> {
-    let mut messages = state.take_messages()?;
-    let children = state.child()?;
-    for (i, mut child) in children.into_iter().enumerate() {
-        let rate = match state.reproduction_rate()?[i] {
-            Some(rate) => rate,
-            None => 1.0,
-        };
+use crate::{
+    package::simulation::random_agent_id,
+    runner::rust::{
+        agent::{AgentFields, AgentState},
+        context::AgentContext,
+        error::RustResult,
+    },
+};
 
-        let mut num_children = (rate / 1.0) as i64;
+pub fn behavior(state: &mut AgentState<'_>, context: &AgentContext<'_>) -> RustResult<()> {
+    let mut rng = context.rng();
+    let rate = state.number("reproduction_rate").unwrap_or(1.0);
 
-        let chance = rate - (num_children as f64);
+    let mut num_children = rate as i64;
 
-        if rand::thread_rng().gen_range(0.0..1.0) < chance {
-            num_children += 1;
-        }
+    let chance = rate - (num_children as f64);
 
-        if let Some(map) = &state.reproduction_child_values()?[i]
-            .as_ref()
-            .map(|v| v.as_object())
-            .flatten()
-        {
-            for (key, value) in *map {
-                child.set(key, value.clone()).map_err(|e| e.to_string())?;
-            }
-        }
+    if rng.gen_range(0.0..1.0) < chance {
+        num_children += 1;
+    }
 
-        for _x in 0..num_children {
-            messages[i].push(OutboundMessage::create_agent(child.clone()));
+    // Children don't inherit the name and the messages of their parent
+    let mut child = state.fields_json();
+    child.remove(AgentStateField::AgentName.name());
+    child.remove(AgentStateField::Messages.name());
+    if let Some(map) = state.json("reproduction_child_values").as_object() {
+        for (key, value) in map {
+            child.insert(key.clone(), value.clone());
         }
     }
-    state.set_messages(messages);
-    Ok(())
-}
 
-pub fn get_named_behavior() -> SharedBehavior {
-    SharedBehavior {
-        id: "@hash/reproduce/reproduce.rs".into(),
-        name: "@hash/reproduce/reproduce.rs".into(),
-        shortnames: vec!["@hash/reproduce/reproduce.rs".into()],
-        behavior_src: None,
-        behavior_keys_src: Some(include_str!("reproduce.rs.json").to_string()),
+    for _ in 0..num_children {
+        // Every child needs its own agent id
+        let mut agent = child.clone();
+        agent.insert(
+            AgentStateField::AgentId.name().to_string(),
+            json!(random_agent_id(&mut *rng)),
+        );
+        state.add_message(&"hash", CreateAgent::KIND, Some(agent.into()))?;
     }
+
+    Ok(())
 }
 
 // Original Source
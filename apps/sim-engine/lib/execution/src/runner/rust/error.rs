@@ -0,0 +1,46 @@
+use thiserror::Error as ThisError;
+use tokio::sync::mpsc::error::SendError;
+use tracing::Span;
+
+use crate::{
+    package::simulation::SimulationId,
+    runner::{comms::InboundToRunnerMsgPayload, in_process::InProcessError},
+};
+
+pub type RustResult<T, E = RustError> = std::result::Result<T, E>;
+
+#[derive(ThisError, Debug)]
+pub enum RustError {
+    #[error("{0}")]
+    Unique(String),
+
+    #[error("Stateful error: {0}")]
+    Stateful(#[from] stateful::Error),
+
+    #[error("serde: {0:?}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    InProcess(#[from] InProcessError),
+
+    #[error("Can't start Rust runner again when it is already running")]
+    AlreadyRunning,
+
+    #[error("Couldn't send inbound message to runner: {0}")]
+    InboundSend(#[from] SendError<(Span, Option<SimulationId>, InboundToRunnerMsgPayload)>),
+
+    #[error("Couldn't receive outbound message from runner")]
+    OutboundReceive,
+}
+
+impl From<&str> for RustError {
+    fn from(s: &str) -> Self {
+        Self::Unique(s.to_string())
+    }
+}
+
+impl From<String> for RustError {
+    fn from(s: String) -> Self {
+        Self::Unique(s)
+    }
+}
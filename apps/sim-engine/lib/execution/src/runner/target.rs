@@ -19,6 +19,9 @@ pub enum MessageTarget {
     /// The message should be forwarded to _package.js_ implementation and executed on the
     /// JavaScript Language Runner.
     JavaScript,
+    /// The message should be forwarded to the WebAssembly Language Runner, which only implements
+    /// behavior execution.
+    Wasm,
     /// The Package implementation is responsible for deciding the routing of the message. This is
     /// decided by passing it to the [`WorkerHandler::handle_worker_message()`] implementation of
     /// the [`Task`].
@@ -38,6 +41,7 @@ impl From<Language> for MessageTarget {
             Language::Rust => Self::Rust,
             Language::Python => Self::Python,
             Language::JavaScript => Self::JavaScript,
+            Language::Wasm => Self::Wasm,
         }
     }
 }
@@ -48,6 +52,7 @@ impl From<flatbuffers_gen::target_generated::Target> for MessageTarget {
             flatbuffers_gen::target_generated::Target::Rust => Self::Rust,
             flatbuffers_gen::target_generated::Target::Python => Self::Python,
             flatbuffers_gen::target_generated::Target::JavaScript => Self::JavaScript,
+            flatbuffers_gen::target_generated::Target::Wasm => Self::Wasm,
             flatbuffers_gen::target_generated::Target::Dynamic => Self::Dynamic,
             flatbuffers_gen::target_generated::Target::Main => Self::Main,
             _ => unreachable!(),
@@ -61,6 +66,7 @@ impl From<MessageTarget> for flatbuffers_gen::target_generated::Target {
             MessageTarget::Rust => Self::Rust,
             MessageTarget::Python => Self::Python,
             MessageTarget::JavaScript => Self::JavaScript,
+            MessageTarget::Wasm => Self::Wasm,
             MessageTarget::Dynamic => Self::Dynamic,
             MessageTarget::Main => Self::Main,
         }
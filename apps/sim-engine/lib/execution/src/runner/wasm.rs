@@ -0,0 +1,153 @@
+//! Runner for behaviors compiled to WebAssembly.
+//!
+//! Behaviors are `.wasm` modules (or `.wat` modules in the text format), which may be written in
+//! any language compiling to WebAssembly, e.g. Rust, C, or AssemblyScript. They are executed in a
+//! sandbox by [`wasmtime`] within an [`in_process`] runner, which operates directly on the shared
+//! Arrow batches. Only the behavior execution package has a WebAssembly implementation, messages
+//! for other packages are rejected.
+//!
+//! # Module interface
+//!
+//! A behavior module has to export
+//!
+//! - `memory`, the linear memory used to exchange values with the engine,
+//! - `hash_alloc(len: i32) -> i32`, which allocates `len` bytes in `memory` and returns their
+//!   offset, and
+//! - `behavior()`, which is called once for every agent the behavior is executed on.
+//!
+//! The host API mirrors `hash_stdlib` of the other runners and is imported from the `hash`
+//! module. Strings are passed as `(ptr, len)` pairs of UTF-8 bytes in `memory` and values are
+//! encoded as JSON. Values returned by the engine are written to memory allocated by
+//! `hash_alloc` and returned as `i64` with the offset in the upper and the length in the lower
+//! 32 bits. The allocation is owned by the behavior afterwards.
+//!
+//! | Import | Description |
+//! |--------|-------------|
+//! | `state_get(key_ptr, key_len) -> i64` | Returns the value of a field of the agent's state. |
+//! | `state_set(key_ptr, key_len, value_ptr, value_len)` | Sets a field of the agent's state. |
+//! | `context_globals() -> i64` | Returns the globals of the simulation run. |
+//! | `context_step() -> i64` | Returns the current step of the simulation run. |
+//! | `context_neighbors() -> i64` | Returns the states of the agent's neighbors as array. |
+//! | `context_messages() -> i64` | Returns the messages the agent received as array. |
+//! | `send(to_ptr, to_len, type_ptr, type_len, data_ptr, data_len)` | Sends a message. |
+//! | `random() -> f64` | Returns a random number in `[0, 1)`, which respects the seed. |
+//!
+//! The recipients of `send` are a JSON string or an array of strings, the type is a plain string,
+//! and the data is optional JSON, omitted by passing a length of `0`. A behavior fails if it
+//! traps, e.g. by calling `unreachable`, or passes invalid arguments to the host API.
+//!
+//! [`in_process`]: crate::runner::in_process
+
+mod behavior_execution;
+mod error;
+mod host;
+
+use std::{pin::Pin, result::Result as StdResult, sync::Arc};
+
+use futures::{Future, FutureExt};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinError,
+};
+use tracing::Span;
+
+pub(crate) use self::error::WasmError;
+use crate::{
+    package::simulation::SimulationId,
+    runner::{
+        comms::{ExperimentInitRunnerMsg, InboundToRunnerMsgPayload, OutboundFromRunnerMsg},
+        in_process::run_experiment,
+        wasm::behavior_execution::WasmRuntime,
+    },
+    Result,
+};
+
+pub struct WasmRunner {
+    init_msg: Arc<ExperimentInitRunnerMsg>,
+    inbound_sender: UnboundedSender<(Span, Option<SimulationId>, InboundToRunnerMsgPayload)>,
+    inbound_receiver:
+        Option<UnboundedReceiver<(Span, Option<SimulationId>, InboundToRunnerMsgPayload)>>,
+    outbound_sender: Option<UnboundedSender<OutboundFromRunnerMsg>>,
+    outbound_receiver: UnboundedReceiver<OutboundFromRunnerMsg>,
+    spawn: bool,
+}
+
+impl WasmRunner {
+    pub fn new(spawn: bool, init_msg: ExperimentInitRunnerMsg) -> Result<Self> {
+        let (inbound_sender, inbound_receiver) = unbounded_channel();
+        let (outbound_sender, outbound_receiver) = unbounded_channel();
+
+        Ok(Self {
+            init_msg: Arc::new(init_msg),
+            inbound_sender,
+            inbound_receiver: Some(inbound_receiver),
+            outbound_sender: Some(outbound_sender),
+            outbound_receiver,
+            spawn,
+        })
+    }
+
+    pub async fn send(
+        &self,
+        sim_id: Option<SimulationId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> Result<()> {
+        tracing::trace!("Sending message to WebAssembly: {msg:?}");
+        self.inbound_sender
+            .send((Span::current(), sim_id, msg))
+            .map_err(|err| WasmError::InboundSend(err).into())
+    }
+
+    pub async fn send_if_spawned(
+        &self,
+        sim_id: Option<SimulationId>,
+        msg: InboundToRunnerMsgPayload,
+    ) -> Result<()> {
+        if self.spawned() {
+            self.send(sim_id, msg).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn recv(&mut self) -> Result<OutboundFromRunnerMsg> {
+        self.outbound_receiver
+            .recv()
+            .await
+            .ok_or_else(|| WasmError::OutboundReceive.into())
+    }
+
+    // TODO: UNUSED: Needs triage
+    #[allow(dead_code)]
+    pub async fn recv_now(&mut self) -> Result<Option<OutboundFromRunnerMsg>> {
+        self.recv().now_or_never().transpose()
+    }
+
+    pub fn spawned(&self) -> bool {
+        self.spawn
+    }
+
+    pub async fn run(
+        &mut self,
+    ) -> Result<Pin<Box<dyn Future<Output = StdResult<Result<()>, JoinError>> + Send>>> {
+        tracing::debug!("Running WebAssembly runner");
+        if !self.spawn {
+            return Ok(Box::pin(async move { Ok(Ok(())) }));
+        }
+
+        let init_msg = Arc::clone(&self.init_msg);
+        let inbound_receiver = self
+            .inbound_receiver
+            .take()
+            .ok_or(WasmError::AlreadyRunning)?;
+        let outbound_sender = self
+            .outbound_sender
+            .take()
+            .ok_or(WasmError::AlreadyRunning)?;
+
+        let f = || -> Result<()> {
+            run_experiment::<WasmRuntime>(init_msg, inbound_receiver, outbound_sender)
+                .map_err(|err| WasmError::from(err).into())
+        };
+        Ok(Box::pin(tokio::task::spawn_blocking(f)))
+    }
+}
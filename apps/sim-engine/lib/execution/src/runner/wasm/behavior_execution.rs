@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use wasmtime::{Engine, Linker, Module, Store, TypedFunc};
+
+use crate::{
+    package::simulation::state::behavior_execution::{
+        decode_wasm, BehaviorDescription, BehaviorId,
+    },
+    runner::{
+        in_process::{
+            AgentBehaviors, AgentGroup, BehaviorRuntime, InProcessError, InProcessResult, SimState,
+        },
+        wasm::host::{linker, HostState},
+        Language, MessageTarget,
+    },
+};
+
+/// Name of the function every behavior module exports to run the behavior on a single agent.
+const BEHAVIOR_FUNCTION_NAME: &str = "behavior";
+
+/// Invokes the behaviors compiled to WebAssembly.
+pub(in crate::runner::wasm) struct WasmRuntime {
+    engine: Engine,
+    linker: Linker<HostState>,
+}
+
+/// The instances of the behaviors, which are shared by all agents of a group, like the behavior
+/// functions of the JavaScript runner are.
+pub(in crate::runner::wasm) struct Instances {
+    store: Store<HostState>,
+    functions: HashMap<BehaviorId, TypedFunc<(), ()>>,
+}
+
+impl BehaviorRuntime for WasmRuntime {
+    type Behavior = Module;
+    type Group = Instances;
+
+    const LANGUAGE: Language = Language::Wasm;
+    const NAME: &'static str = "WebAssembly";
+    const RECEIVES_MESSAGES: bool = true;
+
+    fn new() -> InProcessResult<Self> {
+        let engine = Engine::default();
+        Ok(Self {
+            linker: linker(&engine)?,
+            engine,
+        })
+    }
+
+    /// Compiles the behavior, so it only has to be instantiated when running a task.
+    fn load(&self, description: &BehaviorDescription) -> InProcessResult<Module> {
+        let bytes = decode_wasm(&description.name, &description.source)
+            .map_err(|err| InProcessError::from(err.to_string()))?;
+        Module::new(&self.engine, bytes).map_err(|err| {
+            InProcessError::from(format!(
+                "Could not compile WebAssembly behavior `{}`: {err:#}",
+                description.name
+            ))
+        })
+    }
+
+    fn start_group(&self, sim: &SimState) -> InProcessResult<Instances> {
+        Ok(Instances {
+            store: Store::new(&self.engine, HostState::new(sim.context().clone())?),
+            functions: HashMap::new(),
+        })
+    }
+
+    fn run_agent(
+        &self,
+        instances: &mut Instances,
+        _sim: &SimState,
+        agents: &mut AgentGroup,
+        behaviors: AgentBehaviors<'_, Module>,
+    ) -> InProcessResult<Option<MessageTarget>> {
+        let Instances { store, functions } = instances;
+        store.data_mut().start_agent(
+            behaviors.agent_index,
+            behaviors.context_index,
+            behaviors.seed,
+        );
+        behaviors.run(|behavior_index, behavior_id, module| {
+            if !functions.contains_key(&behavior_id) {
+                let function = self
+                    .linker
+                    .instantiate(&mut *store, module)
+                    .and_then(|instance| {
+                        instance.get_typed_func::<(), (), _>(&mut *store, BEHAVIOR_FUNCTION_NAME)
+                    })
+                    .map_err(|err| format!("{err:#}"))?;
+                functions.insert(behavior_id, function);
+            }
+
+            // The agents are moved into the store while the behavior is running, so the host API
+            // has access to them.
+            store.data_mut().seed_behavior(behavior_index);
+            std::mem::swap(&mut store.data_mut().agents, agents);
+            let result = functions[&behavior_id].call(&mut *store, ());
+            std::mem::swap(&mut store.data_mut().agents, agents);
+            result.map_err(|trap| trap.to_string())
+        })
+    }
+}
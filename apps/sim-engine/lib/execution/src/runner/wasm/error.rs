@@ -0,0 +1,38 @@
+use thiserror::Error as ThisError;
+use tokio::sync::mpsc::error::SendError;
+use tracing::Span;
+
+use crate::{
+    package::simulation::SimulationId,
+    runner::{comms::InboundToRunnerMsgPayload, in_process::InProcessError},
+};
+
+#[derive(ThisError, Debug)]
+pub enum WasmError {
+    #[error("{0}")]
+    Unique(String),
+
+    #[error("{0}")]
+    InProcess(#[from] InProcessError),
+
+    #[error("Can't start WebAssembly runner again when it is already running")]
+    AlreadyRunning,
+
+    #[error("Couldn't send inbound message to runner: {0}")]
+    InboundSend(#[from] SendError<(Span, Option<SimulationId>, InboundToRunnerMsgPayload)>),
+
+    #[error("Couldn't receive outbound message from runner")]
+    OutboundReceive,
+}
+
+impl From<&str> for WasmError {
+    fn from(s: &str) -> Self {
+        Self::Unique(s.to_string())
+    }
+}
+
+impl From<String> for WasmError {
+    fn from(s: String) -> Self {
+        Self::Unique(s)
+    }
+}
@@ -0,0 +1,261 @@
+//! The host API imported by WebAssembly behaviors, see the [module documentation] for the
+//! interface.
+//!
+//! [module documentation]: crate::runner::wasm
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::Value;
+use stateful::{agent::AgentStateField, message::Message};
+use wasmtime::{Caller, Engine, Extern, Linker, Memory, Trap};
+
+use crate::{
+    package::simulation::Seed,
+    runner::in_process::{AgentColumns, AgentGroup, InProcessError, InProcessResult, SimContext},
+};
+
+/// Name of the module, from which behaviors import the host API.
+const HOST_MODULE: &str = "hash";
+
+/// The data of the store every behavior instance of a task is running in.
+pub(in crate::runner::wasm) struct HostState {
+    /// The agents of the group, which are only available while a behavior is running.
+    pub agents: AgentGroup,
+    /// Index of the agent, which the behavior is currently executed on, in its group.
+    index: usize,
+    context: SimContext,
+    /// Position of the agent in the context.
+    context_index: usize,
+    /// The seed of the agent in the current step, if the simulation run is seeded.
+    seed: Option<Seed>,
+    /// The random number generator of the behavior, which is currently executed.
+    rng: StdRng,
+}
+
+impl HostState {
+    pub fn new(context: SimContext) -> InProcessResult<Self> {
+        Ok(Self {
+            agents: AgentGroup::default(),
+            index: 0,
+            context,
+            context_index: 0,
+            seed: None,
+            rng: StdRng::from_rng(rand::thread_rng()).map_err(|err| {
+                InProcessError::from(format!("Could not create generator: {err}"))
+            })?,
+        })
+    }
+
+    /// Prepares the context for the agent at `index` in its group and at `context_index` in the
+    /// context with the agent's `seed` in the current step.
+    pub fn start_agent(&mut self, index: usize, context_index: usize, seed: Option<Seed>) {
+        self.index = index;
+        self.context_index = context_index;
+        self.seed = seed;
+    }
+
+    /// Seeds the random number generator for the behavior at `behavior_index` in the behavior
+    /// chain of the agent, the same way as the Rust runner does.
+    pub fn seed_behavior(&mut self, behavior_index: usize) {
+        if let Some(seed) = self.seed {
+            self.rng = seed.derive(behavior_index as u64).rng();
+        }
+    }
+}
+
+/// Creates a linker providing the host API to behavior modules compiled by `engine`.
+pub(in crate::runner::wasm) fn linker(engine: &Engine) -> InProcessResult<Linker<HostState>> {
+    let mut linker = Linker::new(engine);
+    let link_error = |err| InProcessError::from(format!("Could not define host API: {err:#}"));
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "state_get",
+            |mut caller: Caller<'_, HostState>, key_ptr: i32, key_len: i32| {
+                let key = read_str(&mut caller, key_ptr, key_len)?;
+                let data = caller.data();
+                let value = data.agents.columns.json(&key, data.index);
+                write_json(&mut caller, &value)
+            },
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "state_set",
+            |mut caller: Caller<'_, HostState>,
+             key_ptr: i32,
+             key_len: i32,
+             value_ptr: i32,
+             value_len: i32| {
+                let key = read_str(&mut caller, key_ptr, key_len)?;
+                let value = read_json(&mut caller, value_ptr, value_len)?;
+                let data = caller.data_mut();
+                data.agents
+                    .columns
+                    .set_json(&key, data.index, value)
+                    .map_err(|err| Trap::new(format!("Could not set `{key}`: {err}")))
+            },
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "context_globals",
+            |mut caller: Caller<'_, HostState>| {
+                let globals = caller.data().context.globals.0.clone();
+                write_json(&mut caller, &globals)
+            },
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "context_step",
+            |caller: Caller<'_, HostState>| caller.data().context.step as i64,
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "context_neighbors",
+            |mut caller: Caller<'_, HostState>| {
+                let data = caller.data();
+                let neighbors = data
+                    .context
+                    .neighbors(data.context_index)
+                    .map_err(to_trap)?
+                    .into_iter()
+                    .map(|(columns, index)| agent_json(columns, index))
+                    .collect();
+                write_json(&mut caller, &Value::Array(neighbors))
+            },
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "context_messages",
+            |mut caller: Caller<'_, HostState>| {
+                let data = caller.data();
+                let messages = data
+                    .context
+                    .messages(data.context_index)
+                    .map_err(to_trap)?
+                    .into_iter()
+                    .map(|(sender, message)| {
+                        let mut message = serde_json::to_value(message).map_err(to_trap)?;
+                        if let Value::Object(fields) = &mut message {
+                            fields.insert("from".to_string(), Value::String(sender.to_string()));
+                        }
+                        Ok(message)
+                    })
+                    .collect::<Result<_, Trap>>()?;
+                write_json(&mut caller, &Value::Array(messages))
+            },
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "send",
+            |mut caller: Caller<'_, HostState>,
+             to_ptr: i32,
+             to_len: i32,
+             type_ptr: i32,
+             type_len: i32,
+             data_ptr: i32,
+             data_len: i32| {
+                let to = match read_json(&mut caller, to_ptr, to_len)? {
+                    Value::String(recipient) => vec![recipient],
+                    recipients => {
+                        serde_json::from_value::<Vec<String>>(recipients).map_err(|_| {
+                            Trap::new("Message recipients must be a string or an array of strings")
+                        })?
+                    }
+                };
+                let kind = read_str(&mut caller, type_ptr, type_len)?;
+                let data = if data_len == 0 {
+                    None
+                } else {
+                    Some(read_json(&mut caller, data_ptr, data_len)?)
+                };
+                let host = caller.data_mut();
+                let sender = host.agents.columns.agent_id(host.index);
+                Message::from_sender(sender, to, &kind, data)
+                    .map(|message| host.agents.outbox.push(host.index, message))
+                    .map_err(|err| Trap::new(format!("Could not send `{kind}` message: {err}")))
+            },
+        )
+        .map_err(link_error)?;
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "random",
+            |mut caller: Caller<'_, HostState>| caller.data_mut().rng.gen::<f64>(),
+        )
+        .map_err(link_error)?;
+
+    Ok(linker)
+}
+
+/// Returns the fields of the agent at `index` in `columns` including its id as JSON object.
+fn agent_json(columns: &AgentColumns, index: usize) -> Value {
+    let mut fields = columns.fields_json(index);
+    fields.insert(
+        AgentStateField::AgentId.name().to_string(),
+        Value::String(columns.agent_id(index).to_string()),
+    );
+    Value::Object(fields)
+}
+
+fn to_trap(err: impl std::fmt::Display) -> Trap {
+    Trap::new(err.to_string())
+}
+
+fn memory(caller: &mut Caller<'_, HostState>) -> Result<Memory, Trap> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Trap::new("Behavior must export its `memory`"))
+}
+
+fn read_bytes(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<Vec<u8>, Trap> {
+    let memory = memory(caller)?;
+    let mut bytes = vec![0; len as u32 as usize];
+    memory
+        .read(&*caller, ptr as u32 as usize, &mut bytes)
+        .map_err(|_| Trap::new(format!("Could not read {len} bytes at {ptr}")))?;
+    Ok(bytes)
+}
+
+fn read_str(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, Trap> {
+    String::from_utf8(read_bytes(caller, ptr, len)?)
+        .map_err(|_| Trap::new("Strings passed to the host API must be valid UTF-8"))
+}
+
+fn read_json(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> Result<Value, Trap> {
+    serde_json::from_slice(&read_bytes(caller, ptr, len)?)
+        .map_err(|err| Trap::new(format!("Values passed to the host API must be JSON: {err}")))
+}
+
+/// Writes `value` as JSON into memory allocated by the behavior and returns the offset and the
+/// length packed into an `i64`.
+fn write_json(caller: &mut Caller<'_, HostState>, value: &Value) -> Result<i64, Trap> {
+    let bytes = serde_json::to_vec(value).map_err(to_trap)?;
+    let len = i32::try_from(bytes.len())
+        .map_err(|_| Trap::new("Value is too large to be passed to a behavior"))?;
+
+    let alloc = caller
+        .get_export("hash_alloc")
+        .and_then(Extern::into_func)
+        .ok_or_else(|| Trap::new("Behavior must export `hash_alloc`"))?
+        .typed::<i32, i32, _>(&*caller)
+        .map_err(|_| Trap::new("`hash_alloc` must have the signature `(i32) -> i32`"))?;
+    let ptr = alloc.call(&mut *caller, len)?;
+    memory(caller)?
+        .write(&mut *caller, ptr as u32 as usize, &bytes)
+        .map_err(|_| Trap::new(format!("`hash_alloc` returned invalid memory at {ptr}")))?;
+
+    Ok((i64::from(ptr as u32) << 32) | i64::from(len as u32))
+}
@@ -0,0 +1,130 @@
+//! A minimal HTTP/1.1 server for tests of the packages talking to HTTP APIs.
+//!
+//! Every connection carries a single request, which is answered by a handler and closed
+//! afterwards.
+
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// A request received by the test server.
+pub struct Request {
+    pub method: String,
+    /// The path of the request target without the query.
+    pub path: String,
+    /// The query parameters, parameters without a value map to an empty string.
+    pub query: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// The response to a [`Request`].
+pub struct Response {
+    pub status: &'static str,
+    pub headers: Vec<(&'static str, &'static str)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn header(mut self, name: &'static str, value: &'static str) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+}
+
+/// Starts a server on a local port answering every request with `handler` and returns its URL.
+pub async fn serve(handler: impl Fn(Request) -> Response + Send + Sync + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let handler = Arc::clone(&handler);
+            tokio::spawn(async move {
+                if let Some(request) = read_request(&mut stream).await {
+                    write_response(&mut stream, handler(request)).await;
+                }
+            });
+        }
+    });
+    format!("http://{address}")
+}
+
+/// Reads a request from `stream`, returns `None` if the connection was closed before.
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 8192];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let content_length = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().unwrap())
+        })
+        .unwrap_or(0);
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await.unwrap();
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let mut request_line = head.split_whitespace();
+    let method = request_line.next().unwrap().to_string();
+    let target = request_line.next().unwrap();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (name.to_string(), value.to_string())
+        })
+        .collect();
+    Some(Request {
+        method,
+        path: path.to_string(),
+        query,
+        body: buffer[header_end..header_end + content_length].to_vec(),
+    })
+}
+
+async fn write_response(stream: &mut TcpStream, response: Response) {
+    let headers = response
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}\r\n"))
+        .collect::<String>();
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.status,
+                response.body.len(),
+                response.body
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+}
@@ -11,13 +11,14 @@ mod pending;
 mod sync;
 mod task;
 
-use std::{collections::hash_map::Entry, future::Future, pin::Pin, time::Duration};
+use std::{collections::hash_map::Entry, future::Future, pin::Pin, sync::Arc, time::Duration};
 
 use futures::{
     future::OptionFuture,
     stream::{FuturesOrdered, FuturesUnordered},
     StreamExt,
 };
+use stateful::global::Globals;
 use tokio::time::timeout;
 use tracing::{Instrument, Span};
 
@@ -37,6 +38,7 @@ use crate::{
             OutboundFromRunnerMsg, OutboundFromRunnerMsgPayload, RunnerTaskMessage,
         },
         JavaScriptRunner, Language, MessageTarget, PythonRunner, RunnerConfig, RustRunner,
+        WasmRunner,
     },
     task::{SharedState, TaskId, TaskMessage, TaskResultOrCancelled, TaskSharedStore},
     worker_pool::comms::{
@@ -46,7 +48,7 @@ use crate::{
     Error, Result,
 };
 
-/// A task worker containing three dedicated language runners.
+/// A task worker containing four dedicated language runners.
 ///
 /// Depending on the [`RunnerSpawnConfig`] provided to the `Worker`, different language runners may
 /// be enabled or disabled.
@@ -60,6 +62,7 @@ pub struct Worker {
     py: PythonRunner,
     js: JavaScriptRunner,
     rs: RustRunner,
+    wasm: WasmRunner,
 
     // TODO: unused, remove?
     _runner_config: RunnerConfig,
@@ -70,8 +73,8 @@ pub struct Worker {
 
 // TODO: impl drop for worker?
 impl Worker {
-    /// Spawns a new worker, containing a runner for each language: JavaScript, Python, and Rust and
-    /// initializes them by sending the [`ExperimentInitRunnerMsg`].
+    /// Spawns a new worker, containing a runner for each language: JavaScript, Python, Rust, and
+    /// WebAssembly and initializes them by sending the [`ExperimentInitRunnerMsg`].
     pub async fn spawn(
         worker_config: WorkerConfig,
         worker_pool_comms: WorkerCommsWithWorkerPool,
@@ -82,12 +85,14 @@ impl Worker {
             python,
             javascript,
             rust,
+            wasm,
         } = worker_config.spawn;
         // TODO: Rust, JS
         Ok(Self {
             py: PythonRunner::new(python, exp_init.clone())?,
             js: JavaScriptRunner::new(javascript, exp_init.clone())?,
-            rs: RustRunner::new(rust, exp_init)?,
+            rs: RustRunner::new(rust, exp_init.clone())?,
+            wasm: WasmRunner::new(wasm, exp_init)?,
             _runner_config: worker_config.runner_config,
             worker_pool_comms,
             tasks: PendingWorkerTasks::default(),
@@ -126,6 +131,7 @@ impl Worker {
         let mut py_handle = self.py.run().await?;
         let mut rs_handle = self.rs.run().await?;
         let mut js_handle = self.js.run().await?;
+        let mut wasm_handle = self.wasm.run().await?;
 
         let mut wp_recv = self.worker_pool_comms.take_recv()?;
         let mut terminate_recv = self
@@ -193,6 +199,8 @@ impl Worker {
                     js_handle.await??;
                     // TODO: send termination to rs_handle
                     rs_handle.await??;
+                    // TODO: send termination to wasm_handle
+                    wasm_handle.await??;
                     return Ok(());
                 }
                 js_res = &mut js_handle, if self.js.spawned() => {
@@ -202,6 +210,8 @@ impl Worker {
                     py_handle.await??;
                     // TODO: send termination to rs_handle
                     rs_handle.await??;
+                    // TODO: send termination to wasm_handle
+                    wasm_handle.await??;
                     return Ok(());
                 }
                 rs_res = &mut rs_handle, if self.rs.spawned() => {
@@ -211,6 +221,19 @@ impl Worker {
                     py_handle.await??;
                     // TODO: send termination to js_handle
                     js_handle.await??;
+                    // TODO: send termination to wasm_handle
+                    wasm_handle.await??;
+                    return Ok(());
+                }
+                wasm_res = &mut wasm_handle, if self.wasm.spawned() => {
+                    tracing::warn!("WebAssembly runner finished unexpectedly: {wasm_res:?}");
+                    wasm_res??;
+                    // TODO: send termination to py_handle
+                    py_handle.await??;
+                    // TODO: send termination to js_handle
+                    js_handle.await??;
+                    // TODO: send termination to rs_handle
+                    rs_handle.await??;
                     return Ok(());
                 }
             }
@@ -219,6 +242,7 @@ impl Worker {
         py_handle.await??;
         rs_handle.await??;
         js_handle.await??;
+        wasm_handle.await??;
 
         Ok(())
     }
@@ -231,11 +255,13 @@ impl Worker {
     ///   - [`CancelTask`], The specified task is canceled, for all runners. A [`CancelTask`]
     ///     containing the task_id is sent to all runners that have been spawned.
     ///   - [`NewSimulationRun`]: Message is forwarded to all runners.
+    ///   - [`UpdateGlobals`]: Message is forwarded to all runners.
     ///
     /// [`Task`]: WorkerPoolToWorkerMsgPayload::Task
     /// [`Sync`]: WorkerPoolToWorkerMsgPayload::Sync
     /// [`CancelTask`]: WorkerPoolToWorkerMsgPayload::CancelTask
     /// [`NewSimulationRun`]: WorkerPoolToWorkerMsgPayload::NewSimulationRun
+    /// [`UpdateGlobals`]: WorkerPoolToWorkerMsgPayload::UpdateGlobals
     ///
     /// [`sync_runners`]: Self::sync_runners
     /// [`CancelTask`]: InboundToRunnerMsgPayload::CancelTask
@@ -272,6 +298,16 @@ impl Worker {
                     .instrument(span)
                     .await?;
             }
+            WorkerPoolToWorkerMsgPayload::UpdateGlobals(globals) => {
+                self.update_globals(
+                    msg.sim_id.ok_or_else(|| {
+                        Error::from("Expected simulation id for updating the globals")
+                    })?,
+                    globals,
+                )
+                .instrument(span)
+                .await?;
+            }
         }
         Ok(())
     }
@@ -280,8 +316,8 @@ impl Worker {
     ///
     /// Depending on the content of the message, the following actions are executed:
     ///   - [`TaskMsg`]: Depending on the [`target`], the following actions are executed:
-    ///     - [`Javascript`]/[`Python`]/[`Rust`]: The message is forwarded to the corresponding
-    ///       language runner and the active runner is set to the language.
+    ///     - [`Javascript`]/[`Python`]/[`Rust`]/[`Wasm`]: The message is forwarded to the
+    ///       corresponding language runner and the active runner is set to the language.
     ///     - [`Dynamic`]: The message is forwarded to the language runner determined dynamically.
     ///     - [`Main`]: Finishes the task if any. See [`handle_end_message`] for more information.
     ///   - [`TaskCancelled`]: Cancels the task if any. See [`handle_cancel_task_confirmation`] for
@@ -305,6 +341,7 @@ impl Worker {
     /// [`JavaScript`]: MessageTarget::JavaScript
     /// [`Python`]: MessageTarget::Python
     /// [`Rust`]: MessageTarget::Rust
+    /// [`Wasm`]: MessageTarget::Wasm
     /// [`Dynamic`]: MessageTarget::Dynamic
     /// [`Main`]: MessageTarget::Main
     async fn handle_runner_msg(&mut self, msg: OutboundFromRunnerMsg) -> Result<()> {
@@ -333,6 +370,12 @@ impl Worker {
                         .in_current_span()
                         .await?;
                 }
+                MessageTarget::Wasm => {
+                    self.wasm
+                        .send(Some(sim_id), InboundToRunnerMsgPayload::TaskMsg(task.msg))
+                        .in_current_span()
+                        .await?;
+                }
                 MessageTarget::Dynamic => {
                     self.run_task_handler_on_outbound(sim_id, task.msg, msg.source)
                         .in_current_span()
@@ -402,6 +445,8 @@ impl Worker {
             self.js
                 .send_if_spawned(None, InboundToRunnerMsgPayload::TerminateRunner),
             self.rs
+                .send_if_spawned(None, InboundToRunnerMsgPayload::TerminateRunner),
+            self.wasm
                 .send_if_spawned(None, InboundToRunnerMsgPayload::TerminateRunner)
         )?;
         Ok(())
@@ -476,8 +521,8 @@ impl Worker {
     ///
     ///
     ///   Depending on the [`target`], the following actions are executed:
-    ///   - [`Javascript`]/[`Python`]/[`Rust`]: The message is forwarded to the corresponding
-    ///     language runner and the active runner is set to the language.
+    ///   - [`Javascript`]/[`Python`]/[`Rust`]/[`Wasm`]: The message is forwarded to the
+    ///     corresponding language runner and the active runner is set to the language.
     ///   - [`Main`]: Finishes the task if any. See [`handle_end_message`] for more information.
     ///   - [`Dynamic`] is an unexpected target and will return an error.
     ///
@@ -486,6 +531,7 @@ impl Worker {
     /// [`JavaScript`]: MessageTarget::JavaScript
     /// [`Python`]: MessageTarget::Python
     /// [`Rust`]: MessageTarget::Rust
+    /// [`Wasm`]: MessageTarget::Wasm
     /// [`Main`]: MessageTarget::Main
     /// [`Dynamic`]: MessageTarget::Dynamic
     async fn run_task_handler_on_outbound(
@@ -550,6 +596,24 @@ impl Worker {
                         .get_pending_group_mut(msg.group_index)?
                         .active_runner = Language::JavaScript;
                 }
+                MessageTarget::Wasm => {
+                    let inbound = InboundToRunnerMsgPayload::TaskMsg(RunnerTaskMessage {
+                        package_id: msg.package_id,
+                        task_id: msg.task_id,
+                        group_index: msg.group_index,
+                        shared_store: msg.shared_store,
+                        payload: next.payload,
+                    });
+                    tracing::trace!(
+                        "Task resulted in a new message from Runner, sending new one to \
+                         WebAssembly: {:?}",
+                        &inbound
+                    );
+                    self.wasm.send(Some(sim_id), inbound).await?;
+                    pending
+                        .get_pending_group_mut(msg.group_index)?
+                        .active_runner = Language::Wasm;
+                }
                 MessageTarget::Dynamic => return Err(Error::UnexpectedTarget(next.target)),
                 MessageTarget::Main => {
                     tracing::trace!("Task message came back to main, finishing task");
@@ -675,6 +739,11 @@ impl Worker {
                     self.rs.send(Some(sim_id), runner_msg).await?;
                     Language::Rust
                 }
+                MessageTarget::Wasm => {
+                    tracing::debug!("Sending task message to WebAssembly");
+                    self.wasm.send(Some(sim_id), runner_msg).await?;
+                    Language::Wasm
+                }
                 MessageTarget::Main | MessageTarget::Dynamic => {
                     // Expected initial message to be directed to a language runtime
                     return Err(Error::UnexpectedTarget(msg.target));
@@ -719,19 +788,23 @@ impl Worker {
                     .send_if_spawned(sim_id, sync_msg.try_clone()?.into()),
                 self.js
                     .send_if_spawned(sim_id, sync_msg.try_clone()?.into()),
-                self.rs.send_if_spawned(sim_id, sync_msg.into())
+                self.rs
+                    .send_if_spawned(sim_id, sync_msg.try_clone()?.into()),
+                self.wasm.send_if_spawned(sim_id, sync_msg.into())
             )?;
             return Ok(());
         };
 
-        debug_assert!(!self.rs.spawned());
         let (runner_msgs, runner_receivers) = sync.create_children(
-            self.js.spawned() as usize + self.py.spawned() as usize + self.rs.spawned() as usize,
+            self.js.spawned() as usize
+                + self.py.spawned() as usize
+                + self.rs.spawned() as usize
+                + self.wasm.spawned() as usize,
         );
         let mut messages = runner_msgs
             .into_iter()
             .map(InboundToRunnerMsgPayload::StateSync);
-        let (js_res, py_res, rs_res) = tokio::join!(
+        let (js_res, py_res, rs_res, wasm_res) = tokio::join!(
             OptionFuture::from(
                 self.js
                     .spawned()
@@ -747,10 +820,16 @@ impl Worker {
                     .spawned()
                     .then(|| self.rs.send(sim_id, messages.next().unwrap()))
             ),
+            OptionFuture::from(
+                self.wasm
+                    .spawned()
+                    .then(|| self.wasm.send(sim_id, messages.next().unwrap()))
+            ),
         );
         js_res.transpose()?;
         py_res.transpose()?;
         rs_res.transpose()?;
+        wasm_res.transpose()?;
 
         let fut = async move {
             // Capture `sync` in lambda.
@@ -781,6 +860,8 @@ impl Worker {
             self.js
                 .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id)),
             self.rs
+                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id)),
+            self.wasm
                 .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
         )?;
         Ok(())
@@ -796,27 +877,23 @@ impl Worker {
         todo!("Cancel messages are not implemented yet");
         // see https://app.asana.com/0/1199548034582004/1202011714603653/f
 
-        if matches!(runner_language, Language::Python) {
-            self.js
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-            self.rs
+        if !matches!(runner_language, Language::Python) {
+            self.py
                 .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
                 .await?;
         }
-        if matches!(runner_language, Language::JavaScript) {
-            self.py
+        if !matches!(runner_language, Language::JavaScript) {
+            self.js
                 .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
                 .await?;
+        }
+        if !matches!(runner_language, Language::Rust) {
             self.rs
                 .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
                 .await?;
         }
-        if matches!(runner_language, Language::Rust) {
-            self.py
-                .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
-                .await?;
-            self.js
+        if !matches!(runner_language, Language::Wasm) {
+            self.wasm
                 .send_if_spawned(None, InboundToRunnerMsgPayload::CancelTask(task_id))
                 .await?;
         }
@@ -840,6 +917,12 @@ impl Worker {
                 )
                 .instrument(span.clone()),
             self.rs
+                .send_if_spawned(
+                    Some(new_simulation_run.short_id),
+                    InboundToRunnerMsgPayload::NewSimulationRun(new_simulation_run.clone())
+                )
+                .instrument(span.clone()),
+            self.wasm
                 .send_if_spawned(
                     Some(new_simulation_run.short_id),
                     InboundToRunnerMsgPayload::NewSimulationRun(new_simulation_run)
@@ -849,6 +932,38 @@ impl Worker {
         Ok(())
     }
 
+    /// Forwards the new globals of a simulation run to all spawned runners.
+    async fn update_globals(&mut self, sim_id: SimulationId, globals: Arc<Globals>) -> Result<()> {
+        let span = Span::current();
+        tokio::try_join!(
+            self.py
+                .send_if_spawned(
+                    Some(sim_id),
+                    InboundToRunnerMsgPayload::UpdateGlobals(Arc::clone(&globals))
+                )
+                .instrument(span.clone()),
+            self.js
+                .send_if_spawned(
+                    Some(sim_id),
+                    InboundToRunnerMsgPayload::UpdateGlobals(Arc::clone(&globals))
+                )
+                .instrument(span.clone()),
+            self.rs
+                .send_if_spawned(
+                    Some(sim_id),
+                    InboundToRunnerMsgPayload::UpdateGlobals(Arc::clone(&globals))
+                )
+                .instrument(span.clone()),
+            self.wasm
+                .send_if_spawned(
+                    Some(sim_id),
+                    InboundToRunnerMsgPayload::UpdateGlobals(globals)
+                )
+                .instrument(span.clone())
+        )?;
+        Ok(())
+    }
+
     /// Waits for a message from any spawned worker.
     async fn recv_from_runners(&mut self) -> Result<OutboundFromRunnerMsg> {
         tokio::select! {
@@ -861,6 +976,9 @@ impl Worker {
             res = self.rs.recv(), if self.rs.spawned() => {
                 res
             }
+            res = self.wasm.recv(), if self.wasm.spawned() => {
+                res
+            }
         }
     }
 }
@@ -5,6 +5,7 @@ pub struct RunnerSpawnConfig {
     pub python: bool,
     pub javascript: bool,
     pub rust: bool,
+    pub wasm: bool,
 }
 
 impl Default for RunnerSpawnConfig {
@@ -13,6 +14,7 @@ impl Default for RunnerSpawnConfig {
             python: true,
             javascript: true,
             rust: true,
+            wasm: true,
         }
     }
 }
@@ -285,6 +285,9 @@ impl WorkerPool {
                 .in_current_span();
                 pending_syncs.push(Box::pin(fut) as _);
             }
+            EngineToWorkerPoolMsgPayload::UpdateGlobals(globals) => {
+                self.send_to_all_workers(WorkerPoolToWorkerMsg::update_globals(sim_id, globals))?;
+            }
         }
         Ok(())
     }
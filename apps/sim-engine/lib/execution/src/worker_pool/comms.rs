@@ -19,6 +19,9 @@ pub mod message;
 pub mod terminate;
 pub mod top;
 
+use std::sync::Arc;
+
+use stateful::global::Globals;
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     oneshot::Receiver,
@@ -41,6 +44,7 @@ pub enum WorkerPoolToWorkerMsgPayload {
     Sync(SyncPayload),
     CancelTask(TaskId),
     NewSimulationRun(NewSimulationRun),
+    UpdateGlobals(Arc<Globals>),
 }
 
 #[derive(Debug)]
@@ -65,6 +69,9 @@ impl WorkerPoolToWorkerMsg {
             WorkerPoolToWorkerMsgPayload::NewSimulationRun(inner) => Ok(
                 WorkerPoolToWorkerMsgPayload::NewSimulationRun(inner.clone()),
             ),
+            WorkerPoolToWorkerMsgPayload::UpdateGlobals(inner) => Ok(
+                WorkerPoolToWorkerMsgPayload::UpdateGlobals(Arc::clone(inner)),
+            ),
         }?;
 
         Ok(WorkerPoolToWorkerMsg {
@@ -111,6 +118,14 @@ impl WorkerPoolToWorkerMsg {
             payload: WorkerPoolToWorkerMsgPayload::NewSimulationRun(new_simulation_run),
         }
     }
+
+    pub fn update_globals(sim_id: SimulationId, globals: Arc<Globals>) -> WorkerPoolToWorkerMsg {
+        WorkerPoolToWorkerMsg {
+            span: Span::current(),
+            sim_id: Some(sim_id),
+            payload: WorkerPoolToWorkerMsgPayload::UpdateGlobals(globals),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,4 +1,6 @@
-use stateful::field::PackageId;
+use std::sync::Arc;
+
+use stateful::{field::PackageId, global::Globals};
 use tracing::Span;
 
 use crate::{
@@ -40,10 +42,20 @@ impl EngineToWorkerPoolMsg {
             payload: EngineToWorkerPoolMsgPayload::Sync(sync_msg),
         }
     }
+
+    pub fn update_globals(sim_id: SimulationId, globals: Arc<Globals>) -> Self {
+        Self {
+            span: Span::current(),
+            sim_id,
+            payload: EngineToWorkerPoolMsgPayload::UpdateGlobals(globals),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum EngineToWorkerPoolMsgPayload {
     Task(WrappedTask),
     Sync(SyncPayload),
+    /// The globals of the simulation run have changed.
+    UpdateGlobals(Arc<Globals>),
 }
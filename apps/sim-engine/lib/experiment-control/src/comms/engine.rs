@@ -1,5 +1,7 @@
+use execution::package::simulation::SimulationId;
 use experiment_structure::ExperimentRun;
 use serde::{Deserialize, Serialize};
+use simulation_control::controller::SimControl;
 
 use crate::environment::ExecutionEnvironment;
 
@@ -22,4 +24,9 @@ pub struct InitMessage {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum EngineMsg {
     Init(InitMessage),
+    /// Pauses, resumes, stops, or single-steps the simulation run with the given id
+    SimControl {
+        sim_id: SimulationId,
+        control: SimControl,
+    },
 }
@@ -46,7 +46,7 @@ pub struct ExperimentController<P: OutputPersistenceCreator> {
     experiment_package_comms: ExperimentPackageComms,
     output_persistence_service_creator: P,
     sim_run_tasks: SimulationRuns,
-    sim_senders: HashMap<SimulationId, SimCtlSend>,
+    sim_senders: SimSenders,
     worker_pool_send_base: MainMsgSendBase,
     package_creators: PackageCreators,
     sim_configurer: SimConfigurer,
@@ -55,11 +55,59 @@ pub struct ExperimentController<P: OutputPersistenceCreator> {
     terminate_recv: TerminateRecv,
 }
 
+/// The control message senders of the simulation runs, which haven't finished yet.
+#[derive(Default)]
+struct SimSenders(HashMap<SimulationId, SimCtlSend>);
+
+impl SimSenders {
+    fn add(&mut self, sim_short_id: SimulationId, sender: SimCtlSend) -> Result<()> {
+        if self.0.contains_key(&sim_short_id) {
+            let msg = "Cannot mutate a simulation control msg sender";
+            tracing::error!("{}, sim short id: {}", msg, sim_short_id);
+            return Err(Error::from(msg));
+        }
+        self.0.insert(sim_short_id, sender);
+        Ok(())
+    }
+
+    fn remove(&mut self, sim_short_id: SimulationId) {
+        self.0.remove(&sim_short_id);
+    }
+
+    /// Sends `msg` to the simulation run.
+    ///
+    /// A simulation run may finish at any time, so a message to a simulation run, which doesn't
+    /// exist (anymore), is dropped with a warning instead of failing the experiment.
+    async fn send(&mut self, sim_short_id: SimulationId, msg: SimControl) {
+        match self.0.get_mut(&sim_short_id) {
+            Some(sender) => {
+                if let Err(err) = sender.send(msg).await {
+                    tracing::warn!(
+                        "Dropping control message for simulation run {sim_short_id}, which has \
+                         already finished: {err}"
+                    );
+                }
+            }
+            None => {
+                tracing::warn!(
+                    "Dropping {msg:?} for simulation run {sim_short_id}, which doesn't exist or has \
+                     already finished"
+                );
+            }
+        }
+    }
+}
+
 impl<P: OutputPersistenceCreator> ExperimentController<P> {
     /// Handle an inbound message from the orchestrator (or CLI)
     async fn handle_orch_msg(&mut self, orch_msg: EngineMsg) -> Result<()> {
         match orch_msg {
             EngineMsg::Init(_) => Err(Error::from("Unexpected init message")),
+            EngineMsg::SimControl { sim_id, control } => {
+                tracing::debug!("Received {control:?} for simulation run {sim_id}");
+                self.sim_senders.send(sim_id, control).await;
+                Ok(())
+            }
         }
     }
 
@@ -117,6 +165,7 @@ impl<P: OutputPersistenceCreator> ExperimentController<P> {
                 sim_id: status.sim_id,
                 was_error: status.error.is_some(),
                 stop_signal: status.stop_signal,
+                analysis_output: status.analysis_output.clone(),
             })
             .await
             .map_err(|exp_controller_err| {
@@ -146,6 +195,7 @@ impl<P: OutputPersistenceCreator> ExperimentController<P> {
     }
 
     async fn handle_sim_run_stop(&mut self, id: SimulationId) -> Result<()> {
+        self.sim_senders.remove(id);
         self.orch_client().send(EngineStatus::SimStop(id)).await
     }
 
@@ -280,9 +330,10 @@ impl<P: OutputPersistenceCreator> ExperimentController<P> {
             packages,
             persistence_service,
             self.sim_status_send.clone(),
+            config::checkpoint(&self.env)?,
         )?;
         let sim_sender = sim_controller.sender;
-        self.add_sim_sender(sim_short_id, sim_sender)?;
+        self.sim_senders.add(sim_short_id, sim_sender)?;
         self.sim_run_tasks.new_run(sim_controller.task_handle);
 
         // Register run with the orchestrator
@@ -296,32 +347,22 @@ impl<P: OutputPersistenceCreator> ExperimentController<P> {
     }
 
     async fn pause_sim_run(&mut self, sim_short_id: SimulationId) -> Result<()> {
-        self.send_sim(sim_short_id, SimControl::Pause).await?;
+        self.sim_senders.send(sim_short_id, SimControl::Pause).await;
         Ok(())
     }
 
     async fn resume_sim_run(&mut self, sim_short_id: SimulationId) -> Result<()> {
-        self.send_sim(sim_short_id, SimControl::Resume).await?;
+        self.sim_senders
+            .send(sim_short_id, SimControl::Resume)
+            .await;
         Ok(())
     }
 
     async fn stop_sim_run(&mut self, sim_short_id: SimulationId) -> Result<()> {
-        self.send_sim(sim_short_id, SimControl::Stop).await?;
+        self.sim_senders.send(sim_short_id, SimControl::Stop).await;
         Ok(())
     }
 
-    async fn send_sim(&mut self, sim_short_id: SimulationId, msg: SimControl) -> Result<()> {
-        if let Some(sender) = self.sim_senders.get_mut(&sim_short_id) {
-            sender.send(msg).await?;
-            Ok(())
-        } else {
-            Err(Error::from(format!(
-                "Simulation with short id {} does not exist",
-                sim_short_id
-            )))
-        }
-    }
-
     pub async fn exp_init_msg_base(&self) -> Result<ExperimentInitRunnerMsgBase> {
         let pkg_start_msgs = self.package_creators.init_message()?;
         Ok(ExperimentInitRunnerMsgBase {
@@ -337,16 +378,6 @@ impl<P: OutputPersistenceCreator> ExperimentController<P> {
         })
     }
 
-    fn add_sim_sender(&mut self, sim_short_id: SimulationId, sender: SimCtlSend) -> Result<()> {
-        if self.sim_senders.contains_key(&sim_short_id) {
-            let msg = "Cannot mutate a simulation control msg sender";
-            tracing::error!("{}, sim short id: {}", msg, sim_short_id);
-            return Err(Error::from(msg));
-        }
-        self.sim_senders.insert(sim_short_id, sender);
-        Ok(())
-    }
-
     fn orch_client(&mut self) -> &mut OrchClient {
         &mut self.env.orch_client
     }
@@ -490,3 +521,30 @@ pub fn apply_globals_changes(base: Globals, changes: &serde_json::Value) -> Resu
     let globals = Globals(map.into());
     Ok(globals)
 }
+
+#[cfg(test)]
+mod tests {
+    use simulation_control::comms::control::new_pair;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn control_message_to_finished_sim_is_dropped() -> Result<()> {
+        let sim_id = SimulationId::new(1);
+        let mut senders = SimSenders::default();
+        let (sender, mut receiver) = new_pair();
+        senders.add(sim_id, sender)?;
+
+        senders.send(sim_id, SimControl::Pause).await;
+        assert_eq!(receiver.recv().await, Some(SimControl::Pause));
+
+        // The simulation run stops its receiver before the experiment controller is notified.
+        drop(receiver);
+        senders.send(sim_id, SimControl::Resume).await;
+
+        senders.remove(sim_id);
+        senders.send(sim_id, SimControl::Stop).await;
+        senders.send(SimulationId::new(2), SimControl::Stop).await;
+        Ok(())
+    }
+}
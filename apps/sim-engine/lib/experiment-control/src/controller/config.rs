@@ -1,13 +1,18 @@
-use execution::package::simulation::output::persistence::local::LocalPersistenceConfig;
+use execution::package::simulation::output::persistence::{
+    local::LocalPersistenceConfig, s3::S3PersistenceConfig,
+};
 use serde::{Deserialize, Serialize};
+use simulation_control::checkpoint::CheckpointConfig;
 
 use crate::{environment::Environment, Error, Result};
 
 pub const OUTPUT_PERSISTENCE_KEY: &str = "output_persistence";
+pub const CHECKPOINT_KEY: &str = "checkpoint";
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum OutputPersistenceConfig {
     Local(LocalPersistenceConfig),
+    S3(S3PersistenceConfig),
     None,
 }
 
@@ -15,6 +20,16 @@ pub fn output_persistence(env: &Environment) -> Result<OutputPersistenceConfig>
     get_dynamic(env, OUTPUT_PERSISTENCE_KEY)
 }
 
+/// Returns where and how often simulation runs write checkpoints, or `None` if checkpoints are
+/// disabled.
+pub fn checkpoint(env: &Environment) -> Result<Option<CheckpointConfig>> {
+    if env.dyn_payloads.contains_key(CHECKPOINT_KEY) {
+        get_dynamic(env, CHECKPOINT_KEY).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 pub fn get_dynamic<K>(env: &Environment, key: &str) -> Result<K>
 where
     K: for<'de> Deserialize<'de>,
@@ -2,9 +2,10 @@ use std::{pin::Pin, sync::Arc, time::Duration};
 
 use execution::{
     package::{
-        experiment::{ExperimentId, ExperimentPackage, ExperimentPackageConfig},
+        experiment::{ExperimentId, ExperimentPackage},
         simulation::output::persistence::{
-            local::LocalOutputPersistence, none::NoOutputPersistence, OutputPersistenceCreator,
+            local::LocalOutputPersistence, none::NoOutputPersistence, s3::S3OutputPersistence,
+            OutputPersistenceCreator,
         },
     },
     worker::Worker,
@@ -83,6 +84,17 @@ pub async fn run_local_experiment(exp_config: ExperimentConfig, env: Environment
             };
             run_experiment_with_persistence(exp_config, env, persistence).await?;
         }
+        OutputPersistenceConfig::S3(s3) => {
+            tracing::debug!("Running experiment with S3 persistence");
+            let persistence = S3OutputPersistence::new(
+                exp_config.experiment_run.simulation().name.clone(),
+                exp_config.experiment_run.name().clone(),
+                exp_config.experiment_run.id(),
+                s3.clone(),
+            )
+            .await;
+            run_experiment_with_persistence(exp_config, env, persistence).await?;
+        }
         OutputPersistenceConfig::None => {
             tracing::debug!("Running experiment without output persistence");
             let persistence = NoOutputPersistence::new();
@@ -122,23 +134,13 @@ async fn run_experiment_with_persistence<P: OutputPersistenceCreator>(
         worker_pool_send,
     )?;
 
-    let experiment_package = if let ExperimentPackageConfig::Basic(package_config) =
-        exp_config.experiment_run.config()
-    {
-        // Start up the experiment package (simple/single)
-        ExperimentPackage::new(package_config.clone())
-            .await
-            .map_err(|experiment_err| Error::from(experiment_err.to_string()))?
-    } else {
-        unreachable!();
-    };
+    // Start up the experiment package (simple/single/optimization)
+    let package_config = exp_config.experiment_run.config();
+    let experiment_package = ExperimentPackage::new(package_config.clone())
+        .await
+        .map_err(|experiment_err| Error::from(experiment_err.to_string()))?;
     let mut experiment_package_handle = experiment_package.join_handle;
 
-    let package_config = match exp_config.experiment_run.config() {
-        ExperimentPackageConfig::Basic(package_config) => package_config,
-        _ => unreachable!(),
-    };
-
     let worker_allocator = SimConfigurer::new(package_config, exp_config.worker_pool.num_workers);
     let package_creators = PackageCreators::from_config(
         &exp_config.packages,
@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use execution::{
     package::{
-        experiment::basic::BasicExperimentConfig,
+        experiment::{
+            basic::BasicExperimentConfig, extended::ExtendedExperimentConfig,
+            ExperimentPackageConfig,
+        },
         simulation::{PersistenceConfig, SimulationId},
     },
     worker_pool::{WorkerAllocation, WorkerIndex},
@@ -15,13 +18,19 @@ pub struct SimConfigurer {
 }
 
 impl SimConfigurer {
-    pub fn new(package_config: &BasicExperimentConfig, num_workers: usize) -> SimConfigurer {
+    pub fn new(package_config: &ExperimentPackageConfig, num_workers: usize) -> SimConfigurer {
         let num_workers_per_sim = match package_config {
-            BasicExperimentConfig::Simple(config) => {
+            ExperimentPackageConfig::Basic(BasicExperimentConfig::Simple(config)) => {
                 let num_runs = config.changed_globals.len();
                 std::cmp::max(1, (num_workers as f64 / num_runs as f64).ceil() as usize)
             }
-            BasicExperimentConfig::SingleRun(_) => std::cmp::max(1, num_workers),
+            ExperimentPackageConfig::Basic(BasicExperimentConfig::SingleRun(_)) => {
+                std::cmp::max(1, num_workers)
+            }
+            ExperimentPackageConfig::Extended(ExtendedExperimentConfig::Optimization(config)) => {
+                let num_runs = config.num_parallel_runs;
+                std::cmp::max(1, (num_workers as f64 / num_runs as f64).ceil() as usize)
+            }
         };
 
         SimConfigurer {
@@ -5,6 +5,7 @@ mod logging;
 use std::time::Duration;
 
 use experiment_structure::ExperimentRun;
+use memory::shared_memory::{set_memory_backend, MemoryBackend, OwnerLock};
 use simulation_control::EngineStatus;
 
 pub use self::{
@@ -17,6 +18,30 @@ use crate::{
     Error, Result,
 };
 
+/// Selects the [`MemoryBackend`] of this engine process from the `args`.
+///
+/// This has to be called before any batch is created. The returned lock marks the segments of the
+/// experiment as in use, so they are not removed by the reaper of another process, and has to be
+/// held until the segments are cleaned up.
+///
+/// # Errors
+///
+/// - if the heap backend is selected, but the `experiment` requires the Python runner, which can't
+///   access memory on the heap of the engine
+/// - if the memory backend can't be set up
+/// - if the segments of the experiment can't be locked
+pub fn init_memory_backend(args: &Args, experiment: &ExperimentRun) -> Result<Option<OwnerLock>> {
+    if args.memory_backend == MemoryBackend::Heap && experiment.create_runner_spawn_config().python
+    {
+        return Err(Error::from(
+            "The `heap` memory backend can't be used with Python behaviors, use `shm` or `mmap` \
+             instead",
+        ));
+    }
+    set_memory_backend(args.memory_backend, args.memory_dir.clone())?;
+    Ok(OwnerLock::acquire(args.experiment_id.as_uuid())?)
+}
+
 const INIT_MSG_RECV_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct Environment {
@@ -66,6 +91,7 @@ impl Environment {
 
         match msg {
             EngineMsg::Init(init) => Ok(init),
+            EngineMsg::SimControl { .. } => Err(Error::UnexpectedEngineMsgExpectedInit),
         }
     }
 }
@@ -6,6 +6,7 @@ use clap::{
     Parser,
 };
 use execution::package::experiment::ExperimentId;
+use memory::shared_memory::MemoryBackend;
 
 use crate::environment::{LogFormat, LogLevel, OutputLocation};
 
@@ -89,6 +90,30 @@ pub struct Args {
     /// Defaults to V8's `max_heap_size` default.
     #[cfg_attr(feature = "clap", clap(long))]
     pub js_runner_max_heap_size: Option<usize>,
+
+    /// Directory containing the Python runner.
+    ///
+    /// Defaults to the location of the Python runner in the repository the engine was built from.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub python_runner_dir: Option<PathBuf>,
+
+    /// Where the memory of agent batches, messages and datasets is stored.
+    ///
+    /// `shm` uses POSIX shared memory in `/dev/shm`. `mmap` uses memory-mapped files in
+    /// "--memory-dir", which helps when `/dev/shm` is small, e.g. inside of containers. `heap`
+    /// allocates the memory inside of the engine process, which can't be used with Python
+    /// behaviors.
+    #[cfg_attr(
+        feature = "clap",
+        clap(long, default_value = "shm", env = "HASH_MEMORY_BACKEND")
+    )]
+    pub memory_backend: MemoryBackend,
+
+    /// Directory containing the files of the `mmap` memory backend.
+    ///
+    /// Defaults to a `hash-engine` directory inside of the temporary directory.
+    #[cfg_attr(feature = "clap", clap(long, env = "HASH_MEMORY_DIR"))]
+    pub memory_dir: Option<PathBuf>,
 }
 
 impl Args {
@@ -14,6 +14,9 @@ pub enum Error {
     #[error("Execution error: {0}")]
     Execution(#[from] execution::Error),
 
+    #[error("Memory error: {0}")]
+    Memory(#[from] memory::Error),
+
     #[error("Structure error: {0}")]
     Structure(#[from] experiment_structure::Error),
 
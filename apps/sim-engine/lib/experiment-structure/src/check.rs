@@ -0,0 +1,477 @@
+//! Validation of a project without running it.
+//!
+//! Most problems in a project, e.g. invalid behavior keys or an invalid experiment definition, are
+//! only detected when an experiment is started. [`check_project`] loads the [`Manifest`] of a
+//! project and runs the same validations up front, reporting all problems at once.
+
+mod location;
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Context, Report};
+use execution::{
+    package::simulation::{
+        output::analysis::validate_analysis_source,
+        state::behavior_execution::{original_position, Behavior, BehaviorKeys},
+    },
+    runner::{compile_javascript_behavior, Language},
+};
+use json_comments::StripComments;
+use serde_json::{json, Value};
+use stateful::field::{FieldSource, RootFieldSpecCreator};
+
+use crate::{
+    experiment::validate_experiments,
+    manifest::{is_csv_dataset_options, local_dependencies_folders, Manifest},
+};
+
+/// A problem found in a project by [`check_project`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The file containing the problem.
+    pub path: PathBuf,
+    /// The 1-based line of the problem in the file, if known.
+    pub line: Option<usize>,
+    /// The 1-based column of the problem in the file, if known.
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    fn from_report<C: Context>(path: impl Into<PathBuf>, report: &Report<C>) -> Self {
+        // The alternate format includes the attached messages, which describe the actual problem
+        Self::new(path, format!("{report:#}"))
+    }
+
+    /// Sets the location of the diagnostic to the member at `path` in the JSON `source` of the file.
+    ///
+    /// If the member doesn't exist, e.g. because it's missing, the root value is used instead.
+    fn at_member(mut self, source: &str, path: &[&str]) -> Self {
+        let location = location::locate(source, path).or_else(|| location::locate(source, &[]));
+        if let Some((line, column)) = location {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+
+    fn from_json_error(path: impl Into<PathBuf>, error: &serde_json::Error) -> Self {
+        Self {
+            path: path.into(),
+            line: Some(error.line()),
+            column: Some(error.column()),
+            message: format!("Invalid JSON: {error}"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(fmt, ":{line}")?;
+            if let Some(column) = self.column {
+                write!(fmt, ":{column}")?;
+            }
+        }
+        write!(fmt, ": {}", self.message)
+    }
+}
+
+/// Checks the project at `project_path` without running it and returns all problems found.
+///
+/// The project is read like [`Manifest::from_local`] does, but reading continues after a file
+/// could not be read. Additionally,
+/// - _src/globals.json_, _dependencies.json_, and the behavior keys are parsed as JSON,
+/// - _views/analysis.json_ is validated like the analysis output package does,
+/// - every experiment in _experiments.json_ is planned, and
+/// - JavaScript and TypeScript behaviors are compiled in V8 without running them.
+///
+/// Problems in JSON files are reported with the line and column of the syntax error, or of the
+/// member causing the problem, e.g. an invalid behavior key, analysis output, or experiment.
+///
+/// Dependencies are only checked as far as they are needed to resolve the behaviors and datasets
+/// of the project.
+pub fn check_project<P: AsRef<Path>>(project_path: P) -> Vec<Diagnostic> {
+    let project_path = project_path.as_ref();
+    let src_folder = project_path.join("src");
+    let behaviors_folder = src_folder.join("behaviors");
+    let globals_json = src_folder.join("globals.json");
+    let views_folder = project_path.join("views");
+    let analysis_json = views_folder.join("analysis.json");
+    let experiments_json = project_path.join("experiments.json");
+    let dependencies_json = project_path.join("dependencies.json");
+    let data_folder = project_path.join("data");
+
+    let mut diagnostics = Vec::new();
+    let mut manifest = Manifest::new();
+
+    if let Err(report) = manifest.set_initial_state_from_directory(&src_folder) {
+        diagnostics.push(Diagnostic::from_report(&src_folder, &report));
+    }
+
+    if let Some(globals) = read_file(&globals_json, &mut diagnostics) {
+        if is_valid_json(&globals_json, &globals, false, &mut diagnostics) {
+            manifest.globals_json = Some(globals);
+        }
+    }
+
+    if let Some(analysis) = read_file(&analysis_json, &mut diagnostics) {
+        // An empty analysis definition is allowed
+        if analysis.trim().is_empty()
+            || is_valid_json(&analysis_json, &analysis, true, &mut diagnostics)
+        {
+            check_analysis(&analysis_json, &analysis, &mut diagnostics);
+            manifest.analysis_json = Some(analysis);
+        }
+    }
+
+    if let Some(experiments) = read_file(&experiments_json, &mut diagnostics) {
+        if is_valid_json(&experiments_json, &experiments, true, &mut diagnostics) {
+            for (name, report) in validate_experiments(&experiments) {
+                let mut diagnostic = Diagnostic::from_report(&experiments_json, &report);
+                if let Some(name) = name {
+                    diagnostic.message = format!("Experiment \"{name}\": {}", diagnostic.message);
+                    diagnostic = diagnostic.at_member(&experiments, &[&name]);
+                } else {
+                    // Errors of no specific experiment are caused by the top-level packages
+                    diagnostic = diagnostic.at_member(&experiments, &["packages"]);
+                }
+                diagnostics.push(diagnostic);
+            }
+            manifest.experiments_json = Some(experiments);
+        }
+    }
+
+    if let Some(dependencies) = read_file(&dependencies_json, &mut diagnostics) {
+        match serde_json::from_str(&dependencies) {
+            Ok(dependencies) => manifest.dependencies = dependencies,
+            Err(err) => diagnostics.push(Diagnostic::from_json_error(&dependencies_json, &err)),
+        }
+    }
+
+    if behaviors_folder.is_dir() {
+        for path in sorted_entries(&behaviors_folder, &mut diagnostics) {
+            let is_declaration_file = path
+                .file_name()
+                .map_or(false, |name| name.to_string_lossy().ends_with(".d.ts"));
+            if path
+                .extension()
+                .map_or(false, |extension| extension == "json")
+                || is_declaration_file
+            {
+                continue;
+            }
+            if let Err(report) = manifest.add_behavior_from_file(&path) {
+                diagnostics.push(Diagnostic::from_report(&path, &report));
+                continue;
+            }
+            if let Some(behavior) = manifest.behaviors.last() {
+                check_behavior(&path, behavior, &mut diagnostics);
+            }
+        }
+    }
+
+    if data_folder.is_dir() {
+        for path in sorted_entries(&data_folder, &mut diagnostics) {
+            if is_csv_dataset_options(&path) {
+                continue;
+            }
+            if let Err(report) = manifest.add_dataset_from_file(&path) {
+                diagnostics.push(Diagnostic::from_report(&path, &report));
+            }
+        }
+    }
+
+    let mut dependency_projects = HashMap::new();
+    for path in local_dependencies_folders(project_path.join("dependencies")) {
+        match Manifest::from_dependency(&path) {
+            Ok(dependency) => {
+                dependency_projects.insert(path, dependency);
+            }
+            Err(report) => diagnostics.push(Diagnostic::from_report(&path, &report)),
+        }
+    }
+    if let Err(report) = manifest.add_dependency_projects(dependency_projects) {
+        diagnostics.push(Diagnostic::from_report(&dependencies_json, &report));
+    }
+
+    diagnostics
+}
+
+/// Reads the file at `path`, or returns `None` if it doesn't exist or can't be read.
+fn read_file(path: &Path, diagnostics: &mut Vec<Diagnostic>) -> Option<String> {
+    if !path.exists() {
+        return None;
+    }
+    match fs::read_to_string(path) {
+        Ok(source) => Some(source),
+        Err(err) => {
+            diagnostics.push(Diagnostic::new(path, format!("Could not read file: {err}")));
+            None
+        }
+    }
+}
+
+/// Returns if `source` read from `path` is valid JSON and reports the syntax error otherwise.
+///
+/// Comments are only allowed if `allow_comments` is set. They are replaced by whitespace, so the
+/// line and column of a syntax error are preserved.
+fn is_valid_json(
+    path: &Path,
+    source: &str,
+    allow_comments: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> bool {
+    let parsed = if allow_comments {
+        serde_json::from_reader::<_, serde_json::Value>(StripComments::new(source.as_bytes()))
+    } else {
+        serde_json::from_str::<serde_json::Value>(source)
+    };
+    match parsed {
+        Ok(_) => true,
+        Err(err) => {
+            diagnostics.push(Diagnostic::from_json_error(path, &err));
+            false
+        }
+    }
+}
+
+/// Checks the keys of a behavior read from `path` and compiles it, if it's a JavaScript behavior.
+fn check_behavior(path: &Path, behavior: &Behavior, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(keys) = &behavior.behavior_keys_src {
+        let mut keys_path = path.as_os_str().to_os_string();
+        keys_path.push(".json");
+        let keys_path = PathBuf::from(keys_path);
+
+        if is_valid_json(&keys_path, keys, false, diagnostics) {
+            let field_spec_creator = RootFieldSpecCreator::new(FieldSource::Engine);
+            if let Err(err) = BehaviorKeys::from_json_str(keys, &field_spec_creator) {
+                let member = invalid_behavior_keys_member(keys, &field_spec_creator);
+                let member = member.iter().map(String::as_str).collect::<Vec<_>>();
+                diagnostics.push(
+                    Diagnostic::new(keys_path, format!("Invalid behavior keys: {err}"))
+                        .at_member(keys, &member),
+                );
+            }
+        }
+    }
+
+    if let (Ok(Language::JavaScript), Some(source)) = (behavior.language(), &behavior.behavior_src)
+    {
+        if let Err(err) = compile_javascript_behavior(source) {
+            // Positions in transpiled TypeScript behaviors are mapped back to the TypeScript source
+            let position = err.line.zip(err.column).map(|(line, column)| {
+                behavior
+                    .source_map
+                    .as_deref()
+                    .and_then(|source_map| original_position(source_map, line, column))
+                    .unwrap_or((line, column))
+            });
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(),
+                line: position.map(|(line, _)| line),
+                column: position.map(|(_, column)| column),
+                message: err.message,
+            });
+        }
+    }
+}
+
+/// Returns the path to the member of the behavior keys `source`, which makes them invalid.
+///
+/// Every key and the other top-level members are validated on their own, so the first invalid one
+/// is found. If all of them are valid on their own, an empty path, i.e. the root value, is
+/// returned.
+fn invalid_behavior_keys_member(
+    source: &str,
+    field_spec_creator: &RootFieldSpecCreator,
+) -> Vec<String> {
+    let is_valid =
+        |keys: Value| BehaviorKeys::from_json_str(keys.to_string(), field_spec_creator).is_ok();
+
+    let members = match serde_json::from_str::<Value>(source) {
+        Ok(Value::Object(members)) => members,
+        _ => return Vec::new(),
+    };
+    match members.get("keys") {
+        Some(Value::Object(keys)) => {
+            for (key, field) in keys {
+                if !is_valid(json!({ "keys": { key: field } })) {
+                    return vec!["keys".to_string(), key.clone()];
+                }
+            }
+        }
+        Some(_) => return vec!["keys".to_string()],
+        None => return Vec::new(),
+    }
+    for (name, member) in members.iter().filter(|(name, _)| *name != "keys") {
+        if !is_valid(json!({ "keys": {}, name: member })) {
+            return vec![name.clone()];
+        }
+    }
+    Vec::new()
+}
+
+/// Validates the analysis definition `source` read from `path`.
+///
+/// Every output is validated on its own, so each invalid output is reported at its name. Problems
+/// not caused by a single output are reported at the root value.
+fn check_analysis(path: &Path, source: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let err = match validate_analysis_source(source) {
+        Ok(()) => return,
+        Err(err) => err,
+    };
+
+    let outputs = serde_json::from_reader::<_, Value>(StripComments::new(source.as_bytes()))
+        .ok()
+        .and_then(|analysis| match analysis.get("outputs") {
+            Some(Value::Object(outputs)) => Some(outputs.clone()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let mut found_invalid_output = false;
+    for (name, operations) in &outputs {
+        let output = json!({ "outputs": { name: operations } }).to_string();
+        if let Err(err) = validate_analysis_source(&output) {
+            diagnostics
+                .push(Diagnostic::new(path, err.to_string()).at_member(source, &["outputs", name]));
+            found_invalid_output = true;
+        }
+    }
+    if !found_invalid_output {
+        diagnostics.push(Diagnostic::new(path, err.to_string()).at_member(source, &[]));
+    }
+}
+
+/// Returns the entries of `directory` sorted by their path, so diagnostics are reported in a
+/// stable order.
+fn sorted_entries(directory: &Path, diagnostics: &mut Vec<Diagnostic>) -> Vec<PathBuf> {
+    match directory.read_dir() {
+        Ok(entries) => {
+            let mut paths = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        }
+        Err(err) => {
+            diagnostics.push(Diagnostic::new(
+                directory,
+                format!("Could not read directory: {err}"),
+            ));
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the project `name` in _tests/fixtures/check_ and returns the diagnostics with their
+    /// paths relative to the project.
+    fn check_fixture(name: &str) -> Vec<Diagnostic> {
+        let project_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("check")
+            .join(name);
+        check_project(&project_path)
+            .into_iter()
+            .map(|diagnostic| Diagnostic {
+                path: diagnostic
+                    .path
+                    .strip_prefix(&project_path)
+                    .unwrap()
+                    .to_path_buf(),
+                ..diagnostic
+            })
+            .collect()
+    }
+
+    fn single_diagnostic(name: &str) -> Diagnostic {
+        let mut diagnostics = check_fixture(name);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:#?}");
+        diagnostics.remove(0)
+    }
+
+    #[test]
+    fn valid_project() {
+        assert_eq!(check_fixture("valid"), Vec::new());
+    }
+
+    #[test]
+    fn invalid_json() {
+        let diagnostic = single_diagnostic("invalid_json");
+        assert_eq!(diagnostic.path, Path::new("src/globals.json"));
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(3), Some(1)));
+        assert!(
+            diagnostic.message.starts_with("Invalid JSON"),
+            "{}",
+            diagnostic.message
+        );
+    }
+
+    #[test]
+    fn invalid_behavior_keys() {
+        let diagnostic = single_diagnostic("behavior_keys");
+        assert_eq!(diagnostic.path, Path::new("src/behaviors/move.py.json"));
+        // The location of `"heading"`, which is missing `nullable`
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(7), Some(5)));
+        assert!(
+            diagnostic.message.starts_with("Invalid behavior keys"),
+            "{}",
+            diagnostic.message
+        );
+    }
+
+    #[test]
+    fn invalid_analysis_output() {
+        let diagnostic = single_diagnostic("analysis");
+        assert_eq!(diagnostic.path, Path::new("views/analysis.json"));
+        // The location of `"empty"`, which has no operations
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(7), Some(5)));
+        assert!(
+            diagnostic.message.contains("'empty'"),
+            "{}",
+            diagnostic.message
+        );
+        assert!(
+            !diagnostic.message.contains("'red'"),
+            "{}",
+            diagnostic.message
+        );
+    }
+
+    #[test]
+    fn invalid_experiment() {
+        let diagnostic = single_diagnostic("experiments");
+        assert_eq!(diagnostic.path, Path::new("experiments.json"));
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(8), Some(3)));
+        assert!(
+            diagnostic.message.starts_with("Experiment \"broken\""),
+            "{}",
+            diagnostic.message
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            format!("experiments.json:8:3: {}", diagnostic.message)
+        );
+    }
+}
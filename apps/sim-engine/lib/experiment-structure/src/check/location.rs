@@ -0,0 +1,161 @@
+//! Locates members in JSON sources, so semantic problems can be reported with a line and column.
+//!
+//! [`serde_json`] only reports positions for syntax errors, the parsed values don't keep their
+//! spans. The sources checked here were already parsed successfully, so a small scanner which skips
+//! over values without validating them is sufficient to find the position of a member.
+
+use std::io::Read;
+
+use json_comments::StripComments;
+
+/// Returns the 1-based line and column of the member at `path` in the JSON `source`.
+///
+/// Every segment of `path` is the key of an object member, the returned position is the opening
+/// quote of the last key. If `path` is empty, the position of the root value is returned. Comments
+/// are ignored. Returns `None` if the member doesn't exist.
+pub(super) fn locate(source: &str, path: &[&str]) -> Option<(usize, usize)> {
+    // Comments are replaced by whitespace, so the offsets are the same as in `source`
+    let mut stripped = String::with_capacity(source.len());
+    StripComments::new(source.as_bytes())
+        .read_to_string(&mut stripped)
+        .ok()?;
+
+    let bytes = stripped.as_bytes();
+    let mut value = skip_whitespace(bytes, 0);
+    if value >= bytes.len() {
+        return None;
+    }
+    let mut location = value;
+    for key in path {
+        let (key, member_value) = find_member(bytes, value, key)?;
+        location = key;
+        value = member_value;
+    }
+    Some(line_and_column(&stripped, location))
+}
+
+/// Returns the offsets of the key and the value of the member `key` in the object starting at
+/// `position`.
+fn find_member(bytes: &[u8], position: usize, key: &str) -> Option<(usize, usize)> {
+    if bytes.get(position) != Some(&b'{') {
+        return None;
+    }
+    let mut position = skip_whitespace(bytes, position + 1);
+    while bytes.get(position) == Some(&b'"') {
+        let key_start = position;
+        let key_end = skip_string(bytes, key_start)?;
+        let member_key: String = serde_json::from_slice(&bytes[key_start..key_end]).ok()?;
+
+        position = skip_whitespace(bytes, key_end);
+        if bytes.get(position) != Some(&b':') {
+            return None;
+        }
+        position = skip_whitespace(bytes, position + 1);
+        if member_key == key {
+            return Some((key_start, position));
+        }
+
+        position = skip_whitespace(bytes, skip_value(bytes, position)?);
+        if bytes.get(position) != Some(&b',') {
+            return None;
+        }
+        position = skip_whitespace(bytes, position + 1);
+    }
+    None
+}
+
+/// Returns the offset after the value starting at `position`.
+fn skip_value(bytes: &[u8], position: usize) -> Option<usize> {
+    match bytes.get(position)? {
+        b'"' => skip_string(bytes, position),
+        b'{' | b'[' => {
+            // Strings are skipped as a whole, so brackets inside of them are not counted
+            let mut depth = 0_usize;
+            let mut position = position;
+            loop {
+                match bytes.get(position)? {
+                    b'"' => {
+                        position = skip_string(bytes, position)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(position + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                position += 1;
+            }
+        }
+        // Numbers, `true`, `false`, and `null`
+        _ => Some(
+            bytes[position..]
+                .iter()
+                .position(|byte| matches!(byte, b',' | b'}' | b']') || byte.is_ascii_whitespace())
+                .map_or(bytes.len(), |length| position + length),
+        ),
+    }
+}
+
+/// Returns the offset after the string starting with the quote at `position`.
+fn skip_string(bytes: &[u8], position: usize) -> Option<usize> {
+    let mut position = position + 1;
+    loop {
+        match bytes.get(position)? {
+            b'\\' => position += 2,
+            b'"' => return Some(position + 1),
+            _ => position += 1,
+        }
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], position: usize) -> usize {
+    bytes[position.min(bytes.len())..]
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .map_or(bytes.len(), |length| position + length)
+}
+
+/// Converts the byte `offset` into a 1-based line and column, where the column counts characters.
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"// comment with a "key": {
+{
+  "a": [1, { "b": "}" }, "\"c\""],
+  "c": {
+    /* "d": 1 */
+    "e f": null,
+    "d": { "g": 2 }
+  }
+}"#;
+
+    #[test]
+    fn locates_members() {
+        assert_eq!(locate(SOURCE, &[]), Some((2, 1)));
+        assert_eq!(locate(SOURCE, &["a"]), Some((3, 3)));
+        assert_eq!(locate(SOURCE, &["c"]), Some((4, 3)));
+        assert_eq!(locate(SOURCE, &["c", "e f"]), Some((6, 5)));
+        assert_eq!(locate(SOURCE, &["c", "d"]), Some((7, 5)));
+        assert_eq!(locate(SOURCE, &["c", "d", "g"]), Some((7, 12)));
+    }
+
+    #[test]
+    fn missing_members() {
+        assert_eq!(locate(SOURCE, &["b"]), None);
+        assert_eq!(locate(SOURCE, &["a", "b"]), None);
+        assert_eq!(locate(SOURCE, &["c", "d", "g", "h"]), None);
+        assert_eq!(locate("  ", &[]), None);
+    }
+}
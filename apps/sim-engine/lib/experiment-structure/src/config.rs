@@ -4,5 +4,5 @@ mod package;
 
 pub use self::{
     experiment::ExperimentConfig,
-    package::{PackageConfig, PackageConfigBuilder},
+    package::{PackageConfig, PackageConfigBuilder, PackageSelection},
 };
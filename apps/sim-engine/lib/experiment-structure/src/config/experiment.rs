@@ -7,7 +7,7 @@ use execution::{
     worker::WorkerConfig,
     worker_pool::WorkerPoolConfig,
 };
-use stateful::global::Globals;
+use stateful::global::{Dataset, Globals};
 
 use crate::{
     config::error::{ConfigError, Result},
@@ -23,6 +23,8 @@ pub struct ExperimentConfig {
     /// The size at which the engine aims to split a group of agents
     pub target_max_group_size: usize,
     pub base_globals: Globals,
+    /// The datasets of the experiment run, shared with the packages of every simulation run
+    pub datasets: Arc<[Dataset]>,
 }
 
 impl ExperimentConfig {
@@ -38,8 +40,13 @@ impl ExperimentConfig {
             .add_init_package(match simulation.package_init.initial_state.name {
                 InitialStateName::InitJson => InitPackageName::Json,
                 InitialStateName::InitPy | InitialStateName::InitJs => InitPackageName::JsPy,
+                InitialStateName::InitCsv
+                | InitialStateName::InitParquet
+                | InitialStateName::InitArrow => InitPackageName::Columnar,
             })
+            .set_selection(experiment_run.packages())
             .build()?;
+        package_config.warn_about_missing_packages(&simulation.package_init.behaviors);
         let base_globals: Globals = serde_json::from_str(&simulation.globals_src)
             .into_report()
             .attach_printable("Could not parse globals JSON")
@@ -57,6 +64,7 @@ impl ExperimentConfig {
             base_globals,
             target_max_group_size,
             worker_pool,
+            datasets: simulation.datasets.as_slice().into(),
         })
     }
 }
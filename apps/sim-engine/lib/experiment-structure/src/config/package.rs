@@ -1,11 +1,37 @@
 use error_stack::{ensure, IntoReport, Report, ResultExt};
 use execution::package::simulation::{
-    context::ContextPackageName, init::InitPackageName, output::OutputPackageName,
-    state::StatePackageName, PackageName,
+    context::ContextPackageName,
+    init::InitPackageName,
+    output::OutputPackageName,
+    state::{behavior_execution::Behavior, StatePackageName},
+    Dependencies, PackageName,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::config::error::{ConfigError, Result};
 
+/// The packages a project chooses to run, read from the `"packages"` section of
+/// _experiments.json_.
+///
+/// A list replaces the default packages of its type, and `disabled` packages are removed from the
+/// defaults. Init packages are determined by the initial state and can't be chosen.
+///
+/// ```json
+/// { "packages": { "output": ["json_state"], "disabled": ["neighbors"] } }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackageSelection {
+    #[serde(default)]
+    pub context: Option<Vec<ContextPackageName>>,
+    #[serde(default)]
+    pub state: Option<Vec<StatePackageName>>,
+    #[serde(default)]
+    pub output: Option<Vec<OutputPackageName>>,
+    #[serde(default)]
+    pub disabled: Vec<PackageName>,
+}
+
 /// Configuration of packages used in the engine.
 ///
 /// Contains the names of all packages used. If a name of a package is included, then the respective
@@ -59,6 +85,77 @@ impl PackageConfig {
     pub fn output_packages(&self) -> &Vec<OutputPackageName> {
         &self.output
     }
+
+    /// Warns about `behaviors`, which appear to use a package, which is not enabled.
+    ///
+    /// The behavior sources are only searched for calls of the package API, so this may miss
+    /// usages, e.g. in binary WebAssembly modules, or report usages in comments.
+    pub fn warn_about_missing_packages(&self, behaviors: &[Behavior]) {
+        if !behaviors.is_empty() && !self.state.contains(&StatePackageName::BehaviorExecution) {
+            tracing::warn!(
+                "The project has behaviors, but the package {} is disabled, so they will not be \
+                 executed",
+                StatePackageName::BehaviorExecution
+            );
+            return;
+        }
+
+        for behavior in behaviors {
+            for package in self.disabled_packages_used_by(behavior) {
+                tracing::warn!(
+                    "Behavior {} appears to use the package {package}, which is disabled",
+                    behavior.name
+                );
+            }
+        }
+    }
+
+    /// Returns the context packages, which are not enabled, but whose API is called by `behavior`.
+    fn disabled_packages_used_by(&self, behavior: &Behavior) -> Vec<ContextPackageName> {
+        // JavaScript and Python behaviors call the getter on their context, WebAssembly behaviors
+        // import the host function
+        let apis = [
+            (
+                ContextPackageName::Neighbors,
+                "neighbors",
+                "context_neighbors",
+            ),
+            (
+                ContextPackageName::AgentMessages,
+                "messages",
+                "context_messages",
+            ),
+        ];
+        let source = match &behavior.behavior_src {
+            Some(source) => source,
+            None => return Vec::new(),
+        };
+        apis.into_iter()
+            .filter(|(package, getter, host_function)| {
+                !self.context.contains(package) && calls_api(source, getter, host_function)
+            })
+            .map(|(package, ..)| package)
+            .collect()
+    }
+}
+
+/// Returns whether `source` calls `context.<getter>()` or refers to the WebAssembly host function
+/// `host_function`.
+///
+/// Other identifiers containing these names, e.g. `neighbors_count` or `agent_context.neighbors`,
+/// don't count as calls.
+fn calls_api(source: &str, getter: &str, host_function: &str) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    let call = format!("context.{getter}");
+    let calls_getter = source.match_indices(&call).any(|(index, _)| {
+        !source[..index].ends_with(is_identifier)
+            && source[index + call.len()..].trim_start().starts_with('(')
+    });
+    calls_getter
+        || source.match_indices(host_function).any(|(index, _)| {
+            !source[..index].ends_with(is_identifier)
+                && !source[index + host_function.len()..].starts_with(is_identifier)
+        })
 }
 
 impl Default for PackageConfig {
@@ -78,6 +175,7 @@ pub struct PackageConfigBuilder {
     context: Option<Vec<ContextPackageName>>,
     state: Option<Vec<StatePackageName>>,
     output: Option<Vec<OutputPackageName>>,
+    disabled: Vec<PackageName>,
 }
 
 impl PackageConfigBuilder {
@@ -117,6 +215,31 @@ impl PackageConfigBuilder {
         self
     }
 
+    /// Removes `package` from the packages, even if it's a default package.
+    ///
+    /// Building the config fails if another package depends on a disabled package.
+    pub fn disable_package(mut self, package: PackageName) -> PackageConfigBuilder {
+        self.disabled.push(package);
+        self
+    }
+
+    /// Applies the packages chosen by a project.
+    pub fn set_selection(mut self, selection: &PackageSelection) -> PackageConfigBuilder {
+        if let Some(context) = &selection.context {
+            self = self.set_context_packages(context);
+        }
+        if let Some(state) = &selection.state {
+            self = self.set_state_packages(state);
+        }
+        if let Some(output) = &selection.output {
+            self = self.set_output_packages(output);
+        }
+        for package in &selection.disabled {
+            self = self.disable_package(*package);
+        }
+        self
+    }
+
     pub fn add_init_package(mut self, init_package: InitPackageName) -> PackageConfigBuilder {
         match self.init {
             Some(ref mut pkgs) => {
@@ -169,21 +292,43 @@ impl PackageConfigBuilder {
     }
 
     pub fn build(self) -> Result<PackageConfig> {
+        self.build_with(PackageName::get_all_dependencies)
+    }
+
+    /// Builds the config with `dependencies_of` returning all dependencies of a package.
+    fn build_with(
+        self,
+        dependencies_of: impl Fn(&PackageName) -> execution::Result<Dependencies>,
+    ) -> Result<PackageConfig> {
+        let disabled = self.disabled;
+        let is_enabled = |name: PackageName| !disabled.contains(&name);
+
         let mut init = self
             .init
             .unwrap_or_else(PackageConfig::default_init_packages);
+        if let Some(name) = init
+            .iter()
+            .find(|name| !is_enabled(PackageName::Init(**name)))
+        {
+            return Err(Report::new(ConfigError).attach_printable(format!(
+                "The init package {name} can't be disabled, it's required by the initial state"
+            )));
+        }
 
         let mut context = self
             .context
             .unwrap_or_else(PackageConfig::default_context_packages);
+        context.retain(|name| is_enabled(PackageName::Context(*name)));
 
-        let state = self
+        let mut state = self
             .state
             .unwrap_or_else(PackageConfig::default_state_packages);
+        state.retain(|name| is_enabled(PackageName::State(*name)));
 
         let mut output = self
             .output
             .unwrap_or_else(PackageConfig::default_output_packages);
+        output.retain(|name| is_enabled(PackageName::Output(*name)));
 
         let init_as_deps = init
             .iter()
@@ -209,18 +354,28 @@ impl PackageConfigBuilder {
             .chain(state_as_deps.iter())
             .chain(output_as_deps.iter())
         {
-            let deps = dependency
-                .get_all_dependencies()
+            let deps = dependencies_of(dependency)
                 .into_report()
                 .attach_printable_lazy(|| format!("Could not get dependencies for {dependency}"))
                 .change_context(ConfigError)?;
             for dep in deps.into_iter_deps() {
+                ensure!(
+                    is_enabled(dep),
+                    Report::new(ConfigError).attach_printable(format!(
+                        "The package {dep} is disabled, but it's a dependency of the {dependency} \
+                         package"
+                    ))
+                );
                 match dep {
                     PackageName::Context(dep_name) => {
-                        context.push(dep_name);
+                        if !context.contains(&dep_name) {
+                            context.push(dep_name);
+                        }
                     }
                     PackageName::Init(dep_name) => {
-                        init.push(dep_name);
+                        if !init.contains(&dep_name) {
+                            init.push(dep_name);
+                        }
                     }
                     PackageName::State(dep_name) => {
                         ensure!(
@@ -234,7 +389,9 @@ impl PackageConfigBuilder {
                         )
                     }
                     PackageName::Output(dep_name) => {
-                        output.push(dep_name);
+                        if !output.contains(&dep_name) {
+                            output.push(dep_name);
+                        }
                     }
                 }
             }
@@ -250,3 +407,143 @@ impl PackageConfigBuilder {
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes the analysis output depend on the neighbors package.
+    fn analysis_needs_neighbors(name: &PackageName) -> execution::Result<Dependencies> {
+        let mut dependencies = Dependencies::new();
+        if *name == PackageName::Output(OutputPackageName::Analysis) {
+            dependencies.add_context_dep(ContextPackageName::Neighbors)?;
+        }
+        Ok(dependencies)
+    }
+
+    fn error(result: Result<PackageConfig>) -> String {
+        format!(
+            "{:?}",
+            result.err().expect("building the config should fail")
+        )
+    }
+
+    #[test]
+    fn disabled_packages_are_removed() {
+        let config = PackageConfigBuilder::new()
+            .disable_package(PackageName::Context(ContextPackageName::Neighbors))
+            .disable_package(PackageName::Output(OutputPackageName::Analysis))
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.context,
+            [
+                ContextPackageName::ApiRequests,
+                ContextPackageName::AgentMessages
+            ]
+        );
+        assert_eq!(config.output, [OutputPackageName::JsonState]);
+    }
+
+    #[test]
+    fn dependencies_are_added() {
+        let config = PackageConfigBuilder::new()
+            .set_context_packages(&[])
+            .build_with(analysis_needs_neighbors)
+            .unwrap();
+        assert_eq!(config.context, [ContextPackageName::Neighbors]);
+    }
+
+    #[test]
+    fn dependencies_can_not_be_disabled() {
+        let error = error(
+            PackageConfigBuilder::new()
+                .disable_package(PackageName::Context(ContextPackageName::Neighbors))
+                .build_with(analysis_needs_neighbors),
+        );
+        assert!(
+            error.contains(
+                "The package neighbors is disabled, but it's a dependency of the analysis package"
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn init_package_can_not_be_disabled() {
+        let error = error(
+            PackageConfigBuilder::new()
+                .disable_package(PackageName::Init(InitPackageName::Json))
+                .build(),
+        );
+        assert!(
+            error.contains("The init package json can't be disabled"),
+            "{error}"
+        );
+    }
+
+    fn behavior(source: &str) -> Behavior {
+        Behavior {
+            id: "behavior.js".to_string(),
+            name: "behavior.js".to_string(),
+            shortnames: Vec::new(),
+            behavior_src: Some(source.to_string()),
+            source_map: None,
+            behavior_keys_src: None,
+        }
+    }
+
+    fn without_context_packages() -> PackageConfig {
+        PackageConfigBuilder::new()
+            .set_context_packages(&[])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_calls_of_disabled_packages() {
+        let config = without_context_packages();
+        assert_eq!(
+            config.disabled_packages_used_by(&behavior(
+                "const behavior = (state, context) => { context.neighbors().length; };"
+            )),
+            [ContextPackageName::Neighbors]
+        );
+        assert_eq!(
+            config.disabled_packages_used_by(&behavior(
+                "def behavior(state, context):\n    for m in context.messages ():\n        pass"
+            )),
+            [ContextPackageName::AgentMessages]
+        );
+        assert_eq!(
+            config.disabled_packages_used_by(&behavior(
+                "(import \"env\" \"context_neighbors\" (func $neighbors (result i32)))"
+            )),
+            [ContextPackageName::Neighbors]
+        );
+    }
+
+    #[test]
+    fn ignores_names_which_are_not_calls() {
+        let config = without_context_packages();
+        for source in [
+            "state.neighbors_count = 3; state.messages.push(message);",
+            "const neighbors = agent_context.neighbors(); const messages = [];",
+            "state.context_neighbors_seen = context.neighbors_seen;",
+        ] {
+            assert!(
+                config
+                    .disabled_packages_used_by(&behavior(source))
+                    .is_empty(),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn enabled_packages_are_not_reported() {
+        let config = PackageConfig::default();
+        let behavior = behavior("context.neighbors(); context.messages();");
+        assert!(config.disabled_packages_used_by(&behavior).is_empty());
+    }
+}
@@ -29,7 +29,7 @@ impl FetchDependencies for Dataset {
             .as_ref()
             .ok_or_else(|| Report::new(DependencyError))
             .attach_printable("Either data or a dataset URL is required")?;
-        let mut contents = surf::get(url)
+        let contents = surf::get(url)
             .recv_string()
             .await
             .map_err(|error| Report::new(DependencyError).attach_printable(error))
@@ -48,11 +48,6 @@ impl FetchDependencies for Dataset {
             );
         }
 
-        if self.raw_csv {
-            contents = parse_raw_csv_into_json(contents)
-                .attach_printable("Could not parser CSV as JSON")
-                .change_context(DependencyError)?;
-        }
         self.data = Some(contents);
         Ok(())
     }
@@ -80,36 +75,3 @@ impl FetchDependencies for ExperimentRun {
         Ok(())
     }
 }
-
-pub fn parse_raw_csv_into_json(contents: String) -> Result<String, csv::Error> {
-    let mut reader = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .from_reader(contents.as_bytes());
-
-    let mut result = String::from("[");
-    let mut is_first_row = true;
-    for record in reader.records() {
-        if is_first_row {
-            result.push('[');
-            is_first_row = false;
-        } else {
-            result.push_str(",[");
-        }
-
-        let mut is_first_element = true;
-        for elem in record?.iter() {
-            if !is_first_element {
-                result.push(',');
-            } else {
-                is_first_element = false;
-            }
-            result.push('"');
-            result.push_str(elem);
-            result.push('"');
-        }
-        result.push(']');
-    }
-
-    result.push(']');
-    Ok(result)
-}
@@ -2,4 +2,5 @@ mod experiment_type;
 mod plan;
 mod run;
 
+pub(crate) use self::plan::validate_experiments;
 pub use self::{experiment_type::ExperimentType, run::ExperimentRun};
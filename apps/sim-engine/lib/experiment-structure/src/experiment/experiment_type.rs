@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use execution::package::experiment::ExperimentName;
 
 /// Specific configuration needed for either Experiments or single runs of Simulations.
@@ -16,4 +18,14 @@ pub enum ExperimentType {
         #[cfg_attr(feature = "clap", clap(short, long))]
         name: ExperimentName,
     },
+    /// Resume a single simulation from a checkpoint written by a previous run.
+    Resume {
+        /// Path to the checkpoint folder, i.e. the `step-<step>` folder containing
+        /// _checkpoint.json_
+        #[cfg_attr(feature = "clap", clap(short, long))]
+        checkpoint: PathBuf,
+        /// Number of steps to run after the step of the checkpoint
+        #[cfg_attr(feature = "clap", clap(short, long))]
+        num_steps: usize,
+    },
 }
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
 use error_stack::{bail, IntoReport, Report, ResultExt};
-use execution::package::experiment::{
-    basic::{BasicExperimentConfig, SimpleExperimentConfig, SingleRunExperimentConfig},
-    ExperimentName, ExperimentPackageConfig,
+use execution::package::{
+    experiment::{
+        basic::{BasicExperimentConfig, SimpleExperimentConfig, SingleRunExperimentConfig},
+        extended::{ExtendedExperimentConfig, OptimizationExperimentConfig},
+        ExperimentName, ExperimentPackageConfig,
+    },
+    simulation::Seed,
 };
 use json_comments::StripComments;
 use rand::{distributions::Distribution, Rng, RngCore};
@@ -12,7 +16,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error;
 
-use crate::{experiment::ExperimentType, SimulationSource};
+use crate::{experiment::ExperimentType, PackageSelection, SimulationSource};
 
 #[derive(Debug, Error)]
 #[error("Could not read experiment plan")]
@@ -24,40 +28,151 @@ impl ExperimentType {
     /// Creates an experiment config from `ExperimentType`.
     ///
     /// If the type is a simple Experiment [`Simple`](Self::Simple), it uses a `base` to load the
-    /// experiment config for the given `name`.
+    /// experiment config for the given `name`. Optimization experiments are turned into an
+    /// extended experiment config, every other experiment into a simple experiment config. A
+    /// [`Resume`](Self::Resume) runs a single simulation like [`SingleRun`](Self::SingleRun).
+    ///
+    /// If a `seed` is provided, sampled experiment plans, e.g. monte-carlo experiments, and the
+    /// globals proposed by optimization experiments are sampled with it.
     pub fn get_package_config(
         self,
         simulation: &SimulationSource,
+        seed: Option<Seed>,
     ) -> Result<ExperimentPackageConfig> {
-        let basic = match self {
-            ExperimentType::SingleRun { num_steps } => {
-                BasicExperimentConfig::SingleRun(SingleRunExperimentConfig { num_steps })
+        match self {
+            ExperimentType::SingleRun { num_steps } | ExperimentType::Resume { num_steps, .. } => {
+                Ok(ExperimentPackageConfig::Basic(
+                    BasicExperimentConfig::SingleRun(SingleRunExperimentConfig { num_steps }),
+                ))
+            }
+            ExperimentType::Simple { name } => {
+                get_experiment_config(simulation.experiments_src.as_deref(), name, seed)
+                    .attach_printable("Could not read experiment config")
             }
-            ExperimentType::Simple { name } => BasicExperimentConfig::Simple(
-                get_simple_experiment_config(simulation, name)
-                    .attach_printable("Could not read simple experiment config")?,
-            ),
+        }
+    }
+
+    /// Returns the seed specified in _experiments.json_ for a [`Simple`](Self::Simple)
+    /// experiment.
+    ///
+    /// The `seed` of the experiment definition takes precedence over the top-level `seed`.
+    pub fn seed(&self, simulation: &SimulationSource) -> Result<Option<Seed>> {
+        let name = match self {
+            ExperimentType::Simple { name } => name,
+            ExperimentType::SingleRun { .. } | ExperimentType::Resume { .. } => return Ok(None),
         };
-        Ok(ExperimentPackageConfig::Basic(basic))
+        let parsed = parse_experiments(simulation.experiments_src.as_deref())?;
+        experiment_seed(&parsed, name.as_str())
+    }
+
+    /// Returns the packages chosen in _experiments.json_.
+    ///
+    /// The `packages` of a [`Simple`](Self::Simple) experiment definition take precedence over the
+    /// top-level `packages`. Without _experiments.json_, the default packages are used.
+    pub fn packages(&self, simulation: &SimulationSource) -> Result<PackageSelection> {
+        if simulation.experiments_src.is_none() {
+            return Ok(PackageSelection::default());
+        }
+        let parsed = parse_experiments(simulation.experiments_src.as_deref())?;
+        let name = match self {
+            ExperimentType::Simple { name } => Some(name.as_str()),
+            ExperimentType::SingleRun { .. } | ExperimentType::Resume { .. } => None,
+        };
+        experiment_packages(&parsed, name)
     }
 }
 
-fn get_simple_experiment_config(
-    simulation: &SimulationSource,
-    experiment_name: ExperimentName,
-) -> Result<SimpleExperimentConfig> {
-    let experiments_manifest = simulation
-        .experiments_src
-        .as_ref()
+/// Validates every experiment defined in _experiments.json_ without running it.
+///
+/// Returns the name and the error of each experiment, which could not be read. If
+/// `experiments_src` can't be parsed at all, the error is returned without a name.
+pub(crate) fn validate_experiments(
+    experiments_src: &str,
+) -> Vec<(Option<String>, Report<ExperimentPlanError>)> {
+    let parsed = match parse_experiments(Some(experiments_src)) {
+        Ok(parsed) => parsed,
+        Err(report) => return vec![(None, report)],
+    };
+
+    let mut errors = Vec::new();
+    if let Err(report) = experiment_packages(&parsed, None) {
+        errors.push((None, report));
+    }
+
+    // Top-level entries without a type, e.g. `seed` or `packages`, are not experiments
+    let mut names = parsed
+        .iter()
+        .filter(|(_, experiment)| experiment.get("type").is_some())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let result = experiment_seed(&parsed, name).and_then(|seed| {
+            experiment_packages(&parsed, Some(name))?;
+            get_experiment_config(Some(experiments_src), name.clone().into(), seed)
+        });
+        if let Err(report) = result {
+            errors.push((Some(name.clone()), report));
+        }
+    }
+    errors
+}
+
+/// Returns the seed of the experiment `name`, or the top-level seed.
+fn experiment_seed(
+    parsed: &HashMap<String, serde_json::Value>,
+    name: &str,
+) -> Result<Option<Seed>> {
+    parsed
+        .get(name)
+        .and_then(|experiment| experiment.get("seed"))
+        .or_else(|| parsed.get("seed"))
+        .map(|seed| {
+            seed.as_u64()
+                .map(Seed::new)
+                .ok_or_else(|| Report::new(ExperimentPlanError))
+        })
+        .transpose()
+        .attach_printable("seed in experiments.json was set, but wasn't a valid integer")
+}
+
+/// Returns the packages of the experiment `name`, or the top-level packages.
+fn experiment_packages(
+    parsed: &HashMap<String, serde_json::Value>,
+    name: Option<&str>,
+) -> Result<PackageSelection> {
+    name.and_then(|name| parsed.get(name))
+        .and_then(|experiment| experiment.get("packages"))
+        .or_else(|| parsed.get("packages"))
+        .map(|packages| {
+            serde_json::from_value(packages.clone())
+                .into_report()
+                .change_context(ExperimentPlanError)
+        })
+        .transpose()
+        .attach_printable("packages in experiments.json were set, but aren't valid")
+        .map(Option::unwrap_or_default)
+}
+
+fn parse_experiments(experiments_src: Option<&str>) -> Result<HashMap<String, serde_json::Value>> {
+    let experiments_manifest = experiments_src
         .ok_or_else(|| Report::new(ExperimentPlanError))
         .attach_printable("Experiment configuration not found: experiments.json")?;
     let experiments_manifest_comment_remover = StripComments::new(experiments_manifest.as_bytes());
-    let parsed = serde_json::from_reader(experiments_manifest_comment_remover)
-        .into_report()
-        .change_context(ExperimentPlanError)
-        .attach_printable("Could not parse experiment manifest")?;
-    let plan = create_experiment_plan(&parsed, &experiment_name)
-        .attach_printable("Could not read experiment plan")?;
+    let parsed: HashMap<String, serde_json::Value> =
+        serde_json::from_reader(experiments_manifest_comment_remover)
+            .into_report()
+            .change_context(ExperimentPlanError)
+            .attach_printable("Could not parse experiment manifest")?;
+    Ok(parsed)
+}
+
+fn get_experiment_config(
+    experiments_src: Option<&str>,
+    experiment_name: ExperimentName,
+    seed: Option<Seed>,
+) -> Result<ExperimentPackageConfig> {
+    let parsed = parse_experiments(experiments_src)?;
 
     let max_sims_in_parallel = parsed
         .get("max_sims_in_parallel")
@@ -71,6 +186,47 @@ fn get_simple_experiment_config(
             "max_sims_in_parallel in globals.json was set, but wasn't a valid integer",
         )?; // Extract and report the error for failed parsing
 
+    let selected_experiment = parsed.get(experiment_name.as_str());
+    let experiment_type = selected_experiment
+        .and_then(|experiment| experiment.get("type"))
+        .and_then(serde_json::Value::as_str);
+    if let (Some(selected_experiment), Some("optimization")) =
+        (selected_experiment, experiment_type)
+    {
+        let payload = serde_json::from_value(selected_experiment.clone())
+            .into_report()
+            .change_context(ExperimentPlanError)
+            .attach_printable("Could not parse optimization experiment definition")?;
+        let num_parallel_runs = max_sims_in_parallel.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        });
+
+        return Ok(ExperimentPackageConfig::Extended(
+            ExtendedExperimentConfig::Optimization(OptimizationExperimentConfig {
+                experiment_name: experiment_name.to_string(),
+                payload,
+                num_parallel_runs,
+                seed,
+            }),
+        ));
+    }
+
+    let config = get_simple_experiment_config(&parsed, experiment_name, max_sims_in_parallel, seed)
+        .attach_printable("Could not read simple experiment config")?;
+    Ok(ExperimentPackageConfig::Basic(
+        BasicExperimentConfig::Simple(config),
+    ))
+}
+
+fn get_simple_experiment_config(
+    experiments: &HashMap<String, serde_json::Value>,
+    experiment_name: ExperimentName,
+    max_sims_in_parallel: Option<usize>,
+    seed: Option<Seed>,
+) -> Result<SimpleExperimentConfig> {
+    let plan = create_experiment_plan(experiments, &experiment_name, seed)
+        .attach_printable("Could not read experiment plan")?;
+
     let config = SimpleExperimentConfig {
         experiment_name,
         changed_globals: plan
@@ -91,6 +247,7 @@ fn get_simple_experiment_config(
 fn create_experiment_plan(
     experiments: &HashMap<String, serde_json::Value>,
     experiment_name: &ExperimentName,
+    seed: Option<Seed>,
 ) -> Result<SimpleExperimentPlan> {
     let selected_experiment = experiments
         .get(experiment_name.as_str())
@@ -109,11 +266,12 @@ fn create_experiment_plan(
         .ok_or_else(|| Report::new(ExperimentPlanError))
         .attach_printable("Expected experiment definition type to have a string value")?;
     match experiment_type {
-        "group" => create_group_variant(selected_experiment, experiments),
-        "multiparameter" => create_multiparameter_variant(selected_experiment, experiments),
-        "optimization" => bail!(Report::new(ExperimentPlanError)
-            .attach_printable("Not implemented for optimization experiment types")),
-        _ => create_basic_variant(selected_experiment, experiment_type)
+        "group" => create_group_variant(selected_experiment, experiments, seed),
+        "multiparameter" => create_multiparameter_variant(selected_experiment, experiments, seed),
+        "optimization" => bail!(Report::new(ExperimentPlanError).attach_printable(
+            "Optimization experiments can't be used as part of another experiment"
+        )),
+        _ => create_basic_variant(selected_experiment, experiment_type, seed)
             .attach_printable("Could not parse basic variant"),
     }
 }
@@ -121,6 +279,7 @@ fn create_experiment_plan(
 fn create_multiparameter_variant(
     selected_experiment: &serde_json::Value,
     experiments: &HashMap<String, serde_json::Value>,
+    seed: Option<Seed>,
 ) -> Result<SimpleExperimentPlan> {
     #[derive(Serialize, Deserialize)]
     struct MultiparameterVariant {
@@ -145,7 +304,7 @@ fn create_multiparameter_variant(
                     format!("Experiment plan does not define the specified experiment: {run_name}")
                 })
                 .attach_printable("Could not parse experiment file")?;
-            create_basic_variant(selected, run_name)
+            create_basic_variant(selected, run_name, seed)
                 .attach_printable("Could not parse basic variant")
         })
         .collect::<Result<Vec<SimpleExperimentPlan>>>()
@@ -178,6 +337,7 @@ fn create_multiparameter_variant(
 fn create_group_variant(
     selected_experiment: &serde_json::Value,
     experiments: &HashMap<String, serde_json::Value>,
+    seed: Option<Seed>,
 ) -> Result<SimpleExperimentPlan> {
     #[derive(Serialize, Deserialize)]
     struct GroupVariant {
@@ -194,7 +354,7 @@ fn create_group_variant(
     var.runs.iter().try_fold(
         SimpleExperimentPlan::new(var.steps as usize),
         |mut acc, name| {
-            let variants = create_experiment_plan(experiments, name)
+            let variants = create_experiment_plan(experiments, name, seed)
                 .attach_printable("Could not read experiment plan")?;
             variants.inner.into_iter().for_each(|v| {
                 acc.push(v);
@@ -207,9 +367,10 @@ fn create_group_variant(
 fn create_basic_variant(
     selected_experiment: &serde_json::Value,
     experiment_type: &str,
+    seed: Option<Seed>,
 ) -> Result<SimpleExperimentPlan> {
     match experiment_type {
-        "monte-carlo" => create_monte_carlo_variant_plan(selected_experiment),
+        "monte-carlo" => create_monte_carlo_variant_plan(selected_experiment, seed),
         "values" => create_value_variant_plan(selected_experiment),
         "linspace" => create_linspace_variant_plan(selected_experiment),
         "arange" => create_arange_variant_plan(selected_experiment),
@@ -239,6 +400,7 @@ fn create_variant_with_mapped_value(
 
 fn create_monte_carlo_variant_plan(
     selected_experiment: &serde_json::Value,
+    seed: Option<Seed>,
 ) -> Result<SimpleExperimentPlan> {
     #[derive(Serialize, Deserialize)]
     struct MonteCarloVariant {
@@ -281,7 +443,7 @@ fn create_monte_carlo_variant_plan(
     }
 
     impl MonteCarloVariant {
-        fn sample_distribution_fn(&self) -> Result<Mapper> {
+        fn sample_distribution_fn(&self, seed: Option<Seed>) -> Result<Mapper> {
             let distribution = match self.distribution.as_str() {
                 "normal" => Box::new(
                     Normal::new(self.mean.unwrap_or(1.0), self.std.unwrap_or(1.0))
@@ -320,9 +482,19 @@ fn create_monte_carlo_variant_plan(
                         .attach_printable("Unable to create normal distribution")?,
                 ),
             };
-            Ok(Box::new(move |_, _| {
-                let mut rng = rand::thread_rng();
-                distribution.sample(&mut rng).into()
+            // Every sample of a seeded plan is drawn from its own stream, derived from the field
+            // name and the index of the sample, so plans sampling multiple fields are uncorrelated
+            let seed = seed.map(|seed| {
+                self.field
+                    .bytes()
+                    .fold(seed, |seed, byte| seed.derive(u64::from(byte)))
+            });
+            Ok(Box::new(move |_, index| {
+                let sample = match seed {
+                    Some(seed) => distribution.sample(&mut seed.derive(index as u64).rng()),
+                    None => distribution.sample(&mut rand::thread_rng()),
+                };
+                sample.into()
             }))
         }
     }
@@ -335,7 +507,7 @@ fn create_monte_carlo_variant_plan(
     Ok(create_variant_with_mapped_value(
         &var.field,
         &values,
-        &var.sample_distribution_fn()?,
+        &var.sample_distribution_fn(seed)?,
         var.steps as usize,
     ))
 }
@@ -1,14 +1,16 @@
+use std::path::{Path, PathBuf};
+
 use execution::{
     package::{
         experiment::{ExperimentId, ExperimentName, ExperimentPackageConfig},
-        simulation::init::InitialStateName,
+        simulation::{init::InitialStateName, Seed},
     },
     runner::Language,
     worker::RunnerSpawnConfig,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::SimulationSource;
+use crate::{PackageSelection, SimulationSource};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ExperimentRun {
@@ -16,6 +18,15 @@ pub struct ExperimentRun {
     id: ExperimentId,
     config: ExperimentPackageConfig,
     simulation: SimulationSource,
+    /// Checkpoint the simulation run is resumed from
+    #[serde(default)]
+    resume_from: Option<PathBuf>,
+    /// Seed of the random number generators of the experiment
+    #[serde(default)]
+    seed: Option<Seed>,
+    /// Packages chosen by the project
+    #[serde(default)]
+    packages: PackageSelection,
 }
 
 impl ExperimentRun {
@@ -29,9 +40,35 @@ impl ExperimentRun {
             id: ExperimentId::generate(),
             config,
             simulation,
+            resume_from: None,
+            seed: None,
+            packages: PackageSelection::default(),
         }
     }
 
+    /// Resumes the simulation run from the checkpoint at `resume_from` instead of initializing the
+    /// state with the init packages.
+    #[must_use]
+    pub fn with_resume_from(mut self, resume_from: Option<PathBuf>) -> Self {
+        self.resume_from = resume_from;
+        self
+    }
+
+    /// Seeds the random number generators of the experiment, so runs with the same inputs
+    /// produce the same outputs.
+    #[must_use]
+    pub fn with_seed(mut self, seed: Option<Seed>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Chooses the packages of the simulation runs instead of the default packages.
+    #[must_use]
+    pub fn with_packages(mut self, packages: PackageSelection) -> Self {
+        self.packages = packages;
+        self
+    }
+
     pub fn id(&self) -> ExperimentId {
         self.id
     }
@@ -44,6 +81,18 @@ impl ExperimentRun {
         &self.config
     }
 
+    pub fn resume_from(&self) -> Option<&Path> {
+        self.resume_from.as_deref()
+    }
+
+    pub fn seed(&self) -> Option<Seed> {
+        self.seed
+    }
+
+    pub fn packages(&self) -> &PackageSelection {
+        &self.packages
+    }
+
     pub fn simulation(&self) -> &SimulationSource {
         &self.simulation
     }
@@ -59,6 +108,7 @@ impl ExperimentRun {
             python: self.requires_runner(Language::Python),
             rust: self.requires_runner(Language::Rust),
             javascript: self.requires_runner(Language::JavaScript),
+            wasm: self.requires_runner(Language::Wasm),
         }
     }
 
@@ -5,6 +5,7 @@
 //! [`ExperimentRun`] there are specific information running a simulation specified by
 //! [`SimulationSource`].
 
+mod check;
 mod config;
 mod dependencies;
 mod error;
@@ -13,7 +14,8 @@ mod manifest;
 mod simulation;
 
 pub use self::{
-    config::{ExperimentConfig, PackageConfig, PackageConfigBuilder},
+    check::{check_project, Diagnostic},
+    config::{ExperimentConfig, PackageConfig, PackageConfigBuilder, PackageSelection},
     dependencies::FetchDependencies,
     error::{Error, Result},
     experiment::{ExperimentRun, ExperimentType},
@@ -11,17 +11,14 @@ use std::{
 use error_stack::{bail, ensure, IntoReport, Report, ResultExt};
 use execution::package::simulation::{
     init::{InitialState, InitialStateName},
-    state::behavior_execution::Behavior,
-    PackageInitConfig, SimPackageArgs,
+    state::behavior_execution::{encode_wasm, transpile_typescript, Behavior},
+    PackageInitConfig, Seed, SimPackageArgs,
 };
-use serde::{self, de::DeserializeOwned};
+use serde::{self, de::DeserializeOwned, Deserialize};
 use stateful::global::Dataset;
 use thiserror::Error;
 
-use crate::{
-    dependencies::parse_raw_csv_into_json, experiment::ExperimentType, ExperimentRun,
-    SimulationSource,
-};
+use crate::{experiment::ExperimentType, ExperimentRun, SimulationSource};
 
 #[derive(Debug, Error)]
 #[error("Could not read manifest file")]
@@ -29,8 +26,17 @@ pub struct ManifestError;
 
 pub type Result<T, E = ManifestError> = error_stack::Result<T, E>;
 
-const BEHAVIOR_FILE_EXTENSIONS: [&str; 3] = ["js", "py", "rs"];
+const BEHAVIOR_FILE_EXTENSIONS: [&str; 6] = ["js", "ts", "py", "rs", "wasm", "wat"];
 const DATASET_FILE_EXTENSIONS: [&str; 2] = ["csv", "json"];
+/// The initial state files of a project, ordered by priority.
+const INIT_FILE_NAMES: [&str; 6] = [
+    "init.js",
+    "init.py",
+    "init.json",
+    "init.csv",
+    "init.parquet",
+    "init.arrow",
+];
 
 /// Contains all the necessary information required to run a simulation.
 ///
@@ -51,6 +57,9 @@ pub struct Manifest {
     /// JSON string describing the analysis that's calculated by the
     /// [analysis output package](execution::package::simulation::output::analysis).
     pub analysis_json: Option<String>,
+    /// JSON string configuring the output packages, e.g. which parts of the state are persisted by
+    /// the [JSON state output package](execution::package::simulation::output::json_state).
+    pub output_json: Option<String>,
     /// JSON string describing the structure of available experiments for this project.
     pub experiments_json: Option<String>,
     /// A list of all dependencies identified by its name.
@@ -65,9 +74,13 @@ impl Manifest {
 
     /// Reads the initial state from the file at the provided `path`.
     ///
+    /// CSV, Parquet, and Arrow IPC files are not read here but when the simulation is initialized,
+    /// so only their path is stored.
+    ///
     /// # Errors
     ///
-    /// - if the `path` does not refer to a JavaScript, Python, or JSON file
+    /// - if the `path` does not refer to a JavaScript, Python, JSON, CSV, Parquet, or Arrow IPC
+    ///   file
     /// - if the file could not be read
     pub fn set_initial_state_from_file<P: AsRef<Path>>(
         &mut self,
@@ -80,22 +93,35 @@ impl Manifest {
                 .attach_printable(format!("Couldn't find the init file at: {path:?}"))
         );
 
-        Ok(self.initial_state.replace(InitialState {
-            name: match file_extension(&path)?.as_str() {
-                "js" => InitialStateName::InitJs,
-                "py" => InitialStateName::InitPy,
-                "json" => InitialStateName::InitJson,
-                _ => bail!(Report::new(ManifestError)
-                    .attach_printable(format!("Not a valid initial state file: {path:?}"))),
-            },
-            src: file_contents(path)?,
-        }))
+        let name = match file_extension(&path)?.as_str() {
+            "js" => InitialStateName::InitJs,
+            "py" => InitialStateName::InitPy,
+            "json" => InitialStateName::InitJson,
+            "csv" => InitialStateName::InitCsv,
+            "parquet" => InitialStateName::InitParquet,
+            "arrow" => InitialStateName::InitArrow,
+            _ => bail!(Report::new(ManifestError)
+                .attach_printable(format!("Not a valid initial state file: {path:?}"))),
+        };
+        let src = if name.is_columnar() {
+            path.canonicalize()
+                .into_report()
+                .attach_printable_lazy(|| format!("Could not resolve path: {path:?}"))
+                .change_context(ManifestError)?
+                .to_string_lossy()
+                .into_owned()
+        } else {
+            file_contents(path)?
+        };
+
+        Ok(self.initial_state.replace(InitialState { name, src }))
     }
 
     /// Reads the initial state from the files provided in a directory specified by `src_folder`.
     ///
-    /// It attempts to read _init.js_, _init.py_, or _init.json_ and prioritizes that order. For
-    /// example if _init.js_ was found, it doesn't try to read _init.py_, or _init.json_.
+    /// It attempts to read _init.js_, _init.py_, _init.json_, _init.csv_, _init.parquet_, or
+    /// _init.arrow_ and prioritizes that order. For example if _init.js_ was found, it doesn't try
+    /// to read any of the other files.
     ///
     /// # Errors
     ///
@@ -112,30 +138,18 @@ impl Manifest {
             Report::new(ManifestError).attach_printable(format!("Not a directory: {src_folder:?}"))
         );
 
-        let js_path = src_folder.join("init.js");
-        let py_path = src_folder.join("init.py");
-        let json_path = src_folder.join("init.json");
-
         tracing::debug!("Reading initial state files");
-        if js_path.is_file() {
-            if py_path.is_file() {
-                tracing::warn!(r#""init.py" was supplied with "init.js", ignoring "init.py""#);
-            }
-            if json_path.is_file() {
-                tracing::warn!(r#""init.json" was supplied with "init.js", ignoring "init.json""#);
-            }
-            self.set_initial_state_from_file(js_path)
-        } else if py_path.is_file() {
-            if json_path.is_file() {
-                tracing::warn!(r#""init.json" was supplied with "init.py", ignoring "init.json""#);
-            }
-            self.set_initial_state_from_file(py_path)
-        } else if json_path.is_file() {
-            self.set_initial_state_from_file(json_path)
-        } else {
-            bail!(Report::new(ManifestError)
-                .attach_printable(format!("No initial state found in {src_folder:?}")));
+        let mut init_files = INIT_FILE_NAMES
+            .into_iter()
+            .filter(|file_name| src_folder.join(file_name).is_file());
+        let init_file = init_files.next().ok_or_else(|| {
+            Report::new(ManifestError)
+                .attach_printable(format!("No initial state found in {src_folder:?}"))
+        })?;
+        for ignored in init_files {
+            tracing::warn!(r#""{ignored}" was supplied with "{init_file}", ignoring "{ignored}""#);
         }
+        self.set_initial_state_from_file(src_folder.join(init_file))
     }
 
     /// Reads the content from the file at the provided `path` describing the
@@ -161,6 +175,16 @@ impl Manifest {
         Ok(())
     }
 
+    /// Reads the content from the file at the provided `path` configuring the output packages.
+    ///
+    /// # Errors
+    ///
+    /// - if the file referred by `path` could not be read
+    pub fn set_output_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.output_json.replace(file_contents(path)?);
+        Ok(())
+    }
+
     /// Reads the content from the file at the provided `path` describing the structure of available
     /// experiments for this project.
     ///
@@ -200,6 +224,7 @@ impl Manifest {
                             name: dependency_name.to_string(),
                             shortnames: vec![],
                             behavior_src: None,
+                            source_map: None,
                             behavior_keys_src: None,
                         }
                     } else {
@@ -231,10 +256,16 @@ impl Manifest {
 
     /// Reads a behavior from the file at the provided `path`.
     ///
+    /// TypeScript behaviors are transpiled to JavaScript together with a source map, so errors
+    /// reported by the JavaScript runner point to the TypeScript source. Binary WebAssembly modules
+    /// are base64-encoded.
+    ///
     /// # Errors
     ///
-    /// - if the `path` does not refer to a JavaScript, Python, or Rust file
+    /// - if the `path` does not refer to a JavaScript, TypeScript, Python, Rust, or WebAssembly
+    ///   file
     /// - if the file could not be read
+    /// - if a TypeScript behavior could not be transpiled
     /// - if the behavior keys at _`path`.json_ could not be read
     pub fn add_behavior_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         let path = path.as_ref();
@@ -267,12 +298,35 @@ impl Manifest {
             })?;
         let key_path = folder_path.join(&format!("{file_name}.json"));
 
+        let mut behavior_src = if file_extension == "wasm" {
+            std::fs::read(path)
+                .map(|module| Some(encode_wasm(&module)))
+                .into_report()
+                .attach_printable_lazy(|| format!("Could not read file: {path:?}"))
+                .change_context(ManifestError)
+                .attach_printable("Could not read behavior")?
+        } else {
+            file_contents_opt(&path).attach_printable("Could not read behavior")?
+        };
+        let mut source_map = None;
+        if file_extension == "ts" {
+            if let Some(source) = behavior_src.take() {
+                let transpiled = transpile_typescript(&file_name, &source)
+                    .into_report()
+                    .attach_printable_lazy(|| format!("Could not transpile behavior {path:?}"))
+                    .change_context(ManifestError)?;
+                behavior_src = Some(transpiled.source);
+                source_map = Some(transpiled.source_map);
+            }
+        }
+
         self.add_behavior(Behavior {
             // `id`, `name` and `shortnames` may be updated later if this behavior is a dependency
             id: file_name.clone(),
             name: file_name,
             shortnames: vec![], // if this is a dependency, then these will be updated later
-            behavior_src: file_contents_opt(&path).attach_printable("Could not read behavior")?,
+            behavior_src,
+            source_map,
             // this may not return anything if file doesn't exist
             behavior_keys_src: file_contents_opt(&key_path)
                 .attach_printable("Could not read behavior keys")?,
@@ -306,8 +360,11 @@ impl Manifest {
             match entry {
                 Ok(entry) => {
                     let path = entry.path();
-                    // Filter for `.json` files for behavior keys
-                    if file_extension(&path)? != "json" {
+                    // Filter for `.json` files for behavior keys and TypeScript declaration files
+                    let is_declaration_file = path
+                        .file_name()
+                        .map_or(false, |name| name.to_string_lossy().ends_with(".d.ts"));
+                    if file_extension(&path)? != "json" && !is_declaration_file {
                         self.add_behavior_from_file(path)
                             .attach_printable("Could not add behavior")?;
                     }
@@ -327,10 +384,15 @@ impl Manifest {
 
     /// Reads a dataset from the file at the provided `path`.
     ///
+    /// The first row of a CSV dataset is its header. For CSV files without a header, a file with the
+    /// same name and an additional `.json` extension, e.g. _data.csv.json_, has to contain
+    /// `{ "header": false }`.
+    ///
     /// # Errors
     ///
     /// - if the `path` does not refer to a *valid* JSON or CSV file
     /// - if the file could not be read
+    /// - if the options of a CSV file could not be parsed
     pub fn add_dataset_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         let path = path.as_ref();
         ensure!(
@@ -345,21 +407,23 @@ impl Manifest {
                 .attach_printable(format!("Not a valid dataset extension: {path:?}"))
         );
 
-        let mut data = file_contents(path).attach_printable("Could not read dataset")?;
-
-        if file_extension == "csv" {
-            data = parse_raw_csv_into_json(data)
-                .attach_printable_lazy(|| format!("Could not convert csv into json: {path:?}"))
-                .change_context(ManifestError)?;
-        }
+        let data = file_contents(path).attach_printable("Could not read dataset")?;
 
         let filename = path.file_name().unwrap().to_string_lossy().to_string();
+        let options_path = path.with_file_name(format!("{filename}.json"));
+        let options = if file_extension == "csv" && options_path.is_file() {
+            parse_file(options_path).attach_printable("Could not read CSV dataset options")?
+        } else {
+            CsvDatasetOptions::default()
+        };
+
         self.add_dataset(Dataset {
             name: Some(filename.clone()),
             shortname: filename.clone(),
             filename,
             url: None,
             raw_csv: file_extension == "csv",
+            has_header: options.header,
             data: Some(data),
         });
         Ok(())
@@ -389,7 +453,12 @@ impl Manifest {
         {
             match entry {
                 Ok(entry) => {
-                    self.add_dataset_from_file(entry.path())
+                    let path = entry.path();
+                    // Filter for the options of CSV datasets
+                    if is_csv_dataset_options(&path) {
+                        continue;
+                    }
+                    self.add_dataset_from_file(path)
                         .attach_printable("Could not add dataset")?;
                 }
                 Err(err) => {
@@ -415,6 +484,8 @@ impl Manifest {
     ///   [`set_experiments_from_file("experiments.json")`](Self::set_experiments_from_file)
     /// - Analysis JSON as specified in
     ///   [`set_analysis_from_file("views/analysis.json")`](Self::set_analysis_from_file)
+    /// - Output JSON as specified in
+    ///   [`set_output_from_file("views/output.json")`](Self::set_output_from_file)
     /// - Dependencies recursively as provided by
     ///   [`set_dependencies_from_file("dependencies.json")`](Self::set_dependencies_from_file)
     pub fn from_local<P: AsRef<Path>>(project_path: P) -> Result<Self> {
@@ -460,6 +531,7 @@ impl Manifest {
         let globals_json = src_folder.join("globals.json");
         let views_folder = project_path.join("views");
         let analysis_json = views_folder.join("analysis.json");
+        let output_json = views_folder.join("output.json");
         let data_folder = project_path.join("data");
         let dependencies_folder = project_path.join("dependencies");
 
@@ -482,6 +554,11 @@ impl Manifest {
                     .set_analysis_from_file(analysis_json)
                     .attach_printable("Could not read analysis view")?;
             }
+            if output_json.exists() {
+                project
+                    .set_output_from_file(output_json)
+                    .attach_printable("Could not read output config")?;
+            }
 
             if experiments_json.exists() {
                 project
@@ -522,10 +599,17 @@ impl Manifest {
     /// Combines this `Manifest` with the specified [`ExperimentType`] to create an
     /// [`ExperimentRun`].
     ///
+    /// The experiment is seeded with `seed` or, if it's not provided, with the seed specified in
+    /// _experiments.json_. Without any seed, the experiment is not reproducible.
+    ///
     /// # Errors
     ///
     /// - if the manifest does not provide an initial state
-    pub fn read(self, experiment_type: ExperimentType) -> Result<ExperimentRun> {
+    pub fn read(
+        self,
+        experiment_type: ExperimentType,
+        seed: Option<Seed>,
+    ) -> Result<ExperimentRun> {
         let simulation = SimulationSource {
             name: self.project_name,
             globals_src: self.globals_json.unwrap_or_else(|| "{}".to_string()),
@@ -534,10 +618,16 @@ impl Manifest {
             // TODO: allow packages themselves to implement resolvers for local projects to build
             // this   field
             package_init: PackageInitConfig {
-                packages: vec![SimPackageArgs {
-                    name: "analysis".into(),
-                    data: serde_json::Value::String(self.analysis_json.unwrap_or_default()),
-                }],
+                packages: vec![
+                    SimPackageArgs {
+                        name: "analysis".into(),
+                        data: serde_json::Value::String(self.analysis_json.unwrap_or_default()),
+                    },
+                    SimPackageArgs {
+                        name: "output".into(),
+                        data: serde_json::Value::String(self.output_json.unwrap_or_default()),
+                    },
+                ],
                 behaviors: self.behaviors,
                 initial_state: self
                     .initial_state
@@ -546,16 +636,33 @@ impl Manifest {
             },
         };
 
-        let name = match &experiment_type {
-            ExperimentType::SingleRun { .. } => "single_run".to_string().into(),
-            ExperimentType::Simple { name } => name.clone(),
+        let (name, resume_from) = match &experiment_type {
+            ExperimentType::SingleRun { .. } => ("single_run".to_string().into(), None),
+            ExperimentType::Simple { name } => (name.clone(), None),
+            ExperimentType::Resume { checkpoint, .. } => {
+                ("resume".to_string().into(), Some(checkpoint.clone()))
+            }
         };
 
+        let seed = match seed {
+            Some(seed) => Some(seed),
+            None => experiment_type
+                .seed(&simulation)
+                .attach_printable("Could not read seed")
+                .change_context(ManifestError)?,
+        };
+        let packages = experiment_type
+            .packages(&simulation)
+            .attach_printable("Could not read packages")
+            .change_context(ManifestError)?;
         let config = experiment_type
-            .get_package_config(&simulation)
+            .get_package_config(&simulation, seed)
             .attach_printable("Could not read package config")
             .change_context(ManifestError)?;
-        Ok(ExperimentRun::new(name, simulation, config))
+        Ok(ExperimentRun::new(name, simulation, config)
+            .with_resume_from(resume_from)
+            .with_seed(seed)
+            .with_packages(packages))
     }
 }
 
@@ -748,7 +855,7 @@ fn _try_read_local_dependencies<P: AsRef<Path>>(dependency_path: P) -> Result<Ve
     Ok(entries)
 }
 
-fn local_dependencies_folders<P: AsRef<Path>>(dependency_path: P) -> Vec<PathBuf> {
+pub(crate) fn local_dependencies_folders<P: AsRef<Path>>(dependency_path: P) -> Vec<PathBuf> {
     // TODO: OS: do we want this wrapper to provide a default, or should we just unwrap
     _try_read_local_dependencies(dependency_path).unwrap_or_default()
 }
@@ -763,6 +870,36 @@ fn file_extension<P: AsRef<Path>>(path: P) -> Result<String> {
         .to_lowercase())
 }
 
+/// Options of a CSV dataset, read from the file next to the dataset, see
+/// [`Manifest::add_dataset_from_file`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CsvDatasetOptions {
+    /// Whether the first row is the header
+    #[serde(default = "CsvDatasetOptions::default_header")]
+    header: bool,
+}
+
+impl CsvDatasetOptions {
+    fn default_header() -> bool {
+        true
+    }
+}
+
+impl Default for CsvDatasetOptions {
+    fn default() -> Self {
+        Self {
+            header: Self::default_header(),
+        }
+    }
+}
+
+/// Returns if `path` is the file containing the options of a CSV dataset, e.g. _data.csv.json_.
+pub(crate) fn is_csv_dataset_options(path: &Path) -> bool {
+    path.file_name()
+        .map_or(false, |name| name.to_string_lossy().ends_with(".csv.json"))
+}
+
 fn file_contents<P: AsRef<Path>>(path: P) -> Result<String> {
     let path = path.as_ref();
     tracing::debug!("Reading contents at path: {path:?}");
@@ -1,10 +1,14 @@
 use std::sync::Arc;
 
 use execution::{
-    package::simulation::{PackageCreatorConfig, PersistenceConfig, SimulationId},
+    package::simulation::{PackageCreatorConfig, PersistenceConfig, Seed, SimulationId},
     worker_pool::WorkerAllocation,
 };
-use stateful::{field::Schema, global::Globals, state::StateCreateParameters};
+use stateful::{
+    field::Schema,
+    global::{Dataset, Globals},
+    state::StateCreateParameters,
+};
 
 use crate::ExperimentConfig;
 
@@ -26,6 +30,8 @@ impl SimulationConfig {
         schema: Schema,
         persistence_config: PersistenceConfig,
         max_num_steps: usize,
+        seed: Option<Seed>,
+        datasets: Arc<[Dataset]>,
     ) -> Self {
         Self {
             id,
@@ -33,6 +39,8 @@ impl SimulationConfig {
                 agent_schema: Arc::clone(&schema.agent_schema),
                 globals,
                 persistence: persistence_config,
+                seed,
+                datasets,
             },
             worker_allocation: Arc::new(worker_allocation),
             schema: Arc::new(schema),
@@ -56,6 +64,11 @@ impl SimulationRunConfig {
         persistence_config: PersistenceConfig,
         max_num_steps: usize,
     ) -> SimulationRunConfig {
+        // Every simulation run of a seeded experiment gets its own seed
+        let seed = experiment_config
+            .experiment_run
+            .seed()
+            .map(|seed| seed.for_simulation(id));
         let simulation_config = SimulationConfig::new(
             id,
             globals,
@@ -63,6 +76,8 @@ impl SimulationRunConfig {
             schema,
             persistence_config,
             max_num_steps,
+            seed,
+            Arc::clone(&experiment_config.datasets),
         );
         SimulationRunConfig {
             experiment: experiment_config,
@@ -0,0 +1,2 @@
+def behavior(state, context):
+    state.speed += 1
@@ -31,13 +31,13 @@ pub const ENUM_MIN_RUNNER_INBOUND_MSG_PAYLOAD: u8 = 0;
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
-pub const ENUM_MAX_RUNNER_INBOUND_MSG_PAYLOAD: u8 = 9;
+pub const ENUM_MAX_RUNNER_INBOUND_MSG_PAYLOAD: u8 = 10;
 #[deprecated(
     since = "2.0.0",
     note = "Use associated constants instead. This will no longer be generated in 2021."
 )]
 #[allow(non_camel_case_types)]
-pub const ENUM_VALUES_RUNNER_INBOUND_MSG_PAYLOAD: [RunnerInboundMsgPayload; 10] = [
+pub const ENUM_VALUES_RUNNER_INBOUND_MSG_PAYLOAD: [RunnerInboundMsgPayload; 11] = [
     RunnerInboundMsgPayload::NONE,
     RunnerInboundMsgPayload::TaskMsg,
     RunnerInboundMsgPayload::CancelTask,
@@ -48,6 +48,7 @@ pub const ENUM_VALUES_RUNNER_INBOUND_MSG_PAYLOAD: [RunnerInboundMsgPayload; 10]
     RunnerInboundMsgPayload::TerminateSimulationRun,
     RunnerInboundMsgPayload::TerminateRunner,
     RunnerInboundMsgPayload::NewSimulationRun,
+    RunnerInboundMsgPayload::UpdateGlobals,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -57,7 +58,7 @@ pub struct RunnerInboundMsgPayload(pub u8);
 impl RunnerInboundMsgPayload {
     pub const CancelTask: Self = Self(2);
     pub const ContextBatchSync: Self = Self(5);
-    pub const ENUM_MAX: u8 = 9;
+    pub const ENUM_MAX: u8 = 10;
     pub const ENUM_MIN: u8 = 0;
     pub const ENUM_VALUES: &'static [Self] = &[
         Self::NONE,
@@ -70,6 +71,7 @@ impl RunnerInboundMsgPayload {
         Self::TerminateSimulationRun,
         Self::TerminateRunner,
         Self::NewSimulationRun,
+        Self::UpdateGlobals,
     ];
     pub const NONE: Self = Self(0);
     pub const NewSimulationRun: Self = Self(9);
@@ -79,6 +81,7 @@ impl RunnerInboundMsgPayload {
     pub const TaskMsg: Self = Self(1);
     pub const TerminateRunner: Self = Self(8);
     pub const TerminateSimulationRun: Self = Self(7);
+    pub const UpdateGlobals: Self = Self(10);
 
     /// Returns the variant's name or "" if unknown.
     pub fn variant_name(self) -> Option<&'static str> {
@@ -93,6 +96,7 @@ impl RunnerInboundMsgPayload {
             Self::TerminateSimulationRun => Some("TerminateSimulationRun"),
             Self::TerminateRunner => Some("TerminateRunner"),
             Self::NewSimulationRun => Some("NewSimulationRun"),
+            Self::UpdateGlobals => Some("UpdateGlobals"),
             _ => None,
         }
     }
@@ -416,6 +420,111 @@ impl std::fmt::Debug for TerminateSimulationRun<'_> {
         ds.finish()
     }
 }
+pub enum UpdateGlobalsOffset {}
+#[derive(Copy, Clone, PartialEq)]
+
+pub struct UpdateGlobals<'a> {
+    pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UpdateGlobals<'a> {
+    type Inner = UpdateGlobals<'a>;
+
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf, loc },
+        }
+    }
+}
+
+impl<'a> UpdateGlobals<'a> {
+    pub const VT_GLOBALS: flatbuffers::VOffsetT = 4;
+
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UpdateGlobals { _tab: table }
+    }
+
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UpdateGlobalsArgs<'args>,
+    ) -> flatbuffers::WIPOffset<UpdateGlobals<'bldr>> {
+        let mut builder = UpdateGlobalsBuilder::new(_fbb);
+        if let Some(x) = args.globals {
+            builder.add_globals(x);
+        }
+        builder.finish()
+    }
+
+    #[inline]
+    pub fn globals(&self) -> &'a str {
+        self._tab
+            .get::<flatbuffers::ForwardsUOffset<&str>>(UpdateGlobals::VT_GLOBALS, None)
+            .unwrap()
+    }
+}
+
+impl flatbuffers::Verifiable for UpdateGlobals<'_> {
+    #[inline]
+    fn run_verifier(
+        v: &mut flatbuffers::Verifier,
+        pos: usize,
+    ) -> Result<(), flatbuffers::InvalidFlatbuffer> {
+        use self::flatbuffers::Verifiable;
+        v.visit_table(pos)?
+            .visit_field::<flatbuffers::ForwardsUOffset<&str>>(&"globals", Self::VT_GLOBALS, true)?
+            .finish();
+        Ok(())
+    }
+}
+pub struct UpdateGlobalsArgs<'a> {
+    pub globals: Option<flatbuffers::WIPOffset<&'a str>>,
+}
+impl<'a> Default for UpdateGlobalsArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UpdateGlobalsArgs {
+            globals: None, // required field
+        }
+    }
+}
+pub struct UpdateGlobalsBuilder<'a: 'b, 'b> {
+    fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+    start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UpdateGlobalsBuilder<'a, 'b> {
+    #[inline]
+    pub fn add_globals(&mut self, globals: flatbuffers::WIPOffset<&'b str>) {
+        self.fbb_
+            .push_slot_always::<flatbuffers::WIPOffset<_>>(UpdateGlobals::VT_GLOBALS, globals);
+    }
+
+    #[inline]
+    pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UpdateGlobalsBuilder<'a, 'b> {
+        let start = _fbb.start_table();
+        UpdateGlobalsBuilder {
+            fbb_: _fbb,
+            start_: start,
+        }
+    }
+
+    #[inline]
+    pub fn finish(self) -> flatbuffers::WIPOffset<UpdateGlobals<'a>> {
+        let o = self.fbb_.end_table(self.start_);
+        self.fbb_.required(o, UpdateGlobals::VT_GLOBALS, "globals");
+        flatbuffers::WIPOffset::new(o.value())
+    }
+}
+
+impl std::fmt::Debug for UpdateGlobals<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ds = f.debug_struct("UpdateGlobals");
+        ds.field("globals", &self.globals());
+        ds.finish()
+    }
+}
 pub enum RunnerInboundMsgOffset {}
 #[derive(Copy, Clone, PartialEq)]
 
@@ -583,6 +692,17 @@ impl<'a> RunnerInboundMsg<'a> {
             None
         }
     }
+
+    #[inline]
+    #[allow(non_snake_case)]
+    pub fn payload_as_update_globals(&self) -> Option<UpdateGlobals<'a>> {
+        if self.payload_type() == RunnerInboundMsgPayload::UpdateGlobals {
+            let u = self.payload();
+            Some(UpdateGlobals::init_from_table(u))
+        } else {
+            None
+        }
+    }
 }
 
 impl flatbuffers::Verifiable for RunnerInboundMsg<'_> {
@@ -648,6 +768,11 @@ impl flatbuffers::Verifiable for RunnerInboundMsg<'_> {
                             "RunnerInboundMsgPayload::NewSimulationRun",
                             pos,
                         ),
+                    RunnerInboundMsgPayload::UpdateGlobals => v
+                        .verify_union_variant::<flatbuffers::ForwardsUOffset<UpdateGlobals>>(
+                            "RunnerInboundMsgPayload::UpdateGlobals",
+                            pos,
+                        ),
                     _ => Ok(()),
                 },
             )?
@@ -812,6 +937,16 @@ impl std::fmt::Debug for RunnerInboundMsg<'_> {
                     )
                 }
             }
+            RunnerInboundMsgPayload::UpdateGlobals => {
+                if let Some(x) = self.payload_as_update_globals() {
+                    ds.field("payload", &x)
+                } else {
+                    ds.field(
+                        "payload",
+                        &"InvalidFlatbuffer: Union discriminant does not match value.",
+                    )
+                }
+            }
             _ => {
                 let x: Option<()> = None;
                 ds.field("payload", &x)
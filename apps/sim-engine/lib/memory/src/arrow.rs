@@ -31,7 +31,8 @@ pub use self::{
     buffer::{new_buffer, new_offsets_buffer, new_zero_bits},
     change::{ColumnChange, IntoArrowChange},
     conversion::{
-        col_to_json_vals, json_utf8_json_vals, json_vals_to_any_type_col, json_vals_to_bool,
-        json_vals_to_col, json_vals_to_primitive, json_vals_to_utf8,
+        categorical_to_json_vals, col_to_json_vals, json_utf8_json_vals, json_vals_to_any_type_col,
+        json_vals_to_bool, json_vals_to_categorical_col, json_vals_to_col, json_vals_to_primitive,
+        json_vals_to_utf8,
     },
 };
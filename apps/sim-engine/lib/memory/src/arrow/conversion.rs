@@ -214,6 +214,9 @@ pub fn json_vals_to_col(vals: Vec<Value>, field: &Field, nullable: bool) -> Resu
         DataType::Float64 => Ok(Box::new(json_vals_to_primitive::<f64>(vals, nullable)?)),
         DataType::Float32 => Ok(Box::new(json_vals_to_primitive::<f32>(vals, nullable)?)),
         DataType::Int64 => Ok(Box::new(json_vals_to_primitive::<i64>(vals, nullable)?)),
+        DataType::Timestamp(..) => Ok(Box::new(
+            json_vals_to_primitive::<i64>(vals, nullable)?.to(field.data_type().clone()),
+        )),
         DataType::Int32 => Ok(Box::new(json_vals_to_primitive::<i32>(vals, nullable)?)),
         DataType::Int16 => Ok(Box::new(json_vals_to_primitive::<i16>(vals, nullable)?)),
         DataType::Int8 => Ok(Box::new(json_vals_to_primitive::<i8>(vals, nullable)?)),
@@ -258,6 +261,38 @@ pub fn json_vals_to_any_type_col(vals: Vec<Value>, dt: &DataType) -> Result<Box<
     Ok(array.boxed())
 }
 
+/// Converts JSON strings into the indices of the corresponding `categories`.
+pub fn json_vals_to_categorical_col(
+    vals: Vec<Value>,
+    categories: &[String],
+    nullable: bool,
+) -> Result<Box<dyn Array>> {
+    let mut builder = MutablePrimitiveArray::<u32>::with_capacity(vals.len());
+    for val in vals {
+        let category: Option<String> = if nullable {
+            serde_json::from_value(val)?
+        } else {
+            Some(serde_json::from_value(val)?)
+        };
+        let index = category
+            .map(|category| {
+                categories
+                    .iter()
+                    .position(|known| *known == category)
+                    .map(|index| index as u32)
+                    .ok_or_else(|| Error::InvalidCategory {
+                        value: category,
+                        categories: categories.to_vec(),
+                    })
+            })
+            .transpose()?;
+        builder.push(index);
+    }
+
+    let array: PrimitiveArray<u32> = builder.into();
+    Ok(array.boxed())
+}
+
 fn numeric_to_json_vals<T: NativeType + Num + Serialize>(col: &dyn Array) -> Result<Vec<Value>> {
     // TODO: Return Err if `as_primitive_array` cast fails,
     //       by calling `downcast_col` instead.
@@ -332,6 +367,32 @@ pub fn json_utf8_json_vals(col: &dyn Array) -> Result<Vec<Value>> {
     Ok(json_vals)
 }
 
+/// Converts the category indices of a categorical column back into JSON strings.
+pub fn categorical_to_json_vals(col: &dyn Array, categories: &[String]) -> Result<Vec<Value>> {
+    let array =
+        col.as_any()
+            .downcast_ref::<PrimitiveArray<u32>>()
+            .ok_or(Error::InvalidArrowDowncast {
+                name: "[categorical]".into(),
+            })?;
+
+    array
+        .iter()
+        .map(|index| match index {
+            Some(index) => categories
+                .get(*index as usize)
+                .map(|category| Value::String(category.clone()))
+                .ok_or_else(|| {
+                    Error::from(format!(
+                        "Category index {index} is out of range for the categories \
+                         {categories:?}"
+                    ))
+                }),
+            None => Ok(Value::Null),
+        })
+        .collect()
+}
+
 fn list_to_json_vals(col: &dyn Array, inner_dt: &DataType) -> Result<Vec<Value>> {
     let array =
         col.as_any()
@@ -420,7 +481,7 @@ pub fn col_to_json_vals(col: &dyn Array, dt: &DataType) -> Result<Vec<Value>> {
         DataType::Int8 => numeric_to_json_vals::<i8>(col),
         DataType::Int16 => numeric_to_json_vals::<i16>(col),
         DataType::Int32 => numeric_to_json_vals::<i32>(col),
-        DataType::Int64 => numeric_to_json_vals::<i64>(col),
+        DataType::Int64 | DataType::Timestamp(..) => numeric_to_json_vals::<i64>(col),
         DataType::UInt8 => numeric_to_json_vals::<u8>(col),
         DataType::UInt16 => numeric_to_json_vals::<u16>(col),
         DataType::UInt32 => numeric_to_json_vals::<u32>(col),
@@ -47,6 +47,9 @@ pub enum Error {
     #[error("Shared memory error: {0}")]
     SharedMemory(shared_memory::ShmemError),
 
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("Arrow RecordBatch message error: {0}")]
     ArrowBatch(String),
 
@@ -68,6 +71,12 @@ pub enum Error {
     #[error("Expected boolean value in `serde_json::Value`")]
     BooleanSerdeValueExpected,
 
+    #[error("{value:?} is not one of the categories {categories:?}")]
+    InvalidCategory {
+        value: String,
+        categories: Vec<String>,
+    },
+
     #[error("Unable to read IPC message as record batch")]
     InvalidRecordBatchIpcMessage,
 
@@ -24,5 +24,9 @@ pub use self::{
     continuation::arrow_continuation,
     markers::Markers,
     metaversion::Metaversion,
-    segment::{cleanup_by_base_id, MemoryId, Segment},
+    segment::{
+        cleanup_by_base_id, is_segment_name, memory_backend, owner_lock_id, owner_status,
+        set_memory_backend, MemoryBackend, MemoryId, OwnerLock, OwnerStatus, Segment,
+        SegmentMemory,
+    },
 };
@@ -1,6 +1,5 @@
 use std::{env, fmt, mem};
 
-use shared_memory::{Shmem, ShmemConf};
 use tracing::trace;
 
 use crate::{
@@ -13,13 +12,17 @@ use crate::{
     },
 };
 
+pub mod backend;
 pub mod buffers;
 pub mod cleanup;
 pub mod memory_id;
+pub mod owner;
 
+pub use backend::{memory_backend, set_memory_backend, MemoryBackend, SegmentMemory};
 pub use buffers::Buffers;
-pub use cleanup::cleanup_by_base_id;
+pub use cleanup::{cleanup_by_base_id, is_segment_name};
 pub use memory_id::MemoryId;
+pub use owner::{owner_lock_id, owner_status, OwnerLock, OwnerStatus};
 
 use self::cleanup::IN_USE_SHM_SEGMENTS;
 
@@ -42,6 +45,9 @@ use self::cleanup::IN_USE_SHM_SEGMENTS;
 /// There are two ways to create a new [`Segment`]: [`Segment::new`] and [`Segment::duplicate`].
 /// [`Segment`] referencing existing segments can be created using [`Segment::open`].
 ///
+/// The memory of a segment is stored in the [`MemoryBackend`] selected with
+/// [`set_memory_backend`], which defaults to POSIX shared memory.
+///
 /// **It is imperative that [`Segment`]s are not created through any other means.** When we create a
 /// [`Segment`], we add a reference to the [`Segment`] in `cleanup::IN_USE_SHM_SEGMENTS` so
 /// that we can later destroy all the segments we have created. **Any new methods which are added
@@ -64,7 +70,7 @@ use self::cleanup::IN_USE_SHM_SEGMENTS;
 ///
 /// Note column data will not be densely packed as it will leave space for array size fluctuations.
 pub struct Segment {
-    pub data: Shmem,
+    pub data: SegmentMemory,
     pub size: usize,
     include_terminal_padding: bool,
 }
@@ -123,14 +129,12 @@ impl Segment {
         include_terminal_padding: bool,
     ) -> Result<Segment> {
         Self::validate_size(size)?;
-        let data = ShmemConf::new(droppable)
-            .os_id(&memory_id.to_string())
-            .size(size)
-            .create()?;
+        let os_id = backend::backend().os_id(&memory_id.to_string());
+        let data = SegmentMemory::create(os_id, size, droppable)?;
         IN_USE_SHM_SEGMENTS
             .lock()
             .unwrap()
-            .insert(memory_id.to_string());
+            .insert(data.get_os_id().to_string());
         Ok(Segment {
             data,
             size,
@@ -170,8 +174,7 @@ impl Segment {
                 debug_assert!(IN_USE_SHM_SEGMENTS.lock().unwrap().contains(os_id));
             }
 
-            let id = &os_id;
-            let data = ShmemConf::new(droppable).os_id(id).open()?;
+            let data = SegmentMemory::open(os_id, droppable)?;
             let size = data.len();
             Self::validate_size(size)?;
             Ok(Segment {
@@ -187,17 +190,13 @@ impl Segment {
     /// Duplicates the provided [`Segment`] - i.e. creates a new [`Segment`] with the provided
     /// [`MemoryId`] with the same contents as the provided [`Segment`].
     pub fn duplicate(memory: &Segment, memory_id: MemoryId) -> Result<Segment> {
-        let os_id = memory_id.to_string();
+        let os_id = backend::backend().os_id(&memory_id.to_string());
 
         // shouldn't duplicate to the same location
         debug_assert_ne!(memory.id(), os_id);
 
-        let shmem = &memory.data;
-        let data = ShmemConf::new(true)
-            .os_id(&os_id)
-            .size(memory.size)
-            .create()?;
-        unsafe { std::ptr::copy_nonoverlapping(shmem.as_ptr(), data.as_ptr(), memory.size) };
+        let data = SegmentMemory::create(os_id.clone(), memory.size, true)?;
+        unsafe { std::ptr::copy_nonoverlapping(memory.data.as_ptr(), data.as_ptr(), memory.size) };
 
         // make a note that we created this segment
         let segment_was_not_in_set_before_creation =
@@ -513,6 +512,20 @@ impl Segment {
         Ok(memory)
     }
 
+    /// Creates a new shared memory segment from `contents` previously returned by
+    /// [`Segment::get_contents_bytes`], e.g. after it was written to disk.
+    pub fn from_contents_bytes(
+        memory_id: MemoryId,
+        contents: &[u8],
+        include_terminal_padding: bool,
+    ) -> Result<Segment> {
+        let size = Self::calculate_total_size(contents.len(), include_terminal_padding)?;
+        let mut memory = Segment::new(memory_id, size, true, include_terminal_padding)?;
+        memory.overwrite_no_bounds_check(contents)?;
+        memory.validate_markers()?;
+        Ok(memory)
+    }
+
     pub fn target_total_size_accommodates_data_size(
         &self,
         target_shmem_size: usize,
@@ -554,6 +567,40 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    pub fn test_contents_bytes() -> Result<()> {
+        let buffer1: Vec<u8> = vec![1; 1482];
+        let buffer2: Vec<u8> = vec![2; 645];
+        let buffer3: Vec<u8> = vec![3; 254];
+        let buffer4: Vec<u8> = vec![4; 173];
+
+        let segment = Segment::from_batch_buffers(
+            MemoryId::new(Uuid::new_v4()),
+            &buffer1,
+            &buffer2,
+            &buffer3,
+            &buffer4,
+            true,
+        )?;
+        let contents = segment.get_contents_bytes()?.to_vec();
+
+        let new_segment =
+            Segment::from_contents_bytes(MemoryId::new(Uuid::new_v4()), &contents, true)?;
+
+        let Buffers {
+            schema,
+            header,
+            meta,
+            data,
+        } = new_segment.get_batch_buffers()?;
+
+        assert_eq!(buffer1, schema);
+        assert_eq!(buffer2, header);
+        assert_eq!(buffer3, meta);
+        assert_eq!(buffer4, data);
+        Ok(())
+    }
+
     #[test]
     pub fn test_message() -> Result<()> {
         let memory_id = MemoryId::new(Uuid::new_v4());
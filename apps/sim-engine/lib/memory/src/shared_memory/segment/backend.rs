@@ -0,0 +1,504 @@
+//! Storage backends for the memory of a [`Segment`].
+//!
+//! By default, every [`Segment`] is a POSIX shared-memory segment under */dev/shm*. As the size of
+//! */dev/shm* is limited in containers, segments can instead be stored in memory-mapped files in a
+//! configurable directory, or on the heap of the engine process if all runners are executed in the
+//! engine process. The backend is selected once per process with [`set_memory_backend`], before
+//! the first [`Segment`] is created.
+//!
+//! The identifier of a file-backed segment is the absolute path of its file, so other processes
+//! (e.g. the Python runner) can open it without knowing which backend the engine is using.
+//!
+//! [`Segment`]: super::Segment
+
+use std::{
+    alloc::{self, Layout},
+    fmt,
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+    ptr::NonNull,
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use memmap2::MmapMut;
+use shared_memory::{Shmem, ShmemConf};
+
+use crate::{arrow::util::alignment::ALIGNMENT, Error, Result};
+
+/// The storage used for the memory of [`Segment`](super::Segment)s.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryBackend {
+    /// POSIX shared memory under */dev/shm*.
+    #[default]
+    SharedMemory,
+    /// Memory-mapped files in a directory, which can be accessed by other processes.
+    Mmap,
+    /// Memory allocated on the heap. Segments can only be accessed by the process which created
+    /// them, so this can't be used together with the Python runner.
+    Heap,
+}
+
+impl fmt::Display for MemoryBackend {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SharedMemory => fmt.write_str("shm"),
+            Self::Mmap => fmt.write_str("mmap"),
+            Self::Heap => fmt.write_str("heap"),
+        }
+    }
+}
+
+impl FromStr for MemoryBackend {
+    type Err = String;
+
+    fn from_str(backend: &str) -> Result<Self, Self::Err> {
+        match backend {
+            "shm" | "shared-memory" => Ok(Self::SharedMemory),
+            "mmap" | "file" => Ok(Self::Mmap),
+            "heap" => Ok(Self::Heap),
+            _ => Err(format!(
+                "Unknown memory backend `{backend}`, expected one of `shm`, `mmap` or `heap`"
+            )),
+        }
+    }
+}
+
+impl MemoryBackend {
+    /// Returns the directory containing the segments of this backend, if they are stored as files.
+    ///
+    /// `directory` is the directory passed to [`set_memory_backend`].
+    pub fn segment_directory(self, directory: Option<PathBuf>) -> Option<PathBuf> {
+        match self {
+            Self::SharedMemory => Backend::SharedMemory.directory().map(Path::to_path_buf),
+            Self::Mmap => Some(directory.unwrap_or_else(default_mmap_directory)),
+            Self::Heap => None,
+        }
+    }
+}
+
+fn default_mmap_directory() -> PathBuf {
+    std::env::temp_dir().join("hash-engine")
+}
+
+/// The backend together with its configuration.
+#[derive(Debug)]
+pub(crate) enum Backend {
+    SharedMemory,
+    Mmap { directory: PathBuf },
+    Heap,
+}
+
+static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+/// Sets the backend used for all [`Segment`](super::Segment)s created by this process.
+///
+/// `directory` is where the files of the [`Mmap`](MemoryBackend::Mmap) backend are stored and is
+/// created if it doesn't exist. It defaults to a `hash-engine` directory inside of the temporary
+/// directory of the operating system.
+///
+/// # Errors
+///
+/// - if the backend was already set, or a segment was already created with the default backend
+/// - if the directory for the [`Mmap`](MemoryBackend::Mmap) backend can't be created
+pub fn set_memory_backend(backend: MemoryBackend, directory: Option<PathBuf>) -> Result<()> {
+    let backend = match backend {
+        MemoryBackend::SharedMemory => Backend::SharedMemory,
+        MemoryBackend::Mmap => {
+            let directory = directory.unwrap_or_else(default_mmap_directory);
+            fs::create_dir_all(&directory)?;
+            Backend::Mmap {
+                directory: directory.canonicalize()?,
+            }
+        }
+        MemoryBackend::Heap => Backend::Heap,
+    };
+    tracing::debug!("Using memory backend {backend:?}");
+    BACKEND
+        .set(backend)
+        .map_err(|_| Error::from("The memory backend can only be set once"))
+}
+
+/// Returns the backend used for [`Segment`](super::Segment)s created by this process.
+pub fn memory_backend() -> MemoryBackend {
+    match backend() {
+        Backend::SharedMemory => MemoryBackend::SharedMemory,
+        Backend::Mmap { .. } => MemoryBackend::Mmap,
+        Backend::Heap => MemoryBackend::Heap,
+    }
+}
+
+pub(crate) fn backend() -> &'static Backend {
+    BACKEND.get_or_init(|| Backend::SharedMemory)
+}
+
+impl Backend {
+    /// The identifier of the segment, which is called `name`.
+    pub(crate) fn os_id(&self, name: &str) -> String {
+        match self {
+            Self::SharedMemory | Self::Heap => name.to_string(),
+            Self::Mmap { directory } => directory.join(name).display().to_string(),
+        }
+    }
+
+    /// The directory containing the segments, if they are stored as files.
+    pub(crate) fn directory(&self) -> Option<&Path> {
+        match self {
+            Self::SharedMemory if cfg!(target_os = "linux") => Some(Path::new("/dev/shm")),
+            Self::SharedMemory | Self::Heap => None,
+            Self::Mmap { directory } => Some(directory),
+        }
+    }
+}
+
+/// The memory of a [`Segment`](super::Segment), stored in one of the [`MemoryBackend`]s.
+pub struct SegmentMemory {
+    id: String,
+    inner: Inner,
+}
+
+enum Inner {
+    SharedMemory(Shmem),
+    Mmap(MmapFile),
+    Heap(HeapMemory),
+}
+
+impl SegmentMemory {
+    /// Creates new zeroed memory of `size` bytes with the identifier `os_id`.
+    ///
+    /// # Errors
+    ///
+    /// - if `size` is zero
+    /// - if the memory can't be created by the backend
+    pub(crate) fn create(os_id: String, size: usize, droppable: bool) -> Result<Self> {
+        Self::create_in(backend(), os_id, size, droppable)
+    }
+
+    fn create_in(backend: &Backend, os_id: String, size: usize, droppable: bool) -> Result<Self> {
+        if size == 0 {
+            return Err(Error::EmptySharedMemory);
+        }
+        let inner = match backend {
+            Backend::SharedMemory => Inner::SharedMemory(
+                ShmemConf::new(droppable)
+                    .os_id(&os_id)
+                    .size(size)
+                    .create()?,
+            ),
+            Backend::Mmap { .. } => Inner::Mmap(MmapFile::create(&os_id, size, droppable)?),
+            Backend::Heap => Inner::Heap(HeapMemory::new(size)?),
+        };
+        Ok(Self { id: os_id, inner })
+    }
+
+    /// Opens existing memory with the identifier `os_id`.
+    ///
+    /// File-backed memory is recognized by its absolute path, so it can be opened by processes
+    /// using a different backend.
+    pub(crate) fn open(os_id: &str, droppable: bool) -> Result<Self> {
+        Self::open_in(backend(), os_id, droppable)
+    }
+
+    fn open_in(backend: &Backend, os_id: &str, droppable: bool) -> Result<Self> {
+        let inner = if Path::new(os_id).is_absolute() {
+            Inner::Mmap(MmapFile::open(os_id)?)
+        } else if matches!(backend, Backend::Heap) {
+            return Err(Error::Memory(format!(
+                "Segment {os_id} is stored on the heap and can't be opened"
+            )));
+        } else {
+            Inner::SharedMemory(ShmemConf::new(droppable).os_id(os_id).open()?)
+        };
+        Ok(Self {
+            id: os_id.to_string(),
+            inner,
+        })
+    }
+
+    /// The identifier used to [open](Self::open) the memory.
+    pub fn get_os_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        match &self.inner {
+            Inner::SharedMemory(shmem) => shmem.as_ptr(),
+            Inner::Mmap(file) => file.map.as_ptr() as *mut u8,
+            Inner::Heap(heap) => heap.ptr.as_ptr(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            Inner::SharedMemory(shmem) => shmem.len(),
+            Inner::Mmap(file) => file.map.len(),
+            Inner::Heap(heap) => heap.layout.size(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the memory was created by this handle and is freed when it's dropped.
+    pub fn is_owner(&self) -> bool {
+        match &self.inner {
+            Inner::SharedMemory(shmem) => shmem.is_owner(),
+            Inner::Mmap(file) => file.is_owner,
+            Inner::Heap(_) => true,
+        }
+    }
+
+    /// Resizes the memory, keeping its contents. New bytes are zeroed.
+    ///
+    /// # Errors
+    ///
+    /// - if `new_size` is zero
+    /// - if the memory can't be resized by the backend
+    pub(crate) fn resize(&mut self, new_size: usize) -> Result<()> {
+        if new_size == 0 {
+            return Err(Error::EmptySharedMemory);
+        }
+        match &mut self.inner {
+            Inner::SharedMemory(shmem) => shmem.resize(new_size)?,
+            Inner::Mmap(file) => file.resize(new_size)?,
+            Inner::Heap(heap) => heap.resize(new_size)?,
+        }
+        Ok(())
+    }
+
+    /// Maps the memory again after it was resized through another handle.
+    pub(crate) fn reload(&mut self) -> Result<()> {
+        match &mut self.inner {
+            Inner::SharedMemory(shmem) => shmem.reload()?,
+            Inner::Mmap(file) => file.reload()?,
+            // Heap memory can't be shared, so it can only be resized through this handle
+            Inner::Heap(_) => {}
+        }
+        Ok(())
+    }
+}
+
+struct MmapFile {
+    path: PathBuf,
+    file: File,
+    map: MmapMut,
+    is_owner: bool,
+}
+
+impl MmapFile {
+    fn create(path: &str, size: usize, is_owner: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        file.set_len(size as u64)?;
+        Self::map(PathBuf::from(path), file, is_owner)
+    }
+
+    fn open(path: &str) -> Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Self::map(PathBuf::from(path), file, false)
+    }
+
+    fn map(path: PathBuf, file: File, is_owner: bool) -> Result<Self> {
+        // SAFETY: The file is only modified through segments, which (like POSIX shared memory)
+        //         synchronize resizing with the metaversion stored in the segment.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self {
+            path,
+            file,
+            map,
+            is_owner,
+        })
+    }
+
+    fn resize(&mut self, new_size: usize) -> Result<()> {
+        self.file.set_len(new_size as u64)?;
+        self.reload()
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        // SAFETY: See `MmapFile::map`
+        self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        Ok(())
+    }
+}
+
+impl Drop for MmapFile {
+    fn drop(&mut self) {
+        if self.is_owner {
+            if let Err(err) = fs::remove_file(&self.path) {
+                tracing::warn!("Could not remove memory file {:?}: {err}", self.path);
+            }
+        }
+    }
+}
+
+/// Zeroed heap memory, aligned like the Arrow buffers stored in it.
+struct HeapMemory {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// SAFETY: `HeapMemory` exclusively owns its allocation, like a `Vec<u8>`.
+unsafe impl Send for HeapMemory {}
+// SAFETY: `HeapMemory` exclusively owns its allocation, like a `Vec<u8>`.
+unsafe impl Sync for HeapMemory {}
+
+impl HeapMemory {
+    fn layout(size: usize) -> Result<Layout> {
+        Layout::from_size_align(size, ALIGNMENT)
+            .map_err(|err| Error::Memory(format!("Invalid heap memory size {size}: {err}")))
+    }
+
+    fn new(size: usize) -> Result<Self> {
+        let layout = Self::layout(size)?;
+        // SAFETY: Segments are never empty, so `layout` has a non-zero size
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Ok(Self { ptr, layout })
+    }
+
+    fn resize(&mut self, new_size: usize) -> Result<()> {
+        let new_layout = Self::layout(new_size)?;
+        // SAFETY: `ptr` was allocated with `layout` and `new_size` is non-zero
+        let ptr = unsafe { alloc::realloc(self.ptr.as_ptr(), self.layout, new_size) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+        if new_size > self.layout.size() {
+            // SAFETY: The bytes after the old size are part of the new allocation
+            unsafe {
+                ptr.as_ptr()
+                    .add(self.layout.size())
+                    .write_bytes(0, new_size - self.layout.size())
+            };
+        }
+        self.ptr = ptr;
+        self.layout = new_layout;
+        Ok(())
+    }
+}
+
+impl Drop for HeapMemory {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` was allocated with `layout`
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Returns a backend mapping the segments to files in `dir`.
+    fn mmap_backend(dir: &TempDir) -> Backend {
+        Backend::Mmap {
+            directory: dir.path().canonicalize().unwrap(),
+        }
+    }
+
+    fn bytes(memory: &SegmentMemory) -> &[u8] {
+        // SAFETY: The memory is valid for `len` bytes as long as `memory` is not resized
+        unsafe { std::slice::from_raw_parts(memory.as_ptr(), memory.len()) }
+    }
+
+    fn bytes_mut(memory: &mut SegmentMemory) -> &mut [u8] {
+        // SAFETY: See `bytes`
+        unsafe { std::slice::from_raw_parts_mut(memory.as_ptr(), memory.len()) }
+    }
+
+    /// Creates memory in `backend`, writes to it, grows and shrinks it and checks its contents.
+    fn create_resize_read_back(backend: &Backend) {
+        let os_id = backend.os_id("segment");
+        let mut memory = SegmentMemory::create_in(backend, os_id.clone(), 100, true).unwrap();
+        assert_eq!(memory.get_os_id(), os_id);
+        assert_eq!(memory.len(), 100);
+        assert!(memory.is_owner());
+        assert!(bytes(&memory).iter().all(|&byte| byte == 0));
+        assert_eq!(memory.as_ptr() as usize % ALIGNMENT, 0);
+
+        bytes_mut(&mut memory).fill(7);
+
+        memory.resize(300).unwrap();
+        assert_eq!(memory.len(), 300);
+        assert!(bytes(&memory)[..100].iter().all(|&byte| byte == 7));
+        assert!(bytes(&memory)[100..].iter().all(|&byte| byte == 0));
+
+        memory.resize(50).unwrap();
+        assert_eq!(memory.len(), 50);
+        assert!(bytes(&memory).iter().all(|&byte| byte == 7));
+
+        // Growing after shrinking zeroes the truncated bytes
+        memory.resize(100).unwrap();
+        assert!(bytes(&memory)[..50].iter().all(|&byte| byte == 7));
+        assert!(bytes(&memory)[50..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn heap_memory() {
+        create_resize_read_back(&Backend::Heap);
+    }
+
+    #[test]
+    fn mmap_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        create_resize_read_back(&mmap_backend(&dir));
+        assert!(!dir.path().join("segment").exists());
+    }
+
+    #[test]
+    fn mmap_memory_is_shared() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = mmap_backend(&dir);
+        let os_id = backend.os_id("segment");
+
+        let mut owner = SegmentMemory::create_in(&backend, os_id.clone(), 100, true).unwrap();
+        assert_eq!(fs::metadata(&os_id).unwrap().len(), 100);
+        bytes_mut(&mut owner).fill(3);
+
+        // File-backed memory can be opened independently of the backend
+        let mut other = SegmentMemory::open_in(&Backend::Heap, &os_id, false).unwrap();
+        assert!(!other.is_owner());
+        assert_eq!(bytes(&other), bytes(&owner));
+
+        owner.resize(200).unwrap();
+        other.reload().unwrap();
+        assert_eq!(other.len(), 200);
+        assert!(bytes(&other)[..100].iter().all(|&byte| byte == 3));
+
+        // Only the owner removes the file
+        drop(other);
+        assert!(Path::new(&os_id).exists());
+        drop(owner);
+        assert!(!Path::new(&os_id).exists());
+    }
+
+    #[test]
+    fn zero_size() {
+        let dir = tempfile::tempdir().unwrap();
+        for backend in [Backend::Heap, mmap_backend(&dir)] {
+            let os_id = backend.os_id("empty");
+            assert!(matches!(
+                SegmentMemory::create_in(&backend, os_id.clone(), 0, true),
+                Err(Error::EmptySharedMemory)
+            ));
+
+            let mut memory = SegmentMemory::create_in(&backend, os_id, 10, true).unwrap();
+            assert!(matches!(memory.resize(0), Err(Error::EmptySharedMemory)));
+            assert_eq!(memory.len(), 10);
+        }
+    }
+
+    #[test]
+    fn heap_memory_can_not_be_opened() {
+        let memory =
+            SegmentMemory::create_in(&Backend::Heap, "segment".to_string(), 10, true).unwrap();
+        assert!(matches!(
+            SegmentMemory::open_in(&Backend::Heap, memory.get_os_id(), false),
+            Err(Error::Memory(_))
+        ));
+    }
+}
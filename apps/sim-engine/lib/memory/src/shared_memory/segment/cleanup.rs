@@ -11,8 +11,21 @@ use uuid::Uuid;
 /// leftover shared memory segments (in release builds; we error in debug builds).
 pub static IN_USE_SHM_SEGMENTS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Mutex::default);
 
+use super::backend::backend;
 use crate::{shared_memory::MemoryId, Error, Result};
 
+/// Returns `true` if `name` is the name of a segment created with the base `id`, or of any segment
+/// if `id` is `None`.
+///
+/// This is used to find the files of segments which were left behind by processes which didn't
+/// exit cleanly, e.g. in */dev/shm*.
+pub fn is_segment_name(name: &str, id: Option<Uuid>) -> bool {
+    match id {
+        Some(id) => name.starts_with(&format!("{}_", MemoryId::prefix(id))),
+        None => name.starts_with(MemoryId::PREFIX),
+    }
+}
+
 /// Clean up generated shared memory segments associated with a given `MemoryId`.
 ///
 /// If debug assertions are enabled, this function will panic if there are any shared-memory
@@ -70,17 +83,19 @@ pub fn cleanup_by_base_id(id: Uuid) -> Result<()> {
     );
 
     #[cfg(debug_assertions)]
-    check_all_deallocated_linux(id)?;
+    check_all_deallocated(id)?;
 
     Ok(())
 }
 
-/// On Linux we can obtain a list of all the shared memory segments in use, so we can perform some
-/// additional cleanup (this function is mostly useful for testing the implementation of the cleanup
-/// code which uses [`static@IN_USE_SHM_SEGMENTS`]).
-fn check_all_deallocated_linux(id: Uuid) -> Result<()> {
-    if cfg!(target_os = "linux") {
-        let shm_files = glob::glob(&format!("/dev/shm/{}_*", MemoryId::prefix(id)))
+/// If the segments are stored as files (on Linux, this includes POSIX shared memory), we can obtain
+/// a list of all the segments in use, so we can perform some additional cleanup (this function is
+/// mostly useful for testing the implementation of the cleanup code which uses
+/// [`static@IN_USE_SHM_SEGMENTS`]).
+fn check_all_deallocated(id: Uuid) -> Result<()> {
+    if let Some(directory) = backend().directory() {
+        let pattern = directory.join(format!("{}_*", MemoryId::prefix(id)));
+        let shm_files = glob::glob(&pattern.to_string_lossy())
             .map_err(|e| Error::Unique(format!("cleanup glob error: {}", e)))?;
 
         let mut not_deallocated: Vec<String> = Vec::new();
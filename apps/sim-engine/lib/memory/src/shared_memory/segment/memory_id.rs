@@ -1,7 +1,9 @@
-use std::{fmt, path::Path};
+use std::fmt;
 
 use uuid::Uuid;
 
+use super::cleanup::IN_USE_SHM_SEGMENTS;
+
 /// An identifier for a shared memory [`super::Segment`].
 ///
 /// Holds a UUID and a random suffix. The UUID can be reused for different [`super::Segment`]s and
@@ -13,17 +15,28 @@ pub struct MemoryId {
 }
 
 impl MemoryId {
+    /// The start of the name of every segment.
+    pub(crate) const PREFIX: &'static str = "shm_";
+
     /// Creates a new identifier from the provided [`Uuid`].
     ///
-    /// This will generate a suffix and ensures, that the shared memory segment does not already
-    /// exists at */dev/shm/*.
+    /// This will generate a suffix and ensures, that the segment does not already exist in the
+    /// [`MemoryBackend`](super::MemoryBackend), e.g. at */dev/shm/*.
     pub fn new(id: Uuid) -> Self {
+        let backend = super::backend::backend();
         loop {
             let memory_id = Self {
                 id,
                 suffix: rand::random::<u16>(),
             };
-            if !Path::new(&format!("/dev/shm/{memory_id}")).exists() {
+            let exists = match backend.directory() {
+                Some(directory) => directory.join(memory_id.to_string()).exists(),
+                None => IN_USE_SHM_SEGMENTS
+                    .lock()
+                    .unwrap()
+                    .contains(&backend.os_id(&memory_id.to_string())),
+            };
+            if !exists {
                 return memory_id;
             }
         }
@@ -40,10 +53,10 @@ impl MemoryId {
             // We need to_string otherwise it's not truncated when formatting
             let id = id.as_simple().to_string();
             // MacOS shmem seems to be limited to 31 chars, probably remnants of HFS
-            format!("shm_{id:.20}")
+            format!("{}{id:.20}", Self::PREFIX)
         } else {
             let id = id.as_simple();
-            format!("shm_{id}")
+            format!("{}{id}", Self::PREFIX)
         }
     }
 }
@@ -0,0 +1,164 @@
+//! Lock files marking the segments of an experiment as owned by a running engine.
+//!
+//! Whether the engine which created a segment is still running can't be determined from its pid
+//! or from */proc*, if the segment directory is shared between containers, e.g. */dev/shm*.
+//! Instead, the engine holds an exclusive `flock` on the lock file of its experiment in the
+//! segment directory for as long as it runs. The operating system releases the lock when the
+//! process exits, even if it crashed, so the owner of an unlocked lock file is provably dead.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+use uuid::Uuid;
+
+use super::backend::backend;
+use crate::{Error, Result};
+
+const OWNER_LOCK_PREFIX: &str = "hash-engine-";
+const OWNER_LOCK_SUFFIX: &str = ".lock";
+
+/// Returns the name of the lock file of the segments created with the base `id`.
+fn owner_lock_name(id: Uuid) -> String {
+    format!("{OWNER_LOCK_PREFIX}{}{OWNER_LOCK_SUFFIX}", id.as_simple())
+}
+
+/// Returns the base id of the segments owned by the lock file `name`, or `None` if `name` is not
+/// the name of a lock file.
+pub fn owner_lock_id(name: &str) -> Option<Uuid> {
+    name.strip_prefix(OWNER_LOCK_PREFIX)?
+        .strip_suffix(OWNER_LOCK_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Tries to lock `file` exclusively without blocking and returns `false` if it's locked already.
+fn try_lock(file: &File) -> io::Result<bool> {
+    // SAFETY: The file descriptor is valid as long as `file` is alive.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+/// Marks the segments created with a base id as owned by this process, until it's dropped.
+///
+/// The lock file contains the pid of the owner, which is only informational.
+#[derive(Debug)]
+pub struct OwnerLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl OwnerLock {
+    /// Locks the segments created with the base `id` in the directory of the
+    /// [`MemoryBackend`](super::MemoryBackend) of this process.
+    ///
+    /// Returns `None` if the segments are not stored as files, so they can't be left behind.
+    ///
+    /// # Errors
+    ///
+    /// - if the lock file can't be created
+    /// - if the segments are already owned by another process
+    pub fn acquire(id: Uuid) -> Result<Option<Self>> {
+        backend()
+            .directory()
+            .map(|directory| Self::acquire_in(directory, id))
+            .transpose()
+    }
+
+    fn acquire_in(directory: &Path, id: Uuid) -> Result<Self> {
+        let path = directory.join(owner_lock_name(id));
+        let mut file = OpenOptions::new().write(true).create(true).open(&path)?;
+        if !try_lock(&file)? {
+            return Err(Error::from(format!(
+                "The segments of {id} are already owned by another process"
+            )));
+        }
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { path, _file: file })
+    }
+}
+
+impl Drop for OwnerLock {
+    fn drop(&mut self) {
+        // The lock itself is released when the file is closed
+        if let Err(err) = fs::remove_file(&self.path) {
+            tracing::warn!("Could not remove lock file {:?}: {err}", self.path);
+        }
+    }
+}
+
+/// Whether the owner of the segments created with a base id is still running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnerStatus {
+    /// The lock file is locked by a running process.
+    Running,
+    /// The lock file exists, but its owner has exited.
+    Exited,
+    /// There is no lock file or it can't be read, so the owner may still be running.
+    Unknown,
+}
+
+/// Returns whether the owner of the lock file at `path` is still running.
+///
+/// The lock is only taken for as long as this function runs.
+pub fn owner_status(path: &Path) -> OwnerStatus {
+    match File::open(path).and_then(|file| try_lock(&file)) {
+        Ok(true) => OwnerStatus::Exited,
+        Ok(false) => OwnerStatus::Running,
+        Err(_) => OwnerStatus::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_names() {
+        let id = Uuid::new_v4();
+        assert_eq!(owner_lock_id(&owner_lock_name(id)), Some(id));
+        assert_eq!(owner_lock_id(&format!("shm_{}", id.as_simple())), None);
+        assert_eq!(owner_lock_id("hash-engine-not-a-uuid.lock"), None);
+    }
+
+    #[test]
+    fn status_follows_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let path = dir.path().join(owner_lock_name(id));
+        assert_eq!(owner_status(&path), OwnerStatus::Unknown);
+
+        let lock = OwnerLock::acquire_in(dir.path(), id).unwrap();
+        assert_eq!(owner_status(&path), OwnerStatus::Running);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        assert!(OwnerLock::acquire_in(dir.path(), id).is_err());
+
+        drop(lock);
+        assert!(!path.exists());
+        assert_eq!(owner_status(&path), OwnerStatus::Unknown);
+    }
+
+    #[test]
+    fn unlocked_file_has_exited_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(owner_lock_name(Uuid::new_v4()));
+
+        // A crashed owner leaves its lock file behind, but the lock is released
+        fs::write(&path, "1").unwrap();
+        assert_eq!(owner_status(&path), OwnerStatus::Exited);
+    }
+}
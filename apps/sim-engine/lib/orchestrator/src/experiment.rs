@@ -2,21 +2,38 @@
 //!
 //! [`Process`]: crate::process::Process
 
-use std::{path::PathBuf, time::Duration};
+use std::{collections::HashSet, path::PathBuf, time::Duration};
 
-use error_stack::{bail, ensure, IntoReport, ResultExt};
-use execution::package::{
-    experiment::ExperimentId, simulation::output::persistence::local::LocalPersistenceConfig,
+use error_stack::{bail, ensure, IntoReport, Report, ResultExt};
+use execution::{
+    package::{
+        experiment::ExperimentId,
+        simulation::output::{
+            json_state::JsonStateFormat,
+            persistence::{local::LocalPersistenceConfig, s3::S3PersistenceConfig},
+        },
+        simulation::SimulationId,
+    },
+    runner::comms::UserError,
 };
 use experiment_control::{
     comms::{EngineMsg, InitMessage},
-    controller::config::{OutputPersistenceConfig, OUTPUT_PERSISTENCE_KEY},
+    controller::config::{OutputPersistenceConfig, CHECKPOINT_KEY, OUTPUT_PERSISTENCE_KEY},
     environment::{ExecutionEnvironment, LogFormat, LogLevel, OutputLocation},
 };
 use experiment_structure::ExperimentRun;
+use memory::shared_memory::MemoryBackend;
 use serde_json::json;
-use simulation_control::{command::StopStatus, EngineStatus};
-use tokio::time::{sleep, timeout};
+use simulation_control::{
+    checkpoint::CheckpointConfig, command::StopStatus, controller::SimControl, EngineStatus,
+};
+use tokio::{
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        Mutex,
+    },
+    time::{sleep, timeout},
+};
 
 use crate::{experiment_server::Handler, process, OrchestratorError, Result};
 
@@ -41,6 +58,66 @@ pub struct ExperimentConfig {
     )]
     pub output_folder: PathBuf,
 
+    /// Format the agent state of every step is written in.
+    ///
+    /// `json` writes a single `json_state.json`. `arrow-ipc` and `parquet` write one file per
+    /// step into a `json_state` directory, together with an `index.json` listing the steps.
+    #[cfg_attr(
+        feature = "clap",
+        clap(
+            global = true,
+            long,
+            default_value = "json",
+            env = "HASH_OUTPUT_FORMAT"
+        )
+    )]
+    pub output_format: JsonStateFormat,
+
+    /// Uploads the output to an S3-compatible object store instead of writing it to the output
+    /// folder, e.g. `s3://bucket/prefix`.
+    ///
+    /// The JSON state is uploaded in parts while the simulation is running. Credentials and the
+    /// region are read from the environment, e.g. `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`
+    /// and `AWS_REGION`.
+    #[cfg_attr(feature = "clap", clap(global = true, long, env = "HASH_OUTPUT_S3"))]
+    pub output_s3: Option<String>,
+
+    /// Custom endpoint of the S3-compatible object store, e.g. `http://localhost:9000` for a
+    /// local MinIO instance. Only used together with `--output-s3`.
+    #[cfg_attr(
+        feature = "clap",
+        clap(global = true, long, env = "HASH_OUTPUT_S3_ENDPOINT")
+    )]
+    pub output_s3_endpoint: Option<String>,
+
+    /// Writes a checkpoint of every simulation run every `N` steps.
+    ///
+    /// A simulation run can be continued from a checkpoint with the `resume` experiment type.
+    #[cfg_attr(
+        feature = "clap",
+        clap(global = true, long, validator = at_least_one, env = "HASH_CHECKPOINT_INTERVAL")
+    )]
+    pub checkpoint_interval: Option<usize>,
+
+    /// Folder the checkpoints are written to.
+    ///
+    /// Defaults to `checkpoints` inside of the output folder. Only used together with
+    /// `--checkpoint-interval`.
+    #[cfg_attr(
+        feature = "clap",
+        clap(global = true, long, env = "HASH_CHECKPOINT_FOLDER")
+    )]
+    pub checkpoint_folder: Option<PathBuf>,
+
+    /// Seeds the random number generators of the experiment, so runs with the same inputs produce
+    /// the same outputs.
+    ///
+    /// Overrides the `seed` specified in _experiments.json_. Every simulation run, agent, and
+    /// behavior derives its own stream from the seed, which is also used by `hash_stdlib.random`
+    /// in JavaScript and Python behaviors.
+    #[cfg_attr(feature = "clap", clap(global = true, long, env = "HASH_SEED"))]
+    pub seed: Option<u64>,
+
     /// Logging output format to be emitted
     #[cfg_attr(
         feature = "clap",
@@ -118,6 +195,92 @@ pub struct ExperimentConfig {
     /// Defaults to V8's `max_heap_size` default.
     #[cfg_attr(feature = "clap", clap(global = true, long))]
     pub js_runner_max_heap_size: Option<usize>,
+
+    /// Directory containing the Python runner.
+    ///
+    /// Only needed if the repository the engine was built from is not available, e.g. when the CLI
+    /// is installed as a standalone artifact.
+    #[cfg_attr(
+        feature = "clap",
+        clap(global = true, long, env = "HASH_PYTHON_RUNNER_DIR")
+    )]
+    pub python_runner_dir: Option<PathBuf>,
+
+    /// Where the memory of agent batches, messages and datasets is stored.
+    ///
+    /// `shm` uses POSIX shared memory in `/dev/shm`. `mmap` uses memory-mapped files in
+    /// "--memory-dir", which helps when `/dev/shm` is small, e.g. inside of containers. `heap`
+    /// allocates the memory inside of the engine process, which can't be used with Python
+    /// behaviors.
+    #[cfg_attr(
+        feature = "clap",
+        clap(
+            global = true,
+            long,
+            default_value = "shm",
+            env = "HASH_MEMORY_BACKEND"
+        )
+    )]
+    pub memory_backend: MemoryBackend,
+
+    /// Directory containing the files of the `mmap` memory backend.
+    ///
+    /// Defaults to a `hash-engine` directory inside of the temporary directory.
+    #[cfg_attr(feature = "clap", clap(global = true, long, env = "HASH_MEMORY_DIR"))]
+    pub memory_dir: Option<PathBuf>,
+}
+
+impl ExperimentConfig {
+    /// Returns where the engine should persist the output of the experiment.
+    fn output_persistence(&self) -> Result<OutputPersistenceConfig> {
+        let s3_url = match &self.output_s3 {
+            Some(s3_url) => s3_url,
+            None => {
+                return Ok(OutputPersistenceConfig::Local(LocalPersistenceConfig {
+                    output_folder: self.output_folder.clone(),
+                    json_state_format: self.output_format,
+                }));
+            }
+        };
+
+        ensure!(
+            self.output_format == JsonStateFormat::Json,
+            OrchestratorError::from(format!(
+                "Output format `{}` is not supported for S3 output",
+                self.output_format
+            ))
+        );
+
+        let (bucket, prefix) = s3_url
+            .strip_prefix("s3://")
+            .map(|path| path.split_once('/').unwrap_or((path, "")))
+            .filter(|(bucket, _)| !bucket.is_empty())
+            .ok_or_else(|| {
+                OrchestratorError::from(format!(
+                    "Invalid S3 output location `{s3_url}`, expected `s3://<bucket>/<prefix>`"
+                ))
+            })?;
+
+        Ok(OutputPersistenceConfig::S3(S3PersistenceConfig {
+            bucket: bucket.to_string(),
+            prefix: (!prefix.is_empty()).then(|| prefix.to_string()),
+            region: None,
+            endpoint: self.output_s3_endpoint.clone(),
+            // Custom endpoints like MinIO usually don't support virtual-hosted-style addressing
+            force_path_style: self.output_s3_endpoint.is_some(),
+        }))
+    }
+
+    /// Returns where and how often the engine should write checkpoints, if enabled.
+    fn checkpoint(&self) -> Option<CheckpointConfig> {
+        self.checkpoint_interval.map(|interval| CheckpointConfig {
+            folder: self
+                .checkpoint_folder
+                .clone()
+                .unwrap_or_else(|| self.output_folder.join("checkpoints")),
+            interval,
+        })
+    }
 }
 
 #[cfg(feature = "clap")]
@@ -134,6 +297,10 @@ fn at_least_one(v: &str) -> core::result::Result<(), String> {
 pub struct Experiment {
     /// Configuration for the experiment.
     pub config: ExperimentConfig,
+    /// Control messages for simulation runs, which are forwarded to the engine while the
+    /// experiment is running.
+    sim_control_send: UnboundedSender<(SimulationId, SimControl)>,
+    sim_control_recv: Mutex<UnboundedReceiver<(SimulationId, SimControl)>>,
 }
 
 impl Experiment {
@@ -141,7 +308,88 @@ impl Experiment {
     pub fn new(mut config: ExperimentConfig) -> Self {
         // TODO: Remove when multiple workers are fixed
         config.num_workers = 1;
-        Self { config }
+        let (sim_control_send, sim_control_recv) = unbounded_channel();
+        Self {
+            config,
+            sim_control_send,
+            sim_control_recv: Mutex::new(sim_control_recv),
+        }
+    }
+
+    /// Pauses the simulation run with the given `sim_id` of the running experiment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the control message could not be queued.
+    pub fn pause_simulation(&self, sim_id: SimulationId) -> Result<()> {
+        self.send_sim_control(sim_id, SimControl::Pause)
+    }
+
+    /// Resumes the paused simulation run with the given `sim_id` of the running experiment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the control message could not be queued.
+    pub fn resume_simulation(&self, sim_id: SimulationId) -> Result<()> {
+        self.send_sim_control(sim_id, SimControl::Resume)
+    }
+
+    /// Stops the simulation run with the given `sim_id` of the running experiment.
+    ///
+    /// The simulation run finishes gracefully, i.e. its output is persisted and its resources are
+    /// cleaned up.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the control message could not be queued.
+    pub fn stop_simulation(&self, sim_id: SimulationId) -> Result<()> {
+        self.send_sim_control(sim_id, SimControl::Stop)
+    }
+
+    /// Takes a single step in the paused simulation run with the given `sim_id` of the running
+    /// experiment. The simulation run stays paused afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the control message could not be queued.
+    pub fn step_simulation(&self, sim_id: SimulationId) -> Result<()> {
+        self.send_sim_control(sim_id, SimControl::Step)
+    }
+
+    /// Changes the globals of the running simulation run with the given `sim_id`.
+    ///
+    /// `patch` is applied to the current globals with JSON merge-patch semantics, i.e. objects are
+    /// merged recursively and `null` removes a property. The new globals are used from the next
+    /// step onwards and the change is recorded in the output of the simulation run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `patch` is not an object or if the control message could not be
+    /// queued.
+    pub fn update_simulation_globals(
+        &self,
+        sim_id: SimulationId,
+        patch: serde_json::Value,
+    ) -> Result<()> {
+        ensure!(
+            patch.is_object(),
+            OrchestratorError::from(format!(
+                "Globals update for simulation run {sim_id} has to be an object, but got {patch}"
+            ))
+        );
+        self.send_sim_control(sim_id, SimControl::UpdateGlobals(patch))
+    }
+
+    fn send_sim_control(&self, sim_id: SimulationId, control: SimControl) -> Result<()> {
+        let description = format!("{control:?}");
+        self.sim_control_send
+            .send((sim_id, control))
+            .into_report()
+            .change_context_lazy(|| {
+                OrchestratorError::from(format!(
+                    "Could not send {description} to simulation run {sim_id}"
+                ))
+            })
     }
 
     /// Creates a [`Command`] from the experiment's configuration, the given `experiment_id`, and
@@ -167,6 +415,9 @@ impl Experiment {
             target_max_group_size,
             js_runner_initial_heap_constraint,
             js_runner_max_heap_size,
+            self.config.python_runner_dir.clone(),
+            self.config.memory_backend,
+            self.config.memory_dir.clone(),
         ))
     }
 
@@ -174,8 +425,14 @@ impl Experiment {
     ///
     /// The `experiment_run` is registered at the server with the provided `handler`, and started
     /// using [`Process`]. After startup it listens to the messages sent from `hash_engine` and
-    /// returns once the experiment has finished.
+    /// returns once the experiment has finished. While running, the simulation runs can be
+    /// controlled with [`pause_simulation`], [`resume_simulation`], [`stop_simulation`], and
+    /// [`step_simulation`].
     ///
+    /// [`pause_simulation`]: Self::pause_simulation
+    /// [`resume_simulation`]: Self::resume_simulation
+    /// [`stop_simulation`]: Self::stop_simulation
+    /// [`step_simulation`]: Self::step_simulation
     /// [`Process`]: crate::process::Process
     #[instrument(skip_all, fields(experiment_name = %experiment_run.name(), experiment_id = %experiment_run.id()))]
     pub async fn run(
@@ -235,17 +492,18 @@ impl Experiment {
         };
         debug!("Received start message from \"{experiment_name}\"");
 
-        let map_iter = [(
+        let mut dyn_payloads = serde_json::Map::from_iter([(
             OUTPUT_PERSISTENCE_KEY.to_string(),
-            json!(OutputPersistenceConfig::Local(LocalPersistenceConfig {
-                output_folder: self.config.output_folder.clone()
-            })),
-        )];
+            json!(self.config.output_persistence()?),
+        )]);
+        if let Some(checkpoint) = self.config.checkpoint() {
+            dyn_payloads.insert(CHECKPOINT_KEY.to_string(), json!(checkpoint));
+        }
         // Now we can send the init message
         let init_message = InitMessage {
             experiment: experiment_run.clone(),
             env: ExecutionEnvironment::None, // We don't connect to the API
-            dyn_payloads: serde_json::Map::from_iter(map_iter),
+            dyn_payloads,
         };
         if let Err(err) = engine_process
             .send(&EngineMsg::Init(init_message))
@@ -267,11 +525,31 @@ impl Experiment {
         }
         debug!("Sent init message to \"{experiment_name}\"");
 
+        // Only one experiment run can be controlled at a time. Control messages sent before this
+        // run started are discarded.
+        let mut sim_control_recv = self.sim_control_recv.lock().await;
+        while sim_control_recv.try_recv().is_ok() {}
+        let mut paused_sims = PausedSimulations::default();
+
+        let wait_timeout = Duration::from_secs_f64(self.config.wait_timeout);
         let mut graceful_finish = true;
+        // The user errors are attached to the error returned if the engine doesn't exit gracefully
+        let mut user_errors = Vec::new();
         loop {
             let msg: Option<EngineStatus>;
             tokio::select! {
-                _ = sleep(Duration::from_secs_f64(self.config.wait_timeout)) => {
+                Some((sim_id, control)) = sim_control_recv.recv() => {
+                    debug!("Sending {control:?} to simulation run {sim_id}");
+                    paused_sims.apply(sim_id, &control);
+                    if let Err(err) = engine_process
+                        .send(&EngineMsg::SimControl { sim_id, control: control.clone() })
+                        .await
+                    {
+                        error!("Could not send {control:?} to simulation run {sim_id}: {err:?}");
+                    }
+                    continue;
+                }
+                _ = sleep(wait_timeout), if paused_sims.status_expected() => {
                     error!(
                         "Did not receive status from experiment \"{experiment_name}\" for over {}s. \
                         Exiting now.",
@@ -332,6 +610,7 @@ impl Experiment {
                     // TODO: OS - handle more status fields
                 }
                 EngineStatus::SimStop(sim_id) => {
+                    paused_sims.stopped(sim_id);
                     debug!("Simulation stopped: {sim_id}");
                 }
                 EngineStatus::RunnerErrors(sim_id, errs) => {
@@ -359,6 +638,15 @@ impl Experiment {
                         "There were user-facing errors when running simulation [{sim_id}]: \
                          {errs:?}"
                     );
+                    user_errors.extend(
+                        errs.into_iter()
+                            .map(|UserError(error)| format!("Simulation [{sim_id}]: {error}")),
+                    );
+                    // The task which failed is never completed, as tasks can't be cancelled, so
+                    // the simulation run can't finish. The engine is stopped right away instead of
+                    // waiting for the status timeout.
+                    graceful_finish = false;
+                    break;
                 }
                 EngineStatus::UserWarnings(sim_id, warnings) => {
                     warn!(
@@ -410,20 +698,92 @@ impl Experiment {
         match join_handle.await {
             Ok(inner) => inner?,
             Err(_) => {
-                return Err(error_stack::Report::new(OrchestratorError::from(
+                return Err(Report::new(OrchestratorError::from(
                     "error: cleanup task panicked (most likely because there were shared-memory \
                      segments which were not deallocated)",
                 )));
             }
         }
 
-        ensure!(
-            graceful_finish,
-            OrchestratorError::from("Engine didn't exit gracefully.")
-        );
+        if !graceful_finish {
+            let mut report = Report::new(OrchestratorError::from("Engine didn't exit gracefully."));
+            for user_error in user_errors {
+                report = report.attach_printable(user_error);
+            }
+            return Err(report);
+        }
 
         Ok(())
     }
 }
 
+/// Tracks the simulation runs, which were paused by a [`SimControl`] message.
+///
+/// Paused simulation runs don't send any status, so the status timeout is disabled while any
+/// simulation run is paused.
+#[derive(Debug, Default)]
+struct PausedSimulations {
+    paused: HashSet<SimulationId>,
+}
+
+impl PausedSimulations {
+    fn apply(&mut self, sim_id: SimulationId, control: &SimControl) {
+        match control {
+            SimControl::Pause => {
+                self.paused.insert(sim_id);
+            }
+            // A step keeps a paused simulation run paused, the engine ignores it otherwise
+            SimControl::Step | SimControl::UpdateGlobals(_) => {}
+            SimControl::Resume | SimControl::Stop => {
+                self.paused.remove(&sim_id);
+            }
+        }
+    }
+
+    fn stopped(&mut self, sim_id: SimulationId) {
+        self.paused.remove(&sim_id);
+    }
+
+    /// Returns if a status is expected from the engine, i.e. the status timeout applies.
+    fn status_expected(&self) -> bool {
+        self.paused.is_empty()
+    }
+}
+
 // TODO: cleanup section below
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_step_resume() {
+        let sim_id = SimulationId::new(1);
+        let mut paused_sims = PausedSimulations::default();
+        assert!(paused_sims.status_expected());
+
+        paused_sims.apply(sim_id, &SimControl::Pause);
+        assert!(!paused_sims.status_expected());
+        paused_sims.apply(sim_id, &SimControl::Step);
+        assert!(!paused_sims.status_expected());
+        paused_sims.apply(sim_id, &SimControl::Resume);
+        assert!(paused_sims.status_expected());
+
+        paused_sims.apply(sim_id, &SimControl::Pause);
+        paused_sims.stopped(sim_id);
+        assert!(paused_sims.status_expected());
+    }
+
+    #[test]
+    fn stray_step_keeps_timeout() {
+        let mut paused_sims = PausedSimulations::default();
+        paused_sims.apply(SimulationId::new(1), &SimControl::Step);
+        assert!(paused_sims.status_expected());
+
+        // Only the paused simulation run disables the timeout
+        paused_sims.apply(SimulationId::new(2), &SimControl::Pause);
+        paused_sims.apply(SimulationId::new(1), &SimControl::Step);
+        paused_sims.apply(SimulationId::new(2), &SimControl::Resume);
+        assert!(paused_sims.status_expected());
+    }
+}
@@ -12,6 +12,8 @@ pub mod error;
 mod experiment;
 mod experiment_server;
 pub mod process;
+pub mod reaper;
+pub mod test_suite;
 
 pub use self::{
     error::{OrchestratorError, Result},
@@ -12,6 +12,7 @@ use experiment_control::{
     controller::run::cleanup_experiment,
     environment::{LogFormat, LogLevel, OutputLocation},
 };
+use memory::shared_memory::MemoryBackend;
 
 use crate::{process, OrchestratorError, Result};
 
@@ -122,6 +123,9 @@ pub struct LocalCommand {
     target_max_group_size: Option<usize>,
     js_runner_initial_heap_constraint: Option<usize>,
     js_runner_max_heap_size: Option<usize>,
+    python_runner_dir: Option<PathBuf>,
+    memory_backend: MemoryBackend,
+    memory_dir: Option<PathBuf>,
 }
 
 impl LocalCommand {
@@ -138,6 +142,9 @@ impl LocalCommand {
         target_max_group_size: Option<usize>,
         js_runner_initial_heap_constraint: Option<usize>,
         js_runner_max_heap_size: Option<usize>,
+        python_runner_dir: Option<PathBuf>,
+        memory_backend: MemoryBackend,
+        memory_dir: Option<PathBuf>,
     ) -> Self {
         // The NNG URL that the engine process will listen on
         let engine_url = format!("ipc://run-{experiment_id}");
@@ -154,6 +161,9 @@ impl LocalCommand {
             target_max_group_size,
             js_runner_initial_heap_constraint,
             js_runner_max_heap_size,
+            python_runner_dir,
+            memory_backend,
+            memory_dir,
         }
     }
 }
@@ -190,6 +200,8 @@ impl process::Command for LocalCommand {
             .arg(self.output_location.to_string())
             .arg("--log-folder")
             .arg(self.log_folder)
+            .arg("--memory-backend")
+            .arg(self.memory_backend.to_string())
             .stdout(std::process::Stdio::inherit())
             .stderr(std::process::Stdio::inherit());
         if let Some(log_level) = self.log_level {
@@ -207,6 +219,12 @@ impl process::Command for LocalCommand {
             cmd.arg("--js-runner-max-heap-size")
                 .arg(js_runner_max_heap_size.to_string());
         }
+        if let Some(python_runner_dir) = &self.python_runner_dir {
+            cmd.arg("--python-runner-dir").arg(python_runner_dir);
+        }
+        if let Some(memory_dir) = &self.memory_dir {
+            cmd.arg("--memory-dir").arg(memory_dir);
+        }
         debug!("Running `{cmd:?}`");
 
         let child = cmd.spawn().into_report().change_context_lazy(|| {
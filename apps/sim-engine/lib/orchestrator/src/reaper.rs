@@ -0,0 +1,708 @@
+//! Removal of files left behind by experiments which didn't exit cleanly.
+//!
+//! When a `hash_engine` process crashes or is killed, its shared-memory segments (e.g. in
+//! */dev/shm*) and the NNG socket files of the engine and its Python runners are not removed. As
+//! these accumulate over time, [`reap`] finds and deletes them.
+//!
+//! Every engine holds the [`OwnerLock`] of its experiment in the segment directory while it's
+//! running. [`reap_orphans`] only removes the files of experiments whose lock was released, so it
+//! never touches files of engines in other containers sharing */dev/shm*. The `cleanup` command
+//! additionally removes files which are not in use according to */proc*.
+//!
+//! [`OwnerLock`]: memory::shared_memory::OwnerLock
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use error_stack::{IntoReport, ResultExt};
+use execution::package::experiment::ExperimentId;
+use memory::shared_memory::{
+    is_segment_name, owner_lock_id, owner_status, MemoryBackend, OwnerStatus,
+};
+use uuid::Uuid;
+
+use crate::{OrchestratorError, Result};
+
+/// Files younger than this are never removed only because they are not in use, as the process
+/// which created them may not have opened them yet.
+const MIN_ORPHAN_AGE: Duration = Duration::from_secs(10);
+
+/// Prefix of the socket the CLI listens on for messages from the engine.
+const ORCHESTRATOR_SOCKET_PREFIX: &str = "hash-orchestrator-";
+
+/// Selects the leftover files removed by [`reap`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct ReaperConfig {
+    /// Removes the segments and socket files of these experiments, even if they are still in use.
+    ///
+    /// If no experiment is passed, the files of all experiments are removed whose engine has
+    /// exited or which are not in use by any running process anymore.
+    #[cfg_attr(feature = "clap", clap(long = "experiment-id"))]
+    pub experiment_ids: Vec<ExperimentId>,
+
+    /// Only removes files which were last modified more than this many seconds ago.
+    ///
+    /// Whether a file is still in use can only be determined on Linux. On other systems, files
+    /// which don't belong to one of the passed experiments are only removed if they are older
+    /// than this.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub older_than: Option<f64>,
+
+    /// Lists the files which would be removed without removing them.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub dry_run: bool,
+}
+
+/// Removes the files left behind by experiments selected by `config` and returns their paths.
+///
+/// Segments are searched in */dev/shm* (on Linux) and in `memory_dir`, the directory of the
+/// [`Mmap`](MemoryBackend::Mmap) memory backend. Socket files are searched in the current
+/// directory, which is where the engine and the Python runners create them.
+///
+/// Files which can't be removed are skipped with a warning.
+///
+/// The files of experiments, whose engine is still running according to its lock file, are only
+/// removed if the experiment is passed explicitly. For experiments without a lock file, e.g.
+/// because it was already removed, */proc* is used to determine whether the files are still in
+/// use.
+///
+/// # Errors
+///
+/// - if one of the directories can't be read
+pub fn reap(config: &ReaperConfig, memory_dir: Option<PathBuf>) -> Result<Vec<PathBuf>> {
+    let in_use = FilesInUse::load();
+    let orphans =
+        if in_use.is_none() && config.experiment_ids.is_empty() && config.older_than.is_none() {
+            warn!(
+                "Can't determine which files are still in use on this system, only removing files \
+                 of engines which have exited. Pass experiment ids or a minimum age to remove all \
+                 leftover files"
+            );
+            Orphans::Exited
+        } else {
+            Orphans::NotInUse(in_use.as_ref())
+        };
+
+    let leftovers = leftovers(memory_dir)?;
+    let owners = owners(&leftovers);
+    Ok(remove_selected(
+        leftovers,
+        config,
+        &owners,
+        orphans,
+        SystemTime::now(),
+    ))
+}
+
+/// The status of the lock files found next to the segments by the experiment they belong to.
+type Owners = HashMap<Uuid, OwnerStatus>;
+
+fn owners(leftovers: &[Leftover]) -> Owners {
+    leftovers
+        .iter()
+        .filter_map(|leftover| match leftover.kind {
+            LeftoverKind::OwnerLock { experiment_id } => {
+                Some((experiment_id, owner_status(&leftover.path)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Selects the files removed if no experiment is passed explicitly.
+#[derive(Clone, Copy)]
+enum Orphans<'a> {
+    /// Only the files of experiments whose engine has provably exited.
+    Exited,
+    /// Also the files of experiments without a lock file, which are not in use by any process, or
+    /// all of them, if this can't be determined.
+    NotInUse(Option<&'a FilesInUse>),
+}
+
+/// Removes the `leftovers` selected by `config` and `orphans` and returns their paths.
+///
+/// The age of a file is determined relative to `now`.
+fn remove_selected(
+    leftovers: Vec<Leftover>,
+    config: &ReaperConfig,
+    owners: &Owners,
+    orphans: Orphans<'_>,
+    now: SystemTime,
+) -> Vec<PathBuf> {
+    let min_age = config.older_than.map(Duration::from_secs_f64);
+
+    let mut removed = Vec::new();
+    for leftover in leftovers {
+        let is_selected = if config.experiment_ids.is_empty() {
+            match (leftover.owner_status(owners), orphans) {
+                (OwnerStatus::Exited, _) => true,
+                (OwnerStatus::Running, _) | (OwnerStatus::Unknown, Orphans::Exited) => false,
+                (OwnerStatus::Unknown, Orphans::NotInUse(Some(in_use))) => {
+                    !in_use.contains(&leftover) && leftover.is_older_than(MIN_ORPHAN_AGE, now)
+                }
+                (OwnerStatus::Unknown, Orphans::NotInUse(None)) => true,
+            }
+        } else {
+            config
+                .experiment_ids
+                .iter()
+                .any(|experiment_id| leftover.belongs_to(experiment_id.as_uuid()))
+        };
+        if !is_selected || !min_age.map_or(true, |min_age| leftover.is_older_than(min_age, now)) {
+            continue;
+        }
+
+        if !config.dry_run {
+            if let Err(err) = fs::remove_file(&leftover.path) {
+                warn!("Could not remove {:?}: {err}", leftover.path);
+                continue;
+            }
+        }
+        debug!("Removed {:?}", leftover.path);
+        removed.push(leftover.path);
+    }
+
+    removed
+}
+
+/// Removes the files left behind by experiments whose engine has provably exited.
+///
+/// This is run when the orchestrator starts, so crashed runs don't fill up */dev/shm* over time.
+/// Unlike [`reap`], only the lock files are considered, as */proc* can't tell whether a file is in
+/// use by a process in another container. Errors are only logged.
+pub fn reap_orphans(memory_dir: Option<PathBuf>) {
+    let removed = leftovers(memory_dir).map(|leftovers| {
+        let owners = owners(&leftovers);
+        remove_selected(
+            leftovers,
+            &ReaperConfig::default(),
+            &owners,
+            Orphans::Exited,
+            SystemTime::now(),
+        )
+    });
+    match removed {
+        Ok(removed) if removed.is_empty() => {}
+        Ok(removed) => warn!(
+            "Removed {} files left behind by experiments which didn't exit cleanly: {removed:?}",
+            removed.len()
+        ),
+        Err(err) => warn!("Could not remove files left behind by previous experiments: {err:?}"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LeftoverKind {
+    Segment,
+    Socket { experiment_id: Option<ExperimentId> },
+    OwnerLock { experiment_id: Uuid },
+}
+
+/// A segment, socket, or lock file which may have been left behind by an experiment.
+#[derive(Debug)]
+struct Leftover {
+    path: PathBuf,
+    kind: LeftoverKind,
+}
+
+impl Leftover {
+    fn name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or_default()
+    }
+
+    fn belongs_to(&self, experiment_id: Uuid) -> bool {
+        match self.kind {
+            LeftoverKind::Segment => {
+                is_segment_name(&self.name().to_string_lossy(), Some(experiment_id))
+            }
+            LeftoverKind::Socket {
+                experiment_id: socket_experiment_id,
+            } => socket_experiment_id.map(ExperimentId::as_uuid) == Some(experiment_id),
+            LeftoverKind::OwnerLock {
+                experiment_id: lock_experiment_id,
+            } => lock_experiment_id == experiment_id,
+        }
+    }
+
+    /// Returns whether the engine, which created this file, is still running.
+    fn owner_status(&self, owners: &Owners) -> OwnerStatus {
+        owners
+            .iter()
+            .find(|(&experiment_id, _)| self.belongs_to(experiment_id))
+            .map_or(OwnerStatus::Unknown, |(_, &status)| status)
+    }
+
+    /// Returns `false` if the file was modified within `age` before `now` or the age can't be
+    /// determined.
+    fn is_older_than(&self, age: Duration, now: SystemTime) -> bool {
+        fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .map_or(false, |elapsed| elapsed >= age)
+    }
+}
+
+/// Returns the experiment of a socket created by the engine (`run-<id>`) or by one of its Python
+/// runners (`<id>-topy<n>` and `<id>-frompy<n>`), or `None` if `name` is not such a socket.
+fn socket_experiment_id(name: &str) -> Option<ExperimentId> {
+    if let Some(experiment_id) = name.strip_prefix("run-") {
+        return experiment_id.parse().ok();
+    }
+
+    // Hyphenated UUIDs have 36 characters
+    let (experiment_id, runner) = (name.get(..36)?, name.get(36..)?);
+    if runner.starts_with("-topy") || runner.starts_with("-frompy") {
+        experiment_id.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns the paths of the entries of `directory`, or nothing if `directory` doesn't exist.
+fn read_dir(directory: &Path) -> Result<Vec<PathBuf>> {
+    match fs::read_dir(directory) {
+        Ok(entries) => Ok(entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).into_report().change_context_lazy(|| {
+            OrchestratorError::from(format!("Could not read directory {directory:?}"))
+        }),
+    }
+}
+
+fn leftovers(memory_dir: Option<PathBuf>) -> Result<Vec<Leftover>> {
+    let mut memory_directories: Vec<PathBuf> = [
+        MemoryBackend::SharedMemory.segment_directory(None),
+        MemoryBackend::Mmap.segment_directory(memory_dir),
+    ]
+    .into_iter()
+    .flatten()
+    // Canonical paths are needed to compare them with the paths mapped by running processes
+    .filter_map(|directory| directory.canonicalize().ok())
+    .collect();
+    memory_directories.dedup();
+
+    let mut leftovers = Vec::new();
+    for directory in &memory_directories {
+        for path in read_dir(directory)? {
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            let kind = if is_segment_name(&name, None) {
+                LeftoverKind::Segment
+            } else if let Some(experiment_id) = owner_lock_id(&name) {
+                LeftoverKind::OwnerLock { experiment_id }
+            } else {
+                continue;
+            };
+            leftovers.push(Leftover { path, kind });
+        }
+    }
+
+    // NNG only uses socket files for IPC on Unix
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        let current_dir =
+            std::env::current_dir()
+                .into_report()
+                .change_context(OrchestratorError::from(
+                    "Could not determine the current directory",
+                ))?;
+        for path in read_dir(&current_dir)? {
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => continue,
+            };
+            let experiment_id = socket_experiment_id(&name);
+            if experiment_id.is_none() && !name.starts_with(ORCHESTRATOR_SOCKET_PREFIX) {
+                continue;
+            }
+            if fs::symlink_metadata(&path)
+                .map_or(false, |metadata| metadata.file_type().is_socket())
+            {
+                leftovers.push(Leftover {
+                    path,
+                    kind: LeftoverKind::Socket { experiment_id },
+                });
+            }
+        }
+    }
+
+    Ok(leftovers)
+}
+
+/// Files which are currently used by running processes.
+struct FilesInUse {
+    /// Paths of files which are memory-mapped by any process.
+    mapped: HashSet<PathBuf>,
+    /// File names of sockets which are bound by any process.
+    sockets: HashSet<OsString>,
+}
+
+impl FilesInUse {
+    /// Collects the files in use from */proc*, or returns `None` if this is not supported on this
+    /// system.
+    fn load() -> Option<Self> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+
+        let mut mapped = HashSet::new();
+        for process in fs::read_dir("/proc").ok()?.filter_map(|entry| entry.ok()) {
+            // Processes may exit in the meantime and other users' processes may not be readable
+            let maps = match fs::read_to_string(process.path().join("maps")) {
+                Ok(maps) => maps,
+                Err(_) => continue,
+            };
+            for line in maps.lines() {
+                // The path is the last column, if the mapping is backed by a file
+                if let Some(start) = line.find('/') {
+                    mapped.insert(PathBuf::from(line[start..].trim_end_matches(" (deleted)")));
+                }
+            }
+        }
+
+        // Sockets are listed with the path they were bound to, which is relative for NNG sockets
+        let unix_sockets = fs::read_to_string("/proc/net/unix").ok()?;
+        let sockets = unix_sockets
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().nth(7))
+            .filter_map(|path| Path::new(path).file_name())
+            .map(OsStr::to_os_string)
+            .collect();
+
+        Some(Self { mapped, sockets })
+    }
+
+    fn contains(&self, leftover: &Leftover) -> bool {
+        match leftover.kind {
+            LeftoverKind::Segment => self.mapped.contains(&leftover.path),
+            LeftoverKind::Socket { .. } => self.sockets.contains(leftover.name()),
+            // Lock files are never mapped, whether they are in use is determined by their lock
+            LeftoverKind::OwnerLock { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn socket_names() {
+        let experiment_id = ExperimentId::generate();
+
+        assert_eq!(
+            socket_experiment_id(&format!("run-{experiment_id}")),
+            Some(experiment_id)
+        );
+        assert_eq!(
+            socket_experiment_id(&format!("{experiment_id}-topy3")),
+            Some(experiment_id)
+        );
+        assert_eq!(
+            socket_experiment_id(&format!("{experiment_id}-frompy12")),
+            Some(experiment_id)
+        );
+        assert_eq!(socket_experiment_id(&experiment_id.to_string()), None);
+        assert_eq!(socket_experiment_id("run-not-a-uuid"), None);
+        assert_eq!(
+            socket_experiment_id("hash-orchestrator-1660000000000"),
+            None
+        );
+    }
+
+    /// Creates an empty file named `name` in `dir` and returns it as [`Leftover`] of `kind`.
+    fn create(dir: &TempDir, name: &str, kind: LeftoverKind) -> Leftover {
+        let path = dir.path().join(name);
+        fs::write(&path, []).unwrap();
+        Leftover { path, kind }
+    }
+
+    fn segment_name(experiment_id: ExperimentId, suffix: u16) -> String {
+        format!("shm_{}_{suffix}", experiment_id.as_uuid().as_simple())
+    }
+
+    struct Fixture {
+        dir: TempDir,
+        leftovers: Vec<Leftover>,
+        mapped: PathBuf,
+        unmapped: PathBuf,
+        bound: PathBuf,
+        unbound: PathBuf,
+        in_use: FilesInUse,
+    }
+
+    /// Creates a mapped and an unmapped segment of `experiment_id` and a bound and an unbound
+    /// socket of `experiment_id`.
+    fn fixture(experiment_id: ExperimentId) -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let leftovers = vec![
+            create(&dir, &segment_name(experiment_id, 1), LeftoverKind::Segment),
+            create(&dir, &segment_name(experiment_id, 2), LeftoverKind::Segment),
+            create(
+                &dir,
+                &format!("run-{experiment_id}"),
+                LeftoverKind::Socket {
+                    experiment_id: Some(experiment_id),
+                },
+            ),
+            create(
+                &dir,
+                &format!("{experiment_id}-topy0"),
+                LeftoverKind::Socket {
+                    experiment_id: Some(experiment_id),
+                },
+            ),
+        ];
+        let paths = leftovers
+            .iter()
+            .map(|leftover| leftover.path.clone())
+            .collect::<Vec<_>>();
+        let in_use = FilesInUse {
+            mapped: HashSet::from([paths[0].clone()]),
+            sockets: HashSet::from([leftovers[2].name().to_os_string()]),
+        };
+        Fixture {
+            dir,
+            leftovers,
+            mapped: paths[0].clone(),
+            unmapped: paths[1].clone(),
+            bound: paths[2].clone(),
+            unbound: paths[3].clone(),
+            in_use,
+        }
+    }
+
+    fn later() -> SystemTime {
+        SystemTime::now() + Duration::from_secs(3600)
+    }
+
+    #[test]
+    fn removes_orphans_not_in_use() {
+        let fixture = fixture(ExperimentId::generate());
+
+        let removed = remove_selected(
+            fixture.leftovers,
+            &ReaperConfig::default(),
+            &Owners::new(),
+            Orphans::NotInUse(Some(&fixture.in_use)),
+            later(),
+        );
+
+        assert_eq!(
+            removed,
+            vec![fixture.unmapped.clone(), fixture.unbound.clone()]
+        );
+        assert!(fixture.mapped.exists());
+        assert!(!fixture.unmapped.exists());
+        assert!(fixture.bound.exists());
+        assert!(!fixture.unbound.exists());
+    }
+
+    #[test]
+    fn skips_young_orphans() {
+        let fixture = fixture(ExperimentId::generate());
+
+        // The files were just created, so they are younger than `MIN_ORPHAN_AGE`
+        let removed = remove_selected(
+            fixture.leftovers,
+            &ReaperConfig::default(),
+            &Owners::new(),
+            Orphans::NotInUse(Some(&fixture.in_use)),
+            SystemTime::now(),
+        );
+        assert!(removed.is_empty());
+        assert!(fixture.unmapped.exists());
+        assert!(fixture.unbound.exists());
+    }
+
+    #[test]
+    fn skips_files_younger_than_cutoff() {
+        let fixture = fixture(ExperimentId::generate());
+
+        let config = ReaperConfig {
+            older_than: Some(7200.0),
+            ..ReaperConfig::default()
+        };
+        let removed = remove_selected(
+            fixture.leftovers,
+            &config,
+            &Owners::new(),
+            Orphans::NotInUse(Some(&fixture.in_use)),
+            later(),
+        );
+        assert!(removed.is_empty());
+        assert!(fixture.unmapped.exists());
+        assert!(fixture.unbound.exists());
+    }
+
+    #[test]
+    fn removes_files_of_experiments_in_use() {
+        let experiment_id = ExperimentId::generate();
+        let fixture = fixture(experiment_id);
+        let other_dir = tempfile::tempdir().unwrap();
+        let other = create(
+            &other_dir,
+            &segment_name(ExperimentId::generate(), 1),
+            LeftoverKind::Segment,
+        );
+        let other_path = other.path.clone();
+
+        let config = ReaperConfig {
+            experiment_ids: vec![experiment_id],
+            ..ReaperConfig::default()
+        };
+        let mut leftovers = fixture.leftovers;
+        leftovers.push(other);
+        let removed = remove_selected(
+            leftovers,
+            &config,
+            &Owners::new(),
+            Orphans::NotInUse(Some(&fixture.in_use)),
+            SystemTime::now(),
+        );
+
+        assert_eq!(
+            removed,
+            vec![
+                fixture.mapped.clone(),
+                fixture.unmapped.clone(),
+                fixture.bound.clone(),
+                fixture.unbound.clone()
+            ]
+        );
+        assert!(!fixture.mapped.exists());
+        assert!(!fixture.bound.exists());
+        assert!(other_path.exists());
+    }
+
+    #[test]
+    fn dry_run_keeps_files() {
+        let fixture = fixture(ExperimentId::generate());
+
+        let config = ReaperConfig {
+            dry_run: true,
+            ..ReaperConfig::default()
+        };
+        let removed = remove_selected(
+            fixture.leftovers,
+            &config,
+            &Owners::new(),
+            Orphans::NotInUse(Some(&fixture.in_use)),
+            later(),
+        );
+
+        assert_eq!(
+            removed,
+            vec![fixture.unmapped.clone(), fixture.unbound.clone()]
+        );
+        assert!(fixture.unmapped.exists());
+        assert!(fixture.unbound.exists());
+    }
+
+    /// Creates the lock file of `experiment_id` in `dir`.
+    fn owner_lock(dir: &TempDir, experiment_id: ExperimentId) -> Leftover {
+        create(
+            dir,
+            &format!("hash-engine-{}.lock", experiment_id.as_uuid().as_simple()),
+            LeftoverKind::OwnerLock {
+                experiment_id: experiment_id.as_uuid(),
+            },
+        )
+    }
+
+    #[test]
+    fn removes_only_files_of_exited_owners() {
+        let exited_id = ExperimentId::generate();
+        let exited = fixture(exited_id);
+        let exited_lock = owner_lock(&exited.dir, exited_id);
+        let exited_lock_path = exited_lock.path.clone();
+        let running_id = ExperimentId::generate();
+        let running = fixture(running_id);
+        let running_lock = owner_lock(&running.dir, running_id);
+        let unlocked = fixture(ExperimentId::generate());
+
+        let owners = Owners::from([
+            (exited_id.as_uuid(), OwnerStatus::Exited),
+            (running_id.as_uuid(), OwnerStatus::Running),
+        ]);
+        let leftovers = exited
+            .leftovers
+            .into_iter()
+            .chain([exited_lock])
+            .chain(running.leftovers)
+            .chain([running_lock])
+            .chain(unlocked.leftovers)
+            .collect();
+        let removed = remove_selected(
+            leftovers,
+            &ReaperConfig::default(),
+            &owners,
+            Orphans::Exited,
+            later(),
+        );
+
+        // Files of an exited engine are removed even if they are still in use by another process
+        assert_eq!(
+            removed,
+            vec![
+                exited.mapped.clone(),
+                exited.unmapped.clone(),
+                exited.bound.clone(),
+                exited.unbound.clone(),
+                exited_lock_path,
+            ]
+        );
+        assert!(running.unmapped.exists());
+        assert!(running.unbound.exists());
+        assert!(unlocked.unmapped.exists());
+        assert!(unlocked.unbound.exists());
+    }
+
+    #[test]
+    fn cleanup_keeps_files_of_running_owners() {
+        let running_id = ExperimentId::generate();
+        let running = fixture(running_id);
+        let running_lock = owner_lock(&running.dir, running_id);
+        let running_lock_path = running_lock.path.clone();
+        let unlocked = fixture(ExperimentId::generate());
+
+        let owners = Owners::from([(running_id.as_uuid(), OwnerStatus::Running)]);
+        let mut leftovers = running.leftovers;
+        leftovers.push(running_lock);
+        leftovers.extend(unlocked.leftovers);
+        let removed = remove_selected(
+            leftovers,
+            &ReaperConfig::default(),
+            &owners,
+            Orphans::NotInUse(Some(&unlocked.in_use)),
+            later(),
+        );
+
+        // Without a lock file, files are removed if they are not in use
+        assert_eq!(
+            removed,
+            vec![unlocked.unmapped.clone(), unlocked.unbound.clone()]
+        );
+        assert!(running.mapped.exists());
+        assert!(running.unmapped.exists());
+        assert!(running.unbound.exists());
+        assert!(running_lock_path.exists());
+    }
+}
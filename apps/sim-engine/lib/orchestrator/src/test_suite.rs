@@ -0,0 +1,550 @@
+//! Runs a project's experiments and compares their outputs with the expectations in its
+//! _integration-test.json_.
+//!
+//! _integration-test.json_ contains a list of objects, where each object has the following values:
+//! - for a simple experiment:
+//!   - "experiment": Name of the experiment to run as specified in _experiments.json_
+//!   - "expected-outputs": List of [`ExpectedOutput`]s, where the length of the list must be equal
+//!     to the number of simulations of the experiment
+//! - for single-run experiments:
+//!   - "steps": Number of steps to run
+//!   - "expected-output": The [`ExpectedOutput`] of the simulation
+//!
+//! An [`ExpectedOutput`] is an object with the following values:
+//! - "json-state": Object with the step number as its key mapped to the agents expected at the
+//!   corresponding step. The agents are either a list, which has to have the same length as the
+//!   output, or an object mapping the `agent_name` or `agent_id` of some of the agents to their
+//!   expected values.
+//! - "globals": set of values required to exist and match the _globals.json_ output
+//! - "analysis-outputs": set of values required to exist and match the _analysis_outputs.json_
+//!   output
+//! - "tolerance": optional [`Tolerance`] with an "absolute" and a "relative" value for comparing
+//!   numbers
+//!
+//! Projects can have an initial state per language, see [`load_manifest`]. The test of every
+//! experiment is run for each of them.
+
+mod error;
+mod expected;
+mod junit;
+
+use std::{
+    fs::File,
+    io::BufReader,
+    iter,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use error_stack::{ensure, IntoReport, Report, ResultExt};
+use execution::{
+    package::{
+        experiment::{ExperimentId, ExperimentName},
+        simulation::{output::json_state::JsonStateFormat, Seed},
+    },
+    runner::Language,
+};
+use experiment_structure::{ExperimentType, Manifest};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+pub use self::{
+    error::{Result, TestContext, TestError},
+    expected::{ExpectedOutput, Tolerance},
+};
+use crate::{Experiment, ExperimentConfig, Server};
+
+pub type AgentStates = Value;
+pub type Globals = Value;
+pub type Analysis = Value;
+
+/// File extensions of the initial states, in the order they are searched for.
+const INITIAL_STATE_EXTENSIONS: [&str; 6] = ["js", "py", "json", "csv", "parquet", "arrow"];
+
+/// Configuration for [`run_test_suite`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct TestSuiteConfig {
+    /// Only runs the tests of the simple experiment with this name.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub experiment: Option<String>,
+
+    /// Runs the tests with the initial state of this language, i.e. `src/init-<suffix>.*` with
+    /// the suffix `js`, `py`, `rs`, or `wasm`. Can be passed multiple times.
+    ///
+    /// Defaults to every language which has an initial state in the project, or to
+    /// `src/init.*` if there is none.
+    #[cfg_attr(
+        feature = "clap",
+        clap(long = "language", parse(try_from_str = parse_language))
+    )]
+    pub languages: Vec<Language>,
+
+    /// Maximum absolute difference between expected and actual floating-point numbers.
+    ///
+    /// Defaults to the machine epsilon, if `--relative-tolerance` is given. Overridden by a
+    /// "tolerance" in _integration-test.json_. Without any tolerance, numbers are compared
+    /// strictly.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub absolute_tolerance: Option<f64>,
+
+    /// Maximum difference between expected and actual floating-point numbers relative to the
+    /// larger of both.
+    ///
+    /// Defaults to 0, if `--absolute-tolerance` is given. Overridden by a "tolerance" in
+    /// _integration-test.json_.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub relative_tolerance: Option<f64>,
+
+    /// Writes the results as JUnit XML report to this file.
+    #[cfg_attr(feature = "clap", clap(long))]
+    pub junit: Option<PathBuf>,
+}
+
+impl TestSuiteConfig {
+    fn tolerance(&self) -> Option<Tolerance> {
+        if self.absolute_tolerance.is_none() && self.relative_tolerance.is_none() {
+            return None;
+        }
+        let default = Tolerance::default();
+        Some(Tolerance {
+            absolute: self.absolute_tolerance.unwrap_or(default.absolute),
+            relative: self.relative_tolerance.unwrap_or(default.relative),
+        })
+    }
+}
+
+/// Parses a language from the suffix of its initial state or from its name.
+fn parse_language(language: &str) -> core::result::Result<Language, String> {
+    match language.to_ascii_lowercase().as_str() {
+        "js" | "javascript" => Ok(Language::JavaScript),
+        "py" | "python" => Ok(Language::Python),
+        "rs" | "rust" => Ok(Language::Rust),
+        "wasm" => Ok(Language::Wasm),
+        _ => Err(format!(
+            "Unknown language `{language}`, expected one of `js`, `py`, `rs`, or `wasm`"
+        )),
+    }
+}
+
+/// The suffix of the initial state file for `language`.
+fn language_suffix(language: Option<Language>) -> &'static str {
+    match language {
+        Some(Language::JavaScript) => "-js",
+        Some(Language::Python) => "-py",
+        Some(Language::Rust) => "-rs",
+        Some(Language::Wasm) => "-wasm",
+        None => "",
+    }
+}
+
+fn initial_states(project_path: &Path, language: Option<Language>) -> Vec<PathBuf> {
+    let suffix = language_suffix(language);
+    INITIAL_STATE_EXTENSIONS
+        .into_iter()
+        .map(|ext| project_path.join("src").join(format!("init{suffix}.{ext}")))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Returns the languages which have an initial state in the project at `project_path`, or `None`
+/// if there are no language specific initial states.
+pub fn initial_state_languages<P: AsRef<Path>>(project_path: P) -> Vec<Option<Language>> {
+    let project_path = project_path.as_ref();
+    let languages: Vec<_> = Language::ORDERED
+        .into_iter()
+        .filter(|&language| !initial_states(project_path, Some(language)).is_empty())
+        .map(Some)
+        .collect();
+    if languages.is_empty() {
+        vec![None]
+    } else {
+        languages
+    }
+}
+
+/// Loads the manifest from `project_path` and optionally loads language specific intial states.
+///
+/// If `language` is specified, it searches for an `init` file with the language appended, so for
+/// example when [`Python`](Language::Python) is passed, it searches for the files `init-py.js`,
+/// `init-py.py`, `init-py.json`, `init-py.csv`, `init-py.parquet`, and `init-py.arrow`. If more
+/// than one initial state is specified, the function fails.
+pub fn load_manifest<P: AsRef<Path>>(
+    project_path: P,
+    language: Option<Language>,
+) -> Result<Manifest> {
+    let project_path = project_path.as_ref();
+
+    // We read the behaviors and datasets like loading a dependency
+    let mut manifest = Manifest::from_dependency(project_path)
+        .attach_printable_lazy(|| format!("Could not load manifest from {project_path:?}"))
+        .change_context(TestContext::ExperimentSetup)?;
+
+    // Now load globals, experiments, and the output config as specified in the documentation of
+    // `Manifest`
+    let globals_path = project_path.join("src").join("globals.json");
+    if globals_path.exists() {
+        manifest
+            .set_globals_from_file(globals_path)
+            .change_context(TestContext::ExperimentSetup)?;
+    }
+    let experiments_path = project_path.join("experiments.json");
+    if experiments_path.exists() {
+        manifest
+            .set_experiments_from_file(experiments_path)
+            .change_context(TestContext::ExperimentSetup)?;
+    }
+    let output_path = project_path.join("views").join("output.json");
+    if output_path.exists() {
+        manifest
+            .set_output_from_file(output_path)
+            .change_context(TestContext::ExperimentSetup)?;
+    }
+
+    // Load the initial state based on the language. if it is specified, use a `-lang` suffix
+    let initial_states = initial_states(project_path, language);
+    ensure!(
+        initial_states.len() == 1,
+        Report::from(TestError::MultipleLanguages).change_context(TestContext::TestSetup)
+    );
+    manifest
+        .set_initial_state_from_file(&initial_states[0])
+        .change_context(TestContext::ExperimentSetup)?;
+
+    Ok(manifest)
+}
+
+pub struct TestOutput {
+    pub outputs: Vec<(AgentStates, Globals, Analysis)>,
+    pub duration: Duration,
+}
+
+/// Runs the experiment of `experiment_type` in the project at `project_path` and reads its
+/// outputs.
+///
+/// The JSON state is always written as JSON, regardless of the `output_format` in
+/// `experiment_config`. Missing globals or analysis outputs are read as `null`.
+pub async fn run_test<P: AsRef<Path>>(
+    experiment_type: ExperimentType,
+    project_path: P,
+    project_name: String,
+    mut experiment_config: ExperimentConfig,
+    language: Option<Language>,
+    target_max_group_size: Option<usize>,
+) -> Result<TestOutput> {
+    let project_path = project_path.as_ref();
+    experiment_config.output_format = JsonStateFormat::Json;
+
+    let nng_listen_url = {
+        let uuid = ExperimentId::generate();
+        if let Some(language) = language {
+            format!("ipc://integration-test-suite-{project_name}-{language}-{uuid}")
+        } else {
+            format!("ipc://integration-test-suite-{project_name}-{uuid}")
+        }
+    };
+
+    let (mut experiment_server, handler) = Server::create(nng_listen_url);
+    tokio::spawn(async move { experiment_server.run().await });
+
+    let manifest = load_manifest(project_path, language)
+        .attach_printable_lazy(|| format!("Could not read project {project_path:?}"))?;
+    let experiment_run = manifest
+        .read(experiment_type, experiment_config.seed.map(Seed::new))
+        .attach_printable("Could not read manifest")
+        .change_context(TestContext::ExperimentSetup)?;
+
+    let experiment = Experiment::new(experiment_config);
+
+    let output_base_directory = experiment
+        .config
+        .output_folder
+        .join(experiment_run.name().as_str())
+        .join(experiment_run.id().to_string());
+
+    let now = Instant::now();
+    experiment
+        .run(experiment_run, handler, target_max_group_size)
+        .await
+        .change_context(TestContext::ExperimentRun)?;
+    let duration = now.elapsed();
+
+    let outputs = iter::repeat(output_base_directory)
+        .enumerate()
+        .map(|(sim_id, base_dir)| base_dir.join((sim_id + 1).to_string()))
+        .take_while(|output_dir| output_dir.exists())
+        .map(|output_dir| {
+            let json_state = parse_file(output_dir.join("json_state.json"))
+                .attach_printable("Could not read JSON state")?;
+            let globals = parse_optional_file(output_dir.join("globals.json"))
+                .attach_printable("Could not read globals")?;
+            let analysis_outputs = parse_optional_file(output_dir.join("analysis_outputs.json"))
+                .attach_printable("Could not read analysis outputs`")?;
+
+            Ok((json_state, globals, analysis_outputs))
+        })
+        .collect::<Result<_, TestError>>()
+        .change_context(TestContext::ExperimentOutput)?;
+
+    Ok(TestOutput { outputs, duration })
+}
+
+fn parse_file<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T, TestError> {
+    let path = path.as_ref();
+    serde_json::from_reader(BufReader::new(
+        File::open(path)
+            .into_report()
+            .change_context_lazy(|| TestError::parse_error(path))?,
+    ))
+    .into_report()
+    .change_context_lazy(|| TestError::parse_error(path))
+}
+
+/// Parses the file at `path`, or returns `null` if it doesn't exist, e.g. because the output
+/// package writing it is disabled.
+fn parse_optional_file<P: AsRef<Path>>(path: P) -> Result<Value, TestError> {
+    let path = path.as_ref();
+    if path.exists() {
+        parse_file(path)
+    } else {
+        Ok(Value::Null)
+    }
+}
+
+pub fn read_config<P: AsRef<Path>>(path: P) -> Result<Vec<(ExperimentType, Vec<ExpectedOutput>)>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum ConfigValue {
+        #[serde(rename_all = "kebab-case")]
+        Simple {
+            experiment: ExperimentName,
+            expected_outputs: Vec<ExpectedOutput>,
+        },
+        #[serde(rename_all = "kebab-case")]
+        SingleRun {
+            steps: usize,
+            expected_output: ExpectedOutput,
+        },
+    }
+
+    Ok(parse_file::<Vec<ConfigValue>, P>(path)
+        .attach_printable("Could not read integration test configuration")
+        .change_context(TestContext::TestSetup)?
+        .into_iter()
+        .map(|config_value| match config_value {
+            ConfigValue::Simple {
+                experiment,
+                expected_outputs,
+            } => (
+                ExperimentType::Simple { name: experiment },
+                expected_outputs,
+            ),
+            ConfigValue::SingleRun {
+                steps,
+                expected_output,
+            } => (
+                ExperimentType::SingleRun { num_steps: steps },
+                vec![expected_output],
+            ),
+        })
+        .collect())
+}
+
+/// Why a test case failed.
+#[derive(Debug, Clone)]
+pub struct TestFailure {
+    pub message: String,
+    pub details: String,
+}
+
+impl TestFailure {
+    fn new<C>(message: impl Into<String>, report: &Report<C>) -> Self {
+        Self {
+            message: message.into(),
+            details: format!("{report:?}"),
+        }
+    }
+}
+
+/// The result of running one experiment with one initial state.
+#[derive(Debug, Clone)]
+pub struct TestCaseResult {
+    pub name: String,
+    pub duration: Duration,
+    pub failure: Option<TestFailure>,
+}
+
+/// The results of [`run_test_suite`].
+#[derive(Debug, Clone)]
+pub struct TestSuiteReport {
+    pub name: String,
+    pub results: Vec<TestCaseResult>,
+}
+
+impl TestSuiteReport {
+    /// The number of failed test cases.
+    pub fn failures(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.failure.is_some())
+            .count()
+    }
+
+    /// Returns the results as JUnit XML report.
+    pub fn to_junit(&self) -> String {
+        junit::report(&self.name, &self.results)
+    }
+}
+
+fn test_name(experiment_type: &ExperimentType, language: Option<Language>) -> String {
+    let name = match experiment_type {
+        ExperimentType::SingleRun { num_steps } => format!("single-run ({num_steps} steps)"),
+        ExperimentType::Simple { name } => name.to_string(),
+        ExperimentType::Resume { num_steps, .. } => format!("resume ({num_steps} steps)"),
+    };
+    match language {
+        Some(language) => format!("{name} [{language}]"),
+        None => name,
+    }
+}
+
+/// Runs the test of one experiment and checks the outputs against `expected_outputs`.
+async fn run_test_case(
+    experiment_type: ExperimentType,
+    expected_outputs: &[ExpectedOutput],
+    project_path: &Path,
+    project_name: &str,
+    experiment_config: &ExperimentConfig,
+    language: Option<Language>,
+    tolerance: Option<Tolerance>,
+) -> Option<TestFailure> {
+    let test_output = match run_test(
+        experiment_type,
+        project_path,
+        project_name.to_string(),
+        experiment_config.clone(),
+        language,
+        None,
+    )
+    .await
+    {
+        Ok(test_output) => test_output,
+        Err(report) => {
+            return Some(TestFailure::new(
+                report.current_context().to_string(),
+                &report,
+            ));
+        }
+    };
+
+    if expected_outputs.len() != test_output.outputs.len() {
+        return Some(TestFailure {
+            message: format!(
+                "Number of expected outputs does not match number of returned simulation results \
+                 for experiment, expected {} found {}",
+                expected_outputs.len(),
+                test_output.outputs.len()
+            ),
+            details: String::new(),
+        });
+    }
+
+    for (output_idx, ((states, globals, analysis), expected)) in
+        test_output.outputs.iter().zip(expected_outputs).enumerate()
+    {
+        if let Err(report) = expected.assert_subset_of(states, globals, analysis, tolerance) {
+            return Some(TestFailure::new(
+                format!(
+                    "Output of simulation {} does not match expected output in experiment",
+                    output_idx + 1
+                ),
+                &report,
+            ));
+        }
+    }
+
+    None
+}
+
+/// Runs the experiments in the _integration-test.json_ of the project at `project_path` and
+/// compares their outputs with the expected outputs.
+///
+/// Failing tests don't return an error but are part of the returned [`TestSuiteReport`].
+///
+/// # Errors
+///
+/// - if _integration-test.json_ can't be read
+/// - if an experiment passed in `config` isn't part of _integration-test.json_
+pub async fn run_test_suite<P: AsRef<Path>>(
+    project_path: P,
+    config: &TestSuiteConfig,
+    experiment_config: &ExperimentConfig,
+) -> Result<TestSuiteReport> {
+    let project_path = project_path.as_ref();
+    let project_name = project_path.file_name().map_or_else(
+        || String::from("project"),
+        |name| name.to_string_lossy().to_string(),
+    );
+
+    let experiments: Vec<_> = read_config(project_path.join("integration-test.json"))?
+        .into_iter()
+        .filter(
+            |(experiment_type, _)| match (&config.experiment, experiment_type) {
+                (None, _) => true,
+                (Some(experiment), ExperimentType::Simple { name }) => experiment == name.as_str(),
+                _ => false,
+            },
+        )
+        .collect();
+    if let Some(experiment) = &config.experiment {
+        ensure!(
+            !experiments.is_empty(),
+            Report::new(TestContext::TestSetup).attach_printable(format!(
+                "integration-test.json has no test for the experiment `{experiment}`"
+            ))
+        );
+    }
+
+    let languages = if config.languages.is_empty() {
+        initial_state_languages(project_path)
+    } else {
+        config.languages.iter().copied().map(Some).collect()
+    };
+    let tolerance = config.tolerance();
+
+    let mut results = Vec::with_capacity(experiments.len() * languages.len());
+    for (experiment_type, expected_outputs) in experiments {
+        for &language in &languages {
+            let name = test_name(&experiment_type, language);
+            tracing::info!("Running test {name}");
+
+            let now = Instant::now();
+            let failure = run_test_case(
+                experiment_type.clone(),
+                &expected_outputs,
+                project_path,
+                &project_name,
+                experiment_config,
+                language,
+                tolerance,
+            )
+            .await;
+            let duration = now.elapsed();
+
+            match &failure {
+                Some(failure) => tracing::error!("Test {name} failed: {}", failure.message),
+                None => tracing::info!("Test {name} passed"),
+            }
+            results.push(TestCaseResult {
+                name,
+                duration,
+                failure,
+            });
+        }
+    }
+
+    Ok(TestSuiteReport {
+        name: project_name,
+        results,
+    })
+}
@@ -9,6 +9,7 @@ pub type Result<T, C = TestContext> = error_stack::Result<T, C>;
 // such as
 //   `-> Result<_, Report<ExperimentSetup>>`
 //   see https://app.asana.com/0/1199548034582004/1202369328773771/f
+/// The stage of a test at which it failed.
 #[derive(Debug)]
 pub enum TestContext {
     TestSetup,
@@ -32,6 +33,7 @@ impl fmt::Display for TestContext {
 
 impl Context for TestContext {}
 
+/// The reason why a test failed.
 #[derive(Debug)]
 pub enum TestError {
     MultipleLanguages,
@@ -148,7 +150,6 @@ impl fmt::Display for TestError {
 impl Error for TestError {}
 
 #[test]
-#[cfg(test)]
 fn check_test_error_output() {
     let e = TestError::unexpected_output_value(
         "for_test".to_string(),
@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use error_stack::{bail, ensure, IntoReport, ResultExt};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::test_suite::{
+    error::{Result, TestError},
+    AgentStates, Analysis, Globals,
+};
+
+/// Maximum difference between an expected and an actual number in the output of a test.
+///
+/// Two numbers are considered equal, if their difference is at most `absolute` or at most
+/// `relative` times the larger of both numbers. Two integers are always compared exactly.
+///
+/// Without a tolerance, only two floating-point numbers are compared and their difference has to
+/// be below the machine epsilon, i.e. an integer never equals a floating-point number.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            absolute: f64::EPSILON,
+            relative: 0.0,
+        }
+    }
+}
+
+impl Tolerance {
+    fn eq(&self, expected: f64, actual: f64) -> bool {
+        let difference = (expected - actual).abs();
+        difference <= self.absolute
+            || difference <= self.relative * expected.abs().max(actual.abs())
+    }
+}
+
+/// The expected output of a simulation run as specified in _integration-test.json_.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExpectedOutput {
+    #[serde(default)]
+    pub json_state: HashMap<String, AgentStates>,
+    #[serde(default)]
+    pub globals: Option<Globals>,
+    #[serde(default)]
+    pub analysis_outputs: Option<Analysis>,
+    /// Overrides the tolerance passed to [`assert_subset_of`](Self::assert_subset_of).
+    #[serde(default)]
+    pub tolerance: Option<Tolerance>,
+}
+
+/// Implementation for [`ExpectedOutput::assert_subset_of`]
+fn assert_subset_value(
+    subset: &Value,
+    superset: &Value,
+    path: String,
+    tolerance: Option<Tolerance>,
+) -> Result<(), TestError> {
+    match (subset, superset) {
+        (Value::Number(a), Value::Number(b)) if tolerance.is_none() && a.is_f64() && b.is_f64() => {
+            ensure!(
+                (a.as_f64().unwrap() - b.as_f64().unwrap()).abs() < f64::EPSILON,
+                TestError::unexpected_output_value(path, subset.clone(), superset.clone())
+            );
+        }
+        (Value::Number(a), Value::Number(b))
+            if tolerance.is_some() && (a.is_f64() || b.is_f64()) =>
+        {
+            ensure!(
+                tolerance
+                    .unwrap()
+                    .eq(a.as_f64().unwrap(), b.as_f64().unwrap()),
+                TestError::unexpected_output_value(path, subset.clone(), superset.clone())
+            );
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            ensure!(
+                a.len() == b.len(),
+                TestError::unexpected_output_length(path, a.clone(), b.clone())
+            );
+            for (i, (sub_value, super_value)) in a.iter().zip(b.iter()).enumerate() {
+                assert_subset_value(sub_value, super_value, format!("{path}[{i}]"), tolerance)?;
+            }
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, expected) in a {
+                match b.get(key) {
+                    Some(value) => {
+                        assert_subset_value(expected, value, format!("{path}.{key}"), tolerance)?
+                    }
+                    None => bail!(TestError::output_missing(path, expected.clone())),
+                }
+            }
+        }
+        _ => {
+            ensure!(
+                subset == superset,
+                TestError::unexpected_output_value(path, subset.clone(), superset.clone())
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the agents of a step by their `agent_name` or `agent_id`.
+///
+/// `expected_agents` maps names or ids to the expected values of the agent, agents not contained in
+/// it are ignored.
+fn assert_subset_agents(
+    expected_agents: &serde_json::Map<String, Value>,
+    agents: &Value,
+    path: String,
+    tolerance: Option<Tolerance>,
+) -> Result<(), TestError> {
+    for (key, expected) in expected_agents {
+        let agent = agents.as_array().and_then(|agents| {
+            agents.iter().find(|agent| {
+                ["agent_name", "agent_id"]
+                    .into_iter()
+                    .any(|field| agent.get(field).and_then(Value::as_str) == Some(key))
+            })
+        });
+        match agent {
+            Some(agent) => {
+                assert_subset_value(expected, agent, format!("{path}.{key}"), tolerance)?
+            }
+            None => bail!(TestError::output_missing(
+                format!("{path}.{key}"),
+                expected.clone()
+            )),
+        }
+    }
+
+    Ok(())
+}
+
+impl ExpectedOutput {
+    /// Compares to an experiment output and returns [`Err`], if this output is not a subset
+    /// of `superset`.
+    ///
+    /// It's considered a subset if for any output (`json_state`, `globals`, `analysis_output`) the
+    /// following conditions are true:
+    /// - All non-array and non-object values must be equal to the corresponding value in `superset`
+    ///   with numbers being compared within the [`Tolerance`], if one is given here or in the
+    ///   expected output
+    /// - For arrays, the length must match and for each element this list applies
+    /// - For objects, for each key present in the subset there must be a corresponding key in
+    ///   `superset`, for which the value needs to be equal as in this list
+    ///
+    /// The expected agents of a step in `json_state` may also be an object instead of a list. Then
+    /// its keys are names or ids of agents, and only these agents are compared.
+    pub fn assert_subset_of(
+        &self,
+        agent_states: &AgentStates,
+        globals: &Globals,
+        analysis: &Analysis,
+        tolerance: Option<Tolerance>,
+    ) -> Result<(), TestError> {
+        let tolerance = self.tolerance.or(tolerance);
+
+        let mut json_state = self.json_state.iter().collect::<Vec<_>>();
+        json_state.sort_unstable_by(|(lhs, _), (rhs, _)| Ord::cmp(lhs, rhs));
+        for (step, expected_states) in json_state {
+            let step = step
+                .parse::<usize>()
+                .into_report()
+                .change_context_lazy(|| TestError::invalid_step(step.clone()))?;
+            let result_states = agent_states
+                .get(step)
+                .ok_or_else(|| TestError::missing_step(step))?;
+            let path = format!("json_state[{step}]");
+            match expected_states {
+                Value::Object(expected_agents) => {
+                    assert_subset_agents(expected_agents, result_states, path, tolerance)?;
+                }
+                _ => assert_subset_value(expected_states, result_states, path, tolerance)?,
+            }
+        }
+
+        if let Some(expected_globals) = &self.globals {
+            assert_subset_value(
+                expected_globals,
+                globals,
+                String::from("globals"),
+                tolerance,
+            )?;
+        }
+
+        if let Some(expected_analysis) = &self.analysis_outputs {
+            assert_subset_value(
+                expected_analysis,
+                analysis,
+                String::from("analysis_outputs"),
+                tolerance,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn assert_subset(lhs: &Value, rhs: &Value) -> Result<(), TestError> {
+        assert_subset_value(lhs, rhs, String::new(), None)
+    }
+
+    #[test]
+    fn test_subset() {
+        // Note, that the function is also implicitly tested by integration tests
+
+        // Compare nulls
+        assert_subset(&json!(null), &json!(null)).unwrap();
+
+        // Compare boolean
+        assert_subset(&json!(true), &json!(true)).unwrap();
+
+        // Compare different types
+        let _ = assert_subset(&json!(false), &json!(null)).unwrap_err();
+
+        // Compare numbers
+        assert_subset(&json!(0.5_f64), &json!(0.5_f64)).unwrap();
+        assert_subset(&json!(5_u32), &json!(5_u32)).unwrap();
+        assert_subset(&json!(5_i32), &json!(5_i32)).unwrap();
+        let _ = assert_subset(&json!(5_i32), &json!(5_f64)).unwrap_err();
+        let _ = assert_subset(&json!(0.5_f64), &json!(0.5_f64 + 1e-10)).unwrap_err();
+
+        // Compare strings
+        assert_subset(&json!("a"), &json!("a")).unwrap();
+
+        // Compare objects
+        assert_subset(&json!({"a": "string"}), &json!({"a": "string"})).unwrap();
+        let _ = assert_subset(&json!({"a": "string"}), &json!({})).unwrap_err();
+
+        // Compare arrays
+        let _ = assert_subset(&json!(["1", "2", "3"]), &json!(["1", "2"])).unwrap_err();
+        assert_subset(
+            &json!([{"1": null}, {"2": "2"}, {"3": true}]),
+            &json!([{"1": null}, {"2": "2"}, {"3": true, "_": false}]),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let tolerance = Tolerance {
+            absolute: 0.01,
+            relative: 0.0,
+        };
+        assert_subset_value(&json!(1.0), &json!(1.005), String::new(), Some(tolerance)).unwrap();
+        let _ = assert_subset_value(&json!(1.0), &json!(1.02), String::new(), Some(tolerance))
+            .unwrap_err();
+
+        let tolerance = Tolerance {
+            absolute: 0.0,
+            relative: 0.01,
+        };
+        assert_subset_value(
+            &json!(1000.0),
+            &json!(1005.0),
+            String::new(),
+            Some(tolerance),
+        )
+        .unwrap();
+        let _ = assert_subset_value(&json!(1.0), &json!(1.02), String::new(), Some(tolerance))
+            .unwrap_err();
+
+        // Integers are compared exactly
+        let _ = assert_subset_value(&json!(1000), &json!(1005), String::new(), Some(tolerance))
+            .unwrap_err();
+
+        // Integers and floating-point numbers are only equal with a tolerance
+        assert_subset_value(&json!(5), &json!(5.0), String::new(), Some(tolerance)).unwrap();
+    }
+
+    #[test]
+    fn test_partial_state() {
+        let expected = ExpectedOutput {
+            json_state: HashMap::from([(
+                String::from("1"),
+                json!({ "b": { "age": 2 }, "3d3c5b1e-7b63-4f3c-9d0e-0d3f1f8a2b6e": { "age": 4 } }),
+            )]),
+            globals: None,
+            analysis_outputs: None,
+            tolerance: None,
+        };
+        let states = json!([
+            [],
+            [
+                { "agent_name": "a", "age": 1 },
+                { "agent_name": "b", "age": 2 },
+                { "agent_id": "3d3c5b1e-7b63-4f3c-9d0e-0d3f1f8a2b6e", "age": 4 },
+            ]
+        ]);
+        expected
+            .assert_subset_of(&states, &json!({}), &json!({}), None)
+            .unwrap();
+
+        let states = json!([[], [{ "agent_name": "b", "age": 3 }]]);
+        let _ = expected
+            .assert_subset_of(&states, &json!({}), &json!({}), None)
+            .unwrap_err();
+    }
+}
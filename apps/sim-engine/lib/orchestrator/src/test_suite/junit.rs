@@ -0,0 +1,113 @@
+use std::{fmt::Write, time::Duration};
+
+use crate::test_suite::TestCaseResult;
+
+/// Escapes `text` to be used in XML attributes and text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters (e.g. from colored output) are not allowed in XML 1.0
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes the results of a test suite as JUnit XML report, as understood by most CI systems.
+pub(super) fn report(suite_name: &str, results: &[TestCaseResult]) -> String {
+    let failures = results
+        .iter()
+        .filter(|result| result.failure.is_some())
+        .count();
+    let time: Duration = results.iter().map(|result| result.duration).sum();
+    let suite_name = escape(suite_name);
+
+    let mut xml = String::new();
+    // Writing into a `String` can't fail
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="{suite_name}" tests="{}" failures="{failures}" time="{:.3}">"#,
+        results.len(),
+        time.as_secs_f64()
+    );
+    let _ = writeln!(
+        xml,
+        r#"  <testsuite name="{suite_name}" tests="{}" failures="{failures}" errors="0" time="{:.3}">"#,
+        results.len(),
+        time.as_secs_f64()
+    );
+    for result in results {
+        let _ = write!(
+            xml,
+            r#"    <testcase name="{}" classname="{suite_name}" time="{:.3}""#,
+            escape(&result.name),
+            result.duration.as_secs_f64()
+        );
+        match &result.failure {
+            Some(failure) => {
+                let _ = writeln!(
+                    xml,
+                    r#">
+      <failure message="{}">{}</failure>
+    </testcase>"#,
+                    escape(&failure.message),
+                    escape(&failure.details)
+                );
+            }
+            None => {
+                let _ = writeln!(xml, "/>");
+            }
+        }
+    }
+    let _ = writeln!(xml, "  </testsuite>");
+    let _ = writeln!(xml, "</testsuites>");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_suite::TestFailure;
+
+    #[test]
+    fn junit_report() {
+        let results = [
+            TestCaseResult {
+                name: String::from("single-run (10 steps) [js]"),
+                duration: Duration::from_millis(1500),
+                failure: None,
+            },
+            TestCaseResult {
+                name: String::from("sweep <values>"),
+                duration: Duration::from_millis(250),
+                failure: Some(TestFailure {
+                    message: String::from("Output of simulation 1 does not match"),
+                    details: String::from("expected `\"a\"` & got `\"b\"`"),
+                }),
+            },
+        ];
+
+        assert_eq!(
+            report("project", &results),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="project" tests="2" failures="1" time="1.750">
+  <testsuite name="project" tests="2" failures="1" errors="0" time="1.750">
+    <testcase name="single-run (10 steps) [js]" classname="project" time="1.500"/>
+    <testcase name="sweep &lt;values&gt;" classname="project" time="0.250">
+      <failure message="Output of simulation 1 does not match">expected `&quot;a&quot;` &amp; got `&quot;b&quot;`</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Checkpoints of simulation runs, which can be used to resume a simulation run later on.
+//!
+//! A checkpoint is written into `<folder>/<experiment id>/<simulation id>/step-<step>` and consists
+//! of the following files:
+//!
+//! - `agents-<i>` and `messages-<i>` for every group `i`: The contents of the [`Segment`] backing
+//!   the agent batch and the message batch of the group, i.e. the markers, the Arrow schema, the
+//!   header, the Arrow metadata, and the column data in the same layout as in shared memory. The
+//!   state of packages, which is stored in agent columns (e.g. the behavior index used by the
+//!   behavior execution package), is part of the agent batches.
+//! - `checkpoint.json`: The [`CheckpointMetadata`] of the checkpoint, e.g. the step, the globals
+//!   at that step, and the messages held back to be received in a later step.
+//!
+//! [`Segment`]: memory::shared_memory::Segment
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use execution::package::simulation::Seed;
+use experiment_structure::SimulationRunConfig;
+use memory::shared_memory::{MemoryId, Segment};
+use serde::{Deserialize, Serialize};
+use stateful::{
+    agent::AgentBatch,
+    global::Globals,
+    message::{DelayedMessages, MessageBatch},
+    proxy::BatchPool,
+    state::State,
+};
+
+use crate::{Error, Result};
+
+const METADATA_FILE_NAME: &str = "checkpoint.json";
+
+/// Configures how often and where checkpoints are written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointConfig {
+    /// The folder the checkpoints are written to
+    pub folder: PathBuf,
+    /// A checkpoint is written every `interval` steps
+    pub interval: usize,
+}
+
+impl CheckpointConfig {
+    /// Returns `true` if a checkpoint should be written after the given `step`.
+    pub fn is_checkpoint_step(&self, step: usize) -> bool {
+        self.interval > 0 && step % self.interval == 0
+    }
+}
+
+/// Describes the content of a checkpoint next to the agent and message batches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointMetadata {
+    /// The step after which the checkpoint was written
+    pub step: usize,
+    /// The globals at `step`, including all patches applied until then
+    pub globals: Globals,
+    /// The seed of the random number generators of the simulation run, if it's seeded
+    #[serde(default)]
+    pub seed: Option<Seed>,
+    /// The number of groups, i.e. the number of agent batches and message batches
+    pub num_groups: usize,
+    /// Messages sent with a delay, which were not received until `step`
+    #[serde(default)]
+    pub delayed_messages: DelayedMessages,
+}
+
+/// A checkpoint written by a previous simulation run.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    path: PathBuf,
+    metadata: CheckpointMetadata,
+}
+
+impl Checkpoint {
+    /// Writes a checkpoint of `state` after `step` and returns the folder it was written to.
+    pub(crate) fn write(
+        config: &CheckpointConfig,
+        sim_config: &SimulationRunConfig,
+        step: usize,
+        globals: &Globals,
+        state: &State,
+        delayed_messages: &DelayedMessages,
+    ) -> Result<PathBuf> {
+        let path = config
+            .folder
+            .join(
+                sim_config
+                    .experiment_config()
+                    .experiment_run
+                    .id()
+                    .to_string(),
+            )
+            .join(sim_config.simulation_config().id.to_string())
+            .join(format!("step-{step}"));
+        fs::create_dir_all(&path).map_err(|err| {
+            Error::Checkpoint(format!(
+                "Could not create checkpoint folder {path:?}: {err}"
+            ))
+        })?;
+
+        let agent_proxies = state.agent_pool().read_proxies()?;
+        for (index, agent_batch) in agent_proxies.batches_iter().enumerate() {
+            write_file(
+                &path.join(format!("agents-{index}")),
+                agent_batch
+                    .batch
+                    .segment()
+                    .get_contents_bytes()
+                    .map_err(stateful::Error::from)?,
+            )?;
+        }
+        let message_proxies = state.message_pool().read_proxies()?;
+        for (index, message_batch) in message_proxies.batches_iter().enumerate() {
+            write_file(
+                &path.join(format!("messages-{index}")),
+                message_batch
+                    .batch
+                    .segment()
+                    .get_contents_bytes()
+                    .map_err(stateful::Error::from)?,
+            )?;
+        }
+
+        let metadata = CheckpointMetadata {
+            step,
+            globals: globals.clone(),
+            seed: sim_config.simulation_config().package_creator.seed,
+            num_groups: agent_proxies.len(),
+            delayed_messages: delayed_messages.clone(),
+        };
+        write_file(
+            &path.join(METADATA_FILE_NAME),
+            &serde_json::to_vec_pretty(&metadata).map_err(|err| {
+                Error::Checkpoint(format!("Could not serialize checkpoint metadata: {err}"))
+            })?,
+        )?;
+
+        Ok(path)
+    }
+
+    /// Opens the checkpoint in the folder at `path` by reading its metadata.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let metadata_path = path.join(METADATA_FILE_NAME);
+        let metadata = fs::read(&metadata_path)
+            .map_err(|err| Error::Checkpoint(format!("Could not read {metadata_path:?}: {err}")))?;
+        let metadata = serde_json::from_slice(&metadata).map_err(|err| {
+            Error::Checkpoint(format!("Could not parse {metadata_path:?}: {err}"))
+        })?;
+        Ok(Self { path, metadata })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn metadata(&self) -> &CheckpointMetadata {
+        &self.metadata
+    }
+
+    /// Restores the agent and message batches of the checkpoint into new shared memory segments.
+    ///
+    /// The batches are validated against the schemas of the simulation run, so the project must
+    /// not have changed in a way which changes the schemas since the checkpoint was written.
+    pub(crate) fn read_state(&self, sim_config: &SimulationRunConfig) -> Result<State> {
+        let create_parameters = sim_config.to_state_create_parameters();
+        let memory_base_id = create_parameters.memory_base_id;
+
+        let mut agent_batches = Vec::with_capacity(self.metadata.num_groups);
+        let mut message_batches = Vec::with_capacity(self.metadata.num_groups);
+        for index in 0..self.metadata.num_groups {
+            let segment = Segment::from_contents_bytes(
+                MemoryId::new(memory_base_id),
+                &read_file(&self.path.join(format!("agents-{index}")))?,
+                true,
+            )
+            .map_err(stateful::Error::from)?;
+            agent_batches.push(AgentBatch::from_segment(
+                segment,
+                Some(&create_parameters.agent_schema),
+                None,
+            )?);
+
+            let segment = Segment::from_contents_bytes(
+                MemoryId::new(memory_base_id),
+                &read_file(&self.path.join(format!("messages-{index}")))?,
+                true,
+            )
+            .map_err(stateful::Error::from)?;
+            message_batches.push(MessageBatch::from_segment(
+                segment,
+                &create_parameters.message_schema,
+            )?);
+        }
+
+        State::from_batches(agent_batches, message_batches, create_parameters).map_err(Error::from)
+    }
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    fs::write(path, contents)
+        .map_err(|err| Error::Checkpoint(format!("Could not write {path:?}: {err}")))
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|err| Error::Checkpoint(format!("Could not read {path:?}: {err}")))
+}
@@ -12,6 +12,7 @@ use std::{
     sync::Arc,
 };
 
+use execution::package::simulation::Seed;
 use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use stateful::{
@@ -94,7 +95,10 @@ pub struct Commands {
 impl Commands {
     /// Push a command for the request of the creation of an agent
     pub fn add_create(&mut self, agent: Agent) {
-        self.create_remove.create.push(CreateCommand { agent });
+        self.create_remove.create.push(CreateCommand {
+            agent,
+            creator: None,
+        });
     }
 
     /// Push a command for the request of the deletion of the agent associated with the given UUID
@@ -122,6 +126,27 @@ impl Commands {
         Ok(())
     }
 
+    /// Assigns an id to every agent, which is requested to be created without an `agent_id`.
+    ///
+    /// In seeded simulation runs, the id is derived from the `seed`, the `step`, the agent which
+    /// requested the creation, and the number of agents without an id it requested before.
+    /// Otherwise, a random id is generated.
+    pub fn assign_agent_ids(&mut self, step: usize, seed: Option<Seed>) {
+        let mut num_created = HashMap::<Option<AgentId>, usize>::new();
+        for create in &mut self.create_remove.create {
+            if !create.agent.agent_id.is_unassigned() {
+                continue;
+            }
+            let index = num_created.entry(create.creator).or_default();
+            create
+                .agent
+                .assign_id(seed.map_or_else(AgentId::generate, |seed| {
+                    seed.created_agent_id(step, create.creator.as_ref(), *index)
+                }));
+            *index += 1;
+        }
+    }
+
     pub fn merge(&mut self, mut other: Commands) {
         self.create_remove
             .create
@@ -233,10 +258,11 @@ fn handle_hash_message(
     match message_type {
         // See https://hash.ai/docs/simulation/creating-simulations/agent-messages/built-in-message-handlers
         HashMessageType::Create => {
-            cmds.add_create(
-                serde_json::from_str(data)
+            cmds.create_remove.create.push(CreateCommand {
+                agent: serde_json::from_str(data)
                     .map_err(|e| Error::CreateAgentPayload(e, data.to_string()))?,
-            );
+                creator: Some(AgentId::from_bytes(*from)),
+            });
         }
         HashMessageType::Remove => {
             handle_remove_data(cmds, data, from)?;
@@ -275,3 +301,56 @@ fn handle_remove_data(cmds: &mut Commands, data: &str, from: &[u8; UUID_V4_LEN])
     cmds.add_remove(uuid);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn commands(creator: AgentId) -> Commands {
+        let mut commands = Commands::default();
+        for agent in [
+            json!({ "agent_name": "a" }),
+            json!({ "agent_name": "b", "agent_id": "00000000-0000-4000-8000-00000000000b" }),
+            json!({ "agent_name": "c" }),
+        ] {
+            commands.create_remove.create.push(CreateCommand {
+                agent: serde_json::from_value(agent).unwrap(),
+                creator: Some(creator),
+            });
+        }
+        commands.add_create(serde_json::from_value(json!({ "agent_name": "d" })).unwrap());
+        commands
+    }
+
+    fn agent_ids(commands: &Commands) -> Vec<AgentId> {
+        commands
+            .create_remove
+            .create
+            .iter()
+            .map(|create| create.agent.agent_id)
+            .collect()
+    }
+
+    #[test]
+    fn seeded_agent_ids_are_reproducible() {
+        let creator = AgentId::generate();
+        let seed = Some(Seed::new(42));
+
+        let mut first = commands(creator);
+        first.assign_agent_ids(3, seed);
+        let mut second = commands(creator);
+        second.assign_agent_ids(3, seed);
+
+        let ids = agent_ids(&first);
+        assert_eq!(ids, agent_ids(&second));
+        assert_eq!(ids[1].to_string(), "00000000-0000-4000-8000-00000000000b");
+        assert!(ids.iter().all(|id| !id.is_unassigned()));
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+
+        let mut next_step = commands(creator);
+        next_step.assign_agent_ids(4, seed);
+        assert_ne!(agent_ids(&next_step)[0], ids[0]);
+    }
+}
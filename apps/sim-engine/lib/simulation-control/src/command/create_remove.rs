@@ -18,6 +18,8 @@ type WorkerIndex = usize;
 #[derive(Debug)]
 pub struct CreateCommand {
     pub(super) agent: Agent,
+    /// The agent, which requested the creation, if any.
+    pub(super) creator: Option<AgentId>,
 }
 
 #[derive(Debug)]
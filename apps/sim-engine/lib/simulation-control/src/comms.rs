@@ -33,6 +33,7 @@ use stateful::{
     agent::{Agent, AgentId},
     context::Context,
     field::PackageId,
+    global::Globals,
     state::StateReadProxy,
 };
 
@@ -152,6 +153,19 @@ impl Comms {
         Ok(())
     }
 
+    /// Sends the new globals of the simulation run to the workers (via the worker pool), which
+    /// forward them to their language runners.
+    ///
+    /// Errors: tokio failed to send the message to the worker pool for some reason;
+    ///         e.g. the worker pool already stopped due to some other error.
+    pub fn update_globals(&self, globals: Arc<Globals>) -> Result<()> {
+        tracing::trace!("Updating globals");
+        self.worker_pool_sender
+            .send(EngineToWorkerPoolMsg::update_globals(self.sim_id, globals))
+            .map_err(|e| Error::from(format!("Worker pool error: {:?}", e)))?;
+        Ok(())
+    }
+
     /// Adds a command to create the specified [`Agent`].
     ///
     /// # Errors
@@ -20,6 +20,7 @@ pub use self::{
     sim_control::SimControl,
 };
 use crate::{
+    checkpoint::CheckpointConfig,
     comms,
     comms::{
         control::{SimCtlRecv, SimCtlSend},
@@ -40,6 +41,7 @@ impl SimulationController {
         packages: Packages,
         persistence_service: P,
         status_sender: SimStatusSend,
+        checkpoint_config: Option<CheckpointConfig>,
     ) -> Result<SimulationController> {
         let (ctl_sender, ctl_receiver) = comms::control::new_pair();
 
@@ -50,6 +52,7 @@ impl SimulationController {
             comms,
             packages,
             persistence_service,
+            checkpoint_config,
         )?;
         Ok(SimulationController {
             sender: ctl_sender,
@@ -65,6 +68,7 @@ fn new_task_handle<P: SimulationOutputPersistence>(
     comms: Comms,
     packages: Packages,
     persistence_service: P,
+    checkpoint_config: Option<CheckpointConfig>,
 ) -> Result<JoinHandle<Result<SimulationId>>> {
     let task = Box::pin(run::sim_run(
         config,
@@ -73,6 +77,7 @@ fn new_task_handle<P: SimulationOutputPersistence>(
         receiver,
         sender,
         persistence_service,
+        checkpoint_config,
     ))
     .in_current_span();
 
@@ -4,7 +4,7 @@ use arrow2::chunk::Chunk;
 use execution::{
     package::simulation::{
         context::ContextPackage,
-        init::InitPackage,
+        init::{InitPackage, InitialAgents},
         output::{Output, OutputPackage},
         state::StatePackage,
         PackageType,
@@ -16,8 +16,10 @@ use experiment_structure::{PackageCreators, SimulationRunConfig};
 use futures::{executor::block_on, stream::FuturesOrdered, StreamExt};
 use memory::shared_memory::MemoryId;
 use stateful::{
+    agent::{arrow::IntoRecordBatch, AgentId},
     context::{Context, ContextColumn, PreContext},
     field::{FieldSource, FieldSpecMapAccessor},
+    global::Globals,
     state::{State, StateReadProxy, StateSnapshot},
 };
 use tracing::{Instrument, Span};
@@ -185,6 +187,20 @@ impl Packages {
         ))
     }
 
+    /// Passes the new globals to every package, so they can update their configuration.
+    pub fn update_globals(&mut self, globals: &Globals) -> Result<()> {
+        self.context
+            .iter_mut()
+            .try_for_each(|package| package.update_globals(globals))?;
+        self.state
+            .iter_mut()
+            .try_for_each(|package| package.update_globals(globals))?;
+        self.output
+            .iter_mut()
+            .try_for_each(|package| package.update_globals(globals))?;
+        Ok(())
+    }
+
     pub async fn run_init(&mut self, sim_config: Arc<SimulationRunConfig>) -> Result<State> {
         // Execute packages in parallel and collect the data
         let mut futs = FuturesOrdered::new();
@@ -211,14 +227,43 @@ impl Packages {
 
         let mut pkgs = Vec::with_capacity(num_packages);
         let mut agents = Vec::with_capacity(num_packages);
+        let mut batches = Vec::new();
         for result in collected {
-            let (pkg, new_agents) = result?;
+            let (pkg, initial_agents) = result?;
             pkgs.push(pkg);
-            agents.append(&mut new_agents?);
+            match initial_agents? {
+                InitialAgents::Agents(mut new_agents) => agents.append(&mut new_agents),
+                InitialAgents::Batches(mut new_batches) => batches.append(&mut new_batches),
+            }
+        }
+
+        // Agents without an `agent_id` get their id here, so it's derived from the seed in seeded
+        // simulation runs
+        let seed = sim_config.simulation_config().package_creator.seed;
+        for (index, agent) in agents
+            .iter_mut()
+            .filter(|agent| agent.agent_id.is_unassigned())
+            .enumerate()
+        {
+            agent.assign_id(
+                seed.map_or_else(AgentId::generate, |seed| seed.initial_agent_id(index)),
+            );
         }
 
         tracing::trace!("Init packages finished, building state");
-        let state = State::from_agent_states(&agents, sim_config.to_state_create_parameters())?;
+        let create_parameters = sim_config.to_state_create_parameters();
+        let state = if batches.is_empty() {
+            State::from_agent_states(&agents, create_parameters)?
+        } else {
+            if !agents.is_empty() {
+                batches.push(
+                    agents
+                        .as_slice()
+                        .to_agent_batch(&create_parameters.agent_schema)?,
+                );
+            }
+            State::from_record_batches(&batches, create_parameters)?
+        };
         Ok(state)
     }
 
@@ -415,6 +460,7 @@ impl Packages {
         &mut self,
         state: &Arc<State>,
         context: &Arc<Context>,
+        step: usize,
     ) -> Result<Vec<Output>> {
         // Execute packages in parallel and collect the data
         let mut futs = FuturesOrdered::new();
@@ -435,14 +481,14 @@ impl Packages {
                         let _entered = current_span.entered();
                         pkg.span()
                     };
-                    let res = block_on(pkg.run(state, context).instrument(package_span));
+                    let res = block_on(pkg.run(state, context, step).instrument(package_span));
                     (pkg, res)
                 })
             } else {
                 let span = pkg.span();
                 tokio::task::spawn(
-                    async {
-                        let res = pkg.run(state, context).instrument(span).await;
+                    async move {
+                        let res = pkg.run(state, context, step).instrument(span).await;
                         (pkg, res)
                     }
                     .in_current_span(),
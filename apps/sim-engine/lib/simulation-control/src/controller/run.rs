@@ -1,15 +1,25 @@
 use std::sync::Arc;
 
 use execution::{
-    package::simulation::{output::persistence::SimulationOutputPersistence, SimulationId},
+    package::simulation::{
+        output::{
+            analysis::AnalysisOutput,
+            persistence::{GlobalsUpdate, SimulationOutputPersistence},
+            Output,
+        },
+        state::topology::TopologyConfig,
+        SimulationId,
+    },
     runner::RunnerError,
 };
 use experiment_structure::SimulationRunConfig;
 use futures::FutureExt;
+use stateful::global::Globals;
 use tokio::time::Duration;
 
 use crate::{
     agent_control::AgentControl,
+    checkpoint::{Checkpoint, CheckpointConfig},
     comms::{control::SimCtlRecv, status::SimStatusSend, Comms},
     controller::{
         error::{Error, Result},
@@ -31,7 +41,8 @@ enum LoopControl {
 /// # Initialization
 /// - Create an uninitialized store (i.e. create the underlying state of the simulation)
 /// - Create the underlying simulation engine which
-///   - Runs the appropriate [init package][init] to initialize [`Agent`] state
+///   - Runs the appropriate [init package][init] to initialize [`Agent`] state, or restores the
+///     [`Agent`] state from a [`Checkpoint`] when resuming a simulation run
 ///   - Creates an empty [`Context`] by calling the [context packages][context]
 ///   - Initializes the datastore with [`Agent`] state and the empty [`Context`]
 /// - Calls the [output packages][output] on the initial state
@@ -39,12 +50,14 @@ enum LoopControl {
 ///
 /// # The Main Loop
 /// The repeating top-level logic of a simulation step.
-/// - Check if the sim has been told to stop by the Experiment Controller
+/// - Check if the sim has been told to pause, step, or stop by the Experiment Controller
+/// - Apply changes of the globals sent by the Experiment Controller
 /// - Tells the simulation engine to take a step [`Engine::next()`]:
 ///   - Runs [Context Packages][context] in parallel
 ///   - Runs [State Packages][state] sequentially
 ///   - Runs [Output packages][output]
 /// - Persists Output
+/// - Writes a [`Checkpoint`] every [`CheckpointConfig::interval`] steps, if configured
 /// - Sends an update on the Step result to the Experiment Controller
 ///
 /// [init]: execution::package::simulation::init
@@ -61,26 +74,71 @@ pub async fn sim_run<P: SimulationOutputPersistence>(
     mut sim_from_exp: SimCtlRecv,
     mut sims_to_exp: SimStatusSend,
     mut persistence_service: P,
+    checkpoint_config: Option<CheckpointConfig>,
 ) -> Result<SimulationId> {
     let sim_run_id = config.simulation_config().id;
-    let max_num_steps = config.simulation_config().max_num_steps;
+    let checkpoint = config
+        .experiment_config()
+        .experiment_run
+        .resume_from()
+        .map(Checkpoint::open)
+        .transpose()
+        .map_err(|e| Error::from(e.to_string()))?;
+    // A resumed simulation run continues counting the steps from the checkpoint
+    let start_step = checkpoint
+        .as_ref()
+        .map_or(0, |checkpoint| checkpoint.metadata().step);
+    let max_num_steps = start_step + config.simulation_config().max_num_steps;
     tracing::info!(steps = &max_num_steps, "Beginning simulation run");
 
-    let mut engine = Engine::new(packages, comms, config.clone())
+    let mut engine = Engine::new(packages, comms, config.clone(), checkpoint.as_ref())
         .await
         .map_err(|sim_err| Error::from(sim_err.to_string()))?;
+    // The globals of a resumed simulation run are the globals at the checkpoint, which may differ
+    // from the globals of the project if they were patched before the checkpoint was written
+    let initial_globals = match &checkpoint {
+        Some(checkpoint) => {
+            tracing::info!(
+                "Resuming from checkpoint {:?} at step {start_step}",
+                checkpoint.path()
+            );
+            // The random streams are derived from the seed of the simulation run, so they only
+            // continue the streams of the checkpointed run if the seeds match
+            if checkpoint.metadata().seed != config.simulation_config().package_creator.seed {
+                tracing::warn!(
+                    "The checkpoint was written with a different seed, the resumed simulation \
+                     run won't reproduce the original run"
+                );
+            }
+            let globals = checkpoint.metadata().globals.clone();
+            let config_globals = &config.simulation_config().package_creator.globals;
+            if &globals != config_globals {
+                engine
+                    .update_globals(Arc::new(globals.clone()), config_globals)
+                    .map_err(|e| Error::from(e.to_string()))?;
+            }
+            globals
+        }
+        None => config.simulation_config().package_creator.globals.clone(),
+    };
 
     tracing::trace!("Initialized the engine, running output packages to persist initial state");
-    // We also store the initial state in the persistence service
+    // We also store the initial state in the persistence service. The initial state of a resumed
+    // simulation run is the state at the checkpoint.
     let initial_output = engine
-        .run_output_packages()
+        .run_output_packages(start_step)
         .await
         .map_err(|e| Error::from(e.to_string()))?;
+    // The analysis output of the last step is reported to the experiment when the run has ended
+    let mut analysis_output = find_analysis_output(&initial_output);
     persistence_service.add_step_output(initial_output).await?;
     let now = std::time::Instant::now();
-    let mut steps_taken = 0;
+    let mut steps_taken = start_step;
     let mut early_stop = false;
     let mut stop_msg = Vec::new();
+    let mut paused = false;
+    let mut globals = initial_globals.clone();
+    let mut globals_patches = Vec::new();
 
     tracing::trace!("Starting main loop");
     'sim_main: loop {
@@ -91,22 +149,47 @@ pub async fn sim_run<P: SimulationOutputPersistence>(
             break;
         }
 
-        if let LoopControl::Stop = maybe_handle_sim_ctl_msg(&mut sim_from_exp).await? {
+        if let LoopControl::Stop =
+            maybe_handle_sim_ctl_msg(&mut sim_from_exp, &mut paused, &mut globals_patches).await?
+        {
             // The experiment controller has signalled to stop
             break;
         }
 
+        // Patches are only persisted after every package accepted them
+        for patch in globals_patches.drain(..) {
+            let patched = match patch_globals(&globals, &patch) {
+                Ok(patched) => patched,
+                Err(err) => {
+                    tracing::warn!("Ignoring globals update {patch}: {err}");
+                    continue;
+                }
+            };
+            match engine.update_globals(Arc::new(patched.clone()), &globals) {
+                Ok(()) => {}
+                Err(crate::Error::InvalidGlobals(err)) => {
+                    tracing::warn!("Ignoring globals update {patch}: {err}");
+                    continue;
+                }
+                Err(err) => return Err(Error::from(err.to_string())),
+            }
+            globals = patched;
+            persistence_service
+                .add_globals_update(GlobalsUpdate {
+                    step: current_step,
+                    patch,
+                })
+                .await?;
+        }
+
         // Take a step in the simulation
         let step_result = match engine.next(current_step).await {
             Ok(step_result) => step_result,
             Err(error) => {
                 tracing::error!("Got error within the engine step process: {:?}", error);
                 // Try to persist before exiting
-                let persistence_result = Some(
-                    persistence_service
-                        .finalize(&config.simulation_config().package_creator.globals)
-                        .await?,
-                );
+                let persistence_result =
+                    Some(persistence_service.finalize(&initial_globals).await?);
                 let runner_error = RunnerError {
                     message: Some(format!("{:?}", error)),
                     code: None,
@@ -138,9 +221,20 @@ pub async fn sim_run<P: SimulationOutputPersistence>(
         };
 
         // Persist the output
+        if let Some(output) = find_analysis_output(&step_result.output) {
+            analysis_output = Some(output);
+        }
         persistence_service
             .add_step_output(step_result.output)
             .await?;
+        if let Some(checkpoint_config) = &checkpoint_config {
+            if checkpoint_config.is_checkpoint_step(current_step) {
+                let path = engine
+                    .write_checkpoint(checkpoint_config, current_step, &globals)
+                    .map_err(|e| Error::from(e.to_string()))?;
+                tracing::debug!("Wrote checkpoint of step {current_step} to {path:?}");
+            }
+        }
         if let AgentControl::Stop(msg) = step_result.agent_control {
             early_stop = true;
             stop_msg = msg;
@@ -169,9 +263,7 @@ pub async fn sim_run<P: SimulationOutputPersistence>(
     let main_loop_dur = now.elapsed().as_millis();
 
     let now = std::time::Instant::now();
-    let persistence_result = persistence_service
-        .finalize(&config.simulation_config().package_creator.globals)
-        .await?;
+    let persistence_result = persistence_service.finalize(&initial_globals).await?;
     sims_to_exp
         .send(
             SimStatus::ended(
@@ -180,6 +272,7 @@ pub async fn sim_run<P: SimulationOutputPersistence>(
                 early_stop,
                 stop_msg,
                 persistence_result,
+                analysis_output,
             )
             .map_err(|sim_err| Error::from(format!("Simulation error: {:?}", sim_err)))?,
         )
@@ -203,30 +296,106 @@ pub async fn sim_run<P: SimulationOutputPersistence>(
     Ok(config.simulation_config().id)
 }
 
-async fn maybe_handle_sim_ctl_msg(sim_from_exp: &mut SimCtlRecv) -> Result<LoopControl> {
-    if let Some(Some(control)) = sim_from_exp.recv().now_or_never() {
-        match control {
-            SimControl::Pause => loop {
-                if let Some(control) = sim_from_exp.recv().await {
-                    match control {
-                        SimControl::Pause => {
-                            tracing::warn!("Pausing when already paused");
-                        }
-                        SimControl::Resume => {
-                            break;
-                        }
-                        SimControl::Stop => return Ok(LoopControl::Stop),
-                    }
-                } else {
+fn find_analysis_output(output: &[Output]) -> Option<AnalysisOutput> {
+    output.iter().find_map(|output| match output {
+        Output::AnalysisOutput(output) => Some(output.clone()),
+        Output::JsonStateOutput(_) => None,
+    })
+}
+
+/// Handles the control messages sent to the simulation run.
+///
+/// While the run is paused, this waits for the next message. A [`SimControl::Step`] keeps the run
+/// paused, but returns so that exactly one step is taken. Patches of the globals are collected in
+/// `globals_patches` to be applied before the next step.
+/// Applies the JSON merge-`patch` to a copy of `globals` and validates the result.
+///
+/// # Errors
+///
+/// - if `patch` is not an object
+/// - if the topology of the patched globals is invalid
+fn patch_globals(globals: &Globals, patch: &serde_json::Value) -> Result<Globals> {
+    let mut patched = globals.clone();
+    patched
+        .merge_patch(patch)
+        .map_err(|err| Error::from(err.to_string()))?;
+    TopologyConfig::from_globals(&patched)
+        .map_err(|err| Error::from(format!("Invalid topology: {err}")))?;
+    Ok(patched)
+}
+
+async fn maybe_handle_sim_ctl_msg(
+    sim_from_exp: &mut SimCtlRecv,
+    paused: &mut bool,
+    globals_patches: &mut Vec<serde_json::Value>,
+) -> Result<LoopControl> {
+    loop {
+        let control = if *paused {
+            match sim_from_exp.recv().await {
+                Some(control) => control,
+                None => {
                     tracing::warn!("Experiment runner exited while paused.");
                     return Ok(LoopControl::Stop);
                 }
-            },
+            }
+        } else {
+            match sim_from_exp.recv().now_or_never() {
+                Some(Some(control)) => control,
+                _ => return Ok(LoopControl::Continue),
+            }
+        };
+
+        match control {
+            SimControl::Pause => {
+                if *paused {
+                    tracing::warn!("Pausing when already paused");
+                }
+                *paused = true;
+            }
             SimControl::Resume => {
-                tracing::warn!("Resuming when not paused");
+                if !*paused {
+                    tracing::warn!("Resuming when not paused");
+                }
+                *paused = false;
+            }
+            SimControl::Step => {
+                if *paused {
+                    return Ok(LoopControl::Continue);
+                }
+                tracing::warn!("Stepping when not paused");
             }
             SimControl::Stop => return Ok(LoopControl::Stop),
+            SimControl::UpdateGlobals(patch) => globals_patches.push(patch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn patch_globals_validates_topology() {
+        let globals = Globals(json!({ "topology": { "x_bounds": [0, 10] }, "a": 1 }));
+
+        let patched = patch_globals(&globals, &json!({ "a": 2 })).unwrap();
+        assert_eq!(
+            patched,
+            Globals(json!({ "topology": { "x_bounds": [0, 10] }, "a": 2 }))
+        );
+
+        for patch in [
+            json!(5),
+            json!({ "topology": { "x_bounds": "wide" } }),
+            json!({ "topology": { "search_radius": [1] } }),
+        ] {
+            assert!(patch_globals(&globals, &patch).is_err(), "{patch}");
         }
+        assert_eq!(
+            globals,
+            Globals(json!({ "topology": { "x_bounds": [0, 10] }, "a": 1 }))
+        );
     }
-    Ok(LoopControl::Continue)
 }
@@ -1,7 +1,13 @@
+use serde::{Deserialize, Serialize};
+
 // Sent from experiment main loop to sim runs.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SimControl {
     Pause,
     Resume,
     Stop,
+    /// Takes a single step while the simulation run is paused, and pauses again afterwards.
+    Step,
+    /// Applies a JSON merge-patch to the globals before the next step is taken.
+    UpdateGlobals(serde_json::Value),
 }
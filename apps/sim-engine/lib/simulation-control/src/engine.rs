@@ -1,4 +1,4 @@
-use std::{mem, sync::Arc};
+use std::{mem, path::PathBuf, sync::Arc};
 
 use execution::package::simulation::output::Output;
 use experiment_structure::SimulationRunConfig;
@@ -6,7 +6,8 @@ use memory::shared_memory::MemoryId;
 use stateful::{
     agent::AgentBatchPool,
     context::Context,
-    message::{MessageBatchPool, MessageMap},
+    global::Globals,
+    message::{DelayedMessages, MessageBatchPool, MessageMap},
     proxy::BatchPool,
     state::{State, StateBatchPools, StateSnapshot},
 };
@@ -14,6 +15,7 @@ use tracing::Instrument;
 
 use crate::{
     agent_control::AgentControl,
+    checkpoint::{Checkpoint, CheckpointConfig},
     command::{Commands, CreateRemovePlanner, StopCommand},
     comms::Comms,
     controller::Packages,
@@ -28,28 +30,43 @@ pub struct Engine {
     comms: Arc<Comms>,
     config: Arc<SimulationRunConfig>,
     stop_messages: Vec<StopCommand>,
+    /// Messages sent with a delay, which are not received yet
+    delayed_messages: DelayedMessages,
 }
 
 impl Engine {
     /// Creates a new simulation engine from a given collection of Packages, an uninitialized
     /// store, a configuration for the simulation run, and a set of Comms to communicate with the
     /// Worker Pool.
-    /// - Initializes Agent State through the init packages
+    /// - Initializes Agent State through the init packages, or restores it from the `checkpoint`
+    ///   if the simulation run is resumed
     /// - Creates an empty Context
     /// - Initializes the Store using the Agent State and empty Context
     pub async fn new(
         mut packages: Packages,
         comms: Comms,
         config: Arc<SimulationRunConfig>,
+        checkpoint: Option<&Checkpoint>,
     ) -> Result<Engine> {
         let comms = Arc::new(comms);
 
-        let state = packages
-            .run_init(Arc::clone(&config.clone()))
-            .instrument(tracing::info_span!("init_packages"))
-            .await?;
-        tracing::trace!("Init packages completed, building empty context");
+        let state = match checkpoint {
+            Some(checkpoint) => {
+                let _span = tracing::info_span!("restore_checkpoint").entered();
+                checkpoint.read_state(&config)?
+            }
+            None => {
+                packages
+                    .run_init(Arc::clone(&config.clone()))
+                    .instrument(tracing::info_span!("init_packages"))
+                    .await?
+            }
+        };
+        tracing::trace!("Agent state initialized, building empty context");
         let context = packages.empty_context(&config, state.num_agents())?;
+        let delayed_messages = checkpoint
+            .map(|checkpoint| checkpoint.metadata().delayed_messages.clone())
+            .unwrap_or_default();
 
         Ok(Engine {
             packages,
@@ -57,6 +74,7 @@ impl Engine {
             comms,
             config,
             stop_messages: Vec::new(),
+            delayed_messages,
         })
     }
 
@@ -82,7 +100,7 @@ impl Engine {
             .instrument(tracing::info_span!("state_packages"))
             .await?;
         let output = self
-            .run_output_packages()
+            .run_output_packages(current_step)
             .instrument(tracing::info_span!("output_packages"))
             .await?;
         let agent_control = if !self.stop_messages.is_empty() {
@@ -100,6 +118,46 @@ impl Engine {
         Ok(result)
     }
 
+    /// Replaces the `previous` globals of the simulation run.
+    ///
+    /// The packages are updated immediately and the language runners are notified before any task
+    /// of the next step is sent to them, so the next step uses the new globals.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidGlobals`] if a package rejects the globals. The packages are reset to the
+    ///   `previous` globals, so they are never left half-updated, and the runners are not notified.
+    pub fn update_globals(&mut self, globals: Arc<Globals>, previous: &Globals) -> Result<()> {
+        tracing::debug!("Updating globals");
+        if let Err(err) = self.packages.update_globals(&globals) {
+            self.packages.update_globals(previous)?;
+            return Err(Error::InvalidGlobals(err.to_string()));
+        }
+        self.comms.update_globals(globals)
+    }
+
+    /// Writes a checkpoint of the current state, which was reached after `step`, and returns the
+    /// folder it was written to.
+    pub fn write_checkpoint(
+        &self,
+        checkpoint_config: &CheckpointConfig,
+        step: usize,
+        globals: &Globals,
+    ) -> Result<PathBuf> {
+        let (state, _) = self
+            .store
+            .as_ref()
+            .expect("state and context should be present");
+        Checkpoint::write(
+            checkpoint_config,
+            &self.config,
+            step,
+            globals,
+            state,
+            &self.delayed_messages,
+        )
+    }
+
     /// TODO: DOC, the "see" is wrong
     /// Finalize state (see [`Engine::finalize_agent_state`]) and create a new context for the
     /// agents.
@@ -125,7 +183,7 @@ impl Engine {
 
         let snapshot = {
             let _span = tracing::debug_span!("prepare_context_packages").entered();
-            self.prepare_for_context_packages(&mut state, &mut context)?
+            self.prepare_for_context_packages(&mut state, &mut context, current_step)?
         };
 
         let snapshot_state_proxy = snapshot.state.read()?;
@@ -207,7 +265,8 @@ impl Engine {
         Ok(())
     }
 
-    pub async fn run_output_packages(&mut self) -> Result<Vec<Output>> {
+    /// Runs the output packages on the current state, which was reached after `step`.
+    pub async fn run_output_packages(&mut self, step: usize) -> Result<Vec<Output>> {
         let (mut state, context) = self
             .store
             .take()
@@ -220,7 +279,7 @@ impl Engine {
         let state = Arc::new(state);
         let context = Arc::new(context);
 
-        let output = self.packages.run_output(&state, &context).await?;
+        let output = self.packages.run_output(&state, &context, step).await?;
         let state = Arc::try_unwrap(state)
             .map_err(|_| Error::from("Unable to unwrap state after output package execution"))?;
         let context = Arc::try_unwrap(context)
@@ -233,6 +292,9 @@ impl Engine {
     /// Prepare for Context Packages
     ///
     /// The following operations are performed:
+    /// 0) Messages which are not due in `current_step` are held back and held messages which are
+    /// due are put back into the outbox of their sender.
+    ///
     /// 1) A message map Recipient -> Vec<MessageReference>
     /// 2) Handling agent messages to "hash", i.e. performing
     /// agent creation and removals.
@@ -249,10 +311,13 @@ impl Engine {
         &mut self,
         state: &mut State,
         context: &mut Context,
+        current_step: usize,
     ) -> Result<StateSnapshot> {
         tracing::trace!("Preparing for context packages");
+        self.delayed_messages
+            .hold_and_release(&mut state.state_mut().message_pool, current_step)?;
         let message_map = state.message_map()?;
-        self.handle_messages(state, &message_map)?;
+        self.handle_messages(state, &message_map, current_step)?;
         let message_pool = self.finalize_agent_messages(state, context)?;
         let agent_pool = self.finalize_agent_state(state, context)?;
         let mut state_view = StateBatchPools {
@@ -270,8 +335,13 @@ impl Engine {
     ///
     /// Operates based on the "create_agent", "remove_agent", and "stop" messages sent to "hash"
     /// through agent inboxes. Also creates and removes agents that have been requested by State
-    /// packages.
-    fn handle_messages(&mut self, state: &mut State, message_map: &MessageMap) -> Result<()> {
+    /// packages. New agents without an `agent_id` get an id for `current_step`.
+    fn handle_messages(
+        &mut self,
+        state: &mut State,
+        message_map: &MessageMap,
+        current_step: usize,
+    ) -> Result<()> {
         let mut commands = {
             // it is necessary to drop `message_proxies` after reading the commands because it
             // contains a strong reference to the `MessageBatch`; if this strong
@@ -282,6 +352,10 @@ impl Engine {
             Commands::from_hash_messages(message_map, &message_proxies)?
         };
         commands.merge(self.comms.take_commands()?);
+        commands.assign_agent_ids(
+            current_step,
+            self.config.simulation_config().package_creator.seed,
+        );
         commands.verify(&self.config.simulation_config().schema.agent_schema)?;
         self.stop_messages = commands.stop;
 
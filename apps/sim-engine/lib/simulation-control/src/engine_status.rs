@@ -10,7 +10,7 @@ use stateful::global::Globals;
 
 use crate::SimStatus;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum EngineStatus {
     Started,
     SimStatus(SimStatus),
@@ -31,6 +31,12 @@ pub enum Error {
 
     #[error("State sync failed: {0}")]
     StateSync(String),
+
+    #[error("Checkpoint error: {0}")]
+    Checkpoint(String),
+
+    #[error("The globals were rejected by a package: {0}")]
+    InvalidGlobals(String),
 }
 
 impl Error {
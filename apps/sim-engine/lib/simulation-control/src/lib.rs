@@ -5,6 +5,9 @@
 //! [`SimulationController`]. The [`Packages`] struct contains different [simulation packages]
 //! created from the [`PackageCreators`]. It's used to run the different packages.
 //!
+//! The [`checkpoint`] module writes the state of simulation runs to disk, so they can be resumed
+//! later on.
+//!
 //! The [`command`] module contains the commands that are sent to the [simulation packages] using
 //! the [`comms`] module.
 //!
@@ -17,6 +20,7 @@
 
 #![cfg_attr(test, feature(test))]
 
+pub mod checkpoint;
 pub mod command;
 pub mod comms;
 pub mod controller;
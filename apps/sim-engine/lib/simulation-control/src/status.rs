@@ -1,5 +1,8 @@
 use execution::{
-    package::simulation::{output::persistence::OutputPersistenceResult, SimulationId},
+    package::simulation::{
+        output::{analysis::AnalysisOutput, persistence::OutputPersistenceResult},
+        SimulationId,
+    },
     runner::RunnerError,
 };
 use serde::{Deserialize, Serialize};
@@ -7,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use crate::{command::StopCommand, Result};
 
 // Sent from sim runs to experiment main loop.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct SimStatus {
     pub sim_id: SimulationId,
     pub steps_taken: isize,
@@ -15,6 +18,8 @@ pub struct SimStatus {
     pub stop_msg: Vec<StopCommand>,
     pub stop_signal: bool,
     pub persistence_result: Option<(String, serde_json::Value)>,
+    /// The analysis output of the last step, set once the simulation run has ended.
+    pub analysis_output: Option<AnalysisOutput>,
     // TODO: OS do we need these within SimStatus or should they be handled elsewhere, such as
     // WorkerPoolToExpCtlMsg::Errors and WorkerPoolToExpCtlMsg::Warnings
     pub error: Option<RunnerError>,
@@ -32,6 +37,7 @@ impl SimStatus {
             stop_msg: vec![],
             stop_signal: false,
             persistence_result: None,
+            analysis_output: None,
             error: None,
             warnings: vec![],
             running: false,
@@ -62,6 +68,7 @@ impl SimStatus {
         early_stop: bool,
         stop_msg: Vec<StopCommand>,
         persistence_result: P,
+        analysis_output: Option<AnalysisOutput>,
     ) -> Result<SimStatus> {
         let persistence_result = OutputPersistenceResult::into_value(persistence_result)
             .map(|(a, b)| (a.to_string(), b))?;
@@ -72,6 +79,7 @@ impl SimStatus {
             stop_signal: true,
             running: false,
             persistence_result: Some(persistence_result),
+            analysis_output,
             ..SimStatus::new(sim_id)
         })
     }
@@ -57,6 +57,7 @@ fn get_schema() -> Result<()> {
 
     let mut meta = BTreeMap::new();
     meta.insert("any_type_fields".into(), "".into());
+    meta.insert("categorical_fields".into(), "{}".into());
     meta.insert("nullable".into(), "1,1,0,1".into());
     let target = Schema::from(vec![
         Field::new("_PRIVATE_0_test1", DataType::Boolean, true),
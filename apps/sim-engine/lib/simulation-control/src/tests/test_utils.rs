@@ -306,6 +306,7 @@ pub fn dummy_sim_run_config() -> SimulationRunConfig {
             num_workers: 0,
         }),
         base_globals: globals.clone(),
+        datasets: Arc::new([]),
     });
 
     let persistence_config = package_creators
@@ -22,7 +22,7 @@ pub mod arrow;
 
 pub use self::{
     arrow::{AgentBatch, AgentBatchPool},
-    field::{Agent, AgentId, AgentStateField},
+    field::{Agent, AgentId, AgentStateField, StrVec},
     into_agent::IntoAgents,
     name::AgentName,
     schema::AgentSchema,
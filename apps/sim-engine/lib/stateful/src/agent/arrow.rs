@@ -11,12 +11,12 @@ mod pool;
 mod record_batch;
 
 pub use self::{
-    array::IntoRecordBatch,
+    array::{columns_to_agent_batch, IntoRecordBatch},
     batch::AgentBatch,
     iterator::{
         agent_id_iter, agent_name_iter, bool_iter, exists_iter, f64_iter, index_iter,
-        json_serialized_value_iter, json_value_iter_cols, position_iter, search_radius_iter,
-        str_iter,
+        json_categorical_value_iter_cols, json_serialized_value_iter, json_value_iter_cols,
+        position_iter, search_radius_iter, str_iter, str_list_iter,
     },
     pool::AgentBatchPool,
 };
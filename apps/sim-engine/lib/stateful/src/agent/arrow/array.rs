@@ -3,18 +3,24 @@ use std::sync::Arc;
 use arrow2::{
     array::{
         Array, FixedSizeBinaryArray, FixedSizeListArray, MutableFixedSizeBinaryArray,
-        PrimitiveArray,
+        PrimitiveArray, Utf8Array,
     },
     chunk::Chunk,
-    datatypes::{DataType, Schema},
+    compute::cast::{cast, CastOptions},
+    datatypes::{DataType, Field, Schema},
 };
 use memory::arrow::{
-    json_vals_to_any_type_col, json_vals_to_bool, json_vals_to_col, json_vals_to_primitive,
-    json_vals_to_utf8, record_batch::RecordBatch,
+    json_vals_to_any_type_col, json_vals_to_bool, json_vals_to_categorical_col, json_vals_to_col,
+    json_vals_to_primitive, json_vals_to_utf8, record_batch::RecordBatch,
 };
+use serde_json::Value;
+use uuid::Uuid;
 
 use crate::{
-    agent::{arrow::PREVIOUS_INDEX_FIELD_KEY, field::AgentId, Agent, AgentSchema, AgentStateField},
+    agent::{
+        arrow::PREVIOUS_INDEX_FIELD_KEY, field::AgentId, Agent, AgentSchema, AgentStateField,
+        BUILTIN_FIELDS,
+    },
     field::{FieldTypeVariant, RootFieldKey, UUID_V4_LEN},
     message::{self, arrow::array::MessageArray},
     Error, Result,
@@ -113,19 +119,24 @@ impl IntoRecordBatch for &[&Agent] {
                 Box::new(json_vals_to_bool(vals)?)
             } else if name == PREVIOUS_INDEX_FIELD_KEY {
                 previous_index_to_empty_col(self.len(), field.data_type().clone())?
-            } else if matches!(
-                schema
+            } else {
+                match &schema
                     .field_spec_map
                     .get_field_spec(&RootFieldKey::new(name.to_string()))?
                     .inner
                     .field_type
-                    .variant,
-                FieldTypeVariant::AnyType
-            ) {
-                // Any-type (JSON string) column
-                json_vals_to_any_type_col(vals, field.data_type())?
-            } else {
-                json_vals_to_col(vals, field, field.is_nullable)?
+                    .variant
+                {
+                    // Any-type (JSON string) column
+                    FieldTypeVariant::AnyType => {
+                        json_vals_to_any_type_col(vals, field.data_type())?
+                    }
+                    // Category index column
+                    FieldTypeVariant::Categorical(categories) => {
+                        json_vals_to_categorical_col(vals, categories, field.is_nullable)?
+                    }
+                    _ => json_vals_to_col(vals, field, field.is_nullable)?,
+                }
             };
             cols.push(col);
         }
@@ -134,6 +145,198 @@ impl IntoRecordBatch for &[&Agent] {
     }
 }
 
+/// Converts columns read from a columnar file, e.g. CSV, Parquet, or Arrow IPC, into a record
+/// batch of the agent `schema`.
+///
+/// Columns are matched to the agent fields by name and cast to the type of the field. Columns,
+/// which can't be cast, are converted through their JSON representation, so a CSV column
+/// `position` may contain `[1, 2, 0]`. Fields without a matching column get the same value as a
+/// key missing from an agent of a JSON initial state, and agents without an `agent_id` get the
+/// next id returned by `new_agent_id`. Columns without a matching field as well as `messages` are
+/// ignored.
+pub fn columns_to_agent_batch(
+    file_schema: &Schema,
+    columns: &Chunk<Box<dyn Array>>,
+    schema: &AgentSchema,
+    mut new_agent_id: impl FnMut() -> AgentId,
+) -> Result<RecordBatch> {
+    let num_agents = columns.len();
+    let mut cols = Vec::with_capacity(schema.arrow.fields.len());
+
+    for field in &schema.arrow.fields {
+        let name = field.name.as_str();
+        let column = file_schema
+            .fields
+            .iter()
+            .position(|file_field| file_field.name == field.name)
+            .map(|index| columns.arrays()[index].as_ref());
+
+        let col = if name == AgentStateField::AgentId.name() {
+            column_to_id_col(column, num_agents, &mut new_agent_id)?
+        } else if name == AgentStateField::Messages.name() {
+            Box::new(MessageArray::new(num_agents))
+        } else if name == PREVIOUS_INDEX_FIELD_KEY {
+            previous_index_to_empty_col(num_agents, field.data_type().clone())?
+        } else if let Some(column) = column {
+            column_to_agent_col(column, field, schema).map_err(|error| {
+                Error::from(format!("Could not convert column `{name}`: {error}"))
+            })?
+        } else {
+            json_vals_to_agent_col(vec![Value::Null; num_agents], field, schema)?
+        };
+        cols.push(col);
+    }
+    Ok(RecordBatch::new(schema.arrow.clone(), Chunk::new(cols)))
+}
+
+fn field_type_variant<'s>(
+    field: &Field,
+    schema: &'s AgentSchema,
+) -> Result<Option<&'s FieldTypeVariant>> {
+    if BUILTIN_FIELDS.contains(&field.name.as_str()) {
+        return Ok(None);
+    }
+    Ok(Some(
+        &schema
+            .field_spec_map
+            .get_field_spec(&RootFieldKey::new(field.name.clone()))?
+            .inner
+            .field_type
+            .variant,
+    ))
+}
+
+fn column_to_agent_col(
+    column: &dyn Array,
+    field: &Field,
+    schema: &AgentSchema,
+) -> Result<Box<dyn Array>> {
+    match field_type_variant(field, schema)? {
+        // Any-type columns are stored as JSON strings, so the values are always re-serialized
+        Some(FieldTypeVariant::AnyType) => {
+            return Ok(json_vals_to_any_type_col(
+                column_to_json_vals(column, true)?,
+                field.data_type(),
+            )?);
+        }
+        // Columns of the index type are assumed to already contain category indices
+        Some(FieldTypeVariant::Categorical(categories))
+            if column.data_type() != field.data_type() =>
+        {
+            return Ok(json_vals_to_categorical_col(
+                column_to_json_vals(column, false)?,
+                categories,
+                field.is_nullable,
+            )?);
+        }
+        _ => {}
+    }
+
+    if column.data_type() == field.data_type() {
+        return Ok(column.to_boxed());
+    }
+    // Casting strings to nested types would wrap every string into a list instead of parsing it
+    let is_nested = matches!(
+        field.data_type(),
+        DataType::List(_) | DataType::FixedSizeList(..) | DataType::Struct(_)
+    );
+    if is_nested && matches!(column.data_type(), DataType::Utf8 | DataType::LargeUtf8) {
+        return json_vals_to_agent_col(column_to_json_vals(column, true)?, field, schema);
+    }
+    match cast(column, field.data_type(), CastOptions::default()) {
+        // Values, which can't be cast, are turned into nulls
+        Ok(col) if col.null_count() == column.null_count() => Ok(col),
+        Ok(_) => Err(Error::from(format!(
+            "Not all values can be converted from {:?} to {:?}",
+            column.data_type(),
+            field.data_type()
+        ))),
+        Err(_) => json_vals_to_agent_col(column_to_json_vals(column, true)?, field, schema),
+    }
+}
+
+/// Reads the values of `column` as strings, which are parsed as JSON if `parse` is set.
+///
+/// Strings, which aren't valid JSON, are kept as JSON strings. Empty strings, e.g. empty CSV cells,
+/// are missing values.
+fn column_to_json_vals(column: &dyn Array, parse: bool) -> Result<Vec<Value>> {
+    let strings = cast(column, &DataType::Utf8, CastOptions::default())?;
+    let strings = strings
+        .as_any()
+        .downcast_ref::<Utf8Array<i32>>()
+        .ok_or_else(|| Error::from("Expected a UTF-8 array"))?;
+    Ok(strings
+        .iter()
+        .map(|string| match string {
+            None | Some("") => Value::Null,
+            Some(string) if parse => {
+                serde_json::from_str(string).unwrap_or_else(|_| Value::String(string.to_owned()))
+            }
+            Some(string) => Value::String(string.to_owned()),
+        })
+        .collect())
+}
+
+/// Creates the column of `field` from JSON values like [`IntoRecordBatch::to_agent_batch`].
+fn json_vals_to_agent_col(
+    vals: Vec<Value>,
+    field: &Field,
+    schema: &AgentSchema,
+) -> Result<Box<dyn Array>> {
+    if field.name == AgentStateField::Hidden.name() {
+        return Ok(Box::new(json_vals_to_bool(vals)?));
+    }
+    match field_type_variant(field, schema)? {
+        // TODO: built-ins should take nullability from the schema
+        None => Ok(json_vals_to_col(vals, field, true)?),
+        Some(FieldTypeVariant::AnyType) => Ok(json_vals_to_any_type_col(vals, field.data_type())?),
+        Some(FieldTypeVariant::Categorical(categories)) => Ok(json_vals_to_categorical_col(
+            vals,
+            categories,
+            field.is_nullable,
+        )?),
+        Some(_) => Ok(json_vals_to_col(vals, field, field.is_nullable)?),
+    }
+}
+
+fn column_to_id_col(
+    column: Option<&dyn Array>,
+    num_agents: usize,
+    mut new_agent_id: impl FnMut() -> AgentId,
+) -> Result<Box<dyn Array>> {
+    let mut builder = MutableFixedSizeBinaryArray::with_capacity(UUID_V4_LEN, num_agents);
+    match column {
+        Some(column)
+            if column.data_type() == &DataType::FixedSizeBinary(UUID_V4_LEN)
+                && column.null_count() == 0 =>
+        {
+            return Ok(column.to_boxed());
+        }
+        Some(column) => {
+            let ids = cast(column, &DataType::Utf8, CastOptions::default())?;
+            let ids = ids
+                .as_any()
+                .downcast_ref::<Utf8Array<i32>>()
+                .ok_or_else(|| Error::from("Expected a UTF-8 array"))?;
+            for id in ids {
+                let id = match id {
+                    Some(id) => AgentId::from_bytes(*Uuid::parse_str(id)?.as_bytes()),
+                    None => new_agent_id(),
+                };
+                builder.push(Some(id.as_bytes()));
+            }
+        }
+        None => {
+            for _ in 0..num_agents {
+                builder.push(Some(new_agent_id().as_bytes()));
+            }
+        }
+    }
+    let array: FixedSizeBinaryArray = builder.into();
+    debug_assert_eq!(array.len(), num_agents);
+    Ok(array.boxed())
+}
+
 // `get_agent_id_array` is needed for public interface, but
 // this function avoids copying ids to separate `Vec`.
 fn agents_to_id_col(agents: &[&Agent]) -> Result<Box<dyn Array>> {
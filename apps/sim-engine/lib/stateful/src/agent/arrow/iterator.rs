@@ -52,6 +52,25 @@ pub fn json_value_iter_cols<'b: 'a, 'a>(
     Ok(Box::new(iterables.into_iter().flatten()))
 }
 
+pub fn json_categorical_value_iter_cols<'b: 'a, 'a>(
+    agent_pool: &'a [&'b AgentBatch],
+    field_name: &str,
+    categories: &[String],
+) -> Result<Box<dyn Iterator<Item = serde_json::Value> + Send + Sync + 'a>> {
+    let mut iterables = Vec::with_capacity(agent_pool.len());
+
+    // Collect iterators first, because we want to check for any errors.
+    for agent_batch in agent_pool {
+        let iterable = record_batch::json_categorical_values(
+            agent_batch.batch.record_batch()?,
+            field_name,
+            categories,
+        )?;
+        iterables.push(iterable.into_iter());
+    }
+    Ok(Box::new(iterables.into_iter().flatten()))
+}
+
 /// Get the index of an agent in Context Batch
 pub fn index_iter<'b: 'a, 'a>(
     agent_pool: &'a [&'b AgentBatch],
@@ -138,6 +157,20 @@ pub fn str_iter<'b: 'a, 'a>(
     Ok(iterables.into_iter().flatten())
 }
 
+pub fn str_list_iter<'b: 'a, 'a>(
+    agent_pool: &'a [&'b AgentBatch],
+    field_name: &str,
+) -> Result<impl Iterator<Item = Option<Vec<&'b str>>> + 'a> {
+    let mut iterables = Vec::with_capacity(agent_pool.len());
+
+    // Collect iterators first, because we want to check for any errors.
+    for agent_batch in agent_pool {
+        let iterable = record_batch::str_list_iter(agent_batch.batch.record_batch()?, field_name)?;
+        iterables.push(iterable);
+    }
+    Ok(iterables.into_iter().flatten())
+}
+
 pub fn bool_iter<'b: 'a, 'a>(
     agent_pool: &'a [&'b AgentBatch],
     field_name: &str,
@@ -2,13 +2,14 @@ use std::borrow::Cow;
 
 use arrow2::{
     array::{
-        Array, BooleanArray, FixedSizeBinaryArray, MutableArray, MutableUtf8Array, PrimitiveArray,
-        Utf8Array,
+        Array, BooleanArray, FixedSizeBinaryArray, ListArray, MutableArray, MutableUtf8Array,
+        PrimitiveArray, Utf8Array,
     },
     datatypes::DataType,
 };
 use memory::arrow::{
-    col_to_json_vals, column_with_name_from_record_batch, record_batch::RecordBatch, ColumnChange,
+    categorical_to_json_vals, col_to_json_vals, column_with_name_from_record_batch,
+    record_batch::RecordBatch, ColumnChange,
 };
 
 use crate::{
@@ -360,6 +361,42 @@ pub(crate) fn str_iter<'a>(
     }))
 }
 
+pub(crate) fn str_list_iter<'a>(
+    record_batch: &'a RecordBatch,
+    column_name: &str,
+) -> Result<impl Iterator<Item = Option<Vec<&'a str>>>> {
+    let row_count = record_batch.num_rows();
+    let column = column_with_name_from_record_batch(record_batch, column_name)?;
+
+    let column = column
+        .as_any()
+        .downcast_ref::<ListArray<i32>>()
+        .ok_or_else(|| Error::InvalidArrowDowncast {
+            name: column_name.into(),
+        })?;
+    let values = column
+        .values()
+        .as_any()
+        .downcast_ref::<Utf8Array<i32>>()
+        .ok_or_else(|| Error::InvalidArrowDowncast {
+            name: column_name.into(),
+        })?;
+    let offsets = column.offsets().as_slice();
+
+    Ok((0..row_count).map(move |i| {
+        if column.is_valid(i) {
+            Some(
+                (offsets[i] as usize..offsets[i + 1] as usize)
+                    .filter(|&j| values.is_valid(j))
+                    .map(|j| values.value(j))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }))
+}
+
 pub(crate) fn bool_iter<'a>(
     record_batch: &'a RecordBatch,
     column_name: &str,
@@ -412,3 +449,13 @@ pub(crate) fn json_values(
     let column = column_with_name_from_record_batch(record_batch, column_name)?;
     Ok(col_to_json_vals(column.as_ref(), data_type)?)
 }
+
+// Iterate over a categorical field and map the stored indices back to their categories
+pub(crate) fn json_categorical_values(
+    record_batch: &RecordBatch,
+    column_name: &str,
+    categories: &[String],
+) -> Result<Vec<serde_json::Value>> {
+    let column = column_with_name_from_record_batch(record_batch, column_name)?;
+    Ok(categorical_to_json_vals(column.as_ref(), categories)?)
+}
@@ -176,7 +176,7 @@ pub(crate) const BUILTIN_FIELDS: [&str; 12] = [
     AgentStateField::Hidden.name(),
 ];
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct AgentId {
     id: Uuid,
@@ -187,6 +187,19 @@ impl AgentId {
         Self { id: Uuid::new_v4() }
     }
 
+    /// The id of an agent, which was created without an `agent_id` and didn't get one assigned
+    /// yet.
+    ///
+    /// The engine assigns the ids of new agents, so seeded simulation runs can derive them
+    /// deterministically, see [`Agent::assign_id`].
+    pub const fn unassigned() -> Self {
+        Self { id: Uuid::nil() }
+    }
+
+    pub fn is_unassigned(&self) -> bool {
+        self.id.is_nil()
+    }
+
     pub fn from_slice(b: &[u8]) -> Result<Self> {
         Ok(Self {
             id: Uuid::from_slice(b)?,
@@ -217,7 +230,9 @@ impl fmt::Display for AgentId {
 #[derive(Clone, Serialize, Debug, PartialEq)]
 pub struct Agent {
     /// The unique identifier (UUIDv4) of an agent.
-    #[serde(default = "AgentId::generate")]
+    ///
+    /// Agents deserialized without an `agent_id` have an [unassigned](AgentId::unassigned) id.
+    #[serde(default = "AgentId::unassigned")]
     pub agent_id: AgentId,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -255,7 +270,7 @@ pub struct Agent {
 
 // Custom deserializer for AgentState
 // Differences (from serde_derive):
-//  Agent ID will be auto generated if not explicitely given
+//  Agent ID will be unassigned if not explicitely given
 //  Messages are parsed using an intermediate state
 //  Messages are saved until the very end of parsing the agent state
 //
@@ -307,7 +322,10 @@ impl<'de> Deserialize<'de> for Agent {
                 }
 
                 // empty comes with our defaults already applied
-                let mut agent_state_buf: Agent = Agent::empty();
+                let mut agent_state_buf: Agent = Agent {
+                    agent_id: AgentId::unassigned(),
+                    ..Agent::empty()
+                };
 
                 let mut set_fields = HashSet::new();
 
@@ -584,6 +602,24 @@ impl Agent {
         }
     }
 
+    /// Assigns `agent_id` to an agent with an [unassigned](AgentId::unassigned) id.
+    ///
+    /// `remove_agent` messages in the outbox of the agent, which refer to the agent itself, are
+    /// updated as well.
+    pub fn assign_id(&mut self, agent_id: AgentId) {
+        debug_assert!(self.agent_id.is_unassigned(), "agent already has an id");
+        for message in &mut self.messages {
+            if let message::Message::RemoveAgent(message::payload::RemoveAgent { data, .. }) =
+                message
+            {
+                if data.agent_id == self.agent_id {
+                    data.agent_id = agent_id;
+                }
+            }
+        }
+        self.agent_id = agent_id;
+    }
+
     /// `add_message` will attempt to add a message to the `Agent`'s outbound message queue based
     /// on `kind`. It works just like `add_message`, but accepts a collection of values
     /// for `to`.
@@ -598,47 +634,12 @@ impl Agent {
         kind: &str,
         data: Option<serde_json::Value>,
     ) -> Result<()> {
-        self.messages.push(match kind {
-            message::payload::RemoveAgent::KIND => {
-                message::Message::RemoveAgent(message::payload::RemoveAgent {
-                    r#type: message::RemoveAgent::Type,
-                    to: to.to_vec(),
-                    data: serde_json::from_value(
-                        // if the data is None, default to using just the agent_id of the `self`
-                        // agent
-                        data.unwrap_or_else(|| {
-                            let mut map = serde_json::Map::new();
-                            map.insert(
-                                String::from("agent_id"),
-                                serde_json::Value::String(self.agent_id.to_string()),
-                            );
-                            serde_json::Value::Object(map)
-                        }),
-                    )?,
-                })
-            }
-            message::payload::CreateAgent::KIND => {
-                message::Message::CreateAgent(message::payload::CreateAgent {
-                    r#type: message::CreateAgent::Type,
-                    to: to.to_vec(),
-                    data: serde_json::from_value(
-                        data.ok_or_else(|| Error::from("Missing AgentState to create"))?,
-                    )?,
-                })
-            }
-            message::payload::StopSim::KIND => {
-                message::Message::StopSim(message::payload::StopSim {
-                    r#type: message::StopSim::Type,
-                    to: to.to_vec(),
-                    data,
-                })
-            }
-            _ => message::Message::Generic(message::payload::Generic {
-                r#type: kind.to_string(),
-                to: to.to_vec(),
-                data,
-            }),
-        });
+        self.messages.push(message::Message::from_sender(
+            self.agent_id,
+            to.to_vec(),
+            kind,
+            data,
+        )?);
         Ok(())
     }
 
@@ -921,6 +922,23 @@ mod tests {
         assert_eq!(arr, json!([5.0, 2.0, 1.0]));
     }
 
+    #[test]
+    fn assign_missing_agent_id() {
+        let mut agent: Agent = serde_json::from_value(json!({
+            "messages": [{ "to": "hash", "type": "remove_agent" }],
+        }))
+        .unwrap();
+        assert!(agent.agent_id.is_unassigned());
+
+        let agent_id = AgentId::generate();
+        agent.assign_id(agent_id);
+        assert_eq!(agent.agent_id, agent_id);
+        match &agent.messages[0] {
+            message::Message::RemoveAgent(message) => assert_eq!(message.data.agent_id, agent_id),
+            message => panic!("Expected RemoveAgent message, got {message:?}"),
+        }
+    }
+
     #[test]
     fn test_scale_default() {
         let agent = Agent::default();
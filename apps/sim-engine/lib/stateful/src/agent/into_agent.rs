@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use arrow2::{
     array::{self, Array, FixedSizeListArray, Utf8Array},
     datatypes::{Field, Schema},
 };
-use memory::arrow::{col_to_json_vals, json_utf8_json_vals, record_batch::RecordBatch};
+use memory::arrow::{
+    categorical_to_json_vals, col_to_json_vals, json_utf8_json_vals, record_batch::RecordBatch,
+};
 
 use crate::{
     agent::{
@@ -109,6 +111,29 @@ impl IntoAgents for RecordBatch {
                     .collect()
             });
 
+        let categoricals = &agent_schema
+            .map(|v| {
+                Ok(v.field_spec_map
+                    .iter()
+                    .filter_map(|(key, field_spec)| {
+                        if let FieldTypeVariant::Categorical(categories) =
+                            &field_spec.inner.field_type.variant
+                        {
+                            Some((key.value().to_string(), categories.clone()))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<HashMap<String, Vec<String>>>())
+            })
+            .unwrap_or_else(|| {
+                // Older schemas might not contain any categorical fields in their metadata
+                self.schema()
+                    .metadata
+                    .get("categorical_fields")
+                    .map_or_else(|| Ok(HashMap::new()), |v| serde_json::from_str(v))
+            })?;
+
         for (i_field, field) in agents.schema().fields.iter().enumerate() {
             // TODO: remove the need for this
             if BUILTIN_FIELDS.contains(&field.name.as_str()) {
@@ -117,6 +142,8 @@ impl IntoAgents for RecordBatch {
             if any_types.contains(&field.name) {
                 // We need to use "from_str" and not "to_value" when converting to serde_json::Value
                 set_states_serialized(&mut states, agents, i_field, field)?;
+            } else if let Some(categories) = categoricals.get(&field.name) {
+                set_states_categorical(&mut states, agents, i_field, field, categories)?;
             } else {
                 set_states_custom(&mut states, agents, i_field, field)?;
             }
@@ -447,6 +474,23 @@ fn set_states_custom(
     Ok(())
 }
 
+fn set_states_categorical(
+    states: &mut [Agent],
+    record_batch: &RecordBatch,
+    i_field: usize,
+    field: &Field,
+    categories: &[String],
+) -> Result<()> {
+    let col = record_batch.column(i_field);
+    let vals = categorical_to_json_vals(col.as_ref(), categories)?;
+    for (i_val, val) in vals.into_iter().enumerate() {
+        if col.null_count() == 0 || col.is_valid(i_val) {
+            states[i_val].custom.insert(field.name.clone(), val); // i_val == i_state
+        }
+    }
+    Ok(())
+}
+
 fn set_states_serialized(
     states: &mut [Agent],
     record_batch: &RecordBatch,
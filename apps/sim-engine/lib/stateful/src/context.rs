@@ -96,6 +96,12 @@ impl Context {
         )
     }
 
+    /// Returns the messages sent in the previous step, which are received by the agents in the
+    /// current step.
+    pub fn message_pool(&self) -> &MessageBatchPool {
+        &self.previous_state.message_pool
+    }
+
     pub fn removed_batches(&mut self) -> &mut Vec<String> {
         &mut self.removed_batches
     }
@@ -46,6 +46,10 @@ impl ContextBatch {
         &self.segment
     }
 
+    pub fn record_batch(&self) -> &RecordBatch {
+        &self.batch
+    }
+
     pub(in crate::context) fn from_record_batch(
         record_batch: &RecordBatch,
         schema: Arc<Schema>,
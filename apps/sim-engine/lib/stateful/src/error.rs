@@ -70,6 +70,9 @@ pub enum Error {
 
     #[error("Unable to read flatbuffers: {0}")]
     Planus(#[from] arrow_format::ipc::planus::Error),
+
+    #[error("Invalid message recipient {recipient}: {reason}")]
+    InvalidRecipient { recipient: String, reason: String },
 }
 
 impl From<&str> for Error {
@@ -5,7 +5,7 @@
 
 use core::fmt;
 
-use arrow2::datatypes::{DataType, Field};
+use arrow2::datatypes::{DataType, Field, TimeUnit};
 
 use crate::field::{FieldSpec, IsFixedSize, UUID_V4_LEN};
 
@@ -50,10 +50,21 @@ impl IsFixedSize for PresetFieldType {
 pub enum FieldTypeVariant {
     /// A 64 bit floating point number
     Number,
+    /// A 64 bit signed integer
+    Integer,
+    /// Milliseconds since the Unix epoch, stored as a 64 bit signed integer
+    Timestamp,
     Boolean,
     String,
     /// A JSON-encoded String
     AnyType,
+    /// A String restricted to the given categories, stored as the index of the category.
+    ///
+    /// The categories are stored in the schema metadata (see
+    /// [`FieldSpecMap::create_arrow_schema`]), so only top-level fields can be categorical.
+    ///
+    /// [`FieldSpecMap::create_arrow_schema`]: crate::field::FieldSpecMap::create_arrow_schema
+    Categorical(Vec<String>),
     FixedLengthArray {
         field_type: Box<FieldType>,
         len: usize,
@@ -67,9 +78,12 @@ impl From<FieldTypeVariant> for DataType {
     fn from(type_variant: FieldTypeVariant) -> Self {
         match type_variant {
             FieldTypeVariant::Number => Self::Float64,
+            FieldTypeVariant::Integer => Self::Int64,
+            FieldTypeVariant::Timestamp => Self::Timestamp(TimeUnit::Millisecond, None),
             FieldTypeVariant::Boolean => Self::Boolean,
             FieldTypeVariant::String => Self::Utf8,
             FieldTypeVariant::AnyType => Self::Utf8,
+            FieldTypeVariant::Categorical(_) => Self::UInt32,
             FieldTypeVariant::FixedLengthArray { field_type, len } => DataType::FixedSizeList(
                 Box::new(Field::new("item", DataType::from(field_type.variant), true)),
                 len,
@@ -98,7 +112,11 @@ impl From<FieldTypeVariant> for DataType {
 impl IsFixedSize for FieldTypeVariant {
     fn is_fixed_size(&self) -> bool {
         match self {
-            Self::Number | Self::Boolean => true,
+            Self::Number
+            | Self::Integer
+            | Self::Timestamp
+            | Self::Boolean
+            | Self::Categorical(_) => true,
             Self::String | Self::AnyType => false,
             Self::FixedLengthArray {
                 field_type: element,
@@ -117,9 +135,14 @@ impl fmt::Debug for FieldTypeVariant {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             FieldTypeVariant::Number => write!(fmt, "number"),
+            FieldTypeVariant::Integer => write!(fmt, "integer"),
+            FieldTypeVariant::Timestamp => write!(fmt, "timestamp"),
             FieldTypeVariant::Boolean => write!(fmt, "boolean"),
             FieldTypeVariant::String => write!(fmt, "string"),
             FieldTypeVariant::AnyType => write!(fmt, "any"),
+            FieldTypeVariant::Categorical(categories) => {
+                write!(fmt, "categorical: {{categories: {:?}}}", categories)
+            }
             FieldTypeVariant::FixedLengthArray {
                 field_type: kind,
                 len,
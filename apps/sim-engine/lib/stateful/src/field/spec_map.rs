@@ -137,6 +137,7 @@ impl FieldSpecMap {
         let mut fixed_size_no = 0;
 
         let mut any_types = vec![];
+        let mut categoricals = BTreeMap::new();
 
         for (key, field_spec) in self.iter() {
             let key = key.value().to_string();
@@ -148,11 +149,12 @@ impl FieldSpecMap {
                 partitioned_fields.push((field_spec, key.clone()));
             }
 
-            if matches!(
-                field_spec.inner.field_type.variant,
-                FieldTypeVariant::AnyType
-            ) {
-                any_types.push(key)
+            match &field_spec.inner.field_type.variant {
+                FieldTypeVariant::AnyType => any_types.push(key),
+                FieldTypeVariant::Categorical(categories) => {
+                    categoricals.insert(key, categories);
+                }
+                _ => {}
             }
         }
 
@@ -169,6 +171,10 @@ impl FieldSpecMap {
         // TODO: this can be simplified when we update arrow-rs (beyond 1.0.1), we can set this on
         //   Field's custom metadata instead of the schema
         metadata.insert("any_type_fields".into(), any_types.join(","));
+        metadata.insert(
+            "categorical_fields".into(),
+            serde_json::to_string(&categoricals)?,
+        );
         metadata.insert("nullable".into(), nullabilities.join(","));
         Ok(Schema {
             fields: partitioned_fields
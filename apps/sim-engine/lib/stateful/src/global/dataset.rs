@@ -1,7 +1,18 @@
+mod table;
+
 use core::fmt;
 use std::collections::HashMap;
 
-use memory::shared_memory::{MemoryId, Metaversion, Segment};
+use arrow2::io::ipc::write::{default_ipc_fields, schema_to_bytes};
+use memory::{
+    arrow::{
+        ipc::{
+            calculate_ipc_header_data, write_record_batch_body, write_record_batch_message_header,
+        },
+        record_batch::RecordBatch,
+    },
+    shared_memory::{MemoryId, Metaversion, Segment},
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -9,7 +20,8 @@ use crate::Result;
 
 /// Record for a [`Dataset`] pointing to a file.
 ///
-/// A `Dataset` can either be stored as JSON or as CSV.
+/// A `Dataset` can either be stored as JSON or as CSV. CSV datasets are kept as CSV and read
+/// directly into Arrow arrays when the [`SharedDataset`] is created.
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Dataset {
     pub name: Option<String>,
@@ -18,9 +30,16 @@ pub struct Dataset {
     pub url: Option<String>,
     /// Whether the downloadable dataset is a csv
     pub raw_csv: bool,
+    /// Whether the first row of a CSV dataset is its header, which names the columns
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
     pub data: Option<String>,
 }
 
+fn default_has_header() -> bool {
+    true
+}
+
 impl fmt::Debug for Dataset {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SharedDataset")
@@ -29,6 +48,7 @@ impl fmt::Debug for Dataset {
             .field("filename", &self.filename)
             .field("url", &self.url)
             .field("raw_csv", &self.raw_csv)
+            .field("has_header", &self.has_header)
             .field(
                 "data",
                 if self.data.is_some() {
@@ -48,6 +68,12 @@ impl fmt::Debug for Dataset {
 /// In comparison to [`Globals`], a [`Dataset`] is stored in a memory [`Segment`] and can be
 /// constructed from a [`Dataset`]. Its data can be accessed by [`data()`].
 ///
+/// Tabular datasets, i.e. CSV files with rows of equal length and JSON arrays of flat objects, are
+/// stored as an Arrow record batch, including its schema and metadata, so the runners can access
+/// typed columns directly from shared memory. All other datasets are stored as JSON string, which
+/// is parsed by the runners. CSV files with rows of different lengths are converted to a JSON list
+/// of rows for this.
+///
 /// [HASH documentation]: https://hash.ai/docs/simulation/creating-simulations/datasets
 /// [`Globals`]: crate::global::Globals
 /// [`Segment`]: memory::shared_memory::Segment
@@ -55,6 +81,7 @@ impl fmt::Debug for Dataset {
 #[derive(Debug)]
 pub struct SharedDataset {
     segment: Segment,
+    is_tabular: bool,
 }
 
 impl SharedDataset {
@@ -62,35 +89,79 @@ impl SharedDataset {
         &self.segment
     }
 
+    /// Whether the dataset is stored as a record batch rather than a JSON string.
+    pub fn is_tabular(&self) -> bool {
+        self.is_tabular
+    }
+
     pub fn from_shared(dataset: &Dataset, memory_id: MemoryId) -> Result<Self> {
         let metaversion = Metaversion::default().to_le_bytes();
         let dataset_name = &dataset.shortname;
         let mut header = vec![0u8; metaversion.len() + dataset_name.len()];
         header[..metaversion.len()].copy_from_slice(&metaversion);
         header[metaversion.len()..].copy_from_slice(dataset_name.as_bytes());
-        let dataset_size = dataset
-            .data
-            .as_ref()
-            .map(|data| data.len())
-            .unwrap_or_default();
-
-        let mut segment = Segment::from_sizes(memory_id, 0, header.len(), 0, dataset_size, false)?;
-        let change = segment.set_header(&header)?;
+
+        let data = dataset.data.as_deref().unwrap_or_default();
+        if dataset.raw_csv {
+            let rows = table::read_csv_rows(data)?;
+            if let Some(record_batch) = table::csv_to_record_batch(&rows, dataset.has_header) {
+                return Self::from_record_batch(&record_batch, &header, memory_id);
+            }
+            // CSV datasets with rows of different lengths are passed as JSON list of rows
+            return Self::from_json(&table::csv_to_json(&rows)?, &header, memory_id);
+        }
+        if let Some(record_batch) = table::json_to_record_batch(data) {
+            return Self::from_record_batch(&record_batch, &header, memory_id);
+        }
+        Self::from_json(data, &header, memory_id)
+    }
+
+    fn from_json(json: &str, header: &[u8], memory_id: MemoryId) -> Result<Self> {
+        let mut segment = Segment::from_sizes(memory_id, 0, header.len(), 0, json.len(), false)?;
+        let change = segment.set_header(header)?;
         debug_assert!(!change.resized() && !change.shifted());
 
         let buffer = segment.get_mut_data_buffer()?;
-        buffer.copy_from_slice(
-            dataset
-                .data
-                .as_ref()
-                .map(|data| data.as_bytes())
-                .unwrap_or_default(),
-        );
-
-        Ok(Self { segment })
+        buffer.copy_from_slice(json.as_bytes());
+
+        Ok(Self {
+            segment,
+            is_tabular: false,
+        })
+    }
+
+    fn from_record_batch(
+        record_batch: &RecordBatch,
+        header: &[u8],
+        memory_id: MemoryId,
+    ) -> Result<Self> {
+        let schema = record_batch.schema();
+        let schema = schema_to_bytes(&schema, &default_ipc_fields(&schema.fields));
+        let header_data = calculate_ipc_header_data(record_batch);
+        let mut metadata = Vec::new();
+        write_record_batch_message_header(&mut metadata, &header_data)?;
+
+        let mut segment = Segment::from_sizes(
+            memory_id,
+            schema.len(),
+            header.len(),
+            metadata.len(),
+            header_data.body_len,
+            true,
+        )?;
+        let _ = segment.set_schema(&schema)?;
+        let _ = segment.set_header(&header)?;
+        let _ = segment.set_metadata(&metadata)?;
+        write_record_batch_body(record_batch, segment.get_mut_data_buffer()?, &header_data)?;
+
+        Ok(Self {
+            segment,
+            is_tabular: true,
+        })
     }
 
-    /// Contents of the dataset, e.g. a JSON or CSV string.
+    /// Contents of the dataset, i.e. a JSON string, or the body of the record batch if the
+    /// dataset [is tabular](Self::is_tabular).
     ///
     /// # Panics
     ///
@@ -0,0 +1,462 @@
+//! Conversion of tabular datasets into Arrow record batches.
+//!
+//! A dataset is tabular if it's either a CSV file with the same number of cells in every row or a
+//! JSON array of flat objects, which all have the same keys. Tabular datasets are stored as a
+//! record batch, so runners can access their columns without parsing the dataset again.
+//!
+//! CSV datasets are read directly into Arrow arrays. Whether the first row is the header is
+//! specified by the dataset. CSV cells are stored as integers, numbers or booleans if every cell of
+//! the column has exactly the text which the runners produce when converting the value back to a
+//! string. This allows the runners to still provide the rows of a CSV dataset as arrays of
+//! strings. Empty cells of these columns are stored as `null`.
+
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use arrow2::{
+    array::{Array, BooleanArray, PrimitiveArray, Utf8Array},
+    chunk::Chunk,
+    datatypes::{DataType, Field, Schema},
+    io::csv::{self, read::ByteRecord},
+};
+use memory::arrow::record_batch::RecordBatch;
+use serde::{de, Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::Result;
+
+/// Schema metadata key for the format of the original dataset, either `"csv"` or `"json"`.
+const FORMAT_META_KEY: &str = "dataset_format";
+
+/// Schema metadata key for the header of a CSV dataset, if it has one.
+///
+/// The value is a JSON array of the header cells.
+const CSV_HEADER_META_KEY: &str = "csv_header";
+
+/// The number of rows read from a CSV dataset at once.
+const CSV_CHUNK_SIZE: usize = 1 << 16;
+
+/// Largest integer, which can be represented exactly by a JavaScript number.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Integer,
+    Number,
+    Boolean,
+    String,
+}
+
+impl ColumnType {
+    fn data_type(self) -> DataType {
+        match self {
+            Self::Integer => DataType::Int64,
+            Self::Number => DataType::Float64,
+            Self::Boolean => DataType::Boolean,
+            Self::String => DataType::Utf8,
+        }
+    }
+
+    /// Returns if `cell` can be stored in a column of this type without changing its text.
+    fn fits_cell(self, cell: &str) -> bool {
+        cell.is_empty()
+            || match self {
+                Self::Integer => parse_integer(cell).is_some(),
+                Self::Number => parse_number(cell).is_some(),
+                Self::Boolean => parse_boolean(cell).is_some(),
+                Self::String => true,
+            }
+    }
+
+    /// The most specific type which fits all `cells`.
+    fn infer_from_cells<'c>(cells: impl Iterator<Item = &'c str> + Clone) -> Self {
+        let mut non_empty = cells.filter(|cell| !cell.is_empty()).peekable();
+        if non_empty.peek().is_none() {
+            return Self::String;
+        }
+        [Self::Integer, Self::Number, Self::Boolean]
+            .into_iter()
+            .find(|column_type| non_empty.clone().all(|cell| column_type.fits_cell(cell)))
+            .unwrap_or(Self::String)
+    }
+
+    /// The type of the JSON `values`, if they all have the same type and are not nested.
+    fn infer_from_json(values: &[&Value]) -> Option<Self> {
+        let mut column_type = None;
+        for value in values {
+            let value_type = match value {
+                Value::Null => continue,
+                Value::Bool(_) => Self::Boolean,
+                Value::Number(number) if number.is_f64() => Self::Number,
+                Value::Number(number) => {
+                    number
+                        .as_i64()
+                        .filter(|integer| integer.unsigned_abs() <= MAX_SAFE_INTEGER)?;
+                    Self::Integer
+                }
+                Value::String(_) => Self::String,
+                Value::Array(_) | Value::Object(_) => return None,
+            };
+            if *column_type.get_or_insert(value_type) != value_type {
+                return None;
+            }
+        }
+        Some(column_type.unwrap_or(Self::String))
+    }
+}
+
+fn parse_integer(cell: &str) -> Option<i64> {
+    let integer = cell.parse::<i64>().ok()?;
+    (integer.unsigned_abs() <= MAX_SAFE_INTEGER && integer.to_string() == cell).then_some(integer)
+}
+
+fn parse_number(cell: &str) -> Option<f64> {
+    let number = cell.parse::<f64>().ok()?;
+    // The runners only print numbers in this range without an exponent
+    let in_range = number == 0.0 || (1e-4..1e21).contains(&number.abs());
+    (number.is_finite()
+        && in_range
+        && !(number == 0.0 && number.is_sign_negative())
+        && number.to_string() == cell)
+        .then_some(number)
+}
+
+fn parse_boolean(cell: &str) -> Option<bool> {
+    match cell {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn json_to_column(values: &[&Value], column_type: ColumnType) -> Box<dyn Array> {
+    let values = values.iter();
+    match column_type {
+        ColumnType::Integer => Box::new(PrimitiveArray::<i64>::from_iter(
+            values.map(|value| value.as_i64()),
+        )),
+        ColumnType::Number => Box::new(PrimitiveArray::<f64>::from_iter(
+            values.map(|value| value.as_f64()),
+        )),
+        ColumnType::Boolean => {
+            Box::new(BooleanArray::from_iter(values.map(|value| value.as_bool())))
+        }
+        ColumnType::String => Box::new(Utf8Array::<i32>::from_iter(
+            values.map(|value| value.as_str()),
+        )),
+    }
+}
+
+fn to_record_batch(
+    fields: Vec<Field>,
+    columns: Vec<Box<dyn Array>>,
+    metadata: BTreeMap<String, String>,
+) -> RecordBatch {
+    let schema = Schema::from(fields).with_metadata(metadata);
+    RecordBatch::new(Arc::new(schema), Chunk::new(columns))
+}
+
+/// Returns the text of the cell at `index` of `row`, or an empty string if the row is too short.
+fn cell(row: &ByteRecord, index: usize) -> &str {
+    row.get(index)
+        .and_then(|cell| std::str::from_utf8(cell).ok())
+        .unwrap_or_default()
+}
+
+/// Reads all rows of the CSV dataset `source` including the header, if any.
+///
+/// Rows may have a different number of cells.
+pub(super) fn read_csv_rows(source: &str) -> Result<Vec<ByteRecord>> {
+    let mut reader = csv::read::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(source.as_bytes());
+
+    let mut rows = Vec::new();
+    loop {
+        let num_rows = rows.len();
+        rows.resize(num_rows + CSV_CHUNK_SIZE, ByteRecord::default());
+        let rows_read = csv::read::read_rows(&mut reader, 0, &mut rows[num_rows..])?;
+        rows.truncate(num_rows + rows_read);
+        if rows_read < CSV_CHUNK_SIZE {
+            return Ok(rows);
+        }
+    }
+}
+
+/// Converts the `rows` of a CSV dataset into a record batch, if every row has the same number of
+/// cells.
+///
+/// If `has_header` is set, the first row is the header. Unique and non-empty header cells are used
+/// as column names, otherwise columns are named by their index.
+pub(super) fn csv_to_record_batch(rows: &[ByteRecord], has_header: bool) -> Option<RecordBatch> {
+    let num_columns = rows.first()?.len();
+    if num_columns == 0 || rows.iter().any(|row| row.len() != num_columns) {
+        return None;
+    }
+    let (header, rows) = if has_header {
+        let header = (0..num_columns)
+            .map(|index| cell(&rows[0], index))
+            .collect::<Vec<_>>();
+        (Some(header), &rows[1..])
+    } else {
+        (None, rows)
+    };
+
+    let names_from_header = header.as_ref().filter(|header| {
+        header
+            .iter()
+            .enumerate()
+            .all(|(index, name)| !name.is_empty() && !header[..index].contains(name))
+    });
+    let fields = (0..num_columns)
+        .map(|index| {
+            let column_type =
+                ColumnType::infer_from_cells(rows.iter().map(move |row| cell(row, index)));
+            let name =
+                names_from_header.map_or_else(|| index.to_string(), |h| h[index].to_string());
+            Field::new(name, column_type.data_type(), true)
+        })
+        .collect::<Vec<_>>();
+    // The column types only accept cells, which are parsed without changing their value, and
+    // empty cells, which are read as `null` for all types except strings.
+    let chunk =
+        csv::read::deserialize_batch(rows, &fields, None, 0, csv::read::deserialize_column).ok()?;
+
+    let mut metadata = BTreeMap::new();
+    metadata.insert(FORMAT_META_KEY.to_string(), "csv".to_string());
+    if let Some(header) = header {
+        metadata.insert(
+            CSV_HEADER_META_KEY.to_string(),
+            serde_json::to_string(&header).ok()?,
+        );
+    }
+    Some(to_record_batch(fields, chunk.into_arrays(), metadata))
+}
+
+/// Converts the `rows` of a CSV dataset into a JSON array of rows, where each row is an array of
+/// strings.
+///
+/// This is used for CSV datasets, which are not tabular.
+pub(super) fn csv_to_json(rows: &[ByteRecord]) -> Result<String> {
+    let rows = rows
+        .iter()
+        .map(|row| (0..row.len()).map(|index| cell(row, index)).collect())
+        .collect::<Vec<Vec<_>>>();
+    Ok(serde_json::to_string(&rows)?)
+}
+
+/// A JSON object, which keeps its members in the order of the source.
+struct OrderedObject(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for OrderedObject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct ObjectVisitor;
+
+        impl<'de> de::Visitor<'de> for ObjectVisitor {
+            type Value = OrderedObject;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a JSON object")
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut members = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some(member) = map.next_entry()? {
+                    members.push(member);
+                }
+                Ok(OrderedObject(members))
+            }
+        }
+
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
+
+impl OrderedObject {
+    /// Returns the value of the member `key`, which is expected to be at `index`.
+    fn get(&self, index: usize, key: &str) -> Option<&Value> {
+        match self.0.get(index) {
+            Some((member_key, value)) if member_key == key => Some(value),
+            _ => self
+                .0
+                .iter()
+                .find(|(member_key, _)| member_key == key)
+                .map(|(_, value)| value),
+        }
+    }
+}
+
+/// Converts a JSON dataset into a record batch, if it's an array of objects with the same keys
+/// and every key has values of a single, non-nested type.
+///
+/// Columns are named by the keys of the objects and are in the order of the keys in the first
+/// object.
+pub(super) fn json_to_record_batch(json: &str) -> Option<RecordBatch> {
+    // Avoid parsing datasets, which can't be tabular
+    if !json.trim_start().starts_with('[') {
+        return None;
+    }
+    let rows: Vec<OrderedObject> = serde_json::from_str(json).ok()?;
+    let keys = rows
+        .first()?
+        .0
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .collect::<Vec<_>>();
+    let has_duplicate_keys = keys
+        .iter()
+        .enumerate()
+        .any(|(index, key)| keys[..index].contains(key));
+    if keys.is_empty() || has_duplicate_keys || rows.iter().any(|row| row.0.len() != keys.len()) {
+        return None;
+    }
+
+    let mut fields = Vec::with_capacity(keys.len());
+    let mut columns = Vec::with_capacity(keys.len());
+    for (index, key) in keys.iter().enumerate() {
+        let values = rows
+            .iter()
+            .map(|row| row.get(index, key))
+            .collect::<Option<Vec<_>>>()?;
+        let column_type = ColumnType::infer_from_json(&values)?;
+        fields.push(Field::new(*key, column_type.data_type(), true));
+        columns.push(json_to_column(&values, column_type));
+    }
+
+    let mut metadata = BTreeMap::new();
+    metadata.insert(FORMAT_META_KEY.to_string(), "json".to_string());
+    Some(to_record_batch(fields, columns, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(source: &str, has_header: bool) -> Option<RecordBatch> {
+        csv_to_record_batch(&read_csv_rows(source).unwrap(), has_header)
+    }
+
+    fn names(batch: &RecordBatch) -> Vec<String> {
+        batch
+            .schema()
+            .fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn csv_columns_are_typed() {
+        let batch = csv("1,true,black,0.5\n2,false,,3\n", false).unwrap();
+        let schema = batch.schema();
+        let data_types = schema
+            .fields
+            .iter()
+            .map(|field| field.data_type().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            data_types,
+            [
+                DataType::Int64,
+                DataType::Boolean,
+                DataType::Utf8,
+                DataType::Float64
+            ]
+        );
+        assert_eq!(names(&batch), ["0", "1", "2", "3"]);
+        assert!(!schema.metadata.contains_key(CSV_HEADER_META_KEY));
+        assert_eq!(batch.num_rows(), 2);
+
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<PrimitiveArray<i64>>()
+            .unwrap();
+        assert_eq!(ids.values().as_slice(), [1, 2]);
+        let colors = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Utf8Array<i32>>()
+            .unwrap();
+        assert_eq!(colors.value(1), "");
+    }
+
+    #[test]
+    fn csv_cells_keep_their_text() {
+        for cell in ["01", "1.0", "1e3", "-0", "0.00001", "NaN", "True"] {
+            let batch = csv(cell, false).unwrap();
+            assert_eq!(
+                batch.schema().fields[0].data_type(),
+                &DataType::Utf8,
+                "{cell}"
+            );
+        }
+    }
+
+    #[test]
+    fn csv_header_is_explicit() {
+        // A header consisting of strings only is still a header
+        let batch = csv("id,name\na,b\nc,d\n", true).unwrap();
+        let schema = batch.schema();
+        assert_eq!(names(&batch), ["id", "name"]);
+        assert_eq!(schema.metadata[CSV_HEADER_META_KEY], r#"["id","name"]"#);
+        assert_eq!(batch.num_rows(), 2);
+
+        // Without a header, the first row is data even if it doesn't fit the column types
+        let batch = csv("id,name\n1,2\n", false).unwrap();
+        assert_eq!(names(&batch), ["0", "1"]);
+        assert_eq!(batch.schema().fields[0].data_type(), &DataType::Utf8);
+        assert_eq!(batch.num_rows(), 2);
+
+        let batch = csv("id,id\n1,a\n", true).unwrap();
+        assert_eq!(names(&batch), ["0", "1"]);
+        assert_eq!(batch.schema().fields[0].data_type(), &DataType::Int64);
+
+        let batch = csv("id,name\n", true).unwrap();
+        assert_eq!(names(&batch), ["id", "name"]);
+        assert_eq!(batch.num_rows(), 0);
+    }
+
+    #[test]
+    fn ragged_csv_is_not_tabular() {
+        let rows = read_csv_rows("1,\"a \"\"b\"\"\"\n3\n").unwrap();
+        assert!(csv_to_record_batch(&rows, false).is_none());
+        assert_eq!(csv_to_json(&rows).unwrap(), r#"[["1","a \"b\""],["3"]]"#);
+
+        assert!(csv("", false).is_none());
+    }
+
+    #[test]
+    fn json_records_are_tabular() {
+        let batch = json_to_record_batch(r#"[{"a": 1, "b": "x"}, {"a": null, "b": "y"}]"#).unwrap();
+        let schema = batch.schema();
+        assert_eq!(schema.fields[0].data_type(), &DataType::Int64);
+        assert_eq!(schema.fields[1].data_type(), &DataType::Utf8);
+        assert_eq!(batch.column(0).null_count(), 1);
+
+        assert!(json_to_record_batch(r#"{"a": 1}"#).is_none());
+        assert!(json_to_record_batch(r#"[{"a": 1}, {"a": 1.5}]"#).is_none());
+        assert!(json_to_record_batch(r#"[{"a": [1]}]"#).is_none());
+        assert!(json_to_record_batch(r#"[{"a": 1}, {"b": 1}]"#).is_none());
+        assert!(json_to_record_batch(r#"[{"a": 1, "a": 2}]"#).is_none());
+    }
+
+    #[test]
+    fn json_columns_keep_their_order() {
+        let batch = json_to_record_batch(
+            r#"[{"z": 1, "a": "x", "m": true}, {"m": false, "z": 2, "a": "y"}]"#,
+        )
+        .unwrap();
+        assert_eq!(names(&batch), ["z", "a", "m"]);
+        let flags = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        assert!(flags.value(0));
+        assert!(!flags.value(1));
+    }
+}
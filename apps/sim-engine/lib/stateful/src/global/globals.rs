@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{Error, Result};
+
 /// Global constant values that are available within a simulation.
 ///
 /// [`Globals`] is are provided along with the initial world state.
@@ -37,6 +39,53 @@ impl Globals {
     {
         self.0.get(key.as_ref()).cloned()
     }
+
+    /// Applies `patch` to the globals with JSON merge-patch semantics ([RFC 7386]).
+    ///
+    /// Objects in the patch are merged recursively, `null` removes a property, and every other
+    /// value replaces the existing one.
+    ///
+    /// # Errors
+    ///
+    /// - if `patch` is not an object, as it would replace the globals with a non-object value. The
+    ///   globals are left unchanged.
+    ///
+    /// [RFC 7386]: https://datatracker.ietf.org/doc/html/rfc7386
+    pub fn merge_patch(&mut self, patch: &serde_json::Value) -> Result<()> {
+        if !patch.is_object() {
+            return Err(Error::from(format!(
+                "A globals update has to be an object, but got {patch}"
+            )));
+        }
+        merge_patch(&mut self.0, patch);
+        Ok(())
+    }
+}
+
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let patch = match patch {
+        serde_json::Value::Object(patch) => patch,
+        _ => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let target = target.as_object_mut().expect("target was set to an object");
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(
+                target
+                    .entry(key.as_str())
+                    .or_insert(serde_json::Value::Null),
+                value,
+            );
+        }
+    }
 }
 
 impl Default for Globals {
@@ -44,3 +93,51 @@ impl Default for Globals {
         Globals::empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_patch() {
+        let mut globals = Globals(json!({
+            "a": 1,
+            "b": { "c": 2, "d": 3 },
+            "e": [1, 2],
+        }));
+        globals
+            .merge_patch(&json!({
+                "a": null,
+                "b": { "c": 4, "f": { "g": 5 } },
+                "e": [3],
+            }))
+            .unwrap();
+        assert_eq!(
+            globals,
+            Globals(json!({
+                "b": { "c": 4, "d": 3, "f": { "g": 5 } },
+                "e": [3],
+            }))
+        );
+    }
+
+    #[test]
+    fn merge_patch_nested_non_object() {
+        let mut globals = Globals(json!({ "a": 1 }));
+        globals
+            .merge_patch(&json!({ "a": { "b": null, "c": 2 } }))
+            .unwrap();
+        assert_eq!(globals, Globals(json!({ "a": { "c": 2 } })));
+    }
+
+    #[test]
+    fn merge_patch_rejects_non_object() {
+        let mut globals = Globals(json!({ "a": 1 }));
+        for patch in [json!(5), json!([]), json!(null), json!("a")] {
+            assert!(globals.merge_patch(&patch).is_err(), "{patch}");
+        }
+        assert_eq!(globals, Globals(json!({ "a": 1 })));
+    }
+}
@@ -19,19 +19,23 @@ pub mod payload;
 pub(crate) mod arrow;
 
 mod batch;
+mod delayed;
 mod kind;
 mod loader;
 mod map;
 mod outbound;
 mod pool;
+mod recipient;
 mod schema;
 
 pub use self::{
     batch::MessageBatch,
+    delayed::DelayedMessages,
     loader::{MessageLoader, RawMessage},
-    map::MessageMap,
+    map::{Broadcast, MessageMap},
     outbound::Message,
     pool::{MessageBatchPool, MessageReader},
+    recipient::RecipientSpec,
     schema::MessageSchema,
 };
 pub(crate) use self::{
@@ -10,7 +10,7 @@ use memory::{
         ipc::{self, calculate_ipc_header_data, write_record_batch_message_header},
         meta::DynamicMetadata,
         record_batch::RecordBatch,
-        ArrowBatch,
+        ArrowBatch, ColumnChange,
     },
     shared_memory::{MemoryId, Metaversion, Segment},
 };
@@ -18,7 +18,13 @@ use tracing::trace;
 
 use crate::{
     agent::{arrow::array::IntoRecordBatch, AgentBatch, AgentStateField},
-    message::{arrow::array::MessageArray, MessageSchema},
+    message::{
+        arrow::{
+            array::{MessageArray, MESSAGE_COLUMN_INDEX},
+            column::MessageColumn,
+        },
+        Message, MessageSchema,
+    },
     Error, Result,
 };
 
@@ -188,6 +194,25 @@ impl MessageBatch {
         Ok(())
     }
 
+    /// Returns the outbound [`Message`]s of every agent in this batch.
+    pub fn messages(&self) -> Result<Vec<Vec<Message>>> {
+        Ok(MessageColumn::from_record_batch(self.batch.record_batch()?)?.0)
+    }
+
+    /// Creates a [`ColumnChange`] which replaces the messages of every agent in this batch.
+    ///
+    /// `messages` has to contain one list of messages per agent.
+    pub fn messages_change(&self, messages: &[Vec<Message>]) -> Result<ColumnChange> {
+        let column = messages
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        Ok(ColumnChange {
+            data: MessageArray::from_json(column)?.0.boxed(),
+            index: MESSAGE_COLUMN_INDEX,
+        })
+    }
+
     pub fn empty_from_agent_batch(
         agent_batch: &AgentBatch,
         schema: &MessageSchema,
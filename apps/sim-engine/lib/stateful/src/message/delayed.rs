@@ -0,0 +1,369 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+};
+
+use rayon::iter::ParallelIterator;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::AgentId,
+    message::{
+        arrow::record_batch::message_recipients_iter, payload, Message, MessageBatchPool,
+        MessageLoader, RecipientSpec,
+    },
+    proxy::BatchPool,
+    Result,
+};
+
+/// A message held back by [`DelayedMessages`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct HeldMessage {
+    sender: AgentId,
+    message: payload::Generic,
+}
+
+/// Messages, which are held back until a later step.
+///
+/// Messages sent to a [`RecipientSpec`] with `delay` or `deliver_at` are taken out of the outbox
+/// of their sender and put back into it in the step before they are due, so they are received in
+/// the step requested.
+///
+/// Held messages are not part of the agent state, so they have to be written to checkpoints next to
+/// it to be received after resuming.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelayedMessages {
+    /// Held messages by the step they are received in
+    held: BTreeMap<usize, Vec<HeldMessage>>,
+}
+
+impl DelayedMessages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of messages currently held back.
+    pub fn len(&self) -> usize {
+        self.held.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Holds back the messages of `message_pool`, which are not due in `current_step`, and
+    /// releases the held messages, which are.
+    ///
+    /// `message_pool` is the outbox written in the previous step, so its messages are received in
+    /// `current_step`. Released messages are added to the outbox of their sender. If the sender
+    /// doesn't exist anymore, the message is dropped.
+    pub fn hold_and_release(
+        &mut self,
+        message_pool: &mut MessageBatchPool,
+        current_step: usize,
+    ) -> Result<()> {
+        let sent_step = current_step.saturating_sub(1);
+        let mut due = self.take_due(current_step);
+
+        let mut proxies = message_pool.write_proxies()?;
+        for batch in proxies.batches_iter_mut() {
+            let senders = {
+                let loader = MessageLoader::from_batch(batch)?;
+                (0..loader.num_agents())
+                    .map(|agent_index| AgentId::from_bytes(*loader.get_from(agent_index)))
+                    .collect::<Vec<_>>()
+            };
+            let has_specs = message_recipients_iter(batch.batch.record_batch()?).any(|messages| {
+                messages.any(|recipients| {
+                    recipients
+                        .iter()
+                        .any(|recipient| recipient.trim_start().starts_with('{'))
+                })
+            });
+            let has_due = senders.iter().any(|sender| due.contains_key(sender));
+            if !has_specs && !has_due {
+                continue;
+            }
+
+            let mut messages = batch.messages()?;
+            for (sender, agent_messages) in senders.iter().zip(&mut messages) {
+                let mut outbox = Vec::with_capacity(agent_messages.len());
+                for message in agent_messages.drain(..) {
+                    match message {
+                        Message::Generic(message) => {
+                            if let Some(message) =
+                                self.hold(*sender, message, sent_step, current_step)?
+                            {
+                                outbox.push(Message::Generic(message));
+                            }
+                        }
+                        message => outbox.push(message),
+                    }
+                }
+                if let Some(released) = due.remove(sender) {
+                    outbox.extend(released.into_iter().map(Message::Generic));
+                }
+                *agent_messages = outbox;
+            }
+
+            let change = batch.messages_change(&messages)?;
+            batch.batch.queue_change(change)?;
+            batch.batch.flush_changes()?;
+        }
+
+        let dropped = due.values().map(Vec::len).sum::<usize>();
+        if dropped > 0 {
+            tracing::warn!(
+                "Dropped {dropped} delayed messages, because their senders were removed"
+            );
+        }
+        Ok(())
+    }
+
+    /// Removes the messages due in `current_step` (or earlier) by their sender.
+    fn take_due(&mut self, current_step: usize) -> HashMap<AgentId, Vec<payload::Generic>> {
+        let held = self.held.split_off(&(current_step + 1));
+        let mut due = HashMap::<_, Vec<_>>::new();
+        for message in mem::replace(&mut self.held, held).into_values().flatten() {
+            due.entry(message.sender).or_default().push(message.message);
+        }
+        due
+    }
+
+    /// Holds back `message` for all recipients, which are not due in `current_step`, and returns
+    /// the message for the remaining recipients, if any.
+    fn hold(
+        &mut self,
+        sender: AgentId,
+        mut message: payload::Generic,
+        sent_step: usize,
+        current_step: usize,
+    ) -> Result<Option<payload::Generic>> {
+        decode_data(&mut message);
+
+        let mut recipients = Vec::with_capacity(message.to.len());
+        let mut held = BTreeMap::<usize, Vec<String>>::new();
+        for recipient in mem::take(&mut message.to) {
+            let spec = match RecipientSpec::parse(&recipient) {
+                Some(Ok(spec)) => spec,
+                // Invalid specs are reported when creating the `MessageMap`
+                _ => {
+                    recipients.push(recipient);
+                    continue;
+                }
+            };
+            match spec.delivery_step(sent_step) {
+                Some(step) if step > current_step => {
+                    held.entry(step)
+                        .or_default()
+                        .extend(spec.into_due_recipients()?);
+                }
+                Some(_) => recipients.extend(spec.into_due_recipients()?),
+                None => recipients.push(recipient),
+            }
+        }
+
+        for (step, to) in held {
+            self.held.entry(step).or_default().push(HeldMessage {
+                sender,
+                message: payload::Generic {
+                    r#type: message.r#type.clone(),
+                    to,
+                    data: message.data.clone(),
+                },
+            });
+        }
+
+        if recipients.is_empty() {
+            Ok(None)
+        } else {
+            message.to = recipients;
+            Ok(Some(message))
+        }
+    }
+}
+
+/// [`MessageBatch::messages`] returns the data of a message as JSON-encoded string, which would be
+/// encoded a second time when writing it back.
+///
+/// [`MessageBatch::messages`]: crate::message::MessageBatch::messages
+fn decode_data(message: &mut payload::Generic) {
+    let decoded = match &message.data {
+        Some(serde_json::Value::String(data)) => serde_json::from_str(data).ok(),
+        _ => None,
+    };
+    if decoded.is_some() {
+        message.data = decoded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use memory::shared_memory::MemoryId;
+    use parking_lot::RwLock;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        agent::Agent,
+        message::{MessageBatch, MessageSchema},
+    };
+
+    const A: &str = "00000000-0000-4000-8000-00000000000a";
+    const B: &str = "00000000-0000-4000-8000-00000000000b";
+
+    /// Creates a message pool with a single batch containing the outbox of every agent.
+    fn message_pool(agents: serde_json::Value) -> MessageBatchPool {
+        let agents: Vec<Agent> = serde_json::from_value(agents).unwrap();
+        let batch = MessageBatch::from_agent_states(
+            agents.as_slice(),
+            &MessageSchema::new(),
+            MemoryId::new(Uuid::new_v4()),
+        )
+        .unwrap();
+        MessageBatchPool::new(vec![Arc::new(RwLock::new(batch))])
+    }
+
+    /// The type and the recipients of the messages sent by an agent
+    type Outbox = Vec<(String, Vec<String>)>;
+
+    /// Returns the outbox of every agent in `message_pool`.
+    fn outboxes(message_pool: &MessageBatchPool) -> Vec<Outbox> {
+        let proxies = message_pool.read_proxies().unwrap();
+        proxies
+            .batches_iter()
+            .flat_map(|batch| batch.messages().unwrap())
+            .map(|messages| {
+                messages
+                    .into_iter()
+                    .map(|message| match message {
+                        Message::Generic(message) => (message.r#type, message.to),
+                        message => panic!("Unexpected message {message:?}"),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn message(r#type: &str, to: &[&str]) -> (String, Vec<String>) {
+        (
+            r#type.to_string(),
+            to.iter().map(|recipient| recipient.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn hold_and_release_across_steps() {
+        let mut delayed = DelayedMessages::new();
+
+        // Sent in step 1, so `delay: 2` is received in step 3
+        let mut step_2 = message_pool(json!([
+            {
+                "agent_id": A,
+                "messages": [{
+                    "type": "ping",
+                    "to": [r#"{"to": "b", "delay": 2}"#, "c"],
+                    "data": { "n": 1 },
+                }],
+            },
+            { "agent_id": B, "messages": [{ "type": "pong", "to": ["a"] }] },
+        ]));
+        delayed.hold_and_release(&mut step_2, 2).unwrap();
+        assert_eq!(
+            outboxes(&step_2),
+            [vec![message("ping", &["c"])], vec![message("pong", &["a"])],]
+        );
+        assert_eq!(delayed.len(), 1);
+
+        let mut step_3 = message_pool(json!([
+            { "agent_id": A, "messages": [] },
+            { "agent_id": B, "messages": [] },
+        ]));
+        delayed.hold_and_release(&mut step_3, 3).unwrap();
+        assert_eq!(outboxes(&step_3), [vec![message("ping", &["b"])], vec![]]);
+        assert!(delayed.is_empty());
+
+        // The data is written back as it was sent instead of being encoded twice
+        let proxies = step_3.read_proxies().unwrap();
+        match &proxies.batches_iter().next().unwrap().messages().unwrap()[0][0] {
+            Message::Generic(message) => {
+                let mut message = message.clone();
+                decode_data(&mut message);
+                assert_eq!(message.data, Some(json!({ "n": 1 })));
+            }
+            message => panic!("Unexpected message {message:?}"),
+        }
+    }
+
+    #[test]
+    fn drop_messages_of_removed_senders() {
+        let mut delayed = DelayedMessages::new();
+
+        let mut step_2 = message_pool(json!([{
+            "agent_id": A,
+            "messages": [{ "type": "ping", "to": [r#"{"to": "b", "delay": 2}"#] }],
+        }]));
+        delayed.hold_and_release(&mut step_2, 2).unwrap();
+        assert_eq!(outboxes(&step_2), [Outbox::new()]);
+        assert_eq!(delayed.len(), 1);
+
+        // `a` was removed in step 2
+        let mut step_3 = message_pool(json!([{ "agent_id": B, "messages": [] }]));
+        delayed.hold_and_release(&mut step_3, 3).unwrap();
+        assert_eq!(outboxes(&step_3), [Outbox::new()]);
+        assert!(delayed.is_empty());
+    }
+
+    #[test]
+    fn deliver_past_messages_immediately() {
+        let mut delayed = DelayedMessages::new();
+
+        let mut step_5 = message_pool(json!([{
+            "agent_id": A,
+            "messages": [
+                { "type": "late", "to": [r#"{"to": "b", "deliver_at": 2}"#] },
+                { "type": "due", "to": [r#"{"to": "b", "deliver_at": 5}"#] },
+                { "type": "broadcast", "to": [r#"{"within": 1, "deliver_at": 3}"#] },
+            ],
+        }]));
+        delayed.hold_and_release(&mut step_5, 5).unwrap();
+        assert_eq!(
+            outboxes(&step_5),
+            [vec![
+                message("late", &["b"]),
+                message("due", &["b"]),
+                message("broadcast", &[r#"{"within":1.0}"#]),
+            ]]
+        );
+        assert!(delayed.is_empty());
+    }
+
+    #[test]
+    fn restore_held_messages() {
+        let mut delayed = DelayedMessages::new();
+
+        let mut step_2 = message_pool(json!([{
+            "agent_id": A,
+            "messages": [{ "type": "ping", "to": [r#"{"topic": "news", "deliver_at": 4}"#] }],
+        }]));
+        delayed.hold_and_release(&mut step_2, 2).unwrap();
+
+        let mut restored: DelayedMessages =
+            serde_json::from_value(serde_json::to_value(&delayed).unwrap()).unwrap();
+        assert_eq!(restored, delayed);
+
+        let mut step_3 = message_pool(json!([{ "agent_id": A, "messages": [] }]));
+        restored.hold_and_release(&mut step_3, 3).unwrap();
+        assert_eq!(outboxes(&step_3), [Outbox::new()]);
+
+        let mut step_4 = message_pool(json!([{ "agent_id": A, "messages": [] }]));
+        restored.hold_and_release(&mut step_4, 4).unwrap();
+        assert_eq!(
+            outboxes(&step_4),
+            [vec![message("ping", &[r#"{"topic":"news"}"#])]]
+        );
+    }
+}
@@ -57,6 +57,16 @@ impl<'a> MessageLoader<'a> {
         })
     }
 
+    /// Returns the number of agents in the batch.
+    pub fn num_agents(&self) -> usize {
+        self.typ_bufs[0].len() - 1
+    }
+
+    /// Returns the number of messages sent by the agent at `agent_index`.
+    pub fn num_messages(&self, agent_index: usize) -> usize {
+        (self.typ_bufs[0][agent_index + 1] - self.typ_bufs[0][agent_index]) as usize
+    }
+
     pub(crate) fn get_from(&self, agent_index: usize) -> &'a [u8; UUID_V4_LEN] {
         let content_start = agent_index * UUID_V4_LEN;
         unsafe {
@@ -65,7 +75,7 @@ impl<'a> MessageLoader<'a> {
         }
     }
 
-    pub(crate) fn get_type(&self, agent_index: usize, message_index: usize) -> &str {
+    pub fn get_type(&self, agent_index: usize, message_index: usize) -> &'a str {
         let list_index = self.typ_bufs[0][agent_index] as usize + message_index;
         let type_start = self.typ_bufs[1][list_index] as usize;
         let next_type_start = self.typ_bufs[1][list_index + 1] as usize;
@@ -6,58 +6,233 @@ use std::{
 use rayon::iter::ParallelIterator;
 
 use crate::{
+    agent::AgentId,
     error::Result,
-    message::{pool::recipient_iter_all, MessageBatch},
+    message::{pool::recipient_iter_all, MessageBatch, MessageReader, RecipientSpec},
     proxy::PoolReadProxy,
     state::MessageReference,
 };
 
+/// A message sent to every agent within `radius` of its sender.
+#[derive(Debug, Clone)]
+pub struct Broadcast {
+    pub sender: AgentId,
+    pub radius: f64,
+    pub message: MessageReference,
+}
+
 /// A mapping from recipient to message reference.
 ///
+/// Plain recipients are agent ids or names. Messages sent to a [`RecipientSpec`] are mapped by
+/// their topic or collected as [`Broadcast`]s, which have to be resolved against the positions of
+/// the agents. `delay` and `deliver_at` of a [`RecipientSpec`] are not considered here, messages
+/// are expected to be held back until they are due before the map is created.
+///
 /// Used in combination with [`MessageReader`].
 ///
 /// [`MessageReader`]: crate::message::MessageReader
 pub struct MessageMap {
     inner: HashMap<String, Vec<MessageReference>>,
+    topics: HashMap<String, Vec<MessageReference>>,
+    broadcasts: Vec<Broadcast>,
+}
+
+/// Routes collected by a single thread while creating a [`MessageMap`].
+#[derive(Default)]
+struct Routes {
+    recipients: HashMap<String, Vec<MessageReference>>,
+    topics: HashMap<String, Vec<MessageReference>>,
+    /// Radius and message of broadcasts, the sender is looked up afterwards
+    broadcasts: Vec<(f64, MessageReference)>,
+}
+
+fn insert_ref(map: &mut HashMap<String, Vec<MessageReference>>, key: &str, msg: &MessageReference) {
+    // TODO: OS - (decide) currently if message has duplicate recipients then
+    //   agents can get duplicate messages (filtering is expensive)
+    if let Some(entry) = map.get_mut(key) {
+        entry.push(msg.clone())
+    } else {
+        map.insert(key.to_string(), vec![msg.clone()]);
+    }
+}
+
+fn merge_refs(
+    a: &mut HashMap<String, Vec<MessageReference>>,
+    b: HashMap<String, Vec<MessageReference>>,
+) {
+    b.into_iter().for_each(|(name, mut value)| {
+        match a.entry(name) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().append(&mut value);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+            }
+        };
+    });
+}
+
+impl Routes {
+    fn add(&mut self, recipient: &str, message_ref: &MessageReference) {
+        match RecipientSpec::parse(recipient) {
+            None => insert_ref(&mut self.recipients, recipient, message_ref),
+            Some(Ok(spec)) => {
+                spec.to
+                    .iter()
+                    .for_each(|to| insert_ref(&mut self.recipients, to, message_ref));
+                if let Some(topic) = &spec.topic {
+                    insert_ref(&mut self.topics, topic, message_ref);
+                }
+                if let Some(radius) = spec.within {
+                    self.broadcasts.push((radius, message_ref.clone()));
+                }
+            }
+            Some(Err(err)) => tracing::warn!("Message is not delivered: {err}"),
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        merge_refs(&mut self.recipients, other.recipients);
+        merge_refs(&mut self.topics, other.topics);
+        self.broadcasts.extend(other.broadcasts);
+        self
+    }
 }
 
 impl MessageMap {
     pub fn new(pool: &PoolReadProxy<MessageBatch>) -> Result<MessageMap> {
         let iter = recipient_iter_all(pool);
-        let inner = iter
-            .fold(
-                HashMap::<String, Vec<MessageReference>>::new,
-                |mut acc, (recipients, message_ref)| {
-                    recipients.iter().for_each(|recipient| {
-                        // TODO: OS - (decide) currently if message has duplicate recipients then
-                        //   agents can get duplicate messages (filtering is expensive)
-                        if let Some(entry) = acc.get_mut(&**recipient) {
-                            entry.push(message_ref.clone())
-                        } else {
-                            acc.insert(recipient.to_string(), vec![message_ref.clone()]);
-                        }
-                    });
-                    acc
-                },
-            )
-            .reduce(HashMap::new, |mut a, b| {
-                b.into_iter().for_each(|(name, mut value)| {
-                    match a.entry(name) {
-                        Entry::Occupied(mut entry) => {
-                            entry.get_mut().append(&mut value);
-                        }
-                        Entry::Vacant(entry) => {
-                            entry.insert(value);
-                        }
-                    };
-                });
-                a
-            });
-
-        Ok(MessageMap { inner })
+        let routes = iter
+            .fold(Routes::default, |mut acc, (recipients, message_ref)| {
+                recipients
+                    .iter()
+                    .for_each(|recipient| acc.add(recipient, &message_ref));
+                acc
+            })
+            .reduce(Routes::default, Routes::merge);
+
+        let broadcasts = if routes.broadcasts.is_empty() {
+            Vec::new()
+        } else {
+            let reader = MessageReader::from_message_pool(pool)?;
+            routes
+                .broadcasts
+                .into_iter()
+                .map(|(radius, message)| {
+                    let loader = reader.get_loader(message.batch_index)?;
+                    Ok(Broadcast {
+                        sender: AgentId::from_bytes(*loader.get_from(message.agent_index)),
+                        radius,
+                        message,
+                    })
+                })
+                .collect::<Result<_>>()?
+        };
+
+        Ok(MessageMap {
+            inner: routes.recipients,
+            topics: routes.topics,
+            broadcasts,
+        })
     }
 
     pub fn get_msg_refs(&self, recipient: &str) -> &[MessageReference] {
         self.inner.get(recipient).map(Deref::deref).unwrap_or(&[])
     }
+
+    /// Returns the messages sent to `topic`.
+    pub fn get_topic_msg_refs(&self, topic: &str) -> &[MessageReference] {
+        self.topics.get(topic).map(Deref::deref).unwrap_or(&[])
+    }
+
+    /// Returns if any message was sent to a topic.
+    pub fn has_topics(&self) -> bool {
+        !self.topics.is_empty()
+    }
+
+    /// Returns the messages sent to all agents within a radius of their sender.
+    pub fn broadcasts(&self) -> &[Broadcast] {
+        &self.broadcasts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use memory::shared_memory::MemoryId;
+    use parking_lot::RwLock;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        agent::Agent,
+        message::{MessageBatchPool, MessageSchema},
+        proxy::BatchPool,
+    };
+
+    const A: &str = "00000000-0000-4000-8000-00000000000a";
+
+    /// Returns the sorted indices of `refs`.
+    fn indices(refs: &[MessageReference]) -> Vec<(usize, usize, usize)> {
+        let mut indices: Vec<_> = refs
+            .iter()
+            .map(|message| {
+                (
+                    message.batch_index,
+                    message.agent_index,
+                    message.message_index,
+                )
+            })
+            .collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    #[test]
+    fn route_recipient_specs() {
+        let agents: Vec<Agent> = serde_json::from_value(json!([
+            {
+                "agent_id": A,
+                "messages": [
+                    { "type": "case", "to": [r#"{"topic": "infections"}"#, "b"] },
+                    { "type": "alarm", "to": [r#"{"within": 2.5}"#] },
+                    { "type": "invalid", "to": [r#"{"topic": "a", "within": 1}"#] },
+                ],
+            },
+            {
+                "messages": [{ "type": "both", "to": [r#"{"to": ["a", "b"]}"#] }],
+            },
+        ]))
+        .unwrap();
+        let batch = MessageBatch::from_agent_states(
+            agents.as_slice(),
+            &MessageSchema::new(),
+            MemoryId::new(Uuid::new_v4()),
+        )
+        .unwrap();
+        let pool = MessageBatchPool::new(vec![Arc::new(RwLock::new(batch))]);
+        let map = MessageMap::new(&pool.read_proxies().unwrap()).unwrap();
+
+        // Messages are referenced by (batch index, agent index, message index)
+        let case = (0, 0, 0);
+        let alarm = (0, 0, 1);
+        let both = (0, 1, 0);
+
+        assert!(map.has_topics());
+        assert_eq!(indices(map.get_topic_msg_refs("infections")), [case]);
+        assert!(map.get_topic_msg_refs("a").is_empty());
+        assert_eq!(indices(map.get_msg_refs("a")), [both]);
+        assert_eq!(indices(map.get_msg_refs("b")), [case, both]);
+
+        assert_eq!(map.broadcasts().len(), 1);
+        let broadcast = &map.broadcasts()[0];
+        assert_eq!(
+            broadcast.sender,
+            AgentId::from_bytes(*Uuid::parse_str(A).unwrap().as_bytes())
+        );
+        assert_eq!(broadcast.radius, 2.5);
+        assert_eq!(indices(&[broadcast.message.clone()]), [alarm]);
+    }
 }
@@ -3,8 +3,8 @@ use std::{error::Error as StdError, fmt};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    agent::Agent,
-    message::{payload, SYSTEM_MESSAGE},
+    agent::{Agent, AgentId},
+    message::{self, payload, SYSTEM_MESSAGE},
     Result,
 };
 
@@ -132,6 +132,49 @@ fn is_system_message(kind: &str) -> bool {
 }
 
 impl Message {
+    /// Creates a message of type `kind` sent by the agent with the id `sender` to `to`.
+    ///
+    /// `"remove_agent"` messages without data remove the sender itself.
+    ///
+    /// # Errors
+    ///
+    /// - if `data` is not a valid payload for a `"remove_agent"` or `"create_agent"` message
+    /// - if a `"create_agent"` message doesn't have any data
+    pub fn from_sender(
+        sender: AgentId,
+        to: Vec<String>,
+        kind: &str,
+        data: Option<serde_json::Value>,
+    ) -> Result<Self> {
+        Ok(match kind {
+            payload::RemoveAgent::KIND => Self::RemoveAgent(payload::RemoveAgent {
+                r#type: message::RemoveAgent::Type,
+                to,
+                data: match data {
+                    Some(data) => serde_json::from_value(data)?,
+                    None => payload::RemoveAgentData { agent_id: sender },
+                },
+            }),
+            payload::CreateAgent::KIND => Self::CreateAgent(payload::CreateAgent {
+                r#type: message::CreateAgent::Type,
+                to,
+                data: serde_json::from_value(
+                    data.ok_or_else(|| crate::Error::from("Missing AgentState to create"))?,
+                )?,
+            }),
+            payload::StopSim::KIND => Self::StopSim(payload::StopSim {
+                r#type: message::StopSim::Type,
+                to,
+                data,
+            }),
+            _ => Self::Generic(payload::Generic {
+                r#type: kind.to_string(),
+                to,
+                data,
+            }),
+        })
+    }
+
     pub(in crate::message) fn new(to: &[&str], r#type: &str, data_string: &str) -> Result<Message> {
         let to_clone = to.iter().map(|v| (*v).to_string()).collect();
 
@@ -9,7 +9,9 @@ use crate::{
     message,
 };
 
-fn value_or_string_array<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+pub(in crate::message) fn value_or_string_array<'de, D>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{message::payload::value_or_string_array, Error, Result};
+
+/// A recipient of a message which is not a plain agent id or name.
+///
+/// Recipient specs are JSON objects, which are stored as strings in the `to` field of a message, so
+/// they can be sent next to plain recipients. Exactly one of `to`, `within`, and `topic` has to be
+/// specified, `delay` and `deliver_at` optionally hold the message back until a later step:
+///
+/// ```json
+/// { "within": 5 }
+/// { "topic": "infections", "delay": 3 }
+/// { "to": ["a", "b"], "deliver_at": 100 }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecipientSpec {
+    /// Agent ids or names receiving the message.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "value_or_string_array"
+    )]
+    pub to: Vec<String>,
+    /// Radius around the sender, in which every agent receives the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub within: Option<f64>,
+    /// Topic, which is received by every agent subscribed to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// Number of steps after which the message is received, `1` being the next step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<usize>,
+    /// Step in which the message is received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deliver_at: Option<usize>,
+}
+
+impl RecipientSpec {
+    /// Parses `recipient` as a recipient spec.
+    ///
+    /// Returns `None` if `recipient` is a plain agent id or name, i.e. it doesn't start with `{`.
+    ///
+    /// # Errors
+    ///
+    /// - if `recipient` is not a valid recipient spec
+    pub fn parse(recipient: &str) -> Option<Result<Self>> {
+        if !recipient.trim_start().starts_with('{') {
+            return None;
+        }
+        let invalid = |reason: String| Error::InvalidRecipient {
+            recipient: recipient.to_string(),
+            reason,
+        };
+
+        let spec: Self = match serde_json::from_str(recipient) {
+            Ok(spec) => spec,
+            Err(err) => return Some(Err(invalid(err.to_string()))),
+        };
+        let targets = usize::from(!spec.to.is_empty())
+            + spec.within.map_or(0, |_| 1)
+            + spec.topic.iter().count();
+        if targets != 1 {
+            return Some(Err(invalid(
+                "exactly one of `to`, `within`, and `topic` has to be specified".to_string(),
+            )));
+        }
+        if let Some(radius) = spec.within {
+            if !radius.is_finite() || radius < 0.0 {
+                return Some(Err(invalid(
+                    "`within` has to be a non-negative number".to_string(),
+                )));
+            }
+        }
+        if spec.delay.is_some() && spec.deliver_at.is_some() {
+            return Some(Err(invalid(
+                "only one of `delay` and `deliver_at` can be specified".to_string(),
+            )));
+        }
+        if spec.delay == Some(0) {
+            return Some(Err(invalid("`delay` has to be at least 1".to_string())));
+        }
+        Some(Ok(spec))
+    }
+
+    /// Returns the step in which a message sent in `sent_step` is received, if it's held back.
+    pub fn delivery_step(&self, sent_step: usize) -> Option<usize> {
+        self.deliver_at
+            .or_else(|| self.delay.map(|delay| sent_step + delay))
+    }
+
+    /// Returns the recipients of the message once it's due, i.e. without `delay` and
+    /// `deliver_at`.
+    pub fn into_due_recipients(self) -> Result<Vec<String>> {
+        if !self.to.is_empty() {
+            return Ok(self.to);
+        }
+        let spec = Self {
+            delay: None,
+            deliver_at: None,
+            ..self
+        };
+        Ok(vec![serde_json::to_string(&spec)?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_recipients() {
+        assert!(RecipientSpec::parse("agent").is_none());
+
+        let spec = RecipientSpec::parse(r#"{"within": 2.5, "delay": 3}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(spec.within, Some(2.5));
+        assert_eq!(spec.delivery_step(4), Some(7));
+        assert_eq!(
+            spec.into_due_recipients().unwrap(),
+            vec![r#"{"within":2.5}"#.to_string()]
+        );
+
+        let spec = RecipientSpec::parse(r#"{"to": "a", "deliver_at": 10}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(spec.delivery_step(4), Some(10));
+        assert_eq!(spec.into_due_recipients().unwrap(), vec!["a".to_string()]);
+
+        let spec = RecipientSpec::parse(r#"{"topic": "infections"}"#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(spec.delivery_step(4), None);
+
+        assert!(RecipientSpec::parse(r#"{"topic": "a", "within": 1}"#)
+            .unwrap()
+            .is_err());
+        assert!(RecipientSpec::parse(r#"{"to": "a", "delay": 0}"#)
+            .unwrap()
+            .is_err());
+        assert!(RecipientSpec::parse(r#"{"radius": 1}"#).unwrap().is_err());
+    }
+}
@@ -20,7 +20,8 @@ mod view;
 
 use std::{ops::Range, sync::Arc};
 
-use memory::shared_memory::MemoryId;
+use arrow2::chunk::Chunk;
+use memory::{arrow::record_batch::RecordBatch, shared_memory::MemoryId};
 use uuid::Uuid;
 
 pub use self::{
@@ -33,7 +34,7 @@ use crate::{
     agent::{Agent, AgentBatch, AgentBatchPool, AgentSchema},
     message::{MessageBatch, MessageBatchPool, MessageMap, MessageSchema},
     proxy::BatchPool,
-    Result,
+    Error, Result,
 };
 
 /// Used for creating a new [`State`].
@@ -50,6 +51,16 @@ pub struct StateCreateParameters {
     pub message_schema: Arc<MessageSchema>,
 }
 
+impl StateCreateParameters {
+    /// The number of agents per group when distributing `num_agents` agents.
+    fn group_size(&self, num_agents: usize) -> usize {
+        // Distribute agents over the expected minimum number of groups
+        let target_group_size = (num_agents as f64 / self.target_min_groups as f64).ceil() as usize;
+        // We may have lower or upper bounds on the size of an individual group so adjust for that
+        target_group_size.clamp(self.target_group_size.start, self.target_group_size.end)
+    }
+}
+
 /// Holds shared data for [`Agent`]s and [`Message`]s.
 ///
 /// [`Message`]: crate::message::Message
@@ -135,15 +146,7 @@ impl State {
         create_parameters: StateCreateParameters,
     ) -> Result<State> {
         let num_agents = agent_states.len();
-
-        // Distribute agents over the expected minimum number of groups
-        let target_group_size =
-            (num_agents as f64 / create_parameters.target_min_groups as f64).ceil() as usize;
-        // We may have lower or upper bounds on the size of an individual group so adjust for that
-        let target_group_size = target_group_size.clamp(
-            create_parameters.target_group_size.start,
-            create_parameters.target_group_size.end,
-        );
+        let target_group_size = create_parameters.group_size(num_agents);
 
         let mut agent_state_groups = vec![];
         let mut next_index = 0;
@@ -156,6 +159,99 @@ impl State {
         Self::from_agent_groups(&agent_state_groups, num_agents, create_parameters)
     }
 
+    /// Creates a new State object from record batches of the agent schema, e.g. the batches read
+    /// from a columnar initial state.
+    ///
+    /// The record batches are split up into groups like in
+    /// [`from_agent_states`](Self::from_agent_states), the message batches are empty.
+    pub fn from_record_batches(
+        record_batches: &[RecordBatch],
+        create_parameters: StateCreateParameters,
+    ) -> Result<State> {
+        let num_agents = record_batches.iter().map(RecordBatch::num_rows).sum();
+        let target_group_size = create_parameters.group_size(num_agents);
+
+        let mut agent_batches = Vec::new();
+        let mut message_batches = Vec::new();
+        for record_batch in record_batches {
+            let mut offset = 0;
+            while offset != record_batch.num_rows() {
+                let length = target_group_size.min(record_batch.num_rows() - offset);
+                let group = RecordBatch::new(
+                    record_batch.schema(),
+                    Chunk::new(
+                        record_batch
+                            .columns()
+                            .iter()
+                            .map(|column| column.slice(offset, length))
+                            .collect(),
+                    ),
+                );
+                offset += length;
+
+                let agent_batch = AgentBatch::from_record_batch(
+                    &group,
+                    &create_parameters.agent_schema,
+                    MemoryId::new(create_parameters.memory_base_id),
+                )?;
+                message_batches.push(MessageBatch::empty_from_agent_batch(
+                    &agent_batch,
+                    &create_parameters.message_schema,
+                    MemoryId::new(create_parameters.memory_base_id),
+                )?);
+                agent_batches.push(agent_batch);
+            }
+        }
+
+        Self::from_batches(agent_batches, message_batches, create_parameters)
+    }
+
+    /// Creates a new State object from existing batches, e.g. batches restored from a checkpoint.
+    ///
+    /// Every [`AgentBatch`] forms a group together with the [`MessageBatch`] at the same index.
+    pub fn from_batches(
+        agent_batches: Vec<AgentBatch>,
+        message_batches: Vec<MessageBatch>,
+        create_parameters: StateCreateParameters,
+    ) -> Result<State> {
+        if agent_batches.len() != message_batches.len() {
+            return Err(Error::from(format!(
+                "Expected the same number of agent and message batches, got {} and {}",
+                agent_batches.len(),
+                message_batches.len()
+            )));
+        }
+
+        let mut group_start_indices = Vec::with_capacity(agent_batches.len());
+        let mut num_agents = 0;
+        for agent_batch in &agent_batches {
+            group_start_indices.push(num_agents);
+            num_agents += agent_batch.num_agents();
+        }
+
+        Ok(Self {
+            state: StateBatchPools {
+                agent_pool: AgentBatchPool::new(
+                    agent_batches
+                        .into_iter()
+                        .map(|batch| Arc::new(parking_lot::RwLock::new(batch)))
+                        .collect(),
+                ),
+                message_pool: MessageBatchPool::new(
+                    message_batches
+                        .into_iter()
+                        .map(|batch| Arc::new(parking_lot::RwLock::new(batch)))
+                        .collect(),
+                ),
+            },
+            removed_batches: Vec::new(),
+            num_agents,
+            group_start_indices: Arc::new(group_start_indices),
+            memory_base_id: create_parameters.memory_base_id,
+            message_schema: create_parameters.message_schema,
+        })
+    }
+
     // TODO: OPTIM - We should be using these to release memory, this requires propagation to the
     //   runners, otherwise this is the cause of a possible memory leak
     pub fn removed_batches(&mut self) -> &mut Vec<String> {
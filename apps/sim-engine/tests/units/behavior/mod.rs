@@ -1,4 +1,10 @@
+use std::path::Path;
+
+use crate::experiment::run_failing_test;
+
 crate::run_test!(multiple_runners);
+crate::run_test!(rust_runner);
+crate::run_test!(wasm_runner);
 
 mod js {
     crate::run_test!(composability, JavaScript);
@@ -7,3 +13,26 @@ mod js {
 mod py {
     crate::run_test!(composability, Python);
 }
+
+/// The error thrown by the TypeScript behavior is reported at its line and column in the
+/// TypeScript source, not in the transpiled JavaScript.
+#[tokio::test]
+#[cfg_attr(miri, ignore)]
+async fn typescript_error_position() {
+    let project_path = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("typescript")
+        .canonicalize()
+        .unwrap();
+    let error = run_failing_test(
+        project_path,
+        concat!(module_path!(), "::typescript_error_position"),
+        None,
+        2,
+    )
+    .await;
+
+    assert!(error.contains("Error: count is 1"), "{error}");
+    assert!(error.contains("at behavior (fail.ts:11:11)"), "{error}");
+}
@@ -0,0 +1,6 @@
+/**
+ * Reads the age written by the built-in Rust behavior in the same step
+ */
+const behavior = (state, context) => {
+  state.double_age = state.age * 2;
+};
@@ -0,0 +1,14 @@
+interface Counter {
+  count: number;
+}
+
+/**
+ * Throws in the second step, the types in front of the error are stripped when transpiling
+ */
+const behavior = (state: AgentState & Counter, context: Context): void => {
+  const count: number = state.count as number;
+  if (count > 0) {
+    throw new Error(`count is ${count}`);
+  }
+  state.count = count + 1;
+};
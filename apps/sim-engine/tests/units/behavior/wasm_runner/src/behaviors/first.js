@@ -0,0 +1,6 @@
+/**
+ * Stores a number in the "a" field to be picked up by the WebAssembly behavior
+ */
+const behavior = (state, context) => {
+  state.a = 1;
+};
@@ -0,0 +1,32 @@
+;; Copies the value of the "a" field to the "b" field and stores a greeting through the host API of
+;; the WebAssembly runner
+(module
+  (import "hash" "state_get" (func $state_get (param i32 i32) (result i64)))
+  (import "hash" "state_set" (func $state_set (param i32 i32 i32 i32)))
+
+  (memory (export "memory") 1)
+  (data (i32.const 0) "a")
+  (data (i32.const 1) "b")
+  (data (i32.const 2) "greeting")
+  (data (i32.const 16) "\"hello\"")
+
+  ;; Values returned by the engine are allocated after the static data and released after every
+  ;; call of the behavior
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "hash_alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $len)))
+    (local.get $ptr))
+
+  (func (export "behavior")
+    (local $value i64)
+    (global.set $heap (i32.const 1024))
+    ;; The returned value holds the offset in the upper and the length in the lower 32 bits
+    (local.set $value (call $state_get (i32.const 0) (i32.const 1)))
+    (call $state_set
+      (i32.const 1) (i32.const 1)
+      (i32.wrap_i64 (i64.shr_u (local.get $value) (i64.const 32)))
+      (i32.wrap_i64 (local.get $value)))
+    (call $state_set (i32.const 2) (i32.const 8) (i32.const 16) (i32.const 7))))
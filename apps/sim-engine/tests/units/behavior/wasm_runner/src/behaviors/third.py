@@ -0,0 +1,3 @@
+def behavior(state, context):
+    """Reads the value copied by the WebAssembly behavior and stores a modified value of it in a new field"""
+    state.c = state.b + 1
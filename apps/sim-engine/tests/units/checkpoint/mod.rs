@@ -0,0 +1,49 @@
+//! Resumes simulation runs from a checkpoint and compares them with an uninterrupted run.
+
+use std::path::{Path, PathBuf};
+
+use execution::runner::Language;
+use serde_json::json;
+
+use crate::experiment::run_resume_test;
+
+fn project_path(project: &str) -> PathBuf {
+    Path::new(file!())
+        .parent()
+        .unwrap()
+        .join(project)
+        .canonicalize()
+        .unwrap()
+}
+
+/// Runs `resume` for 6 steps with a checkpoint after step 3, which holds a pending message and a
+/// message delayed until step 5. Only every second step is persisted, so the uninterrupted run
+/// persists steps 0, 2, 4, and 6 and the resumed run persists steps 4 and 6.
+async fn resume(test_path: &'static str, language: Language) {
+    let (uninterrupted, resumed) =
+        run_resume_test(project_path("resume"), test_path, Some(language), 6, 3).await;
+
+    let uninterrupted = uninterrupted.as_array().expect("JSON state is not a list");
+    let resumed = resumed.as_array().expect("JSON state is not a list");
+    assert_eq!(uninterrupted.len(), 4);
+    assert_eq!(resumed, &uninterrupted[2..]);
+
+    let last_step = resumed.last().unwrap().as_array().unwrap();
+    assert_eq!(last_step[0]["sent"], json!(6.0));
+    assert_eq!(
+        last_step[1]["received"],
+        json!(["ping@2", "ping@3", "ping@4", "later@5", "ping@5", "ping@6"])
+    );
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)]
+async fn resume_js() {
+    resume(concat!(module_path!(), "::resume_js"), Language::JavaScript).await;
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)]
+async fn resume_py() {
+    resume(concat!(module_path!(), "::resume_py"), Language::Python).await;
+}
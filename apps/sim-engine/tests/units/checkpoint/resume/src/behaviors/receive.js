@@ -0,0 +1,11 @@
+/**
+ * Appends the sorted types of the received messages suffixed with the current step to
+ * `state.received`
+ */
+const behavior = (state, context) => {
+  const received = context
+    .messages()
+    .map((message) => `${message.type}@${context.step()}`)
+    .sort();
+  state.received = state.received.concat(received);
+};
@@ -0,0 +1,5 @@
+def behavior(state, context):
+    """Appends the sorted types of the received messages suffixed with the current step to
+    `state.received`"""
+    received = sorted(f"{message['type']}@{context.step()}" for message in context.messages())
+    state.received = list(state.received) + received
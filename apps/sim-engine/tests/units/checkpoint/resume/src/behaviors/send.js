@@ -0,0 +1,11 @@
+/**
+ * Sends a "ping" to "receiver" every step and a "later" message in step 2, which is delayed by
+ * three steps
+ */
+const behavior = (state, context) => {
+  state.addMessage("receiver", "ping");
+  if (context.step() === 2) {
+    state.addMessage({ to: "receiver", delay: 3 }, "later");
+  }
+  state.sent += 1;
+};
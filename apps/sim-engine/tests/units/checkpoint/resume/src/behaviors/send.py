@@ -0,0 +1,7 @@
+def behavior(state, context):
+    """Sends a "ping" to "receiver" every step and a "later" message in step 2, which is delayed by
+    three steps"""
+    state.add_message("receiver", "ping")
+    if context.step() == 2:
+        state.add_message({"to": "receiver", "delay": 3}, "later")
+    state.sent += 1
@@ -3,6 +3,7 @@ mod js {
 
     run_test!(access, JavaScript);
     run_test!(immutable, JavaScript);
+    run_test!(typed, JavaScript);
 }
 
 mod py {
@@ -10,4 +11,5 @@ mod py {
 
     run_test!(access, Python);
     run_test!(immutable, Python, #[ignore = "bug: Datasets are currently not immutable in Python"]);
+    run_test!(typed, Python);
 }
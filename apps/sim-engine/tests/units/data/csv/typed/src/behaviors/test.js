@@ -0,0 +1,15 @@
+/**
+ * Reads the rows and the typed columns and rows of a dataset
+ */
+const behavior = (state, context) => {
+  const rows = context.data()["dataset.csv"];
+  const table = context.dataTable("dataset.csv");
+
+  state.is_array = Array.isArray(rows);
+  state.rows = JSON.parse(JSON.stringify(rows));
+  state.header = rows[0];
+  state.ids = table.column("id");
+  state.scores = table.column(2);
+  state.second_row = table.row(1);
+  state.num_rows = table.num_rows;
+};
@@ -0,0 +1,16 @@
+import json
+
+
+def behavior(state, context):
+    """Reads the rows and the typed columns and rows of a dataset"""
+
+    rows = context.data()["dataset.csv"]
+    table = context.data_table("dataset.csv")
+
+    state.is_array = isinstance(rows, list)
+    state.rows = json.loads(json.dumps(rows))
+    state.header = rows[0]
+    state.ids = table.column("id").to_pylist()
+    state.scores = table.column(2).to_pylist()
+    state.second_row = table.row(1)
+    state.num_rows = table.num_rows
@@ -3,11 +3,13 @@ mod js {
 
     run_test!(access, JavaScript);
     run_test!(immutable, JavaScript);
+    run_test!(typed, JavaScript);
 }
 
 mod py {
     use crate::run_test;
 
     run_test!(access, Python);
+    run_test!(typed, Python);
     run_test!(immutable, Python, #[ignore = "bug: Datasets are currently not immutable in Python"]);
 }
@@ -0,0 +1,13 @@
+/**
+ * Reads the rows and the typed columns of a dataset
+ */
+const behavior = (state, context) => {
+  const rows = context.data()["dataset.json"];
+  const table = context.dataTable("dataset.json");
+
+  state.is_array = Array.isArray(rows);
+  state.keys = Object.keys(rows[0]);
+  state.column_names = table.column_names;
+  state.ids = table.column("id");
+  state.scores = table.column(2);
+};
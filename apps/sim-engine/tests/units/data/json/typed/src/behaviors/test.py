@@ -0,0 +1,11 @@
+def behavior(state, context):
+    """Reads the rows and the typed columns of a dataset"""
+
+    rows = context.data()["dataset.json"]
+    table = context.data_table("dataset.json")
+
+    state.is_array = isinstance(rows, list)
+    state.keys = list(rows[0])
+    state.column_names = table.column_names
+    state.ids = table.column("id").to_pylist()
+    state.scores = table.column(2).to_pylist()
@@ -5,6 +5,7 @@ mod js {
     run_test!(no_recipient, JavaScript);
     run_test!(one_recipient, JavaScript);
     run_test!(multiple_recipients, JavaScript);
+    run_test!(recipients, JavaScript);
 
     run_test!(all_types, JavaScript);
     run_test!(nested_types, JavaScript);
@@ -24,6 +25,7 @@ mod py {
     run_test!(no_recipient, Python);
     run_test!(one_recipient, Python);
     run_test!(multiple_recipients, Python);
+    run_test!(recipients, Python);
 
     // Bug: https://app.asana.com/0/1199548034582004/1202011714603646/f
     run_test!(nested_types, Python, #[ignore = "bug: Python and arrow-rs have different expectations about FixedSizeLists"]);
@@ -0,0 +1,9 @@
+/**
+ * Sets `state.received` to the sorted types of the received messages
+ */
+const behavior = (state, context) => {
+  state.received = context
+    .messages()
+    .map((message) => message.type)
+    .sort();
+};
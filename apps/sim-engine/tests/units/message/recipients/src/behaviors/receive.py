@@ -0,0 +1,3 @@
+def behavior(state, context):
+    """Sets `state.received` to the sorted types of the received messages"""
+    state.received = sorted(message["type"] for message in context.messages())
@@ -0,0 +1,11 @@
+/**
+ * Sends a broadcast to the agents within a radius of 2, a message to the topic "news", and a
+ * message to "far", which is delayed by two steps
+ */
+const behavior = (state, context) => {
+  if (context.step() === 1) {
+    state.addMessage({ within: 2 }, "near");
+    state.addMessage({ topic: "news" }, "news");
+    state.addMessage({ to: "far", delay: 2 }, "later");
+  }
+};
@@ -0,0 +1,7 @@
+def behavior(state, context):
+    """Sends a broadcast to the agents within a radius of 2, a message to the topic "news", and a
+    message to "far", which is delayed by two steps"""
+    if context.step() == 1:
+        state.add_message({"within": 2}, "near")
+        state.add_message({"topic": "news"}, "news")
+        state.add_message({"to": "far", "delay": 2}, "later")
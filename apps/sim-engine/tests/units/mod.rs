@@ -5,11 +5,13 @@
 //! functionalities.
 
 mod behavior;
+mod checkpoint;
 mod context;
 mod data;
 mod globals;
 mod message;
 mod neighbors;
+mod seed;
 mod state;
 mod topology;
 
@@ -1,2 +1,3 @@
 mod fields;
+mod network;
 mod search_radius;
@@ -0,0 +1,3 @@
+def behavior(state, context):
+    """Sets `state.neighbor_names` to the sorted names of the neighbors"""
+    state.neighbor_names = sorted(neighbor["agent_name"] for neighbor in context.neighbors())
@@ -0,0 +1,9 @@
+/**
+ * Sets `state.neighbor_names` to the sorted names of the neighbors
+ */
+const behavior = (state, context) => {
+  state.neighbor_names = context
+    .neighbors()
+    .map((neighbor) => neighbor.agent_name)
+    .sort();
+};
@@ -0,0 +1,13 @@
+mod js {
+    use crate::run_test;
+
+    run_test!(edges_field, JavaScript);
+    run_test!(dataset, JavaScript);
+}
+
+mod py {
+    use crate::run_test;
+
+    run_test!(edges_field, Python);
+    run_test!(dataset, Python);
+}
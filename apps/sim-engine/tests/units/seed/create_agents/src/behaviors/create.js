@@ -0,0 +1,12 @@
+/**
+ * Draws a random value and creates two agents with random values every step. The id of the first
+ * agent is assigned by the engine, the id of the second one is generated by the behavior
+ */
+const behavior = (state, context) => {
+  state.value = hstd.random();
+  state.addMessage("hash", "create_agent", { value: hstd.random() });
+  state.addMessage("hash", "create_agent", {
+    agent_id: hstd.generateAgentID(),
+    value: hstd.random(),
+  });
+};
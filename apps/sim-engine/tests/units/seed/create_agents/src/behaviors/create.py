@@ -0,0 +1,10 @@
+def behavior(state, context):
+    """Draws a random value and creates two agents with random values every step. The id of the
+    first agent is assigned by the engine, the id of the second one is generated by the behavior"""
+    state.value = hash_stdlib.random()
+    state.add_message("hash", "create_agent", {"value": hash_stdlib.random()})
+    state.add_message(
+        "hash",
+        "create_agent",
+        {"agent_id": hash_stdlib.generate_agent_id(), "value": hash_stdlib.random()},
+    )
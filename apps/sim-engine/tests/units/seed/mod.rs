@@ -0,0 +1,64 @@
+//! Runs seeded simulations twice and compares their outputs.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use execution::runner::Language;
+use serde_json::Value;
+
+use crate::experiment::run_seeded_test;
+
+fn project_path(project: &str) -> PathBuf {
+    Path::new(file!())
+        .parent()
+        .unwrap()
+        .join(project)
+        .canonicalize()
+        .unwrap()
+}
+
+/// Runs `create_agents` for 4 steps. Every step two agents are created, one of them with an id
+/// generated by the behavior, so the last step has 9 agents with distinct ids.
+async fn create_agents(test_path: &'static str, language: Language) {
+    let (first, second) =
+        run_seeded_test(project_path("create_agents"), test_path, Some(language), 4).await;
+    assert!(
+        first == second,
+        "The JSON states of the simulation runs are not identical"
+    );
+
+    let json_state: Value = serde_json::from_slice(&first).expect("Could not parse JSON state");
+    let last_step = json_state
+        .as_array()
+        .and_then(|steps| steps.last())
+        .and_then(Value::as_array)
+        .expect("JSON state is not a list of steps");
+    let agent_ids: HashSet<_> = last_step
+        .iter()
+        .map(|agent| agent["agent_id"].as_str().expect("Agent has no id"))
+        .collect();
+    assert_eq!(last_step.len(), 9);
+    assert_eq!(agent_ids.len(), 9);
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)]
+async fn create_agents_js() {
+    create_agents(
+        concat!(module_path!(), "::create_agents_js"),
+        Language::JavaScript,
+    )
+    .await;
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)]
+async fn create_agents_py() {
+    create_agents(
+        concat!(module_path!(), "::create_agents_py"),
+        Language::Python,
+    )
+    .await;
+}
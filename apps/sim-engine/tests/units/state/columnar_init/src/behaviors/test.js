@@ -0,0 +1,7 @@
+/**
+ * Updates fields read from a CSV initial state
+ */
+const behavior = (state, context) => {
+  state.counter += 1;
+  state.mood = state.mood === "happy" ? "sad" : "happy";
+};
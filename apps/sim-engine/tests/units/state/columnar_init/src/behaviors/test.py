@@ -0,0 +1,4 @@
+def behavior(state, context):
+    """Updates fields read from a CSV initial state"""
+    state.counter += 1
+    state.mood = "sad" if state.mood == "happy" else "happy"
@@ -5,10 +5,14 @@ mod js {
     crate::run_test!(behavior_index, JavaScript);
     crate::run_test!(nullable_fixed_size_list, JavaScript);
     crate::run_test!(immutability, JavaScript);
+    crate::run_test!(typed_fields, JavaScript);
+    crate::run_test!(columnar_init, JavaScript);
 }
 
 mod py {
     crate::run_test!(behavior_index, Python);
     crate::run_test!(nullable_fixed_size_list, Python);
     crate::run_test!(immutability, Python);
+    crate::run_test!(typed_fields, Python);
+    crate::run_test!(columnar_init, Python);
 }
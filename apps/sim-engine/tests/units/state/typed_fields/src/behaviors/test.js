@@ -0,0 +1,10 @@
+/**
+ * Updates integer, timestamp and categorical fields
+ */
+const behavior = (state, context) => {
+  state.counter_is_number = typeof state.counter === "number";
+  state.counter += 1;
+  state.big_copy = state.big;
+  state.updated_at += 1000;
+  state.mood = state.mood === "happy" ? "sad" : "happy";
+};
@@ -0,0 +1,11 @@
+from datetime import timedelta
+from numbers import Integral
+
+
+def behavior(state, context):
+    """Updates integer, timestamp and categorical fields"""
+    state.counter_is_number = isinstance(state.counter, Integral)
+    state.counter += 1
+    state.big_copy = state.big
+    state.updated_at += timedelta(seconds=1)
+    state.mood = "sad" if state.mood == "happy" else "happy"
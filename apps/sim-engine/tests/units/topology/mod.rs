@@ -14,6 +14,7 @@ mod js {
         run_test!(wrapping, JavaScript, experiment: torus);
         run_test!(wrapping, JavaScript, experiment: spherical);
         run_test!(wrapping, JavaScript, experiment: reflection);
+        run_test!(wrapping, JavaScript, experiment: unwrapped);
     }
 }
 
@@ -33,5 +34,6 @@ mod py {
         run_test!(wrapping, Python, experiment: torus);
         run_test!(wrapping, Python, experiment: spherical);
         run_test!(wrapping, Python, experiment: reflection);
+        run_test!(wrapping, Python, experiment: unwrapped);
     }
 }